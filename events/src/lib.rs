@@ -0,0 +1,51 @@
+//! $DRONEOS Event Envelope
+//!
+//! Wire format shared by every protocol program:
+//! - Every event starts with an `EventHeader`
+//! - Events are emitted via self-CPI (`emit_cpi!`) so they can't be
+//!   truncated out of transaction logs
+//! - `seq` increases by one per event of the same entity, so consumers can
+//!   detect gaps and order events without relying on slot/log position
+//! - New fields are only ever appended to an event; layout changes to the
+//!   header itself bump `EVENT_VERSION`
+
+use anchor_lang::prelude::*;
+
+/// Current envelope version
+pub const EVENT_VERSION: u8 = 1;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProgramTag {
+    IdentityRegistry,
+    PaymentStreams,
+    TaskMarket,
+    Token,
+    SwarmCoordinator,
+    OracleVerifier,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EventHeader {
+    pub version: u8,
+    pub program: ProgramTag,
+    /// Account whose lifecycle the event describes
+    pub entity: Pubkey,
+    /// Per-entity sequence number, starting at 1
+    pub seq: u64,
+    pub timestamp: i64,
+}
+
+impl EventHeader {
+    /// Header for the next event of `entity`, advancing the sequence number
+    /// stored on the entity's account
+    pub fn next(program: ProgramTag, entity: Pubkey, seq: &mut u64, timestamp: i64) -> Self {
+        *seq += 1;
+        Self {
+            version: EVENT_VERSION,
+            program,
+            entity,
+            seq: *seq,
+            timestamp,
+        }
+    }
+}
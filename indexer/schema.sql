@@ -0,0 +1,105 @@
+-- $DRONEOS indexer schema
+--
+-- `events` is the append-only log of every decoded protocol event; the entity
+-- tables hold the latest known state of each stream, task, bid, proof and
+-- dispute. All pubkeys are base58 text, all amounts are raw token units.
+--
+-- `entity`/`seq` come from the event header: `seq` increases by one per event
+-- of the same entity, so a missing number means a missed event. Entity rows
+-- store the highest `seq` applied to them and ignore older updates.
+
+CREATE TABLE IF NOT EXISTS events (
+    signature   TEXT        NOT NULL,
+    event_index INTEGER     NOT NULL,
+    slot        BIGINT      NOT NULL,
+    program     TEXT        NOT NULL,
+    name        TEXT        NOT NULL,
+    version     SMALLINT    NOT NULL,
+    entity      TEXT        NOT NULL,
+    seq         BIGINT      NOT NULL,
+    timestamp   BIGINT      NOT NULL,
+    data        BYTEA       NOT NULL,
+    indexed_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (signature, event_index),
+    UNIQUE (program, entity, seq)
+);
+
+CREATE INDEX IF NOT EXISTS events_program_name_idx ON events (program, name);
+CREATE INDEX IF NOT EXISTS events_slot_idx ON events (slot);
+
+CREATE TABLE IF NOT EXISTS streams (
+    pubkey          TEXT PRIMARY KEY,
+    payer           TEXT,
+    payee           TEXT,
+    rate_per_second BIGINT,
+    status          TEXT,
+    escrow_balance  BIGINT,
+    total_paid      BIGINT,
+    total_ticks     BIGINT,
+    task            TEXT,
+    updated_at      BIGINT,
+    seq             BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS streams_payer_idx ON streams (payer);
+CREATE INDEX IF NOT EXISTS streams_payee_idx ON streams (payee);
+
+CREATE TABLE IF NOT EXISTS tasks (
+    pubkey          TEXT PRIMARY KEY,
+    kind            TEXT,
+    creator         TEXT,
+    title           TEXT,
+    reward          BIGINT,
+    rate_per_second BIGINT,
+    status          TEXT,
+    assignee        TEXT,
+    progress        BIGINT,
+    expires_at      BIGINT,
+    updated_at      BIGINT,
+    seq             BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS tasks_status_idx ON tasks (status);
+CREATE INDEX IF NOT EXISTS tasks_creator_idx ON tasks (creator);
+
+CREATE TABLE IF NOT EXISTS bids (
+    pubkey        TEXT PRIMARY KEY,
+    kind          TEXT,
+    task          TEXT,
+    bidder        TEXT,
+    operator      TEXT,
+    proposed_rate BIGINT,
+    status        TEXT,
+    updated_at    BIGINT,
+    seq           BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS bids_task_idx ON bids (task);
+
+CREATE TABLE IF NOT EXISTS proofs (
+    pubkey           TEXT PRIMARY KEY,
+    task             TEXT,
+    robot            TEXT,
+    oracle           TEXT,
+    proof_type       TEXT,
+    status           TEXT,
+    confidence_score BIGINT,
+    latitude         BIGINT,
+    longitude        BIGINT,
+    data_hash        BYTEA,
+    updated_at       BIGINT,
+    seq              BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS proofs_task_idx ON proofs (task);
+
+CREATE TABLE IF NOT EXISTS disputes (
+    pubkey        TEXT PRIMARY KEY,
+    proof         TEXT,
+    challenger    TEXT,
+    status        TEXT,
+    votes_for     BIGINT,
+    votes_against BIGINT,
+    updated_at    BIGINT,
+    seq           BIGINT
+);
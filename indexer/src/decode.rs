@@ -0,0 +1,1033 @@
+use std::str::FromStr;
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use droneos_events::EventHeader;
+use solana_transaction_status::{
+    EncodedTransactionWithStatusMeta, UiInnerInstructions, UiInstruction, UiLoadedAddresses,
+};
+
+use crate::model::*;
+
+/// Match an event discriminator against a list of event types, deserialize the
+/// body of the first hit and map it into normalized rows.
+macro_rules! match_events {
+    ($disc:expr, $body:expr, { $($ty:ident => |$ev:pat_param| $map:expr),* $(,)? }) => {{
+        $(
+            if $disc == <$ty as Discriminator>::discriminator() {
+                let $ev = <$ty as AnchorDeserialize>::deserialize(&mut &$body[..]).ok()?;
+                return Some((stringify!($ty), $map));
+            }
+        )*
+        None
+    }};
+}
+
+// ============================================================================
+// CPI EVENTS
+// ============================================================================
+
+/// Collect every event payload of a transaction, attributed to the protocol
+/// program that emitted it. Programs emit events by invoking themselves with
+/// `EVENT_IX_TAG_LE` followed by the event bytes, so events are the inner
+/// instructions of a protocol program carrying that prefix.
+pub fn extract_event_data(tx: &EncodedTransactionWithStatusMeta) -> Vec<(ProgramKind, Vec<u8>)> {
+    let (Some(decoded), Some(meta)) = (tx.transaction.decode(), tx.meta.as_ref()) else {
+        return Vec::new();
+    };
+
+    // Inner instructions index into static keys followed by lookup-table keys
+    let mut keys = decoded.message.static_account_keys().to_vec();
+    if let Some(loaded) = Option::<&UiLoadedAddresses>::from(meta.loaded_addresses.as_ref()) {
+        keys.extend(
+            loaded
+                .writable
+                .iter()
+                .chain(&loaded.readonly)
+                .filter_map(|key| Pubkey::from_str(key).ok()),
+        );
+    }
+
+    let Some(inner) = Option::<&Vec<UiInnerInstructions>>::from(meta.inner_instructions.as_ref())
+    else {
+        return Vec::new();
+    };
+
+    let mut out = Vec::new();
+    for ix in inner.iter().flat_map(|set| &set.instructions) {
+        let UiInstruction::Compiled(ix) = ix else {
+            continue;
+        };
+        let Some(program) = keys
+            .get(ix.program_id_index as usize)
+            .and_then(ProgramKind::from_program_id)
+        else {
+            continue;
+        };
+        let Ok(data) = bs58::decode(&ix.data).into_vec() else {
+            continue;
+        };
+        if let Some(event) = data.strip_prefix(&EVENT_IX_TAG_LE[..]) {
+            out.push((program, event.to_vec()));
+        }
+    }
+
+    out
+}
+
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+pub fn decode_event(program: ProgramKind, data: Vec<u8>) -> Option<ProtocolEvent> {
+    if data.len() < 8 {
+        return None;
+    }
+    let mut disc = [0u8; 8];
+    disc.copy_from_slice(&data[..8]);
+    let body = &data[8..];
+
+    // Every event body starts with the shared envelope
+    let header = EventHeader::deserialize(&mut &body[..]).ok()?;
+
+    let (name, mut entities) = match program {
+        ProgramKind::IdentityRegistry => identity_registry_event(disc, body),
+        ProgramKind::PaymentStreams => payment_streams_event(disc, body),
+        ProgramKind::TaskMarket => task_market_event(disc, body),
+        ProgramKind::Token => token_event(disc, body),
+        ProgramKind::SwarmCoordinator => swarm_coordinator_event(disc, body),
+        ProgramKind::OracleVerifier => oracle_verifier_event(disc, body),
+    }?;
+
+    for entity in &mut entities {
+        if entity.pubkey() == header.entity {
+            entity.set_seq(header.seq);
+        }
+    }
+
+    Some(ProtocolEvent {
+        program,
+        name,
+        header,
+        data,
+        entities,
+    })
+}
+
+fn identity_registry_event(disc: [u8; 8], body: &[u8]) -> Option<(&'static str, Vec<Entity>)> {
+    use identity_registry::{
+        CapabilityAdded, RegistryInitialized, ReputationUpdated, RobotDeactivated,
+        RobotRegistered, RobotStatusChanged, RobotVerified,
+    };
+
+    match_events!(disc, body, {
+        RegistryInitialized => |_| vec![],
+        RobotRegistered => |_| vec![],
+        CapabilityAdded => |_| vec![],
+        RobotStatusChanged => |_| vec![],
+        ReputationUpdated => |_| vec![],
+        RobotVerified => |_| vec![],
+        RobotDeactivated => |_| vec![],
+    })
+}
+
+fn payment_streams_event(disc: [u8; 8], body: &[u8]) -> Option<(&'static str, Vec<Entity>)> {
+    use payment_streams::{
+        ClaimNftMinted, EscrowSponsored, EscrowToppedUp, LowEscrowWarning, MilestoneAdded,
+        MilestoneApproved, MilestoneReleased, PayeeTransferred, RateChangeAccepted,
+        RateChangeProposed, RateScheduleSet, StreamAccepted, StreamCancelled, StreamClosed,
+        StreamCreated, StreamExtended, StreamFeeDiscountSet, StreamGraceStarted, StreamPaused,
+        StreamRejected, StreamRescued, StreamResumed, StreamStale, StreamStarted,
+        StreamTemplateCreated, StreamTerminated, StreamTick, UsageReported,
+    };
+
+    match_events!(disc, body, {
+        StreamCreated => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            payer: Some(e.payer),
+            payee: Some(e.payee),
+            rate_per_second: Some(e.rate_per_second),
+            status: Some("pending"),
+            escrow_balance: Some(e.escrow_amount),
+            total_paid: Some(0),
+            total_ticks: Some(0),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        StreamStarted => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("active"),
+            updated_at: Some(e.started_at),
+            ..Default::default()
+        })],
+        StreamTick => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            escrow_balance: Some(e.escrow_remaining),
+            total_paid: Some(e.total_paid),
+            total_ticks: Some(e.tick_number),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        StreamPaused => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("paused"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        StreamResumed => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("active"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        StreamTerminated => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("completed"),
+            total_paid: Some(e.total_paid),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        StreamAccepted => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("accepted"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        StreamRejected => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("rejected"),
+            escrow_balance: Some(0),
+            ..Default::default()
+        })],
+        StreamCancelled => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("cancelled"),
+            escrow_balance: Some(0),
+            ..Default::default()
+        })],
+        EscrowToppedUp => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            escrow_balance: Some(e.new_balance),
+            ..Default::default()
+        })],
+        PayeeTransferred => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            payee: Some(e.new_payee),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        StreamClosed => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("closed"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        StreamExtended => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            escrow_balance: Some(e.new_balance),
+            ..Default::default()
+        })],
+        EscrowSponsored => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            escrow_balance: Some(e.new_balance),
+            ..Default::default()
+        })],
+        LowEscrowWarning => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            escrow_balance: Some(e.escrow_balance),
+            ..Default::default()
+        })],
+        StreamGraceStarted => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("grace"),
+            escrow_balance: Some(e.escrow_balance),
+            ..Default::default()
+        })],
+        StreamRescued => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            status: Some("active"),
+            escrow_balance: Some(e.escrow_balance),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        StreamTemplateCreated => |_| vec![],
+        StreamFeeDiscountSet => |_| vec![],
+        ClaimNftMinted => |_| vec![],
+        UsageReported => |_| vec![],
+        StreamStale => |_| vec![],
+        RateScheduleSet => |_| vec![],
+        RateChangeProposed => |_| vec![],
+        RateChangeAccepted => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            rate_per_second: Some(e.new_rate),
+            updated_at: Some(e.effective_at),
+            ..Default::default()
+        })],
+        MilestoneAdded => |_| vec![],
+        MilestoneApproved => |_| vec![],
+        MilestoneReleased => |e| vec![Entity::Stream(StreamRow {
+            pubkey: e.stream,
+            escrow_balance: Some(e.escrow_remaining),
+            ..Default::default()
+        })],
+    })
+}
+
+fn task_market_event(disc: [u8; 8], body: &[u8]) -> Option<(&'static str, Vec<Entity>)> {
+    use task_market::{
+        AssignedTaskCancelled, BidBondForfeited, BidClosed, BidCommitted, BidExpired, BidRejected,
+        BidRevealed, BidSubmitted, BidWithdrawn, BlacklistUpdated, OccurrenceSpawned,
+        RecurringTaskCancelled, RecurringTaskCreated, RemainingBidsRejected, SealedBiddingEnabled,
+        SlaBreached, TaskAborted, TaskAllowlistSet, TaskAssigned, TaskBoosted, TaskCancelled,
+        TaskClosed, TaskCompleted, TaskCreated, TaskDelegatedToSwarm, TaskDisputeOpened,
+        TaskDisputeResolved, TaskDisputed, TaskEscalationSet, TaskExpired, TaskGeofenceSet,
+        TaskIndexed, TaskInsuranceClaimed, TaskInsured, TaskMilestoneApproved,
+        TaskMilestoneCompleted, TaskMilestonesSet, TaskPendingVerification, TaskProgressUpdated,
+        TaskRewardEscalated, TaskStarted, TaskTemplateCreated, TaskUpdated,
+    };
+
+    match_events!(disc, body, {
+        TaskCreated => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            kind: Some("single"),
+            creator: Some(e.creator),
+            title: Some(e.title),
+            reward: Some(e.reward),
+            status: Some("open"),
+            progress: Some(0),
+            expires_at: Some(e.expires_at),
+            ..Default::default()
+        })],
+        TaskUpdated => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            reward: Some(e.reward),
+            expires_at: Some(e.expires_at),
+            ..Default::default()
+        })],
+        BidSubmitted => |e| vec![Entity::Bid(BidRow {
+            pubkey: e.bid,
+            kind: Some("single"),
+            task: Some(e.task),
+            bidder: Some(e.robot),
+            proposed_rate: Some(e.proposed_rate),
+            status: Some("pending"),
+            ..Default::default()
+        })],
+        BidCommitted => |e| vec![Entity::Bid(BidRow {
+            pubkey: e.bid,
+            kind: Some("single"),
+            task: Some(e.task),
+            bidder: Some(e.robot),
+            status: Some("committed"),
+            ..Default::default()
+        })],
+        BidRevealed => |e| vec![Entity::Bid(BidRow {
+            pubkey: e.bid,
+            proposed_rate: Some(e.proposed_rate),
+            status: Some("pending"),
+            ..Default::default()
+        })],
+        BidRejected => |e| vec![Entity::Bid(BidRow {
+            pubkey: e.bid,
+            status: Some("rejected"),
+            ..Default::default()
+        })],
+        BidWithdrawn => |e| vec![Entity::Bid(BidRow {
+            pubkey: e.bid,
+            status: Some("withdrawn"),
+            ..Default::default()
+        })],
+        BidExpired => |e| vec![Entity::Bid(BidRow {
+            pubkey: e.bid,
+            status: Some("expired"),
+            ..Default::default()
+        })],
+        BidClosed => |e| vec![Entity::Bid(BidRow {
+            pubkey: e.bid,
+            status: Some("closed"),
+            ..Default::default()
+        })],
+        TaskAssigned => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("assigned"),
+            assignee: Some(e.robot),
+            rate_per_second: Some(e.rate),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        TaskStarted => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("in_progress"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        TaskProgressUpdated => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            progress: Some(e.progress),
+            ..Default::default()
+        })],
+        TaskPendingVerification => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("pending_verification"),
+            progress: Some(100),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        TaskCompleted => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("completed"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        TaskDisputed => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("disputed"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        TaskDisputeOpened => |_| vec![],
+        SealedBiddingEnabled => |_| vec![],
+        RecurringTaskCreated => |_| vec![],
+        OccurrenceSpawned => |_| vec![],
+        RecurringTaskCancelled => |_| vec![],
+        TaskMilestonesSet => |_| vec![],
+        TaskGeofenceSet => |_| vec![],
+        TaskEscalationSet => |_| vec![],
+        TaskRewardEscalated => |_| vec![],
+        RemainingBidsRejected => |_| vec![],
+        TaskIndexed => |_| vec![],
+        TaskAllowlistSet => |_| vec![],
+        TaskDelegatedToSwarm => |_| vec![],
+        SlaBreached => |_| vec![],
+        TaskInsured => |_| vec![],
+        TaskInsuranceClaimed => |_| vec![],
+        BlacklistUpdated => |_| vec![],
+        TaskBoosted => |_| vec![],
+        BidBondForfeited => |_| vec![],
+        TaskMilestoneCompleted => |_| vec![],
+        TaskMilestoneApproved => |_| vec![],
+        TaskTemplateCreated => |_| vec![],
+        TaskDisputeResolved => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some(if e.upheld { "failed" } else { "completed" }),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        TaskCancelled => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("cancelled"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        AssignedTaskCancelled => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some(if e.reopened { "open" } else { "cancelled" }),
+            progress: e.reopened.then_some(0),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        TaskAborted => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("failed"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        TaskExpired => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("cancelled"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+        TaskClosed => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("closed"),
+            updated_at: Some(e.timestamp),
+            ..Default::default()
+        })],
+    })
+}
+
+fn token_event(disc: [u8; 8], body: &[u8]) -> Option<(&'static str, Vec<Entity>)> {
+    use droneos_token::{
+        AccountMigrated, AutoRelockEnabled, BuybackBurned, BuybackLimitSet, DelegationIncomeClaimed,
+        EmissionScheduleSet, EpochRewardsDistributed, ExitProcessed, FeesDeposited,
+        InitialSupplyMinted, InsuranceClaimFiled, InsuranceClaimSettled, InsurancePremiumPaid,
+        LockExtended, LockTiersSet, OperatorIncomeShared, OperatorReputationRecovered,
+        OperatorSlashed, OperatorStakeCreated, OperatorStakeToppedUp, OperatorUnstakeRequested,
+        OperatorUnstaked, ParametersApplied, ParametersQueued, ProposalCreated, ProposalExecuted,
+        ProposalVoteCast, ReferralRewarded, ReputationBoostUpdated, RewardsClaimed,
+        RewardsCompounded, RewardsVaultFunded, RewardsVaultShortfall, SlashAppealSettled,
+        SlashAppealed, SnapshotTaken, StakeDelegated, StakeReceiptMinted, StakeUndelegated,
+        SwarmStakeCreated, SwarmStakeSlashed, TaskBondForfeited, TaskInsurancePaid, TokensStaked,
+        TokensUnstaked, TransferFeeUpdated, TransferFeesWithdrawn, TreasuryBurned,
+        UndelegationRequested, UnlockRequested, UnstakeQueued, VotingPowerUpdated,
+    };
+
+    match_events!(disc, body, {
+        InitialSupplyMinted => |_| vec![],
+        TokensStaked => |_| vec![],
+        RewardsClaimed => |_| vec![],
+        TokensUnstaked => |_| vec![],
+        OperatorStakeCreated => |_| vec![],
+        OperatorSlashed => |_| vec![],
+        EmissionScheduleSet => |_| vec![],
+        RewardsVaultFunded => |_| vec![],
+        RewardsCompounded => |_| vec![],
+        LockExtended => |_| vec![],
+        VotingPowerUpdated => |_| vec![],
+        ProposalCreated => |_| vec![],
+        ProposalVoteCast => |_| vec![],
+        ProposalExecuted => |_| vec![],
+        OperatorStakeToppedUp => |_| vec![],
+        OperatorUnstakeRequested => |_| vec![],
+        OperatorUnstaked => |_| vec![],
+        SlashAppealed => |_| vec![],
+        SlashAppealSettled => |_| vec![],
+        InsuranceClaimFiled => |_| vec![],
+        InsuranceClaimSettled => |_| vec![],
+        InsurancePremiumPaid => |_| vec![],
+        TaskInsurancePaid => |_| vec![],
+        OperatorReputationRecovered => |_| vec![],
+        ParametersQueued => |_| vec![],
+        ParametersApplied => |_| vec![],
+        FeesDeposited => |_| vec![],
+        TreasuryBurned => |_| vec![],
+        TransferFeeUpdated => |_| vec![],
+        TransferFeesWithdrawn => |_| vec![],
+        StakeDelegated => |_| vec![],
+        UndelegationRequested => |_| vec![],
+        StakeUndelegated => |_| vec![],
+        DelegationIncomeClaimed => |_| vec![],
+        OperatorIncomeShared => |_| vec![],
+        ReferralRewarded => |_| vec![],
+        SnapshotTaken => |_| vec![],
+        StakeReceiptMinted => |_| vec![],
+        EpochRewardsDistributed => |_| vec![],
+        RewardsVaultShortfall => |_| vec![],
+        AutoRelockEnabled => |_| vec![],
+        UnlockRequested => |_| vec![],
+        AccountMigrated => |_| vec![],
+        BuybackLimitSet => |_| vec![],
+        BuybackBurned => |_| vec![],
+        SwarmStakeCreated => |_| vec![],
+        SwarmStakeSlashed => |_| vec![],
+        ReputationBoostUpdated => |_| vec![],
+        UnstakeQueued => |_| vec![],
+        ExitProcessed => |_| vec![],
+        LockTiersSet => |_| vec![],
+        TaskBondForfeited => |_| vec![],
+    })
+}
+
+fn swarm_coordinator_event(disc: [u8; 8], body: &[u8]) -> Option<(&'static str, Vec<Entity>)> {
+    use swarm_coordinator::{
+        CoordinatorInitialized, GroupTaskCompleted, GroupTaskCreated, GroupTaskFailed,
+        RewardDistributed, RobotJoinedSwarm, SwarmBidAccepted, SwarmBidSubmitted, SwarmCreated,
+    };
+
+    match_events!(disc, body, {
+        CoordinatorInitialized => |_| vec![],
+        SwarmCreated => |_| vec![],
+        RobotJoinedSwarm => |_| vec![],
+        GroupTaskCreated => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            kind: Some("group"),
+            creator: Some(e.creator),
+            reward: Some(e.total_reward),
+            status: Some("open"),
+            ..Default::default()
+        })],
+        SwarmBidSubmitted => |e| vec![Entity::Bid(BidRow {
+            pubkey: e.bid,
+            kind: Some("swarm"),
+            task: Some(e.task),
+            bidder: Some(e.swarm),
+            status: Some("pending"),
+            ..Default::default()
+        })],
+        SwarmBidAccepted => |e| vec![
+            Entity::Task(TaskRow {
+                pubkey: e.task,
+                status: Some("in_progress"),
+                assignee: Some(e.swarm),
+                ..Default::default()
+            }),
+            Entity::Bid(BidRow {
+                pubkey: e.bid,
+                status: Some("accepted"),
+                ..Default::default()
+            }),
+        ],
+        GroupTaskCompleted => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("completed"),
+            ..Default::default()
+        })],
+        RewardDistributed => |_| vec![],
+        GroupTaskFailed => |e| vec![Entity::Task(TaskRow {
+            pubkey: e.task,
+            status: Some("failed"),
+            ..Default::default()
+        })],
+    })
+}
+
+fn oracle_verifier_event(disc: [u8; 8], body: &[u8]) -> Option<(&'static str, Vec<Entity>)> {
+    use oracle_verifier::{
+        CompletionProofSubmitted, DisputeCreated, DisputeResolved, DisputeVoted,
+        GPSProofSubmitted, OracleRegistered, ProofVerified, TaskAutoVerified,
+        VerifierInitialized,
+    };
+
+    match_events!(disc, body, {
+        VerifierInitialized => |_| vec![],
+        OracleRegistered => |_| vec![],
+        GPSProofSubmitted => |e| vec![Entity::Proof(ProofRow {
+            pubkey: e.proof,
+            task: Some(e.task),
+            robot: Some(e.robot),
+            proof_type: Some("gps"),
+            status: Some("pending"),
+            latitude: Some(e.latitude),
+            longitude: Some(e.longitude),
+            ..Default::default()
+        })],
+        CompletionProofSubmitted => |e| vec![Entity::Proof(ProofRow {
+            pubkey: e.proof,
+            task: Some(e.task),
+            robot: Some(e.robot),
+            proof_type: Some("completion"),
+            status: Some("pending"),
+            data_hash: Some(e.data_hash),
+            ..Default::default()
+        })],
+        // The final status depends on the verifier's confidence threshold, so
+        // it is picked up from the refreshed account rather than the event.
+        ProofVerified => |e| vec![Entity::Proof(ProofRow {
+            pubkey: e.proof,
+            oracle: Some(e.oracle),
+            confidence_score: Some(e.confidence_score),
+            ..Default::default()
+        })],
+        DisputeCreated => |e| vec![Entity::Dispute(DisputeRow {
+            pubkey: e.dispute,
+            proof: Some(e.proof),
+            challenger: Some(e.challenger),
+            status: Some("open"),
+            votes_for: Some(0),
+            votes_against: Some(0),
+            ..Default::default()
+        })],
+        DisputeVoted => |e| vec![Entity::Dispute(DisputeRow {
+            pubkey: e.dispute,
+            ..Default::default()
+        })],
+        DisputeResolved => |e| vec![Entity::Dispute(DisputeRow {
+            pubkey: e.dispute,
+            status: Some(dispute_status(&e.outcome)),
+            votes_for: Some(e.votes_for),
+            votes_against: Some(e.votes_against),
+            ..Default::default()
+        })],
+        TaskAutoVerified => |_| vec![],
+    })
+}
+
+// ============================================================================
+// ACCOUNTS
+// ============================================================================
+
+/// Decode a program-owned account into its full normalized row, if it is one
+/// of the indexed entity types.
+pub fn decode_account(program: ProgramKind, pubkey: Pubkey, data: &[u8]) -> Option<Entity> {
+    match program {
+        ProgramKind::PaymentStreams => {
+            let s = payment_streams::PaymentStream::try_deserialize(&mut &data[..]).ok()?;
+            Some(Entity::Stream(StreamRow {
+                pubkey,
+                payer: Some(s.payer),
+                payee: Some(s.payee),
+                rate_per_second: Some(s.rate_per_second),
+                status: Some(stream_status(s.status)),
+                escrow_balance: Some(s.escrow_balance),
+                total_paid: Some(s.total_paid),
+                total_ticks: Some(s.total_ticks),
+                task: s.task_id,
+                updated_at: Some(s.last_tick_at.max(s.created_at)),
+                seq: Some(s.event_seq),
+            }))
+        }
+        ProgramKind::TaskMarket => {
+            if let Ok(t) = task_market::Task::try_deserialize(&mut &data[..]) {
+                return Some(Entity::Task(TaskRow {
+                    pubkey,
+                    kind: Some("single"),
+                    creator: Some(t.creator),
+                    title: Some(t.title()),
+                    reward: Some(t.reward),
+                    rate_per_second: Some(t.rate_per_second),
+                    status: Some(task_status(t.status())),
+                    assignee: task_market::key_if_set(t.assigned_robot),
+                    progress: Some(t.progress),
+                    expires_at: Some(t.expires_at),
+                    updated_at: None,
+                    seq: Some(t.event_seq),
+                }));
+            }
+            let b = task_market::Bid::try_deserialize(&mut &data[..]).ok()?;
+            Some(Entity::Bid(BidRow {
+                pubkey,
+                kind: Some("single"),
+                task: Some(b.task),
+                bidder: Some(b.robot),
+                operator: Some(b.operator),
+                proposed_rate: Some(b.proposed_rate),
+                status: Some(bid_status(b.status)),
+                updated_at: Some(b.submitted_at),
+                seq: Some(b.event_seq),
+            }))
+        }
+        ProgramKind::SwarmCoordinator => {
+            if let Ok(t) = swarm_coordinator::GroupTask::try_deserialize(&mut &data[..]) {
+                return Some(Entity::Task(TaskRow {
+                    pubkey,
+                    kind: Some("group"),
+                    creator: Some(t.creator),
+                    title: Some(t.title),
+                    reward: Some(t.total_reward),
+                    rate_per_second: None,
+                    status: Some(group_task_status(&t.status)),
+                    assignee: t.assigned_swarm,
+                    progress: None,
+                    expires_at: None,
+                    updated_at: t.completed_at.or(t.started_at),
+                    seq: Some(t.event_seq),
+                }));
+            }
+            let b = swarm_coordinator::SwarmBid::try_deserialize(&mut &data[..]).ok()?;
+            Some(Entity::Bid(BidRow {
+                pubkey,
+                kind: Some("swarm"),
+                task: Some(b.task),
+                bidder: Some(b.swarm),
+                operator: None,
+                proposed_rate: Some(b.proposed_rate),
+                status: Some(swarm_bid_status(&b.status)),
+                updated_at: Some(b.submitted_at),
+                seq: Some(b.event_seq),
+            }))
+        }
+        ProgramKind::OracleVerifier => {
+            if let Ok(p) = oracle_verifier::Proof::try_deserialize(&mut &data[..]) {
+                return Some(Entity::Proof(ProofRow {
+                    pubkey,
+                    task: Some(p.task),
+                    robot: Some(p.robot),
+                    oracle: Some(p.oracle),
+                    proof_type: Some(proof_type(&p.proof_type)),
+                    status: Some(proof_status(&p.status)),
+                    confidence_score: Some(p.confidence_score),
+                    latitude: p.latitude,
+                    longitude: p.longitude,
+                    data_hash: p.data_hash,
+                    updated_at: Some(p.verified_at.unwrap_or(p.submitted_at)),
+                    seq: Some(p.event_seq),
+                }));
+            }
+            let d = oracle_verifier::Dispute::try_deserialize(&mut &data[..]).ok()?;
+            Some(Entity::Dispute(DisputeRow {
+                pubkey,
+                proof: Some(d.proof),
+                challenger: Some(d.challenger),
+                status: Some(dispute_status(&d.status)),
+                votes_for: Some(d.votes_for),
+                votes_against: Some(d.votes_against),
+                updated_at: Some(d.resolved_at.unwrap_or(d.created_at)),
+                seq: Some(d.event_seq),
+            }))
+        }
+        ProgramKind::IdentityRegistry | ProgramKind::Token => None,
+    }
+}
+
+// ============================================================================
+// STATUS NAMES
+// ============================================================================
+
+fn stream_status(status: payment_streams::StreamStatus) -> &'static str {
+    use payment_streams::StreamStatus::*;
+    match status {
+        Pending => "pending",
+        Accepted => "accepted",
+        Active => "active",
+        Paused => "paused",
+        Completed => "completed",
+        Cancelled => "cancelled",
+        Disputed => "disputed",
+        Grace => "grace",
+    }
+}
+
+fn task_status(status: task_market::TaskStatus) -> &'static str {
+    use task_market::TaskStatus::*;
+    match status {
+        Open => "open",
+        Assigned => "assigned",
+        InProgress => "in_progress",
+        PendingVerification => "pending_verification",
+        Completed => "completed",
+        Failed => "failed",
+        Cancelled => "cancelled",
+        Disputed => "disputed",
+    }
+}
+
+fn bid_status(status: task_market::BidStatus) -> &'static str {
+    use task_market::BidStatus::*;
+    match status {
+        Pending => "pending",
+        Accepted => "accepted",
+        Rejected => "rejected",
+        Withdrawn => "withdrawn",
+        Expired => "expired",
+        Committed => "committed",
+    }
+}
+
+fn group_task_status(status: &swarm_coordinator::GroupTaskStatus) -> &'static str {
+    use swarm_coordinator::GroupTaskStatus::*;
+    match status {
+        Open => "open",
+        InProgress => "in_progress",
+        Completed => "completed",
+        Cancelled => "cancelled",
+        Failed => "failed",
+    }
+}
+
+fn swarm_bid_status(status: &swarm_coordinator::BidStatus) -> &'static str {
+    use swarm_coordinator::BidStatus::*;
+    match status {
+        Pending => "pending",
+        Accepted => "accepted",
+        Rejected => "rejected",
+    }
+}
+
+fn proof_type(proof_type: &oracle_verifier::ProofType) -> &'static str {
+    use oracle_verifier::ProofType::*;
+    match proof_type {
+        GPS => "gps",
+        Completion => "completion",
+        Sensor => "sensor",
+    }
+}
+
+fn proof_status(status: &oracle_verifier::ProofStatus) -> &'static str {
+    use oracle_verifier::ProofStatus::*;
+    match status {
+        Pending => "pending",
+        Verified => "verified",
+        Failed => "failed",
+        Disputed => "disputed",
+    }
+}
+
+fn dispute_status(status: &oracle_verifier::DisputeStatus) -> &'static str {
+    use oracle_verifier::DisputeStatus::*;
+    match status {
+        Open => "open",
+        ChallengerWins => "challenger_wins",
+        OracleWins => "oracle_wins",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::{AccountSerialize, Event};
+    use droneos_events::ProgramTag;
+    use payment_streams::{PaymentStream, StreamCreated, StreamMode, StreamStatus, StreamTick};
+
+    use super::*;
+
+    fn header(entity: Pubkey, seq: u64) -> EventHeader {
+        EventHeader {
+            version: 1,
+            program: ProgramTag::PaymentStreams,
+            entity,
+            seq,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    fn tick(stream: Pubkey, seq: u64) -> StreamTick {
+        StreamTick {
+            header: header(stream, seq),
+            stream,
+            tick_number: 3,
+            amount: 60,
+            total_paid: 180,
+            escrow_remaining: 3_420,
+            timestamp: 1_700_000_180,
+            fee: 1,
+            tip: 0,
+            referral: 0,
+        }
+    }
+
+    fn stream_row(entities: &[Entity]) -> &StreamRow {
+        match entities {
+            [Entity::Stream(row)] => row,
+            other => panic!("expected one stream row, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn decodes_an_event_payload_into_its_rows() {
+        let stream = Pubkey::new_unique();
+        let created = StreamCreated {
+            header: header(stream, 1),
+            stream,
+            payer: Pubkey::new_unique(),
+            payee: Pubkey::new_unique(),
+            rate_per_second: 1,
+            escrow_amount: 3_600,
+            timestamp: 1_700_000_000,
+            fee_discount_bps: 0,
+        };
+
+        let event = decode_event(ProgramKind::PaymentStreams, created.data()).unwrap();
+        assert_eq!(event.name, "StreamCreated");
+        assert_eq!(event.header, created.header);
+        assert_eq!(event.data, created.data());
+
+        let row = stream_row(&event.entities);
+        assert_eq!(row.pubkey, stream);
+        assert_eq!(row.payer, Some(created.payer));
+        assert_eq!(row.payee, Some(created.payee));
+        assert_eq!(row.status, Some("pending"));
+        assert_eq!(row.escrow_balance, Some(3_600));
+        assert_eq!(row.total_paid, Some(0));
+        assert_eq!(row.seq, Some(1));
+    }
+
+    #[test]
+    fn keeps_the_header_seq_across_a_gap() {
+        let stream = Pubkey::new_unique();
+        // Events 2 and 3 of the stream were missed
+        let event = decode_event(ProgramKind::PaymentStreams, tick(stream, 4).data()).unwrap();
+        assert_eq!(event.name, "StreamTick");
+        assert_eq!(event.header.seq, 4);
+
+        let row = stream_row(&event.entities);
+        assert_eq!(row.seq, Some(4));
+        assert_eq!(row.total_paid, Some(180));
+        assert_eq!(row.escrow_balance, Some(3_420));
+        assert_eq!(row.total_ticks, Some(3));
+        assert_eq!(row.status, None);
+    }
+
+    #[test]
+    fn leaves_seq_unset_on_rows_other_than_the_header_entity() {
+        let stream = Pubkey::new_unique();
+        let mut ev = tick(stream, 2);
+        ev.header.entity = Pubkey::new_unique();
+
+        let event = decode_event(ProgramKind::PaymentStreams, ev.data()).unwrap();
+        assert_eq!(stream_row(&event.entities).seq, None);
+    }
+
+    #[test]
+    fn rejects_unknown_and_truncated_payloads() {
+        let data = tick(Pubkey::new_unique(), 1).data();
+        // Another program's event space
+        assert!(decode_event(ProgramKind::TaskMarket, data.clone()).is_none());
+        assert!(decode_event(ProgramKind::PaymentStreams, data[..8].to_vec()).is_none());
+        assert!(decode_event(ProgramKind::PaymentStreams, data[..40].to_vec()).is_none());
+
+        let mut unknown = data;
+        unknown[0] ^= 0xff;
+        assert!(decode_event(ProgramKind::PaymentStreams, unknown).is_none());
+    }
+
+    fn stream_account() -> PaymentStream {
+        PaymentStream {
+            payer: Pubkey::new_unique(),
+            payee: Pubkey::new_unique(),
+            rate_per_second: 2,
+            max_duration: 3_600,
+            grace_period: 60,
+            auto_terminate: true,
+            status: StreamStatus::Active,
+            created_at: 1_700_000_000,
+            started_at: 1_700_000_010,
+            last_tick_at: 1_700_000_100,
+            total_paid: 180,
+            total_ticks: 2,
+            escrow_balance: 7_020,
+            task_id: Some(Pubkey::new_unique()),
+            rate_schedule: vec![],
+            mode: StreamMode::Continuous,
+            milestones: vec![],
+            grace_started_at: 0,
+            accrued_unpaid: 0,
+            low_balance_threshold_seconds: 0,
+            pending_rate: None,
+            security_deposit: 0,
+            fee_discount_bps: 0,
+            tick_authority: None,
+            claim_mint: None,
+            activation_fee: 0,
+            metering: None,
+            arrears: 0,
+            early_termination: None,
+            min_tick_interval: None,
+            cliff_seconds: 0,
+            nonce: 0,
+            referrer: None,
+            task_creator: Pubkey::new_unique(),
+            task_index: 0,
+            task_bump: 255,
+            event_seq: 5,
+            escrow_bump: 255,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn decodes_account_bytes_into_a_full_row() {
+        let stream = stream_account();
+        let mut data = Vec::new();
+        stream.try_serialize(&mut data).unwrap();
+        let pubkey = Pubkey::new_unique();
+
+        let Some(Entity::Stream(row)) = decode_account(ProgramKind::PaymentStreams, pubkey, &data)
+        else {
+            panic!("expected a stream row");
+        };
+        assert_eq!(row.pubkey, pubkey);
+        assert_eq!(row.payer, Some(stream.payer));
+        assert_eq!(row.payee, Some(stream.payee));
+        assert_eq!(row.rate_per_second, Some(2));
+        assert_eq!(row.status, Some("active"));
+        assert_eq!(row.escrow_balance, Some(7_020));
+        assert_eq!(row.total_paid, Some(180));
+        assert_eq!(row.total_ticks, Some(2));
+        assert_eq!(row.task, stream.task_id);
+        assert_eq!(row.updated_at, Some(1_700_000_100));
+        assert_eq!(row.seq, Some(5));
+    }
+
+    #[test]
+    fn skips_accounts_that_are_not_indexed_entities() {
+        let mut data = Vec::new();
+        stream_account().try_serialize(&mut data).unwrap();
+        let pubkey = Pubkey::new_unique();
+
+        // Neither a task nor a bid
+        assert!(decode_account(ProgramKind::TaskMarket, pubkey, &data).is_none());
+        assert!(decode_account(ProgramKind::Token, pubkey, &data).is_none());
+        assert!(decode_account(ProgramKind::PaymentStreams, pubkey, &data[..8]).is_none());
+    }
+}
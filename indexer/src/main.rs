@@ -0,0 +1,214 @@
+//! $DRONEOS Indexer
+//!
+//! Websocket consumer for the six protocol programs:
+//! - Fetches every transaction mentioning a program and decodes the events
+//!   it emitted via self-CPI (including ones emitted inside CPIs)
+//! - Keeps per-entity event order using the sequence number in each header
+//! - Normalizes streams, tasks, bids, proofs and disputes into Postgres
+//! - Refreshes touched accounts so rows reflect on-chain state
+//! - Optional backfill from `getProgramAccounts` on startup
+
+mod decode;
+mod model;
+mod store;
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{
+    RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+use tokio::sync::mpsc;
+
+use crate::model::{ProgramKind, TxContext};
+use crate::store::Store;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+struct Config {
+    rpc_url: String,
+    ws_url: String,
+    database_url: String,
+    backfill: bool,
+}
+
+impl Config {
+    fn from_env() -> Result<Self, BoxError> {
+        Ok(Self {
+            rpc_url: std::env::var("DRONEOS_RPC_URL")
+                .unwrap_or_else(|_| "http://127.0.0.1:8899".to_string()),
+            ws_url: std::env::var("DRONEOS_WS_URL")
+                .unwrap_or_else(|_| "ws://127.0.0.1:8900".to_string()),
+            database_url: std::env::var("DATABASE_URL")
+                .map_err(|_| "DATABASE_URL must be set")?,
+            backfill: std::env::var("DRONEOS_BACKFILL").is_ok_and(|v| v == "1" || v == "true"),
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), BoxError> {
+    let config = Config::from_env()?;
+    let store = Arc::new(Store::connect(&config.database_url).await?);
+    let rpc = RpcClient::new_with_commitment(config.rpc_url.clone(), CommitmentConfig::confirmed());
+
+    if config.backfill {
+        backfill(&rpc, &store).await?;
+    }
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    for program in ProgramKind::ALL {
+        tokio::spawn(subscribe(config.ws_url.clone(), program, sender.clone()));
+    }
+    drop(sender);
+
+    while let Some(tx) = receiver.recv().await {
+        if let Err(err) = process(&rpc, &store, tx).await {
+            eprintln!("failed to index transaction: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Stream signatures of successful transactions mentioning one program
+/// forever, reconnecting on websocket errors. Events aren't read from the logs
+/// (they are emitted via self-CPI), the subscription only tells us which
+/// transactions to fetch.
+async fn subscribe(ws_url: String, program: ProgramKind, sender: mpsc::UnboundedSender<TxContext>) {
+    loop {
+        if let Err(err) = subscribe_once(&ws_url, program, &sender).await {
+            eprintln!("{} log subscription dropped: {err}", program.as_str());
+        }
+        if sender.is_closed() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+async fn subscribe_once(
+    ws_url: &str,
+    program: ProgramKind,
+    sender: &mpsc::UnboundedSender<TxContext>,
+) -> Result<(), BoxError> {
+    let client = PubsubClient::new(ws_url).await?;
+    let (mut logs, _unsubscribe) = client
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![program.program_id().to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(CommitmentConfig::confirmed()),
+            },
+        )
+        .await?;
+
+    while let Some(response) = logs.next().await {
+        if response.value.err.is_some() {
+            continue;
+        }
+        let tx = TxContext {
+            signature: response.value.signature,
+            slot: response.context.slot,
+        };
+        if sender.send(tx).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Fetch a transaction, decode its events, log them and refresh the entities
+/// they touch
+async fn process(rpc: &RpcClient, store: &Store, tx: TxContext) -> Result<(), BoxError> {
+    if store.has_transaction(&tx.signature).await? {
+        // Already indexed through another program's subscription
+        return Ok(());
+    }
+
+    let confirmed = rpc
+        .get_transaction_with_config(
+            &Signature::from_str(&tx.signature)?,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+
+    let mut touched = BTreeMap::new();
+
+    for (event_index, (program, data)) in decode::extract_event_data(&confirmed.transaction)
+        .into_iter()
+        .enumerate()
+    {
+        let Some(event) = decode::decode_event(program, data) else {
+            continue;
+        };
+        if !store.insert_event(&tx, event_index as u32, &event).await? {
+            continue;
+        }
+        for entity in &event.entities {
+            store.upsert(entity).await?;
+            touched.insert(entity.pubkey(), program);
+        }
+    }
+
+    refresh(rpc, store, touched).await
+}
+
+/// Re-read touched accounts so derived fields (statuses, balances) match chain
+/// state. Closed accounts are skipped and keep their last event-derived row.
+async fn refresh(
+    rpc: &RpcClient,
+    store: &Store,
+    touched: BTreeMap<Pubkey, ProgramKind>,
+) -> Result<(), BoxError> {
+    if touched.is_empty() {
+        return Ok(());
+    }
+
+    let keys: Vec<Pubkey> = touched.keys().copied().collect();
+    let accounts = rpc.get_multiple_accounts(&keys).await?;
+
+    for (pubkey, account) in keys.into_iter().zip(accounts) {
+        let Some(account) = account else {
+            continue;
+        };
+        let Some(program) = ProgramKind::from_program_id(&account.owner) else {
+            continue;
+        };
+        if let Some(entity) = decode::decode_account(program, pubkey, &account.data) {
+            store.upsert(&entity).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Seed the entity tables from every account the programs currently own
+async fn backfill(rpc: &RpcClient, store: &Store) -> Result<(), BoxError> {
+    for program in ProgramKind::ALL {
+        let accounts = rpc.get_program_accounts(&program.program_id()).await?;
+        let mut indexed = 0usize;
+        for (pubkey, account) in accounts {
+            if let Some(entity) = decode::decode_account(program, pubkey, &account.data) {
+                store.upsert(&entity).await?;
+                indexed += 1;
+            }
+        }
+        println!("backfilled {indexed} {} accounts", program.as_str());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use anchor_lang::prelude::Pubkey;
+use droneos_events::EventHeader;
+
+/// The six $DRONEOS programs an event or account can originate from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgramKind {
+    IdentityRegistry,
+    PaymentStreams,
+    TaskMarket,
+    Token,
+    SwarmCoordinator,
+    OracleVerifier,
+}
+
+impl ProgramKind {
+    pub const ALL: [ProgramKind; 6] = [
+        ProgramKind::IdentityRegistry,
+        ProgramKind::PaymentStreams,
+        ProgramKind::TaskMarket,
+        ProgramKind::Token,
+        ProgramKind::SwarmCoordinator,
+        ProgramKind::OracleVerifier,
+    ];
+
+    pub fn program_id(self) -> Pubkey {
+        match self {
+            ProgramKind::IdentityRegistry => identity_registry::ID,
+            ProgramKind::PaymentStreams => payment_streams::ID,
+            ProgramKind::TaskMarket => task_market::ID,
+            ProgramKind::Token => droneos_token::ID,
+            ProgramKind::SwarmCoordinator => swarm_coordinator::ID,
+            ProgramKind::OracleVerifier => oracle_verifier::ID,
+        }
+    }
+
+    pub fn from_program_id(id: &Pubkey) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.program_id() == *id)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProgramKind::IdentityRegistry => "identity_registry",
+            ProgramKind::PaymentStreams => "payment_streams",
+            ProgramKind::TaskMarket => "task_market",
+            ProgramKind::Token => "droneos_token",
+            ProgramKind::SwarmCoordinator => "swarm_coordinator",
+            ProgramKind::OracleVerifier => "oracle_verifier",
+        }
+    }
+}
+
+// ============================================================================
+// NORMALIZED ROWS
+// ============================================================================
+//
+// Every column except the primary key is optional: events only carry part of
+// an entity's state, so `None` means "leave the stored value untouched".
+// `seq` is the entity's event sequence number the row reflects; rows with a
+// lower `seq` than the stored one are stale and dropped.
+
+#[derive(Clone, Debug, Default)]
+pub struct StreamRow {
+    pub pubkey: Pubkey,
+    pub payer: Option<Pubkey>,
+    pub payee: Option<Pubkey>,
+    pub rate_per_second: Option<u64>,
+    pub status: Option<&'static str>,
+    pub escrow_balance: Option<u64>,
+    pub total_paid: Option<u64>,
+    pub total_ticks: Option<u32>,
+    pub task: Option<Pubkey>,
+    pub updated_at: Option<i64>,
+    pub seq: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TaskRow {
+    pub pubkey: Pubkey,
+    pub kind: Option<&'static str>, // "single" | "group"
+    pub creator: Option<Pubkey>,
+    pub title: Option<String>,
+    pub reward: Option<u64>,
+    pub rate_per_second: Option<u64>,
+    pub status: Option<&'static str>,
+    pub assignee: Option<Pubkey>, // Robot for single tasks, swarm for group tasks
+    pub progress: Option<u8>,
+    pub expires_at: Option<i64>,
+    pub updated_at: Option<i64>,
+    pub seq: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BidRow {
+    pub pubkey: Pubkey,
+    pub kind: Option<&'static str>, // "single" | "swarm"
+    pub task: Option<Pubkey>,
+    pub bidder: Option<Pubkey>, // Robot for single bids, swarm for swarm bids
+    pub operator: Option<Pubkey>,
+    pub proposed_rate: Option<u64>,
+    pub status: Option<&'static str>,
+    pub updated_at: Option<i64>,
+    pub seq: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProofRow {
+    pub pubkey: Pubkey,
+    pub task: Option<Pubkey>,
+    pub robot: Option<Pubkey>,
+    pub oracle: Option<Pubkey>,
+    pub proof_type: Option<&'static str>,
+    pub status: Option<&'static str>,
+    pub confidence_score: Option<u8>,
+    pub latitude: Option<i64>,
+    pub longitude: Option<i64>,
+    pub data_hash: Option<[u8; 32]>,
+    pub updated_at: Option<i64>,
+    pub seq: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DisputeRow {
+    pub pubkey: Pubkey,
+    pub proof: Option<Pubkey>,
+    pub challenger: Option<Pubkey>,
+    pub status: Option<&'static str>,
+    pub votes_for: Option<u64>,
+    pub votes_against: Option<u64>,
+    pub updated_at: Option<i64>,
+    pub seq: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Entity {
+    Stream(StreamRow),
+    Task(TaskRow),
+    Bid(BidRow),
+    Proof(ProofRow),
+    Dispute(DisputeRow),
+}
+
+impl Entity {
+    pub fn pubkey(&self) -> Pubkey {
+        match self {
+            Entity::Stream(row) => row.pubkey,
+            Entity::Task(row) => row.pubkey,
+            Entity::Bid(row) => row.pubkey,
+            Entity::Proof(row) => row.pubkey,
+            Entity::Dispute(row) => row.pubkey,
+        }
+    }
+
+    pub fn set_seq(&mut self, seq: u64) {
+        match self {
+            Entity::Stream(row) => row.seq = Some(seq),
+            Entity::Task(row) => row.seq = Some(seq),
+            Entity::Bid(row) => row.seq = Some(seq),
+            Entity::Proof(row) => row.seq = Some(seq),
+            Entity::Dispute(row) => row.seq = Some(seq),
+        }
+    }
+}
+
+/// A decoded `emit_cpi!` payload together with the rows it touches
+#[derive(Clone, Debug)]
+pub struct ProtocolEvent {
+    pub program: ProgramKind,
+    pub name: &'static str,
+    pub header: EventHeader,
+    pub data: Vec<u8>,
+    pub entities: Vec<Entity>,
+}
+
+/// Transaction the events were found in
+#[derive(Clone, Debug)]
+pub struct TxContext {
+    pub signature: String,
+    pub slot: u64,
+}
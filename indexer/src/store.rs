@@ -0,0 +1,261 @@
+use anchor_lang::prelude::Pubkey;
+use tokio_postgres::{types::ToSql, Client, Error, NoTls};
+
+use crate::model::*;
+
+/// Postgres sink for decoded events and normalized entity rows
+pub struct Store {
+    client: Client,
+}
+
+impl Store {
+    /// Connect and make sure the schema exists
+    pub async fn connect(database_url: &str) -> Result<Self, Error> {
+        let (client, connection) = tokio_postgres::connect(database_url, NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                eprintln!("postgres connection error: {err}");
+            }
+        });
+
+        client.batch_execute(include_str!("../schema.sql")).await?;
+
+        Ok(Self { client })
+    }
+
+    /// Whether any event of this transaction has been indexed already (the
+    /// same transaction is delivered once per program subscription)
+    pub async fn has_transaction(&self, signature: &str) -> Result<bool, Error> {
+        let row = self
+            .client
+            .query_opt("SELECT 1 FROM events WHERE signature = $1 LIMIT 1", &[&signature])
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// Append an event to the log. Returns false if it was already indexed,
+    /// either at the same position or under the same (entity, seq).
+    pub async fn insert_event(
+        &self,
+        tx: &TxContext,
+        event_index: u32,
+        event: &ProtocolEvent,
+    ) -> Result<bool, Error> {
+        let rows = self
+            .client
+            .execute(
+                "INSERT INTO events
+                     (signature, event_index, slot, program, name, version, entity, seq, timestamp, data)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                 ON CONFLICT DO NOTHING",
+                &[
+                    &tx.signature,
+                    &(event_index as i32),
+                    &(tx.slot as i64),
+                    &event.program.as_str(),
+                    &event.name,
+                    &i16::from(event.header.version),
+                    &event.header.entity.to_string(),
+                    &(event.header.seq as i64),
+                    &event.header.timestamp,
+                    &event.data,
+                ],
+            )
+            .await?;
+
+        Ok(rows == 1)
+    }
+
+    /// Merge a (possibly partial) row into its entity table
+    pub async fn upsert(&self, entity: &Entity) -> Result<(), Error> {
+        match entity {
+            Entity::Stream(row) => {
+                self.merge(
+                    "streams",
+                    &[
+                        "payer",
+                        "payee",
+                        "rate_per_second",
+                        "status",
+                        "escrow_balance",
+                        "total_paid",
+                        "total_ticks",
+                        "task",
+                        "updated_at",
+                    ],
+                    &[
+                        &row.pubkey.to_string(),
+                        &key(row.payer),
+                        &key(row.payee),
+                        &num(row.rate_per_second),
+                        &row.status,
+                        &num(row.escrow_balance),
+                        &num(row.total_paid),
+                        &row.total_ticks.map(i64::from),
+                        &key(row.task),
+                        &row.updated_at,
+                    ],
+                    row.seq,
+                )
+                .await
+            }
+            Entity::Task(row) => {
+                self.merge(
+                    "tasks",
+                    &[
+                        "kind",
+                        "creator",
+                        "title",
+                        "reward",
+                        "rate_per_second",
+                        "status",
+                        "assignee",
+                        "progress",
+                        "expires_at",
+                        "updated_at",
+                    ],
+                    &[
+                        &row.pubkey.to_string(),
+                        &row.kind,
+                        &key(row.creator),
+                        &row.title,
+                        &num(row.reward),
+                        &num(row.rate_per_second),
+                        &row.status,
+                        &key(row.assignee),
+                        &row.progress.map(i64::from),
+                        &row.expires_at,
+                        &row.updated_at,
+                    ],
+                    row.seq,
+                )
+                .await
+            }
+            Entity::Bid(row) => {
+                self.merge(
+                    "bids",
+                    &[
+                        "kind",
+                        "task",
+                        "bidder",
+                        "operator",
+                        "proposed_rate",
+                        "status",
+                        "updated_at",
+                    ],
+                    &[
+                        &row.pubkey.to_string(),
+                        &row.kind,
+                        &key(row.task),
+                        &key(row.bidder),
+                        &key(row.operator),
+                        &num(row.proposed_rate),
+                        &row.status,
+                        &row.updated_at,
+                    ],
+                    row.seq,
+                )
+                .await
+            }
+            Entity::Proof(row) => {
+                self.merge(
+                    "proofs",
+                    &[
+                        "task",
+                        "robot",
+                        "oracle",
+                        "proof_type",
+                        "status",
+                        "confidence_score",
+                        "latitude",
+                        "longitude",
+                        "data_hash",
+                        "updated_at",
+                    ],
+                    &[
+                        &row.pubkey.to_string(),
+                        &key(row.task),
+                        &key(row.robot),
+                        &key(row.oracle),
+                        &row.proof_type,
+                        &row.status,
+                        &row.confidence_score.map(i64::from),
+                        &row.latitude,
+                        &row.longitude,
+                        &row.data_hash.map(|h| h.to_vec()),
+                        &row.updated_at,
+                    ],
+                    row.seq,
+                )
+                .await
+            }
+            Entity::Dispute(row) => {
+                self.merge(
+                    "disputes",
+                    &[
+                        "proof",
+                        "challenger",
+                        "status",
+                        "votes_for",
+                        "votes_against",
+                        "updated_at",
+                    ],
+                    &[
+                        &row.pubkey.to_string(),
+                        &key(row.proof),
+                        &key(row.challenger),
+                        &row.status,
+                        &num(row.votes_for),
+                        &num(row.votes_against),
+                        &row.updated_at,
+                    ],
+                    row.seq,
+                )
+                .await
+            }
+        }
+    }
+
+    /// `INSERT ... ON CONFLICT DO UPDATE` where NULL parameters keep the
+    /// stored value. `params[0]` is the pubkey, followed by one per column.
+    /// Rows older than the stored `seq` are ignored so replays and
+    /// out-of-order deliveries can't roll an entity back.
+    async fn merge(
+        &self,
+        table: &str,
+        columns: &[&str],
+        params: &[&(dyn ToSql + Sync)],
+        seq: Option<u64>,
+    ) -> Result<(), Error> {
+        let seq = num(seq);
+        let mut params = params.to_vec();
+        params.push(&seq);
+
+        let placeholders: Vec<String> = (1..=params.len()).map(|i| format!("${i}")).collect();
+        let updates: Vec<String> = columns
+            .iter()
+            .map(|c| format!("{c} = COALESCE(EXCLUDED.{c}, {table}.{c})"))
+            .collect();
+
+        let sql = format!(
+            "INSERT INTO {table} (pubkey, {}, seq) VALUES ({}) ON CONFLICT (pubkey) DO UPDATE SET {}, \
+             seq = GREATEST(EXCLUDED.seq, {table}.seq) \
+             WHERE EXCLUDED.seq IS NULL OR {table}.seq IS NULL OR EXCLUDED.seq >= {table}.seq",
+            columns.join(", "),
+            placeholders.join(", "),
+            updates.join(", "),
+        );
+
+        self.client.execute(sql.as_str(), &params).await?;
+        Ok(())
+    }
+}
+
+fn key(pubkey: Option<Pubkey>) -> Option<String> {
+    pubkey.map(|k| k.to_string())
+}
+
+fn num(value: Option<u64>) -> Option<i64> {
+    value.map(|v| v as i64)
+}
@@ -1,7 +1,15 @@
 use anchor_lang::prelude::*;
+use droneos_events::{EventHeader, ProgramTag};
 
 declare_id!("DOS4id11111111111111111111111111111111111111");
 
+// Programs that depend on this one. Their ids are declared here because
+// importing them from their crates would be a dependency cycle.
+
+/// task-market, which marks robots busy while assigned to its tasks.
+pub const TASK_MARKET_PROGRAM_ID: Pubkey =
+    pubkey!("DOS4mkt1111111111111111111111111111111111111");
+
 /// $DRONEOS Identity Registry Program
 /// 
 /// Manages robot identities using 403 proofs:
@@ -20,9 +28,11 @@ pub mod identity_registry {
         registry.authority = ctx.accounts.authority.key();
         registry.total_robots = 0;
         registry.total_operators = 0;
+        registry.event_seq = 0;
         registry.bump = ctx.bumps.registry;
         
-        emit!(RegistryInitialized {
+        emit_cpi!(RegistryInitialized {
+            header: event_header(registry.key(), &mut registry.event_seq, Clock::get()?.unix_timestamp),
             authority: registry.authority,
         });
         
@@ -58,11 +68,21 @@ pub mod identity_registry {
         robot.total_earnings = 0;
         robot.status = RobotStatus::Idle;
         robot.capabilities = Vec::new();
+        robot.event_seq = 0;
         robot.bump = ctx.bumps.robot;
 
+        let fleet = &mut ctx.accounts.fleet;
+        if fleet.robot_count == 0 {
+            fleet.operator = ctx.accounts.operator.key();
+            fleet.bump = ctx.bumps.fleet;
+            registry.total_operators += 1;
+        }
+        fleet.robot_count += 1;
+
         registry.total_robots += 1;
 
-        emit!(RobotRegistered {
+        emit_cpi!(RobotRegistered {
+            header: event_header(robot.key(), &mut robot.event_seq, clock.unix_timestamp),
             robot: robot.key(),
             device_id,
             operator: robot.operator,
@@ -80,7 +100,7 @@ pub mod identity_registry {
         certification_level: u8,
         valid_days: u32,
     ) -> Result<()> {
-        require!(certification_level >= 1 && certification_level <= 5, ErrorCode::InvalidCertificationLevel);
+        require!((1..=5).contains(&certification_level), ErrorCode::InvalidCertificationLevel);
         
         let robot = &mut ctx.accounts.robot;
         let clock = Clock::get()?;
@@ -104,7 +124,8 @@ pub mod identity_registry {
             });
         }
 
-        emit!(CapabilityAdded {
+        emit_cpi!(CapabilityAdded {
+            header: event_header(robot.key(), &mut robot.event_seq, clock.unix_timestamp),
             robot: robot.key(),
             capability,
             level: certification_level,
@@ -122,6 +143,10 @@ pub mod identity_registry {
         let robot = &mut ctx.accounts.robot;
         let clock = Clock::get()?;
         
+        // Busy is set and cleared by task-market as robots take on tasks
+        require!(robot.status != RobotStatus::Busy, ErrorCode::RobotBusy);
+        require!(new_status != RobotStatus::Busy, ErrorCode::InvalidStatusTransition);
+        
         // Validate status transition
         require!(
             is_valid_status_transition(robot.status, new_status),
@@ -132,7 +157,8 @@ pub mod identity_registry {
         robot.status = new_status;
         robot.last_active_at = clock.unix_timestamp;
 
-        emit!(RobotStatusChanged {
+        emit_cpi!(RobotStatusChanged {
+            header: event_header(robot.key(), &mut robot.event_seq, clock.unix_timestamp),
             robot: robot.key(),
             old_status,
             new_status,
@@ -142,9 +168,57 @@ pub mod identity_registry {
         Ok(())
     }
 
-    /// Update reputation after task completion
+    /// Mark an available robot busy with a task (task-market CPI, signed by
+    /// its market PDA), so it can't be assigned overlapping tasks
+    pub fn begin_robot_task(ctx: Context<RobotTask>) -> Result<()> {
+        let robot = &mut ctx.accounts.robot;
+        let clock = Clock::get()?;
+
+        require!(robot.status == RobotStatus::Available, ErrorCode::RobotNotAvailable);
+
+        robot.status = RobotStatus::Busy;
+        robot.last_active_at = clock.unix_timestamp;
+
+        emit_cpi!(RobotStatusChanged {
+            header: event_header(robot.key(), &mut robot.event_seq, clock.unix_timestamp),
+            robot: robot.key(),
+            old_status: RobotStatus::Available,
+            new_status: RobotStatus::Busy,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Make a busy robot available again once its task ends (task-market
+    /// CPI, signed by its market PDA). Robots that are not busy, assigned
+    /// before task-market tracked status, are left as they are.
+    pub fn end_robot_task(ctx: Context<RobotTask>) -> Result<()> {
+        let robot = &mut ctx.accounts.robot;
+        let clock = Clock::get()?;
+
+        if robot.status != RobotStatus::Busy {
+            return Ok(());
+        }
+
+        robot.status = RobotStatus::Available;
+        robot.last_active_at = clock.unix_timestamp;
+
+        emit_cpi!(RobotStatusChanged {
+            header: event_header(robot.key(), &mut robot.event_seq, clock.unix_timestamp),
+            robot: robot.key(),
+            old_status: RobotStatus::Busy,
+            new_status: RobotStatus::Available,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Update reputation after a task (task-market CPI, signed by its market
+    /// PDA)
     pub fn update_reputation(
-        ctx: Context<UpdateRobotByProgram>,
+        ctx: Context<RobotTask>,
         delta: i32,
         task_completed: bool,
         earnings: u64,
@@ -153,7 +227,7 @@ pub mod identity_registry {
         let clock = Clock::get()?;
         
         // Apply reputation change (clamped to 0-10000)
-        let new_rep = (robot.reputation_score as i32 + delta).max(0).min(10000);
+        let new_rep = (robot.reputation_score as i32 + delta).clamp(0, 10000);
         robot.reputation_score = new_rep as u16;
         
         if task_completed {
@@ -163,7 +237,8 @@ pub mod identity_registry {
         
         robot.last_active_at = clock.unix_timestamp;
 
-        emit!(ReputationUpdated {
+        emit_cpi!(ReputationUpdated {
+            header: event_header(robot.key(), &mut robot.event_seq, clock.unix_timestamp),
             robot: robot.key(),
             old_score: robot.reputation_score as i32 - delta,
             new_score: robot.reputation_score,
@@ -178,7 +253,7 @@ pub mod identity_registry {
         ctx: Context<VerifyRobot>,
         required_capability: Capability,
     ) -> Result<()> {
-        let robot = &ctx.accounts.robot;
+        let robot = &mut ctx.accounts.robot;
         let clock = Clock::get()?;
         
         // Check robot is active
@@ -187,14 +262,10 @@ pub mod identity_registry {
             ErrorCode::RobotNotActive
         );
         
-        // Find and verify capability
-        let cap = robot.capabilities.iter()
-            .find(|c| c.capability == required_capability)
-            .ok_or(ErrorCode::CapabilityNotFound)?;
-        
-        require!(cap.valid_until > clock.unix_timestamp, ErrorCode::CapabilityExpired);
+        robot.check_capability(required_capability, clock.unix_timestamp)?;
 
-        emit!(RobotVerified {
+        emit_cpi!(RobotVerified {
+            header: event_header(robot.key(), &mut robot.event_seq, clock.unix_timestamp),
             robot: robot.key(),
             capability: required_capability,
             verified_at: clock.unix_timestamp,
@@ -203,9 +274,53 @@ pub mod identity_registry {
         Ok(())
     }
 
+    /// Check a robot is active and meets a task's requirements: its class, a
+    /// current certification for each capability and a minimum reputation.
+    /// Fails on the first unmet requirement; for task markets via CPI.
+    pub fn verify_requirements(
+        ctx: Context<ReadRobot>,
+        requirements: RobotRequirements,
+    ) -> Result<()> {
+        let robot = &ctx.accounts.robot;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            robot.status == RobotStatus::Available || robot.status == RobotStatus::Busy,
+            ErrorCode::RobotNotActive
+        );
+        require!(robot.robot_class == requirements.robot_class, ErrorCode::WrongRobotClass);
+        for capability in requirements.capabilities {
+            robot.check_capability(capability, now)?;
+        }
+        require!(
+            robot.reputation_score >= requirements.min_reputation,
+            ErrorCode::ReputationTooLow
+        );
+
+        Ok(())
+    }
+
+    /// Number of robots an operator has registered (view function, readable
+    /// via CPI), for fleet-scaled stake requirements
+    pub fn get_fleet_size(ctx: Context<ReadFleet>) -> Result<u32> {
+        Ok(ctx.accounts.fleet.robot_count)
+    }
+
+    /// A robot's operator, reputation and status (view function, readable
+    /// via CPI), for reputation-boosted staking rewards
+    pub fn get_robot_reputation(ctx: Context<ReadRobot>) -> Result<RobotReputation> {
+        let robot = &ctx.accounts.robot;
+        Ok(RobotReputation {
+            operator: robot.operator,
+            reputation_score: robot.reputation_score,
+            status: robot.status,
+        })
+    }
+
     /// Deactivate robot (by operator)
     pub fn deactivate_robot(ctx: Context<UpdateRobotByOperator>) -> Result<()> {
         let robot = &mut ctx.accounts.robot;
+        let clock = Clock::get()?;
         
         require!(
             robot.status != RobotStatus::Busy,
@@ -214,7 +329,8 @@ pub mod identity_registry {
         
         robot.status = RobotStatus::Offline;
 
-        emit!(RobotDeactivated {
+        emit_cpi!(RobotDeactivated {
+            header: event_header(robot.key(), &mut robot.event_seq, clock.unix_timestamp),
             robot: robot.key(),
         });
 
@@ -226,6 +342,7 @@ pub mod identity_registry {
 // ACCOUNTS
 // ============================================================================
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(
@@ -243,6 +360,7 @@ pub struct Initialize<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(device_id: [u8; 32])]
 pub struct RegisterRobot<'info> {
@@ -262,12 +380,34 @@ pub struct RegisterRobot<'info> {
     )]
     pub robot: Account<'info, Robot>,
     
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = 8 + OperatorFleet::INIT_SPACE,
+        seeds = [b"fleet", operator.key().as_ref()],
+        bump
+    )]
+    pub fleet: Account<'info, OperatorFleet>,
+    
     #[account(mut)]
     pub operator: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ReadRobot<'info> {
+    #[account(seeds = [b"robot", robot.device_id.as_ref()], bump = robot.bump)]
+    pub robot: Account<'info, Robot>,
+}
+
+#[derive(Accounts)]
+pub struct ReadFleet<'info> {
+    #[account(seeds = [b"fleet", fleet.operator.as_ref()], bump = fleet.bump)]
+    pub fleet: Account<'info, OperatorFleet>,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 pub struct UpdateRobot<'info> {
     #[account(mut)]
@@ -279,6 +419,7 @@ pub struct UpdateRobot<'info> {
     pub authority: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct UpdateRobotByOperator<'info> {
     #[account(
@@ -290,17 +431,21 @@ pub struct UpdateRobotByOperator<'info> {
     pub operator: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct UpdateRobotByProgram<'info> {
-    #[account(mut)]
+pub struct RobotTask<'info> {
+    #[account(mut, seeds = [b"robot", robot.device_id.as_ref()], bump = robot.bump)]
     pub robot: Account<'info, Robot>,
     
-    /// CHECK: Verified by caller program via CPI
-    pub caller_program: AccountInfo<'info>,
+    /// task-market's market PDA, signing for the CPI
+    #[account(seeds = [b"market"], bump, seeds::program = TASK_MARKET_PROGRAM_ID)]
+    pub market: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct VerifyRobot<'info> {
+    #[account(mut)]
     pub robot: Account<'info, Robot>,
 }
 
@@ -314,6 +459,7 @@ pub struct Registry {
     pub authority: Pubkey,
     pub total_robots: u64,
     pub total_operators: u64,
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -336,6 +482,44 @@ pub struct Robot {
     pub status: RobotStatus,
     #[max_len(10)]
     pub capabilities: Vec<CapabilityProof>,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl Robot {
+    /// Require a certification for `capability` that is still valid at `now`
+    pub fn check_capability(&self, capability: Capability, now: i64) -> Result<()> {
+        let cap = self.capabilities.iter()
+            .find(|c| c.capability == capability)
+            .ok_or(ErrorCode::CapabilityNotFound)?;
+        
+        require!(cap.valid_until > now, ErrorCode::CapabilityExpired);
+        Ok(())
+    }
+}
+
+/// Argument of `verify_requirements`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RobotRequirements {
+    pub robot_class: RobotClass,
+    pub capabilities: Vec<Capability>,
+    pub min_reputation: u16,
+}
+
+/// Return data of `get_robot_reputation`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RobotReputation {
+    pub operator: Pubkey,
+    pub reputation_score: u16,
+    pub status: RobotStatus,
+}
+
+/// Robots registered by an operator
+#[account]
+#[derive(InitSpace)]
+pub struct OperatorFleet {
+    pub operator: Pubkey,
+    pub robot_count: u32,
     pub bump: u8,
 }
 
@@ -388,6 +572,10 @@ pub enum Capability {
 // HELPERS
 // ============================================================================
 
+fn event_header(entity: Pubkey, seq: &mut u64, timestamp: i64) -> EventHeader {
+    EventHeader::next(ProgramTag::IdentityRegistry, entity, seq, timestamp)
+}
+
 fn is_valid_status_transition(from: RobotStatus, to: RobotStatus) -> bool {
     match from {
         RobotStatus::Idle => matches!(to, RobotStatus::Available | RobotStatus::Maintenance | RobotStatus::Offline),
@@ -405,11 +593,13 @@ fn is_valid_status_transition(from: RobotStatus, to: RobotStatus) -> bool {
 
 #[event]
 pub struct RegistryInitialized {
+    pub header: EventHeader,
     pub authority: Pubkey,
 }
 
 #[event]
 pub struct RobotRegistered {
+    pub header: EventHeader,
     pub robot: Pubkey,
     pub device_id: [u8; 32],
     pub operator: Pubkey,
@@ -419,6 +609,7 @@ pub struct RobotRegistered {
 
 #[event]
 pub struct CapabilityAdded {
+    pub header: EventHeader,
     pub robot: Pubkey,
     pub capability: Capability,
     pub level: u8,
@@ -427,6 +618,7 @@ pub struct CapabilityAdded {
 
 #[event]
 pub struct RobotStatusChanged {
+    pub header: EventHeader,
     pub robot: Pubkey,
     pub old_status: RobotStatus,
     pub new_status: RobotStatus,
@@ -435,6 +627,7 @@ pub struct RobotStatusChanged {
 
 #[event]
 pub struct ReputationUpdated {
+    pub header: EventHeader,
     pub robot: Pubkey,
     pub old_score: i32,
     pub new_score: u16,
@@ -443,6 +636,7 @@ pub struct ReputationUpdated {
 
 #[event]
 pub struct RobotVerified {
+    pub header: EventHeader,
     pub robot: Pubkey,
     pub capability: Capability,
     pub verified_at: i64,
@@ -450,6 +644,7 @@ pub struct RobotVerified {
 
 #[event]
 pub struct RobotDeactivated {
+    pub header: EventHeader,
     pub robot: Pubkey,
 }
 
@@ -485,4 +680,13 @@ pub enum ErrorCode {
     
     #[msg("Capability has expired")]
     CapabilityExpired,
+    
+    #[msg("Robot is not of the required class")]
+    WrongRobotClass,
+    
+    #[msg("Robot reputation is below the required minimum")]
+    ReputationTooLow,
+    
+    #[msg("Robot is not available for a task")]
+    RobotNotAvailable,
 }
@@ -1,7 +1,18 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as sysvar_instructions, load_current_index_checked, load_instruction_at_checked,
+};
+use droneos_events::{EventHeader, ProgramTag};
+use droneos_token::program::DroneosToken;
+use droneos_token::{SlashAppeal, VotingPower};
+use identity_registry::Robot;
 
 declare_id!("DOS4orc1111111111111111111111111111111111111");
 
+/// How long disputes stay open for votes
+pub const DISPUTE_VOTING_PERIOD: i64 = 7 * 24 * 60 * 60;
+
 /// $DRONEOS Oracle Verifier Program
 /// 
 /// Decentralized verification system for robot tasks:
@@ -23,9 +34,11 @@ pub mod oracle_verifier {
         verifier.successful_verifications = 0;
         verifier.disputed_verifications = 0;
         verifier.min_confidence_score = 80; // 80% minimum
+        verifier.event_seq = 0;
         verifier.bump = ctx.bumps.verifier;
         
-        emit!(VerifierInitialized {
+        emit_cpi!(VerifierInitialized {
+            header: event_header(verifier.key(), &mut verifier.event_seq, Clock::get()?.unix_timestamp),
             authority: verifier.authority,
         });
         
@@ -51,18 +64,23 @@ pub mod oracle_verifier {
         oracle.successful_verifications = 0;
         oracle.is_active = true;
         oracle.registered_at = Clock::get()?.unix_timestamp;
+        oracle.event_seq = 0;
         oracle.bump = ctx.bumps.oracle;
         
-        emit!(OracleRegistered {
+        emit_cpi!(OracleRegistered {
+            header: event_header(oracle.key(), &mut oracle.event_seq, Clock::get()?.unix_timestamp),
             oracle: oracle.key(),
             provider: oracle.provider,
-            oracle_type,
+            oracle_type: oracle.oracle_type.clone(),
         });
         
         Ok(())
     }
 
-    /// Submit GPS proof for task
+    /// Submit GPS proof for task, signed by the robot's device key (its
+    /// identity-registry device id). The instruction right before this one
+    /// must be an Ed25519 program instruction checking `signature` over
+    /// `gps_proof_message`.
     pub fn submit_gps_proof(
         ctx: Context<SubmitGPSProof>,
         latitude: i64,  // Fixed-point: actual * 1_000_000
@@ -71,6 +89,21 @@ pub mod oracle_verifier {
         timestamp: i64,
         signature: [u8; 64], // Ed25519 signature from robot
     ) -> Result<()> {
+        let message = gps_proof_message(
+            &ctx.accounts.task.key(),
+            &ctx.accounts.robot.key(),
+            latitude,
+            longitude,
+            altitude,
+            timestamp,
+        );
+        verify_ed25519(
+            &ctx.accounts.instructions,
+            &ctx.accounts.robot.device_id,
+            &signature,
+            &message,
+        )?;
+        
         let proof = &mut ctx.accounts.proof;
         proof.task = ctx.accounts.task.key();
         proof.robot = ctx.accounts.robot.key();
@@ -84,9 +117,11 @@ pub mod oracle_verifier {
         proof.confidence_score = 0; // To be set by oracle
         proof.status = ProofStatus::Pending;
         proof.submitted_at = Clock::get()?.unix_timestamp;
+        proof.event_seq = 0;
         proof.bump = ctx.bumps.proof;
         
-        emit!(GPSProofSubmitted {
+        emit_cpi!(GPSProofSubmitted {
+            header: event_header(proof.key(), &mut proof.event_seq, Clock::get()?.unix_timestamp),
             proof: proof.key(),
             task: proof.task,
             robot: proof.robot,
@@ -115,12 +150,16 @@ pub mod oracle_verifier {
         proof.data_hash = Some(data_hash);
         proof.proof_url = Some(proof_url);
         proof.metadata = Some(metadata);
+        // Completion proofs carry no reading, so date them by submission
+        proof.timestamp = Clock::get()?.unix_timestamp;
         proof.confidence_score = 0;
         proof.status = ProofStatus::Pending;
-        proof.submitted_at = Clock::get()?.unix_timestamp;
+        proof.submitted_at = proof.timestamp;
+        proof.event_seq = 0;
         proof.bump = ctx.bumps.proof;
         
-        emit!(CompletionProofSubmitted {
+        emit_cpi!(CompletionProofSubmitted {
+            header: event_header(proof.key(), &mut proof.event_seq, Clock::get()?.unix_timestamp),
             proof: proof.key(),
             task: proof.task,
             robot: proof.robot,
@@ -146,35 +185,39 @@ pub mod oracle_verifier {
         
         require!(proof.status == ProofStatus::Pending, ErrorCode::ProofAlreadyVerified);
         
+        let verified = is_valid && confidence_score >= verifier.min_confidence_score;
+        
         proof.confidence_score = confidence_score;
-        proof.status = if is_valid && confidence_score >= verifier.min_confidence_score {
+        proof.status = if verified {
             ProofStatus::Verified
         } else {
             ProofStatus::Failed
         };
-        proof.verification_data = Some(verification_data);
+        // Skip storing an empty payload so the account isn't re-serialized with a useless String
+        proof.verification_data = if verification_data.is_empty() {
+            None
+        } else {
+            Some(verification_data)
+        };
         proof.verified_at = Some(Clock::get()?.unix_timestamp);
         
         // Update statistics
         verifier.total_verifications += 1;
         oracle.total_verifications += 1;
         
-        if proof.status == ProofStatus::Verified {
+        if verified {
             verifier.successful_verifications += 1;
             oracle.successful_verifications += 1;
             
             // Update oracle reputation
-            if oracle.reputation < 100 {
-                oracle.reputation = std::cmp::min(100, oracle.reputation + 1);
-            }
+            oracle.reputation = (oracle.reputation + 1).min(100);
         } else {
             // Decrease reputation on failure
-            if oracle.reputation > 0 {
-                oracle.reputation = oracle.reputation.saturating_sub(2);
-            }
+            oracle.reputation = oracle.reputation.saturating_sub(2);
         }
         
-        emit!(ProofVerified {
+        emit_cpi!(ProofVerified {
+            header: event_header(proof.key(), &mut proof.event_seq, Clock::get()?.unix_timestamp),
             proof: proof.key(),
             oracle: oracle.key(),
             is_valid,
@@ -210,11 +253,13 @@ pub mod oracle_verifier {
         dispute.votes_for = 0;
         dispute.votes_against = 0;
         dispute.created_at = Clock::get()?.unix_timestamp;
+        dispute.event_seq = 0;
         dispute.bump = ctx.bumps.dispute;
         
         verifier.disputed_verifications += 1;
         
-        emit!(DisputeCreated {
+        emit_cpi!(DisputeCreated {
+            header: event_header(dispute.key(), &mut dispute.event_seq, Clock::get()?.unix_timestamp),
             dispute: dispute.key(),
             proof: dispute.proof,
             challenger: dispute.challenger,
@@ -223,22 +268,30 @@ pub mod oracle_verifier {
         Ok(())
     }
 
-    /// Vote on dispute (requires staked DRONEOS)
+    /// Vote on dispute (requires staked DRONEOS), weighted by the voter's
+    /// veDRONEOS voting power
     pub fn vote_on_dispute(
         ctx: Context<VoteOnDispute>,
         vote_for_challenger: bool,
     ) -> Result<()> {
+        let weight = droneos_token::cpi::get_voting_power(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            droneos_token::cpi::accounts::ReadVotingPower {
+                voting_power: ctx.accounts.voting_power.to_account_info(),
+            },
+        ))?
+        .get();
+        require!(weight > 0, ErrorCode::NoVotingPower);
+
         let dispute = &mut ctx.accounts.dispute;
         let vote = &mut ctx.accounts.vote;
         
         require!(dispute.status == DisputeStatus::Open, ErrorCode::DisputeNotOpen);
         
-        // TODO: Verify voter has staked tokens via CPI
-        
         vote.dispute = dispute.key();
         vote.voter = ctx.accounts.voter.key();
         vote.vote_for_challenger = vote_for_challenger;
-        vote.weight = 100; // Based on stake amount
+        vote.weight = weight;
         vote.voted_at = Clock::get()?.unix_timestamp;
         vote.bump = ctx.bumps.vote;
         
@@ -248,7 +301,8 @@ pub mod oracle_verifier {
             dispute.votes_against += vote.weight;
         }
         
-        emit!(DisputeVoted {
+        emit_cpi!(DisputeVoted {
+            header: event_header(dispute.key(), &mut dispute.event_seq, Clock::get()?.unix_timestamp),
             dispute: dispute.key(),
             voter: vote.voter,
             vote_for_challenger,
@@ -265,11 +319,10 @@ pub mod oracle_verifier {
         
         require!(dispute.status == DisputeStatus::Open, ErrorCode::DisputeNotOpen);
         
-        // Check voting period (e.g., 7 days)
+        // Check voting period
         let current_time = Clock::get()?.unix_timestamp;
-        let voting_period = 7 * 24 * 60 * 60; // 7 days
         require!(
-            current_time >= dispute.created_at + voting_period,
+            current_time >= dispute.created_at + DISPUTE_VOTING_PERIOD,
             ErrorCode::VotingPeriodNotEnded
         );
         
@@ -285,7 +338,8 @@ pub mod oracle_verifier {
             dispute.resolved_at = Some(current_time);
         }
         
-        emit!(DisputeResolved {
+        emit_cpi!(DisputeResolved {
+            header: event_header(dispute.key(), &mut dispute.event_seq, Clock::get()?.unix_timestamp),
             dispute: dispute.key(),
             outcome: dispute.status.clone(),
             votes_for: dispute.votes_for,
@@ -295,6 +349,102 @@ pub mod oracle_verifier {
         Ok(())
     }
 
+    /// Open a dispute over an operator's slash appeal (droneos_token
+    /// `appeal_slash`). The dispute's `proof` is the appeal; votes for the
+    /// challenger are votes to uphold it.
+    pub fn open_slash_appeal(
+        ctx: Context<OpenSlashAppeal>,
+        reason: String,
+        evidence_url: String,
+    ) -> Result<()> {
+        require!(reason.len() <= 256, ErrorCode::ReasonTooLong);
+        require!(evidence_url.len() <= 128, ErrorCode::URLTooLong);
+        require!(
+            ctx.accounts.appeal.status == droneos_token::AppealStatus::Open,
+            ErrorCode::DisputeNotOpen
+        );
+
+        let dispute = &mut ctx.accounts.dispute;
+        let verifier = &mut ctx.accounts.verifier;
+
+        dispute.proof = ctx.accounts.appeal.key();
+        dispute.challenger = ctx.accounts.operator.key();
+        dispute.reason = reason;
+        dispute.evidence_url = evidence_url;
+        dispute.status = DisputeStatus::Open;
+        dispute.votes_for = 0;
+        dispute.votes_against = 0;
+        dispute.created_at = Clock::get()?.unix_timestamp;
+        dispute.event_seq = 0;
+        dispute.bump = ctx.bumps.dispute;
+
+        verifier.disputed_verifications += 1;
+
+        emit_cpi!(DisputeCreated {
+            header: event_header(dispute.key(), &mut dispute.event_seq, Clock::get()?.unix_timestamp),
+            dispute: dispute.key(),
+            proof: dispute.proof,
+            challenger: dispute.challenger,
+        });
+
+        Ok(())
+    }
+
+    /// Resolve a slash appeal dispute and settle the appeal in the token
+    /// program, signed by the verifier PDA
+    pub fn resolve_slash_appeal(ctx: Context<ResolveSlashAppeal>) -> Result<()> {
+        let dispute = &mut ctx.accounts.dispute;
+
+        require!(dispute.status == DisputeStatus::Open, ErrorCode::DisputeNotOpen);
+
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= dispute.created_at + DISPUTE_VOTING_PERIOD,
+            ErrorCode::VotingPeriodNotEnded
+        );
+
+        let upheld = dispute.votes_for > dispute.votes_against;
+        dispute.status = if upheld {
+            DisputeStatus::ChallengerWins
+        } else {
+            DisputeStatus::OracleWins
+        };
+        dispute.resolved_at = Some(current_time);
+
+        let seeds = &[b"verifier".as_ref(), &[ctx.accounts.verifier.bump]];
+        droneos_token::cpi::settle_slash_appeal(
+            CpiContext::new_with_signer(
+                ctx.accounts.droneos_token_program.to_account_info(),
+                droneos_token::cpi::accounts::SettleSlashAppeal {
+                    config: ctx.accounts.token_config.to_account_info(),
+                    operator_stake: ctx.accounts.operator_stake.to_account_info(),
+                    appeal: ctx.accounts.appeal.to_account_info(),
+                    treasury: ctx.accounts.treasury.to_account_info(),
+                    appeal_vault: ctx.accounts.appeal_vault.to_account_info(),
+                    operator_vault: ctx.accounts.operator_vault.to_account_info(),
+                    operator_token: ctx.accounts.operator_token.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    verifier: ctx.accounts.verifier.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    event_authority: ctx.accounts.token_event_authority.to_account_info(),
+                    program: ctx.accounts.droneos_token_program.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            upheld,
+        )?;
+
+        emit_cpi!(DisputeResolved {
+            header: event_header(dispute.key(), &mut dispute.event_seq, current_time),
+            dispute: dispute.key(),
+            outcome: dispute.status.clone(),
+            votes_for: dispute.votes_for,
+            votes_against: dispute.votes_against,
+        });
+
+        Ok(())
+    }
+
     /// Auto-verify task if all required proofs are verified
     pub fn auto_verify_task(ctx: Context<AutoVerifyTask>) -> Result<()> {
         // Check if task has required proofs:
@@ -304,7 +454,10 @@ pub mod oracle_verifier {
         
         // TODO: Implement CPI to task-market to mark task as verified
         
-        emit!(TaskAutoVerified {
+        let verifier = &mut ctx.accounts.verifier;
+        
+        emit_cpi!(TaskAutoVerified {
+            header: event_header(verifier.key(), &mut verifier.event_seq, Clock::get()?.unix_timestamp),
             task: ctx.accounts.task.key(),
             verified_at: Clock::get()?.unix_timestamp,
         });
@@ -313,6 +466,69 @@ pub mod oracle_verifier {
     }
 }
 
+// Helpers
+
+fn event_header(entity: Pubkey, seq: &mut u64, timestamp: i64) -> EventHeader {
+    EventHeader::next(ProgramTag::OracleVerifier, entity, seq, timestamp)
+}
+
+/// Message a robot signs with its device key for a GPS proof: the task and
+/// robot followed by the reading, integers little-endian
+pub fn gps_proof_message(
+    task: &Pubkey,
+    robot: &Pubkey,
+    latitude: i64,
+    longitude: i64,
+    altitude: i32,
+    timestamp: i64,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(92);
+    message.extend_from_slice(task.as_ref());
+    message.extend_from_slice(robot.as_ref());
+    message.extend_from_slice(&latitude.to_le_bytes());
+    message.extend_from_slice(&longitude.to_le_bytes());
+    message.extend_from_slice(&altitude.to_le_bytes());
+    message.extend_from_slice(&timestamp.to_le_bytes());
+    message
+}
+
+/// Require the instruction before the current one to be an Ed25519 program
+/// instruction verifying `signature` by `public_key` over `message`, all
+/// three held in its own data
+fn verify_ed25519(
+    instructions: &AccountInfo,
+    public_key: &[u8; 32],
+    signature: &[u8; 64],
+    message: &[u8],
+) -> Result<()> {
+    let index = load_current_index_checked(instructions)? as usize;
+    require!(index > 0, ErrorCode::MissingSignature);
+    let ix = load_instruction_at_checked(index - 1, instructions)?;
+    require!(ix.program_id == ed25519_program::ID, ErrorCode::MissingSignature);
+
+    // A signature count of 1 and a padding byte, then seven u16 offsets
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidSignature);
+    let offset = |i: usize| u16::from_le_bytes([data[2 + 2 * i], data[3 + 2 * i]]) as usize;
+    // Instruction indices of u16::MAX refer to the Ed25519 instruction itself
+    require!(
+        offset(1) == u16::MAX as usize &&
+        offset(3) == u16::MAX as usize &&
+        offset(6) == u16::MAX as usize,
+        ErrorCode::InvalidSignature
+    );
+
+    let field = |start: usize, len: usize| data.get(start..start + len);
+    require!(
+        field(offset(0), 64) == Some(&signature[..]) &&
+        field(offset(2), 32) == Some(&public_key[..]) &&
+        field(offset(4), offset(5)) == Some(message),
+        ErrorCode::InvalidSignature
+    );
+
+    Ok(())
+}
+
 // Account Structures
 
 #[account]
@@ -322,6 +538,7 @@ pub struct Verifier {
     pub successful_verifications: u64,
     pub disputed_verifications: u64,
     pub min_confidence_score: u8,
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -335,6 +552,7 @@ pub struct Oracle {
     pub successful_verifications: u64,
     pub is_active: bool,
     pub registered_at: i64,
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -362,6 +580,7 @@ pub struct Proof {
     pub verification_data: Option<String>,
     pub submitted_at: i64,
     pub verified_at: Option<i64>,
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -376,6 +595,7 @@ pub struct Dispute {
     pub votes_against: u64,
     pub created_at: i64,
     pub resolved_at: Option<i64>,
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -384,7 +604,7 @@ pub struct DisputeVote {
     pub dispute: Pubkey,
     pub voter: Pubkey,
     pub vote_for_challenger: bool,
-    pub weight: u64, // Based on staked amount
+    pub weight: u64, // veDRONEOS voting power at vote time
     pub voted_at: i64,
     pub bump: u8,
 }
@@ -424,12 +644,13 @@ pub enum DisputeStatus {
 
 // Context Structs (simplified)
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 8 + 8 + 8 + 1 + 1,
+        space = 8 + 32 + 8 + 8 + 8 + 1 + 8 + 1,
         seeds = [b"verifier"],
         bump
     )]
@@ -439,12 +660,13 @@ pub struct Initialize<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct RegisterOracle<'info> {
     #[account(
         init,
         payer = provider,
-        space = 8 + 32 + 1 + 132 + 2 + 8 + 8 + 1 + 8 + 1,
+        space = 8 + 32 + 1 + 132 + 2 + 8 + 8 + 1 + 8 + 8 + 1,
         seeds = [b"oracle", provider.key().as_ref()],
         bump
     )]
@@ -454,26 +676,35 @@ pub struct RegisterOracle<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct SubmitGPSProof<'info> {
     /// CHECK: Task account
     pub task: AccountInfo<'info>,
-    /// CHECK: Robot account
-    pub robot: AccountInfo<'info>,
+    #[account(
+        seeds = [b"robot", robot.device_id.as_ref()],
+        bump = robot.bump,
+        seeds::program = identity_registry::ID
+    )]
+    pub robot: Account<'info, Robot>,
     pub oracle: Account<'info, Oracle>,
     #[account(
         init,
         payer = operator,
-        space = 8 + 32 + 32 + 32 + 1 + 9 + 9 + 5 + 33 + 132 + 260 + 8 + 64 + 1 + 1 + 260 + 8 + 9 + 1,
+        space = 8 + 32 + 32 + 32 + 1 + 9 + 9 + 5 + 33 + 132 + 260 + 8 + 64 + 1 + 1 + 260 + 8 + 9 + 8 + 1,
         seeds = [b"proof", task.key().as_ref(), robot.key().as_ref()],
         bump
     )]
     pub proof: Account<'info, Proof>,
     #[account(mut)]
     pub operator: Signer<'info>,
+    /// CHECK: Instructions sysvar, holding the Ed25519 signature check
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct SubmitCompletionProof<'info> {
     /// CHECK: Task account
@@ -484,7 +715,7 @@ pub struct SubmitCompletionProof<'info> {
     #[account(
         init,
         payer = operator,
-        space = 8 + 32 + 32 + 32 + 1 + 9 + 9 + 5 + 33 + 132 + 260 + 8 + 64 + 1 + 1 + 260 + 8 + 9 + 1,
+        space = 8 + 32 + 32 + 32 + 1 + 9 + 9 + 5 + 33 + 132 + 260 + 8 + 64 + 1 + 1 + 260 + 8 + 9 + 8 + 1,
         seeds = [b"completion-proof", task.key().as_ref()],
         bump
     )]
@@ -494,6 +725,7 @@ pub struct SubmitCompletionProof<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct VerifyProof<'info> {
     #[account(mut)]
@@ -505,6 +737,7 @@ pub struct VerifyProof<'info> {
     pub oracle_authority: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CreateDispute<'info> {
     #[account(mut)]
@@ -513,7 +746,7 @@ pub struct CreateDispute<'info> {
     #[account(
         init,
         payer = challenger,
-        space = 8 + 32 + 32 + 260 + 132 + 1 + 8 + 8 + 8 + 9 + 1,
+        space = 8 + 32 + 32 + 260 + 132 + 1 + 8 + 8 + 8 + 9 + 8 + 1,
         seeds = [b"dispute", proof.key().as_ref(), challenger.key().as_ref()],
         bump
     )]
@@ -523,6 +756,7 @@ pub struct CreateDispute<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct VoteOnDispute<'info> {
     #[account(mut)]
@@ -535,24 +769,95 @@ pub struct VoteOnDispute<'info> {
         bump
     )]
     pub vote: Account<'info, DisputeVote>,
+    #[account(
+        seeds = [b"voting_power", voter.key().as_ref()],
+        bump = voting_power.bump,
+        seeds::program = droneos_token::ID
+    )]
+    pub voting_power: Account<'info, VotingPower>,
     #[account(mut)]
     pub voter: Signer<'info>,
+    pub token_program: Program<'info, DroneosToken>,
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct ResolveDispute<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = dispute.proof == proof.key() @ ErrorCode::DisputeProofMismatch)]
     pub dispute: Account<'info, Dispute>,
     #[account(mut)]
     pub proof: Account<'info, Proof>,
     pub authority: Signer<'info>,
 }
 
+#[event_cpi]
+#[derive(Accounts)]
+pub struct OpenSlashAppeal<'info> {
+    #[account(mut, seeds = [b"verifier"], bump = verifier.bump)]
+    pub verifier: Account<'info, Verifier>,
+    #[account(
+        seeds = [b"appeal", operator.key().as_ref(), &appeal.slashed_at.to_le_bytes()],
+        bump = appeal.bump,
+        seeds::program = droneos_token::ID
+    )]
+    pub appeal: Account<'info, SlashAppeal>,
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + 32 + 32 + 260 + 132 + 1 + 8 + 8 + 8 + 9 + 8 + 1,
+        seeds = [b"dispute", appeal.key().as_ref(), operator.key().as_ref()],
+        bump
+    )]
+    pub dispute: Account<'info, Dispute>,
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveSlashAppeal<'info> {
+    #[account(mut, constraint = dispute.proof == appeal.key() @ ErrorCode::DisputeProofMismatch)]
+    pub dispute: Box<Account<'info, Dispute>>,
+    #[account(seeds = [b"verifier"], bump = verifier.bump)]
+    pub verifier: Box<Account<'info, Verifier>>,
+    /// CHECK: Validated by droneos_token
+    #[account(mut)]
+    pub appeal: AccountInfo<'info>,
+    /// CHECK: Validated by droneos_token
+    #[account(mut)]
+    pub token_config: AccountInfo<'info>,
+    /// CHECK: Validated by droneos_token
+    #[account(mut)]
+    pub operator_stake: AccountInfo<'info>,
+    /// CHECK: Validated by droneos_token
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: droneos_token requires its config's registered appeal vault
+    #[account(mut)]
+    pub appeal_vault: AccountInfo<'info>,
+    /// CHECK: Validated by droneos_token
+    #[account(mut)]
+    pub operator_vault: AccountInfo<'info>,
+    /// CHECK: Validated by droneos_token
+    #[account(mut)]
+    pub operator_token: AccountInfo<'info>,
+    /// CHECK: $DRONEOS mint, validated by droneos_token
+    pub token_mint: AccountInfo<'info>,
+    /// CHECK: droneos_token event authority
+    pub token_event_authority: AccountInfo<'info>,
+    pub droneos_token_program: Program<'info, DroneosToken>,
+    /// CHECK: Token-2022 program, validated by droneos_token
+    pub token_program: AccountInfo<'info>,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 pub struct AutoVerifyTask<'info> {
     /// CHECK: Task account
     pub task: AccountInfo<'info>,
+    #[account(mut)]
     pub verifier: Account<'info, Verifier>,
 }
 
@@ -560,11 +865,13 @@ pub struct AutoVerifyTask<'info> {
 
 #[event]
 pub struct VerifierInitialized {
+    pub header: EventHeader,
     pub authority: Pubkey,
 }
 
 #[event]
 pub struct OracleRegistered {
+    pub header: EventHeader,
     pub oracle: Pubkey,
     pub provider: Pubkey,
     pub oracle_type: OracleType,
@@ -572,6 +879,7 @@ pub struct OracleRegistered {
 
 #[event]
 pub struct GPSProofSubmitted {
+    pub header: EventHeader,
     pub proof: Pubkey,
     pub task: Pubkey,
     pub robot: Pubkey,
@@ -581,6 +889,7 @@ pub struct GPSProofSubmitted {
 
 #[event]
 pub struct CompletionProofSubmitted {
+    pub header: EventHeader,
     pub proof: Pubkey,
     pub task: Pubkey,
     pub robot: Pubkey,
@@ -589,6 +898,7 @@ pub struct CompletionProofSubmitted {
 
 #[event]
 pub struct ProofVerified {
+    pub header: EventHeader,
     pub proof: Pubkey,
     pub oracle: Pubkey,
     pub is_valid: bool,
@@ -597,6 +907,7 @@ pub struct ProofVerified {
 
 #[event]
 pub struct DisputeCreated {
+    pub header: EventHeader,
     pub dispute: Pubkey,
     pub proof: Pubkey,
     pub challenger: Pubkey,
@@ -604,6 +915,7 @@ pub struct DisputeCreated {
 
 #[event]
 pub struct DisputeVoted {
+    pub header: EventHeader,
     pub dispute: Pubkey,
     pub voter: Pubkey,
     pub vote_for_challenger: bool,
@@ -612,6 +924,7 @@ pub struct DisputeVoted {
 
 #[event]
 pub struct DisputeResolved {
+    pub header: EventHeader,
     pub dispute: Pubkey,
     pub outcome: DisputeStatus,
     pub votes_for: u64,
@@ -620,6 +933,7 @@ pub struct DisputeResolved {
 
 #[event]
 pub struct TaskAutoVerified {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub verified_at: i64,
 }
@@ -650,4 +964,12 @@ pub enum ErrorCode {
     DisputeNotOpen,
     #[msg("Voting period not ended")]
     VotingPeriodNotEnded,
+    #[msg("Voter has no veDRONEOS voting power")]
+    NoVotingPower,
+    #[msg("Dispute is not about this proof")]
+    DisputeProofMismatch,
+    #[msg("GPS proof must follow an Ed25519 signature instruction")]
+    MissingSignature,
+    #[msg("Ed25519 instruction does not match the robot's device key and GPS proof")]
+    InvalidSignature,
 }
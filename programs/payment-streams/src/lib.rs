@@ -1,8 +1,35 @@
+#![allow(clippy::too_many_arguments)]
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as sysvar_instructions, load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{
+    self, CloseAccount, Mint, MintTo, SetAuthority, TokenAccount, TokenInterface,
+};
+use droneos_events::{EventHeader, ProgramTag};
+use droneos_token::StakeAccount;
+use identity_registry::Robot;
 
 declare_id!("DOS4pay1111111111111111111111111111111111111");
 
+// Programs that depend on this one. Their ids are declared here because
+// importing them from their crates would be a dependency cycle.
+
+/// task-market, whose task accounts sign for the streams linked to them.
+pub const TASK_MARKET_PROGRAM_ID: Pubkey =
+    pubkey!("DOS4mkt1111111111111111111111111111111111111");
+
+/// Maximum number of segments in a stream's rate schedule
+pub const MAX_RATE_SEGMENTS: usize = 8;
+
+/// Maximum number of milestones on a milestone stream
+pub const MAX_MILESTONES: usize = 8;
+
 /// $DRONEOS Payment Streams Program
 /// 
 /// X402 Protocol Implementation:
@@ -24,452 +51,3632 @@ pub mod payment_streams {
         config.max_stream_duration = 30 * 86400; // 30 days
         config.total_streams = 0;
         config.total_volume = 0;
+        config.cranker_tip = CrankerTip::None;
+        config.max_tick_interval = 86400; // 1 day
+        config.min_tick_interval = 0;
+        config.referral_share_bps = 0;
+        config.frozen = false;
         config.bump = ctx.bumps.config;
         
         Ok(())
     }
 
-    /// Create a new payment stream
-    pub fn create_stream(
-        ctx: Context<CreateStream>,
+    /// Create a new payment stream. `security_deposit` is held in escrow
+    /// alongside the streaming balance but never ticked out: it goes back to
+    /// the payer when the stream ends cleanly and to the payee if the payer
+    /// lets it run dry past its grace window. `activation_fee` is also held
+    /// apart and paid to the payee, once, when the stream starts.
+    ///
+    /// The payer may be a PDA, such as a multisig vault, signing via CPI.
+    /// It also funds rent, so it must be system-owned.
+    pub fn create_stream<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateStream<'info>>,
+        nonce: u64,
         rate_per_second: u64,
         max_duration: i64,
         grace_period: i64,
         auto_terminate: bool,
+        security_deposit: u64,
+        activation_fee: u64,
+        early_termination: Option<EarlyTermination>,
     ) -> Result<()> {
-        let config = &ctx.accounts.config;
-        let stream = &mut ctx.accounts.stream;
         let clock = Clock::get()?;
 
-        // Validate parameters
-        require!(rate_per_second > 0, ErrorCode::InvalidRate);
+        if let Some(early) = early_termination {
+            require!(
+                early.min_runtime > 0 && early.penalty_bps <= 10_000,
+                ErrorCode::InvalidEarlyTermination
+            );
+        }
+
+        let terms = StreamTerms {
+            payer: ctx.accounts.payer.key(),
+            payee: ctx.accounts.payee.key(),
+            rate_per_second,
+            max_duration,
+            grace_period,
+            auto_terminate,
+            task_id: None,
+            fee_discount_bps: staker_discount(ctx.accounts.payer_stake.as_ref()),
+        };
+        let required_escrow = terms.required_escrow(&ctx.accounts.config)?;
+
         require!(
-            max_duration >= config.min_stream_duration as i64 && 
-            max_duration <= config.max_stream_duration as i64,
-            ErrorCode::InvalidDuration
+            ctx.accounts.payer_token.amount >=
+                required_escrow
+                    .checked_add(security_deposit)
+                    .and_then(|total| total.checked_add(activation_fee))
+                    .ok_or(ErrorCode::Overflow)?,
+            ErrorCode::InsufficientFunds
         );
-        require!(grace_period >= 0 && grace_period <= 300, ErrorCode::InvalidGracePeriod);
 
-        // Calculate required escrow
-        let required_escrow = rate_per_second
-            .checked_mul(max_duration as u64)
-            .ok_or(ErrorCode::Overflow)?;
-        
+        // Transfer to escrow, crediting only what arrives after any transfer fee
+        let received = deposit_to_escrow(
+            &ctx.accounts.payer_token,
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.mint,
+            &ctx.accounts.payer,
+            required_escrow,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+        )?;
+        let deposit_received = if security_deposit > 0 {
+            deposit_to_escrow(
+                &ctx.accounts.payer_token,
+                &mut ctx.accounts.escrow,
+                &ctx.accounts.mint,
+                &ctx.accounts.payer,
+                security_deposit,
+                &ctx.accounts.token_program,
+                ctx.remaining_accounts,
+            )?
+        } else {
+            0
+        };
+        let activation_fee_received = if activation_fee > 0 {
+            deposit_to_escrow(
+                &ctx.accounts.payer_token,
+                &mut ctx.accounts.escrow,
+                &ctx.accounts.mint,
+                &ctx.accounts.payer,
+                activation_fee,
+                &ctx.accounts.token_program,
+                ctx.remaining_accounts,
+            )?
+        } else {
+            0
+        };
+
+        ctx.accounts.stream.set_inner(terms.into_stream(
+            received,
+            clock.unix_timestamp,
+            ctx.bumps.escrow,
+            ctx.bumps.stream,
+        ));
+        register_stream(
+            &mut ctx.accounts.payer_registry,
+            ctx.accounts.payer.key(),
+            ctx.bumps.payer_registry,
+            ctx.accounts.stream.key(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        register_stream(
+            &mut ctx.accounts.payee_registry,
+            ctx.accounts.payee.key(),
+            ctx.bumps.payee_registry,
+            ctx.accounts.stream.key(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        ctx.accounts.config.total_streams += 1;
+        let stream = &mut ctx.accounts.stream;
+        stream.security_deposit = deposit_received;
+        stream.activation_fee = activation_fee_received;
+        stream.early_termination = early_termination;
+        stream.nonce = nonce;
+        if let Some(referrer) = ctx.accounts.referrer_account.as_mut() {
+            require!(referrer.referrer != stream.payer, ErrorCode::InvalidReferrer);
+            stream.referrer = Some(referrer.referrer);
+            referrer.streams_referred += 1;
+        }
+
+        emit_cpi!(StreamCreated {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            payer: stream.payer,
+            payee: stream.payee,
+            rate_per_second,
+            escrow_amount: received,
+            timestamp: clock.unix_timestamp,
+            fee_discount_bps: stream.fee_discount_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Create a stream on behalf of a task (called by task_market). The task
+    /// account signs via CPI and becomes the stream's task authority, which
+    /// can drive it through the `*_by_task` instructions. One stream per task.
+    pub fn create_stream_for_task<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateStreamForTask<'info>>,
+        rate_per_second: u64,
+        max_duration: i64,
+        grace_period: i64,
+        auto_terminate: bool,
+        task_creator: Pubkey,
+        task_index: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        let terms = StreamTerms {
+            payer: ctx.accounts.payer.key(),
+            payee: ctx.accounts.payee.key(),
+            rate_per_second,
+            max_duration,
+            grace_period,
+            auto_terminate,
+            task_id: Some(ctx.accounts.task_authority.key()),
+            fee_discount_bps: staker_discount(ctx.accounts.payer_stake.as_ref()),
+        };
+        let required_escrow = terms.required_escrow(&ctx.accounts.config)?;
+
         require!(
             ctx.accounts.payer_token.amount >= required_escrow,
             ErrorCode::InsufficientFunds
         );
 
-        // Transfer to escrow
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.payer_token.to_account_info(),
-                to: ctx.accounts.escrow.to_account_info(),
-                authority: ctx.accounts.payer.to_account_info(),
-            },
-        );
-        token::transfer(transfer_ctx, required_escrow)?;
+        let received = deposit_to_escrow(
+            &ctx.accounts.payer_token,
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.mint,
+            &ctx.accounts.payer,
+            required_escrow,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+        )?;
 
-        // Initialize stream
-        stream.payer = ctx.accounts.payer.key();
-        stream.payee = ctx.accounts.payee.key();
-        stream.rate_per_second = rate_per_second;
-        stream.max_duration = max_duration;
-        stream.grace_period = grace_period;
-        stream.auto_terminate = auto_terminate;
-        stream.status = StreamStatus::Pending;
-        stream.created_at = clock.unix_timestamp;
-        stream.started_at = 0;
-        stream.last_tick_at = 0;
-        stream.total_paid = 0;
-        stream.total_ticks = 0;
-        stream.escrow_balance = required_escrow;
-        stream.task_id = None;
-        stream.escrow_bump = ctx.bumps.escrow;
-        stream.bump = ctx.bumps.stream;
-
-        emit!(StreamCreated {
+        ctx.accounts.stream.set_inner(terms.into_stream(
+            received,
+            clock.unix_timestamp,
+            ctx.bumps.escrow,
+            ctx.bumps.stream,
+        ));
+        register_stream(
+            &mut ctx.accounts.payer_registry,
+            ctx.accounts.payer.key(),
+            ctx.bumps.payer_registry,
+            ctx.accounts.stream.key(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        register_stream(
+            &mut ctx.accounts.payee_registry,
+            ctx.accounts.payee.key(),
+            ctx.bumps.payee_registry,
+            ctx.accounts.stream.key(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        ctx.accounts.config.total_streams += 1;
+        let stream = &mut ctx.accounts.stream;
+        stream.task_creator = task_creator;
+        stream.task_index = task_index;
+        stream.task_bump = ctx.bumps.task_authority;
+        // The operator agreed to these terms when its bid was accepted
+        stream.status = StreamStatus::Accepted;
+
+        emit_cpi!(StreamCreated {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
             stream: stream.key(),
             payer: stream.payer,
             payee: stream.payee,
             rate_per_second,
-            escrow_amount: required_escrow,
+            escrow_amount: received,
             timestamp: clock.unix_timestamp,
+            fee_discount_bps: stream.fee_discount_bps,
         });
 
         Ok(())
     }
 
-    /// Start the payment stream
-    pub fn start_stream(ctx: Context<StartStream>) -> Result<()> {
-        let stream = &mut ctx.accounts.stream;
+    /// Save a reusable set of stream terms for one mint
+    pub fn create_template(
+        ctx: Context<CreateTemplate>,
+        template_id: u64,
+        rate_per_second: u64,
+        max_duration: i64,
+        grace_period: i64,
+        auto_terminate: bool,
+    ) -> Result<()> {
         let clock = Clock::get()?;
 
-        require!(stream.status == StreamStatus::Pending, ErrorCode::StreamNotPending);
+        let template = &mut ctx.accounts.template;
+        template.owner = ctx.accounts.owner.key();
+        template.mint = ctx.accounts.mint.key();
+        template.template_id = template_id;
+        template.rate_per_second = rate_per_second;
+        template.max_duration = max_duration;
+        template.grace_period = grace_period;
+        template.auto_terminate = auto_terminate;
+        template.streams_created = 0;
+        template.event_seq = 0;
+        template.bump = ctx.bumps.template;
 
-        stream.status = StreamStatus::Active;
-        stream.started_at = clock.unix_timestamp;
-        stream.last_tick_at = clock.unix_timestamp;
+        // Reject terms create_stream would reject; rechecked at use in case
+        // config limits change
+        template.terms(template.owner, template.owner, 0).required_escrow(&ctx.accounts.config)?;
 
-        emit!(StreamStarted {
-            stream: stream.key(),
-            started_at: clock.unix_timestamp,
+        emit_cpi!(StreamTemplateCreated {
+            header: event_header(template.key(), &mut template.event_seq, clock.unix_timestamp),
+            template: template.key(),
+            owner: template.owner,
+            mint: template.mint,
+            rate_per_second,
+            max_duration,
         });
 
         Ok(())
     }
 
-    /// Process a payment tick - transfers accumulated payment to payee
-    pub fn tick(ctx: Context<Tick>) -> Result<()> {
-        let stream = &mut ctx.accounts.stream;
+    /// Close a template and reclaim its rent. Streams already created from
+    /// it are unaffected.
+    pub fn close_template(_ctx: Context<CloseTemplate>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Create a stream on a template's terms
+    pub fn create_stream_from_template<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateStreamFromTemplate<'info>>,
+        nonce: u64,
+    ) -> Result<()> {
         let clock = Clock::get()?;
 
-        require!(stream.status == StreamStatus::Active, ErrorCode::StreamNotActive);
+        let template = &mut ctx.accounts.template;
+        let terms = template.terms(
+            ctx.accounts.payer.key(),
+            ctx.accounts.payee.key(),
+            staker_discount(ctx.accounts.payer_stake.as_ref()),
+        );
+        let rate_per_second = terms.rate_per_second;
+        let required_escrow = terms.required_escrow(&ctx.accounts.config)?;
 
-        // Calculate time elapsed and amount due
-        let elapsed = clock.unix_timestamp - stream.last_tick_at;
-        require!(elapsed > 0, ErrorCode::NoTimeElapsed);
+        require!(
+            ctx.accounts.payer_token.amount >= required_escrow,
+            ErrorCode::InsufficientFunds
+        );
 
-        let amount_due = stream.rate_per_second
-            .checked_mul(elapsed as u64)
+        template.streams_created = template.streams_created
+            .checked_add(1)
             .ok_or(ErrorCode::Overflow)?;
 
-        // Check if escrow has enough
-        if amount_due > stream.escrow_balance {
-            if stream.auto_terminate {
-                // Pay remaining balance and terminate
-                let remaining = stream.escrow_balance;
-                if remaining > 0 {
-                    transfer_from_escrow(
-                        &ctx.accounts.escrow,
-                        &ctx.accounts.payee_token,
-                        &stream,
-                        remaining,
-                        &ctx.accounts.token_program,
-                    )?;
-                }
-                
-                stream.total_paid += remaining;
-                stream.escrow_balance = 0;
-                stream.status = StreamStatus::Completed;
-                
-                emit!(StreamTerminated {
-                    stream: stream.key(),
-                    reason: "Escrow depleted".to_string(),
-                    total_paid: stream.total_paid,
-                    timestamp: clock.unix_timestamp,
-                });
-                
-                return Ok(());
-            } else {
-                return Err(ErrorCode::InsufficientEscrow.into());
-            }
-        }
-
-        // Transfer payment
-        transfer_from_escrow(
-            &ctx.accounts.escrow,
-            &ctx.accounts.payee_token,
-            &stream,
-            amount_due,
+        let received = deposit_to_escrow(
+            &ctx.accounts.payer_token,
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.mint,
+            &ctx.accounts.payer,
+            required_escrow,
             &ctx.accounts.token_program,
+            ctx.remaining_accounts,
         )?;
 
-        // Update stream state
-        stream.last_tick_at = clock.unix_timestamp;
-        stream.total_paid += amount_due;
-        stream.total_ticks += 1;
-        stream.escrow_balance -= amount_due;
+        ctx.accounts.stream.set_inner(terms.into_stream(
+            received,
+            clock.unix_timestamp,
+            ctx.bumps.escrow,
+            ctx.bumps.stream,
+        ));
+        register_stream(
+            &mut ctx.accounts.payer_registry,
+            ctx.accounts.payer.key(),
+            ctx.bumps.payer_registry,
+            ctx.accounts.stream.key(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        register_stream(
+            &mut ctx.accounts.payee_registry,
+            ctx.accounts.payee.key(),
+            ctx.bumps.payee_registry,
+            ctx.accounts.stream.key(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        ctx.accounts.config.total_streams += 1;
+        let stream = &mut ctx.accounts.stream;
+        stream.nonce = nonce;
 
-        emit!(StreamTick {
+        emit_cpi!(StreamCreated {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
             stream: stream.key(),
-            tick_number: stream.total_ticks,
-            amount: amount_due,
-            total_paid: stream.total_paid,
-            escrow_remaining: stream.escrow_balance,
+            payer: stream.payer,
+            payee: stream.payee,
+            rate_per_second,
+            escrow_amount: received,
             timestamp: clock.unix_timestamp,
+            fee_discount_bps: stream.fee_discount_bps,
         });
 
         Ok(())
     }
 
-    /// Pause the stream
-    pub fn pause_stream(ctx: Context<ControlStream>) -> Result<()> {
+    /// Accept a pending stream's terms (by payee). A stream can't start
+    /// until its payee has accepted.
+    pub fn accept_stream(ctx: Context<AcceptStream>) -> Result<()> {
         let stream = &mut ctx.accounts.stream;
         let clock = Clock::get()?;
 
-        require!(stream.status == StreamStatus::Active, ErrorCode::StreamNotActive);
+        require!(stream.status == StreamStatus::Pending, ErrorCode::StreamNotPending);
 
-        stream.status = StreamStatus::Paused;
+        stream.status = StreamStatus::Accepted;
 
-        emit!(StreamPaused {
+        emit_cpi!(StreamAccepted {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
             stream: stream.key(),
+            payee: stream.payee,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
-    /// Resume a paused stream
-    pub fn resume_stream(ctx: Context<ControlStream>) -> Result<()> {
+    /// Reject a pending stream (by payee), refunding everything the payer
+    /// put into escrow
+    pub fn reject_stream<'info>(ctx: Context<'_, '_, '_, 'info, RejectStream<'info>>) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
         let stream = &mut ctx.accounts.stream;
         let clock = Clock::get()?;
 
-        require!(stream.status == StreamStatus::Paused, ErrorCode::StreamNotPaused);
+        require!(stream.status == StreamStatus::Pending, ErrorCode::StreamNotPending);
 
-        stream.status = StreamStatus::Active;
-        stream.last_tick_at = clock.unix_timestamp; // Reset tick timer
+        let escrow_bump = stream.escrow_bump;
+        let refunded = refund_unstarted(
+            stream,
+            &EscrowTransfer {
+                escrow: &ctx.accounts.escrow,
+                mint: &ctx.accounts.mint,
+                stream_key,
+                escrow_bump,
+                token_program: &ctx.accounts.token_program,
+                extra_accounts: ctx.remaining_accounts,
+            },
+            &ctx.accounts.payer_token,
+        )?;
+        stream.status = StreamStatus::Cancelled;
 
-        emit!(StreamResumed {
-            stream: stream.key(),
-            timestamp: clock.unix_timestamp,
+        emit_cpi!(StreamRejected {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            refunded,
         });
 
         Ok(())
     }
 
-    /// Terminate the stream and refund remaining escrow
-    pub fn terminate_stream(ctx: Context<TerminateStream>, reason: String) -> Result<()> {
+    /// Start the payment stream, paying out any activation fee
+    pub fn start_stream<'info>(ctx: Context<'_, '_, '_, 'info, StartStream<'info>>) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
         let stream = &mut ctx.accounts.stream;
         let clock = Clock::get()?;
 
-        require!(
-            stream.status == StreamStatus::Active || 
-            stream.status == StreamStatus::Paused ||
-            stream.status == StreamStatus::Pending,
-            ErrorCode::StreamAlreadyTerminated
-        );
+        mark_started(stream, clock.unix_timestamp)?;
 
-        // Process final tick if active
-        if stream.status == StreamStatus::Active && stream.last_tick_at > 0 {
-            let elapsed = clock.unix_timestamp - stream.last_tick_at;
-            let final_payment = stream.rate_per_second
-                .checked_mul(elapsed as u64)
-                .ok_or(ErrorCode::Overflow)?
-                .min(stream.escrow_balance);
-
-            if final_payment > 0 {
-                transfer_from_escrow(
-                    &ctx.accounts.escrow,
-                    &ctx.accounts.payee_token,
-                    &stream,
-                    final_payment,
-                    &ctx.accounts.token_program,
-                )?;
-                stream.total_paid += final_payment;
-                stream.escrow_balance -= final_payment;
+        let activation_fee = stream.activation_fee;
+        if activation_fee > 0 {
+            EscrowTransfer {
+                escrow: &ctx.accounts.escrow,
+                mint: &ctx.accounts.mint,
+                stream_key,
+                escrow_bump: stream.escrow_bump,
+                token_program: &ctx.accounts.token_program,
+                extra_accounts: ctx.remaining_accounts,
             }
-        }
-
-        // Refund remaining escrow to payer
-        let refund = stream.escrow_balance;
-        if refund > 0 {
-            transfer_from_escrow(
-                &ctx.accounts.escrow,
-                &ctx.accounts.payer_token,
-                &stream,
-                refund,
-                &ctx.accounts.token_program,
+            .pay(
+                &ctx.accounts.payee_token,
+                &ctx.accounts.treasury,
+                activation_fee,
+                stream_fee_basis_points(stream, &ctx.accounts.config),
             )?;
-            stream.escrow_balance = 0;
+            ctx.accounts.config.total_volume += activation_fee;
+            stream.total_paid += activation_fee;
+            stream.activation_fee = 0;
         }
 
-        stream.status = StreamStatus::Completed;
+        emit_cpi!(StreamStarted {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            started_at: clock.unix_timestamp,
+            activation_fee,
+        });
 
-        emit!(StreamTerminated {
+        Ok(())
+    }
+
+    /// Start a task's stream (called by task_market, signed by the task)
+    pub fn start_stream_by_task(ctx: Context<StartStreamByTask>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        mark_started(stream, clock.unix_timestamp)?;
+
+        emit_cpi!(StreamStarted {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
             stream: stream.key(),
-            reason,
-            total_paid: stream.total_paid,
-            timestamp: clock.unix_timestamp,
+            started_at: clock.unix_timestamp,
+            activation_fee: 0,
         });
 
         Ok(())
     }
 
-    /// Top up escrow balance
-    pub fn top_up_escrow(ctx: Context<TopUpEscrow>, amount: u64) -> Result<()> {
+    /// Process a payment tick - transfers accumulated payment to payee
+    pub fn tick<'info>(ctx: Context<'_, '_, '_, 'info, Tick<'info>>) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
         let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
 
         require!(
-            stream.status != StreamStatus::Completed && 
-            stream.status != StreamStatus::Cancelled,
-            ErrorCode::StreamAlreadyTerminated
+            stream.referrer.is_none() ||
+                (ctx.accounts.referrer_account.is_some() && ctx.accounts.referrer_token.is_some()),
+            ErrorCode::InvalidReferrer
         );
 
-        // Transfer to escrow
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.payer_token.to_account_info(),
-                to: ctx.accounts.escrow.to_account_info(),
-                authority: ctx.accounts.payer.to_account_info(),
-            },
+        let escrow = EscrowTransfer {
+            escrow: &ctx.accounts.escrow,
+            mint: &ctx.accounts.mint,
+            stream_key,
+            escrow_bump: stream.escrow_bump,
+            token_program: &ctx.accounts.token_program,
+            extra_accounts: ctx.remaining_accounts,
+        };
+        let (outcome, stale) = settle_tick(
+            stream,
+            &escrow,
+            &ctx.accounts.payee_token,
+            &ctx.accounts.treasury,
+            ctx.accounts.cranker.key(),
+            ctx.accounts.cranker_token.as_ref(),
+            ctx.accounts.referrer_token.as_ref(),
+            &mut ctx.accounts.config,
+            clock.unix_timestamp,
+        )?;
+
+        if let Some(stale) = stale {
+            emit_cpi!(StreamStale {
+                header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                stream: stream_key,
+                stale_seconds: stale.seconds,
+                moved_to_arrears: stale.amount,
+                arrears: stream.arrears,
+            });
+        }
+
+        match outcome {
+            TickOutcome::Depleted { deposit_forfeited } => {
+                emit_cpi!(StreamTerminated {
+                    header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                    stream: stream_key,
+                    reason: "Escrow depleted".to_string(),
+                    total_paid: stream.total_paid,
+                    timestamp: clock.unix_timestamp,
+                    deposit_refunded: 0,
+                    deposit_forfeited,
+                    early_termination_fee: 0,
+                });
+            }
+            TickOutcome::GraceStarted { amount_due } => {
+                emit_cpi!(StreamGraceStarted {
+                    header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                    stream: stream_key,
+                    amount_due,
+                    escrow_balance: stream.escrow_balance,
+                    grace_ends_at: stream.grace_started_at + stream.grace_period,
+                });
+            }
+            TickOutcome::Paid { amount, fee, tip, referral } => {
+                if let Some(referrer) = ctx.accounts.referrer_account.as_mut() {
+                    referrer.total_earned += referral;
+                }
+
+                emit_cpi!(StreamTick {
+                    header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                    stream: stream_key,
+                    tick_number: stream.total_ticks,
+                    amount,
+                    total_paid: stream.total_paid,
+                    escrow_remaining: stream.escrow_balance,
+                    timestamp: clock.unix_timestamp,
+                    fee,
+                    tip,
+                    referral,
+                });
+
+                if let Some(remaining_seconds) = low_escrow_runway(stream, clock.unix_timestamp) {
+                    emit_cpi!(LowEscrowWarning {
+                        header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                        stream: stream_key,
+                        escrow_balance: stream.escrow_balance,
+                        remaining_seconds,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bill a metered stream for a usage report signed by the robot's device
+    /// key: `units × rate_per_unit` instead of elapsed time. The preceding
+    /// instruction must be an Ed25519 program instruction over
+    /// `stream || report_number (u64 LE) || units (u64 LE)`, where
+    /// report_number is the count of reports billed so far.
+    pub fn tick_usage<'info>(
+        ctx: Context<'_, '_, '_, 'info, TickUsage<'info>>,
+        units: u64,
+    ) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(stream.status == StreamStatus::Active, ErrorCode::StreamNotActive);
+        let metering = stream.metering.ok_or(ErrorCode::NotMeteredStream)?;
+        require!(
+            stream.referrer.is_none() ||
+                (ctx.accounts.referrer_account.is_some() && ctx.accounts.referrer_token.is_some()),
+            ErrorCode::InvalidReferrer
         );
-        token::transfer(transfer_ctx, amount)?;
+        // payee_token is checked to belong to the current payee, who may
+        // have bought the stream's claim NFT
+        if let Some(tick_authority) = stream.tick_authority {
+            let cranker = ctx.accounts.cranker.key();
+            require!(
+                cranker == tick_authority || cranker == ctx.accounts.payee_token.owner,
+                ErrorCode::UnauthorizedCranker
+            );
+        }
 
-        stream.escrow_balance += amount;
+        let mut message = Vec::with_capacity(48);
+        message.extend_from_slice(stream_key.as_ref());
+        message.extend_from_slice(&metering.reports.to_le_bytes());
+        message.extend_from_slice(&units.to_le_bytes());
+        verify_ed25519(&ctx.accounts.instructions, &metering.device_key, &message)?;
 
-        emit!(EscrowToppedUp {
-            stream: stream.key(),
+        let amount = units
+            .checked_mul(metering.rate_per_unit)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(amount <= stream.escrow_balance, ErrorCode::InsufficientEscrow);
+
+        let escrow = EscrowTransfer {
+            escrow: &ctx.accounts.escrow,
+            mint: &ctx.accounts.mint,
+            stream_key,
+            escrow_bump: stream.escrow_bump,
+            token_program: &ctx.accounts.token_program,
+            extra_accounts: ctx.remaining_accounts,
+        };
+        let config = &mut ctx.accounts.config;
+        let fee_basis_points = stream_fee_basis_points(stream, config);
+        let tip = escrow.tip_cranker(ctx.accounts.cranker_token.as_ref(), amount, config, fee_basis_points)?;
+        let (fee, referral) = escrow.pay_referred(
+            &ctx.accounts.payee_token,
+            &ctx.accounts.treasury,
+            ctx.accounts.referrer_token.as_ref(),
+            amount - tip,
+            fee_basis_points,
+            config.referral_share_bps,
+        )?;
+        config.total_volume += amount;
+        if let Some(referrer) = ctx.accounts.referrer_account.as_mut() {
+            referrer.total_earned += referral;
+        }
+
+        stream.last_tick_at = clock.unix_timestamp;
+        stream.total_paid += amount;
+        stream.total_ticks += 1;
+        stream.escrow_balance -= amount;
+        stream.metering = Some(UsageMetering {
+            reports: metering.reports + 1,
+            ..metering
+        });
+
+        emit_cpi!(UsageReported {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            report_number: metering.reports,
+            units,
             amount,
-            new_balance: stream.escrow_balance,
+        });
+        emit_cpi!(StreamTick {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            tick_number: stream.total_ticks,
+            amount,
+            total_paid: stream.total_paid,
+            escrow_remaining: stream.escrow_balance,
+            timestamp: clock.unix_timestamp,
+            fee,
+            tip,
+            referral,
         });
 
         Ok(())
     }
 
-    /// Cancel a pending stream (before start)
-    pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
-        let stream = &mut ctx.accounts.stream;
+    /// Tick many streams sharing one mint in a single transaction. Remaining
+    /// accounts are `(stream, escrow, payee_token)` triples; a stream that
+    /// can't be ticked is skipped rather than failing the whole batch.
+    /// Transfer-hook mints and referred streams aren't supported here, use
+    /// `tick` instead.
+    pub fn tick_many<'info>(ctx: Context<'_, '_, 'info, 'info, TickMany<'info>>) -> Result<()> {
+        let triples = ctx.remaining_accounts;
+        require!(
+            !triples.is_empty() && triples.chunks_exact(3).remainder().is_empty(),
+            ErrorCode::InvalidTickBatch
+        );
 
-        require!(stream.status == StreamStatus::Pending, ErrorCode::StreamNotPending);
+        let clock = Clock::get()?;
+        let mint_key = ctx.accounts.mint.key();
+        let token_program_key = ctx.accounts.token_program.key();
+        let mut ticked: u32 = 0;
 
-        // Refund full escrow
-        let refund = stream.escrow_balance;
-        if refund > 0 {
-            transfer_from_escrow(
-                &ctx.accounts.escrow,
-                &ctx.accounts.payer_token,
-                &stream,
-                refund,
-                &ctx.accounts.token_program,
-            )?;
-            stream.escrow_balance = 0;
+        for triple in triples.chunks_exact(3) {
+            let Some((mut stream, escrow_account, payee_token)) =
+                load_tick_target(triple, &mint_key, &token_program_key)
+            else {
+                msg!("Skipping {}: accounts don't match", triple[0].key);
+                continue;
+            };
+            let stream_key = stream.key();
+
+            let escrow = EscrowTransfer {
+                escrow: &escrow_account,
+                mint: &ctx.accounts.mint,
+                stream_key,
+                escrow_bump: stream.escrow_bump,
+                token_program: &ctx.accounts.token_program,
+                extra_accounts: &[],
+            };
+            let (outcome, stale) = match settle_tick(
+                &mut stream,
+                &escrow,
+                &payee_token,
+                &ctx.accounts.treasury,
+                ctx.accounts.cranker.key(),
+                ctx.accounts.cranker_token.as_ref(),
+                None,
+                &mut ctx.accounts.config,
+                clock.unix_timestamp,
+            ) {
+                Ok(settled) => settled,
+                Err(err) => {
+                    msg!("Skipping {}: {}", stream_key, err);
+                    continue;
+                }
+            };
+
+            if let Some(stale) = stale {
+                emit_cpi!(StreamStale {
+                    header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                    stream: stream_key,
+                    stale_seconds: stale.seconds,
+                    moved_to_arrears: stale.amount,
+                    arrears: stream.arrears,
+                });
+            }
+
+            match outcome {
+                TickOutcome::Depleted { deposit_forfeited } => {
+                    emit_cpi!(StreamTerminated {
+                        header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                        stream: stream_key,
+                        reason: "Escrow depleted".to_string(),
+                        total_paid: stream.total_paid,
+                        timestamp: clock.unix_timestamp,
+                        deposit_refunded: 0,
+                        deposit_forfeited,
+                        early_termination_fee: 0,
+                    });
+                }
+                TickOutcome::GraceStarted { amount_due } => {
+                    emit_cpi!(StreamGraceStarted {
+                        header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                        stream: stream_key,
+                        amount_due,
+                        escrow_balance: stream.escrow_balance,
+                        grace_ends_at: stream.grace_started_at + stream.grace_period,
+                    });
+                }
+                TickOutcome::Paid { amount, fee, tip, referral } => {
+                    emit_cpi!(StreamTick {
+                        header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                        stream: stream_key,
+                        tick_number: stream.total_ticks,
+                        amount,
+                        total_paid: stream.total_paid,
+                        escrow_remaining: stream.escrow_balance,
+                        timestamp: clock.unix_timestamp,
+                        fee,
+                        tip,
+                        referral,
+                    });
+
+                    if let Some(remaining_seconds) = low_escrow_runway(&stream, clock.unix_timestamp) {
+                        emit_cpi!(LowEscrowWarning {
+                            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+                            stream: stream_key,
+                            escrow_balance: stream.escrow_balance,
+                            remaining_seconds,
+                        });
+                    }
+                }
+            }
+
+            // Remaining accounts aren't persisted by Anchor, write back by hand
+            stream.exit(&crate::ID)?;
+            ticked += 1;
         }
 
-        stream.status = StreamStatus::Cancelled;
+        msg!("Ticked {} of {} streams", ticked, triples.len() / 3);
+
+        Ok(())
+    }
+
+    /// Pause the stream
+    pub fn pause_stream(ctx: Context<ControlStream>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        mark_paused(stream, clock.unix_timestamp)?;
 
-        emit!(StreamCancelled {
+        emit_cpi!(StreamPaused {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
             stream: stream.key(),
-            refunded: refund,
+            timestamp: clock.unix_timestamp,
+            accrued_unpaid: stream.accrued_unpaid,
         });
 
         Ok(())
     }
 
-    /// Link stream to a task (called by task_market program)
-    pub fn link_to_task(ctx: Context<LinkToTask>, task_id: Pubkey) -> Result<()> {
+    /// Pause a task's stream (called by task_market, signed by the task).
+    /// A stream in Grace is paused too, ending its grace window: the task
+    /// has stopped, so nothing more accrues while it is verified.
+    pub fn pause_stream_by_task(ctx: Context<TaskControlStream>) -> Result<()> {
         let stream = &mut ctx.accounts.stream;
-        
-        require!(stream.task_id.is_none(), ErrorCode::StreamAlreadyLinked);
-        
-        stream.task_id = Some(task_id);
+        let clock = Clock::get()?;
+
+        if stream.status == StreamStatus::Grace {
+            stream.status = StreamStatus::Active;
+            stream.grace_started_at = 0;
+        }
+        mark_paused(stream, clock.unix_timestamp)?;
+
+        emit_cpi!(StreamPaused {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            timestamp: clock.unix_timestamp,
+            accrued_unpaid: stream.accrued_unpaid,
+        });
 
         Ok(())
     }
-}
 
-// ============================================================================
-// HELPER FUNCTIONS
-// ============================================================================
+    /// Replace a task stream's staker fee discount (called by task_market,
+    /// signed by the task), for discounts applied at settlement rather than
+    /// at creation
+    pub fn set_fee_discount_by_task(
+        ctx: Context<TaskControlStream>,
+        fee_discount_bps: u16,
+    ) -> Result<()> {
+        require!(fee_discount_bps <= 10_000, ErrorCode::InvalidFeeDiscount);
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
 
-fn transfer_from_escrow<'info>(
-    escrow: &Account<'info, TokenAccount>,
-    to: &Account<'info, TokenAccount>,
-    stream: &Account<'info, PaymentStream>,
-    amount: u64,
-    token_program: &Program<'info, Token>,
-) -> Result<()> {
-    let seeds = &[
-        b"escrow",
-        stream.to_account_info().key.as_ref(),
-        &[stream.escrow_bump],
-    ];
-    let signer = &[&seeds[..]];
+        stream.fee_discount_bps = fee_discount_bps;
 
-    let transfer_ctx = CpiContext::new_with_signer(
-        token_program.to_account_info(),
-        Transfer {
-            from: escrow.to_account_info(),
-            to: to.to_account_info(),
-            authority: escrow.to_account_info(),
-        },
-        signer,
+        emit_cpi!(StreamFeeDiscountSet {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            fee_discount_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Resume a paused stream
+    pub fn resume_stream(ctx: Context<ControlStream>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(stream.status == StreamStatus::Paused, ErrorCode::StreamNotPaused);
+
+        stream.status = StreamStatus::Active;
+        stream.last_tick_at = clock.unix_timestamp; // Reset tick timer
+
+        emit_cpi!(StreamResumed {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Terminate the stream and refund remaining escrow
+    pub fn terminate_stream<'info>(
+        ctx: Context<'_, '_, '_, 'info, TerminateStream<'info>>,
+        reason: String,
+    ) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        let escrow = EscrowTransfer {
+            escrow: &ctx.accounts.escrow,
+            mint: &ctx.accounts.mint,
+            stream_key,
+            escrow_bump: stream.escrow_bump,
+            token_program: &ctx.accounts.token_program,
+            extra_accounts: ctx.remaining_accounts,
+        };
+        let settlement = settle_termination(
+            stream,
+            &escrow,
+            &ctx.accounts.payer_token,
+            &ctx.accounts.payee_token,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.config,
+            ctx.accounts.authority.key(),
+            0,
+            clock.unix_timestamp,
+        )?;
+
+        emit_cpi!(StreamTerminated {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            reason,
+            total_paid: stream.total_paid,
+            timestamp: clock.unix_timestamp,
+            deposit_refunded: settlement.deposit_refunded,
+            deposit_forfeited: settlement.deposit_forfeited,
+            early_termination_fee: settlement.early_termination_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Terminate a task's stream (called by task_market, signed by the task)
+    pub fn terminate_stream_by_task<'info>(
+        ctx: Context<'_, '_, '_, 'info, TerminateStreamByTask<'info>>,
+        reason: String,
+    ) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        let escrow = EscrowTransfer {
+            escrow: &ctx.accounts.escrow,
+            mint: &ctx.accounts.mint,
+            stream_key,
+            escrow_bump: stream.escrow_bump,
+            token_program: &ctx.accounts.token_program,
+            extra_accounts: ctx.remaining_accounts,
+        };
+        let settlement = settle_termination(
+            stream,
+            &escrow,
+            &ctx.accounts.payer_token,
+            &ctx.accounts.payee_token,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.config,
+            ctx.accounts.task_authority.key(),
+            0,
+            clock.unix_timestamp,
+        )?;
+
+        emit_cpi!(StreamTerminated {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            reason,
+            total_paid: stream.total_paid,
+            timestamp: clock.unix_timestamp,
+            deposit_refunded: settlement.deposit_refunded,
+            deposit_forfeited: settlement.deposit_forfeited,
+            early_termination_fee: settlement.early_termination_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a task's stream (called by task_market, signed by the task).
+    /// Settles like `terminate_stream_by_task`, but the payee is also paid
+    /// `cancellation_fee_bps` of the escrow left once accrual is paid.
+    pub fn cancel_stream_by_task<'info>(
+        ctx: Context<'_, '_, '_, 'info, TerminateStreamByTask<'info>>,
+        reason: String,
+        cancellation_fee_bps: u16,
+    ) -> Result<()> {
+        require!(cancellation_fee_bps <= 10_000, ErrorCode::InvalidCancellationFee);
+        let stream_key = ctx.accounts.stream.key();
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        let escrow = EscrowTransfer {
+            escrow: &ctx.accounts.escrow,
+            mint: &ctx.accounts.mint,
+            stream_key,
+            escrow_bump: stream.escrow_bump,
+            token_program: &ctx.accounts.token_program,
+            extra_accounts: ctx.remaining_accounts,
+        };
+        let settlement = settle_termination(
+            stream,
+            &escrow,
+            &ctx.accounts.payer_token,
+            &ctx.accounts.payee_token,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.config,
+            ctx.accounts.task_authority.key(),
+            cancellation_fee_bps,
+            clock.unix_timestamp,
+        )?;
+
+        emit_cpi!(StreamTerminated {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            reason,
+            total_paid: stream.total_paid,
+            timestamp: clock.unix_timestamp,
+            deposit_refunded: settlement.deposit_refunded,
+            deposit_forfeited: settlement.deposit_forfeited,
+            early_termination_fee: settlement.early_termination_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Top up escrow balance
+    pub fn top_up_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, TopUpEscrow<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status != StreamStatus::Completed && 
+            stream.status != StreamStatus::Cancelled,
+            ErrorCode::StreamAlreadyTerminated
+        );
+
+        // Transfer to escrow, crediting only what arrives after any transfer fee
+        let received = deposit_to_escrow(
+            &ctx.accounts.payer_token,
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.mint,
+            &ctx.accounts.payer,
+            amount,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+        )?;
+
+        stream.escrow_balance += received;
+
+        emit_cpi!(EscrowToppedUp {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            amount: received,
+            new_balance: stream.escrow_balance,
+        });
+
+        if rescue_from_grace(stream, clock.unix_timestamp)? {
+            emit_cpi!(StreamRescued {
+                header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+                stream: stream.key(),
+                escrow_balance: stream.escrow_balance,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Extend the stream by `additional_seconds`, depositing the escrow to
+    /// cover them at the final rate in the same instruction
+    pub fn extend_stream<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExtendStream<'info>>,
+        additional_seconds: i64,
+    ) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status != StreamStatus::Completed && 
+            stream.status != StreamStatus::Cancelled,
+            ErrorCode::StreamAlreadyTerminated
+        );
+        require!(stream.mode == StreamMode::Continuous, ErrorCode::NotContinuousStream);
+        require!(additional_seconds > 0, ErrorCode::InvalidDuration);
+
+        let max_duration = stream.max_duration
+            .checked_add(additional_seconds)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            max_duration <= ctx.accounts.config.max_stream_duration as i64,
+            ErrorCode::InvalidDuration
+        );
+
+        let final_rate = stream
+            .rate_schedule
+            .last()
+            .map_or(stream.rate_per_second, |segment| segment.rate_per_second);
+        let required = final_rate
+            .checked_mul(additional_seconds as u64)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let received = deposit_to_escrow(
+            &ctx.accounts.payer_token,
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.mint,
+            &ctx.accounts.payer,
+            required,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+        )?;
+
+        stream.max_duration = max_duration;
+        stream.escrow_balance += received;
+
+        emit_cpi!(StreamExtended {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            max_duration,
+            amount: received,
+            new_balance: stream.escrow_balance,
+        });
+
+        if rescue_from_grace(stream, clock.unix_timestamp)? {
+            emit_cpi!(StreamRescued {
+                header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+                stream: stream.key(),
+                escrow_balance: stream.escrow_balance,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Top up someone else's stream. Contributions are tracked per sponsor
+    /// but otherwise behave like the payer's own escrow, including being
+    /// refunded to the payer on termination.
+    pub fn sponsor_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, SponsorEscrow<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status != StreamStatus::Completed && 
+            stream.status != StreamStatus::Cancelled,
+            ErrorCode::StreamAlreadyTerminated
+        );
+
+        let received = deposit_to_escrow(
+            &ctx.accounts.sponsor_token,
+            &mut ctx.accounts.escrow,
+            &ctx.accounts.mint,
+            &ctx.accounts.sponsor,
+            amount,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+        )?;
+
+        stream.escrow_balance += received;
+
+        let sponsorship = &mut ctx.accounts.sponsorship;
+        sponsorship.stream = stream.key();
+        sponsorship.sponsor = ctx.accounts.sponsor.key();
+        sponsorship.total_contributed = sponsorship
+            .total_contributed
+            .checked_add(received)
+            .ok_or(ErrorCode::Overflow)?;
+        sponsorship.last_contributed_at = clock.unix_timestamp;
+        sponsorship.bump = ctx.bumps.sponsorship;
+
+        emit_cpi!(EscrowSponsored {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            sponsor: sponsorship.sponsor,
+            amount: received,
+            total_contributed: sponsorship.total_contributed,
+            new_balance: stream.escrow_balance,
+        });
+
+        if rescue_from_grace(stream, clock.unix_timestamp)? {
+            emit_cpi!(StreamRescued {
+                header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+                stream: stream.key(),
+                escrow_balance: stream.escrow_balance,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Cancel a pending stream (before start)
+    pub fn cancel_stream<'info>(ctx: Context<'_, '_, '_, 'info, CancelStream<'info>>) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status == StreamStatus::Pending || stream.status == StreamStatus::Accepted,
+            ErrorCode::StreamNotPending
+        );
+
+        let escrow_bump = stream.escrow_bump;
+        let refund = refund_unstarted(
+            stream,
+            &EscrowTransfer {
+                escrow: &ctx.accounts.escrow,
+                mint: &ctx.accounts.mint,
+                stream_key,
+                escrow_bump,
+                token_program: &ctx.accounts.token_program,
+                extra_accounts: ctx.remaining_accounts,
+            },
+            &ctx.accounts.payer_token,
+        )?;
+        stream.status = StreamStatus::Cancelled;
+
+        emit_cpi!(StreamCancelled {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            refunded: refund,
+        });
+
+        Ok(())
+    }
+
+    /// Mint a claim NFT for a pending stream to the payee. From then on ticks,
+    /// terminations and milestone releases pay whoever holds it instead of
+    /// `stream.payee`, so the right to the stream's income can be sold or
+    /// moved by transferring the NFT. Meant to be sent in the same
+    /// transaction as `create_stream`. Not available for task streams.
+    pub fn mint_claim_nft(ctx: Context<MintClaimNft>) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
+        let clock = Clock::get()?;
+
+        require!(
+            ctx.accounts.stream.status == StreamStatus::Pending,
+            ErrorCode::StreamNotPending
+        );
+        require!(ctx.accounts.stream.task_id.is_none(), ErrorCode::StreamAlreadyLinked);
+
+        let seeds = &[
+            b"claim_mint",
+            stream_key.as_ref(),
+            &[ctx.bumps.claim_mint],
+        ];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.claim_mint.to_account_info(),
+                    to: ctx.accounts.payee_claim_token.to_account_info(),
+                    authority: ctx.accounts.claim_mint.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            1,
+        )?;
+        // Fix the supply at one
+        token_interface::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: ctx.accounts.claim_mint.to_account_info(),
+                    account_or_mint: ctx.accounts.claim_mint.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            spl_token_2022::instruction::AuthorityType::MintTokens,
+            None,
+        )?;
+
+        let stream = &mut ctx.accounts.stream;
+        stream.claim_mint = Some(ctx.accounts.claim_mint.key());
+
+        emit_cpi!(ClaimNftMinted {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            claim_mint: ctx.accounts.claim_mint.key(),
+            holder: stream.payee,
+        });
+
+        Ok(())
+    }
+
+    /// Reassign the stream to a new payee without touching escrow. Needs the
+    /// payer plus consent from the current payee or, for a linked stream, the
+    /// task account signing via task_market CPI. Anything accrued since the
+    /// last tick goes to the new payee, so tick first to settle the old one.
+    pub fn transfer_payee(ctx: Context<TransferPayee>, new_payee: Pubkey) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status != StreamStatus::Completed &&
+            stream.status != StreamStatus::Cancelled,
+            ErrorCode::StreamAlreadyTerminated
+        );
+        // The NFT holder is the payee; move the NFT instead
+        require!(stream.claim_mint.is_none(), ErrorCode::ClaimNftIssued);
+
+        let old_payee = stream.payee;
+        stream.payee = new_payee;
+
+        emit_cpi!(PayeeTransferred {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            old_payee,
+            new_payee,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close a finished stream and its escrow, returning rent to the payer
+    pub fn close_stream(ctx: Context<CloseStream>) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        close_escrow(
+            stream,
+            stream_key,
+            &ctx.accounts.escrow,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        emit_cpi!(StreamClosed {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close a task's finished stream and its escrow, returning rent to the
+    /// payer (called by task_market, signed by the task), so a reopened task
+    /// can open its next stream at the same address
+    pub fn close_stream_by_task(ctx: Context<CloseStreamByTask>) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        close_escrow(
+            stream,
+            stream_key,
+            &ctx.accounts.escrow,
+            &ctx.accounts.payer,
+            &ctx.accounts.token_program,
+        )?;
+
+        emit_cpi!(StreamClosed {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Propose a new rate (by payer or payee). Replaces any earlier proposal.
+    pub fn propose_rate_change(ctx: Context<RenegotiateRate>, rate_per_second: u64) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(rate_per_second > 0, ErrorCode::InvalidRate);
+        require!(stream.mode == StreamMode::Continuous, ErrorCode::NotContinuousStream);
+        require!(
+            stream.status != StreamStatus::Completed &&
+            stream.status != StreamStatus::Cancelled,
+            ErrorCode::StreamAlreadyTerminated
+        );
+
+        let proposed_by = ctx.accounts.authority.key();
+        stream.pending_rate = Some(RateProposal {
+            rate_per_second,
+            proposed_by,
+            proposed_at: clock.unix_timestamp,
+        });
+
+        emit_cpi!(RateChangeProposed {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            proposed_by,
+            rate_per_second,
+        });
+
+        Ok(())
+    }
+
+    /// Accept the other party's proposed rate. It applies from now on: what
+    /// accrued at the old rate is kept in accrued_unpaid for the next tick,
+    /// and the new rate supersedes any remaining rate schedule.
+    pub fn accept_rate_change(ctx: Context<RenegotiateRate>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status != StreamStatus::Completed &&
+            stream.status != StreamStatus::Cancelled,
+            ErrorCode::StreamAlreadyTerminated
+        );
+        let proposal = stream.pending_rate.ok_or(ErrorCode::NoRateProposal)?;
+
+        // Only the proposer's counterparty can accept; a proposal from a
+        // since-replaced payee or claim holder is stale
+        let payee = stream
+            .current_payee(ctx.accounts.claim_token.as_deref())
+            .ok_or(ErrorCode::NotClaimHolder)?;
+        let counterparty = if proposal.proposed_by == stream.payer {
+            payee
+        } else if proposal.proposed_by == payee {
+            stream.payer
+        } else {
+            return Err(ErrorCode::NoRateProposal.into());
+        };
+        require!(
+            ctx.accounts.authority.key() == counterparty,
+            ErrorCode::Unauthorized
+        );
+
+        // Settle accrual at the old rate up to now
+        if stream.last_tick_at > 0 {
+            stream.accrued_unpaid = amount_owed(stream, clock.unix_timestamp)?;
+            stream.last_tick_at = clock.unix_timestamp;
+        }
+
+        let old_rate = stream.rate_per_second;
+        stream.rate_per_second = proposal.rate_per_second;
+        stream.rate_schedule.clear();
+        stream.pending_rate = None;
+
+        emit_cpi!(RateChangeAccepted {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            old_rate,
+            new_rate: stream.rate_per_second,
+            effective_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Set the runway, in seconds, below which ticks emit LowEscrowWarning.
+    /// Zero disables the warning.
+    pub fn set_low_balance_threshold(ctx: Context<ControlStream>, seconds: u32) -> Result<()> {
+        ctx.accounts.stream.low_balance_threshold_seconds = seconds;
+
+        Ok(())
+    }
+
+    /// Restrict who may tick the stream to `tick_authority` and the payee,
+    /// or pass `None` to make ticking permissionless again
+    pub fn set_tick_authority(ctx: Context<ControlStream>, tick_authority: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.stream.tick_authority = tick_authority;
+
+        Ok(())
+    }
+
+    /// Set a cliff (before start): the stream accrues from start as usual,
+    /// but ticks pay nothing until `cliff_seconds` have passed
+    pub fn set_cliff(ctx: Context<ControlStream>, cliff_seconds: i64) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+
+        require!(stream.status == StreamStatus::Pending, ErrorCode::StreamNotPending);
+        require!(
+            cliff_seconds >= 0 && cliff_seconds <= stream.max_duration,
+            ErrorCode::InvalidDuration
+        );
+
+        stream.cliff_seconds = cliff_seconds;
+
+        Ok(())
+    }
+
+    /// Override the program's minimum tick interval for this stream (before
+    /// start), or pass `None` to use the program default
+    pub fn set_stream_min_tick_interval(ctx: Context<ControlStream>, seconds: Option<i64>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+
+        require!(stream.status == StreamStatus::Pending, ErrorCode::StreamNotPending);
+        require!(!matches!(seconds, Some(s) if s < 0), ErrorCode::InvalidDuration);
+
+        stream.min_tick_interval = seconds;
+
+        Ok(())
+    }
+
+    /// Set a piecewise rate schedule (before start). Each segment's rate
+    /// applies from `started_at + start_offset` until the next segment;
+    /// `rate_per_second` applies before the first one.
+    pub fn set_rate_schedule(ctx: Context<SetRateSchedule>, segments: Vec<RateSegment>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(stream.status == StreamStatus::Pending, ErrorCode::StreamNotPending);
+        require!(segments.len() <= MAX_RATE_SEGMENTS, ErrorCode::TooManyRateSegments);
+        require!(
+            segments.iter().all(|s| s.start_offset >= 0 && s.rate_per_second > 0),
+            ErrorCode::InvalidRateSchedule
+        );
+        require!(
+            segments.windows(2).all(|w| w[0].start_offset < w[1].start_offset),
+            ErrorCode::InvalidRateSchedule
+        );
+
+        stream.rate_schedule = segments;
+
+        emit_cpi!(RateScheduleSet {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            segments: stream.rate_schedule.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Bill the stream by signed usage reports from `robot` (before start).
+    /// `tick_usage` then pays `rate_per_unit` per reported unit and the
+    /// per-second `tick` no longer applies; the stream's rate_per_second
+    /// only sizes its escrow. The robot must be operated by the payee.
+    pub fn set_usage_metering(ctx: Context<SetUsageMetering>, rate_per_unit: u64) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+
+        require!(stream.status == StreamStatus::Pending, ErrorCode::StreamNotPending);
+        require!(stream.mode != StreamMode::Milestone, ErrorCode::NotContinuousStream);
+        require!(stream.rate_schedule.is_empty(), ErrorCode::InvalidRateSchedule);
+        require!(rate_per_unit > 0, ErrorCode::InvalidRate);
+
+        stream.mode = StreamMode::Metered;
+        stream.metering = Some(UsageMetering {
+            robot: ctx.accounts.robot.key(),
+            device_key: Pubkey::new_from_array(ctx.accounts.robot.device_id),
+            rate_per_unit,
+            reports: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Add a milestone (before start). The first milestone switches the
+    /// stream to milestone mode: escrow is only released per approved
+    /// milestone, never per second.
+    pub fn add_milestone(ctx: Context<ManageMilestone>, amount: u64, metadata_hash: [u8; 32]) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(stream.status == StreamStatus::Pending, ErrorCode::StreamNotPending);
+        require!(stream.mode != StreamMode::Metered, ErrorCode::NotContinuousStream);
+        require!(amount > 0, ErrorCode::InvalidMilestoneAmount);
+        require!(stream.milestones.len() < MAX_MILESTONES, ErrorCode::TooManyMilestones);
+
+        let committed = stream
+            .milestones
+            .iter()
+            .try_fold(amount, |sum, m| sum.checked_add(m.amount))
+            .ok_or(ErrorCode::Overflow)?;
+        require!(committed <= stream.escrow_balance, ErrorCode::InsufficientEscrow);
+
+        stream.mode = StreamMode::Milestone;
+        stream.milestones.push(Milestone {
+            amount,
+            metadata_hash,
+            status: MilestoneStatus::Pending,
+        });
+
+        emit_cpi!(MilestoneAdded {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            index: (stream.milestones.len() - 1) as u8,
+            amount,
+            metadata_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Approve a completed milestone (by payer)
+    pub fn approve_milestone(ctx: Context<ManageMilestone>, index: u8) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status == StreamStatus::Active || stream.status == StreamStatus::Paused,
+            ErrorCode::StreamNotActive
+        );
+        let milestone = stream
+            .milestones
+            .get_mut(index as usize)
+            .ok_or(ErrorCode::InvalidMilestone)?;
+        require!(milestone.status == MilestoneStatus::Pending, ErrorCode::MilestoneNotPending);
+
+        milestone.status = MilestoneStatus::Approved;
+
+        emit_cpi!(MilestoneApproved {
+            header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream.key(),
+            index,
+        });
+
+        Ok(())
+    }
+
+    /// Pay an approved milestone out to the payee (callable by anyone)
+    pub fn release_milestone<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReleaseMilestone<'info>>,
+        index: u8,
+    ) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        let milestone = stream
+            .milestones
+            .get(index as usize)
+            .ok_or(ErrorCode::InvalidMilestone)?;
+        require!(milestone.status == MilestoneStatus::Approved, ErrorCode::MilestoneNotApproved);
+
+        let escrow = EscrowTransfer {
+            escrow: &ctx.accounts.escrow,
+            mint: &ctx.accounts.mint,
+            stream_key,
+            escrow_bump: stream.escrow_bump,
+            token_program: &ctx.accounts.token_program,
+            extra_accounts: ctx.remaining_accounts,
+        };
+        let (amount, fee) = pay_milestone(
+            stream,
+            index,
+            &escrow,
+            &ctx.accounts.payee_token,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.config,
+        )?;
+
+        emit_cpi!(MilestoneReleased {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            index,
+            amount,
+            fee,
+            escrow_remaining: stream.escrow_balance,
+        });
+
+        Ok(())
+    }
+
+    /// Split a task's stream into milestones before it starts (called by
+    /// task_market, signed by the task). Escrow is divided by `shares_bps`,
+    /// which must total 100%, the last milestone taking any rounding dust.
+    pub fn set_milestones_by_task(ctx: Context<TaskControlStream>, shares_bps: Vec<u16>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status == StreamStatus::Pending || stream.status == StreamStatus::Accepted,
+            ErrorCode::StreamNotPending
+        );
+        require!(stream.mode == StreamMode::Continuous, ErrorCode::NotContinuousStream);
+        require!(
+            !shares_bps.is_empty() && shares_bps.len() <= MAX_MILESTONES,
+            ErrorCode::TooManyMilestones
+        );
+        require!(
+            shares_bps.iter().all(|&share| share > 0) &&
+                shares_bps.iter().map(|&share| share as u32).sum::<u32>() == 10_000,
+            ErrorCode::InvalidMilestoneShares
+        );
+
+        let escrow = stream.escrow_balance;
+        let mut remaining = escrow;
+        stream.mode = StreamMode::Milestone;
+        for (i, &share) in shares_bps.iter().enumerate() {
+            let amount = if i + 1 == shares_bps.len() {
+                remaining
+            } else {
+                (escrow as u128 * share as u128 / 10_000) as u64
+            };
+            remaining -= amount;
+            stream.milestones.push(Milestone {
+                amount,
+                metadata_hash: [0; 32],
+                status: MilestoneStatus::Pending,
+            });
+
+            emit_cpi!(MilestoneAdded {
+                header: event_header(stream.key(), &mut stream.event_seq, clock.unix_timestamp),
+                stream: stream.key(),
+                index: i as u8,
+                amount,
+                metadata_hash: [0; 32],
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Approve and pay out a task stream's milestone in one step (called by
+    /// task_market, signed by the task)
+    pub fn release_milestone_by_task<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReleaseMilestoneByTask<'info>>,
+        index: u8,
+    ) -> Result<()> {
+        let stream_key = ctx.accounts.stream.key();
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status == StreamStatus::Active || stream.status == StreamStatus::Paused,
+            ErrorCode::StreamNotActive
+        );
+        let milestone = stream
+            .milestones
+            .get(index as usize)
+            .ok_or(ErrorCode::InvalidMilestone)?;
+        require!(milestone.status != MilestoneStatus::Released, ErrorCode::MilestoneNotPending);
+
+        let escrow = EscrowTransfer {
+            escrow: &ctx.accounts.escrow,
+            mint: &ctx.accounts.mint,
+            stream_key,
+            escrow_bump: stream.escrow_bump,
+            token_program: &ctx.accounts.token_program,
+            extra_accounts: ctx.remaining_accounts,
+        };
+        let (amount, fee) = pay_milestone(
+            stream,
+            index,
+            &escrow,
+            &ctx.accounts.payee_token,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.config,
+        )?;
+
+        emit_cpi!(MilestoneReleased {
+            header: event_header(stream_key, &mut stream.event_seq, clock.unix_timestamp),
+            stream: stream_key,
+            index,
+            amount,
+            fee,
+            escrow_remaining: stream.escrow_balance,
+        });
+
+        Ok(())
+    }
+
+    /// Create the fee treasury for a mint (owned by the config PDA)
+    pub fn initialize_treasury(_ctx: Context<InitializeTreasury>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Withdraw collected fees from a treasury (by program authority)
+    pub fn withdraw_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawFees<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount <= ctx.accounts.treasury.amount, ErrorCode::InsufficientFunds);
+
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+
+        transfer_checked(
+            &ctx.accounts.token_program,
+            &ctx.accounts.treasury,
+            &ctx.accounts.mint,
+            &ctx.accounts.destination,
+            ctx.accounts.config.to_account_info(),
+            ctx.remaining_accounts,
+            amount,
+            &[&seeds[..]],
+        )
+    }
+
+    /// Set the tip paid to whoever submits a tick (by program authority)
+    pub fn set_cranker_tip(ctx: Context<UpdateConfig>, cranker_tip: CrankerTip) -> Result<()> {
+        if let CrankerTip::BasisPoints(bps) = cranker_tip {
+            require!(bps <= 10_000, ErrorCode::InvalidCrankerTip);
+        }
+
+        ctx.accounts.config.cranker_tip = cranker_tip;
+
+        Ok(())
+    }
+
+    /// Set the share of the platform fee paid to a stream's referrer (by
+    /// program authority)
+    pub fn set_referral_share(ctx: Context<UpdateConfig>, share_bps: u16) -> Result<()> {
+        require!(share_bps <= 10_000, ErrorCode::InvalidReferralShare);
+
+        ctx.accounts.config.referral_share_bps = share_bps;
+
+        Ok(())
+    }
+
+    /// Register as a referrer. Streams created with this account earn it a
+    /// share of their platform fees on every tick.
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let referrer = &mut ctx.accounts.referrer_account;
+        referrer.referrer = ctx.accounts.referrer.key();
+        referrer.streams_referred = 0;
+        referrer.total_earned = 0;
+        referrer.bump = ctx.bumps.referrer_account;
+
+        Ok(())
+    }
+
+    /// Set the longest gap a tick pays for in full (admin only); 0 disables
+    /// the limit
+    pub fn set_max_tick_interval(ctx: Context<UpdateConfig>, seconds: i64) -> Result<()> {
+        require!(seconds >= 0, ErrorCode::InvalidDuration);
+
+        ctx.accounts.config.max_tick_interval = seconds;
+
+        Ok(())
+    }
+
+    /// Set the shortest gap allowed between ticks of a stream (admin only);
+    /// 0 allows ticking every slot
+    pub fn set_min_tick_interval(ctx: Context<UpdateConfig>, seconds: i64) -> Result<()> {
+        require!(seconds >= 0, ErrorCode::InvalidDuration);
+
+        ctx.accounts.config.min_tick_interval = seconds;
+
+        Ok(())
+    }
+
+    /// Freeze the program in an emergency (admin only). Creating, starting
+    /// and ticking streams are refused until unfrozen; terminating, cancelling
+    /// and withdrawing escrow still work so funds are never stranded.
+    pub fn freeze_program(ctx: Context<UpdateConfig>) -> Result<()> {
+        require!(!ctx.accounts.config.frozen, ErrorCode::ProgramFrozen);
+
+        ctx.accounts.config.frozen = true;
+
+        Ok(())
+    }
+
+    /// Lift an emergency freeze (admin only)
+    pub fn unfreeze_program(ctx: Context<UpdateConfig>) -> Result<()> {
+        require!(ctx.accounts.config.frozen, ErrorCode::ProgramNotFrozen);
+
+        ctx.accounts.config.frozen = false;
+
+        Ok(())
+    }
+
+    /// Link an existing stream to a task, making the task its task
+    /// authority. Needs the payer and the task account, signing via
+    /// task_market CPI; `task_creator` and `task_index` are the task's PDA
+    /// seeds.
+    pub fn link_to_task(
+        ctx: Context<LinkToTask>,
+        task_creator: Pubkey,
+        task_index: u64,
+    ) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        
+        require!(stream.task_id.is_none(), ErrorCode::StreamAlreadyLinked);
+        require!(stream.claim_mint.is_none(), ErrorCode::ClaimNftIssued);
+        
+        stream.task_id = Some(ctx.accounts.task_authority.key());
+        stream.task_creator = task_creator;
+        stream.task_index = task_index;
+        stream.task_bump = ctx.bumps.task_authority;
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+fn event_header(entity: Pubkey, seq: &mut u64, timestamp: i64) -> EventHeader {
+    EventHeader::next(ProgramTag::PaymentStreams, entity, seq, timestamp)
+}
+
+/// `TransferChecked` through whichever token program owns the mint. Goes via
+/// `invoke_transfer_checked` rather than the anchor_spl wrapper so that
+/// transfer-hook extra accounts are resolved from `extra_accounts`.
+#[allow(clippy::too_many_arguments)]
+fn transfer_checked<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    spl_token_2022::onchain::invoke_transfer_checked(
+        &token_program.key(),
+        from.to_account_info(),
+        mint.to_account_info(),
+        to.to_account_info(),
+        authority,
+        extra_accounts,
+        amount,
+        mint.decimals,
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Transfer from the payer into escrow and return the amount that actually
+/// arrived, which is less than `amount` when the mint charges a transfer fee.
+fn deposit_to_escrow<'info>(
+    from: &InterfaceAccount<'info, TokenAccount>,
+    escrow: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &Signer<'info>,
+    amount: u64,
+    token_program: &Interface<'info, TokenInterface>,
+    extra_accounts: &[AccountInfo<'info>],
+) -> Result<u64> {
+    let before = escrow.amount;
+    transfer_checked(
+        token_program,
+        from,
+        mint,
+        escrow,
+        authority.to_account_info(),
+        extra_accounts,
+        amount,
+        &[],
+    )?;
+    escrow.reload()?;
+
+    escrow
+        .amount
+        .checked_sub(before)
+        .ok_or(ErrorCode::Overflow.into())
+}
+
+/// A stream's escrow plus everything needed to sign transfers out of it.
+/// Holds the stream key and stored bump directly so callers don't re-derive
+/// seeds or clone the stream account.
+struct EscrowTransfer<'a, 'info> {
+    escrow: &'a InterfaceAccount<'info, TokenAccount>,
+    mint: &'a InterfaceAccount<'info, Mint>,
+    stream_key: Pubkey,
+    escrow_bump: u8,
+    token_program: &'a Interface<'info, TokenInterface>,
+    /// Transfer-hook accounts, forwarded from `remaining_accounts`
+    extra_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> EscrowTransfer<'a, 'info> {
+    /// Transfer out of escrow. Escrow balances are tracked net of transfer
+    /// fees on the way in; fees on the way out are borne by the recipient.
+    fn transfer(&self, to: &InterfaceAccount<'info, TokenAccount>, amount: u64) -> Result<()> {
+        let seeds = &[
+            b"escrow",
+            self.stream_key.as_ref(),
+            &[self.escrow_bump],
+        ];
+
+        transfer_checked(
+            self.token_program,
+            self.escrow,
+            self.mint,
+            to,
+            self.escrow.to_account_info(),
+            self.extra_accounts,
+            amount,
+            &[&seeds[..]],
+        )
+    }
+
+    /// Pay `amount` out of escrow: the platform fee goes to the treasury and
+    /// the rest to the payee. Returns the fee charged.
+    fn pay(
+        &self,
+        payee_token: &InterfaceAccount<'info, TokenAccount>,
+        treasury: &InterfaceAccount<'info, TokenAccount>,
+        amount: u64,
+        fee_basis_points: u16,
+    ) -> Result<u64> {
+        self.pay_referred(payee_token, treasury, None, amount, fee_basis_points, 0)
+            .map(|(fee, _)| fee)
+    }
+
+    /// Like `pay`, but `referral_share_bps` of the platform fee goes to the
+    /// referrer's token account, if given, instead of the treasury. Returns
+    /// the fee charged and the referrer's share of it.
+    fn pay_referred(
+        &self,
+        payee_token: &InterfaceAccount<'info, TokenAccount>,
+        treasury: &InterfaceAccount<'info, TokenAccount>,
+        referrer_token: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+        fee_basis_points: u16,
+        referral_share_bps: u16,
+    ) -> Result<(u64, u64)> {
+        let fee = platform_fee(amount, fee_basis_points)?;
+        let referral = match referrer_token {
+            Some(referrer_token) => {
+                let referral = platform_fee(fee, referral_share_bps)?;
+                if referral > 0 {
+                    self.transfer(referrer_token, referral)?;
+                }
+                referral
+            }
+            None => 0,
+        };
+
+        self.transfer(payee_token, amount - fee)?;
+        if fee > referral {
+            self.transfer(treasury, fee - referral)?;
+        }
+
+        Ok((fee, referral))
+    }
+
+    /// Pay the configured cranker tip out of a tick `amount`, capped so the
+    /// platform fee is still covered. Returns the tip paid, which is zero if
+    /// the cranker brought no token account.
+    fn tip_cranker(
+        &self,
+        cranker_token: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+        config: &ProgramConfig,
+        fee_basis_points: u16,
+    ) -> Result<u64> {
+        let Some(cranker_token) = cranker_token else {
+            return Ok(0);
+        };
+
+        let fee = platform_fee(amount, fee_basis_points)?;
+        let tip = config.cranker_tip.amount(amount)?.min(amount - fee);
+        if tip > 0 {
+            self.transfer(cranker_token, tip)?;
+        }
+
+        Ok(tip)
+    }
+}
+
+/// What a tick did to a stream
+enum TickOutcome {
+    Paid { amount: u64, fee: u64, tip: u64, referral: u64 },
+    /// Escrow couldn't cover the tick and the stream entered its grace window
+    GraceStarted { amount_due: u64 },
+    /// Escrow couldn't cover the tick after the grace window, so what was
+    /// left was paid out, along with the security deposit, and the stream
+    /// auto-terminated
+    Depleted { deposit_forfeited: u64 },
+}
+
+/// Accrual older than `max_tick_interval` moved into arrears by a late tick
+struct StaleBacklog {
+    seconds: i64,
+    amount: u64,
+}
+
+/// Settle one tick: pay out what has accrued and update stream and config
+/// totals. Every check runs before the first transfer, so an error leaves
+/// nothing half-done; `tick_many` relies on this to skip bad streams.
+///
+/// After a gap longer than `config.max_tick_interval`, only the last
+/// interval is paid in full. The rest goes to arrears, which later ticks
+/// pay off at up to one interval's worth each.
+///
+/// Nothing is paid before the stream's cliff ends; the first tick after it
+/// pays everything accrued since start.
+#[allow(clippy::too_many_arguments)]
+fn settle_tick<'info>(
+    stream: &mut PaymentStream,
+    escrow: &EscrowTransfer<'_, 'info>,
+    payee_token: &InterfaceAccount<'info, TokenAccount>,
+    treasury: &InterfaceAccount<'info, TokenAccount>,
+    cranker: Pubkey,
+    cranker_token: Option<&InterfaceAccount<'info, TokenAccount>>,
+    referrer_token: Option<&InterfaceAccount<'info, TokenAccount>>,
+    config: &mut ProgramConfig,
+    now: i64,
+) -> Result<(TickOutcome, Option<StaleBacklog>)> {
+    require!(
+        stream.status == StreamStatus::Active || stream.status == StreamStatus::Grace,
+        ErrorCode::StreamNotActive
+    );
+    // Callers check payee_token against the current payee, who may have
+    // bought the stream's claim NFT
+    if let Some(tick_authority) = stream.tick_authority {
+        require!(
+            cranker == tick_authority || cranker == payee_token.owner,
+            ErrorCode::UnauthorizedCranker
+        );
+    }
+    require!(stream.mode == StreamMode::Continuous, ErrorCode::NotContinuousStream);
+    let cliff_ends_at = stream.started_at + stream.cliff_seconds;
+    require!(now >= cliff_ends_at, ErrorCode::CliffNotReached);
+
+    // Calculate time elapsed and amount due
+    let elapsed = now - stream.last_tick_at;
+    require!(
+        elapsed > 0 || stream.accrued_unpaid > 0 || stream.arrears > 0,
+        ErrorCode::NoTimeElapsed
+    );
+    require!(
+        elapsed >= stream.min_tick_interval.unwrap_or(config.min_tick_interval),
+        ErrorCode::TickTooSoon
+    );
+
+    // Accrual held back by the cliff is released in full, never as arrears
+    let max_interval = config.max_tick_interval;
+    let cutoff = now - max_interval;
+    let stale_from = stream.last_tick_at.max(cliff_ends_at);
+    let stale = if max_interval > 0 && stale_from < cutoff {
+        let held = accrued(stream, stream.last_tick_at, stale_from)?;
+        stream.accrued_unpaid = stream.accrued_unpaid.checked_add(held).ok_or(ErrorCode::Overflow)?;
+        let amount = accrued(stream, stale_from, cutoff)?;
+        stream.arrears = stream.arrears.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        stream.last_tick_at = cutoff;
+        Some(StaleBacklog { seconds: cutoff - stale_from, amount })
+    } else {
+        None
+    };
+
+    let installment = if max_interval > 0 {
+        stream.arrears.min(rate_at(stream, now).saturating_mul(max_interval as u64))
+    } else {
+        stream.arrears
+    };
+    let amount_due = amount_owed(stream, now)?
+        .checked_add(installment)
+        .ok_or(ErrorCode::Overflow)?;
+    let fee_basis_points = stream_fee_basis_points(stream, config);
+
+    // Check if escrow has enough
+    if amount_due > stream.escrow_balance {
+        // Give the payer grace_period seconds to top up before giving up
+        if stream.status == StreamStatus::Active && stream.grace_period > 0 {
+            stream.status = StreamStatus::Grace;
+            stream.grace_started_at = now;
+            return Ok((TickOutcome::GraceStarted { amount_due }, stale));
+        }
+
+        require!(
+            now >= stream.grace_started_at + stream.grace_period,
+            ErrorCode::GracePeriodActive
+        );
+        require!(stream.auto_terminate, ErrorCode::InsufficientEscrow);
+
+        // Pay remaining balance and terminate
+        let remaining = stream.escrow_balance;
+        if remaining > 0 {
+            let tip = escrow.tip_cranker(cranker_token, remaining, config, fee_basis_points)?;
+            escrow.pay(payee_token, treasury, remaining - tip, fee_basis_points)?;
+            config.total_volume += remaining;
+        }
+
+        // The payer let the stream lapse, so the payee keeps the deposit
+        let deposit_forfeited = stream.security_deposit;
+        if deposit_forfeited > 0 {
+            escrow.transfer(payee_token, deposit_forfeited)?;
+            stream.security_deposit = 0;
+        }
+
+        stream.total_paid += remaining;
+        stream.escrow_balance = 0;
+        stream.accrued_unpaid = 0;
+        stream.arrears = 0;
+        stream.status = StreamStatus::Completed;
+
+        return Ok((TickOutcome::Depleted { deposit_forfeited }, stale));
+    }
+
+    // Transfer payment, less the cranker tip and platform fee
+    let tip = escrow.tip_cranker(cranker_token, amount_due, config, fee_basis_points)?;
+    let (fee, referral) = escrow.pay_referred(
+        payee_token,
+        treasury,
+        referrer_token,
+        amount_due - tip,
+        fee_basis_points,
+        config.referral_share_bps,
+    )?;
+    config.total_volume += amount_due;
+
+    // Update stream state
+    stream.status = StreamStatus::Active;
+    stream.last_tick_at = now;
+    stream.accrued_unpaid = 0;
+    stream.arrears -= installment;
+    stream.total_paid += amount_due;
+    stream.total_ticks += 1;
+    stream.escrow_balance -= amount_due;
+
+    Ok((TickOutcome::Paid { amount: amount_due, fee, tip, referral }, stale))
+}
+
+/// A `(stream, escrow, payee_token)` triple loaded for `tick_many`
+type TickTarget<'info> = (
+    Account<'info, PaymentStream>,
+    InterfaceAccount<'info, TokenAccount>,
+    InterfaceAccount<'info, TokenAccount>,
+);
+
+/// Load a `tick_many` triple, or `None` if the accounts don't deserialize or
+/// don't belong together. Checked up front because a failed transfer CPI
+/// would abort the whole batch. Streams with a claim NFT or a referrer are
+/// skipped since the triple has no room for the claim or referrer accounts.
+fn load_tick_target<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Option<TickTarget<'info>> {
+    let [stream, escrow, payee_token] = accounts else {
+        return None;
+    };
+    if !(stream.is_writable && escrow.is_writable && payee_token.is_writable) {
+        return None;
+    }
+    if escrow.owner != token_program || payee_token.owner != token_program {
+        return None;
+    }
+
+    let stream = Account::<PaymentStream>::try_from(stream).ok()?;
+    let escrow = InterfaceAccount::<TokenAccount>::try_from(escrow).ok()?;
+    let payee_token = InterfaceAccount::<TokenAccount>::try_from(payee_token).ok()?;
+
+    let escrow_key = Pubkey::create_program_address(
+        &[b"escrow", stream.key().as_ref(), &[stream.escrow_bump]],
+        &crate::ID,
+    )
+    .ok()?;
+
+    (escrow.key() == escrow_key
+        && stream.referrer.is_none()
+        && escrow.mint == *mint
+        && stream.current_payee(None) == Some(payee_token.owner)
+        && payee_token.mint == *mint)
+        .then_some((stream, escrow, payee_token))
+}
+
+/// Amount accrued between two timestamps, integrating the rate schedule
+fn accrued(stream: &PaymentStream, from: i64, to: i64) -> Result<u64> {
+    if stream.rate_schedule.is_empty() {
+        return Ok(stream
+            .rate_per_second
+            .checked_mul((to - from) as u64)
+            .ok_or(ErrorCode::Overflow)?);
+    }
+
+    let mut total: u64 = 0;
+    let mut cursor = from;
+    let mut rate = stream.rate_per_second;
+
+    for segment in &stream.rate_schedule {
+        let segment_start = stream.started_at + segment.start_offset;
+        if segment_start > cursor {
+            let end = segment_start.min(to);
+            total = total
+                .checked_add(rate.checked_mul((end - cursor) as u64).ok_or(ErrorCode::Overflow)?)
+                .ok_or(ErrorCode::Overflow)?;
+            cursor = end;
+        }
+        if cursor >= to {
+            return Ok(total);
+        }
+        rate = segment.rate_per_second;
+    }
+
+    total
+        .checked_add(rate.checked_mul((to - cursor) as u64).ok_or(ErrorCode::Overflow)?)
+        .ok_or(ErrorCode::Overflow.into())
+}
+
+/// Owed to the payee for the current tick window: the amount snapshotted at
+/// the last pause plus whatever has accrued since the last tick while
+/// running. Excludes arrears.
+fn amount_owed(stream: &PaymentStream, now: i64) -> Result<u64> {
+    let running = match stream.status {
+        StreamStatus::Active | StreamStatus::Grace if stream.last_tick_at > 0 => {
+            accrued(stream, stream.last_tick_at, now)?
+        }
+        _ => 0,
+    };
+
+    stream
+        .accrued_unpaid
+        .checked_add(running)
+        .ok_or(ErrorCode::Overflow.into())
+}
+
+/// Rate in effect at `at` under the stream's rate schedule
+fn rate_at(stream: &PaymentStream, at: i64) -> u64 {
+    stream
+        .rate_schedule
+        .iter()
+        .rev()
+        .find(|segment| stream.started_at + segment.start_offset <= at)
+        .map_or(stream.rate_per_second, |segment| segment.rate_per_second)
+}
+
+/// Seconds of escrow left at the current rate, if below the stream's
+/// warning threshold. Ignores upcoming rate segments.
+fn low_escrow_runway(stream: &PaymentStream, now: i64) -> Option<u64> {
+    if stream.low_balance_threshold_seconds == 0 {
+        return None;
+    }
+
+    let rate = rate_at(stream, now);
+    if rate == 0 {
+        return None;
+    }
+
+    let remaining_seconds = stream.escrow_balance / rate;
+    (remaining_seconds < stream.low_balance_threshold_seconds as u64).then_some(remaining_seconds)
+}
+
+/// Terms a new stream is opened with
+struct StreamTerms {
+    payer: Pubkey,
+    payee: Pubkey,
+    rate_per_second: u64,
+    max_duration: i64,
+    grace_period: i64,
+    auto_terminate: bool,
+    task_id: Option<Pubkey>,
+    fee_discount_bps: u16,
+}
+
+impl StreamTerms {
+    /// Validate against program limits and return the escrow required
+    fn required_escrow(&self, config: &ProgramConfig) -> Result<u64> {
+        require!(self.rate_per_second > 0, ErrorCode::InvalidRate);
+        require!(
+            self.max_duration >= config.min_stream_duration as i64 && 
+            self.max_duration <= config.max_stream_duration as i64,
+            ErrorCode::InvalidDuration
+        );
+        require!(
+            self.grace_period >= 0 && self.grace_period <= 300,
+            ErrorCode::InvalidGracePeriod
+        );
+
+        self.rate_per_second
+            .checked_mul(self.max_duration as u64)
+            .ok_or(ErrorCode::Overflow.into())
+    }
+
+    /// A pending continuous stream on these terms
+    fn into_stream(
+        self,
+        escrow_balance: u64,
+        created_at: i64,
+        escrow_bump: u8,
+        bump: u8,
+    ) -> PaymentStream {
+        PaymentStream {
+            payer: self.payer,
+            payee: self.payee,
+            rate_per_second: self.rate_per_second,
+            max_duration: self.max_duration,
+            grace_period: self.grace_period,
+            auto_terminate: self.auto_terminate,
+            status: StreamStatus::Pending,
+            created_at,
+            started_at: 0,
+            last_tick_at: 0,
+            total_paid: 0,
+            total_ticks: 0,
+            escrow_balance,
+            task_id: self.task_id,
+            rate_schedule: Vec::new(),
+            mode: StreamMode::Continuous,
+            milestones: Vec::new(),
+            grace_started_at: 0,
+            accrued_unpaid: 0,
+            low_balance_threshold_seconds: 0,
+            pending_rate: None,
+            security_deposit: 0,
+            fee_discount_bps: self.fee_discount_bps,
+            tick_authority: None,
+            claim_mint: None,
+            activation_fee: 0,
+            metering: None,
+            arrears: 0,
+            early_termination: None,
+            min_tick_interval: None,
+            cliff_seconds: 0,
+            nonce: 0,
+            referrer: None,
+            task_creator: Pubkey::default(),
+            task_index: 0,
+            task_bump: 0,
+            event_seq: 0,
+            escrow_bump,
+            bump,
+        }
+    }
+}
+
+impl StreamTemplate {
+    /// Terms for a stream from `payer` to `payee` on this template
+    fn terms(&self, payer: Pubkey, payee: Pubkey, fee_discount_bps: u16) -> StreamTerms {
+        StreamTerms {
+            payer,
+            payee,
+            rate_per_second: self.rate_per_second,
+            max_duration: self.max_duration,
+            grace_period: self.grace_period,
+            auto_terminate: self.auto_terminate,
+            task_id: None,
+            fee_discount_bps,
+        }
+    }
+}
+
+fn mark_started(stream: &mut PaymentStream, now: i64) -> Result<()> {
+    require!(stream.status == StreamStatus::Accepted, ErrorCode::StreamNotAccepted);
+
+    stream.status = StreamStatus::Active;
+    stream.started_at = now;
+    stream.last_tick_at = now;
+
+    Ok(())
+}
+
+/// Refund a stream that never started: full escrow, security deposit and
+/// activation fee included. Returns the amount refunded.
+fn refund_unstarted<'info>(
+    stream: &mut PaymentStream,
+    escrow: &EscrowTransfer<'_, 'info>,
+    payer_token: &InterfaceAccount<'info, TokenAccount>,
+) -> Result<u64> {
+    let refund = stream.escrow_balance + stream.security_deposit + stream.activation_fee;
+    if refund > 0 {
+        escrow.transfer(payer_token, refund)?;
+        stream.escrow_balance = 0;
+        stream.security_deposit = 0;
+        stream.activation_fee = 0;
+    }
+
+    Ok(refund)
+}
+
+fn mark_paused(stream: &mut PaymentStream, now: i64) -> Result<()> {
+    require!(stream.status == StreamStatus::Active, ErrorCode::StreamNotActive);
+
+    // Snapshot what has accrued so resuming doesn't erase it
+    if stream.mode == StreamMode::Continuous {
+        stream.accrued_unpaid = amount_owed(stream, now)?;
+        stream.last_tick_at = now;
+    }
+    stream.status = StreamStatus::Paused;
+
+    Ok(())
+}
+
+/// Security deposit and penalty outcome of `settle_termination`
+struct TerminationSettlement {
+    deposit_refunded: u64,
+    deposit_forfeited: u64,
+    early_termination_fee: u64,
+}
+
+/// Pay the payee everything owed (accrual for continuous streams, approved
+/// milestones otherwise), refund the rest of escrow to the payer and mark
+/// the stream completed. The security deposit goes to the payee if the
+/// stream is past its grace window, otherwise back to the payer. If the
+/// payer ends a started stream (Active, Paused or in Grace) inside its
+/// early-termination window, the payee also gets the penalty share of the
+/// unstreamed escrow; a non-zero `cancellation_fee_bps` sets that share
+/// regardless.
+#[allow(clippy::too_many_arguments)]
+fn settle_termination<'info>(
+    stream: &mut PaymentStream,
+    escrow: &EscrowTransfer<'_, 'info>,
+    payer_token: &InterfaceAccount<'info, TokenAccount>,
+    payee_token: &InterfaceAccount<'info, TokenAccount>,
+    treasury: &InterfaceAccount<'info, TokenAccount>,
+    config: &mut ProgramConfig,
+    terminated_by: Pubkey,
+    cancellation_fee_bps: u16,
+    now: i64,
+) -> Result<TerminationSettlement> {
+    require!(
+        stream.status == StreamStatus::Active || 
+        stream.status == StreamStatus::Grace ||
+        stream.status == StreamStatus::Paused ||
+        stream.status == StreamStatus::Pending ||
+        stream.status == StreamStatus::Accepted,
+        ErrorCode::StreamAlreadyTerminated
+    );
+    let payer_defaulted = stream.status == StreamStatus::Grace &&
+        now >= stream.grace_started_at + stream.grace_period;
+    let penalty_bps = match stream.early_termination {
+        _ if cancellation_fee_bps > 0 => cancellation_fee_bps,
+        // Pausing first doesn't dodge the penalty: any started stream the
+        // payer ends inside the window counts
+        Some(terms) if terminated_by == stream.payer &&
+            matches!(
+                stream.status,
+                StreamStatus::Active | StreamStatus::Paused | StreamStatus::Grace
+            ) &&
+            now - stream.started_at < terms.min_runtime => terms.penalty_bps,
+        _ => 0,
+    };
+
+    let final_payment = match stream.mode {
+        StreamMode::Continuous => amount_owed(stream, now)?
+            .checked_add(stream.arrears)
+            .ok_or(ErrorCode::Overflow)?,
+        StreamMode::Milestone => take_approved_milestones(stream),
+        // Only signed usage is billed
+        StreamMode::Metered => 0,
+    }
+    .min(stream.escrow_balance);
+    stream.accrued_unpaid = 0;
+    stream.arrears = 0;
+
+    if final_payment > 0 {
+        escrow.pay(payee_token, treasury, final_payment, stream_fee_basis_points(stream, config))?;
+        config.total_volume += final_payment;
+        stream.total_paid += final_payment;
+        stream.escrow_balance -= final_payment;
+    }
+
+    // Penalise the payer for pulling out early, from what was left unstreamed
+    let early_termination_fee =
+        (stream.escrow_balance as u128 * penalty_bps as u128 / 10_000) as u64;
+    if early_termination_fee > 0 {
+        escrow.transfer(payee_token, early_termination_fee)?;
+        stream.escrow_balance -= early_termination_fee;
+    }
+
+    // Refund remaining escrow to payer, with the activation fee if the
+    // stream never started
+    let refund = stream.escrow_balance + stream.activation_fee;
+    if refund > 0 {
+        escrow.transfer(payer_token, refund)?;
+        stream.escrow_balance = 0;
+        stream.activation_fee = 0;
+    }
+
+    let deposit = stream.security_deposit;
+    if deposit > 0 {
+        escrow.transfer(if payer_defaulted { payee_token } else { payer_token }, deposit)?;
+        stream.security_deposit = 0;
+    }
+
+    stream.status = StreamStatus::Completed;
+
+    let (deposit_refunded, deposit_forfeited) =
+        if payer_defaulted { (0, deposit) } else { (deposit, 0) };
+
+    Ok(TerminationSettlement { deposit_refunded, deposit_forfeited, early_termination_fee })
+}
+
+/// Close a finished stream's empty escrow into `destination`; the stream
+/// account itself is closed by its `close` constraint
+fn close_escrow<'info>(
+    stream: &PaymentStream,
+    stream_key: Pubkey,
+    escrow: &InterfaceAccount<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    require!(
+        stream.status == StreamStatus::Completed || stream.status == StreamStatus::Cancelled,
+        ErrorCode::StreamNotFinished
     );
-    token::transfer(transfer_ctx, amount)?;
+    require!(stream.escrow_balance == 0 && escrow.amount == 0, ErrorCode::EscrowNotEmpty);
+
+    let seeds = &[
+        b"escrow",
+        stream_key.as_ref(),
+        &[stream.escrow_bump],
+    ];
+    token_interface::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        CloseAccount {
+            account: escrow.to_account_info(),
+            destination: destination.clone(),
+            authority: escrow.to_account_info(),
+        },
+        &[&seeds[..]],
+    ))
+}
+
+/// Return a stream in its grace window to Active if escrow now covers
+/// everything owed. Returns whether it was rescued.
+fn rescue_from_grace(stream: &mut PaymentStream, now: i64) -> Result<bool> {
+    if stream.status != StreamStatus::Grace ||
+        amount_owed(stream, now)?.saturating_add(stream.arrears) > stream.escrow_balance
+    {
+        return Ok(false);
+    }
+
+    stream.status = StreamStatus::Active;
+    stream.grace_started_at = 0;
+
+    Ok(true)
+}
+
+/// Mark a milestone released and pay it from escrow to the payee. Returns the
+/// amount and the fee taken from it.
+fn pay_milestone<'info>(
+    stream: &mut PaymentStream,
+    index: u8,
+    escrow: &EscrowTransfer<'_, 'info>,
+    payee_token: &InterfaceAccount<'info, TokenAccount>,
+    treasury: &InterfaceAccount<'info, TokenAccount>,
+    config: &mut ProgramConfig,
+) -> Result<(u64, u64)> {
+    let fee_basis_points = stream_fee_basis_points(stream, config);
+    let milestone = &mut stream.milestones[index as usize];
+    milestone.status = MilestoneStatus::Released;
+    let amount = milestone.amount;
+
+    require!(amount <= stream.escrow_balance, ErrorCode::InsufficientEscrow);
+
+    let fee = escrow.pay(payee_token, treasury, amount, fee_basis_points)?;
+    config.total_volume += amount;
+
+    stream.total_paid += amount;
+    stream.escrow_balance -= amount;
+
+    Ok((amount, fee))
+}
+
+/// Mark every approved milestone released and return their total
+fn take_approved_milestones(stream: &mut PaymentStream) -> u64 {
+    stream
+        .milestones
+        .iter_mut()
+        .filter(|m| m.status == MilestoneStatus::Approved)
+        .map(|m| {
+            m.status = MilestoneStatus::Released;
+            m.amount
+        })
+        .sum()
+}
+
+/// Append a stream to a wallet's registry, growing the account by one entry
+/// with the extra rent paid by `payer`
+fn register_stream<'info>(
+    registry: &mut Account<'info, StreamRegistry>,
+    owner: Pubkey,
+    bump: u8,
+    stream: Pubkey,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    // Fresh from init_if_needed
+    if registry.owner == Pubkey::default() {
+        registry.owner = owner;
+        registry.bump = bump;
+    }
+
+    let info = registry.to_account_info();
+    let new_len = StreamRegistry::space(registry.streams.len() + 1);
+    let rent = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+    if rent > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            rent,
+        )?;
+    }
+    info.realloc(new_len, false)?;
+    registry.streams.push(stream);
+
+    Ok(())
+}
+
+/// Check that the instruction before this one is an Ed25519 program
+/// instruction verifying one signature by `signer` over `message`, with all
+/// three stored in that instruction's own data
+fn verify_ed25519(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current = load_current_index_checked(instructions)?;
+    require!(current > 0, ErrorCode::InvalidUsageReport);
+    let ix = load_instruction_at_checked(current as usize - 1, instructions)?;
+    require!(ix.program_id == ed25519_program::ID, ErrorCode::InvalidUsageReport);
+
+    // [num_signatures, padding, then Ed25519SignatureOffsets as u16s]
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidUsageReport);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let [signature_ix, pubkey_offset, pubkey_ix, message_offset, message_len, message_ix] =
+        [4, 6, 8, 10, 12, 14].map(read_u16);
+    require!(
+        signature_ix == u16::MAX && pubkey_ix == u16::MAX && message_ix == u16::MAX,
+        ErrorCode::InvalidUsageReport
+    );
+
+    let pubkey_offset = pubkey_offset as usize;
+    let message_offset = message_offset as usize;
+    require!(
+        data.get(pubkey_offset..pubkey_offset + 32) == Some(signer.as_ref()) &&
+        data.get(message_offset..message_offset + message_len as usize) == Some(message),
+        ErrorCode::InvalidUsageReport
+    );
+
+    Ok(())
+}
+
+/// Fee discount for a payer's $DRONEOS stake, in basis points of the fee
+fn staker_discount(stake: Option<&Account<StakeAccount>>) -> u16 {
+    stake.map_or(0, |stake| droneos_token::fee_tier_discount(droneos_token::fee_tier(stake)))
+}
+
+/// Platform fee rate for a stream after its staker discount
+fn stream_fee_basis_points(stream: &PaymentStream, config: &ProgramConfig) -> u16 {
+    discounted_fee_basis_points(config.fee_basis_points, stream.fee_discount_bps)
+}
+
+/// `fee_basis_points` less `discount_bps` of it, rounded down
+fn discounted_fee_basis_points(fee_basis_points: u16, discount_bps: u16) -> u16 {
+    (fee_basis_points as u32 * (10_000 - discount_bps as u32) / 10_000) as u16
+}
+
+/// Platform fee on a payout, rounded down
+fn platform_fee(amount: u64, fee_basis_points: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(fee_basis_points as u128)
+        .ok_or(ErrorCode::Overflow)?
+        / 10_000;
+    Ok(fee as u64)
+}
+
+// ============================================================================
+// ACCOUNTS
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeProgram<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProgramConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CreateStream<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.frozen @ ErrorCode::ProgramFrozen
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PaymentStream::INIT_SPACE,
+        seeds = [b"stream", payer.key().as_ref(), payee.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+        token::token_program = token_program,
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = payer_token.owner == payer.key(),
+        constraint = payer_token.mint == mint.key()
+    )]
+    pub payer_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Just storing the payee address
+    pub payee: AccountInfo<'info>,
+    
+    /// One of the payer's $DRONEOS stake positions, if any, for a platform
+    /// fee discount
+    #[account(
+        seeds = [b"stake", payer.key().as_ref(), &payer_stake.index.to_le_bytes()],
+        bump = payer_stake.bump,
+        seeds::program = droneos_token::ID,
+    )]
+    pub payer_stake: Option<Account<'info, StakeAccount>>,
+    
+    /// Registered referrer to credit with a share of this stream's fees
+    #[account(
+        mut,
+        seeds = [b"referrer", referrer_account.referrer.as_ref()],
+        bump = referrer_account.bump
+    )]
+    pub referrer_account: Option<Account<'info, Referrer>>,
+    
+    /// Streams this payer has opened
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StreamRegistry::space(0),
+        seeds = [b"payer_streams", payer.key().as_ref()],
+        bump
+    )]
+    pub payer_registry: Box<Account<'info, StreamRegistry>>,
+    
+    /// Streams opened to this payee
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StreamRegistry::space(0),
+        seeds = [b"payee_streams", payee.key().as_ref()],
+        bump
+    )]
+    pub payee_registry: Box<Account<'info, StreamRegistry>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(
+    rate_per_second: u64,
+    max_duration: i64,
+    grace_period: i64,
+    auto_terminate: bool,
+    task_creator: Pubkey,
+    task_index: u64
+)]
+pub struct CreateStreamForTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.frozen @ ErrorCode::ProgramFrozen
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PaymentStream::INIT_SPACE,
+        seeds = [b"task_stream", task_authority.key().as_ref()],
+        bump
+    )]
+    pub stream: Box<Account<'info, PaymentStream>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+        token::token_program = token_program,
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        constraint = payer_token.owner == payer.key(),
+        constraint = payer_token.mint == mint.key()
+    )]
+    pub payer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Just storing the payee address
+    pub payee: AccountInfo<'info>,
+    
+    /// One of the payer's $DRONEOS stake positions, if any, for a platform
+    /// fee discount
+    #[account(
+        seeds = [b"stake", payer.key().as_ref(), &payer_stake.index.to_le_bytes()],
+        bump = payer_stake.bump,
+        seeds::program = droneos_token::ID,
+    )]
+    pub payer_stake: Option<Account<'info, StakeAccount>>,
+    
+    /// Streams this payer has opened
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StreamRegistry::space(0),
+        seeds = [b"payer_streams", payer.key().as_ref()],
+        bump
+    )]
+    pub payer_registry: Box<Account<'info, StreamRegistry>>,
+    
+    /// Streams opened to this payee
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StreamRegistry::space(0),
+        seeds = [b"payee_streams", payee.key().as_ref()],
+        bump
+    )]
+    pub payee_registry: Box<Account<'info, StreamRegistry>>,
+    
+    /// Task account, signing via task_market CPI
+    #[account(
+        seeds = [b"task", task_creator.as_ref(), &task_index.to_le_bytes()],
+        bump,
+        seeds::program = TASK_MARKET_PROGRAM_ID
+    )]
+    pub task_authority: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(template_id: u64)]
+pub struct CreateTemplate<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + StreamTemplate::INIT_SPACE,
+        seeds = [b"template", owner.key().as_ref(), &template_id.to_le_bytes()],
+        bump
+    )]
+    pub template: Account<'info, StreamTemplate>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
 
-    Ok(())
+#[derive(Accounts)]
+pub struct CloseTemplate<'info> {
+    #[account(mut, has_one = owner, close = owner)]
+    pub template: Account<'info, StreamTemplate>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
 }
 
-// ============================================================================
-// ACCOUNTS
-// ============================================================================
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(nonce: u64)]
+pub struct CreateStreamFromTemplate<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.frozen @ ErrorCode::ProgramFrozen
+    )]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    
+    #[account(mut, constraint = template.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub template: Box<Account<'info, StreamTemplate>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PaymentStream::INIT_SPACE,
+        seeds = [b"stream", payer.key().as_ref(), payee.key().as_ref(), &nonce.to_le_bytes()],
+        bump
+    )]
+    pub stream: Box<Account<'info, PaymentStream>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+        token::token_program = token_program,
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = template.mint)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        mut,
+        constraint = payer_token.owner == payer.key(),
+        constraint = payer_token.mint == mint.key()
+    )]
+    pub payer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Just storing the payee address
+    pub payee: AccountInfo<'info>,
+    
+    /// One of the payer's $DRONEOS stake positions, if any, for a platform
+    /// fee discount
+    #[account(
+        seeds = [b"stake", payer.key().as_ref(), &payer_stake.index.to_le_bytes()],
+        bump = payer_stake.bump,
+        seeds::program = droneos_token::ID,
+    )]
+    pub payer_stake: Option<Account<'info, StakeAccount>>,
+    
+    /// Streams this payer has opened
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StreamRegistry::space(0),
+        seeds = [b"payer_streams", payer.key().as_ref()],
+        bump
+    )]
+    pub payer_registry: Box<Account<'info, StreamRegistry>>,
+    
+    /// Streams opened to this payee
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StreamRegistry::space(0),
+        seeds = [b"payee_streams", payee.key().as_ref()],
+        bump
+    )]
+    pub payee_registry: Box<Account<'info, StreamRegistry>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct StartStream<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.frozen @ ErrorCode::ProgramFrozen
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        mut,
+        constraint = stream.payer == payer.key() @ ErrorCode::Unauthorized,
+        constraint = stream.task_id.is_none() @ ErrorCode::TaskControlledStream
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Holder's claim NFT token account, if the stream has a claim NFT
+    pub claim_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Receives the activation fee
+    #[account(
+        mut,
+        constraint = stream.current_payee(claim_token.as_deref()) == Some(payee_token.owner) @ ErrorCode::NotClaimHolder
+    )]
+    pub payee_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.mint == escrow.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Tick<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.frozen @ ErrorCode::ProgramFrozen
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Holder's claim NFT token account, if the stream has a claim NFT
+    pub claim_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = stream.current_payee(claim_token.as_deref()) == Some(payee_token.owner) @ ErrorCode::NotClaimHolder
+    )]
+    pub payee_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.mint == escrow.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub cranker: Signer<'info>,
+    
+    /// Receives the cranker tip; omit to forgo it
+    #[account(
+        mut,
+        constraint = cranker_token.owner == cranker.key(),
+        constraint = cranker_token.mint == escrow.mint
+    )]
+    pub cranker_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// The stream's referrer and its token account; required if it has one
+    #[account(
+        mut,
+        seeds = [b"referrer", referrer_account.referrer.as_ref()],
+        bump = referrer_account.bump,
+        constraint = Some(referrer_account.referrer) == stream.referrer @ ErrorCode::InvalidReferrer
+    )]
+    pub referrer_account: Option<Account<'info, Referrer>>,
+    
+    #[account(
+        mut,
+        constraint = Some(referrer_token.owner) == stream.referrer @ ErrorCode::InvalidReferrer,
+        constraint = referrer_token.mint == escrow.mint @ ErrorCode::InvalidReferrer
+    )]
+    pub referrer_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TickUsage<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.frozen @ ErrorCode::ProgramFrozen
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Holder's claim NFT token account, if the stream has a claim NFT
+    pub claim_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = stream.current_payee(claim_token.as_deref()) == Some(payee_token.owner) @ ErrorCode::NotClaimHolder
+    )]
+    pub payee_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.mint == escrow.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub cranker: Signer<'info>,
+    
+    /// Receives the cranker tip; omit to forgo it
+    #[account(
+        mut,
+        constraint = cranker_token.owner == cranker.key(),
+        constraint = cranker_token.mint == escrow.mint
+    )]
+    pub cranker_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// The stream's referrer and its token account; required if it has one
+    #[account(
+        mut,
+        seeds = [b"referrer", referrer_account.referrer.as_ref()],
+        bump = referrer_account.bump,
+        constraint = Some(referrer_account.referrer) == stream.referrer @ ErrorCode::InvalidReferrer
+    )]
+    pub referrer_account: Option<Account<'info, Referrer>>,
+    
+    #[account(
+        mut,
+        constraint = Some(referrer_token.owner) == stream.referrer @ ErrorCode::InvalidReferrer,
+        constraint = referrer_token.mint == escrow.mint @ ErrorCode::InvalidReferrer
+    )]
+    pub referrer_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// CHECK: Instructions sysvar, for the Ed25519 signature check
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetUsageMetering<'info> {
+    #[account(
+        mut,
+        has_one = payer @ ErrorCode::Unauthorized,
+        constraint = stream.task_id.is_none() @ ErrorCode::TaskControlledStream
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(constraint = robot.operator == stream.payee @ ErrorCode::Unauthorized)]
+    pub robot: Account<'info, Robot>,
+    
+    pub payer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TickMany<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.frozen @ ErrorCode::ProgramFrozen
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.mint == mint.key() @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub cranker: Signer<'info>,
+    
+    /// Receives the cranker tip; omit to forgo it
+    #[account(
+        mut,
+        constraint = cranker_token.owner == cranker.key(),
+        constraint = cranker_token.mint == mint.key()
+    )]
+    pub cranker_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ControlStream<'info> {
+    #[account(
+        mut,
+        constraint = stream.payer == authority.key() @ ErrorCode::Unauthorized,
+        constraint = stream.task_id.is_none() @ ErrorCode::TaskControlledStream
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TerminateStream<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        mut,
+        constraint = stream.payer == authority.key() ||
+            stream.current_payee(claim_token.as_deref()) == Some(authority.key()) @ ErrorCode::Unauthorized,
+        constraint = stream.task_id.is_none() @ ErrorCode::TaskControlledStream
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, constraint = payer_token.owner == stream.payer)]
+    pub payer_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Holder's claim NFT token account, if the stream has a claim NFT
+    pub claim_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = stream.current_payee(claim_token.as_deref()) == Some(payee_token.owner) @ ErrorCode::NotClaimHolder
+    )]
+    pub payee_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.mint == escrow.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TaskControlStream<'info> {
+    #[account(
+        mut,
+        constraint = stream.task_id == Some(task_authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    /// Task account, signing via task_market CPI
+    #[account(
+        seeds = [b"task", stream.task_creator.as_ref(), &stream.task_index.to_le_bytes()],
+        bump = stream.task_bump,
+        seeds::program = TASK_MARKET_PROGRAM_ID
+    )]
+    pub task_authority: Signer<'info>,
+}
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct InitializeProgram<'info> {
+pub struct StartStreamByTask<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + ProgramConfig::INIT_SPACE,
         seeds = [b"config"],
-        bump
+        bump = config.bump,
+        constraint = !config.frozen @ ErrorCode::ProgramFrozen
     )]
     pub config: Account<'info, ProgramConfig>,
     
+    #[account(
+        mut,
+        constraint = stream.task_id == Some(task_authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    /// Task account, signing via task_market CPI
+    #[account(
+        seeds = [b"task", stream.task_creator.as_ref(), &stream.task_index.to_le_bytes()],
+        bump = stream.task_bump,
+        seeds::program = TASK_MARKET_PROGRAM_ID
+    )]
+    pub task_authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TerminateStreamByTask<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    
+    #[account(
+        mut,
+        constraint = stream.task_id == Some(task_authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub stream: Box<Account<'info, PaymentStream>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(mut, constraint = payer_token.owner == stream.payer)]
+    pub payer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut, constraint = payee_token.owner == stream.payee)]
+    pub payee_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.mint == escrow.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Task account, signing via task_market CPI
+    #[account(
+        seeds = [b"task", stream.task_creator.as_ref(), &stream.task_index.to_le_bytes()],
+        bump = stream.task_bump,
+        seeds::program = TASK_MARKET_PROGRAM_ID
+    )]
+    pub task_authority: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TopUpEscrow<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub stream: Account<'info, PaymentStream>,
     
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, constraint = payer_token.owner == payer.key())]
+    pub payer_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(constraint = payer.key() == stream.payer @ ErrorCode::Unauthorized)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct CreateStream<'info> {
+pub struct ExtendStream<'info> {
     #[account(seeds = [b"config"], bump = config.bump)]
     pub config: Account<'info, ProgramConfig>,
     
     #[account(
-        init,
-        payer = payer,
-        space = 8 + PaymentStream::INIT_SPACE,
-        seeds = [b"stream", payer.key().as_ref(), payee.key().as_ref(), &Clock::get()?.unix_timestamp.to_le_bytes()],
-        bump
+        mut,
+        constraint = stream.task_id.is_none() @ ErrorCode::TaskControlledStream
     )]
     pub stream: Account<'info, PaymentStream>,
     
     #[account(
-        init,
-        payer = payer,
+        mut,
         seeds = [b"escrow", stream.key().as_ref()],
-        bump,
-        token::mint = mint,
-        token::authority = escrow,
+        bump = stream.escrow_bump
     )]
-    pub escrow: Account<'info, TokenAccount>,
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
     
-    pub mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, constraint = payer_token.owner == payer.key())]
+    pub payer_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(constraint = payer.key() == stream.payer @ ErrorCode::Unauthorized)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SponsorEscrow<'info> {
+    #[account(mut)]
+    pub stream: Account<'info, PaymentStream>,
     
     #[account(
         mut,
-        constraint = payer_token.owner == payer.key(),
-        constraint = payer_token.mint == mint.key()
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        space = 8 + Sponsorship::INIT_SPACE,
+        seeds = [b"sponsorship", stream.key().as_ref(), sponsor.key().as_ref()],
+        bump
     )]
-    pub payer_token: Account<'info, TokenAccount>,
+    pub sponsorship: Account<'info, Sponsorship>,
+    
+    #[account(mut, constraint = sponsor_token.owner == sponsor.key())]
+    pub sponsor_token: InterfaceAccount<'info, TokenAccount>,
     
     #[account(mut)]
+    pub sponsor: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptStream<'info> {
+    #[account(
+        mut,
+        constraint = stream.payee == payee.key() @ ErrorCode::Unauthorized
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    pub payee: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RejectStream<'info> {
+    #[account(
+        mut,
+        constraint = stream.payee == payee.key() @ ErrorCode::Unauthorized
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, constraint = payer_token.owner == stream.payer)]
+    pub payer_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub payee: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelStream<'info> {
+    #[account(
+        mut,
+        constraint = stream.payer == payer.key() @ ErrorCode::Unauthorized,
+        constraint = stream.task_id.is_none() @ ErrorCode::TaskControlledStream
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, constraint = payer_token.owner == payer.key())]
+    pub payer_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub payer: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TransferPayee<'info> {
+    #[account(
+        mut,
+        constraint = stream.payer == payer.key() @ ErrorCode::Unauthorized,
+        constraint = stream.payee == consenter.key() ||
+            stream.task_authority() == Some(consenter.key()) @ ErrorCode::Unauthorized
+    )]
+    pub stream: Account<'info, PaymentStream>,
+    
     pub payer: Signer<'info>,
     
-    /// CHECK: Just storing the payee address
-    pub payee: AccountInfo<'info>,
+    /// Current payee, or the linked task signing via CPI
+    pub consenter: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RenegotiateRate<'info> {
+    #[account(
+        mut,
+        constraint = stream.payer == authority.key() ||
+            stream.current_payee(claim_token.as_deref()) == Some(authority.key()) @ ErrorCode::Unauthorized,
+        constraint = stream.task_id.is_none() @ ErrorCode::TaskControlledStream
+    )]
+    pub stream: Account<'info, PaymentStream>,
     
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    /// Holder's claim NFT token account, if the stream has a claim NFT
+    pub claim_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub authority: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct StartStream<'info> {
+pub struct CloseStream<'info> {
     #[account(
         mut,
+        close = payer,
         constraint = stream.payer == payer.key() @ ErrorCode::Unauthorized
     )]
     pub stream: Account<'info, PaymentStream>,
     
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
     pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct Tick<'info> {
-    #[account(mut)]
+pub struct CloseStreamByTask<'info> {
+    #[account(
+        mut,
+        close = payer,
+        constraint = stream.task_id == Some(task_authority.key()) @ ErrorCode::Unauthorized
+    )]
     pub stream: Account<'info, PaymentStream>,
     
     #[account(
@@ -477,103 +3684,260 @@ pub struct Tick<'info> {
         seeds = [b"escrow", stream.key().as_ref()],
         bump = stream.escrow_bump
     )]
-    pub escrow: Account<'info, TokenAccount>,
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: The stream's payer, receiving its rent
+    #[account(mut, address = stream.payer @ ErrorCode::Unauthorized)]
+    pub payer: AccountInfo<'info>,
     
+    /// Task account, signing via task_market CPI
     #[account(
-        mut,
-        constraint = payee_token.owner == stream.payee
+        seeds = [b"task", stream.task_creator.as_ref(), &stream.task_index.to_le_bytes()],
+        bump = stream.task_bump,
+        seeds::program = TASK_MARKET_PROGRAM_ID
     )]
-    pub payee_token: Account<'info, TokenAccount>,
+    pub task_authority: Signer<'info>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct ControlStream<'info> {
+pub struct SetRateSchedule<'info> {
     #[account(
         mut,
-        constraint = stream.payer == authority.key() @ ErrorCode::Unauthorized
+        constraint = stream.payer == payer.key() @ ErrorCode::Unauthorized,
+        constraint = stream.task_id.is_none() @ ErrorCode::TaskControlledStream
     )]
     pub stream: Account<'info, PaymentStream>,
     
-    pub authority: Signer<'info>,
+    pub payer: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct TerminateStream<'info> {
+pub struct ManageMilestone<'info> {
     #[account(
         mut,
-        constraint = stream.payer == authority.key() || stream.payee == authority.key() @ ErrorCode::Unauthorized
+        constraint = stream.payer == payer.key() @ ErrorCode::Unauthorized,
+        constraint = stream.task_id.is_none() @ ErrorCode::TaskControlledStream
     )]
     pub stream: Account<'info, PaymentStream>,
     
+    pub payer: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseMilestone<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", stream.key().as_ref()],
+        bump = stream.escrow_bump
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Holder's claim NFT token account, if the stream has a claim NFT
+    pub claim_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = stream.current_payee(claim_token.as_deref()) == Some(payee_token.owner) @ ErrorCode::NotClaimHolder
+    )]
+    pub payee_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.mint == escrow.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseMilestoneByTask<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    
+    #[account(
+        mut,
+        constraint = stream.task_id == Some(task_authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub stream: Box<Account<'info, PaymentStream>>,
+    
     #[account(
         mut,
         seeds = [b"escrow", stream.key().as_ref()],
         bump = stream.escrow_bump
     )]
-    pub escrow: Account<'info, TokenAccount>,
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(address = escrow.mint)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(mut, constraint = payee_token.owner == stream.payee)]
+    pub payee_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidTreasury,
+        constraint = treasury.mint == escrow.mint @ ErrorCode::InvalidTreasury
+    )]
+    pub treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Task account, signing via task_market CPI
+    #[account(
+        seeds = [b"task", stream.task_creator.as_ref(), &stream.task_index.to_le_bytes()],
+        bump = stream.task_bump,
+        seeds::program = TASK_MARKET_PROGRAM_ID
+    )]
+    pub task_authority: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MintClaimNft<'info> {
+    #[account(mut, has_one = payer @ ErrorCode::Unauthorized, has_one = payee)]
+    pub stream: Account<'info, PaymentStream>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"claim_mint", stream.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = claim_mint,
+        mint::token_program = token_program,
+    )]
+    pub claim_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = claim_mint,
+        associated_token::authority = payee,
+        associated_token::token_program = token_program,
+    )]
+    pub payee_claim_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Checked against stream.payee
+    pub payee: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"treasury", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = config,
+        token::token_program = token_program,
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
     
-    #[account(mut, constraint = payer_token.owner == stream.payer)]
-    pub payer_token: Account<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
     
-    #[account(mut, constraint = payee_token.owner == stream.payee)]
-    pub payee_token: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
     
-    pub authority: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct TopUpEscrow<'info> {
-    #[account(mut)]
-    pub stream: Account<'info, PaymentStream>,
+pub struct WithdrawFees<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
     
     #[account(
         mut,
-        seeds = [b"escrow", stream.key().as_ref()],
-        bump = stream.escrow_bump
+        seeds = [b"treasury", treasury.mint.as_ref()],
+        bump
     )]
-    pub escrow: Account<'info, TokenAccount>,
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
     
-    #[account(mut, constraint = payer_token.owner == payer.key())]
-    pub payer_token: Account<'info, TokenAccount>,
+    #[account(mut, constraint = destination.mint == treasury.mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
     
-    #[account(constraint = payer.key() == stream.payer @ ErrorCode::Unauthorized)]
-    pub payer: Signer<'info>,
+    #[account(address = treasury.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 #[derive(Accounts)]
-pub struct CancelStream<'info> {
+pub struct RegisterReferrer<'info> {
     #[account(
-        mut,
-        constraint = stream.payer == payer.key() @ ErrorCode::Unauthorized
+        init,
+        payer = referrer,
+        space = 8 + Referrer::INIT_SPACE,
+        seeds = [b"referrer", referrer.key().as_ref()],
+        bump
     )]
-    pub stream: Account<'info, PaymentStream>,
+    pub referrer_account: Account<'info, Referrer>,
     
-    #[account(
-        mut,
-        seeds = [b"escrow", stream.key().as_ref()],
-        bump = stream.escrow_bump
-    )]
-    pub escrow: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub referrer: Signer<'info>,
     
-    #[account(mut, constraint = payer_token.owner == payer.key())]
-    pub payer_token: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
     
-    pub payer: Signer<'info>,
-    pub token_program: Program<'info, Token>,
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
+#[instruction(task_creator: Pubkey, task_index: u64)]
 pub struct LinkToTask<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = stream.payer == payer.key() @ ErrorCode::Unauthorized
+    )]
     pub stream: Account<'info, PaymentStream>,
     
-    /// CHECK: Verified by CPI caller
-    pub task_market_program: AccountInfo<'info>,
+    pub payer: Signer<'info>,
+    
+    /// Task account, signing via task_market CPI
+    #[account(
+        seeds = [b"task", task_creator.as_ref(), &task_index.to_le_bytes()],
+        bump,
+        seeds::program = TASK_MARKET_PROGRAM_ID
+    )]
+    pub task_authority: Signer<'info>,
 }
 
 // ============================================================================
@@ -589,9 +3953,36 @@ pub struct ProgramConfig {
     pub max_stream_duration: u32,
     pub total_streams: u64,
     pub total_volume: u64,
+    pub cranker_tip: CrankerTip,
+    /// Longest gap a single tick pays for in full; 0 for no limit
+    pub max_tick_interval: i64,
+    /// Shortest gap allowed between ticks of a stream; 0 for no limit
+    pub min_tick_interval: i64,
+    /// Share of the platform fee paid to a stream's referrer
+    pub referral_share_bps: u16,
+    /// Emergency stop: blocks create, start and tick while set
+    pub frozen: bool,
     pub bump: u8,
 }
 
+/// Tip paid from escrow to whoever submits a tick, out of the tick amount
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CrankerTip {
+    None,
+    Flat(u64),
+    BasisPoints(u16),
+}
+
+impl CrankerTip {
+    pub fn amount(&self, tick_amount: u64) -> Result<u64> {
+        Ok(match *self {
+            CrankerTip::None => 0,
+            CrankerTip::Flat(tip) => tip,
+            CrankerTip::BasisPoints(bps) => platform_fee(tick_amount, bps)?,
+        })
+    }
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct PaymentStream {
@@ -609,10 +4000,197 @@ pub struct PaymentStream {
     pub total_ticks: u32,
     pub escrow_balance: u64,
     pub task_id: Option<Pubkey>,
+    #[max_len(MAX_RATE_SEGMENTS)]
+    pub rate_schedule: Vec<RateSegment>,
+    pub mode: StreamMode,
+    #[max_len(MAX_MILESTONES)]
+    pub milestones: Vec<Milestone>,
+    /// When the stream entered Grace, or 0
+    pub grace_started_at: i64,
+    /// Accrued at pause but not yet paid; settled by the next tick
+    pub accrued_unpaid: u64,
+    /// Runway below which ticks emit LowEscrowWarning; 0 disables it
+    pub low_balance_threshold_seconds: u32,
+    /// Rate change awaiting the other party's acceptance
+    pub pending_rate: Option<RateProposal>,
+    /// Held in escrow on top of escrow_balance and never ticked out
+    pub security_deposit: u64,
+    /// Share of the platform fee waived, from the payer's stake at creation
+    pub fee_discount_bps: u16,
+    /// If set, only this key or the payee may tick
+    pub tick_authority: Option<Pubkey>,
+    /// Claim NFT whose holder is paid in place of `payee`
+    pub claim_mint: Option<Pubkey>,
+    /// Held in escrow apart from escrow_balance until paid out at start
+    pub activation_fee: u64,
+    /// Usage-report billing, for metered streams
+    pub metering: Option<UsageMetering>,
+    /// Accrual from beyond max_tick_interval, paid off over later ticks
+    pub arrears: u64,
+    /// Penalty if the payer terminates soon after start
+    pub early_termination: Option<EarlyTermination>,
+    /// Overrides config.min_tick_interval when set
+    pub min_tick_interval: Option<i64>,
+    /// Seconds after start before the first payout
+    pub cliff_seconds: i64,
+    /// Caller-chosen stream PDA seed; 0 for task streams
+    pub nonce: u64,
+    /// Registered referrer earning a share of platform fees
+    pub referrer: Option<Pubkey>,
+    /// PDA seeds of the linked task in task-market, checked whenever the
+    /// task signs for the stream
+    pub task_creator: Pubkey,
+    pub task_index: u64,
+    pub task_bump: u8,
+    pub event_seq: u64,
     pub escrow_bump: u8,
     pub bump: u8,
 }
 
+impl PaymentStream {
+    /// Who the stream pays: the holder of its claim NFT, as shown by
+    /// `claim_token`, or `payee` if it has none. `None` if the stream has a
+    /// claim NFT and `claim_token` doesn't hold it.
+    pub fn current_payee(&self, claim_token: Option<&TokenAccount>) -> Option<Pubkey> {
+        match (self.claim_mint, claim_token) {
+            (None, _) => Some(self.payee),
+            (Some(claim_mint), Some(token)) if token.mint == claim_mint && token.amount == 1 => {
+                Some(token.owner)
+            }
+            _ => None,
+        }
+    }
+
+    /// The linked task, if its key is the task-market PDA for the stored
+    /// task seeds
+    pub fn task_authority(&self) -> Option<Pubkey> {
+        let task = self.task_id?;
+        let derived = Pubkey::create_program_address(
+            &[
+                b"task",
+                self.task_creator.as_ref(),
+                &self.task_index.to_le_bytes(),
+                &[self.task_bump],
+            ],
+            &TASK_MARKET_PROGRAM_ID,
+        )
+        .ok()?;
+        (derived == task).then_some(task)
+    }
+}
+
+/// Reusable stream terms, for payers who open many identical streams
+#[account]
+#[derive(InitSpace)]
+pub struct StreamTemplate {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub template_id: u64,
+    pub rate_per_second: u64,
+    pub max_duration: i64,
+    pub grace_period: i64,
+    pub auto_terminate: bool,
+    pub streams_created: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+/// Every stream a wallet has opened as payer, or been opened to as payee,
+/// for on-chain discovery. Grows by one entry per stream.
+#[account]
+pub struct StreamRegistry {
+    pub owner: Pubkey,
+    pub bump: u8,
+    pub streams: Vec<Pubkey>,
+}
+
+impl StreamRegistry {
+    /// Account size holding `len` streams
+    pub const fn space(len: usize) -> usize {
+        8 + 32 + 1 + 4 + 32 * len
+    }
+}
+
+/// A referrer's running totals across every stream it referred. Earnings
+/// are summed in base units regardless of mint.
+#[account]
+#[derive(InitSpace)]
+pub struct Referrer {
+    pub referrer: Pubkey,
+    pub streams_referred: u64,
+    pub total_earned: u64,
+    pub bump: u8,
+}
+
+/// Running total of one sponsor's contributions to a stream's escrow
+#[account]
+#[derive(InitSpace)]
+pub struct Sponsorship {
+    pub stream: Pubkey,
+    pub sponsor: Pubkey,
+    pub total_contributed: u64,
+    pub last_contributed_at: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct RateSegment {
+    /// Seconds after `started_at` this rate takes effect
+    pub start_offset: i64,
+    pub rate_per_second: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct UsageMetering {
+    /// identity_registry Robot that signs reports
+    pub robot: Pubkey,
+    /// The robot's device_id, as an ed25519 public key
+    pub device_key: Pubkey,
+    pub rate_per_unit: u64,
+    /// Reports billed so far; each report signs the next number
+    pub reports: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct EarlyTermination {
+    /// Seconds after start during which payer termination is penalised
+    pub min_runtime: i64,
+    /// Share of the unstreamed escrow paid to the payee
+    pub penalty_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct RateProposal {
+    pub rate_per_second: u64,
+    pub proposed_by: Pubkey,
+    pub proposed_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct Milestone {
+    pub amount: u64,
+    /// Hash of the off-chain milestone spec
+    pub metadata_hash: [u8; 32],
+    pub status: MilestoneStatus,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum MilestoneStatus {
+    Pending,
+    Approved,
+    Released,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum StreamMode {
+    /// Paid per second by `tick`
+    Continuous,
+    /// Paid per approved milestone
+    Milestone,
+    /// Paid per unit in signed usage reports by `tick_usage`
+    Metered,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
 pub enum StreamStatus {
     Pending,
@@ -621,6 +4199,10 @@ pub enum StreamStatus {
     Completed,
     Cancelled,
     Disputed,
+    /// Escrow ran short; top up within `grace_period` or the stream terminates
+    Grace,
+    /// Payee agreed to the terms; ready to start
+    Accepted,
 }
 
 // ============================================================================
@@ -629,58 +4211,244 @@ pub enum StreamStatus {
 
 #[event]
 pub struct StreamCreated {
+    pub header: EventHeader,
     pub stream: Pubkey,
     pub payer: Pubkey,
     pub payee: Pubkey,
     pub rate_per_second: u64,
     pub escrow_amount: u64,
     pub timestamp: i64,
+    pub fee_discount_bps: u16,
+}
+
+#[event]
+pub struct StreamTemplateCreated {
+    pub header: EventHeader,
+    pub template: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub rate_per_second: u64,
+    pub max_duration: i64,
 }
 
 #[event]
 pub struct StreamStarted {
+    pub header: EventHeader,
     pub stream: Pubkey,
     pub started_at: i64,
+    pub activation_fee: u64,
 }
 
 #[event]
 pub struct StreamTick {
+    pub header: EventHeader,
     pub stream: Pubkey,
     pub tick_number: u32,
     pub amount: u64,
     pub total_paid: u64,
     pub escrow_remaining: u64,
     pub timestamp: i64,
+    pub fee: u64,
+    pub tip: u64,
+    pub referral: u64,
+}
+
+#[event]
+pub struct UsageReported {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub report_number: u64,
+    pub units: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StreamStale {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub stale_seconds: i64,
+    pub moved_to_arrears: u64,
+    pub arrears: u64,
+}
+
+#[event]
+pub struct LowEscrowWarning {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub escrow_balance: u64,
+    /// Seconds of escrow left at the current rate
+    pub remaining_seconds: u64,
+}
+
+#[event]
+pub struct StreamGraceStarted {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub amount_due: u64,
+    pub escrow_balance: u64,
+    pub grace_ends_at: i64,
+}
+
+#[event]
+pub struct StreamRescued {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub escrow_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamExtended {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub max_duration: i64,
+    pub amount: u64,
+    pub new_balance: u64,
+}
+
+#[event]
+pub struct EscrowSponsored {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub sponsor: Pubkey,
+    pub amount: u64,
+    pub total_contributed: u64,
+    pub new_balance: u64,
+}
+
+#[event]
+pub struct ClaimNftMinted {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub claim_mint: Pubkey,
+    pub holder: Pubkey,
+}
+
+#[event]
+pub struct PayeeTransferred {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub old_payee: Pubkey,
+    pub new_payee: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamClosed {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RateScheduleSet {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub segments: Vec<RateSegment>,
+}
+
+#[event]
+pub struct RateChangeProposed {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub proposed_by: Pubkey,
+    pub rate_per_second: u64,
+}
+
+#[event]
+pub struct RateChangeAccepted {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub old_rate: u64,
+    pub new_rate: u64,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct MilestoneAdded {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+    pub metadata_hash: [u8; 32],
+}
+
+#[event]
+pub struct MilestoneApproved {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub index: u8,
+}
+
+#[event]
+pub struct MilestoneReleased {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+    pub fee: u64,
+    pub escrow_remaining: u64,
+}
+
+#[event]
+pub struct StreamFeeDiscountSet {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub fee_discount_bps: u16,
 }
 
 #[event]
 pub struct StreamPaused {
+    pub header: EventHeader,
     pub stream: Pubkey,
     pub timestamp: i64,
+    pub accrued_unpaid: u64,
 }
 
 #[event]
 pub struct StreamResumed {
+    pub header: EventHeader,
     pub stream: Pubkey,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct StreamTerminated {
+    pub header: EventHeader,
     pub stream: Pubkey,
     pub reason: String,
     pub total_paid: u64,
     pub timestamp: i64,
+    pub deposit_refunded: u64,
+    pub deposit_forfeited: u64,
+    pub early_termination_fee: u64,
+}
+
+#[event]
+pub struct StreamAccepted {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub payee: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamRejected {
+    pub header: EventHeader,
+    pub stream: Pubkey,
+    pub refunded: u64,
 }
 
 #[event]
 pub struct StreamCancelled {
+    pub header: EventHeader,
     pub stream: Pubkey,
     pub refunded: u64,
 }
 
 #[event]
 pub struct EscrowToppedUp {
+    pub header: EventHeader,
     pub stream: Pubkey,
     pub amount: u64,
     pub new_balance: u64,
@@ -730,4 +4498,152 @@ pub enum ErrorCode {
     
     #[msg("Arithmetic overflow")]
     Overflow,
+    
+    #[msg("Treasury must be a config-owned account for the stream's mint")]
+    InvalidTreasury,
+    
+    #[msg("Cranker tip cannot exceed 100%")]
+    InvalidCrankerTip,
+    
+    #[msg("Remaining accounts must be (stream, escrow, payee_token) triples")]
+    InvalidTickBatch,
+    
+    #[msg("Stream is in its grace period")]
+    GracePeriodActive,
+    
+    #[msg("Only completed or cancelled streams can be closed")]
+    StreamNotFinished,
+    
+    #[msg("Escrow still holds funds")]
+    EscrowNotEmpty,
+    
+    #[msg("No rate change is pending")]
+    NoRateProposal,
+    
+    #[msg("Too many rate segments")]
+    TooManyRateSegments,
+    
+    #[msg("Rate segments must have positive rates and increasing offsets")]
+    InvalidRateSchedule,
+    
+    #[msg("Milestone streams are not paid per second")]
+    NotContinuousStream,
+    
+    #[msg("Too many milestones")]
+    TooManyMilestones,
+    
+    #[msg("Invalid milestone amount")]
+    InvalidMilestoneAmount,
+    
+    #[msg("Milestone does not exist")]
+    InvalidMilestone,
+    
+    #[msg("Milestone is not pending")]
+    MilestoneNotPending,
+    
+    #[msg("Milestone is not approved")]
+    MilestoneNotApproved,
+    
+    #[msg("Only the stream's tick authority or payee may tick it")]
+    UnauthorizedCranker,
+    
+    #[msg("Payee token account is not owned by the claim NFT holder")]
+    NotClaimHolder,
+    
+    #[msg("Stream has a claim NFT; transfer the NFT instead")]
+    ClaimNftIssued,
+    
+    #[msg("Stream is not billed by usage reports")]
+    NotMeteredStream,
+    
+    #[msg("Usage report signature missing or invalid")]
+    InvalidUsageReport,
+    
+    #[msg("Program is frozen")]
+    ProgramFrozen,
+    
+    #[msg("Program is not frozen")]
+    ProgramNotFrozen,
+    
+    #[msg("Invalid early termination terms")]
+    InvalidEarlyTermination,
+    
+    #[msg("Minimum tick interval has not elapsed")]
+    TickTooSoon,
+    
+    #[msg("Stream has not been accepted by the payee")]
+    StreamNotAccepted,
+    
+    #[msg("Stream cliff has not ended")]
+    CliffNotReached,
+    
+    #[msg("Referrer accounts missing or invalid")]
+    InvalidReferrer,
+    
+    #[msg("Referral share cannot exceed 100%")]
+    InvalidReferralShare,
+    
+    #[msg("Milestone shares must be non-zero and total 100%")]
+    InvalidMilestoneShares,
+    
+    #[msg("Cancellation fee cannot exceed 100%")]
+    InvalidCancellationFee,
+    
+    #[msg("Fee discount cannot exceed 100%")]
+    InvalidFeeDiscount,
+    
+    #[msg("Stream is controlled by its task")]
+    TaskControlledStream,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fee charged on a payout by a payer naming a position of `staked`
+    /// DRONEOS base units
+    fn charged_fee(staked: u64, fee_basis_points: u16, payout: u64) -> u64 {
+        let stake = StakeAccount {
+            version: 2,
+            owner: Pubkey::new_unique(),
+            index: 0,
+            amount: staked,
+            staked_at: 0,
+            lock_duration: 0,
+            lock_until: 0,
+            multiplier: 10_000,
+            accumulated_rewards: 0,
+            last_claim_at: 0,
+            settled_rewards: 0,
+            receipt_mint: None,
+            auto_relock: false,
+            boost_robot: None,
+            reputation_boost_bps: 0,
+            event_seq: 0,
+            bump: 255,
+        };
+        let discount_bps = droneos_token::fee_tier_discount(droneos_token::fee_tier(&stake));
+        platform_fee(payout, discounted_fee_basis_points(fee_basis_points, discount_bps)).unwrap()
+    }
+
+    #[test]
+    fn staker_discounts_start_at_each_fee_tier_threshold() {
+        const DRONEOS: u64 = 1_000_000;
+        let payout = 1_000_000_000;
+        // 100 bps undiscounted
+        assert_eq!(charged_fee(0, 100, payout), 10_000_000);
+        assert_eq!(charged_fee(10_000 * DRONEOS - 1, 100, payout), 10_000_000);
+        assert_eq!(charged_fee(10_000 * DRONEOS, 100, payout), 5_000_000);
+        assert_eq!(charged_fee(100_000 * DRONEOS - 1, 100, payout), 5_000_000);
+        assert_eq!(charged_fee(100_000 * DRONEOS, 100, payout), 2_500_000);
+    }
+
+    #[test]
+    fn discounted_fee_rates_round_down() {
+        // The default 10 bps at the top tier's 75% discount is 2.5 bps
+        assert_eq!(discounted_fee_basis_points(10, 7_500), 2);
+        assert_eq!(discounted_fee_basis_points(10, 5_000), 5);
+        assert_eq!(discounted_fee_basis_points(10, 0), 10);
+        assert_eq!(discounted_fee_basis_points(10, 10_000), 0);
+    }
 }
@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::TokenInterface;
+use droneos_events::{EventHeader, ProgramTag};
+use droneos_token::program::DroneosToken;
 
 declare_id!("DOS4swm1111111111111111111111111111111111111");
 
@@ -21,15 +24,26 @@ pub mod swarm_coordinator {
         coordinator.authority = ctx.accounts.authority.key();
         coordinator.total_swarms = 0;
         coordinator.total_group_tasks = 0;
+        coordinator.swarm_bond_bps = 0;
+        coordinator.event_seq = 0;
         coordinator.bump = ctx.bumps.coordinator;
         
-        emit!(CoordinatorInitialized {
+        emit_cpi!(CoordinatorInitialized {
+            header: event_header(coordinator.key(), &mut coordinator.event_seq, Clock::get()?.unix_timestamp),
             authority: coordinator.authority,
         });
         
         Ok(())
     }
 
+    /// Set the swarm bond required to win a group task, in bps of its total
+    /// reward (by authority). Zero disables the requirement.
+    pub fn set_swarm_bond_bps(ctx: Context<UpdateCoordinator>, swarm_bond_bps: u16) -> Result<()> {
+        require!(swarm_bond_bps <= 10_000, ErrorCode::InvalidBondBps);
+        ctx.accounts.coordinator.swarm_bond_bps = swarm_bond_bps;
+        Ok(())
+    }
+
     /// Create a swarm (group of robots)
     pub fn create_swarm(
         ctx: Context<CreateSwarm>,
@@ -37,7 +51,7 @@ pub mod swarm_coordinator {
         max_robots: u8,
         min_reputation: u16,
     ) -> Result<()> {
-        require!(max_robots >= 2 && max_robots <= 20, ErrorCode::InvalidSwarmSize);
+        require!((2..=20).contains(&max_robots), ErrorCode::InvalidSwarmSize);
         require!(name.len() <= 32, ErrorCode::NameTooLong);
         
         let swarm = &mut ctx.accounts.swarm;
@@ -50,12 +64,14 @@ pub mod swarm_coordinator {
         swarm.total_tasks_completed = 0;
         swarm.total_earned = 0;
         swarm.created_at = Clock::get()?.unix_timestamp;
+        swarm.event_seq = 0;
         swarm.bump = ctx.bumps.swarm;
         
         let coordinator = &mut ctx.accounts.coordinator;
         coordinator.total_swarms += 1;
         
-        emit!(SwarmCreated {
+        emit_cpi!(SwarmCreated {
+            header: event_header(swarm.key(), &mut swarm.event_seq, Clock::get()?.unix_timestamp),
             swarm: swarm.key(),
             leader: swarm.leader,
             max_robots,
@@ -81,6 +97,7 @@ pub mod swarm_coordinator {
         membership.joined_at = Clock::get()?.unix_timestamp;
         membership.tasks_completed = 0;
         membership.contribution_score = 100; // Base score
+        membership.event_seq = 0;
         membership.bump = ctx.bumps.membership;
         
         swarm.current_robots += 1;
@@ -90,7 +107,8 @@ pub mod swarm_coordinator {
             swarm.status = SwarmStatus::Active;
         }
         
-        emit!(RobotJoinedSwarm {
+        emit_cpi!(RobotJoinedSwarm {
+            header: event_header(swarm.key(), &mut swarm.event_seq, Clock::get()?.unix_timestamp),
             swarm: swarm.key(),
             robot: membership.robot,
             operator: membership.operator,
@@ -108,32 +126,65 @@ pub mod swarm_coordinator {
         total_reward: u64,
         duration_seconds: i64,
     ) -> Result<()> {
-        require!(required_robots >= 2 && required_robots <= 20, ErrorCode::InvalidRobotCount);
-        require!(title.len() <= 64, ErrorCode::TitleTooLong);
-        require!(description.len() <= 256, ErrorCode::DescriptionTooLong);
-        require!(total_reward > 0, ErrorCode::InvalidReward);
-        
         let task = &mut ctx.accounts.group_task;
-        task.creator = ctx.accounts.creator.key();
-        task.title = title;
-        task.description = description;
-        task.required_robots = required_robots;
-        task.current_robots = 0;
-        task.total_reward = total_reward;
-        task.reward_per_robot = total_reward / required_robots as u64;
-        task.duration_seconds = duration_seconds;
-        task.status = GroupTaskStatus::Open;
-        task.created_at = Clock::get()?.unix_timestamp;
-        task.bump = ctx.bumps.group_task;
+        open_group_task(
+            task,
+            &mut ctx.accounts.coordinator,
+            ctx.accounts.creator.key(),
+            title,
+            description,
+            required_robots,
+            total_reward,
+            duration_seconds,
+            ctx.bumps.group_task,
+            None,
+        )?;
         
-        let coordinator = &mut ctx.accounts.coordinator;
-        coordinator.total_group_tasks += 1;
+        emit_cpi!(GroupTaskCreated {
+            header: event_header(task.key(), &mut task.event_seq, Clock::get()?.unix_timestamp),
+            task: task.key(),
+            creator: task.creator,
+            required_robots,
+            total_reward,
+            market_task: None,
+        });
+        
+        Ok(())
+    }
+
+    /// Create a group task for a task-market task (signed by the market
+    /// task's PDA), linking the two so swarm jobs can be published through
+    /// the marketplace
+    pub fn create_market_group_task(
+        ctx: Context<CreateMarketGroupTask>,
+        title: String,
+        description: String,
+        required_robots: u8,
+        total_reward: u64,
+        duration_seconds: i64,
+    ) -> Result<()> {
+        let task = &mut ctx.accounts.group_task;
+        let market_task = ctx.accounts.market_task.key();
+        open_group_task(
+            task,
+            &mut ctx.accounts.coordinator,
+            ctx.accounts.creator.key(),
+            title,
+            description,
+            required_robots,
+            total_reward,
+            duration_seconds,
+            ctx.bumps.group_task,
+            Some(market_task),
+        )?;
         
-        emit!(GroupTaskCreated {
+        emit_cpi!(GroupTaskCreated {
+            header: event_header(task.key(), &mut task.event_seq, Clock::get()?.unix_timestamp),
             task: task.key(),
             creator: task.creator,
             required_robots,
             total_reward,
+            market_task: Some(market_task),
         });
         
         Ok(())
@@ -141,7 +192,7 @@ pub mod swarm_coordinator {
 
     /// Swarm bids on group task (collective bid)
     pub fn swarm_bid(
-        ctx: Context<SwarmBid>,
+        ctx: Context<SubmitSwarmBid>,
         proposed_rate: u64,
         estimated_duration: i64,
     ) -> Result<()> {
@@ -160,9 +211,11 @@ pub mod swarm_coordinator {
         bid.total_cost = proposed_rate * estimated_duration as u64;
         bid.status = BidStatus::Pending;
         bid.submitted_at = Clock::get()?.unix_timestamp;
+        bid.event_seq = 0;
         bid.bump = ctx.bumps.bid;
         
-        emit!(SwarmBidSubmitted {
+        emit_cpi!(SwarmBidSubmitted {
+            header: event_header(bid.key(), &mut bid.event_seq, Clock::get()?.unix_timestamp),
             bid: bid.key(),
             swarm: swarm.key(),
             task: task.key(),
@@ -172,7 +225,9 @@ pub mod swarm_coordinator {
         Ok(())
     }
 
-    /// Accept swarm bid and assign task
+    /// Accept swarm bid and assign task. The swarm's droneos_token bond must
+    /// cover `swarm_bond_bps` of the task's reward; that much is held
+    /// against the task and slashed if it fails.
     pub fn accept_swarm_bid(ctx: Context<AcceptSwarmBid>) -> Result<()> {
         let task = &mut ctx.accounts.group_task;
         let bid = &mut ctx.accounts.bid;
@@ -181,6 +236,23 @@ pub mod swarm_coordinator {
         require!(task.status == GroupTaskStatus::Open, ErrorCode::TaskNotOpen);
         require!(bid.status == BidStatus::Pending, ErrorCode::BidNotPending);
         
+        let required = (task.total_reward as u128 * ctx.accounts.coordinator.swarm_bond_bps as u128 / 10_000) as u64;
+        if required > 0 {
+            let bond = if ctx.accounts.swarm_stake.owner == &droneos_token::ID {
+                droneos_token::cpi::get_swarm_bond(CpiContext::new(
+                    ctx.accounts.droneos_token_program.to_account_info(),
+                    droneos_token::cpi::accounts::ReadSwarmStake {
+                        swarm_stake: ctx.accounts.swarm_stake.to_account_info(),
+                    },
+                ))?
+                .get()
+            } else {
+                0
+            };
+            require!(bond >= required, ErrorCode::InsufficientSwarmBond);
+        }
+        task.bonded_amount = required;
+        
         bid.status = BidStatus::Accepted;
         task.status = GroupTaskStatus::InProgress;
         task.assigned_swarm = Some(swarm.key());
@@ -188,7 +260,8 @@ pub mod swarm_coordinator {
         
         // TODO: Initialize payment streams for all swarm members via CPI
         
-        emit!(SwarmBidAccepted {
+        emit_cpi!(SwarmBidAccepted {
+            header: event_header(task.key(), &mut task.event_seq, Clock::get()?.unix_timestamp),
             task: task.key(),
             swarm: swarm.key(),
             bid: bid.key(),
@@ -210,7 +283,8 @@ pub mod swarm_coordinator {
         swarm.total_tasks_completed += 1;
         swarm.total_earned += task.total_reward;
         
-        emit!(GroupTaskCompleted {
+        emit_cpi!(GroupTaskCompleted {
+            header: event_header(task.key(), &mut task.event_seq, Clock::get()?.unix_timestamp),
             task: task.key(),
             swarm: swarm.key(),
             total_reward: task.total_reward,
@@ -219,6 +293,66 @@ pub mod swarm_coordinator {
         Ok(())
     }
 
+    /// Fail an overdue group task (by its creator), slashing the bond held
+    /// against it from the swarm's droneos_token stake. Swarm-coordinator
+    /// must be a registered slasher program.
+    pub fn fail_group_task(ctx: Context<FailGroupTask>, reason: String) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let task = &mut ctx.accounts.group_task;
+        
+        require!(task.status == GroupTaskStatus::InProgress, ErrorCode::TaskNotInProgress);
+        let started_at = task.started_at.ok_or(ErrorCode::TaskNotInProgress)?;
+        require!(now >= started_at + task.duration_seconds, ErrorCode::TaskNotOverdue);
+        
+        task.status = GroupTaskStatus::Failed;
+        task.completed_at = Some(now);
+        
+        let bond = if ctx.accounts.swarm_stake.owner == &droneos_token::ID {
+            droneos_token::SwarmStake::try_deserialize(
+                &mut &ctx.accounts.swarm_stake.try_borrow_data()?[..],
+            )?
+            .amount
+        } else {
+            0
+        };
+        let slashed = task.bonded_amount.min(bond);
+        
+        if slashed > 0 {
+            let seeds = &[b"slasher".as_ref(), &[ctx.bumps.slasher]];
+            droneos_token::cpi::slash_swarm_stake(
+                CpiContext::new_with_signer(
+                    ctx.accounts.droneos_token_program.to_account_info(),
+                    droneos_token::cpi::accounts::SlashSwarmStake {
+                        config: ctx.accounts.token_config.to_account_info(),
+                        swarm_stake: ctx.accounts.swarm_stake.to_account_info(),
+                        swarm_vault: ctx.accounts.swarm_vault.to_account_info(),
+                        treasury: ctx.accounts.treasury.to_account_info(),
+                        insurance_pool: ctx.accounts.insurance_pool.to_account_info(),
+                        insurance_vault: ctx.accounts.insurance_vault.to_account_info(),
+                        slasher_program: ctx.accounts.program.to_account_info(),
+                        authority: ctx.accounts.slasher.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        token_program: ctx.accounts.token_program.to_account_info(),
+                        event_authority: ctx.accounts.token_event_authority.to_account_info(),
+                        program: ctx.accounts.droneos_token_program.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                slashed,
+                reason,
+            )?;
+        }
+        
+        emit_cpi!(GroupTaskFailed {
+            header: event_header(task.key(), &mut task.event_seq, now),
+            task: task.key(),
+            swarm: ctx.accounts.swarm.key(),
+            slashed,
+        });
+        
+        Ok(())
+    }
+
     /// Distribute rewards to swarm members based on contribution
     pub fn distribute_rewards(ctx: Context<DistributeRewards>) -> Result<()> {
         let task = &ctx.accounts.group_task;
@@ -227,15 +361,17 @@ pub mod swarm_coordinator {
         require!(task.status == GroupTaskStatus::Completed, ErrorCode::TaskNotCompleted);
         
         // Calculate reward based on contribution score
-        let base_reward = task.reward_per_robot;
-        let contribution_multiplier = membership.contribution_score as u64;
-        let final_reward = (base_reward * contribution_multiplier) / 100;
+        let final_reward = task.reward_per_robot
+            .checked_mul(membership.contribution_score as u64)
+            .ok_or(ErrorCode::Overflow)?
+            / 100;
         
         // TODO: Transfer tokens via CPI
         
         membership.tasks_completed += 1;
         
-        emit!(RewardDistributed {
+        emit_cpi!(RewardDistributed {
+            header: event_header(membership.key(), &mut membership.event_seq, Clock::get()?.unix_timestamp),
             task: task.key(),
             robot: membership.robot,
             amount: final_reward,
@@ -245,6 +381,50 @@ pub mod swarm_coordinator {
     }
 }
 
+// Helpers
+
+fn event_header(entity: Pubkey, seq: &mut u64, timestamp: i64) -> EventHeader {
+    EventHeader::next(ProgramTag::SwarmCoordinator, entity, seq, timestamp)
+}
+
+/// Validate and initialize a new open group task
+#[allow(clippy::too_many_arguments)]
+fn open_group_task(
+    task: &mut GroupTask,
+    coordinator: &mut Coordinator,
+    creator: Pubkey,
+    title: String,
+    description: String,
+    required_robots: u8,
+    total_reward: u64,
+    duration_seconds: i64,
+    bump: u8,
+    market_task: Option<Pubkey>,
+) -> Result<()> {
+    require!((2..=20).contains(&required_robots), ErrorCode::InvalidRobotCount);
+    require!(title.len() <= 64, ErrorCode::TitleTooLong);
+    require!(description.len() <= 256, ErrorCode::DescriptionTooLong);
+    require!(total_reward > 0, ErrorCode::InvalidReward);
+    
+    task.creator = creator;
+    task.title = title;
+    task.description = description;
+    task.required_robots = required_robots;
+    task.current_robots = 0;
+    task.total_reward = total_reward;
+    task.reward_per_robot = total_reward / required_robots as u64;
+    task.duration_seconds = duration_seconds;
+    task.status = GroupTaskStatus::Open;
+    task.created_at = Clock::get()?.unix_timestamp;
+    task.bonded_amount = 0;
+    task.market_task = market_task;
+    task.event_seq = 0;
+    task.bump = bump;
+    
+    coordinator.total_group_tasks += 1;
+    Ok(())
+}
+
 // Account Structures
 
 #[account]
@@ -252,6 +432,8 @@ pub struct Coordinator {
     pub authority: Pubkey,
     pub total_swarms: u64,
     pub total_group_tasks: u64,
+    pub swarm_bond_bps: u16, // bond required per group task, in bps of its reward
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -266,6 +448,7 @@ pub struct Swarm {
     pub total_tasks_completed: u64,
     pub total_earned: u64,
     pub created_at: i64,
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -277,6 +460,7 @@ pub struct SwarmMembership {
     pub joined_at: i64,
     pub tasks_completed: u32,
     pub contribution_score: u16, // 0-200, base 100
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -295,6 +479,9 @@ pub struct GroupTask {
     pub created_at: i64,
     pub started_at: Option<i64>,
     pub completed_at: Option<i64>,
+    pub bonded_amount: u64, // swarm bond slashed if the task fails
+    pub market_task: Option<Pubkey>, // task-market task this was created for
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -307,6 +494,7 @@ pub struct SwarmBid {
     pub total_cost: u64,
     pub status: BidStatus,
     pub submitted_at: i64,
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -325,6 +513,7 @@ pub enum GroupTaskStatus {
     InProgress,
     Completed,
     Cancelled,
+    Failed,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq)]
@@ -336,12 +525,13 @@ pub enum BidStatus {
 
 // Context Structs (simplified)
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 8 + 8 + 1,
+        space = 8 + 32 + 8 + 8 + 2 + 8 + 1,
         seeds = [b"coordinator"],
         bump
     )]
@@ -351,6 +541,15 @@ pub struct Initialize<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateCoordinator<'info> {
+    #[account(mut, seeds = [b"coordinator"], bump = coordinator.bump)]
+    pub coordinator: Account<'info, Coordinator>,
+    #[account(constraint = authority.key() == coordinator.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CreateSwarm<'info> {
     #[account(mut)]
@@ -358,7 +557,7 @@ pub struct CreateSwarm<'info> {
     #[account(
         init,
         payer = leader,
-        space = 8 + 32 + 36 + 1 + 1 + 2 + 1 + 8 + 8 + 8 + 1,
+        space = 8 + 32 + 36 + 1 + 1 + 2 + 1 + 8 + 8 + 8 + 8 + 1,
         seeds = [b"swarm", leader.key().as_ref()],
         bump
     )]
@@ -368,6 +567,7 @@ pub struct CreateSwarm<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct JoinSwarm<'info> {
     #[account(mut)]
@@ -375,7 +575,7 @@ pub struct JoinSwarm<'info> {
     #[account(
         init,
         payer = operator,
-        space = 8 + 32 + 32 + 32 + 8 + 4 + 2 + 1,
+        space = 8 + 32 + 32 + 32 + 8 + 4 + 2 + 8 + 1,
         seeds = [b"membership", swarm.key().as_ref(), robot.key().as_ref()],
         bump
     )]
@@ -387,6 +587,7 @@ pub struct JoinSwarm<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CreateGroupTask<'info> {
     #[account(mut)]
@@ -394,7 +595,7 @@ pub struct CreateGroupTask<'info> {
     #[account(
         init,
         payer = creator,
-        space = 8 + 32 + 68 + 260 + 1 + 1 + 8 + 8 + 8 + 1 + 33 + 8 + 9 + 9 + 1,
+        space = 8 + 32 + 68 + 260 + 1 + 1 + 8 + 8 + 8 + 1 + 33 + 8 + 9 + 9 + 8 + 33 + 8 + 1,
         seeds = [b"group-task", creator.key().as_ref(), &coordinator.total_group_tasks.to_le_bytes()],
         bump
     )]
@@ -404,14 +605,36 @@ pub struct CreateGroupTask<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct SwarmBid<'info> {
+pub struct CreateMarketGroupTask<'info> {
+    #[account(mut, seeds = [b"coordinator"], bump = coordinator.bump)]
+    pub coordinator: Account<'info, Coordinator>,
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + 32 + 68 + 260 + 1 + 1 + 8 + 8 + 8 + 1 + 33 + 8 + 9 + 9 + 8 + 33 + 8 + 1,
+        seeds = [b"group-task", creator.key().as_ref(), &coordinator.total_group_tasks.to_le_bytes()],
+        bump
+    )]
+    pub group_task: Account<'info, GroupTask>,
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    /// task-market task PDA the group task is created for
+    #[account(owner = task_market::ID)]
+    pub market_task: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SubmitSwarmBid<'info> {
     pub swarm: Account<'info, Swarm>,
     pub group_task: Account<'info, GroupTask>,
     #[account(
         init,
         payer = leader,
-        space = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 8 + 1,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 8 + 8 + 1,
         seeds = [b"swarm-bid", group_task.key().as_ref(), swarm.key().as_ref()],
         bump
     )]
@@ -421,6 +644,7 @@ pub struct SwarmBid<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct AcceptSwarmBid<'info> {
     #[account(mut)]
@@ -429,8 +653,52 @@ pub struct AcceptSwarmBid<'info> {
     pub bid: Account<'info, SwarmBid>,
     pub swarm: Account<'info, Swarm>,
     pub creator: Signer<'info>,
+    #[account(seeds = [b"coordinator"], bump = coordinator.bump)]
+    pub coordinator: Account<'info, Coordinator>,
+    /// CHECK: The swarm's droneos_token bond, if it has one
+    #[account(seeds = [b"swarm-stake", swarm.key().as_ref()], bump, seeds::program = droneos_token::ID)]
+    pub swarm_stake: AccountInfo<'info>,
+    pub droneos_token_program: Program<'info, DroneosToken>,
 }
 
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FailGroupTask<'info> {
+    #[account(mut, has_one = creator @ ErrorCode::Unauthorized)]
+    pub group_task: Account<'info, GroupTask>,
+    #[account(constraint = group_task.assigned_swarm == Some(swarm.key()) @ ErrorCode::WrongSwarm)]
+    pub swarm: Account<'info, Swarm>,
+    pub creator: Signer<'info>,
+    /// CHECK: The swarm's droneos_token bond, if it has one
+    #[account(mut, seeds = [b"swarm-stake", swarm.key().as_ref()], bump, seeds::program = droneos_token::ID)]
+    pub swarm_stake: AccountInfo<'info>,
+    /// CHECK: This program's slasher PDA, signing the slash CPI
+    #[account(seeds = [b"slasher"], bump)]
+    pub slasher: AccountInfo<'info>,
+    /// CHECK: droneos_token config, validated by droneos_token
+    #[account(mut)]
+    pub token_config: AccountInfo<'info>,
+    /// CHECK: Vault holding the swarm bond, validated by droneos_token
+    #[account(mut)]
+    pub swarm_vault: AccountInfo<'info>,
+    /// CHECK: droneos_token treasury, validated by droneos_token
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+    /// CHECK: droneos_token insurance pool, validated by droneos_token
+    #[account(mut)]
+    pub insurance_pool: AccountInfo<'info>,
+    /// CHECK: Insurance pool vault, validated by droneos_token
+    #[account(mut)]
+    pub insurance_vault: AccountInfo<'info>,
+    /// CHECK: DRONEOS mint, validated by droneos_token
+    pub mint: AccountInfo<'info>,
+    /// CHECK: droneos_token event authority
+    pub token_event_authority: AccountInfo<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub droneos_token_program: Program<'info, DroneosToken>,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 pub struct CompleteGroupTask<'info> {
     #[account(mut)]
@@ -440,6 +708,7 @@ pub struct CompleteGroupTask<'info> {
     pub leader: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct DistributeRewards<'info> {
     pub group_task: Account<'info, GroupTask>,
@@ -453,11 +722,13 @@ pub struct DistributeRewards<'info> {
 
 #[event]
 pub struct CoordinatorInitialized {
+    pub header: EventHeader,
     pub authority: Pubkey,
 }
 
 #[event]
 pub struct SwarmCreated {
+    pub header: EventHeader,
     pub swarm: Pubkey,
     pub leader: Pubkey,
     pub max_robots: u8,
@@ -465,6 +736,7 @@ pub struct SwarmCreated {
 
 #[event]
 pub struct RobotJoinedSwarm {
+    pub header: EventHeader,
     pub swarm: Pubkey,
     pub robot: Pubkey,
     pub operator: Pubkey,
@@ -472,14 +744,17 @@ pub struct RobotJoinedSwarm {
 
 #[event]
 pub struct GroupTaskCreated {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub creator: Pubkey,
     pub required_robots: u8,
     pub total_reward: u64,
+    pub market_task: Option<Pubkey>,
 }
 
 #[event]
 pub struct SwarmBidSubmitted {
+    pub header: EventHeader,
     pub bid: Pubkey,
     pub swarm: Pubkey,
     pub task: Pubkey,
@@ -488,6 +763,7 @@ pub struct SwarmBidSubmitted {
 
 #[event]
 pub struct SwarmBidAccepted {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub swarm: Pubkey,
     pub bid: Pubkey,
@@ -495,13 +771,24 @@ pub struct SwarmBidAccepted {
 
 #[event]
 pub struct GroupTaskCompleted {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub swarm: Pubkey,
     pub total_reward: u64,
 }
 
+#[event]
+pub struct GroupTaskFailed {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub swarm: Pubkey,
+    /// Swarm bond slashed
+    pub slashed: u64,
+}
+
 #[event]
 pub struct RewardDistributed {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub robot: Pubkey,
     pub amount: u64,
@@ -539,4 +826,16 @@ pub enum ErrorCode {
     TaskNotInProgress,
     #[msg("Task is not completed")]
     TaskNotCompleted,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Bond bps must be at most 10000")]
+    InvalidBondBps,
+    #[msg("Swarm bond does not cover the task's reward")]
+    InsufficientSwarmBond,
+    #[msg("Task is not overdue")]
+    TaskNotOverdue,
+    #[msg("Swarm is not assigned to this task")]
+    WrongSwarm,
 }
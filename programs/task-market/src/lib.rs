@@ -1,8 +1,65 @@
+#![allow(clippy::too_many_arguments)]
+
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
+use droneos_events::{EventHeader, ProgramTag};
+use droneos_token::program::DroneosToken;
+use droneos_token::StakeAccount;
+use identity_registry::program::IdentityRegistry;
+use identity_registry::{Capability, Robot, RobotClass, RobotRequirements};
+use payment_streams::program::PaymentStreams;
+use payment_streams::{PaymentStream, StreamStatus};
 
 declare_id!("DOS4mkt1111111111111111111111111111111111111");
 
+/// Grace period, in seconds, for streams opened on bid acceptance
+pub const STREAM_GRACE_PERIOD: i64 = 60;
+
+// Programs that depend on this one. Their ids are declared here because
+// importing them from their crates would be a dependency cycle.
+
+/// oracle-verifier, whose disputes settle disputed completions.
+pub const ORACLE_VERIFIER_PROGRAM_ID: Pubkey =
+    pubkey!("DOS4orc1111111111111111111111111111111111111");
+
+/// swarm-coordinator, which runs tasks needing more than one robot.
+pub const SWARM_COORDINATOR_PROGRAM_ID: Pubkey =
+    pubkey!("DOS4swm1111111111111111111111111111111111111");
+
+/// Most milestones a task can be split into
+pub const MAX_TASK_MILESTONES: usize = 5;
+
+/// Progress above which `update_progress` must reference a verified
+/// oracle-verifier proof
+pub const PROGRESS_PROOF_THRESHOLD: u8 = 50;
+
+/// Longest deliverable URI a completed task can reference
+pub const MAX_DELIVERABLE_URI_LEN: usize = 128;
+
+/// Most robots or operators a task's on-chain allowlist can hold; larger
+/// lists go in a merkle root
+pub const MAX_ALLOWLIST_MEMBERS: usize = 10;
+
+/// Most robots or operators a creator can blacklist
+pub const MAX_BLACKLIST_MEMBERS: usize = 32;
+
+/// Tasks per task index page, one bit each in the page's open bitmap
+pub const TASK_INDEX_PAGE_SIZE: usize = 64;
+
+/// Reputation (out of 10000) a robot loses for a task aborted through its
+/// fault
+pub const ABORT_REPUTATION_PENALTY: i32 = 500;
+
+/// Seconds a disputed task has to be resolved before it settles in its
+/// robot's favour, until the authority sets another window
+pub const DEFAULT_DISPUTE_WINDOW: i64 = 14 * 24 * 60 * 60;
+
 /// $DRONEOS Task Market Program
 /// 
 /// On-chain labor marketplace for robots:
@@ -23,12 +80,35 @@ pub mod task_market {
         market.total_completed = 0;
         market.total_volume = 0;
         market.fee_basis_points = 50; // 0.5% platform fee
+        market.min_stake_per_robot = 0;
+        market.total_recurring = 0;
+        market.task_bond_bps = 0;
+        market.sla_buffer_seconds = 0;
+        market.late_penalty_bps = 0;
+        market.cancellation_fee_bps = 0;
+        market.verification_window = 0;
+        market.bid_bond_lamports = 0;
+        market.start_window = 0;
+        market.boost_fee_per_hour = 0;
+        market.treasury = Pubkey::default();
+        market.paused = false;
+        market.insurance_premium_bps = 0;
+        market.dispute_window = DEFAULT_DISPUTE_WINDOW;
         market.bump = ctx.bumps.market;
         
         Ok(())
     }
 
-    /// Create a new task
+    /// Create a new task. Tasks needing more than one robot are delegated to
+    /// a swarm-coordinator group task, created here and linked to the task;
+    /// swarms bid on the group task rather than robots on this one.
+    /// A non-zero `insured_amount` (up to the reward) insures the task: when
+    /// a bid is accepted the creator pays a premium, priced on the assigned
+    /// robot's reputation, into droneos_token's insurance pool and can be
+    /// repaid their loss, up to that amount, if the task fails through its
+    /// robot's fault.
+    /// With `requires_proof`, completion is only approved with a verified
+    /// oracle-verifier proof for the task.
     pub fn create_task(
         ctx: Context<CreateTask>,
         title: String,
@@ -41,74 +121,400 @@ pub mod task_market {
         estimated_duration: u32,
         priority: u8,
         expires_in: i64,
+        requires_proof: bool,
+        required_robots: u8,
+        insured_amount: u64,
+    ) -> Result<()> {
+        let params = TaskParams {
+            title,
+            description,
+            robot_class,
+            capabilities,
+            min_reputation,
+            reward,
+            rate_per_second,
+            estimated_duration,
+            priority,
+            expires_in,
+            requires_proof,
+        };
+        params.validate()?;
+        require!(insured_amount <= reward, ErrorCode::InvalidInsuredAmount);
+        require!(
+            insured_amount == 0 || ctx.accounts.market.insurance_premium_bps > 0,
+            ErrorCode::InsuranceDisabled
+        );
+
+        // Group tasks are created first, while the task is only signing and
+        // its data isn't borrowed
+        let group_task = if required_robots > 1 {
+            Some(create_group_task(ctx.accounts, &params, ctx.bumps.task, required_robots)?)
+        } else {
+            None
+        };
+
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_init()?;
+        let clock = Clock::get()?;
+
+        open_task(
+            task,
+            &mut ctx.accounts.market,
+            ctx.accounts.creator.key(),
+            params,
+            ctx.bumps.task,
+            clock.unix_timestamp,
+        );
+        task.insured_amount = insured_amount;
+        task.group_task = group_task.unwrap_or_default();
+
+        emit_cpi!(TaskCreated {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            creator: task.creator,
+            title: task.title(),
+            reward: task.reward,
+            expires_at: task.expires_at,
+        });
+
+        if let Some(group_task) = group_task {
+            emit_cpi!(TaskDelegatedToSwarm {
+                header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+                task: task_key,
+                group_task,
+                required_robots,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Publish a recurring task (by creator): `occurrences` tasks opened from
+    /// `params`, the first due at `first_due_at` and then every `interval`
+    /// seconds. Rent for every occurrence is prepaid into the recurring task
+    /// and refunded to whoever spawns each one.
+    pub fn create_recurring_task(
+        ctx: Context<CreateRecurringTask>,
+        params: TaskParams,
+        interval: i64,
+        occurrences: u32,
+        first_due_at: i64,
     ) -> Result<()> {
-        require!(title.len() <= 64, ErrorCode::TitleTooLong);
-        require!(description.len() <= 256, ErrorCode::DescriptionTooLong);
-        require!(capabilities.len() <= 5, ErrorCode::TooManyCapabilities);
-        require!(reward > 0, ErrorCode::InvalidReward);
-        require!(priority >= 1 && priority <= 5, ErrorCode::InvalidPriority);
-        require!(expires_in > 0 && expires_in <= 7 * 86400, ErrorCode::InvalidExpiration);
-
-        let task = &mut ctx.accounts.task;
+        params.validate()?;
+        require!(interval > 0, ErrorCode::InvalidRecurrence);
+        require!(occurrences > 0, ErrorCode::InvalidRecurrence);
+
+        let rent_per_occurrence = Rent::get()?.minimum_balance(8 + Task::INIT_SPACE);
+        let prepaid = rent_per_occurrence
+            .checked_mul(occurrences as u64)
+            .ok_or(ErrorCode::InvalidRecurrence)?;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.creator.to_account_info(),
+                    to: ctx.accounts.recurring.to_account_info(),
+                },
+            ),
+            prepaid,
+        )?;
+
+        let recurring = &mut ctx.accounts.recurring;
         let market = &mut ctx.accounts.market;
+        let now = Clock::get()?.unix_timestamp;
+
+        recurring.creator = ctx.accounts.creator.key();
+        recurring.index = market.total_recurring;
+        recurring.params = params;
+        recurring.interval = interval;
+        recurring.occurrences = occurrences;
+        recurring.spawned = 0;
+        recurring.next_due_at = first_due_at.max(now);
+        recurring.rent_per_occurrence = rent_per_occurrence;
+        recurring.event_seq = 0;
+        recurring.bump = ctx.bumps.recurring;
+
+        market.total_recurring += 1;
+
+        emit_cpi!(RecurringTaskCreated {
+            header: event_header(recurring.key(), &mut recurring.event_seq, now),
+            recurring: recurring.key(),
+            creator: recurring.creator,
+            interval,
+            occurrences,
+            next_due_at: recurring.next_due_at,
+        });
+
+        Ok(())
+    }
+
+    /// Open the next task of a recurring task once it is due
+    /// (permissionless). The caller pays the task's rent and is refunded from
+    /// the recurring task. Occurrences missed while nobody spawned them are
+    /// skipped rather than opened late.
+    pub fn spawn_occurrence(ctx: Context<SpawnOccurrence>) -> Result<()> {
+        let recurring = &mut ctx.accounts.recurring;
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_init()?;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(recurring.spawned < recurring.occurrences, ErrorCode::RecurrenceFinished);
+        require!(now >= recurring.next_due_at, ErrorCode::OccurrenceNotDue);
+
+        open_task(
+            task,
+            &mut ctx.accounts.market,
+            recurring.creator,
+            recurring.params.clone(),
+            ctx.bumps.task,
+            now,
+        );
+
+        let due = (now - recurring.next_due_at) / recurring.interval + 1;
+        recurring.next_due_at += due * recurring.interval;
+        recurring.spawned += 1;
+
+        let refund = recurring.rent_per_occurrence;
+        recurring.sub_lamports(refund)?;
+        ctx.accounts.payer.add_lamports(refund)?;
+
+        emit_cpi!(TaskCreated {
+            header: event_header(task_key, &mut task.event_seq, now),
+            task: task_key,
+            creator: task.creator,
+            title: task.title(),
+            reward: task.reward,
+            expires_at: task.expires_at,
+        });
+        emit_cpi!(OccurrenceSpawned {
+            header: event_header(recurring.key(), &mut recurring.event_seq, now),
+            recurring: recurring.key(),
+            task: task_key,
+            occurrence: recurring.spawned,
+            next_due_at: recurring.next_due_at,
+        });
+
+        Ok(())
+    }
+
+    /// Stop a recurring task (by creator), closing it and returning the rent
+    /// prepaid for occurrences not yet spawned
+    pub fn cancel_recurring_task(ctx: Context<CancelRecurringTask>) -> Result<()> {
+        let recurring = &mut ctx.accounts.recurring;
+        let now = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(RecurringTaskCancelled {
+            header: event_header(recurring.key(), &mut recurring.event_seq, now),
+            recurring: recurring.key(),
+            spawned: recurring.spawned,
+        });
+
+        Ok(())
+    }
+
+    /// Publish task parameters once for reuse by `create_task_from_template`.
+    /// Any creator can open tasks from any template, so the platform can
+    /// publish templates for common jobs.
+    pub fn create_task_template(
+        ctx: Context<CreateTaskTemplate>,
+        template_id: u64,
+        params: TaskParams,
+    ) -> Result<()> {
+        params.validate()?;
+
+        let template = &mut ctx.accounts.template;
+        let now = Clock::get()?.unix_timestamp;
+
+        template.publisher = ctx.accounts.publisher.key();
+        template.template_id = template_id;
+        template.params = params;
+        template.tasks_created = 0;
+        template.event_seq = 0;
+        template.bump = ctx.bumps.template;
+
+        emit_cpi!(TaskTemplateCreated {
+            header: event_header(template.key(), &mut template.event_seq, now),
+            template: template.key(),
+            publisher: template.publisher,
+            title: template.params.title.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Close a task template and reclaim its rent (by publisher). Tasks
+    /// already opened from it are unaffected.
+    pub fn close_task_template(_ctx: Context<CloseTaskTemplate>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Open a task with a template's parameters
+    pub fn create_task_from_template(ctx: Context<CreateTaskFromTemplate>) -> Result<()> {
+        let template = &mut ctx.accounts.template;
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_init()?;
         let clock = Clock::get()?;
 
-        task.creator = ctx.accounts.creator.key();
-        task.title = title.clone();
-        task.description = description;
-        task.robot_class = robot_class;
-        task.required_capabilities = capabilities;
-        task.min_reputation = min_reputation;
-        task.reward = reward;
-        task.rate_per_second = rate_per_second;
-        task.estimated_duration = estimated_duration;
-        task.priority = priority;
-        task.status = TaskStatus::Open;
-        task.created_at = clock.unix_timestamp;
-        task.expires_at = clock.unix_timestamp + expires_in;
-        task.assigned_robot = None;
-        task.assigned_at = None;
-        task.started_at = None;
-        task.completed_at = None;
-        task.stream_id = None;
-        task.progress = 0;
-        task.bids_count = 0;
-        task.bump = ctx.bumps.task;
-
-        market.total_tasks += 1;
-
-        emit!(TaskCreated {
-            task: task.key(),
+        template.tasks_created += 1;
+
+        open_task(
+            task,
+            &mut ctx.accounts.market,
+            ctx.accounts.creator.key(),
+            template.params.clone(),
+            ctx.bumps.task,
+            clock.unix_timestamp,
+        );
+
+        emit_cpi!(TaskCreated {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
             creator: task.creator,
-            title,
-            reward,
+            title: task.title(),
+            reward: task.reward,
             expires_at: task.expires_at,
         });
 
         Ok(())
     }
 
-    /// Submit a bid on a task
+    /// Set the operator bond required per registered robot to bid on and be
+    /// assigned tasks (by authority). Zero turns the requirement off.
+    pub fn set_min_stake_per_robot(ctx: Context<UpdateMarket>, min_stake_per_robot: u64) -> Result<()> {
+        ctx.accounts.market.min_stake_per_robot = min_stake_per_robot;
+        Ok(())
+    }
+
+    /// Set the performance bond, as basis points of a task's reward, that an
+    /// operator's stake must hold while assigned a task (by authority). Zero
+    /// turns bonds off.
+    pub fn set_task_bond_bps(ctx: Context<UpdateMarket>, task_bond_bps: u16) -> Result<()> {
+        require!(task_bond_bps <= 10_000, ErrorCode::InvalidTaskBond);
+        ctx.accounts.market.task_bond_bps = task_bond_bps;
+        Ok(())
+    }
+
+    /// Set the SLA terms of newly assigned tasks (by authority): the grace
+    /// added to the accepted bid's duration to form the deadline, and the
+    /// share of the task bond (basis points) a late robot forfeits
+    pub fn set_sla_terms(
+        ctx: Context<UpdateMarket>,
+        sla_buffer_seconds: i64,
+        late_penalty_bps: u16,
+    ) -> Result<()> {
+        require!(
+            sla_buffer_seconds >= 0 && late_penalty_bps <= 10_000,
+            ErrorCode::InvalidSlaTerms
+        );
+        let market = &mut ctx.accounts.market;
+        market.sla_buffer_seconds = sla_buffer_seconds;
+        market.late_penalty_bps = late_penalty_bps;
+        Ok(())
+    }
+
+    /// Set the share of an assigned task's remaining reward, in basis
+    /// points, paid to the robot when the creator cancels it (by authority)
+    pub fn set_cancellation_fee_bps(
+        ctx: Context<UpdateMarket>,
+        cancellation_fee_bps: u16,
+    ) -> Result<()> {
+        require!(cancellation_fee_bps <= 10_000, ErrorCode::InvalidCancellationFee);
+        ctx.accounts.market.cancellation_fee_bps = cancellation_fee_bps;
+        Ok(())
+    }
+
+    /// Set how long creators of newly created tasks have to verify a
+    /// completion before anyone can approve it for them (by authority). Zero
+    /// leaves verification open indefinitely.
+    pub fn set_verification_window(
+        ctx: Context<UpdateMarket>,
+        verification_window: i64,
+    ) -> Result<()> {
+        require!(verification_window >= 0, ErrorCode::InvalidVerificationWindow);
+        ctx.accounts.market.verification_window = verification_window;
+        Ok(())
+    }
+
+    /// Set how long a newly disputed task has to be resolved before anyone
+    /// can settle it in its robot's favour (by authority)
+    pub fn set_dispute_window(ctx: Context<UpdateMarket>, dispute_window: i64) -> Result<()> {
+        require!(dispute_window > 0, ErrorCode::InvalidDisputeWindow);
+        ctx.accounts.market.dispute_window = dispute_window;
+        Ok(())
+    }
+
+    /// Set the refundable lamport bond each new bid holds, and how long an
+    /// accepted bidder has to start before anyone can forfeit its bond to the
+    /// creator (by authority). Zero turns either off.
+    pub fn set_bid_bond_terms(
+        ctx: Context<UpdateMarket>,
+        bid_bond_lamports: u64,
+        start_window: i64,
+    ) -> Result<()> {
+        require!(start_window >= 0, ErrorCode::InvalidStartWindow);
+        let market = &mut ctx.accounts.market;
+        market.bid_bond_lamports = bid_bond_lamports;
+        market.start_window = start_window;
+        Ok(())
+    }
+
+    /// Set the lamports per hour creators pay to boost an open task, and the
+    /// treasury receiving them (by authority). A default treasury disables
+    /// boosting.
+    pub fn set_boost_terms(
+        ctx: Context<UpdateMarket>,
+        boost_fee_per_hour: u64,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        market.boost_fee_per_hour = boost_fee_per_hour;
+        market.treasury = treasury;
+        Ok(())
+    }
+
+    /// Pause or unpause the market (by authority). While paused no tasks can
+    /// be created, bid on or assigned; tasks already assigned can still be
+    /// worked, completed, verified, disputed and cancelled.
+    pub fn set_paused(ctx: Context<UpdateMarket>, paused: bool) -> Result<()> {
+        ctx.accounts.market.paused = paused;
+        Ok(())
+    }
+
+    /// Set the insurance premium (by authority), in basis points of the
+    /// insured amount for tasks open to robots of any reputation. Zero
+    /// disables insurance.
+    pub fn set_insurance_premium_bps(
+        ctx: Context<UpdateMarket>,
+        insurance_premium_bps: u16,
+    ) -> Result<()> {
+        require!(insurance_premium_bps <= 10_000, ErrorCode::InvalidInsurancePremium);
+        ctx.accounts.market.insurance_premium_bps = insurance_premium_bps;
+        Ok(())
+    }
+
+    /// Submit a bid on a task, open for acceptance for `valid_for` seconds.
+    /// `allowlist_proof` is the merkle proof for allowlisted tasks whose
+    /// bidder is not listed on the task itself; empty otherwise.
     pub fn submit_bid(
         ctx: Context<SubmitBid>,
         proposed_rate: u64,
         estimated_duration: u32,
         message: String,
+        valid_for: i64,
+        allowlist_proof: Vec<[u8; 32]>,
     ) -> Result<()> {
         require!(message.len() <= 128, ErrorCode::MessageTooLong);
+        require!(valid_for > 0, ErrorCode::InvalidExpiration);
 
-        let task = &mut ctx.accounts.task;
-        let bid = &mut ctx.accounts.bid;
         let clock = Clock::get()?;
+        check_bidder(ctx.accounts, clock.unix_timestamp, &allowlist_proof)?;
+        require!(ctx.accounts.task.load()?.commit_ends_at == 0, ErrorCode::SealedBidsOnly);
 
-        // Verify task is open
-        require!(task.status == TaskStatus::Open, ErrorCode::TaskNotOpen);
-        require!(clock.unix_timestamp < task.expires_at, ErrorCode::TaskExpired);
-
-        // TODO: Verify robot meets requirements via CPI to identity-registry
-        // For now, just check robot is provided
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let bid = &mut ctx.accounts.bid;
         
-        bid.task = task.key();
+        bid.task = task_key;
         bid.robot = ctx.accounts.robot.key();
         bid.operator = ctx.accounts.operator.key();
         bid.proposed_rate = proposed_rate;
@@ -116,384 +522,3888 @@ pub mod task_market {
         bid.message = message;
         bid.status = BidStatus::Pending;
         bid.submitted_at = clock.unix_timestamp;
+        bid.valid_until = clock.unix_timestamp + valid_for;
+        bid.commitment = None;
+        bid.event_seq = 0;
         bid.bump = ctx.bumps.bid;
+        hold_bid_bond(
+            bid,
+            &ctx.accounts.operator,
+            &ctx.accounts.market,
+            &ctx.accounts.system_program,
+        )?;
 
         task.bids_count += 1;
 
-        emit!(BidSubmitted {
-            task: task.key(),
+        emit_cpi!(BidSubmitted {
+            header: event_header(bid.key(), &mut bid.event_seq, clock.unix_timestamp),
+            task: task_key,
             bid: bid.key(),
             robot: bid.robot,
             proposed_rate,
             estimated_duration,
+            valid_until: bid.valid_until,
         });
 
         Ok(())
     }
 
-    /// Accept a bid and assign the task
-    pub fn accept_bid(ctx: Context<AcceptBid>) -> Result<()> {
-        let task = &mut ctx.accounts.task;
-        let bid = &mut ctx.accounts.bid;
-        let clock = Clock::get()?;
+    /// Switch an open task without bids to sealed bidding (by creator): bids
+    /// are committed for `commit_seconds`, then revealed for `reveal_seconds`
+    pub fn enable_sealed_bidding(
+        ctx: Context<UpdateTask>,
+        commit_seconds: i64,
+        reveal_seconds: i64,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let now = Clock::get()?.unix_timestamp;
 
-        require!(task.status == TaskStatus::Open, ErrorCode::TaskNotOpen);
-        require!(bid.status == BidStatus::Pending, ErrorCode::BidNotPending);
-        require!(task.creator == ctx.accounts.creator.key(), ErrorCode::Unauthorized);
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+        require!(task.bids_count == 0, ErrorCode::TaskHasBids);
+        require!(commit_seconds > 0 && reveal_seconds > 0, ErrorCode::InvalidBidWindows);
 
-        // Update bid status
-        bid.status = BidStatus::Accepted;
+        let commit_ends_at = now + commit_seconds;
+        let reveal_ends_at = commit_ends_at + reveal_seconds;
+        require!(reveal_ends_at <= task.expires_at, ErrorCode::InvalidBidWindows);
 
-        // Assign task
-        task.status = TaskStatus::Assigned;
-        task.assigned_robot = Some(bid.robot);
-        task.assigned_at = Some(clock.unix_timestamp);
-        task.rate_per_second = bid.proposed_rate;
+        task.commit_ends_at = commit_ends_at;
+        task.reveal_ends_at = reveal_ends_at;
 
-        // TODO: Create payment stream via CPI to payment-streams
-        // task.stream_id = Some(stream_pubkey);
+        emit_cpi!(SealedBiddingEnabled {
+            header: event_header(task_key, &mut task.event_seq, now),
+            task: task_key,
+            commit_ends_at,
+            reveal_ends_at,
+        });
 
-        emit!(TaskAssigned {
-            task: task.key(),
-            robot: bid.robot,
-            rate: bid.proposed_rate,
-            timestamp: clock.unix_timestamp,
+        Ok(())
+    }
+
+    /// Edit an open task without bids (by creator). Each given field replaces
+    /// the task's; `expires_in` counts from now. No escrow exists before a
+    /// bid is accepted, so the new reward is simply what the stream will be
+    /// funded with.
+    pub fn update_task(
+        ctx: Context<UpdateTask>,
+        description: Option<String>,
+        reward: Option<u64>,
+        expires_in: Option<i64>,
+        capabilities: Option<Vec<u8>>,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+        require!(task.bids_count == 0, ErrorCode::TaskHasBids);
+        require!(task.group_task == Pubkey::default(), ErrorCode::SwarmTask);
+
+        if let Some(description) = description {
+            require!(description.len() <= 256, ErrorCode::DescriptionTooLong);
+            task.set_description(&description);
+        }
+        if let Some(reward) = reward {
+            require!(reward > 0, ErrorCode::InvalidReward);
+            task.reward = reward;
+        }
+        if let Some(expires_in) = expires_in {
+            require!(
+                expires_in > 0 && expires_in <= 7 * 86400,
+                ErrorCode::InvalidExpiration
+            );
+            let expires_at = now + expires_in;
+            require!(task.reveal_ends_at <= expires_at, ErrorCode::InvalidBidWindows);
+            task.expires_at = expires_at;
+        }
+        if let Some(capabilities) = capabilities {
+            require!(capabilities.len() <= 5, ErrorCode::TooManyCapabilities);
+            robot_requirements(task.robot_class, &capabilities, task.min_reputation)?;
+            task.set_required_capabilities(&capabilities);
+            // Capability index bits refer to positions in the old list
+            task.indexed &= 1;
+        }
+
+        emit_cpi!(TaskUpdated {
+            header: event_header(task_key, &mut task.event_seq, now),
+            task: task_key,
+            reward: task.reward,
+            expires_at: task.expires_at,
         });
 
         Ok(())
     }
 
-    /// Reject a bid
-    pub fn reject_bid(ctx: Context<RejectBid>) -> Result<()> {
-        let task = &ctx.accounts.task;
-        let bid = &mut ctx.accounts.bid;
+    /// Split an open task without bids into milestones (by creator). Each
+    /// pays its share of the task's escrow once the robot completes it and
+    /// the creator approves, instead of the escrow streaming per second.
+    pub fn set_milestones(ctx: Context<UpdateTask>, milestones: Vec<MilestoneTerms>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let now = Clock::get()?.unix_timestamp;
 
-        require!(task.creator == ctx.accounts.creator.key(), ErrorCode::Unauthorized);
-        require!(bid.status == BidStatus::Pending, ErrorCode::BidNotPending);
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+        require!(task.bids_count == 0, ErrorCode::TaskHasBids);
+        require!(
+            !milestones.is_empty() && milestones.len() <= MAX_TASK_MILESTONES,
+            ErrorCode::InvalidMilestones
+        );
+        require!(
+            milestones
+                .iter()
+                .all(|m| m.share_bps > 0 && m.proof_type.unwrap_or(0) < ORACLE_PROOF_TYPES) &&
+                milestones.iter().map(|m| m.share_bps as u32).sum::<u32>() == 10_000,
+            ErrorCode::InvalidMilestones
+        );
 
-        bid.status = BidStatus::Rejected;
+        task.set_milestones(&milestones);
 
-        emit!(BidRejected {
-            task: task.key(),
-            bid: bid.key(),
+        emit_cpi!(TaskMilestonesSet {
+            header: event_header(task_key, &mut task.event_seq, now),
+            task: task_key,
+            milestones,
         });
 
         Ok(())
     }
 
-    /// Withdraw a bid (by robot operator)
-    pub fn withdraw_bid(ctx: Context<WithdrawBid>) -> Result<()> {
-        let bid = &mut ctx.accounts.bid;
+    /// Restrict bidding on an open task without bids to allowlisted robots or
+    /// operators (by creator): those in `members`, or with a proof against
+    /// `root` for lists too large to store. Empty `members` and no `root`
+    /// make the task public again.
+    pub fn set_allowlist(
+        ctx: Context<UpdateTask>,
+        members: Vec<Pubkey>,
+        root: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let now = Clock::get()?.unix_timestamp;
 
-        require!(bid.operator == ctx.accounts.operator.key(), ErrorCode::Unauthorized);
-        require!(bid.status == BidStatus::Pending, ErrorCode::BidNotPending);
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+        require!(task.bids_count == 0, ErrorCode::TaskHasBids);
+        require!(members.len() <= MAX_ALLOWLIST_MEMBERS, ErrorCode::AllowlistTooLarge);
 
-        bid.status = BidStatus::Withdrawn;
+        let member_count = members.len() as u8;
+        task.set_allowlist(&members);
+        task.allowlist_root = root.unwrap_or_default();
 
-        emit!(BidWithdrawn {
-            bid: bid.key(),
+        emit_cpi!(TaskAllowlistSet {
+            header: event_header(task_key, &mut task.event_seq, now),
+            task: task_key,
+            member_count,
+            root,
         });
 
         Ok(())
     }
 
-    /// Start task execution (by assigned robot)
-    pub fn start_task(ctx: Context<ExecuteTask>) -> Result<()> {
-        let task = &mut ctx.accounts.task;
+    /// Block a robot or operator from bidding on any of the creator's tasks,
+    /// creating the creator's blacklist on first use
+    pub fn add_to_blacklist(ctx: Context<AddToBlacklist>, member: Pubkey) -> Result<()> {
+        let blacklist = &mut ctx.accounts.blacklist;
         let clock = Clock::get()?;
 
-        require!(task.status == TaskStatus::Assigned, ErrorCode::TaskNotAssigned);
+        blacklist.creator = ctx.accounts.creator.key();
+        blacklist.bump = ctx.bumps.blacklist;
+        require!(!blacklist.members.contains(&member), ErrorCode::AlreadyBlacklisted);
         require!(
-            task.assigned_robot == Some(ctx.accounts.robot.key()),
-            ErrorCode::NotAssignedRobot
+            blacklist.members.len() < MAX_BLACKLIST_MEMBERS,
+            ErrorCode::BlacklistFull
         );
+        blacklist.members.push(member);
 
-        task.status = TaskStatus::InProgress;
-        task.started_at = Some(clock.unix_timestamp);
+        emit_cpi!(BlacklistUpdated {
+            header: event_header(blacklist.key(), &mut blacklist.event_seq, clock.unix_timestamp),
+            creator: blacklist.creator,
+            member,
+            added: true,
+        });
 
-        // TODO: Start payment stream via CPI
+        Ok(())
+    }
 
-        emit!(TaskStarted {
-            task: task.key(),
-            robot: ctx.accounts.robot.key(),
-            timestamp: clock.unix_timestamp,
+    /// Let a blacklisted robot or operator bid on the creator's tasks again
+    pub fn remove_from_blacklist(ctx: Context<RemoveFromBlacklist>, member: Pubkey) -> Result<()> {
+        let blacklist = &mut ctx.accounts.blacklist;
+        let clock = Clock::get()?;
+
+        let position = blacklist
+            .members
+            .iter()
+            .position(|m| *m == member)
+            .ok_or(ErrorCode::NotBlacklisted)?;
+        blacklist.members.swap_remove(position);
+
+        emit_cpi!(BlacklistUpdated {
+            header: event_header(blacklist.key(), &mut blacklist.event_seq, clock.unix_timestamp),
+            creator: blacklist.creator,
+            member,
+            added: false,
         });
 
         Ok(())
     }
 
-    /// Update task progress
-    pub fn update_progress(ctx: Context<ExecuteTask>, progress: u8) -> Result<()> {
-        let task = &mut ctx.accounts.task;
+    /// Restrict an open task without bids to a circular geofence (by
+    /// creator). Approving its completion then requires a verified
+    /// oracle-verifier GPS proof from inside the fence. Coordinates are in
+    /// microdegrees, as in oracle GPS proofs.
+    pub fn set_geofence(
+        ctx: Context<UpdateTask>,
+        latitude: i64,
+        longitude: i64,
+        radius_meters: u32,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let now = Clock::get()?.unix_timestamp;
 
-        require!(task.status == TaskStatus::InProgress, ErrorCode::TaskNotInProgress);
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+        require!(task.bids_count == 0, ErrorCode::TaskHasBids);
         require!(
-            task.assigned_robot == Some(ctx.accounts.robot.key()),
-            ErrorCode::NotAssignedRobot
+            latitude.abs() <= 90 * MICRODEGREES as i64 &&
+            longitude.abs() <= 180 * MICRODEGREES as i64 &&
+            radius_meters > 0,
+            ErrorCode::InvalidGeofence
         );
-        require!(progress <= 100, ErrorCode::InvalidProgress);
 
-        task.progress = progress;
+        task.set_geofence(Some(Geofence { latitude, longitude, radius_meters }));
 
-        emit!(TaskProgressUpdated {
-            task: task.key(),
-            progress,
+        emit_cpi!(TaskGeofenceSet {
+            header: event_header(task_key, &mut task.event_seq, now),
+            task: task_key,
+            latitude,
+            longitude,
+            radius_meters,
         });
 
         Ok(())
     }
 
-    /// Complete the task (by robot)
-    pub fn complete_task(ctx: Context<ExecuteTask>) -> Result<()> {
-        let task = &mut ctx.accounts.task;
-        let clock = Clock::get()?;
+    /// Raise an open task's reward and accepted rate by `step_bps` every
+    /// `interval` seconds since its creation, up to `cap_bps` in total,
+    /// applied when a bid is accepted. Bids above the escalated rate cannot
+    /// be accepted. A `step_bps` of 0 turns escalation off.
+    pub fn set_escalation(
+        ctx: Context<UpdateTask>,
+        step_bps: u16,
+        interval: u32,
+        cap_bps: u16,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let now = Clock::get()?.unix_timestamp;
 
-        require!(task.status == TaskStatus::InProgress, ErrorCode::TaskNotInProgress);
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
         require!(
-            task.assigned_robot == Some(ctx.accounts.robot.key()),
-            ErrorCode::NotAssignedRobot
+            step_bps == 0 || (interval > 0 && cap_bps >= step_bps),
+            ErrorCode::InvalidEscalation
         );
 
-        task.status = TaskStatus::PendingVerification;
-        task.progress = 100;
-
-        // TODO: Pause payment stream pending verification
+        task.set_escalation(
+            (step_bps > 0).then_some(RewardEscalation { step_bps, interval, cap_bps }),
+        );
 
-        emit!(TaskPendingVerification {
-            task: task.key(),
-            timestamp: clock.unix_timestamp,
+        emit_cpi!(TaskEscalationSet {
+            header: event_header(task_key, &mut task.event_seq, now),
+            task: task_key,
+            step_bps,
+            interval,
+            cap_bps,
         });
 
         Ok(())
     }
 
-    /// Verify task completion (by creator)
-    pub fn verify_completion(ctx: Context<VerifyTask>, approved: bool) -> Result<()> {
-        let task = &mut ctx.accounts.task;
-        let market = &mut ctx.accounts.market;
-        let clock = Clock::get()?;
+    /// Commit a sealed bid on a task during its commit window. `commitment`
+    /// is `sha256(proposed_rate as u64 LE || salt)`, opened by `reveal_bid`.
+    /// `allowlist_proof` is as for `submit_bid`.
+    pub fn commit_bid(
+        ctx: Context<SubmitBid>,
+        commitment: [u8; 32],
+        estimated_duration: u32,
+        message: String,
+        valid_for: i64,
+        allowlist_proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(message.len() <= 128, ErrorCode::MessageTooLong);
+        require!(valid_for > 0, ErrorCode::InvalidExpiration);
 
-        require!(task.status == TaskStatus::PendingVerification, ErrorCode::TaskNotPendingVerification);
-        require!(task.creator == ctx.accounts.creator.key(), ErrorCode::Unauthorized);
+        let clock = Clock::get()?;
+        check_bidder(ctx.accounts, clock.unix_timestamp, &allowlist_proof)?;
+        let commit_ends_at = ctx.accounts.task.load()?.commit_ends_at;
+        require!(commit_ends_at != 0, ErrorCode::NotSealedBidding);
+        require!(clock.unix_timestamp < commit_ends_at, ErrorCode::CommitWindowClosed);
 
-        if approved {
-            task.status = TaskStatus::Completed;
-            task.completed_at = Some(clock.unix_timestamp);
-            
-            market.total_completed += 1;
-            market.total_volume += task.reward;
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let bid = &mut ctx.accounts.bid;
 
-            // TODO: Complete payment stream via CPI
-            // TODO: Update robot reputation via CPI
+        bid.task = task_key;
+        bid.robot = ctx.accounts.robot.key();
+        bid.operator = ctx.accounts.operator.key();
+        bid.proposed_rate = 0;
+        bid.estimated_duration = estimated_duration;
+        bid.message = message;
+        bid.status = BidStatus::Committed;
+        bid.submitted_at = clock.unix_timestamp;
+        bid.valid_until = clock.unix_timestamp + valid_for;
+        bid.commitment = Some(commitment);
+        bid.event_seq = 0;
+        bid.bump = ctx.bumps.bid;
+        hold_bid_bond(
+            bid,
+            &ctx.accounts.operator,
+            &ctx.accounts.market,
+            &ctx.accounts.system_program,
+        )?;
 
-            emit!(TaskCompleted {
-                task: task.key(),
-                robot: task.assigned_robot.unwrap(),
-                total_paid: task.reward,
-                timestamp: clock.unix_timestamp,
-            });
-        } else {
-            task.status = TaskStatus::Disputed;
+        task.bids_count += 1;
 
-            emit!(TaskDisputed {
-                task: task.key(),
-                timestamp: clock.unix_timestamp,
-            });
-        }
+        emit_cpi!(BidCommitted {
+            header: event_header(bid.key(), &mut bid.event_seq, clock.unix_timestamp),
+            task: task_key,
+            bid: bid.key(),
+            robot: bid.robot,
+            estimated_duration,
+            valid_until: bid.valid_until,
+        });
 
         Ok(())
     }
 
-    /// Cancel a task (before assignment)
-    pub fn cancel_task(ctx: Context<CancelTask>) -> Result<()> {
-        let task = &mut ctx.accounts.task;
-        let clock = Clock::get()?;
+    /// Open a committed bid during the task's reveal window (by operator),
+    /// making it a pending bid at `proposed_rate`
+    pub fn reveal_bid(ctx: Context<RevealBid>, proposed_rate: u64, salt: [u8; 32]) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &ctx.accounts.task.load()?;
+        let bid = &mut ctx.accounts.bid;
+        let now = Clock::get()?.unix_timestamp;
 
-        require!(task.creator == ctx.accounts.creator.key(), ErrorCode::Unauthorized);
+        require!(bid.status == BidStatus::Committed, ErrorCode::BidNotCommitted);
+        require!(task.commit_ends_at != 0, ErrorCode::NotSealedBidding);
         require!(
-            task.status == TaskStatus::Open,
-            ErrorCode::TaskCannotBeCancelled
+            now >= task.commit_ends_at && now < task.reveal_ends_at,
+            ErrorCode::NotInRevealWindow
         );
 
-        task.status = TaskStatus::Cancelled;
+        let opened = hashv(&[&proposed_rate.to_le_bytes(), &salt]).to_bytes();
+        require!(bid.commitment == Some(opened), ErrorCode::CommitmentMismatch);
 
-        emit!(TaskCancelled {
-            task: task.key(),
-            timestamp: clock.unix_timestamp,
+        bid.proposed_rate = proposed_rate;
+        bid.status = BidStatus::Pending;
+
+        emit_cpi!(BidRevealed {
+            header: event_header(bid.key(), &mut bid.event_seq, now),
+            task: task_key,
+            bid: bid.key(),
+            proposed_rate,
         });
 
         Ok(())
     }
 
-    /// Abort a task in progress (emergency)
-    pub fn abort_task(ctx: Context<AbortTask>, reason: String) -> Result<()> {
-        let task = &mut ctx.accounts.task;
+    /// Accept a bid, assign the task and open its payment stream from the
+    /// creator to the bidding operator. The first `2 * losing_bids` remaining
+    /// accounts are `(bid, operator)` pairs of other bids on the task to
+    /// reject and close, as in `reject_remaining_bids`; the rest are passed
+    /// on to the stream's token transfer.
+    pub fn accept_bid<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AcceptBid<'info>>,
+        losing_bids: u8,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+
+        // Insurance is priced on the robot actually assigned. A creator
+        // can't insure work done by their own operator.
+        let (insured_amount, creator) = {
+            let task = ctx.accounts.task.load()?;
+            (task.insured_amount, task.creator)
+        };
+        let premium = if insured_amount > 0 {
+            require_keys_neq!(ctx.accounts.bid.operator, creator, ErrorCode::InsuredSelfDealing);
+            let reputation = load_robot(&ctx.accounts.robot)?.reputation_score;
+            pay_insurance_premium(ctx.accounts, task_key, insured_amount, reputation)?
+        } else {
+            0
+        };
+
+        let mut task = ctx.accounts.task.load_mut()?;
+        let bid = &mut ctx.accounts.bid;
         let clock = Clock::get()?;
 
-        require!(reason.len() <= 128, ErrorCode::MessageTooLong);
-        require!(
-            task.creator == ctx.accounts.authority.key() || 
-            task.assigned_robot == Some(ctx.accounts.authority.key()),
-            ErrorCode::Unauthorized
-        );
-        require!(
-            task.status == TaskStatus::Assigned || 
-            task.status == TaskStatus::InProgress,
-            ErrorCode::TaskCannotBeAborted
-        );
+        // Creator is checked by the account constraint
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+        require!(bid.status == BidStatus::Pending, ErrorCode::BidNotPending);
+        require!(clock.unix_timestamp < bid.valid_until, ErrorCode::BidExpired);
+        // Sealed bids are compared only once all could be revealed
+        require!(clock.unix_timestamp >= task.reveal_ends_at, ErrorCode::RevealWindowOpen);
 
-        task.status = TaskStatus::Failed;
+        // The fleet or bond may have changed since the bid
+        check_fleet_bond(
+            &ctx.accounts.market,
+            &ctx.accounts.operator_stake,
+            &ctx.accounts.operator_fleet,
+            &ctx.accounts.identity_registry_program,
+        )?;
 
-        // TODO: Terminate payment stream via CPI
-        // TODO: Apply reputation penalty to robot if robot's fault
+        if task.insured_amount > 0 {
+            task.insurance_premium = premium;
 
-        emit!(TaskAborted {
-            task: task.key(),
-            reason,
-            timestamp: clock.unix_timestamp,
-        });
+            emit_cpi!(TaskInsured {
+                header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+                task: task_key,
+                insured_amount: task.insured_amount,
+                premium,
+            });
+        }
 
-        Ok(())
-    }
-}
+        let pairs_len = 2 * losing_bids as usize;
+        require!(ctx.remaining_accounts.len() >= pairs_len, ErrorCode::InvalidBidAccounts);
+        let (losing_pairs, transfer_accounts) = ctx.remaining_accounts.split_at(pairs_len);
 
-// ============================================================================
-// ACCOUNTS
-// ============================================================================
+        // Escalated terms are fixed at acceptance
+        if let Some(escalation) = task.escalation() {
+            let elapsed = clock.unix_timestamp - task.created_at;
+            require!(
+                bid.proposed_rate <= escalation.apply(task.rate_per_second, elapsed),
+                ErrorCode::BidAboveEscalatedRate
+            );
+            let bps = escalation.bps_after(elapsed);
+            if bps > 0 {
+                task.reward = escalation.apply(task.reward, elapsed);
 
+                emit_cpi!(TaskRewardEscalated {
+                    header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+                    task: task_key,
+                    escalation_bps: bps,
+                    reward: task.reward,
+                });
+            }
+        }
+
+        let bond = (task.reward as u128 * ctx.accounts.market.task_bond_bps as u128 / 10_000) as u64;
+        if bond > 0 {
+            lock_task_bond(
+                &ctx.accounts.market,
+                &ctx.accounts.operator_stake,
+                &ctx.accounts.droneos_token_program,
+                bond,
+            )?;
+        }
+        task.bond = bond;
+
+        // Update bid status
+        bid.status = BidStatus::Accepted;
+
+        // Assign task
+        task.set_status(TaskStatus::Assigned);
+        task.assigned_robot = bid.robot;
+        task.assigned_operator = bid.operator;
+        task.assigned_at = clock.unix_timestamp;
+        task.rate_per_second = bid.proposed_rate;
+        task.sla_window = bid.estimated_duration as i64 + ctx.accounts.market.sla_buffer_seconds;
+        task.stream_id = ctx.accounts.stream.key();
+
+        // The task signs the stream CPIs, so it can't stay borrowed
+        let (creator, index, bump) = task_seeds(&task);
+        let task_index = task.index;
+        let milestone_shares: Vec<u16> = task.milestones().iter().map(|m| m.share_bps).collect();
+        drop(task);
+
+        let seeds = &[b"task".as_ref(), creator.as_ref(), &index, &bump];
+        payment_streams::cpi::create_stream_for_task(
+            CpiContext::new_with_signer(
+                ctx.accounts.payment_streams_program.to_account_info(),
+                payment_streams::cpi::accounts::CreateStreamForTask {
+                    config: ctx.accounts.stream_config.to_account_info(),
+                    stream: ctx.accounts.stream.to_account_info(),
+                    escrow: ctx.accounts.escrow.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    payer_token: ctx.accounts.creator_token.to_account_info(),
+                    payer: ctx.accounts.creator.to_account_info(),
+                    payee: ctx.accounts.operator.to_account_info(),
+                    payer_stake: None,
+                    payer_registry: ctx.accounts.creator_registry.to_account_info(),
+                    payee_registry: ctx.accounts.operator_registry.to_account_info(),
+                    task_authority: ctx.accounts.task.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    event_authority: ctx.accounts.stream_event_authority.to_account_info(),
+                    program: ctx.accounts.payment_streams_program.to_account_info(),
+                },
+                &[&seeds[..]],
+            )
+            .with_remaining_accounts(transfer_accounts.to_vec()),
+            bid.proposed_rate,
+            bid.estimated_duration as i64,
+            STREAM_GRACE_PERIOD,
+            true,
+            creator,
+            task_index,
+        )?;
+        if !milestone_shares.is_empty() {
+            payment_streams::cpi::set_milestones_by_task(
+                CpiContext::new_with_signer(
+                    ctx.accounts.payment_streams_program.to_account_info(),
+                    payment_streams::cpi::accounts::TaskControlStream {
+                        stream: ctx.accounts.stream.to_account_info(),
+                        task_authority: ctx.accounts.task.to_account_info(),
+                        event_authority: ctx.accounts.stream_event_authority.to_account_info(),
+                        program: ctx.accounts.payment_streams_program.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                milestone_shares,
+            )?;
+        }
+
+        track_operator_task(
+            &ctx.accounts.market,
+            &ctx.accounts.operator_stake,
+            &ctx.accounts.droneos_token_program,
+            true,
+        )?;
+        track_robot_task(
+            &ctx.accounts.market,
+            &ctx.accounts.robot,
+            &ctx.accounts.registry_event_authority,
+            &ctx.accounts.identity_registry_program,
+            true,
+        )?;
+
+        let task = &mut ctx.accounts.task.load_mut()?;
+        emit_cpi!(TaskAssigned {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            robot: bid.robot,
+            rate: bid.proposed_rate,
+            timestamp: clock.unix_timestamp,
+        });
+
+        if losing_bids > 0 {
+            let bids_closed = close_losing_bids(task_key, Some(bid.key()), losing_pairs)?;
+
+            emit_cpi!(RemainingBidsRejected {
+                header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+                task: task_key,
+                bids_closed,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reject a bid, returning its bond to the operator
+    pub fn reject_bid(ctx: Context<RejectBid>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &ctx.accounts.task.load()?;
+        let bid = &mut ctx.accounts.bid;
+        let clock = Clock::get()?;
+
+        require!(task.creator == ctx.accounts.creator.key(), ErrorCode::Unauthorized);
+        require!(bid.status == BidStatus::Pending, ErrorCode::BidNotPending);
+
+        bid.status = BidStatus::Rejected;
+        return_bid_bond(bid, &ctx.accounts.operator)?;
+
+        emit_cpi!(BidRejected {
+            header: event_header(bid.key(), &mut bid.event_seq, clock.unix_timestamp),
+            task: task_key,
+            bid: bid.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Reject and close the bids left on a task once it is no longer open
+    /// (permissionless). Remaining accounts are `(bid, operator)` pairs of
+    /// the task's bids other than the accepted one; pending and sealed bids
+    /// are rejected, and each bid's rent and bond go back to its operator.
+    pub fn reject_remaining_bids<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RejectRemainingBids<'info>>,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.status() != TaskStatus::Open, ErrorCode::TaskStillOpen);
+
+        let bids_closed = close_losing_bids(task_key, None, ctx.remaining_accounts)?;
+
+        emit_cpi!(RemainingBidsRejected {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            bids_closed,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw a bid (by robot operator), returning its bond
+    pub fn withdraw_bid(ctx: Context<WithdrawBid>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let clock = Clock::get()?;
+
+        require!(bid.operator == ctx.accounts.operator.key(), ErrorCode::Unauthorized);
+        require!(
+            matches!(bid.status, BidStatus::Pending | BidStatus::Committed),
+            ErrorCode::BidNotPending
+        );
+
+        bid.status = BidStatus::Withdrawn;
+        return_bid_bond(bid, &ctx.accounts.operator)?;
+
+        emit_cpi!(BidWithdrawn {
+            header: event_header(bid.key(), &mut bid.event_seq, clock.unix_timestamp),
+            bid: bid.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Mark a pending or unrevealed bid past its `valid_until` as expired
+    /// (permissionless)
+    pub fn expire_bid(ctx: Context<ExpireBid>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let clock = Clock::get()?;
+
+        require!(
+            matches!(bid.status, BidStatus::Pending | BidStatus::Committed),
+            ErrorCode::BidNotPending
+        );
+        require!(clock.unix_timestamp >= bid.valid_until, ErrorCode::BidNotExpired);
+
+        bid.status = BidStatus::Expired;
+
+        emit_cpi!(BidExpired {
+            header: event_header(bid.key(), &mut bid.event_seq, clock.unix_timestamp),
+            task: bid.task,
+            bid: bid.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Close a rejected, withdrawn or expired bid, returning its rent to the
+    /// operator
+    pub fn close_bid(ctx: Context<CloseBid>) -> Result<()> {
+        let bid = &mut ctx.accounts.bid;
+        let clock = Clock::get()?;
+
+        require!(
+            matches!(
+                bid.status,
+                BidStatus::Rejected | BidStatus::Withdrawn | BidStatus::Expired
+            ),
+            ErrorCode::BidStillActive
+        );
+
+        emit_cpi!(BidClosed {
+            header: event_header(bid.key(), &mut bid.event_seq, clock.unix_timestamp),
+            bid: bid.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Start task execution (by assigned robot) and its payment stream,
+    /// returning the accepted bid's bond
+    pub fn start_task(ctx: Context<StartTask>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let mut task = ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.status() == TaskStatus::Assigned, ErrorCode::TaskNotAssigned);
+        require!(
+            task.assigned_robot == ctx.accounts.robot.key(),
+            ErrorCode::NotAssignedRobot
+        );
+
+        task.set_status(TaskStatus::InProgress);
+        task.started_at = clock.unix_timestamp;
+        return_bid_bond(&mut ctx.accounts.bid, &ctx.accounts.operator)?;
+
+        let (creator, index, bump) = task_seeds(&task);
+        drop(task);
+        let seeds = &[b"task".as_ref(), creator.as_ref(), &index, &bump];
+        payment_streams::cpi::start_stream_by_task(CpiContext::new_with_signer(
+            ctx.accounts.payment_streams_program.to_account_info(),
+            payment_streams::cpi::accounts::StartStreamByTask {
+                config: ctx.accounts.stream_config.to_account_info(),
+                stream: ctx.accounts.stream.to_account_info(),
+                task_authority: ctx.accounts.task.to_account_info(),
+                event_authority: ctx.accounts.stream_event_authority.to_account_info(),
+                program: ctx.accounts.payment_streams_program.to_account_info(),
+            },
+            &[&seeds[..]],
+        ))?;
+
+        let task = &mut ctx.accounts.task.load_mut()?;
+        emit_cpi!(TaskStarted {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            robot: ctx.accounts.robot.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Forfeit the bond of an accepted bid to the task's creator once the
+    /// robot has failed to start within the market's start window
+    /// (permissionless)
+    pub fn forfeit_bid_bond(ctx: Context<ForfeitBidBond>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &ctx.accounts.task.load()?;
+        let bid = &mut ctx.accounts.bid;
+        let clock = Clock::get()?;
+
+        require!(task.status() == TaskStatus::Assigned, ErrorCode::TaskNotAssigned);
+        require!(bid.status == BidStatus::Accepted, ErrorCode::BidNotAccepted);
+        let start_window = ctx.accounts.market.start_window;
+        let start_by = task.assigned_at + start_window;
+        require!(
+            start_window > 0 && clock.unix_timestamp >= start_by,
+            ErrorCode::StartWindowOpen
+        );
+
+        let amount = bid.bond;
+        return_bid_bond(bid, &ctx.accounts.creator)?;
+
+        emit_cpi!(BidBondForfeited {
+            header: event_header(bid.key(), &mut bid.event_seq, clock.unix_timestamp),
+            task: task_key,
+            bid: bid.key(),
+            creator: task.creator,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Update task progress. `proof`, a verified oracle-verifier GPS or
+    /// sensor proof for this task taken since it started, backs the
+    /// checkpoint and is kept as the last proof of the milestone in progress
+    /// (or of the task, without milestones). Progress above
+    /// `PROGRESS_PROOF_THRESHOLD` requires one.
+    pub fn update_progress(ctx: Context<ExecuteTask>, progress: u8) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.status() == TaskStatus::InProgress, ErrorCode::TaskNotInProgress);
+        require!(
+            task.assigned_robot == ctx.accounts.robot.key(),
+            ErrorCode::NotAssignedRobot
+        );
+        require!(progress <= 100, ErrorCode::InvalidProgress);
+
+        let proof = match ctx.accounts.proof.as_ref() {
+            Some(proof) => {
+                let parsed = read_task_proof(proof, task_key, ctx.accounts.robot.key())?;
+                require!(parsed.status == ORACLE_PROOF_VERIFIED, ErrorCode::ProofNotVerified);
+                require!(parsed.timestamp >= task.started_at, ErrorCode::ProofMismatch);
+                Some(proof.key())
+            }
+            None => {
+                require!(progress <= PROGRESS_PROOF_THRESHOLD, ErrorCode::ProofRequired);
+                None
+            }
+        };
+
+        task.progress = progress;
+        if let Some(proof) = proof {
+            match task
+                .milestones_mut()
+                .iter_mut()
+                .find(|m| m.status() == MilestoneStatus::Pending)
+            {
+                Some(milestone) => milestone.last_proof = proof,
+                None => task.progress_proof = proof,
+            }
+        }
+
+        emit_cpi!(TaskProgressUpdated {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            progress,
+            proof,
+        });
+
+        Ok(())
+    }
+
+    /// Complete the task (by robot), pausing its stream until verified. The
+    /// SHA-256 hash of the deliverable, and optionally where to fetch it, are
+    /// recorded on the task for verification and any dispute.
+    pub fn complete_task(
+        ctx: Context<ExecuteTaskStream>,
+        deliverable_hash: [u8; 32],
+        deliverable_uri: Option<String>,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let mut task = ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.status() == TaskStatus::InProgress, ErrorCode::TaskNotInProgress);
+        require!(
+            task.assigned_robot == ctx.accounts.robot.key(),
+            ErrorCode::NotAssignedRobot
+        );
+        require!(
+            deliverable_uri.as_ref().map_or(0, String::len) <= MAX_DELIVERABLE_URI_LEN,
+            ErrorCode::DeliverableUriTooLong
+        );
+
+        task.set_status(TaskStatus::PendingVerification);
+        task.progress = 100;
+        task.deliverable_hash = deliverable_hash;
+        task.set_deliverable_uri(deliverable_uri.as_deref());
+        if task.verification_window > 0 {
+            task.verification_due_at = clock.unix_timestamp + task.verification_window;
+        }
+        let (creator, index, bump) = task_seeds(&task);
+        drop(task);
+
+        // The stream may have run dry and settled already; only a running
+        // stream needs pausing
+        if matches!(
+            stream_status(&ctx.accounts.stream)?,
+            StreamStatus::Active | StreamStatus::Grace
+        ) {
+            let seeds = &[b"task".as_ref(), creator.as_ref(), &index, &bump];
+            payment_streams::cpi::pause_stream_by_task(CpiContext::new_with_signer(
+                ctx.accounts.payment_streams_program.to_account_info(),
+                payment_streams::cpi::accounts::TaskControlStream {
+                    stream: ctx.accounts.stream.to_account_info(),
+                    task_authority: ctx.accounts.task.to_account_info(),
+                    event_authority: ctx.accounts.stream_event_authority.to_account_info(),
+                    program: ctx.accounts.payment_streams_program.to_account_info(),
+                },
+                &[&seeds[..]],
+            ))?;
+        }
+
+        let task = &mut ctx.accounts.task.load_mut()?;
+        emit_cpi!(TaskPendingVerification {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            deliverable_hash,
+            deliverable_uri,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Mark a milestone of a task in progress done (by assigned robot). If the
+    /// milestone requires a proof type, `proof` must be a verified
+    /// oracle-verifier proof of that type for this task.
+    pub fn complete_milestone(ctx: Context<CompleteMilestone>, index: u8) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.status() == TaskStatus::InProgress, ErrorCode::TaskNotInProgress);
+        require!(
+            task.assigned_robot == ctx.accounts.robot.key(),
+            ErrorCode::NotAssignedRobot
+        );
+        let milestone = task
+            .milestones()
+            .get(index as usize)
+            .ok_or(ErrorCode::InvalidMilestone)?;
+        require!(milestone.status() == MilestoneStatus::Pending, ErrorCode::MilestoneNotPending);
+
+        if let Some(proof_type) = milestone.proof_type() {
+            let proof = ctx.accounts.proof.as_ref().ok_or(ErrorCode::ProofRequired)?;
+            let proof = read_task_proof(proof, task_key, ctx.accounts.robot.key())?;
+            require!(proof.proof_type == proof_type, ErrorCode::ProofMismatch);
+            require!(proof.status == ORACLE_PROOF_VERIFIED, ErrorCode::ProofNotVerified);
+        }
+
+        task.milestones[index as usize].set_status(MilestoneStatus::Completed);
+
+        emit_cpi!(TaskMilestoneCompleted {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            index,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Approve a completed milestone (by creator), paying its share of escrow
+    /// to the operator
+    pub fn approve_milestone<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApproveMilestone<'info>>,
+        index: u8,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let clock = Clock::get()?;
+
+        let share_bps = {
+            let task = ctx.accounts.task.load()?;
+            require!(
+                task.status() == TaskStatus::InProgress ||
+                task.status() == TaskStatus::PendingVerification,
+                ErrorCode::TaskNotInProgress
+            );
+            let milestone = task
+                .milestones()
+                .get(index as usize)
+                .ok_or(ErrorCode::InvalidMilestone)?;
+            require!(
+                milestone.status() == MilestoneStatus::Completed,
+                ErrorCode::MilestoneNotCompleted
+            );
+            milestone.share_bps
+        };
+
+        release_task_milestone(&ctx.accounts.task, &ctx.accounts.stream, ctx.remaining_accounts, index)?;
+
+        let task = &mut ctx.accounts.task.load_mut()?;
+        emit_cpi!(TaskMilestoneApproved {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            index,
+            share_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Verify task completion (by creator). Approval settles the task's
+    /// stream, paying any milestones not yet approved, and for geofenced
+    /// tasks requires `gps_proof` from inside the fence and for tasks
+    /// requiring proof a verified `completion_proof`; a dispute leaves it
+    /// paused.
+    pub fn verify_completion<'info>(
+        ctx: Context<'_, '_, '_, 'info, VerifyTask<'info>>,
+        approved: bool,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        {
+            let task = ctx.accounts.task.load()?;
+            require!(task.status() == TaskStatus::PendingVerification, ErrorCode::TaskNotPendingVerification);
+            require!(task.creator == ctx.accounts.creator.key(), ErrorCode::Unauthorized);
+        }
+
+        if approved {
+            if let Some(creator_stake) = ctx.accounts.creator_stake.as_ref() {
+                apply_creator_fee_tier(
+                    &ctx.accounts.task,
+                    &ctx.accounts.stream,
+                    creator_stake,
+                    &ctx.accounts.droneos_token_program,
+                )?;
+            }
+            approve_completion(
+                &ctx.accounts.task,
+                market,
+                &ctx.accounts.stream,
+                ctx.remaining_accounts,
+                &ctx.accounts.operator_stake,
+                &ctx.accounts.droneos_token_program,
+                ctx.accounts.gps_proof.as_ref(),
+                ctx.accounts.completion_proof.as_ref(),
+                clock.unix_timestamp,
+            )?;
+            track_robot_task(
+                market,
+                &ctx.accounts.robot,
+                &ctx.accounts.registry_event_authority,
+                &ctx.accounts.identity_registry_program,
+                false,
+            )?;
+
+            // TODO: Update robot reputation via CPI
+
+            let task = &mut ctx.accounts.task.load_mut()?;
+            emit_cpi!(TaskCompleted {
+                header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+                task: task_key,
+                robot: task.assigned_robot,
+                total_paid: task.reward,
+                timestamp: clock.unix_timestamp,
+            });
+        } else {
+            let task = &mut ctx.accounts.task.load_mut()?;
+            task.set_status(TaskStatus::Disputed);
+            task.dispute_due_at = clock.unix_timestamp + market.dispute_window;
+
+            emit_cpi!(TaskDisputed {
+                header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+                task: task_key,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Approve a completed task its creator has not verified within its
+    /// verification window (permissionless), settling it as an approving
+    /// `verify_completion` would. Disputed tasks are settled by
+    /// `resolve_dispute` instead.
+    pub fn finalize_unverified<'info>(
+        ctx: Context<'_, '_, '_, 'info, FinalizeUnverified<'info>>,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        {
+            let task = ctx.accounts.task.load()?;
+            require!(task.status() == TaskStatus::PendingVerification, ErrorCode::TaskNotPendingVerification);
+            let due_at = task.verification_due_at;
+            require!(
+                due_at != 0 && clock.unix_timestamp >= due_at,
+                ErrorCode::VerificationWindowOpen
+            );
+        }
+
+        approve_completion(
+            &ctx.accounts.task,
+            market,
+            &ctx.accounts.stream,
+            ctx.remaining_accounts,
+            &ctx.accounts.operator_stake,
+            &ctx.accounts.droneos_token_program,
+            ctx.accounts.gps_proof.as_ref(),
+            ctx.accounts.completion_proof.as_ref(),
+            clock.unix_timestamp,
+        )?;
+        track_robot_task(
+            market,
+            &ctx.accounts.robot,
+            &ctx.accounts.registry_event_authority,
+            &ctx.accounts.identity_registry_program,
+            false,
+        )?;
+
+        let task = &mut ctx.accounts.task.load_mut()?;
+        emit_cpi!(TaskCompleted {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            robot: task.assigned_robot,
+            total_paid: task.reward,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Link a disputed task to the oracle-verifier dispute the creator opened
+    /// against its completion proof (by creator)
+    pub fn open_dispute(ctx: Context<OpenDispute>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.status() == TaskStatus::Disputed, ErrorCode::TaskNotDisputed);
+        require!(task.dispute == Pubkey::default(), ErrorCode::DisputeAlreadyOpen);
+
+        let dispute = read_task_dispute(&ctx.accounts.dispute, task_key)?;
+        require!(dispute.challenger == task.creator, ErrorCode::DisputeMismatch);
+
+        task.dispute = ctx.accounts.dispute.key();
+
+        emit_cpi!(TaskDisputeOpened {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            dispute: ctx.accounts.dispute.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Settle a disputed task once its oracle-verifier dispute is resolved
+    /// (permissionless). If the creator's dispute is upheld the task fails:
+    /// its stream is terminated and the operator is slashed up to the task's
+    /// reward, for which this program must be a registered slasher in
+    /// droneos_token. Otherwise the task completes and its stream pays out,
+    /// including any milestones not yet approved.
+    ///
+    /// Once the task's dispute window has passed, a dispute that was never
+    /// opened in oracle-verifier or is still unresolved there settles as
+    /// rejected: the creator didn't make their case in time. `dispute` is
+    /// the linked oracle-verifier dispute, omitted if none was opened.
+    pub fn resolve_dispute<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveTaskDispute<'info>>,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let market = &mut ctx.accounts.market;
+        let clock = Clock::get()?;
+
+        let (expired, linked) = {
+            let task = ctx.accounts.task.load()?;
+            require!(task.status() == TaskStatus::Disputed, ErrorCode::TaskNotDisputed);
+            let due_at = task.dispute_due_at;
+            (due_at != 0 && clock.unix_timestamp >= due_at, key_if_set(task.dispute))
+        };
+
+        let upheld = match (linked, ctx.accounts.dispute.as_ref()) {
+            (Some(linked), Some(dispute)) => {
+                require_keys_eq!(dispute.key(), linked, ErrorCode::DisputeMismatch);
+                match read_task_dispute(dispute, task_key)?.status {
+                    ORACLE_DISPUTE_OPEN => {
+                        require!(expired, ErrorCode::DisputeNotResolved);
+                        false
+                    }
+                    ORACLE_DISPUTE_CHALLENGER_WINS => true,
+                    _ => false,
+                }
+            }
+            (Some(_), None) => return err!(ErrorCode::DisputeMismatch),
+            (None, _) => {
+                require!(expired, ErrorCode::DisputeNotResolved);
+                false
+            }
+        };
+
+        let mut slashed = 0;
+        if upheld {
+            {
+                let mut task = ctx.accounts.task.load_mut()?;
+                task.set_status(TaskStatus::Failed);
+                task.robot_fault = 1;
+            }
+
+            terminate_task_stream(
+                &ctx.accounts.task,
+                &ctx.accounts.stream,
+                ctx.remaining_accounts,
+                "Dispute upheld".to_string(),
+            )?;
+            slashed = slash_task_operator(
+                &ctx.accounts.slash,
+                &ctx.accounts.operator_stake,
+                task_key,
+                &*ctx.accounts.task.load()?,
+                "Lost task dispute".to_string(),
+            )?;
+        } else {
+            {
+                let mut task = ctx.accounts.task.load_mut()?;
+                task.set_status(TaskStatus::Completed);
+                task.completed_at = clock.unix_timestamp;
+
+                market.total_completed += 1;
+                market.total_volume += task.reward;
+            }
+
+            release_outstanding_milestones(
+                &ctx.accounts.task,
+                &ctx.accounts.stream,
+                ctx.remaining_accounts,
+            )?;
+            terminate_task_stream(
+                &ctx.accounts.task,
+                &ctx.accounts.stream,
+                ctx.remaining_accounts,
+                "Task completed".to_string(),
+            )?;
+        }
+        track_operator_task(
+            market,
+            &ctx.accounts.operator_stake,
+            &ctx.accounts.droneos_token_program,
+            false,
+        )?;
+        track_robot_task(
+            market,
+            &ctx.accounts.robot,
+            &ctx.accounts.registry_event_authority,
+            &ctx.accounts.identity_registry_program,
+            false,
+        )?;
+        // A lost dispute is already slashed, so the bond goes back either way
+        let task = &mut ctx.accounts.task.load_mut()?;
+        release_task_bond(
+            market,
+            &ctx.accounts.operator_stake,
+            &ctx.accounts.droneos_token_program,
+            task,
+        )?;
+
+        emit_cpi!(TaskDisputeResolved {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            dispute: task.dispute,
+            upheld,
+            slashed,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a task (before assignment)
+    pub fn cancel_task(ctx: Context<CancelTask>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.creator == ctx.accounts.creator.key(), ErrorCode::Unauthorized);
+        require!(
+            task.status() == TaskStatus::Open,
+            ErrorCode::TaskCannotBeCancelled
+        );
+
+        task.set_status(TaskStatus::Cancelled);
+
+        emit_cpi!(TaskCancelled {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel an assigned or in-progress task (by creator). Its stream pays
+    /// the robot for time elapsed plus the market's cancellation fee share of
+    /// the remaining reward and refunds the rest; the operator's bond is
+    /// released. With `reopen` the task goes back to Open for new bids and
+    /// its settled stream is closed, so the next accepted bid can open one
+    /// at the same address; otherwise it is cancelled.
+    pub fn cancel_assigned_task<'info>(
+        ctx: Context<'_, '_, '_, 'info, CancelAssignedTask<'info>>,
+        reopen: bool,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let clock = Clock::get()?;
+
+        {
+            let task = ctx.accounts.task.load()?;
+            require!(
+                task.status() == TaskStatus::Assigned ||
+                task.status() == TaskStatus::InProgress,
+                ErrorCode::TaskCannotBeCancelled
+            );
+            require!(!reopen || clock.unix_timestamp < task.expires_at, ErrorCode::TaskExpired);
+        }
+
+        let cancellation_fee_bps = ctx.accounts.market.cancellation_fee_bps;
+        cancel_task_stream(
+            &ctx.accounts.task,
+            &ctx.accounts.stream,
+            ctx.remaining_accounts,
+            cancellation_fee_bps,
+        )?;
+        if reopen {
+            // The next accepted bid opens its stream at the same address
+            close_task_stream(&ctx.accounts.task, &ctx.accounts.stream, &ctx.accounts.creator)?;
+        }
+        track_operator_task(
+            &ctx.accounts.market,
+            &ctx.accounts.operator_stake,
+            &ctx.accounts.droneos_token_program,
+            false,
+        )?;
+        track_robot_task(
+            &ctx.accounts.market,
+            &ctx.accounts.robot,
+            &ctx.accounts.registry_event_authority,
+            &ctx.accounts.identity_registry_program,
+            false,
+        )?;
+        let task = &mut ctx.accounts.task.load_mut()?;
+        release_task_bond(
+            &ctx.accounts.market,
+            &ctx.accounts.operator_stake,
+            &ctx.accounts.droneos_token_program,
+            task,
+        )?;
+
+        let robot = key_if_set(task.assigned_robot).ok_or(ErrorCode::NotAssignedRobot)?;
+        if reopen {
+            task.set_status(TaskStatus::Open);
+            task.assigned_robot = Pubkey::default();
+            task.assigned_operator = Pubkey::default();
+            task.assigned_at = 0;
+            task.started_at = 0;
+            task.stream_id = Pubkey::default();
+            task.progress = 0;
+            task.sla_window = 0;
+            task.sla_breached = 0;
+            task.progress_proof = Pubkey::default();
+            for milestone in task.milestones_mut() {
+                milestone.set_status(MilestoneStatus::Pending);
+                milestone.last_proof = Pubkey::default();
+            }
+        } else {
+            task.set_status(TaskStatus::Cancelled);
+        }
+
+        emit_cpi!(AssignedTaskCancelled {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            robot,
+            cancellation_fee_bps,
+            reopened: reopen,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel an open task whose bidding window has passed (permissionless).
+    /// Open tasks have no escrow yet, it is only funded on bid acceptance.
+    pub fn expire_task(ctx: Context<ExpireTask>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+        require!(clock.unix_timestamp >= task.expires_at, ErrorCode::TaskNotExpired);
+
+        task.set_status(TaskStatus::Cancelled);
+
+        emit_cpi!(TaskExpired {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Flag a task in progress past its SLA deadline (permissionless). The
+    /// market's late penalty share of the task's bond goes to the creator;
+    /// tasks without a bond are only marked. A task is flagged at most once.
+    pub fn flag_late(ctx: Context<FlagLate>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.status() == TaskStatus::InProgress, ErrorCode::TaskNotInProgress);
+        require!(task.sla_breached == 0, ErrorCode::AlreadyFlaggedLate);
+        let deadline = task.deadline().ok_or(ErrorCode::TaskNotLate)?;
+        require!(clock.unix_timestamp > deadline, ErrorCode::TaskNotLate);
+
+        let penalty =
+            (task.bond as u128 * ctx.accounts.market.late_penalty_bps as u128 / 10_000) as u64;
+        forfeit_task_bond(
+            &ctx.accounts.market,
+            &ctx.accounts.operator_stake,
+            &ctx.accounts.bond,
+            &ctx.accounts.droneos_token_program,
+            task_key,
+            task,
+            penalty,
+        )?;
+        task.sla_breached = 1;
+
+        emit_cpi!(SlaBreached {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            deadline,
+            penalty,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close a completed, failed or cancelled task, returning its rent to the
+    /// creator. Remaining accounts are `(bid, operator)` pairs of the task's
+    /// bids to close too, each returning its rent to the bidding operator.
+    pub fn close_task<'info>(ctx: Context<'_, '_, 'info, 'info, CloseTask<'info>>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(
+            matches!(task.status(),
+                TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Cancelled
+            ),
+            ErrorCode::TaskNotFinished
+        );
+
+        let pairs = ctx.remaining_accounts;
+        require!(pairs.chunks_exact(2).remainder().is_empty(), ErrorCode::InvalidBidAccounts);
+
+        let mut bids_closed: u16 = 0;
+        for pair in pairs.chunks_exact(2) {
+            let (bid_info, operator) = (&pair[0], &pair[1]);
+            require!(bid_info.is_writable && operator.is_writable, ErrorCode::InvalidBidAccounts);
+
+            let bid = Account::<Bid>::try_from(bid_info)?;
+            require!(bid.task == task_key, ErrorCode::BidTaskMismatch);
+            require!(bid.operator == operator.key(), ErrorCode::Unauthorized);
+
+            bid.close(operator.clone())?;
+            bids_closed += 1;
+        }
+
+        emit_cpi!(TaskClosed {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            bids_closed,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Abort a task in progress (emergency), settling its stream. An abort by
+    /// the assigned robot is its own fault, as is a creator abort of a task
+    /// flagged late: the performance bond then goes to the creator as
+    /// compensation and the robot loses `ABORT_REPUTATION_PENALTY`
+    /// reputation, and on a creator abort the operator is also slashed as
+    /// for a lost dispute. Otherwise the bond is released.
+    pub fn abort_task<'info>(
+        ctx: Context<'_, '_, '_, 'info, AbortTask<'info>>,
+        reason: String,
+    ) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let clock = Clock::get()?;
+
+        {
+            let mut task = ctx.accounts.task.load_mut()?;
+            require!(reason.len() <= 128, ErrorCode::MessageTooLong);
+            require!(
+                task.creator == ctx.accounts.authority.key() || 
+                task.assigned_robot == ctx.accounts.authority.key(),
+                ErrorCode::Unauthorized
+            );
+            require!(
+                task.status() == TaskStatus::Assigned || 
+                task.status() == TaskStatus::InProgress,
+                ErrorCode::TaskCannotBeAborted
+            );
+
+            task.set_status(TaskStatus::Failed);
+        }
+
+        terminate_task_stream(
+            &ctx.accounts.task,
+            &ctx.accounts.stream,
+            ctx.remaining_accounts,
+            reason.clone(),
+        )?;
+        track_operator_task(
+            &ctx.accounts.market,
+            &ctx.accounts.operator_stake,
+            &ctx.accounts.droneos_token_program,
+            false,
+        )?;
+        track_robot_task(
+            &ctx.accounts.market,
+            &ctx.accounts.robot,
+            &ctx.accounts.registry_event_authority,
+            &ctx.accounts.identity_registry_program,
+            false,
+        )?;
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let by_robot = task.assigned_robot == ctx.accounts.authority.key();
+        let robot_fault = by_robot || task.sla_breached != 0;
+        task.robot_fault = robot_fault as u8;
+        if robot_fault {
+            let bond = task.bond;
+            forfeit_task_bond(
+                &ctx.accounts.market,
+                &ctx.accounts.operator_stake,
+                &ctx.accounts.bond,
+                &ctx.accounts.droneos_token_program,
+                task_key,
+                task,
+                bond,
+            )?;
+        } else {
+            release_task_bond(
+                &ctx.accounts.market,
+                &ctx.accounts.operator_stake,
+                &ctx.accounts.droneos_token_program,
+                task,
+            )?;
+        }
+
+        let mut slashed = 0;
+        if robot_fault {
+            if !by_robot {
+                slashed = slash_task_operator(
+                    &ctx.accounts.slash,
+                    &ctx.accounts.operator_stake,
+                    task_key,
+                    task,
+                    "Task aborted for robot fault".to_string(),
+                )?;
+            }
+            penalize_robot(
+                &ctx.accounts.market,
+                &ctx.accounts.robot,
+                &ctx.accounts.registry_event_authority,
+                &ctx.accounts.identity_registry_program,
+            )?;
+        }
+
+        emit_cpi!(TaskAborted {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            reason,
+            robot_fault,
+            slashed,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pay an insured task's creator from droneos_token's insurance pool once
+    /// the task failed through its robot's fault: a robot abort or an upheld
+    /// dispute (permissionless). Pays once, covering the creator's loss:
+    /// what the stream paid the operator, less any bond forfeited to the
+    /// creator, up to the insured amount.
+    pub fn claim_task_insurance(ctx: Context<ClaimTaskInsurance>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(task.insured_amount > 0, ErrorCode::TaskNotInsured);
+        require!(
+            task.status() == TaskStatus::Failed && task.robot_fault != 0,
+            ErrorCode::NotRobotFault
+        );
+        require!(task.insurance_claimed == 0, ErrorCode::InsuranceAlreadyClaimed);
+        require_keys_eq!(
+            ctx.accounts.creator_token.owner,
+            task.creator,
+            ErrorCode::Unauthorized
+        );
+
+        let loss = load_stream(&ctx.accounts.stream)?
+            .total_paid
+            .saturating_sub(task.bond_forfeited);
+        let covered = loss.min(task.insured_amount);
+        require!(covered > 0, ErrorCode::NoInsuredLoss);
+
+        let seeds = &[b"market".as_ref(), &[ctx.accounts.market.bump]];
+        let paid = droneos_token::cpi::pay_task_insurance(
+            CpiContext::new_with_signer(
+                ctx.accounts.droneos_token_program.to_account_info(),
+                droneos_token::cpi::accounts::PayTaskInsurance {
+                    config: ctx.accounts.token_config.to_account_info(),
+                    insurance_pool: ctx.accounts.insurance_pool.to_account_info(),
+                    insurance_vault: ctx.accounts.insurance_vault.to_account_info(),
+                    creator_token: ctx.accounts.creator_token.to_account_info(),
+                    market: ctx.accounts.market.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    event_authority: ctx.accounts.token_event_authority.to_account_info(),
+                    program: ctx.accounts.droneos_token_program.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            covered,
+            task_key,
+        )?
+        .get();
+        task.insurance_claimed = 1;
+
+        emit_cpi!(TaskInsuranceClaimed {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            paid,
+        });
+
+        Ok(())
+    }
+
+    /// Create the discovery index of tasks for a robot class or capability,
+    /// with its first page (permissionless)
+    pub fn create_task_index(
+        ctx: Context<CreateTaskIndex>,
+        kind: TaskIndexKind,
+        key: u8,
+    ) -> Result<()> {
+        match kind {
+            TaskIndexKind::Class => RobotClass::try_from_slice(&[key])
+                .map(|_| ())
+                .map_err(|_| error!(ErrorCode::InvalidRobotClass))?,
+            TaskIndexKind::Capability => Capability::try_from_slice(&[key])
+                .map(|_| ())
+                .map_err(|_| error!(ErrorCode::InvalidCapability))?,
+        }
+
+        let index = &mut ctx.accounts.index;
+        index.kind = kind;
+        index.key = key;
+        index.pages = 1;
+        index.bump = ctx.bumps.index;
+
+        let page = &mut ctx.accounts.page;
+        page.index = index.key();
+        page.page = 0;
+        page.tasks = Vec::new();
+        page.open = 0;
+        page.boosted = 0;
+        page.bump = ctx.bumps.page;
+
+        Ok(())
+    }
+
+    /// Add a page to a task index whose last page is full (permissionless)
+    pub fn add_task_index_page(ctx: Context<AddTaskIndexPage>) -> Result<()> {
+        require!(
+            ctx.accounts.last_page.tasks.len() == TASK_INDEX_PAGE_SIZE,
+            ErrorCode::TaskIndexPageNotFull
+        );
+
+        let index = &mut ctx.accounts.index;
+        let page = &mut ctx.accounts.page;
+        page.index = index.key();
+        page.page = index.pages;
+        page.tasks = Vec::new();
+        page.open = 0;
+        page.boosted = 0;
+        page.bump = ctx.bumps.page;
+
+        index.pages += 1;
+
+        Ok(())
+    }
+
+    /// Append an open task to the last page of an index for its robot class
+    /// or one of its capabilities (permissionless), marked open. A task is
+    /// indexed at most once per index.
+    pub fn index_task(ctx: Context<IndexTask>) -> Result<()> {
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let index = &ctx.accounts.index;
+        let page = &mut ctx.accounts.page;
+        let clock = Clock::get()?;
+
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+        // Bit 0 for the class index, bit 1 + i for capability i
+        let flag = match index.kind {
+            TaskIndexKind::Class if task.robot_class == index.key => 1u8,
+            TaskIndexKind::Capability => task
+                .required_capabilities()
+                .iter()
+                .position(|&capability| capability == index.key)
+                .map(|i| 2u8 << i)
+                .ok_or(ErrorCode::TaskIndexMismatch)?,
+            _ => return err!(ErrorCode::TaskIndexMismatch),
+        };
+        require!(task.indexed & flag == 0, ErrorCode::TaskAlreadyIndexed);
+        require!(page.tasks.len() < TASK_INDEX_PAGE_SIZE, ErrorCode::TaskIndexPageFull);
+
+        let slot = page.tasks.len() as u8;
+        page.tasks.push(task_key);
+        page.open |= 1 << slot;
+        if task.is_boosted(clock.unix_timestamp) {
+            page.boosted |= 1 << slot;
+        }
+        task.indexed |= flag;
+
+        emit_cpi!(TaskIndexed {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            index: index.key(),
+            page: page.key(),
+            slot,
+        });
+
+        Ok(())
+    }
+
+    /// Refresh a task's open and boosted bits in an index page
+    /// (permissionless): open while the task is Open, cleared once it is
+    /// assigned, finished or closed; boosted while it is also boosted
+    pub fn sync_task_index(ctx: Context<SyncTaskIndex>, slot: u8) -> Result<()> {
+        let page = &mut ctx.accounts.page;
+        let task = &ctx.accounts.task;
+        let clock = Clock::get()?;
+
+        require!(
+            page.tasks.get(slot as usize) == Some(&task.key()),
+            ErrorCode::TaskIndexMismatch
+        );
+
+        // The task may have been closed since it was indexed
+        let data = task.try_borrow_data()?;
+        let task = (task.owner == &crate::ID && data.starts_with(&Task::DISCRIMINATOR))
+            .then(|| data.get(8..8 + std::mem::size_of::<Task>()))
+            .flatten()
+            .and_then(|data| bytemuck::try_from_bytes::<Task>(data).ok());
+        let open = task.as_ref().is_some_and(|task| task.status() == TaskStatus::Open);
+        if open {
+            page.open |= 1 << slot;
+        } else {
+            page.open &= !(1 << slot);
+        }
+
+        let boosted = open && task.is_some_and(|task| task.is_boosted(clock.unix_timestamp));
+        if boosted {
+            page.boosted |= 1 << slot;
+        } else {
+            page.boosted &= !(1 << slot);
+        }
+
+        Ok(())
+    }
+
+    /// Boost an open task for `duration` seconds (by creator), paying the
+    /// market's hourly boost fee to its treasury. Boosted tasks are flagged
+    /// in task index pages for discovery tooling to list first; boosting a
+    /// boosted task extends it.
+    pub fn boost_task(ctx: Context<BoostTask>, duration: i64) -> Result<()> {
+        let market = &ctx.accounts.market;
+        let task_key = ctx.accounts.task.key();
+        let task = &mut ctx.accounts.task.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(market.treasury != Pubkey::default(), ErrorCode::BoostingDisabled);
+        require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+        require!(duration > 0, ErrorCode::InvalidBoostDuration);
+
+        let fee = (market.boost_fee_per_hour as u128 * duration as u128 / 3600) as u64;
+        if fee > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.creator.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+        }
+
+        let boosted_until = task.boosted_until.max(clock.unix_timestamp) + duration;
+        task.boosted_until = boosted_until;
+
+        emit_cpi!(TaskBoosted {
+            header: event_header(task_key, &mut task.event_seq, clock.unix_timestamp),
+            task: task_key,
+            fee,
+            boosted_until,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn event_header(entity: Pubkey, seq: &mut u64, timestamp: i64) -> EventHeader {
+    EventHeader::next(ProgramTag::TaskMarket, entity, seq, timestamp)
+}
+
+/// Fill a newly initialized task from `params` and open it for bids. The
+/// account starts zeroed, so fields left unset are already none.
+fn open_task(
+    task: &mut Task,
+    market: &mut Market,
+    creator: Pubkey,
+    params: TaskParams,
+    bump: u8,
+    now: i64,
+) {
+    task.creator = creator;
+    task.set_title(&params.title);
+    task.set_description(&params.description);
+    task.robot_class = params.robot_class;
+    task.set_required_capabilities(&params.capabilities);
+    task.min_reputation = params.min_reputation;
+    task.reward = params.reward;
+    task.rate_per_second = params.rate_per_second;
+    task.estimated_duration = params.estimated_duration;
+    task.priority = params.priority;
+    task.set_status(TaskStatus::Open);
+    task.created_at = now;
+    task.expires_at = now + params.expires_in;
+    task.verification_window = market.verification_window;
+    task.requires_proof = params.requires_proof as u8;
+    task.index = market.total_tasks;
+    task.bump = bump;
+
+    market.total_tasks += 1;
+}
+
+/// Arguments of swarm-coordinator's `create_market_group_task`
+#[derive(AnchorSerialize)]
+struct CreateMarketGroupTaskArgs {
+    title: String,
+    description: String,
+    required_robots: u8,
+    total_reward: u64,
+    duration_seconds: i64,
+}
+
+/// Premium for insuring `insured_amount` of a task assigned to a robot with
+/// `reputation`. The market rate applies to a robot with no reputation and
+/// falls linearly to half of it for a perfect one.
+fn insurance_premium(market: &Market, insured_amount: u64, reputation: u16) -> u64 {
+    let rate_bps =
+        market.insurance_premium_bps as u128 * (20_000 - reputation.min(10_000) as u128)
+            / 20_000;
+    (insured_amount as u128 * rate_bps / 10_000) as u64
+}
+
+/// Pay the premium for `insured_amount` of `task`, priced on the assigned
+/// robot's `reputation`, from the creator into droneos_token's insurance
+/// pool.
+/// Returns the premium.
+fn pay_insurance_premium(
+    accounts: &AcceptBid,
+    task: Pubkey,
+    insured_amount: u64,
+    reputation: u16,
+) -> Result<u64> {
+    let (
+        Some(token_config),
+        Some(insurance_pool),
+        Some(insurance_vault),
+        Some(premium_token),
+        Some(premium_mint),
+        Some(premium_token_program),
+        Some(token_event_authority),
+    ) = (
+        &accounts.token_config,
+        &accounts.insurance_pool,
+        &accounts.insurance_vault,
+        &accounts.premium_token,
+        &accounts.premium_mint,
+        &accounts.premium_token_program,
+        &accounts.token_event_authority,
+    )
+    else {
+        return err!(ErrorCode::InsuranceAccountsRequired);
+    };
+
+    let premium = insurance_premium(&accounts.market, insured_amount, reputation);
+    if premium > 0 {
+        droneos_token::cpi::pay_insurance_premium(
+            CpiContext::new(
+                accounts.droneos_token_program.to_account_info(),
+                droneos_token::cpi::accounts::PayInsurancePremium {
+                    config: token_config.to_account_info(),
+                    insurance_pool: insurance_pool.to_account_info(),
+                    insurance_vault: insurance_vault.to_account_info(),
+                    payer_token: premium_token.to_account_info(),
+                    payer: accounts.creator.to_account_info(),
+                    mint: premium_mint.to_account_info(),
+                    token_program: premium_token_program.to_account_info(),
+                    event_authority: token_event_authority.to_account_info(),
+                    program: accounts.droneos_token_program.to_account_info(),
+                },
+            ),
+            premium,
+            task,
+        )?;
+    }
+
+    Ok(premium)
+}
+
+/// Create the swarm-coordinator group task for a new task opened from
+/// `params` and needing `required_robots` robots, signed by the task (whose
+/// PDA bump is `bump`), returning its address
+fn create_group_task(
+    accounts: &CreateTask,
+    params: &TaskParams,
+    bump: u8,
+    required_robots: u8,
+) -> Result<Pubkey> {
+    let (
+        Some(coordinator),
+        Some(group_task),
+        Some(event_authority),
+        Some(swarm_coordinator_program),
+    ) = (
+        &accounts.coordinator,
+        &accounts.group_task,
+        &accounts.swarm_event_authority,
+        &accounts.swarm_coordinator_program,
+    )
+    else {
+        return err!(ErrorCode::SwarmAccountsRequired);
+    };
+    let task = &accounts.task;
+
+    let mut data = hashv(&[b"global:create_market_group_task"]).to_bytes()[..8].to_vec();
+    CreateMarketGroupTaskArgs {
+        title: params.title.clone(),
+        description: params.description.clone(),
+        required_robots,
+        total_reward: params.reward,
+        duration_seconds: params.estimated_duration as i64,
+    }
+    .serialize(&mut data)?;
+    let ix = Instruction {
+        program_id: SWARM_COORDINATOR_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(coordinator.key(), false),
+            AccountMeta::new(group_task.key(), false),
+            AccountMeta::new(accounts.creator.key(), true),
+            AccountMeta::new_readonly(task.key(), true),
+            AccountMeta::new_readonly(accounts.system_program.key(), false),
+            AccountMeta::new_readonly(event_authority.key(), false),
+            AccountMeta::new_readonly(SWARM_COORDINATOR_PROGRAM_ID, false),
+        ],
+        data,
+    };
+
+    let index = accounts.market.total_tasks.to_le_bytes();
+    let seeds = &[b"task".as_ref(), accounts.creator.key.as_ref(), &index, &[bump]];
+    invoke_signed(
+        &ix,
+        &[
+            coordinator.to_account_info(),
+            group_task.to_account_info(),
+            accounts.creator.to_account_info(),
+            task.to_account_info(),
+            accounts.system_program.to_account_info(),
+            event_authority.to_account_info(),
+            swarm_coordinator_program.to_account_info(),
+        ],
+        &[&seeds[..]],
+    )?;
+
+    Ok(group_task.key())
+}
+
+/// Require the task to be open for bids and the robot to meet its
+/// requirements (checked by identity-registry CPI) and fleet bond. The robot's
+/// operator is checked by the account constraint.
+/// Move the market's bid bond from the operator into a new bid account
+fn hold_bid_bond<'info>(
+    bid: &mut Account<'info, Bid>,
+    operator: &Signer<'info>,
+    market: &Market,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    bid.bond = market.bid_bond_lamports;
+    if bid.bond == 0 {
+        return Ok(());
+    }
+
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            Transfer {
+                from: operator.to_account_info(),
+                to: bid.to_account_info(),
+            },
+        ),
+        bid.bond,
+    )
+}
+
+/// Close `(bid, operator)` pairs of a task's bids other than the accepted
+/// one (`accepted`, if its status is not yet written), returning each bid's
+/// rent and bond to its operator. Returns the number of bids closed.
+fn close_losing_bids<'info>(
+    task: Pubkey,
+    accepted: Option<Pubkey>,
+    pairs: &'info [AccountInfo<'info>],
+) -> Result<u16> {
+    require!(pairs.chunks_exact(2).remainder().is_empty(), ErrorCode::InvalidBidAccounts);
+
+    let mut bids_closed: u16 = 0;
+    for pair in pairs.chunks_exact(2) {
+        let (bid_info, operator) = (&pair[0], &pair[1]);
+        require!(bid_info.is_writable && operator.is_writable, ErrorCode::InvalidBidAccounts);
+        require!(accepted != Some(bid_info.key()), ErrorCode::InvalidBidAccounts);
+
+        let bid = Account::<Bid>::try_from(bid_info)?;
+        require!(bid.task == task, ErrorCode::BidTaskMismatch);
+        require!(bid.operator == operator.key(), ErrorCode::Unauthorized);
+        require!(bid.status != BidStatus::Accepted, ErrorCode::InvalidBidAccounts);
+
+        bid.close(operator.clone())?;
+        bids_closed += 1;
+    }
+
+    Ok(bids_closed)
+}
+
+/// Pay a bid's bond out of the bid account to `to`: the operator when it is
+/// returned, the creator when forfeited
+fn return_bid_bond<'info>(bid: &mut Account<'info, Bid>, to: &AccountInfo<'info>) -> Result<()> {
+    let bond = std::mem::take(&mut bid.bond);
+    if bond > 0 {
+        bid.sub_lamports(bond)?;
+        to.add_lamports(bond)?;
+    }
+    Ok(())
+}
+
+fn check_bidder<'info>(
+    accounts: &SubmitBid<'info>,
+    now: i64,
+    allowlist_proof: &[[u8; 32]],
+) -> Result<()> {
+    let task = accounts.task.load()?;
+    require!(task.status() == TaskStatus::Open, ErrorCode::TaskNotOpen);
+    require!(now < task.expires_at, ErrorCode::TaskExpired);
+    require!(task.group_task == Pubkey::default(), ErrorCode::SwarmTask);
+    require!(
+        task.is_allowlisted(&accounts.robot.key(), allowlist_proof) ||
+        task.is_allowlisted(&accounts.operator.key(), allowlist_proof),
+        ErrorCode::NotAllowlisted
+    );
+    if accounts.creator_blacklist.owner == &crate::ID {
+        let blacklist =
+            Blacklist::try_deserialize(&mut &accounts.creator_blacklist.try_borrow_data()?[..])?;
+        require!(
+            !blacklist.members.contains(&accounts.robot.key()) &&
+            !blacklist.members.contains(&accounts.operator.key()),
+            ErrorCode::Blacklisted
+        );
+    }
+
+    identity_registry::cpi::verify_requirements(
+        CpiContext::new(
+            accounts.identity_registry_program.to_account_info(),
+            identity_registry::cpi::accounts::ReadRobot {
+                robot: accounts.robot.to_account_info(),
+            },
+        ),
+        robot_requirements(task.robot_class, task.required_capabilities(), task.min_reputation)?,
+    )?;
+
+    check_fleet_bond(
+        &accounts.market,
+        &accounts.operator_stake,
+        &accounts.operator_fleet,
+        &accounts.identity_registry_program,
+    )
+}
+
+/// Report an operator taking on (`started`) or finishing a task to the token
+/// program, signed by the market PDA, so their operator stake can't be
+/// withdrawn mid-task. Operators without an operator stake are skipped.
+fn track_operator_task<'info>(
+    market: &Account<'info, Market>,
+    operator_stake: &AccountInfo<'info>,
+    droneos_token_program: &Program<'info, DroneosToken>,
+    started: bool,
+) -> Result<()> {
+    if operator_stake.owner != &droneos_token::ID {
+        return Ok(());
+    }
+
+    let seeds = &[b"market".as_ref(), &[market.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        droneos_token_program.to_account_info(),
+        droneos_token::cpi::accounts::OperatorTask {
+            operator_stake: operator_stake.clone(),
+            market: market.to_account_info(),
+        },
+        signer,
+    );
+    if started {
+        droneos_token::cpi::begin_operator_task(cpi_ctx)
+    } else {
+        droneos_token::cpi::end_operator_task(cpi_ctx)
+    }
+}
+
+/// Mark a task's robot busy with it (`started`) or available again in
+/// identity-registry, signed by the market PDA, so no robot is assigned
+/// overlapping tasks
+fn track_robot_task<'info>(
+    market: &Account<'info, Market>,
+    robot: &AccountInfo<'info>,
+    registry_event_authority: &AccountInfo<'info>,
+    identity_registry_program: &Program<'info, IdentityRegistry>,
+    started: bool,
+) -> Result<()> {
+    let seeds = &[b"market".as_ref(), &[market.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        identity_registry_program.to_account_info(),
+        identity_registry::cpi::accounts::RobotTask {
+            robot: robot.clone(),
+            market: market.to_account_info(),
+            event_authority: registry_event_authority.clone(),
+            program: identity_registry_program.to_account_info(),
+        },
+        signer,
+    );
+    if started {
+        identity_registry::cpi::begin_robot_task(cpi_ctx)
+    } else {
+        identity_registry::cpi::end_robot_task(cpi_ctx)
+    }
+}
+
+/// Lower a robot's identity-registry reputation by `ABORT_REPUTATION_PENALTY`
+/// for a task aborted through its fault, signed by the market PDA
+fn penalize_robot<'info>(
+    market: &Account<'info, Market>,
+    robot: &AccountInfo<'info>,
+    registry_event_authority: &AccountInfo<'info>,
+    identity_registry_program: &Program<'info, IdentityRegistry>,
+) -> Result<()> {
+    let seeds = &[b"market".as_ref(), &[market.bump]];
+    identity_registry::cpi::update_reputation(
+        CpiContext::new_with_signer(
+            identity_registry_program.to_account_info(),
+            identity_registry::cpi::accounts::RobotTask {
+                robot: robot.clone(),
+                market: market.to_account_info(),
+                event_authority: registry_event_authority.clone(),
+                program: identity_registry_program.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        -ABORT_REPUTATION_PENALTY,
+        false,
+        0,
+    )
+}
+
+/// Hold `bond` of the operator's stake for a task via CPI, signed by the
+/// market PDA. Operators without an operator stake can't take bonded tasks.
+fn lock_task_bond<'info>(
+    market: &Account<'info, Market>,
+    operator_stake: &AccountInfo<'info>,
+    droneos_token_program: &Program<'info, DroneosToken>,
+    bond: u64,
+) -> Result<()> {
+    require!(operator_stake.owner == &droneos_token::ID, ErrorCode::TaskBondRequired);
+
+    let seeds = &[b"market".as_ref(), &[market.bump]];
+    droneos_token::cpi::lock_task_bond(
+        CpiContext::new_with_signer(
+            droneos_token_program.to_account_info(),
+            droneos_token::cpi::accounts::OperatorTask {
+                operator_stake: operator_stake.clone(),
+                market: market.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        bond,
+    )
+}
+
+/// Release a settled task's performance bond, if it has one
+fn release_task_bond<'info>(
+    market: &Account<'info, Market>,
+    operator_stake: &AccountInfo<'info>,
+    droneos_token_program: &Program<'info, DroneosToken>,
+    task: &mut Task,
+) -> Result<()> {
+    if task.bond == 0 {
+        return Ok(());
+    }
+
+    let seeds = &[b"market".as_ref(), &[market.bump]];
+    droneos_token::cpi::release_task_bond(
+        CpiContext::new_with_signer(
+            droneos_token_program.to_account_info(),
+            droneos_token::cpi::accounts::OperatorTask {
+                operator_stake: operator_stake.clone(),
+                market: market.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        task.bond,
+    )?;
+    task.bond = 0;
+
+    Ok(())
+}
+
+/// Pay `amount` of a task's performance bond from the operator's stake to
+/// the creator
+fn forfeit_task_bond<'info>(
+    market: &Account<'info, Market>,
+    operator_stake: &AccountInfo<'info>,
+    forfeit: &BondForfeit<'info>,
+    droneos_token_program: &Program<'info, DroneosToken>,
+    task_key: Pubkey,
+    task: &mut Task,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    require_keys_eq!(forfeit.creator_token.owner, task.creator, ErrorCode::Unauthorized);
+
+    let seeds = &[b"market".as_ref(), &[market.bump]];
+    droneos_token::cpi::forfeit_task_bond(
+        CpiContext::new_with_signer(
+            droneos_token_program.to_account_info(),
+            droneos_token::cpi::accounts::ForfeitTaskBond {
+                config: forfeit.token_config.to_account_info(),
+                operator_stake: operator_stake.clone(),
+                operator_vault: forfeit.operator_vault.to_account_info(),
+                creator_token: forfeit.creator_token.to_account_info(),
+                market: market.to_account_info(),
+                mint: forfeit.mint.to_account_info(),
+                token_program: forfeit.token_program.to_account_info(),
+                event_authority: forfeit.token_event_authority.to_account_info(),
+                program: droneos_token_program.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+        task_key,
+    )?;
+    task.bond -= amount;
+    task.bond_forfeited += amount;
+
+    Ok(())
+}
+
+/// Require the operator's slashable bond (own plus delegated stake) to cover
+/// `min_stake_per_robot` for each robot they have registered, counted by
+/// identity-registry CPI. Operators without a fleet account count as zero.
+fn check_fleet_bond<'info>(
+    market: &Account<'info, Market>,
+    operator_stake: &AccountInfo<'info>,
+    operator_fleet: &AccountInfo<'info>,
+    identity_registry_program: &Program<'info, IdentityRegistry>,
+) -> Result<()> {
+    if market.min_stake_per_robot == 0 || operator_fleet.owner != &identity_registry::ID {
+        return Ok(());
+    }
+
+    let robots = identity_registry::cpi::get_fleet_size(CpiContext::new(
+        identity_registry_program.to_account_info(),
+        identity_registry::cpi::accounts::ReadFleet { fleet: operator_fleet.clone() },
+    ))?
+    .get();
+    let required = market
+        .min_stake_per_robot
+        .checked_mul(robots as u64)
+        .ok_or(ErrorCode::InsufficientFleetBond)?;
+
+    let bond = if operator_stake.owner == &droneos_token::ID {
+        let stake = droneos_token::OperatorStake::try_deserialize(
+            &mut &operator_stake.try_borrow_data()?[..],
+        )?;
+        stake.slashable_amount.saturating_add(stake.delegated_amount)
+    } else {
+        0
+    };
+    require!(bond >= required, ErrorCode::InsufficientFleetBond);
+
+    Ok(())
+}
+
+/// `DisputeStatus` tags of oracle-verifier disputes
+const ORACLE_DISPUTE_OPEN: u8 = 0;
+const ORACLE_DISPUTE_CHALLENGER_WINS: u8 = 1;
+
+/// Leading fields of an oracle-verifier `Dispute`, read from its data
+#[derive(AnchorDeserialize)]
+struct OracleDispute {
+    proof: Pubkey,
+    challenger: Pubkey,
+    _reason: String,
+    _evidence_url: String,
+    status: u8,
+}
+
+/// Read an oracle-verifier dispute over `task`'s completion proof. Its
+/// address must be oracle-verifier's dispute PDA for that proof, which only
+/// `create_dispute` initializes.
+fn read_task_dispute(dispute: &AccountInfo, task: Pubkey) -> Result<OracleDispute> {
+    require!(dispute.owner == &ORACLE_VERIFIER_PROGRAM_ID, ErrorCode::DisputeMismatch);
+    let data = dispute.try_borrow_data()?;
+    let parsed = OracleDispute::deserialize(&mut data.get(8..).unwrap_or_default())
+        .map_err(|_| error!(ErrorCode::DisputeMismatch))?;
+
+    let (proof, _) = Pubkey::find_program_address(
+        &[b"completion-proof", task.as_ref()],
+        &ORACLE_VERIFIER_PROGRAM_ID,
+    );
+    let (address, _) = Pubkey::find_program_address(
+        &[b"dispute", proof.as_ref(), parsed.challenger.as_ref()],
+        &ORACLE_VERIFIER_PROGRAM_ID,
+    );
+    require!(
+        parsed.proof == proof && dispute.key() == address,
+        ErrorCode::DisputeMismatch
+    );
+
+    Ok(parsed)
+}
+
+/// Slash the assigned operator for a task lost in dispute or aborted through
+/// the robot's fault, up to its reward, signed by this program's slasher PDA.
+/// The token program caps the slash at 10% of the bond; operators without an
+/// operator stake aren't slashed.
+fn slash_task_operator<'info>(
+    slash: &OperatorSlash<'info>,
+    operator_stake: &AccountInfo<'info>,
+    task_key: Pubkey,
+    task: &Task,
+    reason: String,
+) -> Result<u64> {
+    if operator_stake.owner != &droneos_token::ID {
+        return Ok(0);
+    }
+    let stake =
+        droneos_token::OperatorStake::try_deserialize(&mut &operator_stake.try_borrow_data()?[..])?;
+    let bond = stake.slashable_amount.saturating_add(stake.delegated_amount);
+    let amount = task.reward.min(bond / 10);
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    let (_, bump) = Pubkey::find_program_address(&[b"slasher"], &crate::ID);
+    let seeds = &[b"slasher".as_ref(), &[bump]];
+    droneos_token::cpi::slash_operator(
+        CpiContext::new_with_signer(
+            slash.droneos_token_program.to_account_info(),
+            droneos_token::cpi::accounts::SlashOperator {
+                config: slash.token_config.to_account_info(),
+                operator_stake: operator_stake.clone(),
+                slash_record: slash.slash_record.to_account_info(),
+                operator_vault: slash.operator_vault.to_account_info(),
+                treasury: slash.treasury.to_account_info(),
+                insurance_pool: slash.insurance_pool.to_account_info(),
+                insurance_vault: slash.insurance_vault.to_account_info(),
+                slasher_program: slash.task_market_program.to_account_info(),
+                authority: slash.slasher.to_account_info(),
+                mint: slash.mint.to_account_info(),
+                payer: slash.payer.to_account_info(),
+                token_program: slash.token_program.to_account_info(),
+                system_program: slash.system_program.to_account_info(),
+                event_authority: slash.token_event_authority.to_account_info(),
+                program: slash.droneos_token_program.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+        reason,
+        Some(task_key),
+    )?;
+
+    Ok(amount)
+}
+
+/// Number of oracle-verifier `ProofType`s, stored on milestones as their index
+const ORACLE_PROOF_TYPES: u8 = 3;
+/// `ProofType::GPS` tag of oracle-verifier proofs
+const ORACLE_PROOF_GPS: u8 = 0;
+/// `ProofStatus::Verified` tag of oracle-verifier proofs
+const ORACLE_PROOF_VERIFIED: u8 = 1;
+
+/// Leading fields of an oracle-verifier `Proof`, read from its data
+#[derive(AnchorDeserialize)]
+struct OracleProof {
+    task: Pubkey,
+    _robot: Pubkey,
+    _oracle: Pubkey,
+    proof_type: u8,
+    latitude: Option<i64>,
+    longitude: Option<i64>,
+    _altitude: Option<i32>,
+    _data_hash: Option<[u8; 32]>,
+    _proof_url: Option<String>,
+    _metadata: Option<String>,
+    timestamp: i64,
+    _signature: [u8; 64],
+    _confidence_score: u8,
+    status: u8,
+}
+
+/// Read an oracle-verifier proof for `task`. Its address must be the GPS
+/// proof PDA for the task and `robot` or the task's completion proof PDA.
+fn read_task_proof(proof: &AccountInfo, task: Pubkey, robot: Pubkey) -> Result<OracleProof> {
+    require!(proof.owner == &ORACLE_VERIFIER_PROGRAM_ID, ErrorCode::ProofMismatch);
+    let data = proof.try_borrow_data()?;
+    let parsed = OracleProof::deserialize(&mut data.get(8..).unwrap_or_default())
+        .map_err(|_| error!(ErrorCode::ProofMismatch))?;
+
+    let (gps, _) = Pubkey::find_program_address(
+        &[b"proof", task.as_ref(), robot.as_ref()],
+        &ORACLE_VERIFIER_PROGRAM_ID,
+    );
+    let (completion, _) = Pubkey::find_program_address(
+        &[b"completion-proof", task.as_ref()],
+        &ORACLE_VERIFIER_PROGRAM_ID,
+    );
+    require!(
+        parsed.task == task && (proof.key() == gps || proof.key() == completion),
+        ErrorCode::ProofMismatch
+    );
+
+    Ok(parsed)
+}
+
+/// Require `proof` to be a verified proof of the task's robot, of any type,
+/// taken since the task started
+fn check_completion_proof(task_key: Pubkey, task: &Task, proof: &AccountInfo) -> Result<()> {
+    let robot = key_if_set(task.assigned_robot).ok_or(ErrorCode::NotAssignedRobot)?;
+    let proof = read_task_proof(proof, task_key, robot)?;
+    require!(proof.status == ORACLE_PROOF_VERIFIED, ErrorCode::ProofNotVerified);
+    require!(proof.timestamp >= task.started_at, ErrorCode::ProofMismatch);
+    Ok(())
+}
+
+/// Require `proof` to be a verified GPS proof of the task's robot, taken
+/// since the task started, inside `geofence`
+fn check_geofence_proof(
+    task_key: Pubkey,
+    task: &Task,
+    proof: &AccountInfo,
+    geofence: Geofence,
+) -> Result<()> {
+    let robot = key_if_set(task.assigned_robot).ok_or(ErrorCode::NotAssignedRobot)?;
+    let proof = read_task_proof(proof, task_key, robot)?;
+    require!(proof.proof_type == ORACLE_PROOF_GPS, ErrorCode::ProofMismatch);
+    require!(proof.status == ORACLE_PROOF_VERIFIED, ErrorCode::ProofNotVerified);
+    require!(proof.timestamp >= task.started_at, ErrorCode::ProofMismatch);
+
+    let (Some(latitude), Some(longitude)) = (proof.latitude, proof.longitude) else {
+        return err!(ErrorCode::ProofMismatch);
+    };
+    require!(geofence.contains(latitude, longitude), ErrorCode::OutsideGeofence);
+
+    Ok(())
+}
+
+/// A task's PDA seeds, for CPIs signed by the task: its creator, index and
+/// bump
+fn task_seeds(task: &Task) -> (Pubkey, [u8; 8], [u8; 1]) {
+    (task.creator, task.index.to_le_bytes(), [task.bump])
+}
+
+/// Read a task's PDA seeds after checking `stream` is its stream. The task
+/// isn't borrowed afterwards, so it can sign a CPI.
+fn task_stream_seeds(
+    task: &AccountLoader<Task>,
+    stream: &AccountInfo,
+) -> Result<(Pubkey, [u8; 8], [u8; 1])> {
+    let task = task.load()?;
+    require!(task.stream_id == stream.key(), ErrorCode::StreamMismatch);
+    Ok(task_seeds(&task))
+}
+
+/// Pay a task's milestone out of its stream's escrow via CPI, signed by the
+/// task PDA, and mark it released
+fn release_task_milestone<'info>(
+    task: &AccountLoader<'info, Task>,
+    stream: &TaskStream<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+    index: u8,
+) -> Result<()> {
+    let (creator, task_index, bump) = task_stream_seeds(task, &stream.stream)?;
+    let seeds = &[b"task".as_ref(), creator.as_ref(), &task_index, &bump];
+    payment_streams::cpi::release_milestone_by_task(
+        CpiContext::new_with_signer(
+            stream.payment_streams_program.to_account_info(),
+            payment_streams::cpi::accounts::ReleaseMilestoneByTask {
+                config: stream.stream_config.to_account_info(),
+                stream: stream.stream.to_account_info(),
+                escrow: stream.escrow.to_account_info(),
+                mint: stream.mint.to_account_info(),
+                payee_token: stream.operator_token.to_account_info(),
+                treasury: stream.treasury.to_account_info(),
+                task_authority: task.to_account_info(),
+                token_program: stream.token_program.to_account_info(),
+                event_authority: stream.stream_event_authority.to_account_info(),
+                program: stream.payment_streams_program.to_account_info(),
+            },
+            &[&seeds[..]],
+        )
+        .with_remaining_accounts(extra_accounts.to_vec()),
+        index,
+    )?;
+
+    task.load_mut()?.milestones[index as usize].set_status(MilestoneStatus::Released);
+    Ok(())
+}
+
+/// Read the creator's fee tier from their stake position via the token
+/// program's `get_fee_tier` view, record it on the task and set the matching
+/// discount on its stream before settlement
+fn apply_creator_fee_tier<'info>(
+    task: &AccountLoader<'info, Task>,
+    stream: &TaskStream<'info>,
+    creator_stake: &Account<'info, StakeAccount>,
+    droneos_token_program: &Program<'info, DroneosToken>,
+) -> Result<()> {
+    let (creator, task_index, bump) = task_stream_seeds(task, &stream.stream)?;
+
+    let tier = droneos_token::cpi::get_fee_tier(CpiContext::new(
+        droneos_token_program.to_account_info(),
+        droneos_token::cpi::accounts::ReadFeeTier {
+            stake_account: creator_stake.to_account_info(),
+        },
+    ))?
+    .get();
+    task.load_mut()?.fee_tier = tier;
+
+    let seeds = &[b"task".as_ref(), creator.as_ref(), &task_index, &bump];
+    payment_streams::cpi::set_fee_discount_by_task(
+        CpiContext::new_with_signer(
+            stream.payment_streams_program.to_account_info(),
+            payment_streams::cpi::accounts::TaskControlStream {
+                stream: stream.stream.to_account_info(),
+                task_authority: task.to_account_info(),
+                event_authority: stream.stream_event_authority.to_account_info(),
+                program: stream.payment_streams_program.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        droneos_token::fee_tier_discount(tier),
+    )
+}
+
+/// Approve a task pending verification: check its geofence and completion
+/// proofs, settle its milestones and stream, and free the operator's stake
+/// and bond
+#[allow(clippy::too_many_arguments)]
+fn approve_completion<'info>(
+    task: &AccountLoader<'info, Task>,
+    market: &mut Account<'info, Market>,
+    stream: &TaskStream<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+    operator_stake: &AccountInfo<'info>,
+    droneos_token_program: &Program<'info, DroneosToken>,
+    gps_proof: Option<&UncheckedAccount<'info>>,
+    completion_proof: Option<&UncheckedAccount<'info>>,
+    now: i64,
+) -> Result<()> {
+    {
+        let task_key = task.key();
+        let mut task = task.load_mut()?;
+        if let Some(geofence) = task.geofence() {
+            let proof = gps_proof.ok_or(ErrorCode::ProofRequired)?;
+            check_geofence_proof(task_key, &task, proof, geofence)?;
+        }
+        if task.requires_proof != 0 {
+            let proof = completion_proof.ok_or(ErrorCode::ProofRequired)?;
+            check_completion_proof(task_key, &task, proof)?;
+        }
+
+        task.set_status(TaskStatus::Completed);
+        task.completed_at = now;
+
+        market.total_completed += 1;
+        market.total_volume += task.reward;
+    }
+
+    release_outstanding_milestones(task, stream, extra_accounts)?;
+    terminate_task_stream(task, stream, extra_accounts, "Task completed".to_string())?;
+    track_operator_task(market, operator_stake, droneos_token_program, false)?;
+    release_task_bond(market, operator_stake, droneos_token_program, &mut *task.load_mut()?)
+}
+
+/// Release every milestone of a task not yet paid, for a completed task
+fn release_outstanding_milestones<'info>(
+    task: &AccountLoader<'info, Task>,
+    stream: &TaskStream<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let outstanding: Vec<u8> = task
+        .load()?
+        .milestones()
+        .iter()
+        .enumerate()
+        .filter(|(_, milestone)| milestone.status() != MilestoneStatus::Released)
+        .map(|(index, _)| index as u8)
+        .collect();
+    for index in outstanding {
+        release_task_milestone(task, stream, extra_accounts, index)?;
+    }
+    Ok(())
+}
+
+/// A task's robot requirements as identity-registry types. Tasks store the
+/// class and capabilities as their enum indices.
+fn robot_requirements(
+    robot_class: u8,
+    capabilities: &[u8],
+    min_reputation: u16,
+) -> Result<RobotRequirements> {
+    Ok(RobotRequirements {
+        robot_class: RobotClass::try_from_slice(&[robot_class])
+            .map_err(|_| error!(ErrorCode::InvalidRobotClass))?,
+        capabilities: capabilities
+            .iter()
+            .map(|&capability| {
+                Capability::try_from_slice(&[capability])
+                    .map_err(|_| error!(ErrorCode::InvalidCapability))
+            })
+            .collect::<Result<_>>()?,
+        min_reputation,
+    })
+}
+
+/// Status of a task's stream. `settle_tick` is permissionless, so the
+/// stream can reach Grace or settle to Completed on its own while the task
+/// is still open.
+fn stream_status(stream: &AccountInfo) -> Result<StreamStatus> {
+    Ok(load_stream(stream)?.status)
+}
+
+/// A task's stream, read from its unchecked account
+fn load_stream(stream: &AccountInfo) -> Result<PaymentStream> {
+    require_keys_eq!(*stream.owner, payment_streams::ID, ErrorCode::StreamMismatch);
+    let data = stream.try_borrow_data()?;
+    PaymentStream::try_deserialize(&mut &data[..])
+}
+
+/// A bid's robot, read from its unchecked account
+fn load_robot(robot: &AccountInfo) -> Result<Robot> {
+    require_keys_eq!(*robot.owner, identity_registry::ID, ErrorCode::BidTaskMismatch);
+    let data = robot.try_borrow_data()?;
+    Robot::try_deserialize(&mut &data[..])
+}
+
+/// Whether a task's stream has already been settled, leaving nothing to
+/// terminate
+fn stream_settled(stream: &AccountInfo) -> Result<bool> {
+    Ok(matches!(
+        stream_status(stream)?,
+        StreamStatus::Completed | StreamStatus::Cancelled
+    ))
+}
+
+/// Terminate a task's stream via CPI, signed by the task PDA. Pays the
+/// operator what is owed and refunds the rest of escrow to the creator.
+/// Does nothing if the stream has already settled.
+fn terminate_task_stream<'info>(
+    task: &AccountLoader<'info, Task>,
+    stream: &TaskStream<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+    reason: String,
+) -> Result<()> {
+    let (creator, index, bump) = task_stream_seeds(task, &stream.stream)?;
+    if stream_settled(&stream.stream)? {
+        return Ok(());
+    }
+
+    let seeds = &[b"task".as_ref(), creator.as_ref(), &index, &bump];
+    payment_streams::cpi::terminate_stream_by_task(
+        CpiContext::new_with_signer(
+            stream.payment_streams_program.to_account_info(),
+            payment_streams::cpi::accounts::TerminateStreamByTask {
+                config: stream.stream_config.to_account_info(),
+                stream: stream.stream.to_account_info(),
+                escrow: stream.escrow.to_account_info(),
+                mint: stream.mint.to_account_info(),
+                payer_token: stream.creator_token.to_account_info(),
+                payee_token: stream.operator_token.to_account_info(),
+                treasury: stream.treasury.to_account_info(),
+                task_authority: task.to_account_info(),
+                token_program: stream.token_program.to_account_info(),
+                event_authority: stream.stream_event_authority.to_account_info(),
+                program: stream.payment_streams_program.to_account_info(),
+            },
+            &[&seeds[..]],
+        )
+        .with_remaining_accounts(extra_accounts.to_vec()),
+        reason,
+    )
+}
+
+/// Cancel the task's stream, paying the payee `cancellation_fee_bps` of the
+/// escrow left after accrual on top of it. Does nothing if the stream has
+/// already settled.
+fn cancel_task_stream<'info>(
+    task: &AccountLoader<'info, Task>,
+    stream: &TaskStream<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+    cancellation_fee_bps: u16,
+) -> Result<()> {
+    let (creator, index, bump) = task_stream_seeds(task, &stream.stream)?;
+    if stream_settled(&stream.stream)? {
+        return Ok(());
+    }
+
+    let seeds = &[b"task".as_ref(), creator.as_ref(), &index, &bump];
+    payment_streams::cpi::cancel_stream_by_task(
+        CpiContext::new_with_signer(
+            stream.payment_streams_program.to_account_info(),
+            payment_streams::cpi::accounts::TerminateStreamByTask {
+                config: stream.stream_config.to_account_info(),
+                stream: stream.stream.to_account_info(),
+                escrow: stream.escrow.to_account_info(),
+                mint: stream.mint.to_account_info(),
+                payer_token: stream.creator_token.to_account_info(),
+                payee_token: stream.operator_token.to_account_info(),
+                treasury: stream.treasury.to_account_info(),
+                task_authority: task.to_account_info(),
+                token_program: stream.token_program.to_account_info(),
+                event_authority: stream.stream_event_authority.to_account_info(),
+                program: stream.payment_streams_program.to_account_info(),
+            },
+            &[&seeds[..]],
+        )
+        .with_remaining_accounts(extra_accounts.to_vec()),
+        "Cancelled by creator".to_string(),
+        cancellation_fee_bps,
+    )
+}
+
+/// Close the task's settled stream and its escrow via CPI, signed by the
+/// task PDA, returning their rent to the creator
+fn close_task_stream<'info>(
+    task: &AccountLoader<'info, Task>,
+    stream: &TaskStream<'info>,
+    creator: &Signer<'info>,
+) -> Result<()> {
+    let (creator_key, index, bump) = task_stream_seeds(task, &stream.stream)?;
+
+    let seeds = &[b"task".as_ref(), creator_key.as_ref(), &index, &bump];
+    payment_streams::cpi::close_stream_by_task(CpiContext::new_with_signer(
+        stream.payment_streams_program.to_account_info(),
+        payment_streams::cpi::accounts::CloseStreamByTask {
+            stream: stream.stream.to_account_info(),
+            escrow: stream.escrow.to_account_info(),
+            payer: creator.to_account_info(),
+            task_authority: task.to_account_info(),
+            token_program: stream.token_program.to_account_info(),
+            event_authority: stream.stream_event_authority.to_account_info(),
+            program: stream.payment_streams_program.to_account_info(),
+        },
+        &[&seeds[..]],
+    ))
+}
+
+// ============================================================================
+// ACCOUNTS
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [b"market"],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(title: String)]
+pub struct CreateTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"market"],
+        bump = market.bump,
+        constraint = !market.paused @ ErrorCode::MarketPaused
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Task::INIT_SPACE,
+        seeds = [b"task", creator.key().as_ref(), &market.total_tasks.to_le_bytes()],
+        bump
+    )]
+    pub task: AccountLoader<'info, Task>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: swarm-coordinator accounts, needed only when more than one robot
+    /// is required; validated by swarm-coordinator
+    #[account(mut)]
+    pub coordinator: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Group task created by swarm-coordinator
+    #[account(mut)]
+    pub group_task: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: swarm-coordinator's event authority
+    pub swarm_event_authority: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: swarm-coordinator program
+    #[account(address = SWARM_COORDINATOR_PROGRAM_ID)]
+    pub swarm_coordinator_program: Option<UncheckedAccount<'info>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimTaskInsurance<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    /// CHECK: The task's stream, read for what it paid the operator
+    #[account(constraint = task.load()?.stream_id == stream.key() @ ErrorCode::StreamMismatch)]
+    pub stream: AccountInfo<'info>,
+    
+    /// CHECK: droneos_token config, validated by droneos_token
+    pub token_config: AccountInfo<'info>,
+    
+    /// CHECK: droneos_token insurance pool, validated by droneos_token
+    #[account(mut)]
+    pub insurance_pool: AccountInfo<'info>,
+    
+    /// CHECK: Insurance pool vault, validated by droneos_token
+    #[account(mut)]
+    pub insurance_vault: AccountInfo<'info>,
+    
+    /// The creator's DRONEOS token account, checked against task.creator
+    #[account(mut)]
+    pub creator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: DRONEOS mint, validated by droneos_token
+    pub mint: AccountInfo<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    /// CHECK: droneos_token event authority
+    pub token_event_authority: AccountInfo<'info>,
+    
+    pub droneos_token_program: Program<'info, DroneosToken>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateRecurringTask<'info> {
+    #[account(
+        mut,
+        seeds = [b"market"],
+        bump = market.bump,
+        constraint = !market.paused @ ErrorCode::MarketPaused
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + RecurringTask::INIT_SPACE,
+        seeds = [b"recurring", creator.key().as_ref(), &market.total_recurring.to_le_bytes()],
+        bump
+    )]
+    pub recurring: Account<'info, RecurringTask>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SpawnOccurrence<'info> {
+    #[account(
+        mut,
+        seeds = [b"market"],
+        bump = market.bump,
+        constraint = !market.paused @ ErrorCode::MarketPaused
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"recurring", recurring.creator.as_ref(), &recurring.index.to_le_bytes()],
+        bump = recurring.bump
+    )]
+    pub recurring: Account<'info, RecurringTask>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Task::INIT_SPACE,
+        seeds = [b"task", recurring.creator.as_ref(), &market.total_tasks.to_le_bytes()],
+        bump
+    )]
+    pub task: AccountLoader<'info, Task>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelRecurringTask<'info> {
+    #[account(mut, has_one = creator @ ErrorCode::Unauthorized, close = creator)]
+    pub recurring: Account<'info, RecurringTask>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(template_id: u64)]
+pub struct CreateTaskTemplate<'info> {
+    #[account(
+        init,
+        payer = publisher,
+        space = 8 + TaskTemplate::INIT_SPACE,
+        seeds = [b"task-template", publisher.key().as_ref(), &template_id.to_le_bytes()],
+        bump
+    )]
+    pub template: Account<'info, TaskTemplate>,
+    
+    #[account(mut)]
+    pub publisher: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTaskTemplate<'info> {
+    #[account(mut, has_one = publisher @ ErrorCode::Unauthorized, close = publisher)]
+    pub template: Account<'info, TaskTemplate>,
+    
+    #[account(mut)]
+    pub publisher: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateTaskFromTemplate<'info> {
+    #[account(
+        mut,
+        seeds = [b"market"],
+        bump = market.bump,
+        constraint = !market.paused @ ErrorCode::MarketPaused
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        mut,
+        seeds = [b"task-template", template.publisher.as_ref(), &template.template_id.to_le_bytes()],
+        bump = template.bump
+    )]
+    pub template: Account<'info, TaskTemplate>,
+    
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Task::INIT_SPACE,
+        seeds = [b"task", creator.key().as_ref(), &market.total_tasks.to_le_bytes()],
+        bump
+    )]
+    pub task: AccountLoader<'info, Task>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SubmitBid<'info> {
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + Bid::INIT_SPACE,
+        seeds = [b"bid", task.key().as_ref(), robot.key().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    #[account(constraint = robot.operator == operator.key() @ ErrorCode::NotRobotOperator)]
+    pub robot: Box<Account<'info, Robot>>,
+    
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    
+    #[account(
+        seeds = [b"market"],
+        bump = market.bump,
+        constraint = !market.paused @ ErrorCode::MarketPaused
+    )]
+    pub market: Account<'info, Market>,
+    
+    /// CHECK: The operator's droneos_token operator stake, if they have one
+    #[account(
+        seeds = [b"operator", operator.key().as_ref()],
+        bump,
+        seeds::program = droneos_token::ID
+    )]
+    pub operator_stake: AccountInfo<'info>,
+    
+    /// CHECK: The operator's identity-registry fleet, if they have one
+    #[account(
+        seeds = [b"fleet", operator.key().as_ref()],
+        bump,
+        seeds::program = identity_registry::ID
+    )]
+    pub operator_fleet: AccountInfo<'info>,
+    
+    /// CHECK: The task creator's blacklist, if they have one
+    #[account(seeds = [b"blacklist", task.load()?.creator.as_ref()], bump)]
+    pub creator_blacklist: UncheckedAccount<'info>,
+    
+    pub identity_registry_program: Program<'info, IdentityRegistry>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AddToBlacklist<'info> {
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + Blacklist::INIT_SPACE,
+        seeds = [b"blacklist", creator.key().as_ref()],
+        bump
+    )]
+    pub blacklist: Account<'info, Blacklist>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemoveFromBlacklist<'info> {
+    #[account(
+        mut,
+        seeds = [b"blacklist", creator.key().as_ref()],
+        bump = blacklist.bump
+    )]
+    pub blacklist: Account<'info, Blacklist>,
+    
+    pub creator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateTask<'info> {
+    #[account(mut, has_one = creator @ ErrorCode::Unauthorized)]
+    pub task: AccountLoader<'info, Task>,
+    
+    pub creator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevealBid<'info> {
+    pub task: AccountLoader<'info, Task>,
+    
+    #[account(
+        mut,
+        constraint = bid.task == task.key() @ ErrorCode::BidTaskMismatch,
+        constraint = bid.operator == operator.key() @ ErrorCode::Unauthorized
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    pub operator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptBid<'info> {
+    #[account(
+        seeds = [b"market"],
+        bump = market.bump,
+        constraint = !market.paused @ ErrorCode::MarketPaused
+    )]
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    #[account(
+        mut,
+        constraint = bid.task == task.key() @ ErrorCode::BidTaskMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    #[account(mut, constraint = creator.key() == task.load()?.creator @ ErrorCode::Unauthorized)]
+    pub creator: Signer<'info>,
+    
+    /// CHECK: Stream config, validated by payment_streams
+    #[account(mut)]
+    pub stream_config: AccountInfo<'info>,
+    
+    /// CHECK: Initialized by payment_streams at ["task_stream", task]
+    #[account(mut)]
+    pub stream: AccountInfo<'info>,
+    
+    /// CHECK: Initialized by payment_streams
+    #[account(mut)]
+    pub escrow: AccountInfo<'info>,
+    
+    /// CHECK: Validated by payment_streams
+    pub mint: AccountInfo<'info>,
+    
+    /// CHECK: Creator's token account, validated by payment_streams
+    #[account(mut)]
+    pub creator_token: AccountInfo<'info>,
+    
+    /// CHECK: Stream payee
+    #[account(address = bid.operator @ ErrorCode::Unauthorized)]
+    pub operator: AccountInfo<'info>,
+    
+    /// CHECK: Creator's stream registry, created or grown by payment_streams
+    #[account(mut)]
+    pub creator_registry: AccountInfo<'info>,
+    
+    /// CHECK: Operator's stream registry, created or grown by payment_streams
+    #[account(mut)]
+    pub operator_registry: AccountInfo<'info>,
+    
+    /// CHECK: payment_streams event authority
+    pub stream_event_authority: AccountInfo<'info>,
+    
+    /// CHECK: The operator's droneos_token operator stake, if they have one
+    #[account(
+        mut,
+        seeds = [b"operator", bid.operator.as_ref()],
+        bump,
+        seeds::program = droneos_token::ID
+    )]
+    pub operator_stake: AccountInfo<'info>,
+    
+    /// CHECK: The operator's identity-registry fleet, if they have one
+    #[account(
+        seeds = [b"fleet", bid.operator.as_ref()],
+        bump,
+        seeds::program = identity_registry::ID
+    )]
+    pub operator_fleet: AccountInfo<'info>,
+    
+    /// CHECK: The bidding robot, marked busy by identity-registry
+    #[account(mut, address = bid.robot @ ErrorCode::BidTaskMismatch)]
+    pub robot: AccountInfo<'info>,
+    
+    /// CHECK: identity-registry event authority
+    pub registry_event_authority: AccountInfo<'info>,
+    
+    pub payment_streams_program: Program<'info, PaymentStreams>,
+    pub droneos_token_program: Program<'info, DroneosToken>,
+    pub identity_registry_program: Program<'info, IdentityRegistry>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    
+    /// CHECK: droneos_token accounts, needed only for insured tasks;
+    /// validated by droneos_token
+    pub token_config: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: droneos_token insurance pool
+    #[account(mut)]
+    pub insurance_pool: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: Insurance pool vault
+    #[account(mut)]
+    pub insurance_vault: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: The creator's DRONEOS token account paying the premium
+    #[account(mut)]
+    pub premium_token: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: DRONEOS mint
+    pub premium_mint: Option<UncheckedAccount<'info>>,
+    
+    pub premium_token_program: Option<Interface<'info, TokenInterface>>,
+    
+    /// CHECK: droneos_token event authority
+    pub token_event_authority: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarket<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    
+    #[account(constraint = authority.key() == market.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RejectBid<'info> {
+    pub task: AccountLoader<'info, Task>,
+    
+    #[account(
+        mut,
+        constraint = bid.task == task.key() @ ErrorCode::BidTaskMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    pub creator: Signer<'info>,
+    
+    /// CHECK: The bidding operator, receiving the bid's bond back
+    #[account(mut, address = bid.operator @ ErrorCode::Unauthorized)]
+    pub operator: AccountInfo<'info>,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
-pub struct Initialize<'info> {
+pub struct RejectRemainingBids<'info> {
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawBid<'info> {
+    #[account(mut)]
+    pub bid: Account<'info, Bid>,
+    
+    #[account(mut)]
+    pub operator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExpireBid<'info> {
+    #[account(mut)]
+    pub bid: Account<'info, Bid>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseBid<'info> {
+    #[account(mut, close = operator)]
+    pub bid: Account<'info, Bid>,
+    
+    #[account(mut, constraint = operator.key() == bid.operator @ ErrorCode::Unauthorized)]
+    pub operator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteTask<'info> {
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    /// CHECK: Robot account from identity-registry
+    pub robot: AccountInfo<'info>,
+    
+    pub operator: Signer<'info>,
+    
+    /// CHECK: oracle-verifier proof backing the checkpoint, checked by
+    /// `read_task_proof`
+    pub proof: Option<UncheckedAccount<'info>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteTaskStream<'info> {
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    /// CHECK: Robot account from identity-registry
+    pub robot: AccountInfo<'info>,
+    
+    pub operator: Signer<'info>,
+    
+    /// CHECK: Stream config, validated by payment_streams
+    pub stream_config: AccountInfo<'info>,
+    
+    /// CHECK: The task's stream, validated by payment_streams
+    #[account(mut, constraint = task.load()?.stream_id == stream.key() @ ErrorCode::StreamMismatch)]
+    pub stream: AccountInfo<'info>,
+    
+    /// CHECK: payment_streams event authority
+    pub stream_event_authority: AccountInfo<'info>,
+    
+    pub payment_streams_program: Program<'info, PaymentStreams>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct StartTask<'info> {
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    /// CHECK: Robot account from identity-registry
+    pub robot: AccountInfo<'info>,
+    
+    /// The accepted bid, whose bond is returned
     #[account(
-        init,
-        payer = authority,
-        space = 8 + Market::INIT_SPACE,
-        seeds = [b"market"],
-        bump
+        mut,
+        seeds = [b"bid", task.key().as_ref(), robot.key().as_ref()],
+        bump = bid.bump,
+        constraint = bid.operator == operator.key() @ ErrorCode::Unauthorized
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    
+    /// CHECK: Stream config, validated by payment_streams
+    pub stream_config: AccountInfo<'info>,
+    
+    /// CHECK: The task's stream, validated by payment_streams
+    #[account(mut, constraint = task.load()?.stream_id == stream.key() @ ErrorCode::StreamMismatch)]
+    pub stream: AccountInfo<'info>,
+    
+    /// CHECK: payment_streams event authority
+    pub stream_event_authority: AccountInfo<'info>,
+    
+    pub payment_streams_program: Program<'info, PaymentStreams>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ForfeitBidBond<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    
+    pub task: AccountLoader<'info, Task>,
+    
+    #[account(
+        mut,
+        seeds = [b"bid", task.key().as_ref(), task.load()?.assigned_robot.as_ref()],
+        bump = bid.bump
+    )]
+    pub bid: Account<'info, Bid>,
+    
+    /// CHECK: The task's creator, receiving the forfeited bond
+    #[account(mut, address = task.load()?.creator @ ErrorCode::Unauthorized)]
+    pub creator: AccountInfo<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CompleteMilestone<'info> {
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    /// CHECK: Robot account from identity-registry
+    pub robot: AccountInfo<'info>,
+    
+    #[account(constraint = operator.key() == task.load()?.assigned_operator @ ErrorCode::Unauthorized)]
+    pub operator: Signer<'info>,
+    
+    /// CHECK: oracle-verifier proof, checked by `read_task_proof` when the
+    /// milestone requires one
+    pub proof: Option<UncheckedAccount<'info>>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ApproveMilestone<'info> {
+    #[account(mut, has_one = creator @ ErrorCode::Unauthorized)]
+    pub task: AccountLoader<'info, Task>,
+    
+    pub creator: Signer<'info>,
+    
+    pub stream: TaskStream<'info>,
+}
+
+/// Accounts payment_streams needs to settle a task's stream
+#[derive(Accounts)]
+pub struct TaskStream<'info> {
+    /// CHECK: Stream config, validated by payment_streams
+    #[account(mut)]
+    pub stream_config: AccountInfo<'info>,
+    
+    /// CHECK: Checked against task.stream_id and by payment_streams
+    #[account(mut)]
+    pub stream: AccountInfo<'info>,
+    
+    /// CHECK: Validated by payment_streams
+    #[account(mut)]
+    pub escrow: AccountInfo<'info>,
+    
+    /// CHECK: Validated by payment_streams
+    pub mint: AccountInfo<'info>,
+    
+    /// CHECK: Refund destination, validated by payment_streams
+    #[account(mut)]
+    pub creator_token: AccountInfo<'info>,
+    
+    /// CHECK: Payout destination, validated by payment_streams
+    #[account(mut)]
+    pub operator_token: AccountInfo<'info>,
+    
+    /// CHECK: Fee treasury, validated by payment_streams
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+    
+    /// CHECK: payment_streams event authority
+    pub stream_event_authority: AccountInfo<'info>,
+    
+    pub payment_streams_program: Program<'info, PaymentStreams>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VerifyTask<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    pub creator: Signer<'info>,
+    
+    pub stream: TaskStream<'info>,
+    
+    /// CHECK: The assigned operator's droneos_token operator stake, if any
+    #[account(
+        mut,
+        seeds = [b"operator", task.load()?.assigned_operator.as_ref()],
+        bump,
+        seeds::program = droneos_token::ID
+    )]
+    pub operator_stake: AccountInfo<'info>,
+    
+    pub droneos_token_program: Program<'info, DroneosToken>,
+    
+    /// CHECK: oracle-verifier GPS proof, checked by `check_geofence_proof`
+    /// for geofenced tasks
+    pub gps_proof: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: oracle-verifier proof of completion, checked by
+    /// `check_completion_proof` for tasks requiring proof
+    pub completion_proof: Option<UncheckedAccount<'info>>,
+    
+    /// One of the creator's $DRONEOS stake positions, if any, for a reduced
+    /// platform fee
+    #[account(
+        seeds = [b"stake", creator.key().as_ref(), &creator_stake.index.to_le_bytes()],
+        bump = creator_stake.bump,
+        seeds::program = droneos_token::ID,
+    )]
+    pub creator_stake: Option<Box<Account<'info, StakeAccount>>>,
+    
+    /// CHECK: The assigned robot, marked available again by identity-registry
+    #[account(
+        mut,
+        address = task.load()?.assigned_robot @ ErrorCode::NotAssignedRobot
+    )]
+    pub robot: AccountInfo<'info>,
+    
+    /// CHECK: identity-registry event authority
+    pub registry_event_authority: AccountInfo<'info>,
+    
+    pub identity_registry_program: Program<'info, IdentityRegistry>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizeUnverified<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    pub stream: TaskStream<'info>,
+    
+    /// CHECK: The assigned operator's droneos_token operator stake, if any
+    #[account(
+        mut,
+        seeds = [b"operator", task.load()?.assigned_operator.as_ref()],
+        bump,
+        seeds::program = droneos_token::ID
+    )]
+    pub operator_stake: AccountInfo<'info>,
+    
+    pub droneos_token_program: Program<'info, DroneosToken>,
+    
+    /// CHECK: oracle-verifier GPS proof, checked by `check_geofence_proof`
+    /// for geofenced tasks
+    pub gps_proof: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: oracle-verifier proof of completion, checked by
+    /// `check_completion_proof` for tasks requiring proof
+    pub completion_proof: Option<UncheckedAccount<'info>>,
+    
+    /// CHECK: The assigned robot, marked available again by identity-registry
+    #[account(
+        mut,
+        address = task.load()?.assigned_robot @ ErrorCode::NotAssignedRobot
+    )]
+    pub robot: AccountInfo<'info>,
+    
+    /// CHECK: identity-registry event authority
+    pub registry_event_authority: AccountInfo<'info>,
+    
+    pub identity_registry_program: Program<'info, IdentityRegistry>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    #[account(mut, has_one = creator @ ErrorCode::Unauthorized)]
+    pub task: AccountLoader<'info, Task>,
+    
+    /// CHECK: oracle-verifier dispute, checked by `read_task_dispute`
+    pub dispute: AccountInfo<'info>,
+    
+    pub creator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveTaskDispute<'info> {
+    #[account(mut, seeds = [b"market"], bump = market.bump)]
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    /// CHECK: oracle-verifier dispute, checked against task.dispute;
+    /// omitted if none was opened
+    pub dispute: Option<UncheckedAccount<'info>>,
+    
+    pub stream: TaskStream<'info>,
+    
+    /// CHECK: The assigned operator's droneos_token operator stake, if any
+    #[account(
+        mut,
+        seeds = [b"operator", task.load()?.assigned_operator.as_ref()],
+        bump,
+        seeds::program = droneos_token::ID
+    )]
+    pub operator_stake: AccountInfo<'info>,
+    
+    pub slash: OperatorSlash<'info>,
+    
+    pub droneos_token_program: Program<'info, DroneosToken>,
+    
+    /// CHECK: The assigned robot, marked available again by identity-registry
+    #[account(
+        mut,
+        address = task.load()?.assigned_robot @ ErrorCode::NotAssignedRobot
+    )]
+    pub robot: AccountInfo<'info>,
+    
+    /// CHECK: identity-registry event authority
+    pub registry_event_authority: AccountInfo<'info>,
+    
+    pub identity_registry_program: Program<'info, IdentityRegistry>,
+}
+
+/// Accounts droneos_token needs to slash a task's operator, for a lost
+/// dispute or an abort through the robot's fault
+#[derive(Accounts)]
+pub struct OperatorSlash<'info> {
+    /// CHECK: This program's slasher PDA, signing the slash CPI
+    #[account(seeds = [b"slasher"], bump)]
+    pub slasher: AccountInfo<'info>,
+    
+    /// CHECK: droneos_token config, validated by droneos_token
+    #[account(mut)]
+    pub token_config: AccountInfo<'info>,
+    
+    /// CHECK: Initialized by droneos_token at ["slash", operator, slash_count]
+    #[account(mut)]
+    pub slash_record: AccountInfo<'info>,
+    
+    /// CHECK: Vault holding operator stakes, validated by droneos_token
+    #[account(mut)]
+    pub operator_vault: AccountInfo<'info>,
+    
+    /// CHECK: droneos_token treasury, validated by droneos_token
+    #[account(mut)]
+    pub treasury: AccountInfo<'info>,
+    
+    /// CHECK: droneos_token insurance pool, validated by droneos_token
+    #[account(mut)]
+    pub insurance_pool: AccountInfo<'info>,
+    
+    /// CHECK: Insurance pool vault, validated by droneos_token
+    #[account(mut)]
+    pub insurance_vault: AccountInfo<'info>,
+    
+    /// CHECK: DRONEOS mint, validated by droneos_token
+    pub mint: AccountInfo<'info>,
+    
+    /// CHECK: droneos_token event authority
+    pub token_event_authority: AccountInfo<'info>,
+    
+    /// Pays for the slash record
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: This program, the registered slasher
+    #[account(address = crate::ID)]
+    pub task_market_program: AccountInfo<'info>,
+    
+    pub droneos_token_program: Program<'info, DroneosToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelTask<'info> {
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    pub creator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelAssignedTask<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut, has_one = creator @ ErrorCode::Unauthorized)]
+    pub task: AccountLoader<'info, Task>,
+    
+    /// Receives the rent of the task's stream when the task is reopened
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub stream: TaskStream<'info>,
+    
+    /// CHECK: The assigned operator's droneos_token operator stake, if any
+    #[account(
+        mut,
+        seeds = [b"operator", task.load()?.assigned_operator.as_ref()],
+        bump,
+        seeds::program = droneos_token::ID
+    )]
+    pub operator_stake: AccountInfo<'info>,
+    
+    pub droneos_token_program: Program<'info, DroneosToken>,
+    
+    /// CHECK: The assigned robot, marked available again by identity-registry
+    #[account(
+        mut,
+        address = task.load()?.assigned_robot @ ErrorCode::NotAssignedRobot
+    )]
+    pub robot: AccountInfo<'info>,
+    
+    /// CHECK: identity-registry event authority
+    pub registry_event_authority: AccountInfo<'info>,
+    
+    pub identity_registry_program: Program<'info, IdentityRegistry>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FlagLate<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    /// CHECK: The assigned operator's droneos_token operator stake, if any
+    #[account(
+        mut,
+        seeds = [b"operator", task.load()?.assigned_operator.as_ref()],
+        bump,
+        seeds::program = droneos_token::ID
     )]
-    pub market: Account<'info, Market>,
+    pub operator_stake: AccountInfo<'info>,
     
+    pub bond: BondForfeit<'info>,
+    
+    pub droneos_token_program: Program<'info, DroneosToken>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExpireTask<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub task: AccountLoader<'info, Task>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseTask<'info> {
+    #[account(mut, close = creator)]
+    pub task: AccountLoader<'info, Task>,
     
-    pub system_program: Program<'info, System>,
+    #[account(mut, constraint = creator.key() == task.load()?.creator @ ErrorCode::Unauthorized)]
+    pub creator: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(title: String)]
-pub struct CreateTask<'info> {
-    #[account(mut, seeds = [b"market"], bump = market.bump)]
-    pub market: Account<'info, Market>,
+pub struct AbortTask<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(mut)]
+    pub task: AccountLoader<'info, Task>,
+    
+    pub authority: Signer<'info>,
+    
+    pub stream: TaskStream<'info>,
     
+    /// CHECK: The assigned operator's droneos_token operator stake, if any
     #[account(
-        init,
-        payer = creator,
-        space = 8 + Task::INIT_SPACE,
-        seeds = [b"task", creator.key().as_ref(), &market.total_tasks.to_le_bytes()],
-        bump
+        mut,
+        seeds = [b"operator", task.load()?.assigned_operator.as_ref()],
+        bump,
+        seeds::program = droneos_token::ID
     )]
-    pub task: Account<'info, Task>,
+    pub operator_stake: AccountInfo<'info>,
     
-    #[account(mut)]
-    pub creator: Signer<'info>,
+    pub bond: BondForfeit<'info>,
     
-    pub system_program: Program<'info, System>,
+    pub slash: OperatorSlash<'info>,
+    
+    pub droneos_token_program: Program<'info, DroneosToken>,
+    
+    /// CHECK: The assigned robot, marked available again by identity-registry
+    #[account(
+        mut,
+        address = task.load()?.assigned_robot @ ErrorCode::NotAssignedRobot
+    )]
+    pub robot: AccountInfo<'info>,
+    
+    /// CHECK: identity-registry event authority
+    pub registry_event_authority: AccountInfo<'info>,
+    
+    pub identity_registry_program: Program<'info, IdentityRegistry>,
 }
 
+/// Accounts droneos_token needs to pay a forfeited task bond to the creator
 #[derive(Accounts)]
-pub struct SubmitBid<'info> {
+pub struct BondForfeit<'info> {
+    /// CHECK: droneos_token config, validated by droneos_token
     #[account(mut)]
-    pub task: Account<'info, Task>,
+    pub token_config: AccountInfo<'info>,
     
+    /// CHECK: Vault holding operator stakes, validated by droneos_token
+    #[account(mut)]
+    pub operator_vault: AccountInfo<'info>,
+    
+    /// The creator's DRONEOS token account, checked against task.creator
+    #[account(mut)]
+    pub creator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: DRONEOS mint, validated by droneos_token
+    pub mint: AccountInfo<'info>,
+    
+    /// CHECK: droneos_token event authority
+    pub token_event_authority: AccountInfo<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(kind: TaskIndexKind, key: u8)]
+pub struct CreateTaskIndex<'info> {
     #[account(
         init,
-        payer = operator,
-        space = 8 + Bid::INIT_SPACE,
-        seeds = [b"bid", task.key().as_ref(), robot.key().as_ref()],
+        payer = payer,
+        space = 8 + TaskIndex::INIT_SPACE,
+        seeds = [b"task-index".as_ref(), &[kind as u8], &[key]],
         bump
     )]
-    pub bid: Account<'info, Bid>,
+    pub index: Account<'info, TaskIndex>,
     
-    /// CHECK: Robot account from identity-registry
-    pub robot: AccountInfo<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TaskIndexPage::INIT_SPACE,
+        seeds = [b"task-index-page", index.key().as_ref(), &0u32.to_le_bytes()],
+        bump
+    )]
+    pub page: Account<'info, TaskIndexPage>,
     
     #[account(mut)]
-    pub operator: Signer<'info>,
+    pub payer: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct AcceptBid<'info> {
-    #[account(mut)]
-    pub task: Account<'info, Task>,
-    
+pub struct AddTaskIndexPage<'info> {
     #[account(
         mut,
-        constraint = bid.task == task.key() @ ErrorCode::BidTaskMismatch
+        seeds = [b"task-index".as_ref(), &[index.kind as u8], &[index.key]],
+        bump = index.bump
     )]
-    pub bid: Account<'info, Bid>,
+    pub index: Account<'info, TaskIndex>,
     
-    #[account(constraint = creator.key() == task.creator @ ErrorCode::Unauthorized)]
-    pub creator: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct RejectBid<'info> {
-    pub task: Account<'info, Task>,
+    #[account(
+        seeds = [b"task-index-page", index.key().as_ref(), &(index.pages - 1).to_le_bytes()],
+        bump = last_page.bump
+    )]
+    pub last_page: Account<'info, TaskIndexPage>,
     
     #[account(
-        mut,
-        constraint = bid.task == task.key() @ ErrorCode::BidTaskMismatch
+        init,
+        payer = payer,
+        space = 8 + TaskIndexPage::INIT_SPACE,
+        seeds = [b"task-index-page", index.key().as_ref(), &index.pages.to_le_bytes()],
+        bump
     )]
-    pub bid: Account<'info, Bid>,
+    pub page: Account<'info, TaskIndexPage>,
     
-    pub creator: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct WithdrawBid<'info> {
     #[account(mut)]
-    pub bid: Account<'info, Bid>,
+    pub payer: Signer<'info>,
     
-    pub operator: Signer<'info>,
+    pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct ExecuteTask<'info> {
+pub struct IndexTask<'info> {
     #[account(mut)]
-    pub task: Account<'info, Task>,
+    pub task: AccountLoader<'info, Task>,
     
-    /// CHECK: Robot account from identity-registry
-    pub robot: AccountInfo<'info>,
+    #[account(
+        seeds = [b"task-index".as_ref(), &[index.kind as u8], &[index.key]],
+        bump = index.bump
+    )]
+    pub index: Account<'info, TaskIndex>,
     
-    pub operator: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"task-index-page", index.key().as_ref(), &(index.pages - 1).to_le_bytes()],
+        bump = page.bump
+    )]
+    pub page: Account<'info, TaskIndexPage>,
 }
 
 #[derive(Accounts)]
-pub struct VerifyTask<'info> {
-    #[account(mut, seeds = [b"market"], bump = market.bump)]
-    pub market: Account<'info, Market>,
-    
+pub struct SyncTaskIndex<'info> {
     #[account(mut)]
-    pub task: Account<'info, Task>,
+    pub page: Account<'info, TaskIndexPage>,
     
-    pub creator: Signer<'info>,
+    /// CHECK: Checked against the page; may be closed
+    pub task: UncheckedAccount<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct CancelTask<'info> {
-    #[account(mut)]
-    pub task: Account<'info, Task>,
+pub struct BoostTask<'info> {
+    #[account(seeds = [b"market"], bump = market.bump)]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut, has_one = creator @ ErrorCode::Unauthorized)]
+    pub task: AccountLoader<'info, Task>,
     
-    pub creator: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct AbortTask<'info> {
     #[account(mut)]
-    pub task: Account<'info, Task>,
+    pub creator: Signer<'info>,
     
-    pub authority: Signer<'info>,
+    /// CHECK: The market treasury, receiving the boost fee
+    #[account(mut, address = market.treasury @ ErrorCode::Unauthorized)]
+    pub treasury: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 // ============================================================================
@@ -508,36 +4418,469 @@ pub struct Market {
     pub total_completed: u64,
     pub total_volume: u64,
     pub fee_basis_points: u16,
+    /// Operator bond required per registered robot; zero for none
+    pub min_stake_per_robot: u64,
+    pub total_recurring: u64,
+    /// Performance bond held from the operator's stake per assigned task, in
+    /// basis points of its reward
+    pub task_bond_bps: u16,
+    /// Grace added to an accepted bid's duration to form a task's deadline
+    pub sla_buffer_seconds: i64,
+    /// Share of the task bond (basis points) forfeited for missing it
+    pub late_penalty_bps: u16,
+    /// Share of the remaining reward (basis points) paid to the robot when an
+    /// assigned task is cancelled
+    pub cancellation_fee_bps: u16,
+    /// Seconds creators of new tasks have to verify a completion before it
+    /// can be approved without them; zero for no limit
+    pub verification_window: i64,
+    /// Refundable lamports each new bid holds
+    pub bid_bond_lamports: u64,
+    /// Seconds an accepted bidder has to start before its bond is forfeit
+    pub start_window: i64,
+    /// Lamports per hour creators pay to boost a task
+    pub boost_fee_per_hour: u64,
+    /// Receives boost fees; default while boosting is disabled
+    pub treasury: Pubkey,
+    /// Circuit breaker blocking task creation, bidding and assignment
+    pub paused: bool,
+    /// Insurance premium in basis points of the insured amount, before the
+    /// reputation discount; zero when insurance is off
+    pub insurance_premium_bps: u16,
+    /// Seconds a disputed task has to be resolved before it settles in the
+    /// robot's favour
+    pub dispute_window: i64,
     pub bump: u8,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct Task {
-    pub creator: Pubkey,
+/// Parameters a task is opened with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TaskParams {
     #[max_len(64)]
     pub title: String,
     #[max_len(256)]
     pub description: String,
     pub robot_class: u8,
     #[max_len(5)]
-    pub required_capabilities: Vec<u8>,
+    pub capabilities: Vec<u8>,
     pub min_reputation: u16,
     pub reward: u64,
     pub rate_per_second: u64,
     pub estimated_duration: u32,
     pub priority: u8,
-    pub status: TaskStatus,
+    /// Seconds the task stays open for bids
+    pub expires_in: i64,
+    /// Completion needs a verified oracle-verifier proof for the task
+    pub requires_proof: bool,
+}
+
+impl TaskParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.title.len() <= 64, ErrorCode::TitleTooLong);
+        require!(self.description.len() <= 256, ErrorCode::DescriptionTooLong);
+        require!(self.capabilities.len() <= 5, ErrorCode::TooManyCapabilities);
+        require!(self.reward > 0, ErrorCode::InvalidReward);
+        require!(self.priority >= 1 && self.priority <= 5, ErrorCode::InvalidPriority);
+        require!(
+            self.expires_in > 0 && self.expires_in <= 7 * 86400,
+            ErrorCode::InvalidExpiration
+        );
+        robot_requirements(self.robot_class, &self.capabilities, self.min_reputation)?;
+        Ok(())
+    }
+}
+
+/// A task opened again every `interval` seconds, `occurrences` times
+#[account]
+#[derive(InitSpace)]
+pub struct RecurringTask {
+    pub creator: Pubkey,
+    /// Market recurring-task counter at creation, part of the PDA seeds
+    pub index: u64,
+    pub params: TaskParams,
+    pub interval: i64,
+    pub occurrences: u32,
+    pub spawned: u32,
+    pub next_due_at: i64,
+    /// Rent prepaid per occurrence, refunded to whoever spawns it
+    pub rent_per_occurrence: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+/// Task parameters published once and opened as tasks by any creator
+#[account]
+#[derive(InitSpace)]
+pub struct TaskTemplate {
+    pub publisher: Pubkey,
+    /// Publisher-chosen id, part of the PDA seeds
+    pub template_id: u64,
+    pub params: TaskParams,
+    pub tasks_created: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+/// A task, stored zero-copy: fields are ordered by alignment so the layout
+/// has no padding, optional keys and times are zero when unset, and
+/// variable-length fields are fixed arrays with their length alongside.
+/// The status and fault flag sit at fixed offsets other programs read.
+#[account(zero_copy)]
+#[derive(InitSpace)]
+pub struct Task {
+    pub creator: Pubkey,
+    /// `TaskStatus` discriminant, see `status`
+    pub status: u8,
+    /// Set when the task failed through its robot's fault
+    pub robot_fault: u8,
+    pub insurance_claimed: u8,
+    pub sla_breached: u8,
+    /// Completion is only approved with a verified oracle-verifier proof
+    pub requires_proof: u8,
+    pub robot_class: u8,
+    pub priority: u8,
+    pub progress: u8,
+    pub reward: u64,
+    pub rate_per_second: u64,
     pub created_at: i64,
     pub expires_at: i64,
-    pub assigned_robot: Option<Pubkey>,
-    pub assigned_at: Option<i64>,
-    pub started_at: Option<i64>,
-    pub completed_at: Option<i64>,
-    pub stream_id: Option<Pubkey>,
-    pub progress: u8,
+    pub assigned_at: i64,
+    pub started_at: i64,
+    pub completed_at: i64,
+    /// Market task counter at creation, part of the task's PDA seeds
+    pub index: u64,
+    /// End of the commit window for sealed bidding; zero for open bidding
+    pub commit_ends_at: i64,
+    pub reveal_ends_at: i64,
+    /// Area a verified GPS proof must come from before completion is
+    /// approved, while `geofence_radius_meters` is non-zero
+    pub geofence_latitude: i64,
+    pub geofence_longitude: i64,
+    /// Performance bond held from the assigned operator's stake
+    pub bond: u64,
+    /// Seconds from start to the SLA deadline, fixed at assignment
+    pub sla_window: i64,
+    /// Verification window taken from the market at creation
+    pub verification_window: i64,
+    /// Completion is approved automatically from this time if not verified
+    pub verification_due_at: i64,
+    /// A dispute unresolved by this time settles in the robot's favour
+    pub dispute_due_at: i64,
+    /// End of the task's paid priority boost, if it was boosted
+    pub boosted_until: i64,
+    /// Amount the creator is insured for if the robot fails the task
+    pub insured_amount: u64,
+    /// Premium paid for the insurance when the task was assigned
+    pub insurance_premium: u64,
+    /// Operator bond forfeited to the creator, netted off insurance payouts
+    pub bond_forfeited: u64,
+    pub event_seq: u64,
+    pub assigned_robot: Pubkey,
+    pub assigned_operator: Pubkey,
+    pub stream_id: Pubkey,
+    /// oracle-verifier dispute settling a disputed completion
+    pub dispute: Pubkey,
+    /// swarm-coordinator group task running this task, when it needs more
+    /// than one robot; bids go to the group task instead
+    pub group_task: Pubkey,
+    /// Last oracle-verifier proof backing a progress checkpoint, for tasks
+    /// without milestones
+    pub progress_proof: Pubkey,
+    /// SHA-256 of the deliverable submitted with completion
+    pub deliverable_hash: [u8; 32],
+    /// Merkle root of further allowed robots or operators
+    pub allowlist_root: [u8; 32],
+    /// Robots or operators allowed to bid; with no root, empty means anyone
+    pub allowlist: [Pubkey; MAX_ALLOWLIST_MEMBERS],
+    /// Escrow split paid per approved milestone; none for per-second pay
+    pub milestones: [TaskMilestone; MAX_TASK_MILESTONES],
+    pub estimated_duration: u32,
+    pub geofence_radius_meters: u32,
+    /// Schedule raising the reward and accepted rate while the task is
+    /// open, while `escalation_step_bps` is non-zero
+    pub escalation_interval: u32,
+    pub min_reputation: u16,
     pub bids_count: u16,
+    pub escalation_step_bps: u16,
+    pub escalation_cap_bps: u16,
+    pub description_len: u16,
+    pub title: [u8; 64],
+    pub description: [u8; 256],
+    pub deliverable_uri: [u8; MAX_DELIVERABLE_URI_LEN],
+    pub required_capabilities: [u8; 5],
+    pub title_len: u8,
+    pub deliverable_uri_len: u8,
+    pub capabilities_len: u8,
+    pub milestone_count: u8,
+    pub allowlist_len: u8,
+    /// Task indexes the task has been added to: bit 0 for its class, bit
+    /// 1 + i for its capability i
+    pub indexed: u8,
+    /// Creator's $DRONEOS fee tier applied to the platform fee at
+    /// verification, 0 for none
+    pub fee_tier: u8,
     pub bump: u8,
+    pub _padding: [u8; 1],
+}
+
+/// Offset of `Task::status` in a task account's data, past the discriminator
+pub const TASK_STATUS_OFFSET: usize = 8 + 32;
+
+/// Offset of `Task::robot_fault` in a task account's data
+pub const TASK_ROBOT_FAULT_OFFSET: usize = TASK_STATUS_OFFSET + 1;
+
+/// `Some(key)` unless `key` is the default "unset" key
+pub fn key_if_set(key: Pubkey) -> Option<Pubkey> {
+    (key != Pubkey::default()).then_some(key)
+}
+
+impl Task {
+    pub fn status(&self) -> TaskStatus {
+        TaskStatus::try_from_slice(&[self.status]).expect("task status is a TaskStatus")
+    }
+
+    pub fn set_status(&mut self, status: TaskStatus) {
+        self.status = status as u8;
+    }
+
+    pub fn title(&self) -> String {
+        String::from_utf8_lossy(&self.title[..self.title_len as usize]).into_owned()
+    }
+
+    pub fn description(&self) -> String {
+        String::from_utf8_lossy(&self.description[..self.description_len as usize]).into_owned()
+    }
+
+    pub fn deliverable_uri(&self) -> Option<String> {
+        (self.deliverable_uri_len > 0).then(|| {
+            String::from_utf8_lossy(&self.deliverable_uri[..self.deliverable_uri_len as usize])
+                .into_owned()
+        })
+    }
+
+    pub fn required_capabilities(&self) -> &[u8] {
+        &self.required_capabilities[..self.capabilities_len as usize]
+    }
+
+    pub fn milestones(&self) -> &[TaskMilestone] {
+        &self.milestones[..self.milestone_count as usize]
+    }
+
+    pub fn milestones_mut(&mut self) -> &mut [TaskMilestone] {
+        &mut self.milestones[..self.milestone_count as usize]
+    }
+
+    pub fn allowlist(&self) -> &[Pubkey] {
+        &self.allowlist[..self.allowlist_len as usize]
+    }
+
+    pub fn geofence(&self) -> Option<Geofence> {
+        (self.geofence_radius_meters > 0).then_some(Geofence {
+            latitude: self.geofence_latitude,
+            longitude: self.geofence_longitude,
+            radius_meters: self.geofence_radius_meters,
+        })
+    }
+
+    pub fn escalation(&self) -> Option<RewardEscalation> {
+        (self.escalation_step_bps > 0).then_some(RewardEscalation {
+            step_bps: self.escalation_step_bps,
+            interval: self.escalation_interval,
+            cap_bps: self.escalation_cap_bps,
+        })
+    }
+
+    // Setters for the fixed-size fields; callers check the lengths first
+
+    pub fn set_title(&mut self, title: &str) {
+        self.title = [0; 64];
+        self.title[..title.len()].copy_from_slice(title.as_bytes());
+        self.title_len = title.len() as u8;
+    }
+
+    pub fn set_description(&mut self, description: &str) {
+        self.description = [0; 256];
+        self.description[..description.len()].copy_from_slice(description.as_bytes());
+        self.description_len = description.len() as u16;
+    }
+
+    pub fn set_deliverable_uri(&mut self, uri: Option<&str>) {
+        let uri = uri.unwrap_or_default();
+        self.deliverable_uri = [0; MAX_DELIVERABLE_URI_LEN];
+        self.deliverable_uri[..uri.len()].copy_from_slice(uri.as_bytes());
+        self.deliverable_uri_len = uri.len() as u8;
+    }
+
+    pub fn set_required_capabilities(&mut self, capabilities: &[u8]) {
+        self.required_capabilities = [0; 5];
+        self.required_capabilities[..capabilities.len()].copy_from_slice(capabilities);
+        self.capabilities_len = capabilities.len() as u8;
+    }
+
+    pub fn set_milestones(&mut self, terms: &[MilestoneTerms]) {
+        self.milestones = [TaskMilestone::default(); MAX_TASK_MILESTONES];
+        for (milestone, terms) in self.milestones.iter_mut().zip(terms) {
+            milestone.share_bps = terms.share_bps;
+            milestone.proof_type = terms.proof_type.unwrap_or(NO_PROOF_TYPE);
+        }
+        self.milestone_count = terms.len() as u8;
+    }
+
+    pub fn set_allowlist(&mut self, members: &[Pubkey]) {
+        self.allowlist = [Pubkey::default(); MAX_ALLOWLIST_MEMBERS];
+        self.allowlist[..members.len()].copy_from_slice(members);
+        self.allowlist_len = members.len() as u8;
+    }
+
+    pub fn set_geofence(&mut self, geofence: Option<Geofence>) {
+        let fence = geofence.unwrap_or(Geofence { latitude: 0, longitude: 0, radius_meters: 0 });
+        self.geofence_latitude = fence.latitude;
+        self.geofence_longitude = fence.longitude;
+        self.geofence_radius_meters = fence.radius_meters;
+    }
+
+    pub fn set_escalation(&mut self, escalation: Option<RewardEscalation>) {
+        let schedule = escalation.unwrap_or(RewardEscalation { step_bps: 0, interval: 0, cap_bps: 0 });
+        self.escalation_step_bps = schedule.step_bps;
+        self.escalation_interval = schedule.interval;
+        self.escalation_cap_bps = schedule.cap_bps;
+    }
+
+    /// Whether the task's paid priority boost is running at `now`
+    pub fn is_boosted(&self, now: i64) -> bool {
+        now < self.boosted_until
+    }
+
+    /// Whether `member` (a robot or operator) may bid. Merkle leaves are
+    /// `keccak(member)`; each parent is the keccak of its two children,
+    /// smaller first, so proofs need no left/right flags.
+    pub fn is_allowlisted(&self, member: &Pubkey, proof: &[[u8; 32]]) -> bool {
+        if self.allowlist().contains(member) {
+            return true;
+        }
+        if self.allowlist_root == [0; 32] {
+            return self.allowlist_len == 0;
+        }
+
+        let leaf = keccak::hashv(&[member.as_ref()]).to_bytes();
+        let computed = proof.iter().fold(leaf, |node, sibling| {
+            if node <= *sibling {
+                keccak::hashv(&[&node, sibling]).to_bytes()
+            } else {
+                keccak::hashv(&[sibling, &node]).to_bytes()
+            }
+        });
+        computed == self.allowlist_root
+    }
+
+    /// SLA deadline, once the task has started
+    pub fn deadline(&self) -> Option<i64> {
+        (self.started_at != 0).then(|| self.started_at + self.sla_window)
+    }
+}
+
+/// Microdegrees per degree, the fixed-point scale of coordinates
+const MICRODEGREES: i128 = 1_000_000;
+
+/// A circle around a point, in microdegrees
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct Geofence {
+    pub latitude: i64,
+    pub longitude: i64,
+    pub radius_meters: u32,
+}
+
+impl Geofence {
+    /// Whether a point lies inside the fence, by the equirectangular
+    /// approximation in integer math, which is accurate at geofence scales
+    pub fn contains(&self, latitude: i64, longitude: i64) -> bool {
+        // Millimetres per microdegree of latitude: 111.32
+        const MM_PER_MICRODEGREE_X100: i128 = 11_132;
+
+        let d_lat = (latitude - self.latitude) as i128;
+        let mut d_lon = (longitude - self.longitude) as i128;
+        // Take the short way round the antimeridian
+        if d_lon > 180 * MICRODEGREES {
+            d_lon -= 360 * MICRODEGREES;
+        } else if d_lon < -180 * MICRODEGREES {
+            d_lon += 360 * MICRODEGREES;
+        }
+
+        // cos(latitude), scaled by MICRODEGREES, from its Taylor series up
+        // to x^6, within 0.001 of the true value across -90..90 degrees
+        let x = self.latitude as i128 * 174_533 / 10_000_000;
+        let x2 = x * x / MICRODEGREES;
+        let x4 = x2 * x2 / MICRODEGREES;
+        let x6 = x4 * x2 / MICRODEGREES;
+        let cos = (MICRODEGREES - x2 / 2 + x4 / 24 - x6 / 720).clamp(0, MICRODEGREES);
+
+        let north = d_lat * MM_PER_MICRODEGREE_X100 / 100;
+        let east = d_lon * MM_PER_MICRODEGREE_X100 / 100 * cos / MICRODEGREES;
+        let radius = self.radius_meters as i128 * 1_000;
+        north * north + east * east <= radius * radius
+    }
+}
+
+/// Dutch-auction schedule raising a task's terms by `step_bps` every
+/// `interval` seconds it stays open, up to `cap_bps` in total
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub struct RewardEscalation {
+    pub step_bps: u16,
+    pub interval: u32,
+    pub cap_bps: u16,
+}
+
+impl RewardEscalation {
+    /// Increase in basis points once the task has been open `elapsed` seconds
+    pub fn bps_after(&self, elapsed: i64) -> u64 {
+        let steps = elapsed.max(0) as u64 / self.interval as u64;
+        (steps * self.step_bps as u64).min(self.cap_bps as u64)
+    }
+
+    /// `amount` raised by `bps_after(elapsed)`
+    pub fn apply(&self, amount: u64, elapsed: i64) -> u64 {
+        (amount as u128 * (10_000 + self.bps_after(elapsed)) as u128 / 10_000) as u64
+    }
+}
+
+/// A milestone as set by the task's creator
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct MilestoneTerms {
+    /// Share of the task's escrow, in basis points
+    pub share_bps: u16,
+    /// oracle-verifier `ProofType` index a verified proof must have, if any
+    pub proof_type: Option<u8>,
+}
+
+/// `TaskMilestone::proof_type` of a milestone needing no proof
+pub const NO_PROOF_TYPE: u8 = u8::MAX;
+
+#[zero_copy]
+#[derive(Default, PartialEq, Eq, InitSpace)]
+pub struct TaskMilestone {
+    /// Last oracle-verifier proof backing a progress checkpoint of this
+    /// milestone; default while none has
+    pub last_proof: Pubkey,
+    pub share_bps: u16,
+    /// oracle-verifier `ProofType` index a verified proof must have, or
+    /// `NO_PROOF_TYPE`
+    pub proof_type: u8,
+    /// `MilestoneStatus` discriminant, see `status`
+    pub status: u8,
+}
+
+impl TaskMilestone {
+    pub fn status(&self) -> MilestoneStatus {
+        MilestoneStatus::try_from_slice(&[self.status]).expect("milestone status is a MilestoneStatus")
+    }
+
+    pub fn set_status(&mut self, status: MilestoneStatus) {
+        self.status = status as u8;
+    }
+
+    pub fn proof_type(&self) -> Option<u8> {
+        (self.proof_type != NO_PROOF_TYPE).then_some(self.proof_type)
+    }
 }
 
 #[account]
@@ -552,6 +4895,13 @@ pub struct Bid {
     pub message: String,
     pub status: BidStatus,
     pub submitted_at: i64,
+    /// The bid can be accepted before this time
+    pub valid_until: i64,
+    /// Hash of the sealed rate and salt, for committed bids
+    pub commitment: Option<[u8; 32]>,
+    /// Lamports held in this account as the bid's bond, over its rent
+    pub bond: u64,
+    pub event_seq: u64,
     pub bump: u8,
 }
 
@@ -568,12 +4918,67 @@ pub enum TaskStatus {
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
-pub enum BidStatus {
+pub enum BidStatus {
+    Pending,
+    Accepted,
+    Rejected,
+    Withdrawn,
+    Expired,
+    Committed,
+}
+
+/// Robots and operators a creator won't take bids from, at
+/// ["blacklist", creator]
+#[account]
+#[derive(InitSpace)]
+pub struct Blacklist {
+    pub creator: Pubkey,
+    #[max_len(MAX_BLACKLIST_MEMBERS)]
+    pub members: Vec<Pubkey>,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+/// What a task index groups tasks by; its key is a `RobotClass` or
+/// `Capability` discriminant
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum TaskIndexKind {
+    Class,
+    Capability,
+}
+
+/// Head of the discovery index for one robot class or capability
+#[account]
+#[derive(InitSpace)]
+pub struct TaskIndex {
+    pub kind: TaskIndexKind,
+    pub key: u8,
+    /// Pages so far; tasks are appended to the last
+    pub pages: u32,
+    pub bump: u8,
+}
+
+/// Append-only page of a task index
+#[account]
+#[derive(InitSpace)]
+pub struct TaskIndexPage {
+    pub index: Pubkey,
+    pub page: u32,
+    #[max_len(TASK_INDEX_PAGE_SIZE)]
+    pub tasks: Vec<Pubkey>,
+    /// Bit i set while `tasks[i]` is open for bids, as of its last sync
+    pub open: u64,
+    /// Bit i set while `tasks[i]` is open and boosted, as of its last sync
+    pub boosted: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum MilestoneStatus {
     Pending,
-    Accepted,
-    Rejected,
-    Withdrawn,
-    Expired,
+    /// Done by the robot, awaiting the creator's approval
+    Completed,
+    Released,
 }
 
 // ============================================================================
@@ -582,6 +4987,7 @@ pub enum BidStatus {
 
 #[event]
 pub struct TaskCreated {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub creator: Pubkey,
     pub title: String,
@@ -589,28 +4995,123 @@ pub struct TaskCreated {
     pub expires_at: i64,
 }
 
+#[event]
+pub struct TaskDelegatedToSwarm {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub group_task: Pubkey,
+    pub required_robots: u8,
+}
+
+#[event]
+pub struct RecurringTaskCreated {
+    pub header: EventHeader,
+    pub recurring: Pubkey,
+    pub creator: Pubkey,
+    pub interval: i64,
+    pub occurrences: u32,
+    pub next_due_at: i64,
+}
+
+#[event]
+pub struct OccurrenceSpawned {
+    pub header: EventHeader,
+    pub recurring: Pubkey,
+    pub task: Pubkey,
+    pub occurrence: u32,
+    pub next_due_at: i64,
+}
+
+#[event]
+pub struct RecurringTaskCancelled {
+    pub header: EventHeader,
+    pub recurring: Pubkey,
+    pub spawned: u32,
+}
+
+#[event]
+pub struct TaskTemplateCreated {
+    pub header: EventHeader,
+    pub template: Pubkey,
+    pub publisher: Pubkey,
+    pub title: String,
+}
+
 #[event]
 pub struct BidSubmitted {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub bid: Pubkey,
     pub robot: Pubkey,
     pub proposed_rate: u64,
     pub estimated_duration: u32,
+    pub valid_until: i64,
+}
+
+#[event]
+pub struct SealedBiddingEnabled {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub commit_ends_at: i64,
+    pub reveal_ends_at: i64,
+}
+
+#[event]
+pub struct BidCommitted {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub bid: Pubkey,
+    pub robot: Pubkey,
+    pub estimated_duration: u32,
+    pub valid_until: i64,
+}
+
+#[event]
+pub struct BidRevealed {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub bid: Pubkey,
+    pub proposed_rate: u64,
 }
 
 #[event]
 pub struct BidRejected {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub bid: Pubkey,
 }
 
 #[event]
 pub struct BidWithdrawn {
+    pub header: EventHeader,
+    pub bid: Pubkey,
+}
+
+#[event]
+pub struct BidExpired {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub bid: Pubkey,
+}
+
+#[event]
+pub struct BidBondForfeited {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub bid: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct BidClosed {
+    pub header: EventHeader,
     pub bid: Pubkey,
 }
 
 #[event]
 pub struct TaskAssigned {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub robot: Pubkey,
     pub rate: u64,
@@ -619,6 +5120,7 @@ pub struct TaskAssigned {
 
 #[event]
 pub struct TaskStarted {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub robot: Pubkey,
     pub timestamp: i64,
@@ -626,18 +5128,24 @@ pub struct TaskStarted {
 
 #[event]
 pub struct TaskProgressUpdated {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub progress: u8,
+    pub proof: Option<Pubkey>,
 }
 
 #[event]
 pub struct TaskPendingVerification {
+    pub header: EventHeader,
     pub task: Pubkey,
+    pub deliverable_hash: [u8; 32],
+    pub deliverable_uri: Option<String>,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct TaskCompleted {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub robot: Pubkey,
     pub total_paid: u64,
@@ -646,20 +5154,191 @@ pub struct TaskCompleted {
 
 #[event]
 pub struct TaskDisputed {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TaskDisputeOpened {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub dispute: Pubkey,
+}
+
+#[event]
+pub struct TaskDisputeResolved {
+    pub header: EventHeader,
     pub task: Pubkey,
+    pub dispute: Pubkey,
+    pub upheld: bool,
+    pub slashed: u64,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct TaskCancelled {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AssignedTaskCancelled {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub robot: Pubkey,
+    pub cancellation_fee_bps: u16,
+    /// Whether the task went back to Open rather than Cancelled
+    pub reopened: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SlaBreached {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub deadline: i64,
+    /// Bond forfeited to the creator
+    pub penalty: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TaskExpired {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TaskClosed {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub bids_closed: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RemainingBidsRejected {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub bids_closed: u16,
+}
+
+#[event]
+pub struct TaskMilestonesSet {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub milestones: Vec<MilestoneTerms>,
+}
+
+#[event]
+pub struct TaskBoosted {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub fee: u64,
+    pub boosted_until: i64,
+}
+
+#[event]
+pub struct TaskUpdated {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub reward: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct TaskIndexed {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub index: Pubkey,
+    pub page: Pubkey,
+    pub slot: u8,
+}
+
+#[event]
+pub struct TaskAllowlistSet {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub member_count: u8,
+    pub root: Option<[u8; 32]>,
+}
+
+#[event]
+pub struct BlacklistUpdated {
+    pub header: EventHeader,
+    pub creator: Pubkey,
+    pub member: Pubkey,
+    pub added: bool,
+}
+
+#[event]
+pub struct TaskGeofenceSet {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub latitude: i64,
+    pub longitude: i64,
+    pub radius_meters: u32,
+}
+
+#[event]
+pub struct TaskEscalationSet {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub step_bps: u16,
+    pub interval: u32,
+    pub cap_bps: u16,
+}
+
+#[event]
+pub struct TaskRewardEscalated {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub escalation_bps: u64,
+    pub reward: u64,
+}
+
+#[event]
+pub struct TaskMilestoneCompleted {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub index: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TaskMilestoneApproved {
+    pub header: EventHeader,
     pub task: Pubkey,
+    pub index: u8,
+    pub share_bps: u16,
     pub timestamp: i64,
 }
 
+#[event]
+pub struct TaskInsured {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub insured_amount: u64,
+    pub premium: u64,
+}
+
+#[event]
+pub struct TaskInsuranceClaimed {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub paid: u64,
+}
+
 #[event]
 pub struct TaskAborted {
+    pub header: EventHeader,
     pub task: Pubkey,
     pub reason: String,
+    pub robot_fault: bool,
+    pub slashed: u64,
     pub timestamp: i64,
 }
 
@@ -725,4 +5404,376 @@ pub enum ErrorCode {
     
     #[msg("Not the assigned robot")]
     NotAssignedRobot,
+    
+    #[msg("Stream does not belong to this task")]
+    StreamMismatch,
+    
+    #[msg("Operator stake too low for fleet size")]
+    InsufficientFleetBond,
+    
+    #[msg("Invalid robot class")]
+    InvalidRobotClass,
+    
+    #[msg("Invalid capability")]
+    InvalidCapability,
+    
+    #[msg("Signer does not operate this robot")]
+    NotRobotOperator,
+    
+    #[msg("Task has not expired yet")]
+    TaskNotExpired,
+    
+    #[msg("Task is not completed, failed or cancelled")]
+    TaskNotFinished,
+    
+    #[msg("Remaining accounts must be writable (bid, operator) pairs")]
+    InvalidBidAccounts,
+    
+    #[msg("Bid has expired")]
+    BidExpired,
+    
+    #[msg("Bid has not expired yet")]
+    BidNotExpired,
+    
+    #[msg("Bid is pending or accepted")]
+    BidStillActive,
+    
+    #[msg("Task is not disputed")]
+    TaskNotDisputed,
+    
+    #[msg("Task already has a dispute")]
+    DisputeAlreadyOpen,
+    
+    #[msg("Not the creator's dispute over this task's completion proof")]
+    DisputeMismatch,
+    
+    #[msg("Dispute has not been resolved yet")]
+    DisputeNotResolved,
+    
+    #[msg("Task already has bids")]
+    TaskHasBids,
+    
+    #[msg("Commit and reveal windows must be positive and end before the task expires")]
+    InvalidBidWindows,
+    
+    #[msg("Task takes sealed bids only")]
+    SealedBidsOnly,
+    
+    #[msg("Task does not use sealed bidding")]
+    NotSealedBidding,
+    
+    #[msg("Commit window has closed")]
+    CommitWindowClosed,
+    
+    #[msg("Not in the task's reveal window")]
+    NotInRevealWindow,
+    
+    #[msg("Bid is not committed")]
+    BidNotCommitted,
+    
+    #[msg("Rate and salt do not match the commitment")]
+    CommitmentMismatch,
+    
+    #[msg("Sealed bids can't be accepted until the reveal window ends")]
+    RevealWindowOpen,
+    
+    #[msg("Recurrence interval and occurrence count must be positive")]
+    InvalidRecurrence,
+    
+    #[msg("All occurrences have been spawned")]
+    RecurrenceFinished,
+    
+    #[msg("Next occurrence is not due yet")]
+    OccurrenceNotDue,
+    
+    #[msg("Milestone shares must be non-zero and total 100%, with valid proof types")]
+    InvalidMilestones,
+    
+    #[msg("Milestone does not exist")]
+    InvalidMilestone,
+    
+    #[msg("Milestone is not pending")]
+    MilestoneNotPending,
+    
+    #[msg("Milestone has not been completed")]
+    MilestoneNotCompleted,
+    
+    #[msg("Milestone requires an oracle proof")]
+    ProofRequired,
+    
+    #[msg("Proof is not an oracle proof of the required type for this task")]
+    ProofMismatch,
+    
+    #[msg("Proof has not been verified")]
+    ProofNotVerified,
+    
+    #[msg("Geofence coordinates out of range or radius is zero")]
+    InvalidGeofence,
+    
+    #[msg("GPS proof is outside the task's geofence")]
+    OutsideGeofence,
+    
+    #[msg("Task bond cannot exceed 100% of the reward")]
+    InvalidTaskBond,
+    
+    #[msg("Operator needs an operator stake to hold this task's bond")]
+    TaskBondRequired,
+    
+    #[msg("SLA buffer cannot be negative and late penalty cannot exceed 100%")]
+    InvalidSlaTerms,
+    
+    #[msg("Task is not past its SLA deadline")]
+    TaskNotLate,
+    
+    #[msg("Task has already been flagged late")]
+    AlreadyFlaggedLate,
+    
+    #[msg("Deliverable URI too long (max 128 characters)")]
+    DeliverableUriTooLong,
+    
+    #[msg("Swarm-coordinator accounts are required for tasks needing more than one robot")]
+    SwarmAccountsRequired,
+    
+    #[msg("Task is run by a swarm; bid on its group task")]
+    SwarmTask,
+    
+    #[msg("Cancellation fee cannot exceed 100%")]
+    InvalidCancellationFee,
+    
+    #[msg("Verification window cannot be negative")]
+    InvalidVerificationWindow,
+    
+    #[msg("Verification window has not elapsed")]
+    VerificationWindowOpen,
+    
+    #[msg("Dispute window must be positive")]
+    InvalidDisputeWindow,
+    
+    #[msg("Allowlist has too many members (max 10)")]
+    AllowlistTooLarge,
+    
+    #[msg("Robot and operator are not on the task's allowlist")]
+    NotAllowlisted,
+    
+    #[msg("Task does not match this index's class or capability")]
+    TaskIndexMismatch,
+    
+    #[msg("Task is already in this index")]
+    TaskAlreadyIndexed,
+    
+    #[msg("Task index page is full; add a page")]
+    TaskIndexPageFull,
+    
+    #[msg("Task index's last page is not full yet")]
+    TaskIndexPageNotFull,
+    
+    #[msg("Start window cannot be negative")]
+    InvalidStartWindow,
+    
+    #[msg("Bid has not been accepted")]
+    BidNotAccepted,
+    
+    #[msg("Accepted bidder is still within its start window")]
+    StartWindowOpen,
+    
+    #[msg("Task boosting is disabled")]
+    BoostingDisabled,
+    
+    #[msg("Boost duration must be positive")]
+    InvalidBoostDuration,
+    
+    #[msg("Creator's blacklist is full")]
+    BlacklistFull,
+    
+    #[msg("Already blacklisted")]
+    AlreadyBlacklisted,
+    
+    #[msg("Not blacklisted")]
+    NotBlacklisted,
+    
+    #[msg("Robot or operator is blacklisted by the task creator")]
+    Blacklisted,
+    
+    #[msg("Market is paused")]
+    MarketPaused,
+    
+    #[msg("Insurance premium cannot exceed 100%")]
+    InvalidInsurancePremium,
+    
+    #[msg("Insured amount cannot exceed the reward")]
+    InvalidInsuredAmount,
+    
+    #[msg("Task insurance is disabled")]
+    InsuranceDisabled,
+    
+    #[msg("droneos_token insurance accounts are required to insure a task")]
+    InsuranceAccountsRequired,
+    
+    #[msg("Task is not insured")]
+    TaskNotInsured,
+    
+    #[msg("An insured task can't be assigned to its creator's own operator")]
+    InsuredSelfDealing,
+    
+    #[msg("Task's creator has no loss left to cover")]
+    NoInsuredLoss,
+    
+    #[msg("Task did not fail through its robot's fault")]
+    NotRobotFault,
+    
+    #[msg("Task insurance already claimed")]
+    InsuranceAlreadyClaimed,
+    
+    #[msg("Escalation needs a non-zero interval and a cap of at least one step")]
+    InvalidEscalation,
+    
+    #[msg("Bid rate is above the task's escalated rate")]
+    BidAboveEscalatedRate,
+    
+    #[msg("Task is still open for bids")]
+    TaskStillOpen,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    /// The data of a task account holding `task`
+    fn task_data(task: &Task) -> Vec<u8> {
+        [&Task::DISCRIMINATOR[..], bytemuck::bytes_of(task)].concat()
+    }
+
+    #[test]
+    fn task_layout_has_no_padding() {
+        assert_eq!(std::mem::size_of::<Task>(), Task::INIT_SPACE);
+        assert_eq!(std::mem::size_of::<Task>() % 8, 0);
+        assert_eq!(8 + std::mem::offset_of!(Task, status), TASK_STATUS_OFFSET);
+        assert_eq!(8 + std::mem::offset_of!(Task, robot_fault), TASK_ROBOT_FAULT_OFFSET);
+    }
+
+    #[test]
+    fn text_fills_its_fixed_buffer_up_to_capacity() {
+        let mut task = Task::zeroed();
+        let title = "t".repeat(64);
+        let description = "d".repeat(256);
+        task.set_title(&title);
+        task.set_description(&description);
+        assert_eq!(task.title(), title);
+        assert_eq!(task.description(), description);
+
+        // A shorter value clears what the longer one left behind
+        task.set_title("Survey");
+        task.set_description("Map the north field");
+        assert_eq!(task.title(), "Survey");
+        assert_eq!(task.title_len, 6);
+        assert!(task.title[6..].iter().all(|&b| b == 0));
+        assert_eq!(task.description(), "Map the north field");
+        assert!(task.description[19..].iter().all(|&b| b == 0));
+
+        assert_eq!(task.deliverable_uri(), None);
+        task.set_deliverable_uri(Some("ipfs://deliverable"));
+        assert_eq!(task.deliverable_uri().as_deref(), Some("ipfs://deliverable"));
+        task.set_deliverable_uri(None);
+        assert_eq!(task.deliverable_uri(), None);
+        assert!(task.deliverable_uri.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn accessors_read_back_what_the_setters_wrote() {
+        let mut task = Task::zeroed();
+        assert!(task.status() == TaskStatus::Open);
+        task.set_status(TaskStatus::Disputed);
+        assert!(task.status() == TaskStatus::Disputed);
+
+        let capabilities = [Capability::Surveillance as u8, Capability::Inspection as u8];
+        task.set_required_capabilities(&capabilities);
+        assert_eq!(task.required_capabilities(), capabilities);
+
+        task.set_milestones(&[
+            MilestoneTerms { share_bps: 4_000, proof_type: Some(1) },
+            MilestoneTerms { share_bps: 6_000, proof_type: None },
+        ]);
+        assert_eq!(task.milestones().len(), 2);
+        assert_eq!(task.milestones()[0].proof_type(), Some(1));
+        assert_eq!(task.milestones()[1].proof_type(), None);
+        assert_eq!(task.milestones()[1].share_bps, 6_000);
+        task.milestones_mut()[0].set_status(MilestoneStatus::Released);
+        assert!(task.milestones()[0].status() == MilestoneStatus::Released);
+        assert!(task.milestones()[1].status() == MilestoneStatus::Pending);
+
+        let member = Pubkey::new_unique();
+        assert!(task.is_allowlisted(&member, &[]));
+        task.set_allowlist(&[member]);
+        assert_eq!(task.allowlist(), [member]);
+        assert!(task.is_allowlisted(&member, &[]));
+        assert!(!task.is_allowlisted(&Pubkey::new_unique(), &[]));
+
+        assert!(task.geofence().is_none());
+        let fence = Geofence { latitude: 52_000_000, longitude: 4_000_000, radius_meters: 500 };
+        task.set_geofence(Some(fence));
+        assert!(task.geofence() == Some(fence));
+        task.set_geofence(None);
+        assert!(task.geofence().is_none());
+
+        assert!(task.escalation().is_none());
+        let escalation = RewardEscalation { step_bps: 100, interval: 3_600, cap_bps: 1_000 };
+        task.set_escalation(Some(escalation));
+        assert!(task.escalation() == Some(escalation));
+
+        assert_eq!(task.deadline(), None);
+        task.started_at = 1_000;
+        task.sla_window = 600;
+        assert_eq!(task.deadline(), Some(1_600));
+
+        assert_eq!(key_if_set(task.assigned_robot), None);
+        task.assigned_robot = member;
+        assert_eq!(key_if_set(task.assigned_robot), Some(member));
+    }
+
+    #[test]
+    fn task_accounts_round_trip_through_account_loader() {
+        let mut task = Task::zeroed();
+        task.creator = Pubkey::new_unique();
+        task.reward = 5_000_000;
+        task.set_title("Survey");
+        task.set_status(TaskStatus::InProgress);
+        task.robot_fault = 1;
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = task_data(&task);
+        let info =
+            AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+        let loader = AccountLoader::<Task>::try_from(&info).unwrap();
+        {
+            let loaded = loader.load().unwrap();
+            assert_eq!(loaded.creator, task.creator);
+            assert_eq!(loaded.reward, 5_000_000);
+            assert_eq!(loaded.title(), "Survey");
+            assert!(loaded.status() == TaskStatus::InProgress);
+        }
+
+        loader.load_mut().unwrap().set_status(TaskStatus::Failed);
+        let data = info.try_borrow_data().unwrap();
+        assert_eq!(data[TASK_STATUS_OFFSET], TaskStatus::Failed as u8);
+        assert_eq!(data[TASK_ROBOT_FAULT_OFFSET], 1);
+    }
+
+    #[test]
+    fn account_loader_rejects_foreign_accounts() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = task_data(&Task::zeroed());
+        let owner = Pubkey::new_unique();
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, 0);
+        assert!(AccountLoader::<Task>::try_from(&info).is_err());
+
+        let mut lamports = 0;
+        let mut data = task_data(&Task::zeroed());
+        data[..8].copy_from_slice(&Bid::DISCRIMINATOR);
+        let info =
+            AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+        assert!(AccountLoader::<Task>::try_from(&info).is_err());
+    }
 }
@@ -1,35 +1,291 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer, MintTo};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program::{self, CreateAccount, Transfer};
+use anchor_lang::Discriminator;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_2022::{self, spl_token_2022, InitializeMint2, Token2022};
+use anchor_spl::token_2022_extensions::spl_token_metadata_interface::state::Field;
+use anchor_spl::token_interface::{
+    self, Burn, MetadataPointerInitialize, Mint, MintTo, SetAuthority, TokenAccount,
+    TokenInterface, TokenMetadataInitialize, TokenMetadataUpdateField, TransferChecked,
+    TransferFeeInitialize, TransferFeeSetTransferFee, WithdrawWithheldTokensFromMint,
+};
+use droneos_events::{EventHeader, ProgramTag};
+use identity_registry::program::IdentityRegistry;
+use identity_registry::RobotStatus;
 
 declare_id!("DOS4tkn1111111111111111111111111111111111111");
 
-/// $DRONEOS Token Program
-/// 
-/// $DRONEOS Token operations:
-/// - Token initialization
-/// - Staking with lock periods
-/// - Reward distribution
-/// - Operator stake management
-
 // Constants
 const DECIMALS: u8 = 6;
 const TOTAL_SUPPLY: u64 = 1_000_000_000 * 1_000_000; // 1B tokens
-const BASE_APY_BPS: u64 = 1200; // 12% base APY
-const MIN_STAKE: u64 = 100 * 1_000_000; // 100 DRONEOS minimum
+const DEFAULT_BASE_APY_BPS: u16 = 1200; // 12% base APY
+const DEFAULT_MIN_STAKE: u64 = 100 * 1_000_000; // 100 DRONEOS minimum
+const DEFAULT_MIN_OPERATOR_STAKE: u64 = 1_000 * 1_000_000; // 1K DRONEOS minimum
+const MAX_BASE_APY_BPS: u16 = 5000;
+const MAX_MIN_STAKE: u64 = 100_000 * 1_000_000;
+const PARAMETER_TIMELOCK: i64 = 2 * 24 * 60 * 60;
+const MAX_TRANSFER_FEE_BPS: u16 = 500; // 5%
+const MAX_NAME_LEN: usize = 32;
+const MAX_SYMBOL_LEN: usize = 10;
+const MAX_URI_LEN: usize = 200;
 const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+const MAX_EMISSION_EPOCHS: usize = 64;
+const CRANK_TIP_BPS: u64 = 10; // 0.1% of the epoch's budget
+const MAX_CRANK_TIP: u64 = 100 * 1_000_000;
+const MAX_LOCK_SECONDS: u64 = 365 * 24 * 60 * 60; // remaining lock worth one vote per token
+const MAX_LOCK_TIERS: usize = 8;
+const PROPOSAL_VOTING_PERIOD: i64 = 5 * 24 * 60 * 60;
+const MIN_PROPOSAL_POWER: u64 = 10_000 * 1_000_000; // 10K veDRONEOS to propose
+const PROPOSAL_QUORUM: u64 = 1_000_000 * 1_000_000; // 1M veDRONEOS in favour to pass
+const MAX_PROPOSAL_ACCOUNTS: usize = 8;
+const MAX_PROPOSAL_DATA: usize = 256;
+const OPERATOR_UNBONDING_PERIOD: i64 = 7 * 24 * 60 * 60;
+const REFERRAL_BONUS_BPS: u64 = 100; // 1% of the referred stake
+const REFERRAL_EPOCH: i64 = 7 * 24 * 60 * 60;
+const REFERRAL_EPOCH_CAP: u64 = 10_000 * 1_000_000; // per referrer per epoch
+const DELEGATION_COOLDOWN: i64 = 7 * 24 * 60 * 60;
+const INCOME_PRECISION: u128 = 1_000_000_000_000;
+const SLASH_APPEAL_WINDOW: i64 = 3 * 24 * 60 * 60;
+const SLASH_APPEAL_BOND: u64 = 100 * 1_000_000; // 100 DRONEOS, forfeited if the appeal fails
+const MAX_SLASHER_PROGRAMS: usize = 4;
+const MAX_SWAP_PROGRAMS: usize = 4;
+const MAX_REPUTATION_BOOST_BPS: u64 = 5000; // 1.5x at full reputation
+const REPUTATION_BOOST_FLOOR: u16 = 5000; // where robots start, earns no boost
+const BUYBACK_EPOCH: i64 = 7 * 24 * 60 * 60;
+const STAKE_ACCOUNT_VERSION: u8 = 2;
+const OPERATOR_STAKE_VERSION: u8 = 3;
+
+// Programs that depend on this one. Their ids are declared here because
+// importing them from their crates would be a dependency cycle.
+
+/// task-market, which reports operators' active tasks.
+pub const TASK_MARKET_PROGRAM_ID: Pubkey =
+    pubkey!("DOS4mkt1111111111111111111111111111111111111");
+
+/// Offsets into a task-market `Task` account (after its discriminator come
+/// `creator`, `status`, `robot_fault`) and the `TaskStatus::Failed` tag.
+const TASK_CREATOR_RANGE: std::ops::Range<usize> = 8..40;
+const TASK_STATUS_OFFSET: usize = 40;
+const TASK_ROBOT_FAULT_OFFSET: usize = 41;
+const TASK_STATUS_FAILED: u8 = 5;
+
+/// swarm-coordinator, whose swarm PDAs own swarm bonds.
+pub const SWARM_COORDINATOR_PROGRAM_ID: Pubkey =
+    pubkey!("DOS4swm1111111111111111111111111111111111111");
+
+/// oracle-verifier, whose disputes settle slash appeals.
+pub const ORACLE_VERIFIER_PROGRAM_ID: Pubkey =
+    pubkey!("DOS4orc1111111111111111111111111111111111111");
+
+/// Fee discount tiers for stakers as `(min_staked, discount)`, lowest tier
+/// first. The discount is in basis points of the fee being discounted.
+pub const FEE_TIERS: [(u64, u16); 2] = [
+    (10_000 * 1_000_000, 5_000),
+    (100_000 * 1_000_000, 7_500),
+];
+
+/// Fee tier of a stake position: the number of `FEE_TIERS` thresholds its
+/// amount meets, so 0 for none
+pub fn fee_tier(stake: &StakeAccount) -> u8 {
+    FEE_TIERS.iter().filter(|(min_staked, _)| stake.amount >= *min_staked).count() as u8
+}
+
+/// Fee discount (bps) granted at `tier`
+pub fn fee_tier_discount(tier: u8) -> u16 {
+    match tier {
+        0 => 0,
+        tier => FEE_TIERS[(tier as usize).min(FEE_TIERS.len()) - 1].1,
+    }
+}
 
+/// Verify `owner` held `amount` staked in a snapshot with `root`. Leaves are
+/// `keccak(owner || amount as u64 LE)`; each parent is the keccak of its two
+/// children, smaller first, so proofs need no left/right flags.
+pub fn verify_snapshot_proof(root: &[u8; 32], owner: &Pubkey, amount: u64, proof: &[[u8; 32]]) -> bool {
+    let leaf = keccak::hashv(&[owner.as_ref(), &amount.to_le_bytes()]).to_bytes();
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            keccak::hashv(&[&node, sibling]).to_bytes()
+        } else {
+            keccak::hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+    computed == *root
+}
+
+/// Fee discount for the owner of `stake_account`, read by CPI into
+/// `get_fee_tier`, for programs that only hold the account info. Pass
+/// `None` for payers without a stake position.
+///
+/// ```ignore
+/// let discount = droneos_token::cpi_fee_discount(
+///     ctx.accounts.droneos_token_program.to_account_info(),
+///     ctx.accounts.payer_stake.as_ref().map(|stake| stake.to_account_info()),
+/// )?;
+/// ```
+#[cfg(feature = "cpi")]
+pub fn cpi_fee_discount<'info>(
+    droneos_token_program: AccountInfo<'info>,
+    stake_account: Option<AccountInfo<'info>>,
+) -> Result<u16> {
+    let Some(stake_account) = stake_account else {
+        return Ok(0);
+    };
+    let tier = cpi::get_fee_tier(CpiContext::new(
+        droneos_token_program,
+        cpi::accounts::ReadFeeTier { stake_account },
+    ))?
+    .get();
+    Ok(fee_tier_discount(tier))
+}
+
+/// $DRONEOS Token Program
+/// 
+/// $DRONEOS Token operations:
+/// - Token initialization (Token-2022, with on-chain metadata and an
+///   optional transfer fee)
+/// - Staking with lock periods, with optional position NFTs
+/// - Reward distribution
+/// - Operator stake management and delegation
+/// - veDRONEOS-weighted governance
+/// - Protocol fee burns
+/// - Stake snapshots for airdrops
 #[program]
 pub mod droneos_token {
     use super::*;
 
-    /// Initialize the $DRONEOS token
-    pub fn initialize(ctx: Context<InitializeToken>) -> Result<()> {
+    /// Initialize the $DRONEOS token: a Token-2022 mint carrying its own
+    /// name/symbol/URI metadata and, if `transfer_fee` is set, a protocol
+    /// transfer fee. The config PDA is the metadata and fee authority.
+    pub fn initialize(
+        ctx: Context<InitializeToken>,
+        name: String,
+        symbol: String,
+        uri: String,
+        transfer_fee: Option<TransferFeeParams>,
+    ) -> Result<()> {
+        require!(
+            name.len() <= MAX_NAME_LEN && symbol.len() <= MAX_SYMBOL_LEN && uri.len() <= MAX_URI_LEN,
+            ErrorCode::InvalidMetadata
+        );
+        if let Some(fee) = &transfer_fee {
+            require!(fee.basis_points <= MAX_TRANSFER_FEE_BPS, ErrorCode::InvalidTransferFee);
+        }
+
+        let config_key = ctx.accounts.config.key();
+        let mint_key = ctx.accounts.mint.key();
+        let mint_seeds = &[b"mint".as_ref(), &[ctx.bumps.mint]];
+        let mint_signer = &[&mint_seeds[..]];
+
+        let mut extensions = vec![ExtensionType::MetadataPointer];
+        if transfer_fee.is_some() {
+            extensions.push(ExtensionType::TransferFeeConfig);
+        }
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)?;
+        // Token-2022 appends the metadata entry when it's initialized, so
+        // fund its rent up front: type and length, update authority, mint,
+        // three length-prefixed strings and an empty additional-metadata list
+        let metadata_len = 4 + 32 + 32 + 4 + name.len() + 4 + symbol.len() + 4 + uri.len() + 4;
+        let lamports = Rent::get()?.minimum_balance(space + metadata_len);
+
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                CreateAccount {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.mint.to_account_info(),
+                },
+                mint_signer,
+            ),
+            lamports,
+            space as u64,
+            &ctx.accounts.token_program.key(),
+        )?;
+
+        token_interface::metadata_pointer_initialize(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                MetadataPointerInitialize {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            Some(config_key),
+            Some(mint_key),
+        )?;
+
+        if let Some(fee) = &transfer_fee {
+            token_interface::transfer_fee_initialize(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferFeeInitialize {
+                        token_program_id: ctx.accounts.token_program.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                    },
+                ),
+                Some(&config_key),
+                Some(&config_key),
+                fee.basis_points,
+                fee.maximum_fee,
+            )?;
+        }
+
+        token_2022::initialize_mint2(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                InitializeMint2 { mint: ctx.accounts.mint.to_account_info() },
+            ),
+            DECIMALS,
+            &mint_key,
+            None,
+        )?;
+
+        token_interface::token_metadata_initialize(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TokenMetadataInitialize {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    metadata: ctx.accounts.mint.to_account_info(),
+                    mint_authority: ctx.accounts.mint.to_account_info(),
+                    update_authority: ctx.accounts.config.to_account_info(),
+                },
+                mint_signer,
+            ),
+            name,
+            symbol,
+            uri,
+        )?;
+
         let config = &mut ctx.accounts.config;
         config.authority = ctx.accounts.authority.key();
         config.mint = ctx.accounts.mint.key();
         config.total_staked = 0;
         config.total_rewards_distributed = 0;
         config.stake_count = 0;
+        config.proposal_count = 0;
+        config.insurance_share_bps = 0;
+        config.reputation_recovery_delay = 30 * 86400;
+        config.reputation_recovery_rate = 50; // 0.5% a day
+        config.reputation_ceiling = 5000; // back to where operators start
+        config.base_apy_bps = DEFAULT_BASE_APY_BPS;
+        config.min_stake = DEFAULT_MIN_STAKE;
+        config.min_operator_stake = DEFAULT_MIN_OPERATOR_STAKE;
+        config.pending_parameters = None;
+        config.burn_share_bps = 0;
+        config.total_burned = 0;
+        config.snapshot_count = 0;
+        config.slasher_programs = Vec::new();
+        config.swap_programs = Vec::new();
+        config.exit_queue_threshold = 0;
+        config.event_seq = 0;
         config.bump = ctx.bumps.config;
         config.mint_bump = ctx.bumps.mint;
         
@@ -38,16 +294,17 @@ pub mod droneos_token {
 
     /// Mint initial supply (one-time)
     pub fn mint_initial_supply(ctx: Context<MintInitialSupply>) -> Result<()> {
-        let config = &ctx.accounts.config;
+        let config = &mut ctx.accounts.config;
         
         // Can only mint once
         require!(
-            ctx.accounts.treasury.amount == 0,
+            ctx.accounts.treasury.amount == 0 && config.treasury == Pubkey::default(),
             ErrorCode::AlreadyMinted
         );
+        config.treasury = ctx.accounts.treasury.key();
 
         let seeds = &[
-            b"mint",
+            b"mint".as_ref(),
             &[config.mint_bump],
         ];
         let signer = &[&seeds[..]];
@@ -62,9 +319,10 @@ pub mod droneos_token {
             signer,
         );
         
-        token::mint_to(cpi_ctx, TOTAL_SUPPLY)?;
+        token_interface::mint_to(cpi_ctx, TOTAL_SUPPLY)?;
 
-        emit!(InitialSupplyMinted {
+        emit_cpi!(InitialSupplyMinted {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
             amount: TOTAL_SUPPLY,
             treasury: ctx.accounts.treasury.key(),
         });
@@ -72,64 +330,480 @@ pub mod droneos_token {
         Ok(())
     }
 
-    /// Stake tokens
+    /// Register the vaults holding staked tokens and staking rewards
+    /// (one-time, by authority). Every staking path is pinned to them.
+    pub fn initialize_staking_vaults(ctx: Context<InitializeStakingVaults>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.stake_vault == Pubkey::default() && config.rewards_vault == Pubkey::default(),
+            ErrorCode::StakingVaultsAlreadySet
+        );
+        config.stake_vault = ctx.accounts.stake_vault.key();
+        config.rewards_vault = ctx.accounts.rewards_vault.key();
+        Ok(())
+    }
+
+    /// Set up the rewards emission schedule (by authority). Epoch `n` runs
+    /// from `start_time + n * epoch_duration` and may pay out at most
+    /// `epoch_caps[n]` in staking rewards; nothing is paid after the last
+    /// epoch until more are appended.
+    pub fn initialize_emission_schedule(
+        ctx: Context<InitializeEmissionSchedule>,
+        start_time: i64,
+        epoch_duration: i64,
+        epoch_caps: Vec<u64>,
+    ) -> Result<()> {
+        require!(epoch_duration > 0, ErrorCode::InvalidEmissionSchedule);
+        require!(epoch_caps.len() <= MAX_EMISSION_EPOCHS, ErrorCode::TooManyEpochs);
+
+        let emissions = &mut ctx.accounts.emissions;
+        emissions.start_time = start_time;
+        emissions.epoch_duration = epoch_duration;
+        emissions.epoch_caps = epoch_caps;
+        emissions.current_epoch = 0;
+        emissions.distributed_in_epoch = 0;
+        emissions.funded_epochs = 0;
+        emissions.bump = ctx.bumps.emissions;
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(EmissionScheduleSet {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            start_time,
+            epoch_duration,
+            epochs: emissions.epoch_caps.len() as u32,
+        });
+
+        Ok(())
+    }
+
+    /// Create the lock tier table (by authority) with the launch tiers:
+    /// none 1x, 30 days 1.1x, 90 days 1.25x, 180 days 1.5x, 365 days 2x
+    pub fn initialize_lock_tiers(ctx: Context<InitializeLockTiers>) -> Result<()> {
+        let lock_tiers = &mut ctx.accounts.lock_tiers;
+        lock_tiers.tiers = vec![
+            LockTier { days: 0, multiplier_bps: 10000 },
+            LockTier { days: 30, multiplier_bps: 11000 },
+            LockTier { days: 90, multiplier_bps: 12500 },
+            LockTier { days: 180, multiplier_bps: 15000 },
+            LockTier { days: 365, multiplier_bps: 20000 },
+        ];
+        lock_tiers.bump = ctx.bumps.lock_tiers;
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(LockTiersSet {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            tiers: lock_tiers.tiers.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Replace the lock tiers offered to new locks (by authority or
+    /// governance). Tiers must be in increasing order of days, with
+    /// multipliers of at least 1x. Existing positions keep their multiplier.
+    pub fn set_lock_tiers(ctx: Context<SetLockTiers>, tiers: Vec<LockTier>) -> Result<()> {
+        require!(!tiers.is_empty() && tiers.len() <= MAX_LOCK_TIERS, ErrorCode::InvalidLockTiers);
+        require!(
+            tiers.windows(2).all(|pair| pair[0].days < pair[1].days) &&
+            tiers.iter().all(|tier| tier.multiplier_bps >= 10000),
+            ErrorCode::InvalidLockTiers
+        );
+
+        ctx.accounts.lock_tiers.tiers = tiers;
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(LockTiersSet {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            tiers: ctx.accounts.lock_tiers.tiers.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Append epochs to the end of the emission schedule (by authority)
+    pub fn append_emission_epochs(ctx: Context<UpdateEmissionSchedule>, epoch_caps: Vec<u64>) -> Result<()> {
+        let emissions = &mut ctx.accounts.emissions;
+        require!(
+            emissions.epoch_caps.len() + epoch_caps.len() <= MAX_EMISSION_EPOCHS,
+            ErrorCode::TooManyEpochs
+        );
+
+        emissions.epoch_caps.extend(epoch_caps);
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(EmissionScheduleSet {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            start_time: emissions.start_time,
+            epoch_duration: emissions.epoch_duration,
+            epochs: emissions.epoch_caps.len() as u32,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit tokens into the rewards vault (by anyone)
+    pub fn fund_rewards_vault(ctx: Context<FundRewardsVault>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.funder_token.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.rewards_vault.to_account_info(),
+                authority: ctx.accounts.funder.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+        let received = net_of_transfer_fee(&ctx.accounts.mint, amount)?;
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(RewardsVaultFunded {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            funder: ctx.accounts.funder.key(),
+            amount: received,
+        });
+
+        Ok(())
+    }
+
+    /// Move the next unfunded epoch's emission cap from the treasury into
+    /// the rewards vault once that epoch has started (by anyone). The caller
+    /// is tipped 0.1% of the cap from the treasury, at most 100 DRONEOS.
+    /// Epochs are funded one per call, in order, so a lapsed crank catches
+    /// up over several calls.
+    pub fn distribute_epoch_rewards(ctx: Context<DistributeEpochRewards>) -> Result<()> {
+        let clock = Clock::get()?;
+        let emissions = &ctx.accounts.emissions;
+        let epoch = emissions.funded_epochs;
+
+        let epoch_start = (epoch as i64)
+            .checked_mul(emissions.epoch_duration)
+            .and_then(|offset| offset.checked_add(emissions.start_time))
+            .ok_or(ErrorCode::Overflow)?;
+        require!(clock.unix_timestamp >= epoch_start, ErrorCode::EpochNotStarted);
+        let budget = *emissions
+            .epoch_caps
+            .get(epoch as usize)
+            .ok_or(ErrorCode::EmissionBudgetExhausted)?;
+
+        let tip = (budget as u128 * CRANK_TIP_BPS as u128 / 10_000) as u64;
+        let tip = tip.min(MAX_CRANK_TIP);
+        require!(
+            ctx.accounts.treasury.amount >= budget.checked_add(tip).ok_or(ErrorCode::Overflow)?,
+            ErrorCode::InsufficientTreasury
+        );
+
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        let signer = &[&seeds[..]];
+
+        if budget > 0 {
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.rewards_vault.to_account_info(),
+                    authority: ctx.accounts.config.to_account_info(),
+                },
+                signer,
+            );
+            token_interface::transfer_checked(transfer_ctx, budget, DECIMALS)?;
+        }
+        if tip > 0 {
+            let tip_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.cranker_token.to_account_info(),
+                    authority: ctx.accounts.config.to_account_info(),
+                },
+                signer,
+            );
+            token_interface::transfer_checked(tip_ctx, tip, DECIMALS)?;
+        }
+        let funded = net_of_transfer_fee(&ctx.accounts.mint, budget)?;
+
+        ctx.accounts.emissions.funded_epochs += 1;
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(EpochRewardsDistributed {
+            header: event_header(config.key(), &mut config.event_seq, clock.unix_timestamp),
+            epoch,
+            amount: funded,
+            cranker: ctx.accounts.cranker.key(),
+            tip,
+        });
+
+        Ok(())
+    }
+
+    /// Register a wallet as a referrer so stakers can name it in `stake`
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let referrer = &mut ctx.accounts.referrer_account;
+        referrer.owner = ctx.accounts.owner.key();
+        referrer.referred_volume = 0;
+        referrer.referral_count = 0;
+        referrer.total_bonus = 0;
+        referrer.epoch_start = Clock::get()?.unix_timestamp;
+        referrer.epoch_bonus = 0;
+        referrer.event_seq = 0;
+        referrer.bump = ctx.bumps.referrer_account;
+        Ok(())
+    }
+
+    /// Stake tokens in a new position. A wallet can hold any number of
+    /// positions, each with its own lock period; they are numbered from 0 in
+    /// the order opened. A registered `referrer` of a locked stake is paid a
+    /// bonus of 1% of the stake from the rewards vault, up to 10K DRONEOS a
+    /// week, out of the current emission epoch's budget.
     pub fn stake(
         ctx: Context<Stake>,
         amount: u64,
         lock_days: u16,
+        referrer: Option<Pubkey>,
     ) -> Result<()> {
-        require!(amount >= MIN_STAKE, ErrorCode::BelowMinimumStake);
-        
-        let valid_locks = [0, 30, 90, 180, 365];
-        require!(
-            valid_locks.contains(&lock_days),
-            ErrorCode::InvalidLockPeriod
-        );
+        require!(amount >= ctx.accounts.config.min_stake, ErrorCode::BelowMinimumStake);
+        let multiplier = ctx.accounts.lock_tiers.multiplier(lock_days)?;
 
         let stake_account = &mut ctx.accounts.stake_account;
+        let positions = &mut ctx.accounts.positions;
         let config = &mut ctx.accounts.config;
         let clock = Clock::get()?;
 
-        // Calculate multiplier based on lock period
-        let multiplier = match lock_days {
-            0 => 10000,   // 1.0x
-            30 => 11000,  // 1.1x
-            90 => 12500,  // 1.25x
-            180 => 15000, // 1.5x
-            365 => 20000, // 2.0x
-            _ => 10000,
-        };
-
         // Transfer tokens to stake vault
         let transfer_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
+            TransferChecked {
                 from: ctx.accounts.user_token.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
                 to: ctx.accounts.stake_vault.to_account_info(),
                 authority: ctx.accounts.user.to_account_info(),
             },
         );
-        token::transfer(transfer_ctx, amount)?;
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+        let received = net_of_transfer_fee(&ctx.accounts.mint, amount)?;
 
         // Update stake account
+        stake_account.version = STAKE_ACCOUNT_VERSION;
         stake_account.owner = ctx.accounts.user.key();
-        stake_account.amount = amount;
+        stake_account.index = positions.next_index;
+        stake_account.amount = received;
         stake_account.staked_at = clock.unix_timestamp;
         stake_account.lock_duration = lock_days as i64 * 86400;
         stake_account.lock_until = clock.unix_timestamp + (lock_days as i64 * 86400);
         stake_account.multiplier = multiplier;
         stake_account.accumulated_rewards = 0;
         stake_account.last_claim_at = clock.unix_timestamp;
+        stake_account.settled_rewards = 0;
+        stake_account.receipt_mint = None;
+        stake_account.auto_relock = false;
+        stake_account.boost_robot = None;
+        stake_account.reputation_boost_bps = 0;
+        stake_account.event_seq = 0;
         stake_account.bump = ctx.bumps.stake_account;
 
-        config.total_staked += amount;
+        positions.owner = ctx.accounts.user.key();
+        positions.next_index += 1;
+        positions.open_positions += 1;
+        positions.bump = ctx.bumps.positions;
+
+        config.total_staked += received;
         config.stake_count += 1;
 
-        emit!(TokensStaked {
+        emit_cpi!(TokensStaked {
+            header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
             user: ctx.accounts.user.key(),
-            amount,
+            amount: received,
             lock_days,
             multiplier,
+            position: stake_account.index,
+            referrer,
+        });
+
+        if let Some(referrer) = referrer {
+            require!(referrer != ctx.accounts.user.key(), ErrorCode::InvalidReferrer);
+            let referrer_account =
+                ctx.accounts.referrer_account.as_mut().ok_or(ErrorCode::MissingReferralAccounts)?;
+            let referrer_token = ctx.accounts.referrer_token.as_ref().ok_or(ErrorCode::MissingReferralAccounts)?;
+            let rewards_vault = ctx.accounts.rewards_vault.as_ref().ok_or(ErrorCode::MissingReferralAccounts)?;
+            let emissions = ctx.accounts.emissions.as_mut().ok_or(ErrorCode::MissingReferralAccounts)?;
+            require!(
+                referrer_account.owner == referrer && referrer_token.owner == referrer,
+                ErrorCode::InvalidReferrer
+            );
+            // An unlocked stake could be withdrawn right after paying the bonus
+            require!(lock_days > 0, ErrorCode::ReferralRequiresLock);
+
+            if clock.unix_timestamp >= referrer_account.epoch_start + REFERRAL_EPOCH {
+                referrer_account.epoch_start = clock.unix_timestamp;
+                referrer_account.epoch_bonus = 0;
+            }
+            let bonus = (received as u128 * REFERRAL_BONUS_BPS as u128 / 10_000) as u64;
+            let bonus = bonus
+                .min(REFERRAL_EPOCH_CAP - referrer_account.epoch_bonus)
+                .min(emissions.remaining_budget(clock.unix_timestamp))
+                .min(rewards_vault.amount);
+            emissions.distributed_in_epoch += bonus;
+
+            if bonus > 0 {
+                let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+                let signer = &[&seeds[..]];
+
+                let transfer_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: rewards_vault.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: referrer_token.to_account_info(),
+                        authority: ctx.accounts.config.to_account_info(),
+                    },
+                    signer,
+                );
+                token_interface::transfer_checked(transfer_ctx, bonus, DECIMALS)?;
+                ctx.accounts.config.total_rewards_distributed += bonus;
+            }
+
+            referrer_account.referred_volume = referrer_account.referred_volume.saturating_add(received);
+            referrer_account.referral_count += 1;
+            referrer_account.epoch_bonus += bonus;
+            referrer_account.total_bonus += bonus;
+
+            emit_cpi!(ReferralRewarded {
+                header: event_header(referrer_account.key(), &mut referrer_account.event_seq, clock.unix_timestamp),
+                referrer,
+                staker: ctx.accounts.user.key(),
+                staked: received,
+                bonus,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Mint a receipt NFT for a stake position to its owner. From then on
+    /// the position can only be unstaked, in full, by whoever holds the NFT,
+    /// burning it, so the staked principal can be pledged as collateral by
+    /// transferring the NFT. Rewards stay claimable by the owner. The NFT
+    /// carries the position's amount and lock in its Token-2022 metadata;
+    /// neither can shrink while it exists. Meant to be sent in the same
+    /// transaction as `stake`.
+    pub fn mint_stake_receipt(ctx: Context<MintStakeReceipt>) -> Result<()> {
+        let stake_key = ctx.accounts.stake_account.key();
+        let receipt_key = ctx.accounts.receipt_mint.key();
+        let clock = Clock::get()?;
+
+        require!(
+            ctx.accounts.stake_account.receipt_mint.is_none(),
+            ErrorCode::ReceiptAlreadyMinted
+        );
+        require!(ctx.accounts.stake_account.amount > 0, ErrorCode::InsufficientStake);
+        // The holder couldn't end a rolling lock, as request_unlock is the owner's
+        require!(!ctx.accounts.stake_account.auto_relock, ErrorCode::AutoRelockActive);
+
+        let stake = &ctx.accounts.stake_account;
+        let name = format!("DRONEOS Stake #{}", stake.index);
+        let symbol = "stDRONEOS".to_string();
+        let fields = [
+            ("position", stake_key.to_string()),
+            ("amount", stake.amount.to_string()),
+            ("lock_days", (stake.lock_duration / 86400).to_string()),
+            ("lock_until", stake.lock_until.to_string()),
+        ];
+
+        // Fund the metadata entry before Token-2022 grows the mint for it:
+        // type and length, update authority, mint, name, symbol, empty uri,
+        // then each additional key/value pair
+        let metadata_len = 4 + 32 + 32 + 4 + name.len() + 4 + symbol.len() + 4 + 4
+            + fields.iter().map(|(k, v)| 4 + k.len() + 4 + v.len()).sum::<usize>();
+        let receipt_info = ctx.accounts.receipt_mint.to_account_info();
+        let required = Rent::get()?.minimum_balance(receipt_info.data_len() + metadata_len);
+        let top_up = required.saturating_sub(receipt_info.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: receipt_info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+
+        let receipt_seeds = &[b"receipt", stake_key.as_ref(), &[ctx.bumps.receipt_mint]];
+        let config_seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        let signers = &[&receipt_seeds[..], &config_seeds[..]];
+
+        token_interface::token_metadata_initialize(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TokenMetadataInitialize {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: receipt_info.clone(),
+                    metadata: receipt_info.clone(),
+                    mint_authority: receipt_info.clone(),
+                    update_authority: ctx.accounts.config.to_account_info(),
+                },
+                signers,
+            ),
+            name,
+            symbol,
+            String::new(),
+        )?;
+        for (key, value) in fields {
+            token_interface::token_metadata_update_field(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TokenMetadataUpdateField {
+                        token_program_id: ctx.accounts.token_program.to_account_info(),
+                        metadata: receipt_info.clone(),
+                        update_authority: ctx.accounts.config.to_account_info(),
+                    },
+                    signers,
+                ),
+                Field::Key(key.to_string()),
+                value,
+            )?;
+        }
+
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: receipt_info.clone(),
+                    to: ctx.accounts.receipt_token.to_account_info(),
+                    authority: receipt_info.clone(),
+                },
+                signers,
+            ),
+            1,
+        )?;
+        // Fix the supply at one
+        token_interface::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: receipt_info.clone(),
+                    account_or_mint: receipt_info.clone(),
+                },
+                signers,
+            ),
+            spl_token_2022::instruction::AuthorityType::MintTokens,
+            None,
+        )?;
+
+        let stake_account = &mut ctx.accounts.stake_account;
+        stake_account.receipt_mint = Some(receipt_key);
+
+        emit_cpi!(StakeReceiptMinted {
+            header: event_header(stake_key, &mut stake_account.event_seq, clock.unix_timestamp),
+            owner: stake_account.owner,
+            receipt_mint: receipt_key,
+            amount: stake_account.amount,
+            lock_until: stake_account.lock_until,
         });
 
         Ok(())
@@ -141,493 +815,5410 @@ pub mod droneos_token {
         let config = &mut ctx.accounts.config;
         let clock = Clock::get()?;
 
-        let rewards = calculate_rewards(stake_account, clock.unix_timestamp)?;
-        require!(rewards > 0, ErrorCode::NoRewardsToClaim);
+        let pending = calculate_rewards(stake_account, config.base_apy_bps, clock.unix_timestamp)?;
+        require!(pending > 0, ErrorCode::NoRewardsToClaim);
+        let vault_balance = ctx.accounts.rewards_vault.amount;
+        require!(vault_balance > 0, ErrorCode::InsufficientRewardsVault);
+        let rewards = budget_rewards(
+            stake_account,
+            pending,
+            &mut ctx.accounts.emissions,
+            vault_balance,
+            clock.unix_timestamp,
+        );
+        require!(rewards > 0, ErrorCode::EmissionBudgetExhausted);
 
         // Transfer rewards from treasury
-        let seeds = &[b"config", &[config.bump]];
+        let seeds = &[b"config".as_ref(), &[config.bump]];
         let signer = &[&seeds[..]];
 
         let transfer_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
+            TransferChecked {
                 from: ctx.accounts.rewards_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
                 to: ctx.accounts.user_token.to_account_info(),
-                authority: ctx.accounts.config.to_account_info(),
+                authority: config.to_account_info(),
             },
             signer,
         );
-        token::transfer(transfer_ctx, rewards)?;
+        token_interface::transfer_checked(transfer_ctx, rewards, DECIMALS)?;
 
-        stake_account.last_claim_at = clock.unix_timestamp;
         stake_account.accumulated_rewards += rewards;
         config.total_rewards_distributed += rewards;
 
-        emit!(RewardsClaimed {
+        emit_cpi!(RewardsClaimed {
+            header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
             user: ctx.accounts.user.key(),
             amount: rewards,
         });
+        if rewards < pending && rewards == vault_balance {
+            emit_cpi!(RewardsVaultShortfall {
+                header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
+                user: ctx.accounts.user.key(),
+                owed: pending,
+                paid: rewards,
+            });
+        }
 
         Ok(())
     }
 
-    /// Unstake tokens
-    pub fn unstake(ctx: Context<Unstake>, amount: Option<u64>) -> Result<()> {
-        let stake_account = &mut ctx.accounts.stake_account;
-        let config = &mut ctx.accounts.config;
+    /// Restake pending rewards into the position instead of paying them out.
+    /// The lock period and multiplier are unchanged.
+    pub fn compound_rewards(ctx: Context<CompoundRewards>) -> Result<()> {
         let clock = Clock::get()?;
 
-        // Check lock period
-        require!(
-            clock.unix_timestamp >= stake_account.lock_until,
-            ErrorCode::StakeLocked
+        let pending = calculate_rewards(
+            &ctx.accounts.stake_account,
+            ctx.accounts.config.base_apy_bps,
+            clock.unix_timestamp,
+        )?;
+        require!(pending > 0, ErrorCode::NoRewardsToClaim);
+        let vault_balance = ctx.accounts.rewards_vault.amount;
+        require!(vault_balance > 0, ErrorCode::InsufficientRewardsVault);
+        let rewards = budget_rewards(
+            &mut ctx.accounts.stake_account,
+            pending,
+            &mut ctx.accounts.emissions,
+            vault_balance,
+            clock.unix_timestamp,
         );
+        require!(rewards > 0, ErrorCode::EmissionBudgetExhausted);
 
-        let unstake_amount = amount.unwrap_or(stake_account.amount);
-        require!(unstake_amount <= stake_account.amount, ErrorCode::InsufficientStake);
-
-        // Claim any pending rewards first
-        let rewards = calculate_rewards(stake_account, clock.unix_timestamp)?;
-
-        // Transfer staked tokens back
-        let seeds = &[b"config", &[config.bump]];
+        // Move rewards straight into the stake vault
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
         let signer = &[&seeds[..]];
 
         let transfer_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.stake_vault.to_account_info(),
-                to: ctx.accounts.user_token.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.rewards_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.stake_vault.to_account_info(),
                 authority: ctx.accounts.config.to_account_info(),
             },
             signer,
         );
-        token::transfer(transfer_ctx, unstake_amount)?;
-
-        // Transfer rewards if any
-        if rewards > 0 {
-            let reward_transfer_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.rewards_vault.to_account_info(),
-                    to: ctx.accounts.user_token.to_account_info(),
-                    authority: ctx.accounts.config.to_account_info(),
-                },
-                signer,
-            );
-            token::transfer(reward_transfer_ctx, rewards)?;
-            config.total_rewards_distributed += rewards;
-        }
+        token_interface::transfer_checked(transfer_ctx, rewards, DECIMALS)?;
+        let staked = net_of_transfer_fee(&ctx.accounts.mint, rewards)?;
 
-        stake_account.amount -= unstake_amount;
-        stake_account.last_claim_at = clock.unix_timestamp;
-        config.total_staked -= unstake_amount;
+        let stake_account = &mut ctx.accounts.stake_account;
+        let config = &mut ctx.accounts.config;
 
-        if stake_account.amount == 0 {
-            config.stake_count -= 1;
-        }
+        stake_account.amount = stake_account.amount.checked_add(staked).ok_or(ErrorCode::Overflow)?;
+        stake_account.accumulated_rewards += rewards;
+        config.total_staked = config.total_staked.checked_add(staked).ok_or(ErrorCode::Overflow)?;
+        config.total_rewards_distributed += rewards;
 
-        emit!(TokensUnstaked {
+        emit_cpi!(RewardsCompounded {
+            header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
             user: ctx.accounts.user.key(),
-            amount: unstake_amount,
-            rewards_claimed: rewards,
+            amount: rewards,
+            staked_amount: stake_account.amount,
         });
+        if rewards < pending && rewards == vault_balance {
+            emit_cpi!(RewardsVaultShortfall {
+                header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
+                user: ctx.accounts.user.key(),
+                owed: pending,
+                paid: rewards,
+            });
+        }
 
         Ok(())
     }
 
-    /// Create operator stake (for robot operators)
-    pub fn create_operator_stake(
-        ctx: Context<CreateOperatorStake>,
-        amount: u64,
-    ) -> Result<()> {
-        require!(amount >= MIN_STAKE * 10, ErrorCode::BelowMinimumOperatorStake);
-
-        let operator_stake = &mut ctx.accounts.operator_stake;
-        let config = &mut ctx.accounts.config;
+    /// Move a position up to a longer lock tier. Rewards accrued so far are
+    /// settled at the old multiplier; the new lock runs from now.
+    pub fn extend_lock(ctx: Context<ExtendLock>, lock_days: u16) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
         let clock = Clock::get()?;
 
-        // Transfer tokens to operator vault
-        let transfer_ctx = CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.operator_token.to_account_info(),
-                to: ctx.accounts.operator_vault.to_account_info(),
-                authority: ctx.accounts.operator.to_account_info(),
-            },
-        );
-        token::transfer(transfer_ctx, amount)?;
+        let multiplier = ctx.accounts.lock_tiers.multiplier(lock_days)?;
+        require!(multiplier > stake_account.multiplier, ErrorCode::InvalidLockPeriod);
+        require!(stake_account.amount > 0, ErrorCode::InsufficientStake);
 
-        operator_stake.operator = ctx.accounts.operator.key();
-        operator_stake.total_staked = amount;
-        operator_stake.slashable_amount = amount;
-        operator_stake.created_at = clock.unix_timestamp;
-        operator_stake.last_slash_at = None;
-        operator_stake.reputation = 5000; // Start at 50%
-        operator_stake.bump = ctx.bumps.operator_stake;
+        stake_account.settled_rewards =
+            calculate_rewards(stake_account, ctx.accounts.config.base_apy_bps, clock.unix_timestamp)?;
+        stake_account.last_claim_at = clock.unix_timestamp;
 
-        config.total_staked += amount;
+        let old_multiplier = stake_account.multiplier;
+        stake_account.multiplier = multiplier;
+        stake_account.lock_duration = lock_days as i64 * 86400;
+        stake_account.lock_until = clock.unix_timestamp + stake_account.lock_duration;
 
-        emit!(OperatorStakeCreated {
-            operator: ctx.accounts.operator.key(),
-            amount,
+        emit_cpi!(LockExtended {
+            header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
+            user: ctx.accounts.user.key(),
+            lock_days,
+            old_multiplier,
+            multiplier,
+            lock_until: stake_account.lock_until,
         });
 
         Ok(())
     }
 
-    /// Slash operator stake (called by task_market on failures)
-    pub fn slash_operator(
-        ctx: Context<SlashOperator>,
-        amount: u64,
-        reason: String,
-    ) -> Result<()> {
-        require!(reason.len() <= 128, ErrorCode::ReasonTooLong);
-        
-        let operator_stake = &mut ctx.accounts.operator_stake;
+    /// Boost the position's rewards by the reputation of a robot its owner
+    /// operates, from nothing at the starting reputation up to 1.5x at full
+    /// reputation. The owner picks the robot; anyone may refresh the boost
+    /// from the robot already picked, so it follows reputation down as well
+    /// as up. Suspended robots earn no boost.
+    pub fn refresh_reputation_boost(ctx: Context<RefreshReputationBoost>) -> Result<()> {
+        let standing = identity_registry::cpi::get_robot_reputation(CpiContext::new(
+            ctx.accounts.identity_registry_program.to_account_info(),
+            identity_registry::cpi::accounts::ReadRobot { robot: ctx.accounts.robot.to_account_info() },
+        ))?
+        .get();
+
+        let stake_account = &mut ctx.accounts.stake_account;
+        let robot = ctx.accounts.robot.key();
+        let clock = Clock::get()?;
+
+        require_keys_eq!(standing.operator, stake_account.owner, ErrorCode::RobotNotOperatedByStaker);
+        require!(
+            ctx.accounts.caller.key() == stake_account.owner || stake_account.boost_robot == Some(robot),
+            ErrorCode::Unauthorized
+        );
+        require!(stake_account.amount > 0, ErrorCode::InsufficientStake);
+
+        stake_account.settled_rewards =
+            calculate_rewards(stake_account, ctx.accounts.config.base_apy_bps, clock.unix_timestamp)?;
+        stake_account.last_claim_at = clock.unix_timestamp;
+
+        let old_boost_bps = stake_account.reputation_boost_bps;
+        stake_account.reputation_boost_bps = if standing.status == RobotStatus::Suspended {
+            0
+        } else {
+            reputation_boost(standing.reputation_score)
+        };
+        stake_account.boost_robot = Some(robot);
+
+        emit_cpi!(ReputationBoostUpdated {
+            header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
+            user: stake_account.owner,
+            robot,
+            reputation_score: standing.reputation_score,
+            old_boost_bps,
+            boost_bps: stake_account.reputation_boost_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Roll the position's lock over for the same tier whenever it runs out,
+    /// until `request_unlock`. A lock that has already run out restarts now.
+    pub fn enable_auto_relock(ctx: Context<ExtendLock>) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(stake_account.lock_duration > 0, ErrorCode::InvalidLockPeriod);
+        require!(stake_account.amount > 0, ErrorCode::InsufficientStake);
+        require!(!stake_account.auto_relock, ErrorCode::AutoRelockActive);
+        require!(stake_account.receipt_mint.is_none(), ErrorCode::ReceiptAlreadyMinted);
+
+        if stake_account.lock_until <= clock.unix_timestamp {
+            stake_account.lock_until = clock.unix_timestamp + stake_account.lock_duration;
+        }
+        stake_account.auto_relock = true;
+
+        emit_cpi!(AutoRelockEnabled {
+            header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
+            user: ctx.accounts.user.key(),
+            lock_days: (stake_account.lock_duration / 86400) as u16,
+            lock_until: stake_account.lock_until,
+        });
+
+        Ok(())
+    }
+
+    /// Stop a rolling lock. Notice is one full lock period: the position
+    /// unlocks at the first rollover at least that far from now.
+    pub fn request_unlock(ctx: Context<ExtendLock>) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let clock = Clock::get()?;
+
+        require!(stake_account.auto_relock, ErrorCode::AutoRelockNotEnabled);
+
+        let mut unlocks_at = stake_account.lock_end(clock.unix_timestamp);
+        if unlocks_at < clock.unix_timestamp + stake_account.lock_duration {
+            unlocks_at += stake_account.lock_duration;
+        }
+        stake_account.lock_until = unlocks_at;
+        stake_account.auto_relock = false;
+
+        emit_cpi!(UnlockRequested {
+            header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
+            user: ctx.accounts.user.key(),
+            unlocks_at,
+        });
+
+        Ok(())
+    }
+
+    /// Unstake tokens. A position with a receipt NFT is unstaked in full by
+    /// the NFT's holder, who burns it; otherwise by its owner. A partial
+    /// unstake splits the position: the withdrawn part takes its share of
+    /// pending rewards with it, while the remainder keeps its lock,
+    /// multiplier and reward timing as if the withdrawn part had never been
+    /// staked.
+    pub fn unstake(ctx: Context<Unstake>, amount: Option<u64>) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
         let config = &mut ctx.accounts.config;
         let clock = Clock::get()?;
 
-        // Maximum slash is 10% of slashable amount
-        let max_slash = operator_stake.slashable_amount / 10;
-        let actual_slash = amount.min(max_slash).min(operator_stake.slashable_amount);
+        // Check lock period
+        require!(
+            clock.unix_timestamp >= stake_account.lock_end(clock.unix_timestamp),
+            ErrorCode::StakeLocked
+        );
 
-        require!(actual_slash > 0, ErrorCode::NothingToSlash);
+        let unstake_amount = amount.unwrap_or(stake_account.amount);
+        require!(unstake_amount > 0, ErrorCode::InvalidAmount);
+        require!(unstake_amount <= stake_account.amount, ErrorCode::InsufficientStake);
+        require!(
+            config.exit_queue_threshold == 0 || unstake_amount < config.exit_queue_threshold,
+            ErrorCode::ExitQueueRequired
+        );
+
+        let receipt_mint = authorize_withdrawal(
+            stake_account,
+            unstake_amount,
+            &ctx.accounts.user,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.receipt_token,
+            &ctx.accounts.token_program,
+        )?;
+
+        let vault_balance = ctx.accounts.rewards_vault.amount;
+        let (owed, rewards) = withdraw_rewards_share(
+            stake_account,
+            unstake_amount,
+            config.base_apy_bps,
+            &mut ctx.accounts.emissions,
+            vault_balance,
+            clock.unix_timestamp,
+        )?;
 
-        // Transfer slashed tokens to treasury
-        let seeds = &[b"config", &[config.bump]];
+        // Transfer staked tokens back
+        let seeds = &[b"config".as_ref(), &[config.bump]];
         let signer = &[&seeds[..]];
 
         let transfer_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.operator_vault.to_account_info(),
-                to: ctx.accounts.treasury.to_account_info(),
-                authority: ctx.accounts.config.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.stake_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.user_token.to_account_info(),
+                authority: config.to_account_info(),
             },
             signer,
         );
-        token::transfer(transfer_ctx, actual_slash)?;
+        token_interface::transfer_checked(transfer_ctx, unstake_amount, DECIMALS)?;
 
-        operator_stake.total_staked -= actual_slash;
-        operator_stake.slashable_amount -= actual_slash;
-        operator_stake.last_slash_at = Some(clock.unix_timestamp);
-        
-        // Reduce reputation
-        let rep_penalty = (actual_slash * 1000 / operator_stake.total_staked.max(1)) as u16;
-        operator_stake.reputation = operator_stake.reputation.saturating_sub(rep_penalty);
+        // Transfer rewards if any
+        if rewards > 0 {
+            let reward_transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.rewards_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: config.to_account_info(),
+                },
+                signer,
+            );
+            token_interface::transfer_checked(reward_transfer_ctx, rewards, DECIMALS)?;
+            config.total_rewards_distributed += rewards;
+        }
 
-        config.total_staked -= actual_slash;
+        stake_account.amount -= unstake_amount;
+        config.total_staked -= unstake_amount;
 
-        emit!(OperatorSlashed {
-            operator: operator_stake.operator,
-            amount: actual_slash,
-            reason,
-            new_reputation: operator_stake.reputation,
+        if stake_account.amount == 0 {
+            config.stake_count -= 1;
+            ctx.accounts.positions.open_positions -= 1;
+        }
+
+        emit_cpi!(TokensUnstaked {
+            header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
+            user: ctx.accounts.user.key(),
+            amount: unstake_amount,
+            rewards_claimed: rewards,
+            receipt_burned: receipt_mint,
         });
+        if rewards < owed && rewards == vault_balance {
+            emit_cpi!(RewardsVaultShortfall {
+                header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
+                user: ctx.accounts.user.key(),
+                owed,
+                paid: rewards,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Set the withdrawal size from which unstakes must go through the exit
+    /// queue, and how much the queue pays out per emission epoch (by
+    /// authority). A zero threshold turns the queue off.
+    pub fn set_exit_queue(ctx: Context<SetExitQueue>, threshold: u64, epoch_cap: u64) -> Result<()> {
+        require!(threshold == 0 || epoch_cap > 0, ErrorCode::InvalidAmount);
+
+        let queue = &mut ctx.accounts.exit_queue;
+        queue.epoch_cap = epoch_cap;
+        queue.bump = ctx.bumps.exit_queue;
+        ctx.accounts.config.exit_queue_threshold = threshold;
 
         Ok(())
     }
 
-    /// Get pending rewards (view function)
-    pub fn get_pending_rewards(ctx: Context<ViewStake>) -> Result<u64> {
+    /// Withdraw at least `exit_queue_threshold` tokens through the exit
+    /// queue, which `unstake` requires for withdrawals that size. The
+    /// tokens leave the position now, with their share of pending rewards
+    /// paid as in `unstake`, and wait their turn in the queue. While queued
+    /// they earn base APY, without the lock multiplier.
+    pub fn queue_unstake(ctx: Context<QueueUnstake>, amount: Option<u64>) -> Result<()> {
+        let stake_account = &mut ctx.accounts.stake_account;
+        let config = &mut ctx.accounts.config;
         let clock = Clock::get()?;
-        calculate_rewards(&ctx.accounts.stake_account, clock.unix_timestamp)
+
+        require!(
+            clock.unix_timestamp >= stake_account.lock_end(clock.unix_timestamp),
+            ErrorCode::StakeLocked
+        );
+
+        let queued = amount.unwrap_or(stake_account.amount);
+        require!(
+            config.exit_queue_threshold > 0 && queued >= config.exit_queue_threshold,
+            ErrorCode::BelowExitQueueThreshold
+        );
+        require!(queued <= stake_account.amount, ErrorCode::InsufficientStake);
+
+        authorize_withdrawal(
+            stake_account,
+            queued,
+            &ctx.accounts.user,
+            &ctx.accounts.receipt_mint,
+            &ctx.accounts.receipt_token,
+            &ctx.accounts.token_program,
+        )?;
+
+        let vault_balance = ctx.accounts.rewards_vault.amount;
+        let (owed, rewards) = withdraw_rewards_share(
+            stake_account,
+            queued,
+            config.base_apy_bps,
+            &mut ctx.accounts.emissions,
+            vault_balance,
+            clock.unix_timestamp,
+        )?;
+
+        if rewards > 0 {
+            let seeds = &[b"config".as_ref(), &[config.bump]];
+            let signer = &[&seeds[..]];
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.rewards_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.user_token.to_account_info(),
+                    authority: config.to_account_info(),
+                },
+                signer,
+            );
+            token_interface::transfer_checked(transfer_ctx, rewards, DECIMALS)?;
+            config.total_rewards_distributed += rewards;
+        }
+
+        stake_account.amount -= queued;
+        config.total_staked -= queued;
+
+        if stake_account.amount == 0 {
+            config.stake_count -= 1;
+            ctx.accounts.positions.open_positions -= 1;
+        }
+
+        let queue = &mut ctx.accounts.exit_queue;
+        let ticket = &mut ctx.accounts.ticket;
+        ticket.id = queue.tail;
+        ticket.owner = ctx.accounts.user.key();
+        ticket.amount = queued;
+        ticket.queued_at = clock.unix_timestamp;
+        ticket.bump = ctx.bumps.ticket;
+
+        queue.tail += 1;
+        queue.queued_amount += queued;
+
+        emit_cpi!(UnstakeQueued {
+            header: event_header(queue.key(), &mut queue.event_seq, clock.unix_timestamp),
+            user: ticket.owner,
+            ticket: ticket.id,
+            amount: queued,
+            rewards_claimed: rewards,
+            queued_ahead: queue.queued_amount - queued,
+        });
+        if rewards < owed && rewards == vault_balance {
+            emit_cpi!(RewardsVaultShortfall {
+                header: event_header(stake_account.key(), &mut stake_account.event_seq, clock.unix_timestamp),
+                user: ctx.accounts.user.key(),
+                owed,
+                paid: rewards,
+            });
+        }
+
+        Ok(())
     }
-}
 
-// ============================================================================
-// HELPERS
-// ============================================================================
+    /// Pay out the ticket at the head of the exit queue (permissionless
+    /// crank): its tokens, plus base APY for the time queued as far as the
+    /// emission budget and rewards vault allow. The queue pays out at most
+    /// `epoch_cap` per emission epoch, except that a ticket bigger than the
+    /// cap goes through on its own as the first of an epoch.
+    pub fn process_exit_queue(ctx: Context<ProcessExitQueue>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let ticket = &ctx.accounts.ticket;
 
-fn calculate_rewards(stake: &StakeAccount, current_time: i64) -> Result<u64> {
-    let elapsed = (current_time - stake.last_claim_at) as u64;
-    
-    // Base reward calculation
-    let base_reward = stake.amount
-        .checked_mul(BASE_APY_BPS)
-        .ok_or(ErrorCode::Overflow)?
-        .checked_mul(elapsed)
-        .ok_or(ErrorCode::Overflow)?
-        / (10000 * SECONDS_PER_YEAR);
-    
-    // Apply multiplier
-    let multiplied_reward = base_reward
-        .checked_mul(stake.multiplier as u64)
-        .ok_or(ErrorCode::Overflow)?
-        / 10000;
-    
-    Ok(multiplied_reward)
-}
+        let emissions = &mut ctx.accounts.emissions;
+        let budget = emissions.remaining_budget(now);
 
-// ============================================================================
-// ACCOUNTS
-// ============================================================================
+        let queue = &mut ctx.accounts.exit_queue;
+        if emissions.current_epoch != queue.epoch {
+            queue.epoch = emissions.current_epoch;
+            queue.paid_in_epoch = 0;
+        }
+        require!(
+            queue.paid_in_epoch == 0 || queue.paid_in_epoch + ticket.amount <= queue.epoch_cap,
+            ErrorCode::ExitEpochCapReached
+        );
 
-#[derive(Accounts)]
-pub struct InitializeToken<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + TokenConfig::INIT_SPACE,
-        seeds = [b"config"],
-        bump
-    )]
-    pub config: Account<'info, TokenConfig>,
-    
-    #[account(
-        init,
-        payer = authority,
-        seeds = [b"mint"],
-        bump,
-        mint::decimals = DECIMALS,
-        mint::authority = mint,
-    )]
-    pub mint: Account<'info, Mint>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        let elapsed = (now - ticket.queued_at) as u128;
+        let earned = (ticket.amount as u128 * ctx.accounts.config.base_apy_bps as u128 * elapsed
+            / (10000 * SECONDS_PER_YEAR) as u128) as u64;
+        let rewards = earned.min(budget).min(ctx.accounts.rewards_vault.amount);
+        emissions.distributed_in_epoch += rewards;
 
-#[derive(Accounts)]
-pub struct MintInitialSupply<'info> {
-    #[account(seeds = [b"config"], bump = config.bump)]
-    pub config: Account<'info, TokenConfig>,
-    
-    #[account(
-        mut,
-        seeds = [b"mint"],
-        bump = config.mint_bump
-    )]
-    pub mint: Account<'info, Mint>,
-    
-    #[account(mut)]
-    pub treasury: Account<'info, TokenAccount>,
-    
-    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
-    pub authority: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        let signer = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.stake_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.owner_token.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(transfer_ctx, ticket.amount, DECIMALS)?;
+
+        if rewards > 0 {
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.rewards_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.owner_token.to_account_info(),
+                    authority: ctx.accounts.config.to_account_info(),
+                },
+                signer,
+            );
+            token_interface::transfer_checked(transfer_ctx, rewards, DECIMALS)?;
+            ctx.accounts.config.total_rewards_distributed += rewards;
+        }
+
+        queue.head += 1;
+        queue.paid_in_epoch += ticket.amount;
+        queue.queued_amount -= ticket.amount;
+
+        emit_cpi!(ExitProcessed {
+            header: event_header(queue.key(), &mut queue.event_seq, now),
+            user: ticket.owner,
+            ticket: ticket.id,
+            amount: ticket.amount,
+            rewards,
+        });
+
+        Ok(())
+    }
+
+    /// Create operator stake (for robot operators)
+    pub fn create_operator_stake(
+        ctx: Context<CreateOperatorStake>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            amount >= ctx.accounts.config.min_operator_stake,
+            ErrorCode::BelowMinimumOperatorStake
+        );
+
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let config = &mut ctx.accounts.config;
+        let clock = Clock::get()?;
+
+        if config.operator_vault == Pubkey::default() {
+            config.operator_vault = ctx.accounts.operator_vault.key();
+        }
+
+        // Transfer tokens to operator vault
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.operator_token.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.operator_vault.to_account_info(),
+                authority: ctx.accounts.operator.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+        let received = net_of_transfer_fee(&ctx.accounts.mint, amount)?;
+
+        operator_stake.version = OPERATOR_STAKE_VERSION;
+        operator_stake.operator = ctx.accounts.operator.key();
+        operator_stake.total_staked = received;
+        operator_stake.slashable_amount = received;
+        operator_stake.created_at = clock.unix_timestamp;
+        operator_stake.last_slash_at = None;
+        operator_stake.last_slash_amount = 0;
+        operator_stake.last_slash_reputation_loss = 0;
+        operator_stake.reputation = 5000; // Start at 50%
+        operator_stake.reputation_recovered_at = clock.unix_timestamp;
+        operator_stake.active_tasks = 0;
+        operator_stake.unbonding_amount = 0;
+        operator_stake.unbonding_started_at = None;
+        operator_stake.delegated_amount = 0;
+        operator_stake.delegation_shares = 0;
+        operator_stake.delegator_count = 0;
+        operator_stake.delegator_share_bps = 0;
+        operator_stake.income_per_share = 0;
+        operator_stake.last_slash_delegated = 0;
+        operator_stake.slash_count = 0;
+        operator_stake.locked_task_bonds = 0;
+        operator_stake.event_seq = 0;
+        operator_stake.bump = ctx.bumps.operator_stake;
+
+        config.total_staked += received;
+
+        emit_cpi!(OperatorStakeCreated {
+            header: event_header(operator_stake.key(), &mut operator_stake.event_seq, clock.unix_timestamp),
+            operator: ctx.accounts.operator.key(),
+            amount: received,
+        });
+
+        Ok(())
+    }
+
+    /// Add tokens to an existing operator stake, e.g. to restore its
+    /// slashable amount after a slash
+    pub fn top_up_operator_stake(ctx: Context<TopUpOperatorStake>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let config = &mut ctx.accounts.config;
+        let clock = Clock::get()?;
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.operator_token.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.operator_vault.to_account_info(),
+                authority: ctx.accounts.operator.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+        let received = net_of_transfer_fee(&ctx.accounts.mint, amount)?;
+
+        operator_stake.total_staked = operator_stake.total_staked.checked_add(received).ok_or(ErrorCode::Overflow)?;
+        operator_stake.slashable_amount += received;
+        config.total_staked += received;
+
+        emit_cpi!(OperatorStakeToppedUp {
+            header: event_header(operator_stake.key(), &mut operator_stake.event_seq, clock.unix_timestamp),
+            operator: operator_stake.operator,
+            amount: received,
+            total_staked: operator_stake.total_staked,
+            slashable_amount: operator_stake.slashable_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Start withdrawing operator stake. The amount stays slashable through
+    /// a 7-day unbonding period before `finalize_operator_unstake` releases
+    /// it; whatever is left staked must still meet the operator minimum.
+    pub fn request_operator_unstake(ctx: Context<RequestOperatorUnstake>, amount: u64) -> Result<()> {
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let clock = Clock::get()?;
+
+        require!(operator_stake.unbonding_started_at.is_none(), ErrorCode::UnbondingInProgress);
+        require!(amount > 0 && amount <= operator_stake.slashable_amount, ErrorCode::InsufficientStake);
+        let remaining = operator_stake.slashable_amount - amount;
+        require!(
+            remaining == 0 || remaining >= ctx.accounts.config.min_operator_stake,
+            ErrorCode::BelowMinimumOperatorStake
+        );
+
+        operator_stake.unbonding_amount = amount;
+        operator_stake.unbonding_started_at = Some(clock.unix_timestamp);
+
+        emit_cpi!(OperatorUnstakeRequested {
+            header: event_header(operator_stake.key(), &mut operator_stake.event_seq, clock.unix_timestamp),
+            operator: operator_stake.operator,
+            amount,
+            unlocks_at: clock.unix_timestamp + OPERATOR_UNBONDING_PERIOD,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw unbonded operator stake once the unbonding period is over and
+    /// the operator holds no active tasks. Slashes during unbonding come out
+    /// of the withdrawal.
+    pub fn finalize_operator_unstake(ctx: Context<FinalizeOperatorUnstake>) -> Result<()> {
+        let clock = Clock::get()?;
+        let operator_stake = &ctx.accounts.operator_stake;
+
+        let started_at = operator_stake.unbonding_started_at.ok_or(ErrorCode::NoUnbondingRequest)?;
+        require!(
+            clock.unix_timestamp >= started_at + OPERATOR_UNBONDING_PERIOD,
+            ErrorCode::UnbondingNotComplete
+        );
+        require!(operator_stake.active_tasks == 0, ErrorCode::OperatorHasActiveTasks);
+
+        let amount = operator_stake.unbonding_amount.min(operator_stake.slashable_amount);
+
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        let signer = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.operator_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.operator_token.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        operator_stake.total_staked -= amount;
+        operator_stake.slashable_amount -= amount;
+        operator_stake.unbonding_amount = 0;
+        operator_stake.unbonding_started_at = None;
+        ctx.accounts.config.total_staked -= amount;
+
+        emit_cpi!(OperatorUnstaked {
+            header: event_header(operator_stake.key(), &mut operator_stake.event_seq, clock.unix_timestamp),
+            operator: operator_stake.operator,
+            amount,
+            remaining: operator_stake.total_staked,
+        });
+
+        Ok(())
+    }
+
+    /// Set the share of income (bps) an operator passes on to delegators
+    pub fn set_delegator_share(ctx: Context<SetDelegatorShare>, share_bps: u16) -> Result<()> {
+        require!(share_bps <= 10_000, ErrorCode::InvalidDelegatorShare);
+        ctx.accounts.operator_stake.delegator_share_bps = share_bps;
+        Ok(())
+    }
+
+    /// Delegate tokens to an operator. They add to the operator's bond and
+    /// are slashed pro rata with it; in return the delegator earns a share
+    /// of the income the operator passes through `share_operator_income`.
+    pub fn delegate_stake(ctx: Context<DelegateStake>, amount: u64) -> Result<()> {
+        require!(amount >= ctx.accounts.config.min_stake, ErrorCode::BelowMinimumStake);
+        let clock = Clock::get()?;
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.delegator_token.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.operator_vault.to_account_info(),
+                authority: ctx.accounts.delegator.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+        let received = net_of_transfer_fee(&ctx.accounts.mint, amount)?;
+
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let delegation = &mut ctx.accounts.delegation;
+
+        if delegation.shares == 0 {
+            delegation.delegator = ctx.accounts.delegator.key();
+            delegation.operator = operator_stake.operator;
+            delegation.bump = ctx.bumps.delegation;
+            operator_stake.delegator_count += 1;
+        }
+        delegation.settle_income(operator_stake.income_per_share);
+
+        let shares = if operator_stake.delegation_shares == 0 || operator_stake.delegated_amount == 0 {
+            received
+        } else {
+            (received as u128 * operator_stake.delegation_shares as u128
+                / operator_stake.delegated_amount as u128) as u64
+        };
+        require!(shares > 0, ErrorCode::InvalidAmount);
+
+        delegation.shares += shares;
+        delegation.income_debt = delegation.shares as u128 * operator_stake.income_per_share / INCOME_PRECISION;
+        operator_stake.delegation_shares += shares;
+        operator_stake.delegated_amount = operator_stake.delegated_amount.checked_add(received).ok_or(ErrorCode::Overflow)?;
+        ctx.accounts.config.total_staked += received;
+
+        emit_cpi!(StakeDelegated {
+            header: event_header(delegation.key(), &mut delegation.event_seq, clock.unix_timestamp),
+            delegator: delegation.delegator,
+            operator: delegation.operator,
+            amount: received,
+            shares,
+        });
+
+        Ok(())
+    }
+
+    /// Start withdrawing delegated shares. They stay in the operator's bond,
+    /// slashable and earning income, through a 7-day cooldown before
+    /// `finalize_undelegation` pays them out.
+    pub fn undelegate_stake(ctx: Context<UndelegateStake>, shares: u64) -> Result<()> {
+        let delegation = &mut ctx.accounts.delegation;
+        let clock = Clock::get()?;
+
+        require!(delegation.unbonding_started_at.is_none(), ErrorCode::UndelegationInProgress);
+        require!(shares > 0 && shares <= delegation.shares, ErrorCode::InsufficientStake);
+
+        delegation.unbonding_shares = shares;
+        delegation.unbonding_started_at = Some(clock.unix_timestamp);
+
+        emit_cpi!(UndelegationRequested {
+            header: event_header(delegation.key(), &mut delegation.event_seq, clock.unix_timestamp),
+            delegator: delegation.delegator,
+            operator: delegation.operator,
+            shares,
+            unlocks_at: clock.unix_timestamp + DELEGATION_COOLDOWN,
+        });
+
+        Ok(())
+    }
+
+    /// Pay out undelegated shares, at their value after any slashes, once
+    /// the cooldown is over
+    pub fn finalize_undelegation(ctx: Context<FinalizeUndelegation>) -> Result<()> {
+        let clock = Clock::get()?;
+        let delegation = &ctx.accounts.delegation;
+
+        let started_at = delegation.unbonding_started_at.ok_or(ErrorCode::NoUndelegationRequest)?;
+        require!(
+            clock.unix_timestamp >= started_at + DELEGATION_COOLDOWN,
+            ErrorCode::UnbondingNotComplete
+        );
+
+        let shares = delegation.unbonding_shares;
+        let amount = ctx.accounts.operator_stake.delegated_value(shares);
+
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        let signer = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.operator_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.delegator_token.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let delegation = &mut ctx.accounts.delegation;
+
+        delegation.settle_income(operator_stake.income_per_share);
+        delegation.shares -= shares;
+        delegation.income_debt = delegation.shares as u128 * operator_stake.income_per_share / INCOME_PRECISION;
+        delegation.unbonding_shares = 0;
+        delegation.unbonding_started_at = None;
+        operator_stake.delegation_shares -= shares;
+        operator_stake.delegated_amount -= amount;
+        if delegation.shares == 0 {
+            operator_stake.delegator_count -= 1;
+        }
+        ctx.accounts.config.total_staked -= amount;
+
+        emit_cpi!(StakeUndelegated {
+            header: event_header(delegation.key(), &mut delegation.event_seq, clock.unix_timestamp),
+            delegator: delegation.delegator,
+            operator: delegation.operator,
+            amount,
+            remaining_shares: delegation.shares,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a delegator's share of operator income
+    pub fn claim_delegation_income(ctx: Context<ClaimDelegationIncome>) -> Result<()> {
+        let clock = Clock::get()?;
+        let income_per_share = ctx.accounts.operator_stake.income_per_share;
+
+        let delegation = &mut ctx.accounts.delegation;
+        delegation.settle_income(income_per_share);
+        let amount = delegation.unclaimed_income;
+        require!(amount > 0, ErrorCode::NoRewardsToClaim);
+        delegation.unclaimed_income = 0;
+
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        let signer = &[&seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.operator_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.delegator_token.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+
+        let delegation = &mut ctx.accounts.delegation;
+        emit_cpi!(DelegationIncomeClaimed {
+            header: event_header(delegation.key(), &mut delegation.event_seq, clock.unix_timestamp),
+            delegator: delegation.delegator,
+            operator: delegation.operator,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Pass delegators their share of income the operator earned (e.g. a
+    /// task payout). Only `delegator_share_bps` of `income` is transferred,
+    /// split across delegated shares.
+    pub fn share_operator_income(ctx: Context<ShareOperatorIncome>, income: u64) -> Result<()> {
+        let operator_stake = &ctx.accounts.operator_stake;
+        require!(operator_stake.delegation_shares > 0, ErrorCode::NoDelegators);
+
+        let cut = (income as u128 * operator_stake.delegator_share_bps as u128 / 10_000) as u64;
+        require!(cut > 0, ErrorCode::InvalidAmount);
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.operator_token.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.operator_vault.to_account_info(),
+                authority: ctx.accounts.operator.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, cut, DECIMALS)?;
+        let received = net_of_transfer_fee(&ctx.accounts.mint, cut)?;
+
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        operator_stake.income_per_share +=
+            received as u128 * INCOME_PRECISION / operator_stake.delegation_shares as u128;
+
+        emit_cpi!(OperatorIncomeShared {
+            header: event_header(operator_stake.key(), &mut operator_stake.event_seq, Clock::get()?.unix_timestamp),
+            operator: operator_stake.operator,
+            income,
+            to_delegators: received,
+        });
+
+        Ok(())
+    }
+
+    /// Record that an operator took on a task (task-market CPI)
+    pub fn begin_operator_task(ctx: Context<OperatorTask>) -> Result<()> {
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        operator_stake.active_tasks = operator_stake.active_tasks.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Record that an operator's task finished (task-market CPI)
+    pub fn end_operator_task(ctx: Context<OperatorTask>) -> Result<()> {
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        operator_stake.active_tasks = operator_stake.active_tasks.saturating_sub(1);
+        Ok(())
+    }
+
+    /// Hold part of an operator's own stake as the performance bond for a
+    /// task (task-market CPI). Bonds held across their tasks can't exceed
+    /// their slashable amount.
+    pub fn lock_task_bond(ctx: Context<OperatorTask>, amount: u64) -> Result<()> {
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let locked = operator_stake
+            .locked_task_bonds
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(locked <= operator_stake.slashable_amount, ErrorCode::InsufficientTaskBond);
+        operator_stake.locked_task_bonds = locked;
+        Ok(())
+    }
+
+    /// Release a task's performance bond once the task is settled
+    /// (task-market CPI)
+    pub fn release_task_bond(ctx: Context<OperatorTask>, amount: u64) -> Result<()> {
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        operator_stake.locked_task_bonds = operator_stake.locked_task_bonds.saturating_sub(amount);
+        Ok(())
+    }
+
+    /// Pay a task's performance bond out of the operator's stake to the
+    /// task's creator, as compensation for a robot-fault abort (task-market
+    /// CPI). Capped at what is still held after any slashes.
+    pub fn forfeit_task_bond(ctx: Context<ForfeitTaskBond>, amount: u64, task: Pubkey) -> Result<()> {
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let clock = Clock::get()?;
+
+        let forfeited = amount
+            .min(operator_stake.locked_task_bonds)
+            .min(operator_stake.slashable_amount);
+        require!(forfeited > 0, ErrorCode::NothingToSlash);
+
+        let config = &ctx.accounts.config;
+        let seeds = &[b"config".as_ref(), &[config.bump]];
+        let signer = &[&seeds[..]];
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.operator_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.creator_token.to_account_info(),
+                authority: config.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(transfer_ctx, forfeited, DECIMALS)?;
+
+        operator_stake.total_staked -= forfeited;
+        operator_stake.slashable_amount -= forfeited;
+        operator_stake.locked_task_bonds -= forfeited;
+        ctx.accounts.config.total_staked -= forfeited;
+
+        emit_cpi!(TaskBondForfeited {
+            header: event_header(operator_stake.key(), &mut operator_stake.event_seq, clock.unix_timestamp),
+            operator: operator_stake.operator,
+            task,
+            creator: ctx.accounts.creator_token.owner,
+            amount: forfeited,
+        });
+
+        Ok(())
+    }
+
+    /// Allow a program to slash operators. It does so by CPI, signing with
+    /// its `["slasher"]` PDA.
+    pub fn add_slasher_program(ctx: Context<UpdateTokenConfig>, program: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(!config.slasher_programs.contains(&program), ErrorCode::SlasherAlreadyRegistered);
+        require!(config.slasher_programs.len() < MAX_SLASHER_PROGRAMS, ErrorCode::TooManySlashers);
+        config.slasher_programs.push(program);
+        Ok(())
+    }
+
+    /// Revoke a program's permission to slash operators
+    pub fn remove_slasher_program(ctx: Context<UpdateTokenConfig>, program: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let index = config
+            .slasher_programs
+            .iter()
+            .position(|p| *p == program)
+            .ok_or(ErrorCode::SlasherNotRegistered)?;
+        config.slasher_programs.swap_remove(index);
+        Ok(())
+    }
+
+    /// Slash operator stake. Only callable by CPI from a registered slasher
+    /// program (task-market, oracle-verifier), signed by its slasher PDA.
+    /// Each slash is kept in a `SlashRecord`, numbered per operator, for
+    /// task creators auditing an operator before accepting their bids.
+    pub fn slash_operator(
+        ctx: Context<SlashOperator>,
+        amount: u64,
+        reason: String,
+        task: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(reason.len() <= 128, ErrorCode::ReasonTooLong);
+        
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let clock = Clock::get()?;
+
+        // Maximum slash is 10% of the bond: the operator's slashable amount
+        // plus delegated stake, which takes its pro rata share
+        let bond = operator_stake.slashable_amount + operator_stake.delegated_amount;
+        let max_slash = bond / 10;
+        let actual_slash = amount.min(max_slash).min(bond);
+
+        require!(actual_slash > 0, ErrorCode::NothingToSlash);
+
+        let from_delegated = (actual_slash as u128 * operator_stake.delegated_amount as u128 / bond as u128) as u64;
+        let from_operator = actual_slash - from_delegated;
+
+        let to_insurance = route_slash(
+            &ctx.accounts.config,
+            &ctx.accounts.operator_vault,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.insurance_pool,
+            &ctx.accounts.insurance_vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            actual_slash,
+        )?;
+        let config = &mut ctx.accounts.config;
+
+        operator_stake.total_staked -= from_operator;
+        operator_stake.slashable_amount -= from_operator;
+        operator_stake.delegated_amount -= from_delegated;
+        operator_stake.locked_task_bonds =
+            operator_stake.locked_task_bonds.min(operator_stake.slashable_amount);
+        operator_stake.last_slash_at = Some(clock.unix_timestamp);
+        
+        // Reduce reputation
+        let rep_penalty = (actual_slash * 1000 / (operator_stake.total_staked + operator_stake.delegated_amount).max(1)) as u16;
+        let reputation_before = operator_stake.reputation;
+        operator_stake.reputation = operator_stake.reputation.saturating_sub(rep_penalty);
+        operator_stake.last_slash_amount = actual_slash;
+        operator_stake.last_slash_delegated = from_delegated;
+        operator_stake.last_slash_reputation_loss = reputation_before - operator_stake.reputation;
+
+        let record = &mut ctx.accounts.slash_record;
+        record.operator = operator_stake.operator;
+        record.index = operator_stake.slash_count;
+        record.amount = actual_slash;
+        record.from_delegated = from_delegated;
+        record.reason = reason.clone();
+        record.task = task;
+        record.slasher = ctx.accounts.slasher_program.key();
+        record.slashed_at = clock.unix_timestamp;
+        record.bump = ctx.bumps.slash_record;
+        operator_stake.slash_count += 1;
+
+        config.total_staked -= actual_slash;
+
+        emit_cpi!(OperatorSlashed {
+            header: event_header(operator_stake.key(), &mut operator_stake.event_seq, clock.unix_timestamp),
+            operator: operator_stake.operator,
+            amount: actual_slash,
+            reason,
+            new_reputation: operator_stake.reputation,
+            to_insurance,
+            from_delegated,
+            task,
+            slash_index: record.index,
+        });
+
+        Ok(())
+    }
+
+    /// Post a collective bond for a swarm (by its leader). Swarm-coordinator
+    /// reads it via `get_swarm_bond` before accepting the swarm's bids and
+    /// slashes it when a group task fails.
+    pub fn create_swarm_stake(ctx: Context<CreateSwarmStake>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.leader_token.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.swarm_vault.to_account_info(),
+                authority: ctx.accounts.leader.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+        let received = net_of_transfer_fee(&ctx.accounts.mint, amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let swarm_stake = &mut ctx.accounts.swarm_stake;
+        swarm_stake.swarm = ctx.accounts.swarm.key();
+        swarm_stake.leader = ctx.accounts.leader.key();
+        swarm_stake.amount = received;
+        swarm_stake.total_slashed = 0;
+        swarm_stake.created_at = now;
+        swarm_stake.last_slash_at = None;
+        swarm_stake.event_seq = 0;
+        swarm_stake.bump = ctx.bumps.swarm_stake;
+
+        let config = &mut ctx.accounts.config;
+        config.total_staked += received;
+
+        emit_cpi!(SwarmStakeCreated {
+            header: event_header(swarm_stake.key(), &mut swarm_stake.event_seq, now),
+            swarm: swarm_stake.swarm,
+            leader: swarm_stake.leader,
+            amount: received,
+        });
+
+        Ok(())
+    }
+
+    /// A swarm's bond (view function, readable via CPI)
+    pub fn get_swarm_bond(ctx: Context<ReadSwarmStake>) -> Result<u64> {
+        Ok(ctx.accounts.swarm_stake.amount)
+    }
+
+    /// Slash a swarm's bond for a failed group task. Only callable by CPI
+    /// from a registered slasher program, signed by its slasher PDA. Unlike
+    /// operator slashes there is no 10% cap: the caller sizes the slash to
+    /// the task, and it is only limited by the bond.
+    pub fn slash_swarm_stake(ctx: Context<SlashSwarmStake>, amount: u64, reason: String) -> Result<()> {
+        require!(reason.len() <= 128, ErrorCode::ReasonTooLong);
+
+        let actual_slash = amount.min(ctx.accounts.swarm_stake.amount);
+        require!(actual_slash > 0, ErrorCode::NothingToSlash);
+
+        let to_insurance = route_slash(
+            &ctx.accounts.config,
+            &ctx.accounts.swarm_vault,
+            &ctx.accounts.treasury,
+            &mut ctx.accounts.insurance_pool,
+            &ctx.accounts.insurance_vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            actual_slash,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let swarm_stake = &mut ctx.accounts.swarm_stake;
+        swarm_stake.amount -= actual_slash;
+        swarm_stake.total_slashed += actual_slash;
+        swarm_stake.last_slash_at = Some(now);
+
+        let config = &mut ctx.accounts.config;
+        config.total_staked -= actual_slash;
+
+        emit_cpi!(SwarmStakeSlashed {
+            header: event_header(swarm_stake.key(), &mut swarm_stake.event_seq, now),
+            swarm: swarm_stake.swarm,
+            amount: actual_slash,
+            reason,
+            to_insurance,
+        });
+
+        Ok(())
+    }
+
+    /// Restore an operator's reputation after a clean stretch (permissionless
+    /// crank). Once `reputation_recovery_delay` has passed since the last
+    /// slash, each further full day adds `reputation_recovery_rate`, up to
+    /// `reputation_ceiling`.
+    pub fn recover_reputation(ctx: Context<RecoverReputation>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let now = Clock::get()?.unix_timestamp;
+
+        let clean_since = operator_stake.last_slash_at.unwrap_or(operator_stake.created_at);
+        let from = (clean_since + config.reputation_recovery_delay).max(operator_stake.reputation_recovered_at);
+        let days = (now - from) / 86400;
+        require!(
+            days > 0 && operator_stake.reputation < config.reputation_ceiling,
+            ErrorCode::NothingToRecover
+        );
+
+        let gain = (days as u64 * config.reputation_recovery_rate as u64).min(10_000) as u16;
+        operator_stake.reputation = (operator_stake.reputation + gain).min(config.reputation_ceiling);
+        operator_stake.reputation_recovered_at = from + days * 86400;
+
+        emit_cpi!(OperatorReputationRecovered {
+            header: event_header(operator_stake.key(), &mut operator_stake.event_seq, now),
+            operator: operator_stake.operator,
+            days_clean: days as u32,
+            new_reputation: operator_stake.reputation,
+        });
+
+        Ok(())
+    }
+
+    /// Tune reputation recovery: days without a slash before it starts,
+    /// points regained per day and the most it can restore to
+    pub fn set_reputation_recovery(
+        ctx: Context<UpdateTokenConfig>,
+        delay_days: u16,
+        rate_per_day: u16,
+        ceiling: u16,
+    ) -> Result<()> {
+        require!(ceiling <= 10_000 && rate_per_day <= 10_000, ErrorCode::InvalidReputationRecovery);
+
+        let config = &mut ctx.accounts.config;
+        config.reputation_recovery_delay = delay_days as i64 * 86400;
+        config.reputation_recovery_rate = rate_per_day;
+        config.reputation_ceiling = ceiling;
+        Ok(())
+    }
+
+    /// Queue new staking parameters: base APY and the staker and operator
+    /// minimums. They take effect through `apply_parameters` once
+    /// `PARAMETER_TIMELOCK` has passed; queuing again replaces the pending set.
+    pub fn update_parameters(
+        ctx: Context<UpdateParameters>,
+        base_apy_bps: u16,
+        min_stake: u64,
+        min_operator_stake: u64,
+    ) -> Result<()> {
+        require!(
+            base_apy_bps <= MAX_BASE_APY_BPS
+                && min_stake > 0
+                && min_stake <= MAX_MIN_STAKE
+                && min_operator_stake >= min_stake,
+            ErrorCode::InvalidParameters
+        );
+
+        let config = &mut ctx.accounts.config;
+        let clock = Clock::get()?;
+        let effective_at = clock.unix_timestamp + PARAMETER_TIMELOCK;
+        config.pending_parameters = Some(PendingParameters {
+            base_apy_bps,
+            min_stake,
+            min_operator_stake,
+            effective_at,
+        });
+
+        emit_cpi!(ParametersQueued {
+            header: event_header(config.key(), &mut config.event_seq, clock.unix_timestamp),
+            base_apy_bps,
+            min_stake,
+            min_operator_stake,
+            effective_at,
+        });
+
+        Ok(())
+    }
+
+    /// Apply queued staking parameters once their timelock has passed
+    /// (permissionless)
+    pub fn apply_parameters(ctx: Context<ApplyParameters>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let clock = Clock::get()?;
+
+        let pending = config.pending_parameters.take().ok_or(ErrorCode::NoPendingParameters)?;
+        require!(clock.unix_timestamp >= pending.effective_at, ErrorCode::ParametersNotReady);
+
+        config.base_apy_bps = pending.base_apy_bps;
+        config.min_stake = pending.min_stake;
+        config.min_operator_stake = pending.min_operator_stake;
+
+        emit_cpi!(ParametersApplied {
+            header: event_header(config.key(), &mut config.event_seq, clock.unix_timestamp),
+            base_apy_bps: pending.base_apy_bps,
+            min_stake: pending.min_stake,
+            min_operator_stake: pending.min_operator_stake,
+        });
+
+        Ok(())
+    }
+
+    /// Set the share of deposited protocol fees burned on arrival
+    pub fn set_burn_share(ctx: Context<UpdateTokenConfig>, share_bps: u16) -> Result<()> {
+        require!(share_bps <= 10_000, ErrorCode::InvalidBurnShare);
+        ctx.accounts.config.burn_share_bps = share_bps;
+        Ok(())
+    }
+
+    /// Pay protocol fees into the treasury. `burn_share_bps` of them is
+    /// burned straight away; the rest stays in treasury.
+    pub fn deposit_fees(ctx: Context<DepositFees>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.payer_token.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            amount,
+            DECIMALS,
+        )?;
+        let received = net_of_transfer_fee(&ctx.accounts.mint, amount)?;
+
+        let burned = (received as u128 * ctx.accounts.config.burn_share_bps as u128 / 10_000) as u64;
+        if burned > 0 {
+            burn_treasury(
+                &ctx.accounts.config,
+                &ctx.accounts.mint,
+                &ctx.accounts.treasury,
+                &ctx.accounts.token_program,
+                burned,
+            )?;
+        }
+
+        let config = &mut ctx.accounts.config;
+        config.total_burned = config.total_burned.checked_add(burned).ok_or(ErrorCode::Overflow)?;
+
+        emit_cpi!(FeesDeposited {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            payer: ctx.accounts.payer.key(),
+            amount: received,
+            burned,
+            total_burned: config.total_burned,
+        });
+
+        Ok(())
+    }
+
+    /// Burn tokens held in treasury (by authority or governance)
+    pub fn burn_from_treasury(ctx: Context<BurnFromTreasury>, amount: u64) -> Result<()> {
+        require!(amount > 0 && amount <= ctx.accounts.treasury.amount, ErrorCode::InvalidAmount);
+
+        burn_treasury(
+            &ctx.accounts.config,
+            &ctx.accounts.mint,
+            &ctx.accounts.treasury,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        config.total_burned = config.total_burned.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+
+        emit_cpi!(TreasuryBurned {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            authority: ctx.accounts.authority.key(),
+            amount,
+            total_burned: config.total_burned,
+        });
+
+        Ok(())
+    }
+
+    /// Allow the treasury to swap fee revenue through an AMM program
+    /// (Orca, Raydium)
+    pub fn add_swap_program(ctx: Context<UpdateTokenConfig>, program: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(!config.swap_programs.contains(&program), ErrorCode::SwapProgramAlreadyAllowed);
+        require!(config.swap_programs.len() < MAX_SWAP_PROGRAMS, ErrorCode::TooManySwapPrograms);
+        config.swap_programs.push(program);
+        Ok(())
+    }
+
+    /// Stop the treasury swapping through an AMM program
+    pub fn remove_swap_program(ctx: Context<UpdateTokenConfig>, program: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let index = config
+            .swap_programs
+            .iter()
+            .position(|p| *p == program)
+            .ok_or(ErrorCode::SwapProgramNotAllowed)?;
+        config.swap_programs.swap_remove(index);
+        Ok(())
+    }
+
+    /// Set how much of one fee mint the treasury may spend on buybacks per
+    /// week (by authority). Creates the mint's buyback budget on first use.
+    pub fn set_buyback_limit(ctx: Context<SetBuybackLimit>, epoch_limit: u64) -> Result<()> {
+        let budget = &mut ctx.accounts.budget;
+        if budget.fee_mint == Pubkey::default() {
+            budget.fee_mint = ctx.accounts.fee_mint.key();
+            budget.epoch_start = Clock::get()?.unix_timestamp;
+            budget.bump = ctx.bumps.budget;
+        }
+        budget.epoch_limit = epoch_limit;
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(BuybackLimitSet {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            fee_mint: budget.fee_mint,
+            epoch_limit,
+        });
+
+        Ok(())
+    }
+
+    /// Swap fee revenue set aside for buybacks (e.g. USDC) for DRONEOS
+    /// through a whitelisted AMM and burn what comes out (by authority or
+    /// governance).
+    ///
+    /// `swap_data` is the AMM's instruction data and the remaining accounts
+    /// its account list. Both vaults belong to the `["buyback"]` PDA, which
+    /// signs the swap; the config PDA never signs for the caller-supplied
+    /// instruction, so no other vault is exposed to it. At most `amount_in`
+    /// may leave the fee vault, at least `min_out` must land in the buyback
+    /// vault, and spend counts against the weekly limit.
+    pub fn buyback_and_burn<'info>(
+        ctx: Context<'_, '_, '_, 'info, BuybackAndBurn<'info>>,
+        amount_in: u64,
+        min_out: u64,
+        swap_data: Vec<u8>,
+    ) -> Result<()> {
+        require!(amount_in > 0 && amount_in <= ctx.accounts.fee_vault.amount, ErrorCode::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+
+        let budget = &mut ctx.accounts.budget;
+        if now >= budget.epoch_start + BUYBACK_EPOCH {
+            budget.epoch_start = now;
+            budget.spent_in_epoch = 0;
+        }
+        require!(
+            budget.spent_in_epoch.checked_add(amount_in).ok_or(ErrorCode::Overflow)? <= budget.epoch_limit,
+            ErrorCode::BuybackLimitExceeded
+        );
+
+        let fee_before = ctx.accounts.fee_vault.amount;
+        let vault_before = ctx.accounts.buyback_vault.amount;
+
+        let buyback_key = ctx.accounts.buyback.key();
+        let instruction = Instruction {
+            program_id: ctx.accounts.swap_program.key(),
+            accounts: ctx
+                .remaining_accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.key(),
+                    is_signer: account.key() == buyback_key,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: swap_data,
+        };
+
+        let seeds = &[b"buyback".as_ref(), &[ctx.bumps.buyback]];
+        invoke_signed(&instruction, ctx.remaining_accounts, &[&seeds[..]])?;
+
+        ctx.accounts.fee_vault.reload()?;
+        ctx.accounts.buyback_vault.reload()?;
+
+        let spent = fee_before
+            .checked_sub(ctx.accounts.fee_vault.amount)
+            .ok_or(ErrorCode::BuybackSlippage)?;
+        let bought = ctx
+            .accounts
+            .buyback_vault
+            .amount
+            .checked_sub(vault_before)
+            .ok_or(ErrorCode::BuybackSlippage)?;
+        require!(spent <= amount_in && bought >= min_out, ErrorCode::BuybackSlippage);
+
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.buyback_vault.to_account_info(),
+                    authority: ctx.accounts.buyback.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            bought,
+        )?;
+
+        let budget = &mut ctx.accounts.budget;
+        budget.spent_in_epoch = budget.spent_in_epoch.checked_add(spent).ok_or(ErrorCode::Overflow)?;
+        budget.total_spent = budget.total_spent.checked_add(spent).ok_or(ErrorCode::Overflow)?;
+        budget.total_burned = budget.total_burned.checked_add(bought).ok_or(ErrorCode::Overflow)?;
+
+        let config = &mut ctx.accounts.config;
+        config.total_burned = config.total_burned.checked_add(bought).ok_or(ErrorCode::Overflow)?;
+
+        emit_cpi!(BuybackBurned {
+            header: event_header(config.key(), &mut config.event_seq, now),
+            fee_mint: budget.fee_mint,
+            swap_program: ctx.accounts.swap_program.key(),
+            spent,
+            burned: bought,
+            total_burned: config.total_burned,
+        });
+
+        Ok(())
+    }
+
+    /// Change the mint's transfer fee (by authority). Token-2022 applies the
+    /// new fee two epochs later. Fails if the mint was created without one.
+    pub fn set_transfer_fee(
+        ctx: Context<SetTransferFee>,
+        basis_points: u16,
+        maximum_fee: u64,
+    ) -> Result<()> {
+        require!(basis_points <= MAX_TRANSFER_FEE_BPS, ErrorCode::InvalidTransferFee);
+
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        token_interface::transfer_fee_set(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferFeeSetTransferFee {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: ctx.accounts.config.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            basis_points,
+            maximum_fee,
+        )?;
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(TransferFeeUpdated {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            basis_points,
+            maximum_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Move transfer fees withheld on the mint into treasury (permissionless).
+    /// Fees withheld in token accounts must first be harvested to the mint.
+    pub fn withdraw_transfer_fees(ctx: Context<WithdrawTransferFees>) -> Result<()> {
+        let before = ctx.accounts.treasury.amount;
+
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        token_interface::withdraw_withheld_tokens_from_mint(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            WithdrawWithheldTokensFromMint {
+                token_program_id: ctx.accounts.token_program.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                destination: ctx.accounts.treasury.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            &[&seeds[..]],
+        ))?;
+        ctx.accounts.treasury.reload()?;
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(TransferFeesWithdrawn {
+            header: event_header(config.key(), &mut config.event_seq, Clock::get()?.unix_timestamp),
+            amount: ctx.accounts.treasury.amount - before,
+        });
+
+        Ok(())
+    }
+
+    /// Set the share of slashed tokens routed to the insurance pool
+    pub fn set_insurance_share(ctx: Context<UpdateTokenConfig>, share_bps: u16) -> Result<()> {
+        require!(share_bps <= 10_000, ErrorCode::InvalidInsuranceShare);
+        ctx.accounts.config.insurance_share_bps = share_bps;
+        Ok(())
+    }
+
+    /// Create the insurance pool that slash proceeds are routed into
+    pub fn initialize_insurance_pool(ctx: Context<InitializeInsurancePool>) -> Result<()> {
+        let pool = &mut ctx.accounts.insurance_pool;
+        pool.vault = ctx.accounts.insurance_vault.key();
+        pool.balance = 0;
+        pool.total_received = 0;
+        pool.total_paid = 0;
+        pool.claim_count = 0;
+        pool.event_seq = 0;
+        pool.bump = ctx.bumps.insurance_pool;
+        Ok(())
+    }
+
+    /// File an insurance claim for a task that failed through the robot's
+    /// fault, by the task's creator. The authority reviews it in `pay_claim`.
+    pub fn file_claim(ctx: Context<FileClaim>, amount: u64, reason: String) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(reason.len() <= 128, ErrorCode::ReasonTooLong);
+
+        // only the creator of a task that failed through the robot's fault can claim
+        let task = &ctx.accounts.task;
+        require!(task.owner == &TASK_MARKET_PROGRAM_ID, ErrorCode::InvalidClaim);
+        let data = task.try_borrow_data()?;
+        require!(data.len() > TASK_ROBOT_FAULT_OFFSET, ErrorCode::InvalidClaim);
+        require!(
+            data[TASK_CREATOR_RANGE] == ctx.accounts.claimant.key().to_bytes(),
+            ErrorCode::Unauthorized
+        );
+        require!(
+            data[TASK_STATUS_OFFSET] == TASK_STATUS_FAILED && data[TASK_ROBOT_FAULT_OFFSET] != 0,
+            ErrorCode::TaskNotRobotFault
+        );
+        drop(data);
+
+        let clock = Clock::get()?;
+        let pool = &mut ctx.accounts.insurance_pool;
+        let claim = &mut ctx.accounts.claim;
+        claim.id = pool.claim_count;
+        claim.claimant = ctx.accounts.claimant.key();
+        claim.task = task.key();
+        claim.amount = amount;
+        claim.reason = reason;
+        claim.status = ClaimStatus::Pending;
+        claim.filed_at = clock.unix_timestamp;
+        claim.event_seq = 0;
+        claim.bump = ctx.bumps.claim;
+
+        pool.claim_count += 1;
+
+        emit_cpi!(InsuranceClaimFiled {
+            header: event_header(claim.key(), &mut claim.event_seq, clock.unix_timestamp),
+            claimant: claim.claimant,
+            task: claim.task,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Approve (paying out from the pool) or deny a pending claim (by authority)
+    pub fn pay_claim(ctx: Context<PayClaim>, approve: bool) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(ctx.accounts.claim.status == ClaimStatus::Pending, ErrorCode::ClaimNotPending);
+
+        let amount = ctx.accounts.claim.amount;
+        if approve {
+            require!(amount <= ctx.accounts.insurance_pool.balance, ErrorCode::InsufficientInsurance);
+
+            let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+            let signer = &[&seeds[..]];
+            let transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.insurance_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.claimant_token.to_account_info(),
+                    authority: ctx.accounts.config.to_account_info(),
+                },
+                signer,
+            );
+            token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+
+            let pool = &mut ctx.accounts.insurance_pool;
+            pool.balance -= amount;
+            pool.total_paid += amount;
+        }
+
+        let claim = &mut ctx.accounts.claim;
+        claim.status = if approve { ClaimStatus::Paid } else { ClaimStatus::Denied };
+
+        emit_cpi!(InsuranceClaimSettled {
+            header: event_header(claim.key(), &mut claim.event_seq, clock.unix_timestamp),
+            claimant: claim.claimant,
+            approved: approve,
+            paid: if approve { amount } else { 0 },
+        });
+
+        Ok(())
+    }
+
+    /// Pay a premium for insuring `task` into the insurance pool (task-market
+    /// CPI at task creation, signed by the creator). The pool is credited
+    /// with what the vault receives after any transfer fee.
+    pub fn pay_insurance_premium(
+        ctx: Context<PayInsurancePremium>,
+        amount: u64,
+        task: Pubkey,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        let clock = Clock::get()?;
+
+        let before = ctx.accounts.insurance_vault.amount;
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.payer_token.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.insurance_vault.to_account_info(),
+                authority: ctx.accounts.payer.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, DECIMALS)?;
+        ctx.accounts.insurance_vault.reload()?;
+        let received = ctx.accounts.insurance_vault.amount - before;
+
+        let pool = &mut ctx.accounts.insurance_pool;
+        pool.balance += received;
+        pool.total_received += received;
+
+        emit_cpi!(InsurancePremiumPaid {
+            header: event_header(pool.key(), &mut pool.event_seq, clock.unix_timestamp),
+            task,
+            payer: ctx.accounts.payer.key(),
+            amount: received,
+        });
+
+        Ok(())
+    }
+
+    /// Pay an insured task's creator from the insurance pool after the task
+    /// failed through its robot's fault (task-market CPI, signed by the
+    /// market PDA). Pays up to `amount`, capped at the pool's balance;
+    /// returns what was paid.
+    pub fn pay_task_insurance(
+        ctx: Context<PayTaskInsurance>,
+        amount: u64,
+        task: Pubkey,
+    ) -> Result<u64> {
+        let clock = Clock::get()?;
+        let paid = amount.min(ctx.accounts.insurance_pool.balance);
+        require!(paid > 0, ErrorCode::InsufficientInsurance);
+
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        let signer = &[&seeds[..]];
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.insurance_vault.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.creator_token.to_account_info(),
+                authority: ctx.accounts.config.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(transfer_ctx, paid, DECIMALS)?;
+
+        let pool = &mut ctx.accounts.insurance_pool;
+        pool.balance -= paid;
+        pool.total_paid += paid;
+
+        emit_cpi!(TaskInsurancePaid {
+            header: event_header(pool.key(), &mut pool.event_seq, clock.unix_timestamp),
+            task,
+            creator: ctx.accounts.creator_token.owner,
+            amount: paid,
+        });
+
+        Ok(paid)
+    }
+
+    /// Register the vault holding slash appeal bonds (one-time, by authority)
+    pub fn initialize_appeal_vault(ctx: Context<InitializeAppealVault>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(config.appeal_vault == Pubkey::default(), ErrorCode::AppealVaultAlreadySet);
+        config.appeal_vault = ctx.accounts.appeal_vault.key();
+        Ok(())
+    }
+
+    /// Appeal the operator's latest slash within 3 days of it, posting a
+    /// bond. The appeal is then argued as an oracle-verifier dispute
+    /// (`open_slash_appeal`), which settles it here when resolved.
+    pub fn appeal_slash(ctx: Context<AppealSlash>) -> Result<()> {
+        let operator_stake = &ctx.accounts.operator_stake;
+        let clock = Clock::get()?;
+
+        let slashed_at = operator_stake.last_slash_at.ok_or(ErrorCode::NothingToAppeal)?;
+        require!(operator_stake.last_slash_amount > 0, ErrorCode::NothingToAppeal);
+        require!(
+            clock.unix_timestamp <= slashed_at + SLASH_APPEAL_WINDOW,
+            ErrorCode::AppealWindowClosed
+        );
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.operator_token.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.appeal_vault.to_account_info(),
+                authority: ctx.accounts.operator.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, SLASH_APPEAL_BOND, DECIMALS)?;
+        let bond = net_of_transfer_fee(&ctx.accounts.mint, SLASH_APPEAL_BOND)?;
+
+        let appeal = &mut ctx.accounts.appeal;
+        appeal.operator = operator_stake.operator;
+        appeal.slashed_at = slashed_at;
+        appeal.amount = operator_stake.last_slash_amount;
+        appeal.reputation_lost = operator_stake.last_slash_reputation_loss;
+        appeal.delegated = operator_stake.last_slash_delegated;
+        appeal.bond = bond;
+        appeal.status = AppealStatus::Open;
+        appeal.opened_at = clock.unix_timestamp;
+        appeal.event_seq = 0;
+        appeal.bump = ctx.bumps.appeal;
+
+        emit_cpi!(SlashAppealed {
+            header: event_header(appeal.key(), &mut appeal.event_seq, clock.unix_timestamp),
+            operator: appeal.operator,
+            amount: appeal.amount,
+            bond: appeal.bond,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a slash appeal (oracle-verifier CPI, once its dispute is
+    /// resolved). An upheld appeal returns the slashed tokens from treasury,
+    /// restores the reputation lost and refunds the bond; otherwise the bond
+    /// goes to treasury.
+    pub fn settle_slash_appeal(ctx: Context<SettleSlashAppeal>, upheld: bool) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(ctx.accounts.appeal.status == AppealStatus::Open, ErrorCode::AppealNotOpen);
+
+        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
+        let signer = &[&seeds[..]];
+        let (amount, bond) = (ctx.accounts.appeal.amount, ctx.accounts.appeal.bond);
+
+        if upheld {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.treasury.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.operator_vault.to_account_info(),
+                        authority: ctx.accounts.config.to_account_info(),
+                    },
+                    signer,
+                ),
+                amount,
+                DECIMALS,
+            )?;
+        }
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.appeal_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: if upheld {
+                        ctx.accounts.operator_token.to_account_info()
+                    } else {
+                        ctx.accounts.treasury.to_account_info()
+                    },
+                    authority: ctx.accounts.config.to_account_info(),
+                },
+                signer,
+            ),
+            bond,
+            DECIMALS,
+        )?;
+        let restored = net_of_transfer_fee(&ctx.accounts.mint, amount)?;
+
+        let operator_stake = &mut ctx.accounts.operator_stake;
+        let appeal = &mut ctx.accounts.appeal;
+        if upheld {
+            // Delegators get back the same share of the refund they lost
+            let to_delegated = (restored as u128 * appeal.delegated as u128 / appeal.amount.max(1) as u128) as u64;
+            operator_stake.total_staked += restored - to_delegated;
+            operator_stake.slashable_amount += restored - to_delegated;
+            operator_stake.delegated_amount += to_delegated;
+            operator_stake.reputation = (operator_stake.reputation + appeal.reputation_lost).min(10000);
+            ctx.accounts.config.total_staked += restored;
+            appeal.status = AppealStatus::Upheld;
+        } else {
+            appeal.status = AppealStatus::Rejected;
+        }
+
+        emit_cpi!(SlashAppealSettled {
+            header: event_header(appeal.key(), &mut appeal.event_seq, clock.unix_timestamp),
+            operator: appeal.operator,
+            upheld,
+            refunded: if upheld { restored } else { 0 },
+            new_reputation: operator_stake.reputation,
+        });
+
+        Ok(())
+    }
+
+    /// Recompute the signer's vote-escrowed voting power from all of their
+    /// open stake positions, passed as remaining accounts. Each position
+    /// counts `amount * remaining lock / 365 days`.
+    pub fn update_voting_power<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateVotingPower<'info>>,
+    ) -> Result<()> {
+        let owner = ctx.accounts.user.key();
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut power: u128 = 0;
+        let mut slope: u64 = 0;
+        let mut next_expiry = i64::MAX;
+        let mut seen: Vec<u32> = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut open = 0u32;
+
+        for info in ctx.remaining_accounts.iter() {
+            let stake = Account::<StakeAccount>::try_from(info)?;
+            require!(stake.owner == owner, ErrorCode::Unauthorized);
+            require!(!seen.contains(&stake.index), ErrorCode::DuplicatePosition);
+            seen.push(stake.index);
+            if stake.amount == 0 {
+                continue;
+            }
+            open += 1;
+
+            let lock_end = stake.lock_end(now);
+            if lock_end > now {
+                power += stake.amount as u128 * (lock_end - now) as u128;
+                slope += stake.amount;
+                next_expiry = next_expiry.min(lock_end);
+            }
+        }
+        require!(
+            open == ctx.accounts.positions.open_positions,
+            ErrorCode::MissingStakePositions
+        );
+
+        let voting_power = &mut ctx.accounts.voting_power;
+        voting_power.owner = owner;
+        voting_power.power = (power / MAX_LOCK_SECONDS as u128) as u64;
+        voting_power.slope = slope;
+        voting_power.next_expiry = next_expiry;
+        voting_power.updated_at = now;
+        voting_power.bump = ctx.bumps.voting_power;
+
+        emit_cpi!(VotingPowerUpdated {
+            header: event_header(voting_power.key(), &mut voting_power.event_seq, now),
+            owner,
+            power: voting_power.power,
+            slope: voting_power.slope,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade a stake or operator stake account written with an older
+    /// layout to the current one in place, reallocating it (by anyone; the
+    /// payer covers any extra rent). Accounts from before versioning are
+    /// version 0.
+    pub fn migrate_stake_account(ctx: Context<MigrateStakeAccount>) -> Result<()> {
+        let info = ctx.accounts.account.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidAccountVersion);
+        let now = Clock::get()?.unix_timestamp;
+
+        let disc: [u8; 8] = info
+            .try_borrow_data()?
+            .get(..8)
+            .and_then(|disc| disc.try_into().ok())
+            .ok_or(ErrorCode::InvalidAccountVersion)?;
+
+        let (from_version, to_version, header) = if disc == StakeAccount::DISCRIMINATOR {
+            let from_version = account_version(&info, StakeAccountV0::INIT_SPACE)?;
+            require!(from_version < STAKE_ACCOUNT_VERSION, ErrorCode::AccountUpToDate);
+            let mut migrated: StakeAccount = match from_version {
+                0 => StakeAccountV0::deserialize(&mut &info.try_borrow_data()?[8..])?.into(),
+                1 => StakeAccountV1::deserialize(&mut &info.try_borrow_data()?[8..])?.into(),
+                _ => return err!(ErrorCode::InvalidAccountVersion),
+            };
+            let header = event_header(info.key(), &mut migrated.event_seq, now);
+            write_migrated(
+                &info,
+                &migrated,
+                8 + StakeAccount::INIT_SPACE,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+            )?;
+            (from_version, STAKE_ACCOUNT_VERSION, header)
+        } else if disc == OperatorStake::DISCRIMINATOR {
+            let from_version = account_version(&info, OperatorStakeV0::INIT_SPACE)?;
+            require!(from_version < OPERATOR_STAKE_VERSION, ErrorCode::AccountUpToDate);
+            let mut migrated: OperatorStake = match from_version {
+                0 => OperatorStakeV0::deserialize(&mut &info.try_borrow_data()?[8..])?.into(),
+                1 => OperatorStakeV1::deserialize(&mut &info.try_borrow_data()?[8..])?.into(),
+                2 => OperatorStakeV2::deserialize(&mut &info.try_borrow_data()?[8..])?.into(),
+                _ => return err!(ErrorCode::InvalidAccountVersion),
+            };
+            let header = event_header(info.key(), &mut migrated.event_seq, now);
+            write_migrated(
+                &info,
+                &migrated,
+                8 + OperatorStake::INIT_SPACE,
+                &ctx.accounts.payer,
+                &ctx.accounts.system_program,
+            )?;
+            (from_version, OPERATOR_STAKE_VERSION, header)
+        } else {
+            return err!(ErrorCode::InvalidAccountVersion);
+        };
+
+        emit_cpi!(AccountMigrated {
+            header,
+            account: info.key(),
+            from_version,
+            to_version,
+        });
+
+        Ok(())
+    }
+
+    /// Record a snapshot of staking state (by authority): total staked now,
+    /// plus the merkle root of per-wallet staked balances computed off-chain
+    /// at this slot. See `verify_snapshot_proof` for the tree layout.
+    pub fn take_snapshot(ctx: Context<TakeSnapshot>, merkle_root: [u8; 32], holder_count: u32) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.config;
+        let snapshot = &mut ctx.accounts.snapshot;
+
+        snapshot.id = config.snapshot_count;
+        snapshot.epoch = clock.epoch;
+        snapshot.slot = clock.slot;
+        snapshot.taken_at = clock.unix_timestamp;
+        snapshot.total_staked = config.total_staked;
+        snapshot.merkle_root = merkle_root;
+        snapshot.holder_count = holder_count;
+        snapshot.bump = ctx.bumps.snapshot;
+
+        config.snapshot_count += 1;
+
+        emit_cpi!(SnapshotTaken {
+            header: event_header(config.key(), &mut config.event_seq, clock.unix_timestamp),
+            id: snapshot.id,
+            epoch: snapshot.epoch,
+            total_staked: snapshot.total_staked,
+            merkle_root,
+            holder_count,
+        });
+
+        Ok(())
+    }
+
+    /// Check a wallet's staked balance against a snapshot (view function,
+    /// readable via CPI)
+    pub fn verify_snapshot_balance(
+        ctx: Context<ReadSnapshot>,
+        owner: Pubkey,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<bool> {
+        Ok(verify_snapshot_proof(&ctx.accounts.snapshot.merkle_root, &owner, amount, &proof))
+    }
+
+    /// Fee tier of a stake position (view function, readable via CPI), for
+    /// staking-based fee discounts. See `fee_tier` and `cpi_fee_discount`.
+    pub fn get_fee_tier(ctx: Context<ReadFeeTier>) -> Result<u8> {
+        Ok(fee_tier(&ctx.accounts.stake_account))
+    }
+
+    /// Current voting power of a wallet (view function, readable via CPI)
+    pub fn get_voting_power(ctx: Context<ReadVotingPower>) -> Result<u64> {
+        let clock = Clock::get()?;
+        Ok(ctx.accounts.voting_power.current(clock.unix_timestamp))
+    }
+
+    /// Open a governance proposal. If it passes, `execute_proposal` invokes
+    /// `instruction` signed by the `governance` PDA, so it can call any
+    /// config setter in the workspace whose authority is that PDA.
+    pub fn create_proposal(ctx: Context<CreateProposal>, instruction: ProposalInstruction) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.voting_power.current(now) >= MIN_PROPOSAL_POWER,
+            ErrorCode::InsufficientVotingPower
+        );
+        require!(
+            instruction.accounts.len() <= MAX_PROPOSAL_ACCOUNTS &&
+                instruction.data.len() <= MAX_PROPOSAL_DATA,
+            ErrorCode::InvalidProposal
+        );
+        let governance = ctx.accounts.governance.key();
+        require!(
+            instruction.accounts.iter().all(|meta| !meta.is_signer || meta.pubkey == governance),
+            ErrorCode::InvalidProposal
+        );
+
+        let config = &mut ctx.accounts.config;
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.id = config.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.instruction = instruction;
+        proposal.votes_for = 0;
+        proposal.votes_against = 0;
+        proposal.created_at = now;
+        proposal.voting_ends_at = now + PROPOSAL_VOTING_PERIOD;
+        proposal.status = ProposalStatus::Active;
+        proposal.event_seq = 0;
+        proposal.bump = ctx.bumps.proposal;
+
+        config.proposal_count += 1;
+
+        emit_cpi!(ProposalCreated {
+            header: event_header(proposal.key(), &mut proposal.event_seq, now),
+            id: proposal.id,
+            proposer: proposal.proposer,
+            target_program: proposal.instruction.program_id,
+            voting_ends_at: proposal.voting_ends_at,
+        });
+
+        Ok(())
+    }
+
+    /// Vote on an open proposal with the voter's current veDRONEOS power
+    pub fn cast_vote(ctx: Context<CastVote>, support: bool) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(proposal.status == ProposalStatus::Active, ErrorCode::ProposalNotActive);
+        require!(now < proposal.voting_ends_at, ErrorCode::VotingClosed);
+
+        let weight = ctx.accounts.voting_power.current(now);
+        require!(weight > 0, ErrorCode::InsufficientVotingPower);
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.proposal = proposal.key();
+        vote_record.voter = ctx.accounts.voter.key();
+        vote_record.support = support;
+        vote_record.weight = weight;
+        vote_record.voted_at = now;
+        vote_record.bump = ctx.bumps.vote_record;
+
+        if support {
+            proposal.votes_for = proposal.votes_for.checked_add(weight).ok_or(ErrorCode::Overflow)?;
+        } else {
+            proposal.votes_against = proposal.votes_against.checked_add(weight).ok_or(ErrorCode::Overflow)?;
+        }
+
+        emit_cpi!(ProposalVoteCast {
+            header: event_header(proposal.key(), &mut proposal.event_seq, now),
+            id: proposal.id,
+            voter: vote_record.voter,
+            support,
+            weight,
+        });
+
+        Ok(())
+    }
+
+    /// Close voting on a proposal and, if it passed, run its instruction.
+    /// The accounts it references (and the target program) are passed as
+    /// remaining accounts.
+    pub fn execute_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteProposal<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(proposal.status == ProposalStatus::Active, ErrorCode::ProposalNotActive);
+        require!(now >= proposal.voting_ends_at, ErrorCode::VotingNotEnded);
+
+        let passed = proposal.votes_for > proposal.votes_against &&
+            proposal.votes_for >= PROPOSAL_QUORUM;
+
+        if passed {
+            let instruction = Instruction {
+                program_id: proposal.instruction.program_id,
+                accounts: proposal
+                    .instruction
+                    .accounts
+                    .iter()
+                    .map(|meta| AccountMeta {
+                        pubkey: meta.pubkey,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect(),
+                data: proposal.instruction.data.clone(),
+            };
+
+            let mut account_infos = ctx.remaining_accounts.to_vec();
+            account_infos.push(ctx.accounts.governance.to_account_info());
+
+            let seeds = &[b"governance".as_ref(), &[ctx.bumps.governance]];
+            invoke_signed(&instruction, &account_infos, &[&seeds[..]])?;
+
+            proposal.status = ProposalStatus::Executed;
+        } else {
+            proposal.status = ProposalStatus::Defeated;
+        }
+
+        emit_cpi!(ProposalExecuted {
+            header: event_header(proposal.key(), &mut proposal.event_seq, now),
+            id: proposal.id,
+            passed,
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+        });
+
+        Ok(())
+    }
+
+    /// Get pending rewards (view function, returned as borsh return data
+    /// so it can be read via CPI or simulation)
+    pub fn get_pending_rewards(ctx: Context<ViewStake>) -> Result<PendingRewardsView> {
+        let now = Clock::get()?.unix_timestamp;
+        let stake = &ctx.accounts.stake_account;
+        let pending = calculate_rewards(stake, ctx.accounts.config.base_apy_bps, now)?;
+
+        Ok(PendingRewardsView {
+            pending,
+            accrued: pending - stake.settled_rewards,
+            lock_until: stake.lock_end(now),
+            multiplier: stake.multiplier,
+        })
+    }
+
+    /// Get an operator's bond and standing (view function, returned as
+    /// borsh return data)
+    pub fn get_operator_stake(ctx: Context<ViewOperatorStake>) -> Result<OperatorStakeView> {
+        let stake = &ctx.accounts.operator_stake;
+
+        Ok(OperatorStakeView {
+            bond: stake.slashable_amount + stake.delegated_amount,
+            slashable_amount: stake.slashable_amount,
+            delegated_amount: stake.delegated_amount,
+            unbonding_amount: stake.unbonding_amount,
+            unbonds_at: stake.unbonding_started_at.map(|t| t + OPERATOR_UNBONDING_PERIOD),
+            reputation: stake.reputation,
+            active_tasks: stake.active_tasks,
+            delegator_share_bps: stake.delegator_share_bps,
+        })
+    }
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn event_header(entity: Pubkey, seq: &mut u64, timestamp: i64) -> EventHeader {
+    EventHeader::next(ProgramTag::Token, entity, seq, timestamp)
+}
+
+/// Layout version of a stake or operator stake account. Versioned layouts
+/// store it right after the discriminator; older accounts are exactly the
+/// size of the unversioned layout.
+fn account_version(info: &AccountInfo, v0_space: usize) -> Result<u8> {
+    let data = info.try_borrow_data()?;
+    if data.len() == 8 + v0_space {
+        return Ok(0);
+    }
+    Ok(*data.get(8).ok_or(ErrorCode::InvalidAccountVersion)?)
+}
+
+/// Grow `info` to `new_len`, topping up rent from `payer`, and write
+/// `account` over it
+fn write_migrated<'info, T: AccountSerialize>(
+    info: &AccountInfo<'info>,
+    account: &T,
+    new_len: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+    if rent > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            rent,
+        )?;
+    }
+    if info.data_len() < new_len {
+        info.realloc(new_len, true)?;
+    }
+    account.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])
+}
+
+/// Send slashed tokens out of a config-owned vault, splitting them between
+/// the insurance pool (`insurance_share_bps`) and the treasury. Returns the
+/// part sent to insurance.
+#[allow(clippy::too_many_arguments)]
+fn route_slash<'info>(
+    config: &Account<'info, TokenConfig>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    treasury: &InterfaceAccount<'info, TokenAccount>,
+    insurance_pool: &mut Account<'info, InsurancePool>,
+    insurance_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<u64> {
+    let to_insurance = (amount as u128 * config.insurance_share_bps as u128 / 10_000) as u64;
+    let to_treasury = amount - to_insurance;
+
+    let seeds = &[b"config".as_ref(), &[config.bump]];
+    let signer = &[&seeds[..]];
+
+    if to_treasury > 0 {
+        let transfer_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: treasury.to_account_info(),
+                authority: config.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(transfer_ctx, to_treasury, DECIMALS)?;
+    }
+    if to_insurance > 0 {
+        let transfer_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: insurance_vault.to_account_info(),
+                authority: config.to_account_info(),
+            },
+            signer,
+        );
+        token_interface::transfer_checked(transfer_ctx, to_insurance, DECIMALS)?;
+        let received = net_of_transfer_fee(mint, to_insurance)?;
+
+        insurance_pool.balance += received;
+        insurance_pool.total_received += received;
+    }
+
+    Ok(to_insurance)
+}
+
+/// What arrives from a transfer of `amount`, net of the mint's transfer fee
+/// for the current epoch, if it has one
+fn net_of_transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    let data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    let fee = match mint_state.get_extension::<TransferFeeConfig>() {
+        Ok(fee_config) => fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(ErrorCode::Overflow)?,
+        Err(_) => 0,
+    };
+    Ok(amount - fee)
+}
+
+/// Burn `amount` from the config-owned treasury
+fn burn_treasury<'info>(
+    config: &Account<'info, TokenConfig>,
+    mint: &InterfaceAccount<'info, Mint>,
+    treasury: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    let seeds = &[b"config".as_ref(), &[config.bump]];
+    token_interface::burn(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Burn {
+                mint: mint.to_account_info(),
+                from: treasury.to_account_info(),
+                authority: config.to_account_info(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+    )
+}
+
+fn calculate_rewards(stake: &StakeAccount, base_apy_bps: u16, current_time: i64) -> Result<u64> {
+    let elapsed = (current_time - stake.last_claim_at) as u64;
+    
+    // Base reward calculation
+    let base_reward = stake.amount
+        .checked_mul(base_apy_bps as u64)
+        .ok_or(ErrorCode::Overflow)?
+        .checked_mul(elapsed)
+        .ok_or(ErrorCode::Overflow)?
+        / (10000 * SECONDS_PER_YEAR);
+    
+    // Apply multiplier
+    let multiplied_reward = base_reward
+        .checked_mul(stake.multiplier as u64)
+        .ok_or(ErrorCode::Overflow)?
+        / 10000;
+
+    // Apply reputation boost
+    let multiplied_reward = multiplied_reward
+        .checked_mul(10000 + stake.reputation_boost_bps as u64)
+        .ok_or(ErrorCode::Overflow)?
+        / 10000;
+    
+    Ok(multiplied_reward
+        .checked_add(stake.settled_rewards)
+        .ok_or(ErrorCode::Overflow)?)
+}
+
+/// Check the caller may withdraw `amount` from the position: its owner, or
+/// for positions with a receipt NFT, the receipt's holder withdrawing it
+/// all, whose receipt is burned. Returns the burned receipt's mint.
+fn authorize_withdrawal<'info>(
+    stake_account: &mut StakeAccount,
+    amount: u64,
+    user: &Signer<'info>,
+    receipt_mint: &Option<InterfaceAccount<'info, Mint>>,
+    receipt_token: &Option<InterfaceAccount<'info, TokenAccount>>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<Option<Pubkey>> {
+    let receipt = match stake_account.receipt_mint {
+        Some(receipt) => receipt,
+        None => {
+            require!(stake_account.owner == user.key(), ErrorCode::Unauthorized);
+            return Ok(None);
+        }
+    };
+
+    let mint = receipt_mint.as_ref().ok_or(ErrorCode::NotReceiptHolder)?;
+    let token = receipt_token.as_ref().ok_or(ErrorCode::NotReceiptHolder)?;
+    require!(
+        mint.key() == receipt &&
+        token.mint == receipt &&
+        token.owner == user.key() &&
+        token.amount == 1,
+        ErrorCode::NotReceiptHolder
+    );
+    require!(amount == stake_account.amount, ErrorCode::ReceiptRequiresFullUnstake);
+
+    token_interface::burn(
+        CpiContext::new(
+            token_program.to_account_info(),
+            Burn {
+                mint: mint.to_account_info(),
+                from: token.to_account_info(),
+                authority: user.to_account_info(),
+            },
+        ),
+        1,
+    )?;
+    stake_account.receipt_mint = None;
+
+    Ok(Some(receipt))
+}
+
+/// Work out the share of a position's pending rewards that goes with
+/// `withdrawn` of its tokens and how much of it is payable now, as far as
+/// this epoch's budget and the rewards vault allow. The remainder's accrual
+/// keeps running from the same `last_claim_at` on the smaller amount, which
+/// is exactly its share; whatever of the withdrawn share goes unpaid is
+/// settled on the position, to be claimed later. Returns `(owed, payable)`;
+/// the caller pays out `payable`.
+fn withdraw_rewards_share(
+    stake_account: &mut StakeAccount,
+    withdrawn: u64,
+    base_apy_bps: u16,
+    emissions: &mut EmissionSchedule,
+    vault_balance: u64,
+    now: i64,
+) -> Result<(u64, u64)> {
+    let pending = calculate_rewards(stake_account, base_apy_bps, now)?;
+    let settled = stake_account.settled_rewards;
+    let pro_rata = |value: u64| {
+        (value as u128 * withdrawn as u128 / stake_account.amount as u128) as u64
+    };
+    let settled_share = pro_rata(settled);
+    let owed = pro_rata(pending - settled) + settled_share;
+
+    let rewards = owed
+        .min(emissions.remaining_budget(now))
+        .min(vault_balance);
+    emissions.distributed_in_epoch += rewards;
+    stake_account.settled_rewards = settled - settled_share + (owed - rewards);
+
+    Ok((owed, rewards))
+}
+
+/// Reward boost (bps) for a robot's reputation: linear from nothing at
+/// `REPUTATION_BOOST_FLOOR` to `MAX_REPUTATION_BOOST_BPS` at 10000
+fn reputation_boost(reputation_score: u16) -> u16 {
+    let above_floor = reputation_score.saturating_sub(REPUTATION_BOOST_FLOOR) as u64;
+    (above_floor * MAX_REPUTATION_BOOST_BPS / (10000 - REPUTATION_BOOST_FLOOR) as u64) as u16
+}
+
+/// Cap `pending` rewards at what's left of the current epoch's emission
+/// budget and at the rewards vault's balance, record the payout against the
+/// budget and mark it claimed on the stake. Settled rewards are paid first;
+/// when a cap cuts a claim short, `last_claim_at` advances only by the share
+/// of time paid for, so the rest stays claimable later.
+fn budget_rewards(
+    stake: &mut StakeAccount,
+    pending: u64,
+    emissions: &mut EmissionSchedule,
+    vault_balance: u64,
+    now: i64,
+) -> u64 {
+    let rewards = pending.min(emissions.remaining_budget(now)).min(vault_balance);
+    emissions.distributed_in_epoch += rewards;
+
+    let from_settled = rewards.min(stake.settled_rewards);
+    stake.settled_rewards -= from_settled;
+
+    let accrued = pending - from_settled - stake.settled_rewards;
+    let paid_accrual = rewards - from_settled;
+    if paid_accrual == accrued {
+        stake.last_claim_at = now;
+    } else if paid_accrual > 0 {
+        let elapsed = (now - stake.last_claim_at) as u128;
+        stake.last_claim_at += (elapsed * paid_accrual as u128 / accrued as u128) as i64;
+    }
+
+    rewards
+}
+
+// ============================================================================
+// ACCOUNTS
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeToken<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, TokenConfig>,
+    
+    /// CHECK: created and initialized as a Token-2022 mint in the handler
+    #[account(mut, seeds = [b"mint"], bump)]
+    pub mint: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MintInitialSupply<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"mint"],
+        bump = config.mint_bump
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = treasury.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStakingVaults<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        constraint = stake_vault.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = stake_vault.mint == config.mint @ ErrorCode::InvalidVault,
+        constraint = stake_vault.key() != config.treasury @ ErrorCode::InvalidVault
+    )]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        constraint = rewards_vault.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = rewards_vault.mint == config.mint @ ErrorCode::InvalidVault,
+        constraint = rewards_vault.key() != config.treasury @ ErrorCode::InvalidVault,
+        constraint = rewards_vault.key() != stake_vault.key() @ ErrorCode::InvalidVault
+    )]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitializeEmissionSchedule<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EmissionSchedule::INIT_SPACE,
+        seeds = [b"emissions"],
+        bump
+    )]
+    pub emissions: Account<'info, EmissionSchedule>,
+    
+    #[account(mut, constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct InitializeLockTiers<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LockTierTable::INIT_SPACE,
+        seeds = [b"lock-tiers"],
+        bump
+    )]
+    pub lock_tiers: Account<'info, LockTierTable>,
+    
+    #[account(mut, constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetLockTiers<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"lock-tiers"], bump = lock_tiers.bump)]
+    pub lock_tiers: Account<'info, LockTierTable>,
+    
+    /// CHECK: signer PDA for executed proposals, holds no data
+    #[account(seeds = [b"governance"], bump)]
+    pub governance: UncheckedAccount<'info>,
+    
+    /// The config authority, or the governance PDA via `execute_proposal`
+    #[account(
+        constraint = authority.key() == config.authority
+            || authority.key() == governance.key() @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateEmissionSchedule<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"emissions"], bump = emissions.bump)]
+    pub emissions: Account<'info, EmissionSchedule>,
+    
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FundRewardsVault<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, address = config.rewards_vault @ ErrorCode::InvalidVault)]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = funder_token.owner == funder.key())]
+    pub funder_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub funder: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DistributeEpochRewards<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"emissions"], bump = emissions.bump)]
+    pub emissions: Account<'info, EmissionSchedule>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = treasury.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = config.rewards_vault @ ErrorCode::InvalidVault)]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = cranker_token.owner == cranker.key())]
+    pub cranker_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub cranker: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(seeds = [b"lock-tiers"], bump = lock_tiers.bump)]
+    pub lock_tiers: Account<'info, LockTierTable>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + StakerPositions::INIT_SPACE,
+        seeds = [b"positions", user.key().as_ref()],
+        bump
+    )]
+    pub positions: Account<'info, StakerPositions>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + StakeAccount::INIT_SPACE,
+        seeds = [b"stake", user.key().as_ref(), &positions.next_index.to_le_bytes()],
+        bump
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidVault)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = user_token.owner == user.key())]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Referral accounts, only needed when staking with a referrer
+    #[account(mut, seeds = [b"referrer", referrer_account.owner.as_ref()], bump = referrer_account.bump)]
+    pub referrer_account: Option<Account<'info, Referrer>>,
+    
+    #[account(mut)]
+    pub referrer_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut, address = config.rewards_vault @ ErrorCode::InvalidVault)]
+    pub rewards_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut, seeds = [b"emissions"], bump = emissions.bump)]
+    pub emissions: Option<Account<'info, EmissionSchedule>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MintStakeReceipt<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", user.key().as_ref(), &stake_account.index.to_le_bytes()],
+        bump = stake_account.bump,
+        constraint = stake_account.owner == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(
+        init,
+        payer = user,
+        seeds = [b"receipt", stake_account.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = receipt_mint,
+        mint::token_program = token_program,
+        extensions::metadata_pointer::authority = config,
+        extensions::metadata_pointer::metadata_address = receipt_mint,
+    )]
+    pub receipt_mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    #[account(
+        init,
+        payer = user,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub receipt_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Referrer::INIT_SPACE,
+        seeds = [b"referrer", owner.key().as_ref()],
+        bump
+    )]
+    pub referrer_account: Account<'info, Referrer>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", user.key().as_ref(), &stake_account.index.to_le_bytes()],
+        bump = stake_account.bump,
+        constraint = stake_account.owner == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, seeds = [b"emissions"], bump = emissions.bump)]
+    pub emissions: Account<'info, EmissionSchedule>,
+    
+    #[account(mut, address = config.rewards_vault @ ErrorCode::InvalidVault)]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = user_token.owner == user.key())]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CompoundRewards<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", user.key().as_ref(), &stake_account.index.to_le_bytes()],
+        bump = stake_account.bump,
+        constraint = stake_account.owner == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, seeds = [b"emissions"], bump = emissions.bump)]
+    pub emissions: Account<'info, EmissionSchedule>,
+    
+    #[account(mut, address = config.rewards_vault @ ErrorCode::InvalidVault)]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidVault)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExtendLock<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(seeds = [b"lock-tiers"], bump = lock_tiers.bump)]
+    pub lock_tiers: Account<'info, LockTierTable>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", user.key().as_ref(), &stake_account.index.to_le_bytes()],
+        bump = stake_account.bump,
+        constraint = stake_account.owner == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    pub user: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RefreshReputationBoost<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"stake", stake_account.owner.as_ref(), &stake_account.index.to_le_bytes()],
+        bump = stake_account.bump
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    /// CHECK: identity-registry robot, validated by its `get_robot_reputation`
+    pub robot: UncheckedAccount<'info>,
+    
+    /// The stake owner, or anyone when refreshing from the current robot
+    pub caller: Signer<'info>,
+    
+    pub identity_registry_program: Program<'info, IdentityRegistry>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Unstake<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    /// Caller is checked in the handler: the owner, or the receipt holder
+    #[account(
+        mut,
+        seeds = [b"stake", stake_account.owner.as_ref(), &stake_account.index.to_le_bytes()],
+        bump = stake_account.bump
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, seeds = [b"positions", stake_account.owner.as_ref()], bump = positions.bump)]
+    pub positions: Account<'info, StakerPositions>,
+    
+    #[account(mut, seeds = [b"emissions"], bump = emissions.bump)]
+    pub emissions: Account<'info, EmissionSchedule>,
+    
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidVault)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = config.rewards_vault @ ErrorCode::InvalidVault)]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = user_token.owner == user.key())]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Receipt NFT and the caller's account holding it, for positions that have one
+    #[account(mut)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(mut)]
+    pub receipt_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetExitQueue<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ExitQueue::INIT_SPACE,
+        seeds = [b"exit-queue"],
+        bump
+    )]
+    pub exit_queue: Account<'info, ExitQueue>,
+    
+    #[account(mut, constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct QueueUnstake<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    /// Caller is checked in the handler: the owner, or the receipt holder
+    #[account(
+        mut,
+        seeds = [b"stake", stake_account.owner.as_ref(), &stake_account.index.to_le_bytes()],
+        bump = stake_account.bump
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut, seeds = [b"positions", stake_account.owner.as_ref()], bump = positions.bump)]
+    pub positions: Account<'info, StakerPositions>,
+    
+    #[account(mut, seeds = [b"emissions"], bump = emissions.bump)]
+    pub emissions: Account<'info, EmissionSchedule>,
+    
+    #[account(mut, seeds = [b"exit-queue"], bump = exit_queue.bump)]
+    pub exit_queue: Account<'info, ExitQueue>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + ExitTicket::INIT_SPACE,
+        seeds = [b"exit-ticket".as_ref(), &exit_queue.tail.to_le_bytes()],
+        bump
+    )]
+    pub ticket: Account<'info, ExitTicket>,
+    
+    #[account(mut, address = config.rewards_vault @ ErrorCode::InvalidVault)]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = user_token.owner == user.key())]
+    pub user_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// Receipt NFT and the caller's account holding it, for positions that have one
+    #[account(mut)]
+    pub receipt_mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(mut)]
+    pub receipt_token: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProcessExitQueue<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"emissions"], bump = emissions.bump)]
+    pub emissions: Account<'info, EmissionSchedule>,
+    
+    #[account(mut, seeds = [b"exit-queue"], bump = exit_queue.bump)]
+    pub exit_queue: Account<'info, ExitQueue>,
+    
+    /// The queue's head; its rent goes back to the owner
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"exit-ticket".as_ref(), &exit_queue.head.to_le_bytes()],
+        bump = ticket.bump
+    )]
+    pub ticket: Account<'info, ExitTicket>,
+    
+    /// CHECK: the ticket's owner, receiving its rent
+    #[account(mut, address = ticket.owner @ ErrorCode::Unauthorized)]
+    pub owner: UncheckedAccount<'info>,
+    
+    #[account(mut, constraint = owner_token.owner == ticket.owner @ ErrorCode::Unauthorized)]
+    pub owner_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = config.stake_vault @ ErrorCode::InvalidVault)]
+    pub stake_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, address = config.rewards_vault @ ErrorCode::InvalidVault)]
+    pub rewards_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateOperatorStake<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + OperatorStake::INIT_SPACE,
+        seeds = [b"operator", operator.key().as_ref()],
+        bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        mut,
+        constraint = operator_vault.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault,
+        constraint = config.operator_vault == Pubkey::default()
+            || operator_vault.key() == config.operator_vault @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = operator_token.owner == operator.key())]
+    pub operator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TopUpOperatorStake<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"operator", operator.key().as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        mut,
+        address = config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = operator_token.owner == operator.key())]
+    pub operator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub operator: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RequestOperatorUnstake<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"operator", operator.key().as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    pub operator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizeOperatorUnstake<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"operator", operator.key().as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        mut,
+        address = config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = operator_token.owner == operator.key())]
+    pub operator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub operator: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetDelegatorShare<'info> {
+    #[account(
+        mut,
+        seeds = [b"operator", operator.key().as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    pub operator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DelegateStake<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"operator", operator_stake.operator.as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        init_if_needed,
+        payer = delegator,
+        space = 8 + Delegation::INIT_SPACE,
+        seeds = [b"delegation", operator_stake.operator.as_ref(), delegator.key().as_ref()],
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    #[account(
+        mut,
+        address = config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = delegator_token.owner == delegator.key())]
+    pub delegator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub delegator: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UndelegateStake<'info> {
+    #[account(
+        mut,
+        seeds = [b"delegation", delegation.operator.as_ref(), delegator.key().as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    pub delegator: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizeUndelegation<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"operator", delegation.operator.as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        mut,
+        seeds = [b"delegation", delegation.operator.as_ref(), delegator.key().as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    #[account(
+        mut,
+        address = config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = delegator_token.owner == delegator.key())]
+    pub delegator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub delegator: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimDelegationIncome<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(seeds = [b"operator", delegation.operator.as_ref()], bump = operator_stake.bump)]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        mut,
+        seeds = [b"delegation", delegation.operator.as_ref(), delegator.key().as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Account<'info, Delegation>,
+    
+    #[account(
+        mut,
+        address = config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = delegator_token.owner == delegator.key())]
+    pub delegator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub delegator: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ShareOperatorIncome<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"operator", operator.key().as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        mut,
+        address = config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = operator_token.owner == operator.key())]
+    pub operator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub operator: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct OperatorTask<'info> {
+    #[account(mut)]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    /// task-market's market PDA, signing for the CPI
+    #[account(seeds = [b"market"], bump, seeds::program = TASK_MARKET_PROGRAM_ID)]
+    pub market: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ForfeitTaskBond<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut)]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        mut,
+        address = config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// The task creator's token account
+    #[account(mut, constraint = creator_token.mint == mint.key() @ ErrorCode::InvalidVault)]
+    pub creator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// task-market's market PDA, signing for the CPI
+    #[account(seeds = [b"market"], bump, seeds::program = TASK_MARKET_PROGRAM_ID)]
+    pub market: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SlashOperator<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut)]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + SlashRecord::INIT_SPACE,
+        seeds = [b"slash", operator_stake.operator.as_ref(), &operator_stake.slash_count.to_le_bytes()],
+        bump
+    )]
+    pub slash_record: Account<'info, SlashRecord>,
+    
+    #[account(
+        mut,
+        address = config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        address = config.treasury @ ErrorCode::InvalidVault,
+        constraint = treasury.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"insurance"], bump = insurance_pool.bump)]
+    pub insurance_pool: Account<'info, InsurancePool>,
+    
+    #[account(mut, address = insurance_pool.vault @ ErrorCode::InvalidVault)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Must be a registered slasher program
+    #[account(
+        constraint = config.slasher_programs.contains(&slasher_program.key()) @ ErrorCode::SlasherNotRegistered
+    )]
+    pub slasher_program: AccountInfo<'info>,
+    
+    /// The slasher program's `["slasher"]` PDA, which only it can sign for
+    #[account(seeds = [b"slasher"], bump, seeds::program = slasher_program.key())]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateSwarmStake<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    /// CHECK: swarm-coordinator's swarm PDA, which is derived from its leader
+    #[account(seeds = [b"swarm", leader.key().as_ref()], bump, seeds::program = SWARM_COORDINATOR_PROGRAM_ID)]
+    pub swarm: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = leader,
+        space = 8 + SwarmStake::INIT_SPACE,
+        seeds = [b"swarm-stake", swarm.key().as_ref()],
+        bump
+    )]
+    pub swarm_stake: Account<'info, SwarmStake>,
+    
+    #[account(
+        mut,
+        constraint = swarm_vault.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = swarm_vault.mint == mint.key() @ ErrorCode::InvalidVault
+    )]
+    pub swarm_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = leader_token.owner == leader.key())]
+    pub leader_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub leader: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadSwarmStake<'info> {
+    #[account(seeds = [b"swarm-stake", swarm_stake.swarm.as_ref()], bump = swarm_stake.bump)]
+    pub swarm_stake: Account<'info, SwarmStake>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SlashSwarmStake<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"swarm-stake", swarm_stake.swarm.as_ref()], bump = swarm_stake.bump)]
+    pub swarm_stake: Account<'info, SwarmStake>,
+    
+    #[account(
+        mut,
+        constraint = swarm_vault.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = swarm_vault.mint == mint.key() @ ErrorCode::InvalidVault
+    )]
+    pub swarm_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        address = config.treasury @ ErrorCode::InvalidVault,
+        constraint = treasury.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, seeds = [b"insurance"], bump = insurance_pool.bump)]
+    pub insurance_pool: Account<'info, InsurancePool>,
+    
+    #[account(mut, address = insurance_pool.vault @ ErrorCode::InvalidVault)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: Must be a registered slasher program
+    #[account(
+        constraint = config.slasher_programs.contains(&slasher_program.key()) @ ErrorCode::SlasherNotRegistered
+    )]
+    pub slasher_program: AccountInfo<'info>,
+    
+    /// The slasher program's `["slasher"]` PDA, which only it can sign for
+    #[account(seeds = [b"slasher"], bump, seeds::program = slasher_program.key())]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecoverReputation<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        mut,
+        seeds = [b"operator", operator_stake.operator.as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateParameters<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ApplyParameters<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositFees<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = treasury.mint == mint.key() @ ErrorCode::InvalidVault
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = payer_token.owner == payer.key())]
+    pub payer_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BurnFromTreasury<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = treasury.mint == mint.key() @ ErrorCode::InvalidVault
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: signer PDA for executed proposals, holds no data
+    #[account(seeds = [b"governance"], bump)]
+    pub governance: UncheckedAccount<'info>,
+    
+    /// The config authority, or the governance PDA via `execute_proposal`
+    #[account(
+        constraint = authority.key() == config.authority
+            || authority.key() == governance.key() @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetBuybackLimit<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(constraint = fee_mint.key() != config.mint @ ErrorCode::InvalidVault)]
+    pub fee_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + BuybackBudget::INIT_SPACE,
+        seeds = [b"buyback", fee_mint.key().as_ref()],
+        bump
+    )]
+    pub budget: Account<'info, BuybackBudget>,
+    
+    #[account(mut, constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BuybackAndBurn<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut, seeds = [b"buyback", budget.fee_mint.as_ref()], bump = budget.bump)]
+    pub budget: Account<'info, BuybackBudget>,
+    
+    /// CHECK: signer PDA owning the buyback vaults, holds no data
+    #[account(seeds = [b"buyback"], bump)]
+    pub buyback: UncheckedAccount<'info>,
+    
+    /// Fee revenue being sold
+    #[account(
+        mut,
+        constraint = fee_vault.owner == buyback.key() @ ErrorCode::InvalidVault,
+        constraint = fee_vault.mint == budget.fee_mint @ ErrorCode::InvalidVault
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Receives the bought DRONEOS, which is burned from here
+    #[account(
+        mut,
+        constraint = buyback_vault.owner == buyback.key() @ ErrorCode::InvalidVault,
+        constraint = buyback_vault.mint == mint.key() @ ErrorCode::InvalidVault
+    )]
+    pub buyback_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// CHECK: must be a whitelisted AMM, invoked with the remaining accounts
+    #[account(
+        executable,
+        constraint = config.swap_programs.contains(&swap_program.key()) @ ErrorCode::SwapProgramNotAllowed
+    )]
+    pub swap_program: UncheckedAccount<'info>,
+    
+    /// CHECK: signer PDA for executed proposals, holds no data
+    #[account(seeds = [b"governance"], bump)]
+    pub governance: UncheckedAccount<'info>,
+    
+    /// The config authority, or the governance PDA via `execute_proposal`
+    #[account(
+        constraint = authority.key() == config.authority
+            || authority.key() == governance.key() @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetTransferFee<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawTransferFees<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = treasury.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = treasury.mint == mint.key() @ ErrorCode::InvalidVault
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsurancePool<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + InsurancePool::INIT_SPACE,
+        seeds = [b"insurance"],
+        bump
+    )]
+    pub insurance_pool: Account<'info, InsurancePool>,
+    
+    #[account(
+        constraint = insurance_vault.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = insurance_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FileClaim<'info> {
+    #[account(mut, seeds = [b"insurance"], bump = insurance_pool.bump)]
+    pub insurance_pool: Account<'info, InsurancePool>,
+    
+    #[account(
+        init,
+        payer = claimant,
+        space = 8 + InsuranceClaim::INIT_SPACE,
+        seeds = [b"claim".as_ref(), &insurance_pool.claim_count.to_le_bytes()],
+        bump
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+    
+    /// CHECK: task-market Task the claim is about; owner and creator checked in the handler
+    pub task: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PayClaim<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"insurance"], bump = insurance_pool.bump)]
+    pub insurance_pool: Account<'info, InsurancePool>,
+    
+    #[account(mut, seeds = [b"claim", &claim.id.to_le_bytes()], bump = claim.bump)]
+    pub claim: Account<'info, InsuranceClaim>,
+    
+    #[account(mut, address = insurance_pool.vault @ ErrorCode::InvalidVault)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = claimant_token.owner == claim.claimant @ ErrorCode::Unauthorized)]
+    pub claimant_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PayInsurancePremium<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"insurance"], bump = insurance_pool.bump)]
+    pub insurance_pool: Account<'info, InsurancePool>,
+    
+    #[account(mut, address = insurance_pool.vault @ ErrorCode::InvalidVault)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = payer_token.owner == payer.key() @ ErrorCode::Unauthorized)]
+    pub payer_token: InterfaceAccount<'info, TokenAccount>,
+    
+    pub payer: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PayTaskInsurance<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(mut, seeds = [b"insurance"], bump = insurance_pool.bump)]
+    pub insurance_pool: Account<'info, InsurancePool>,
+    
+    #[account(mut, address = insurance_pool.vault @ ErrorCode::InvalidVault)]
+    pub insurance_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    /// The task creator's token account
+    #[account(mut, constraint = creator_token.mint == mint.key() @ ErrorCode::InvalidVault)]
+    pub creator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    /// task-market's market PDA, signing for the CPI
+    #[account(seeds = [b"market"], bump, seeds::program = TASK_MARKET_PROGRAM_ID)]
+    pub market: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAppealVault<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        constraint = appeal_vault.owner == config.key() @ ErrorCode::InvalidVault,
+        constraint = appeal_vault.mint == config.mint @ ErrorCode::InvalidVault,
+        constraint = appeal_vault.key() != config.treasury @ ErrorCode::InvalidVault,
+        constraint = appeal_vault.key() != config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = appeal_vault.key() != config.stake_vault @ ErrorCode::InvalidVault,
+        constraint = appeal_vault.key() != config.rewards_vault @ ErrorCode::InvalidVault
+    )]
+    pub appeal_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AppealSlash<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        seeds = [b"operator", operator.key().as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Account<'info, OperatorStake>,
+    
+    #[account(
+        init,
+        payer = operator,
+        space = 8 + SlashAppeal::INIT_SPACE,
+        seeds = [
+            b"appeal",
+            operator.key().as_ref(),
+            &operator_stake.last_slash_at.unwrap_or_default().to_le_bytes()
+        ],
+        bump
+    )]
+    pub appeal: Account<'info, SlashAppeal>,
+    
+    #[account(mut, address = config.appeal_vault @ ErrorCode::InvalidVault)]
+    pub appeal_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut, constraint = operator_token.owner == operator.key())]
+    pub operator_token: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub operator: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleSlashAppeal<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, TokenConfig>>,
+    
+    #[account(
+        mut,
+        seeds = [b"operator", appeal.operator.as_ref()],
+        bump = operator_stake.bump
+    )]
+    pub operator_stake: Box<Account<'info, OperatorStake>>,
+    
+    #[account(
+        mut,
+        seeds = [b"appeal", appeal.operator.as_ref(), &appeal.slashed_at.to_le_bytes()],
+        bump = appeal.bump
+    )]
+    pub appeal: Box<Account<'info, SlashAppeal>>,
+    
+    #[account(
+        mut,
+        address = config.treasury @ ErrorCode::InvalidVault,
+        constraint = treasury.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut, address = config.appeal_vault @ ErrorCode::InvalidVault)]
+    pub appeal_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        address = config.operator_vault @ ErrorCode::InvalidVault,
+        constraint = operator_vault.mint == config.mint @ ErrorCode::InvalidVault
+    )]
+    pub operator_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut, constraint = operator_token.owner == appeal.operator)]
+    pub operator_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// oracle-verifier's verifier PDA, signing for the CPI
+    #[account(seeds = [b"verifier"], bump, seeds::program = ORACLE_VERIFIER_PROGRAM_ID)]
+    pub verifier: Signer<'info>,
+    
+    #[account(seeds = [b"mint"], bump = config.mint_bump)]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ViewStake<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    pub stake_account: Account<'info, StakeAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ViewOperatorStake<'info> {
+    #[account(seeds = [b"operator", operator_stake.operator.as_ref()], bump = operator_stake.bump)]
+    pub operator_stake: Account<'info, OperatorStake>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateVotingPower<'info> {
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + VotingPower::INIT_SPACE,
+        seeds = [b"voting_power", user.key().as_ref()],
+        bump
+    )]
+    pub voting_power: Account<'info, VotingPower>,
+    
+    #[account(seeds = [b"positions", user.key().as_ref()], bump = positions.bump)]
+    pub positions: Account<'info, StakerPositions>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", &config.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(seeds = [b"voting_power", proposer.key().as_ref()], bump = voting_power.bump)]
+    pub voting_power: Account<'info, VotingPower>,
+    
+    /// CHECK: signer PDA for executed proposals, holds no data
+    #[account(seeds = [b"governance"], bump)]
+    pub governance: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + VoteRecord::INIT_SPACE,
+        seeds = [b"vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+    
+    #[account(seeds = [b"voting_power", voter.key().as_ref()], bump = voting_power.bump)]
+    pub voting_power: Account<'info, VotingPower>,
+    
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    
+    /// CHECK: signer PDA for executed proposals, holds no data
+    #[account(seeds = [b"governance"], bump)]
+    pub governance: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TakeSnapshot<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, TokenConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Snapshot::INIT_SPACE,
+        seeds = [b"snapshot", &config.snapshot_count.to_le_bytes()],
+        bump
+    )]
+    pub snapshot: Account<'info, Snapshot>,
+    
+    #[account(mut, constraint = authority.key() == config.authority @ ErrorCode::Unauthorized)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateStakeAccount<'info> {
+    /// CHECK: a StakeAccount or OperatorStake in any layout; owner and
+    /// discriminator are checked in the handler
+    #[account(mut)]
+    pub account: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReadSnapshot<'info> {
+    #[account(seeds = [b"snapshot", &snapshot.id.to_le_bytes()], bump = snapshot.bump)]
+    pub snapshot: Account<'info, Snapshot>,
+}
+
+#[derive(Accounts)]
+pub struct ReadFeeTier<'info> {
+    #[account(
+        seeds = [b"stake", stake_account.owner.as_ref(), &stake_account.index.to_le_bytes()],
+        bump = stake_account.bump
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ReadVotingPower<'info> {
+    #[account(seeds = [b"voting_power", voting_power.owner.as_ref()], bump = voting_power.bump)]
+    pub voting_power: Account<'info, VotingPower>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+#[account]
+#[derive(InitSpace)]
+pub struct TokenConfig {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub total_staked: u64,
+    pub total_rewards_distributed: u64,
+    pub stake_count: u64,
+    pub proposal_count: u64,
+    /// Share of slashed tokens (bps) routed to the insurance pool
+    pub insurance_share_bps: u16,
+    /// Seconds without a slash before operator reputation starts recovering
+    pub reputation_recovery_delay: i64,
+    /// Reputation points regained per clean day
+    pub reputation_recovery_rate: u16,
+    /// Recovery never lifts reputation above this
+    pub reputation_ceiling: u16,
+    /// Base staking APY before lock multipliers
+    pub base_apy_bps: u16,
+    pub min_stake: u64,
+    pub min_operator_stake: u64,
+    /// Parameter change waiting out its timelock
+    pub pending_parameters: Option<PendingParameters>,
+    /// Share of deposited fees (bps) burned on arrival
+    pub burn_share_bps: u16,
+    /// Cumulative tokens burned from treasury
+    pub total_burned: u64,
+    pub snapshot_count: u64,
+    /// Programs allowed to slash operators via CPI
+    #[max_len(MAX_SLASHER_PROGRAMS)]
+    pub slasher_programs: Vec<Pubkey>,
+    /// AMM programs the treasury may swap fee revenue through
+    #[max_len(MAX_SWAP_PROGRAMS)]
+    pub swap_programs: Vec<Pubkey>,
+    /// Unstakes of at least this much go through the exit queue (0 = off)
+    pub exit_queue_threshold: u64,
+    /// Treasury the initial supply was minted into
+    pub treasury: Pubkey,
+    /// Vault holding operator bonds, fixed by the first operator stake
+    pub operator_vault: Pubkey,
+    /// Vault holding staked tokens, set once by `initialize_staking_vaults`
+    pub stake_vault: Pubkey,
+    /// Vault staking rewards are paid from, set alongside the stake vault
+    pub rewards_vault: Pubkey,
+    /// Vault holding slash appeal bonds, set once by `initialize_appeal_vault`
+    pub appeal_vault: Pubkey,
+    pub event_seq: u64,
+    pub bump: u8,
+    pub mint_bump: u8,
+}
+
+/// Transfer fee to create the mint with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransferFeeParams {
+    pub basis_points: u16,
+    /// Cap on the fee for a single transfer
+    pub maximum_fee: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct PendingParameters {
+    pub base_apy_bps: u16,
+    pub min_stake: u64,
+    pub min_operator_stake: u64,
+    pub effective_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakeAccount {
+    /// Layout version, see `migrate_stake_account`
+    pub version: u8,
+    pub owner: Pubkey,
+    /// Position number among the owner's stakes
+    pub index: u32,
+    pub amount: u64,
+    pub staked_at: i64,
+    pub lock_duration: i64,
+    pub lock_until: i64,
+    pub multiplier: u16,
+    pub accumulated_rewards: u64,
+    pub last_claim_at: i64,
+    /// Rewards accrued before a lock change, owed at the old multiplier
+    pub settled_rewards: u64,
+    /// Position NFT whose holder alone may unstake
+    pub receipt_mint: Option<Pubkey>,
+    /// Lock rolls over for another `lock_duration` each time it runs out
+    pub auto_relock: bool,
+    /// Robot whose reputation sets `reputation_boost_bps`
+    pub boost_robot: Option<Pubkey>,
+    /// Extra reward, in bps of the lock-multiplied reward
+    pub reputation_boost_bps: u16,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl StakeAccount {
+    /// When the lock in force at `now` ends, counting rollovers
+    pub fn lock_end(&self, now: i64) -> i64 {
+        if !self.auto_relock || self.lock_duration <= 0 || now < self.lock_until {
+            return self.lock_until;
+        }
+        let periods = (now - self.lock_until) / self.lock_duration + 1;
+        self.lock_until + periods * self.lock_duration
+    }
+}
+
+/// Return data of `get_pending_rewards`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PendingRewardsView {
+    /// Claimable now: `accrued` plus rewards settled at earlier multipliers
+    pub pending: u64,
+    /// Accrued since the last claim at the current multiplier
+    pub accrued: u64,
+    /// End of the lock in force, counting auto re-lock rollovers
+    pub lock_until: i64,
+    pub multiplier: u16,
+}
+
+/// A wallet's stake positions: `stake` PDAs 0..next_index, of which
+/// `open_positions` still hold tokens
+#[account]
+#[derive(InitSpace)]
+pub struct StakerPositions {
+    pub owner: Pubkey,
+    pub next_index: u32,
+    pub open_positions: u32,
+    pub bump: u8,
+}
+
+/// Vote-escrowed $DRONEOS: `power` as of `updated_at`, decaying as locks run
+/// down by `slope` (the amount still locked) per 365 days
+#[account]
+#[derive(InitSpace)]
+pub struct VotingPower {
+    pub owner: Pubkey,
+    pub power: u64,
+    pub slope: u64,
+    /// Earliest lock expiry among the counted positions
+    pub next_expiry: i64,
+    pub updated_at: i64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl VotingPower {
+    /// Power at `now`. Past `next_expiry` the expired position keeps being
+    /// decayed, so this under-counts until the owner refreshes it.
+    pub fn current(&self, now: i64) -> u64 {
+        let elapsed = (now - self.updated_at).max(0) as u128;
+        let decay = self.slope as u128 * elapsed / MAX_LOCK_SECONDS as u128;
+        self.power.saturating_sub(decay.min(u64::MAX as u128) as u64)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub instruction: ProposalInstruction,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub created_at: i64,
+    pub voting_ends_at: i64,
+    pub status: ProposalStatus,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+    pub voted_at: i64,
+    pub bump: u8,
+}
+
+/// Instruction a proposal runs once passed, signed by the `governance` PDA
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ProposalInstruction {
+    pub program_id: Pubkey,
+    #[max_len(MAX_PROPOSAL_ACCOUNTS)]
+    pub accounts: Vec<ProposalAccountMeta>,
+    #[max_len(MAX_PROPOSAL_DATA)]
+    pub data: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct ProposalAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum ProposalStatus {
+    Active,
+    Executed,
+    Defeated,
+}
+
+/// FIFO queue of large withdrawals: tickets `head..tail` are waiting, and
+/// at most `epoch_cap` is paid out per emission epoch
+#[account]
+#[derive(InitSpace)]
+pub struct ExitQueue {
+    pub head: u64,
+    pub tail: u64,
+    pub epoch_cap: u64,
+    /// Emission epoch `paid_in_epoch` counts towards
+    pub epoch: u64,
+    pub paid_in_epoch: u64,
+    pub queued_amount: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+/// Tokens waiting in the exit queue, earning base APY since `queued_at`
+#[account]
+#[derive(InitSpace)]
+pub struct ExitTicket {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub queued_at: i64,
+    pub bump: u8,
+}
+
+/// Weekly spending limit and running totals for buybacks paid in one fee mint
+#[account]
+#[derive(InitSpace)]
+pub struct BuybackBudget {
+    pub fee_mint: Pubkey,
+    /// Most of the fee mint spent per `BUYBACK_EPOCH`
+    pub epoch_limit: u64,
+    pub epoch_start: i64,
+    pub spent_in_epoch: u64,
+    pub total_spent: u64,
+    /// DRONEOS bought and burned with this fee mint
+    pub total_burned: u64,
+    pub bump: u8,
+}
+
+/// Lock periods stakers may choose and their reward multipliers
+#[account]
+#[derive(InitSpace)]
+pub struct LockTierTable {
+    #[max_len(MAX_LOCK_TIERS)]
+    pub tiers: Vec<LockTier>,
+    pub bump: u8,
+}
+
+impl LockTierTable {
+    /// Reward multiplier (bps) for locking `lock_days`, which must be a tier
+    pub fn multiplier(&self, lock_days: u16) -> Result<u16> {
+        self.tiers
+            .iter()
+            .find(|tier| tier.days == lock_days)
+            .map(|tier| tier.multiplier_bps)
+            .ok_or_else(|| error!(ErrorCode::InvalidLockPeriod))
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct LockTier {
+    pub days: u16,
+    pub multiplier_bps: u16,
+}
+
+/// Per-epoch caps on staking reward payouts
+#[account]
+#[derive(InitSpace)]
+pub struct EmissionSchedule {
+    pub start_time: i64,
+    pub epoch_duration: i64,
+    #[max_len(MAX_EMISSION_EPOCHS)]
+    pub epoch_caps: Vec<u64>,
+    pub current_epoch: u64,
+    /// Rewards paid so far in `current_epoch`
+    pub distributed_in_epoch: u64,
+    /// Epochs whose cap `distribute_epoch_rewards` has moved into the rewards vault
+    pub funded_epochs: u64,
+    pub bump: u8,
+}
+
+impl EmissionSchedule {
+    /// Roll over to the epoch containing `now` and return what is left of
+    /// its cap; zero before the schedule starts or after it runs out
+    fn remaining_budget(&mut self, now: i64) -> u64 {
+        if now < self.start_time {
+            return 0;
+        }
+
+        let epoch = ((now - self.start_time) / self.epoch_duration) as u64;
+        if epoch != self.current_epoch {
+            self.current_epoch = epoch;
+            self.distributed_in_epoch = 0;
+        }
+
+        self.epoch_caps
+            .get(epoch as usize)
+            .map_or(0, |cap| cap.saturating_sub(self.distributed_in_epoch))
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct OperatorStake {
+    /// Layout version, see `migrate_stake_account`
+    pub version: u8,
+    pub operator: Pubkey,
+    pub total_staked: u64,
+    pub slashable_amount: u64,
+    pub created_at: i64,
+    pub last_slash_at: Option<i64>,
+    pub reputation: u16,
+    pub last_slash_amount: u64,
+    pub last_slash_reputation_loss: u16,
+    /// Clean time up to here has already been credited by recover_reputation
+    pub reputation_recovered_at: i64,
+    /// Tasks assigned in task-market and not yet finished
+    pub active_tasks: u32,
+    pub unbonding_amount: u64,
+    pub unbonding_started_at: Option<i64>,
+    /// Tokens delegated by holders, slashed pro rata with the operator's own
+    pub delegated_amount: u64,
+    /// Delegators' claims on `delegated_amount`
+    pub delegation_shares: u64,
+    pub delegator_count: u32,
+    /// Share of income (bps) passed on to delegators
+    pub delegator_share_bps: u16,
+    /// Delegator income per share, scaled by `INCOME_PRECISION`
+    pub income_per_share: u128,
+    pub last_slash_delegated: u64,
+    /// Slashes so far, numbering this operator's `SlashRecord`s
+    pub slash_count: u64,
+    /// Performance bonds held for tasks assigned in task-market, out of
+    /// `slashable_amount`
+    pub locked_task_bonds: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl OperatorStake {
+    /// Tokens currently backing `shares` of the delegated pool
+    pub fn delegated_value(&self, shares: u64) -> u64 {
+        if self.delegation_shares == 0 {
+            return 0;
+        }
+        (shares as u128 * self.delegated_amount as u128 / self.delegation_shares as u128) as u64
+    }
+}
+
+/// Return data of `get_operator_stake`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct OperatorStakeView {
+    /// Slashable amount plus delegated stake
+    pub bond: u64,
+    pub slashable_amount: u64,
+    pub delegated_amount: u64,
+    pub unbonding_amount: u64,
+    /// When the unbonding amount can be withdrawn
+    pub unbonds_at: Option<i64>,
+    pub reputation: u16,
+    pub active_tasks: u32,
+    pub delegator_share_bps: u16,
+}
+
+/// StakeAccount as written before versioning
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct StakeAccountV0 {
+    pub owner: Pubkey,
+    pub index: u32,
+    pub amount: u64,
+    pub staked_at: i64,
+    pub lock_duration: i64,
+    pub lock_until: i64,
+    pub multiplier: u16,
+    pub accumulated_rewards: u64,
+    pub last_claim_at: i64,
+    pub settled_rewards: u64,
+    pub receipt_mint: Option<Pubkey>,
+    pub auto_relock: bool,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl From<StakeAccountV0> for StakeAccount {
+    fn from(old: StakeAccountV0) -> Self {
+        Self {
+            version: STAKE_ACCOUNT_VERSION,
+            owner: old.owner,
+            index: old.index,
+            amount: old.amount,
+            staked_at: old.staked_at,
+            lock_duration: old.lock_duration,
+            lock_until: old.lock_until,
+            multiplier: old.multiplier,
+            accumulated_rewards: old.accumulated_rewards,
+            last_claim_at: old.last_claim_at,
+            settled_rewards: old.settled_rewards,
+            receipt_mint: old.receipt_mint,
+            auto_relock: old.auto_relock,
+            boost_robot: None,
+            reputation_boost_bps: 0,
+            event_seq: old.event_seq,
+            bump: old.bump,
+        }
+    }
+}
+
+/// StakeAccount version 1, before reputation boosts
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct StakeAccountV1 {
+    pub version: u8,
+    pub owner: Pubkey,
+    pub index: u32,
+    pub amount: u64,
+    pub staked_at: i64,
+    pub lock_duration: i64,
+    pub lock_until: i64,
+    pub multiplier: u16,
+    pub accumulated_rewards: u64,
+    pub last_claim_at: i64,
+    pub settled_rewards: u64,
+    pub receipt_mint: Option<Pubkey>,
+    pub auto_relock: bool,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl From<StakeAccountV1> for StakeAccount {
+    fn from(old: StakeAccountV1) -> Self {
+        Self {
+            version: STAKE_ACCOUNT_VERSION,
+            owner: old.owner,
+            index: old.index,
+            amount: old.amount,
+            staked_at: old.staked_at,
+            lock_duration: old.lock_duration,
+            lock_until: old.lock_until,
+            multiplier: old.multiplier,
+            accumulated_rewards: old.accumulated_rewards,
+            last_claim_at: old.last_claim_at,
+            settled_rewards: old.settled_rewards,
+            receipt_mint: old.receipt_mint,
+            auto_relock: old.auto_relock,
+            boost_robot: None,
+            reputation_boost_bps: 0,
+            event_seq: old.event_seq,
+            bump: old.bump,
+        }
+    }
+}
+
+/// OperatorStake as written before versioning
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct OperatorStakeV0 {
+    pub operator: Pubkey,
+    pub total_staked: u64,
+    pub slashable_amount: u64,
+    pub created_at: i64,
+    pub last_slash_at: Option<i64>,
+    pub reputation: u16,
+    pub last_slash_amount: u64,
+    pub last_slash_reputation_loss: u16,
+    pub reputation_recovered_at: i64,
+    pub active_tasks: u32,
+    pub unbonding_amount: u64,
+    pub unbonding_started_at: Option<i64>,
+    pub delegated_amount: u64,
+    pub delegation_shares: u64,
+    pub delegator_count: u32,
+    pub delegator_share_bps: u16,
+    pub income_per_share: u128,
+    pub last_slash_delegated: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl From<OperatorStakeV0> for OperatorStake {
+    fn from(old: OperatorStakeV0) -> Self {
+        Self {
+            version: OPERATOR_STAKE_VERSION,
+            operator: old.operator,
+            total_staked: old.total_staked,
+            slashable_amount: old.slashable_amount,
+            created_at: old.created_at,
+            last_slash_at: old.last_slash_at,
+            reputation: old.reputation,
+            last_slash_amount: old.last_slash_amount,
+            last_slash_reputation_loss: old.last_slash_reputation_loss,
+            reputation_recovered_at: old.reputation_recovered_at,
+            active_tasks: old.active_tasks,
+            unbonding_amount: old.unbonding_amount,
+            unbonding_started_at: old.unbonding_started_at,
+            delegated_amount: old.delegated_amount,
+            delegation_shares: old.delegation_shares,
+            delegator_count: old.delegator_count,
+            delegator_share_bps: old.delegator_share_bps,
+            income_per_share: old.income_per_share,
+            last_slash_delegated: old.last_slash_delegated,
+            slash_count: 0,
+            locked_task_bonds: 0,
+            event_seq: old.event_seq,
+            bump: old.bump,
+        }
+    }
+}
+
+/// OperatorStake version 1, before slash records
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct OperatorStakeV1 {
+    pub version: u8,
+    pub operator: Pubkey,
+    pub total_staked: u64,
+    pub slashable_amount: u64,
+    pub created_at: i64,
+    pub last_slash_at: Option<i64>,
+    pub reputation: u16,
+    pub last_slash_amount: u64,
+    pub last_slash_reputation_loss: u16,
+    pub reputation_recovered_at: i64,
+    pub active_tasks: u32,
+    pub unbonding_amount: u64,
+    pub unbonding_started_at: Option<i64>,
+    pub delegated_amount: u64,
+    pub delegation_shares: u64,
+    pub delegator_count: u32,
+    pub delegator_share_bps: u16,
+    pub income_per_share: u128,
+    pub last_slash_delegated: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl From<OperatorStakeV1> for OperatorStake {
+    fn from(old: OperatorStakeV1) -> Self {
+        Self {
+            version: OPERATOR_STAKE_VERSION,
+            operator: old.operator,
+            total_staked: old.total_staked,
+            slashable_amount: old.slashable_amount,
+            created_at: old.created_at,
+            last_slash_at: old.last_slash_at,
+            reputation: old.reputation,
+            last_slash_amount: old.last_slash_amount,
+            last_slash_reputation_loss: old.last_slash_reputation_loss,
+            reputation_recovered_at: old.reputation_recovered_at,
+            active_tasks: old.active_tasks,
+            unbonding_amount: old.unbonding_amount,
+            unbonding_started_at: old.unbonding_started_at,
+            delegated_amount: old.delegated_amount,
+            delegation_shares: old.delegation_shares,
+            delegator_count: old.delegator_count,
+            delegator_share_bps: old.delegator_share_bps,
+            income_per_share: old.income_per_share,
+            last_slash_delegated: old.last_slash_delegated,
+            slash_count: 0,
+            locked_task_bonds: 0,
+            event_seq: old.event_seq,
+            bump: old.bump,
+        }
+    }
+}
+
+/// OperatorStake version 2, before task performance bonds
+#[derive(AnchorDeserialize, InitSpace)]
+pub struct OperatorStakeV2 {
+    pub version: u8,
+    pub operator: Pubkey,
+    pub total_staked: u64,
+    pub slashable_amount: u64,
+    pub created_at: i64,
+    pub last_slash_at: Option<i64>,
+    pub reputation: u16,
+    pub last_slash_amount: u64,
+    pub last_slash_reputation_loss: u16,
+    pub reputation_recovered_at: i64,
+    pub active_tasks: u32,
+    pub unbonding_amount: u64,
+    pub unbonding_started_at: Option<i64>,
+    pub delegated_amount: u64,
+    pub delegation_shares: u64,
+    pub delegator_count: u32,
+    pub delegator_share_bps: u16,
+    pub income_per_share: u128,
+    pub last_slash_delegated: u64,
+    pub slash_count: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl From<OperatorStakeV2> for OperatorStake {
+    fn from(old: OperatorStakeV2) -> Self {
+        Self {
+            version: OPERATOR_STAKE_VERSION,
+            operator: old.operator,
+            total_staked: old.total_staked,
+            slashable_amount: old.slashable_amount,
+            created_at: old.created_at,
+            last_slash_at: old.last_slash_at,
+            reputation: old.reputation,
+            last_slash_amount: old.last_slash_amount,
+            last_slash_reputation_loss: old.last_slash_reputation_loss,
+            reputation_recovered_at: old.reputation_recovered_at,
+            active_tasks: old.active_tasks,
+            unbonding_amount: old.unbonding_amount,
+            unbonding_started_at: old.unbonding_started_at,
+            delegated_amount: old.delegated_amount,
+            delegation_shares: old.delegation_shares,
+            delegator_count: old.delegator_count,
+            delegator_share_bps: old.delegator_share_bps,
+            income_per_share: old.income_per_share,
+            last_slash_delegated: old.last_slash_delegated,
+            slash_count: old.slash_count,
+            locked_task_bonds: 0,
+            event_seq: old.event_seq,
+            bump: old.bump,
+        }
+    }
+}
+
+/// One slash of an operator, kept for auditing. Records for an operator are
+/// `["slash", operator, i]` for `i` in `0..slash_count`.
+#[account]
+#[derive(InitSpace)]
+pub struct SlashRecord {
+    pub operator: Pubkey,
+    pub index: u64,
+    pub amount: u64,
+    /// Part of `amount` taken from delegated stake
+    pub from_delegated: u64,
+    #[max_len(128)]
+    pub reason: String,
+    /// Task the slash was for, if the slasher gave one
+    pub task: Option<Pubkey>,
+    /// Program that slashed
+    pub slasher: Pubkey,
+    pub slashed_at: i64,
+    pub bump: u8,
+}
+
+/// Staking state at a point in time, for airdrops and retroactive rewards
+#[account]
+#[derive(InitSpace)]
+pub struct Snapshot {
+    pub id: u64,
+    /// Solana epoch and slot the snapshot was taken in
+    pub epoch: u64,
+    pub slot: u64,
+    pub taken_at: i64,
+    pub total_staked: u64,
+    /// Root of the per-wallet staked balance tree
+    pub merkle_root: [u8; 32],
+    pub holder_count: u32,
+    pub bump: u8,
+}
+
+/// A wallet that onboards stakers, and the bonuses it has earned for it
+#[account]
+#[derive(InitSpace)]
+pub struct Referrer {
+    pub owner: Pubkey,
+    /// Total stake opened naming this referrer
+    pub referred_volume: u64,
+    pub referral_count: u32,
+    pub total_bonus: u64,
+    /// Start of the current bonus-cap window
+    pub epoch_start: i64,
+    pub epoch_bonus: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+/// Stake a holder has delegated to an operator, as shares of the operator's
+/// delegated pool
+#[account]
+#[derive(InitSpace)]
+pub struct Delegation {
+    pub delegator: Pubkey,
+    pub operator: Pubkey,
+    pub shares: u64,
+    /// Income already accounted for at the operator's `income_per_share`
+    pub income_debt: u128,
+    pub unclaimed_income: u64,
+    pub unbonding_shares: u64,
+    pub unbonding_started_at: Option<i64>,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+impl Delegation {
+    /// Move income accrued since the last settlement into `unclaimed_income`
+    pub fn settle_income(&mut self, income_per_share: u128) {
+        let accrued = self.shares as u128 * income_per_share / INCOME_PRECISION;
+        self.unclaimed_income += (accrued - self.income_debt) as u64;
+        self.income_debt = accrued;
+    }
+}
+
+/// Collective bond posted by a swarm's leader, slashable when the swarm fails
+/// a group task. Tokens sit in a config-owned vault.
+#[account]
+#[derive(InitSpace)]
+pub struct SwarmStake {
+    /// swarm-coordinator's swarm account
+    pub swarm: Pubkey,
+    pub leader: Pubkey,
+    pub amount: u64,
+    pub total_slashed: u64,
+    pub created_at: i64,
+    pub last_slash_at: Option<i64>,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+/// Slash proceeds set aside to compensate task creators for robot failures.
+/// Tokens sit in `vault`, a config-owned token account.
+#[account]
+#[derive(InitSpace)]
+pub struct InsurancePool {
+    pub vault: Pubkey,
+    pub balance: u64,
+    pub total_received: u64,
+    pub total_paid: u64,
+    pub claim_count: u64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceClaim {
+    pub id: u64,
+    pub claimant: Pubkey,
+    pub task: Pubkey,
+    pub amount: u64,
+    #[max_len(128)]
+    pub reason: String,
+    pub status: ClaimStatus,
+    pub filed_at: i64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum ClaimStatus {
+    Pending,
+    Paid,
+    Denied,
+}
+
+/// An operator's appeal against a slash, argued as an oracle-verifier dispute
+#[account]
+#[derive(InitSpace)]
+pub struct SlashAppeal {
+    pub operator: Pubkey,
+    pub slashed_at: i64,
+    pub amount: u64,
+    /// Part of `amount` taken from delegated stake
+    pub delegated: u64,
+    pub reputation_lost: u16,
+    pub bond: u64,
+    pub status: AppealStatus,
+    pub opened_at: i64,
+    pub event_seq: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AppealStatus {
+    Open,
+    Upheld,
+    Rejected,
+}
+
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+#[event]
+pub struct InitialSupplyMinted {
+    pub header: EventHeader,
+    pub amount: u64,
+    pub treasury: Pubkey,
+}
+
+#[event]
+pub struct EmissionScheduleSet {
+    pub header: EventHeader,
+    pub start_time: i64,
+    pub epoch_duration: i64,
+    pub epochs: u32,
+}
+
+#[event]
+pub struct LockTiersSet {
+    pub header: EventHeader,
+    pub tiers: Vec<LockTier>,
+}
+
+#[event]
+pub struct RewardsVaultFunded {
+    pub header: EventHeader,
+    pub funder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct EpochRewardsDistributed {
+    pub header: EventHeader,
+    pub epoch: u64,
+    pub amount: u64,
+    pub cranker: Pubkey,
+    pub tip: u64,
+}
+
+#[event]
+pub struct TokensStaked {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub lock_days: u16,
+    pub multiplier: u16,
+    pub position: u32,
+    pub referrer: Option<Pubkey>,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub header: EventHeader,
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct SnapshotTaken {
+    pub header: EventHeader,
+    pub id: u64,
+    pub epoch: u64,
+    pub total_staked: u64,
+    pub merkle_root: [u8; 32],
+    pub holder_count: u32,
+}
+
+#[event]
+pub struct ReferralRewarded {
+    pub header: EventHeader,
+    pub referrer: Pubkey,
+    pub staker: Pubkey,
+    pub staked: u64,
+    pub bonus: u64,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+/// The rewards vault ran short of a claim; `owed - paid` stays claimable
+#[event]
+pub struct RewardsVaultShortfall {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub owed: u64,
+    pub paid: u64,
+}
+
+#[event]
+pub struct RewardsCompounded {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub staked_amount: u64,
+}
+
+#[event]
+pub struct AutoRelockEnabled {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub lock_days: u16,
+    pub lock_until: i64,
+}
+
+#[event]
+pub struct UnlockRequested {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub unlocks_at: i64,
+}
+
+#[event]
+pub struct LockExtended {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub lock_days: u16,
+    pub old_multiplier: u16,
+    pub multiplier: u16,
+    pub lock_until: i64,
+}
+
+#[event]
+pub struct VotingPowerUpdated {
+    pub header: EventHeader,
+    pub owner: Pubkey,
+    pub power: u64,
+    pub slope: u64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub header: EventHeader,
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub target_program: Pubkey,
+    pub voting_ends_at: i64,
+}
+
+#[event]
+pub struct ProposalVoteCast {
+    pub header: EventHeader,
+    pub id: u64,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub header: EventHeader,
+    pub id: u64,
+    pub passed: bool,
+    pub votes_for: u64,
+    pub votes_against: u64,
+}
+
+#[event]
+pub struct TokensUnstaked {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub rewards_claimed: u64,
+    pub receipt_burned: Option<Pubkey>,
+}
+
+#[event]
+pub struct UnstakeQueued {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub ticket: u64,
+    pub amount: u64,
+    pub rewards_claimed: u64,
+    /// Tokens queued ahead of this ticket
+    pub queued_ahead: u64,
+}
+
+#[event]
+pub struct ExitProcessed {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub ticket: u64,
+    pub amount: u64,
+    /// Base APY earned while queued
+    pub rewards: u64,
+}
+
+#[event]
+pub struct StakeReceiptMinted {
+    pub header: EventHeader,
+    pub owner: Pubkey,
+    pub receipt_mint: Pubkey,
+    pub amount: u64,
+    pub lock_until: i64,
+}
+
+#[event]
+pub struct OperatorStakeCreated {
+    pub header: EventHeader,
+    pub operator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TaskBondForfeited {
+    pub header: EventHeader,
+    pub operator: Pubkey,
+    pub task: Pubkey,
+    pub creator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OperatorSlashed {
+    pub header: EventHeader,
+    pub operator: Pubkey,
+    pub amount: u64,
+    pub reason: String,
+    pub new_reputation: u16,
+    pub to_insurance: u64,
+    /// Part of `amount` taken from delegated stake
+    pub from_delegated: u64,
+    pub task: Option<Pubkey>,
+    /// Index of the slash's `SlashRecord`
+    pub slash_index: u64,
+}
+
+#[event]
+pub struct SwarmStakeCreated {
+    pub header: EventHeader,
+    pub swarm: Pubkey,
+    pub leader: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct SwarmStakeSlashed {
+    pub header: EventHeader,
+    pub swarm: Pubkey,
+    pub amount: u64,
+    pub reason: String,
+    pub to_insurance: u64,
+}
+
+#[event]
+pub struct ReputationBoostUpdated {
+    pub header: EventHeader,
+    pub user: Pubkey,
+    pub robot: Pubkey,
+    pub reputation_score: u16,
+    pub old_boost_bps: u16,
+    pub boost_bps: u16,
+}
+
+#[event]
+pub struct StakeDelegated {
+    pub header: EventHeader,
+    pub delegator: Pubkey,
+    pub operator: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+}
+
+#[event]
+pub struct UndelegationRequested {
+    pub header: EventHeader,
+    pub delegator: Pubkey,
+    pub operator: Pubkey,
+    pub shares: u64,
+    pub unlocks_at: i64,
+}
+
+#[event]
+pub struct StakeUndelegated {
+    pub header: EventHeader,
+    pub delegator: Pubkey,
+    pub operator: Pubkey,
+    pub amount: u64,
+    pub remaining_shares: u64,
+}
+
+#[event]
+pub struct DelegationIncomeClaimed {
+    pub header: EventHeader,
+    pub delegator: Pubkey,
+    pub operator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OperatorIncomeShared {
+    pub header: EventHeader,
+    pub operator: Pubkey,
+    pub income: u64,
+    pub to_delegators: u64,
 }
 
-#[derive(Accounts)]
-pub struct Stake<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
-    pub config: Account<'info, TokenConfig>,
-    
-    #[account(
-        init,
-        payer = user,
-        space = 8 + StakeAccount::INIT_SPACE,
-        seeds = [b"stake", user.key().as_ref()],
-        bump
-    )]
-    pub stake_account: Account<'info, StakeAccount>,
-    
-    #[account(mut)]
-    pub stake_vault: Account<'info, TokenAccount>,
-    
-    #[account(mut, constraint = user_token.owner == user.key())]
-    pub user_token: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct OperatorReputationRecovered {
+    pub header: EventHeader,
+    pub operator: Pubkey,
+    pub days_clean: u32,
+    pub new_reputation: u16,
 }
 
-#[derive(Accounts)]
-pub struct ClaimRewards<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
-    pub config: Account<'info, TokenConfig>,
-    
-    #[account(
-        mut,
-        seeds = [b"stake", user.key().as_ref()],
-        bump = stake_account.bump,
-        constraint = stake_account.owner == user.key() @ ErrorCode::Unauthorized
-    )]
-    pub stake_account: Account<'info, StakeAccount>,
-    
-    #[account(mut)]
-    pub rewards_vault: Account<'info, TokenAccount>,
-    
-    #[account(mut, constraint = user_token.owner == user.key())]
-    pub user_token: Account<'info, TokenAccount>,
-    
-    pub user: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct ParametersQueued {
+    pub header: EventHeader,
+    pub base_apy_bps: u16,
+    pub min_stake: u64,
+    pub min_operator_stake: u64,
+    pub effective_at: i64,
 }
 
-#[derive(Accounts)]
-pub struct Unstake<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
-    pub config: Account<'info, TokenConfig>,
-    
-    #[account(
-        mut,
-        seeds = [b"stake", user.key().as_ref()],
-        bump = stake_account.bump,
-        constraint = stake_account.owner == user.key() @ ErrorCode::Unauthorized
-    )]
-    pub stake_account: Account<'info, StakeAccount>,
-    
-    #[account(mut)]
-    pub stake_vault: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub rewards_vault: Account<'info, TokenAccount>,
-    
-    #[account(mut, constraint = user_token.owner == user.key())]
-    pub user_token: Account<'info, TokenAccount>,
-    
-    pub user: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct ParametersApplied {
+    pub header: EventHeader,
+    pub base_apy_bps: u16,
+    pub min_stake: u64,
+    pub min_operator_stake: u64,
 }
 
-#[derive(Accounts)]
-pub struct CreateOperatorStake<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
-    pub config: Account<'info, TokenConfig>,
-    
-    #[account(
-        init,
-        payer = operator,
-        space = 8 + OperatorStake::INIT_SPACE,
-        seeds = [b"operator", operator.key().as_ref()],
-        bump
-    )]
-    pub operator_stake: Account<'info, OperatorStake>,
-    
-    #[account(mut)]
-    pub operator_vault: Account<'info, TokenAccount>,
-    
-    #[account(mut, constraint = operator_token.owner == operator.key())]
-    pub operator_token: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub operator: Signer<'info>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct FeesDeposited {
+    pub header: EventHeader,
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub burned: u64,
+    pub total_burned: u64,
 }
 
-#[derive(Accounts)]
-pub struct SlashOperator<'info> {
-    #[account(mut, seeds = [b"config"], bump = config.bump)]
-    pub config: Account<'info, TokenConfig>,
-    
-    #[account(mut)]
-    pub operator_stake: Account<'info, OperatorStake>,
-    
-    #[account(mut)]
-    pub operator_vault: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub treasury: Account<'info, TokenAccount>,
-    
-    /// CHECK: Verified by CPI from task_market
-    pub authority: AccountInfo<'info>,
-    
-    pub token_program: Program<'info, Token>,
+#[event]
+pub struct TreasuryBurned {
+    pub header: EventHeader,
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub total_burned: u64,
 }
 
-#[derive(Accounts)]
-pub struct ViewStake<'info> {
-    pub stake_account: Account<'info, StakeAccount>,
+#[event]
+pub struct BuybackLimitSet {
+    pub header: EventHeader,
+    pub fee_mint: Pubkey,
+    pub epoch_limit: u64,
 }
 
-// ============================================================================
-// STATE
-// ============================================================================
+#[event]
+pub struct BuybackBurned {
+    pub header: EventHeader,
+    pub fee_mint: Pubkey,
+    pub swap_program: Pubkey,
+    /// Fee mint sold
+    pub spent: u64,
+    pub burned: u64,
+    pub total_burned: u64,
+}
 
-#[account]
-#[derive(InitSpace)]
-pub struct TokenConfig {
-    pub authority: Pubkey,
-    pub mint: Pubkey,
-    pub total_staked: u64,
-    pub total_rewards_distributed: u64,
-    pub stake_count: u64,
-    pub bump: u8,
-    pub mint_bump: u8,
+#[event]
+pub struct TransferFeeUpdated {
+    pub header: EventHeader,
+    pub basis_points: u16,
+    pub maximum_fee: u64,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct StakeAccount {
-    pub owner: Pubkey,
+#[event]
+pub struct TransferFeesWithdrawn {
+    pub header: EventHeader,
     pub amount: u64,
-    pub staked_at: i64,
-    pub lock_duration: i64,
-    pub lock_until: i64,
-    pub multiplier: u16,
-    pub accumulated_rewards: u64,
-    pub last_claim_at: i64,
-    pub bump: u8,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct OperatorStake {
-    pub operator: Pubkey,
-    pub total_staked: u64,
-    pub slashable_amount: u64,
-    pub created_at: i64,
-    pub last_slash_at: Option<i64>,
-    pub reputation: u16,
-    pub bump: u8,
+#[event]
+pub struct InsuranceClaimFiled {
+    pub header: EventHeader,
+    pub claimant: Pubkey,
+    pub task: Pubkey,
+    pub amount: u64,
 }
 
-// ============================================================================
-// EVENTS
-// ============================================================================
+#[event]
+pub struct InsuranceClaimSettled {
+    pub header: EventHeader,
+    pub claimant: Pubkey,
+    pub approved: bool,
+    pub paid: u64,
+}
 
 #[event]
-pub struct InitialSupplyMinted {
+pub struct InsurancePremiumPaid {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub payer: Pubkey,
     pub amount: u64,
-    pub treasury: Pubkey,
 }
 
 #[event]
-pub struct TokensStaked {
-    pub user: Pubkey,
+pub struct TaskInsurancePaid {
+    pub header: EventHeader,
+    pub task: Pubkey,
+    pub creator: Pubkey,
     pub amount: u64,
-    pub lock_days: u16,
-    pub multiplier: u16,
 }
 
+/// Lets bid eligibility checks see an operator's restored stake
 #[event]
-pub struct RewardsClaimed {
-    pub user: Pubkey,
+pub struct OperatorStakeToppedUp {
+    pub header: EventHeader,
+    pub operator: Pubkey,
     pub amount: u64,
+    pub total_staked: u64,
+    pub slashable_amount: u64,
 }
 
 #[event]
-pub struct TokensUnstaked {
-    pub user: Pubkey,
+pub struct SlashAppealed {
+    pub header: EventHeader,
+    pub operator: Pubkey,
     pub amount: u64,
-    pub rewards_claimed: u64,
+    pub bond: u64,
 }
 
 #[event]
-pub struct OperatorStakeCreated {
+pub struct SlashAppealSettled {
+    pub header: EventHeader,
+    pub operator: Pubkey,
+    pub upheld: bool,
+    pub refunded: u64,
+    pub new_reputation: u16,
+}
+
+#[event]
+pub struct OperatorUnstakeRequested {
+    pub header: EventHeader,
     pub operator: Pubkey,
     pub amount: u64,
+    pub unlocks_at: i64,
 }
 
 #[event]
-pub struct OperatorSlashed {
+pub struct OperatorUnstaked {
+    pub header: EventHeader,
     pub operator: Pubkey,
     pub amount: u64,
-    pub reason: String,
-    pub new_reputation: u16,
+    pub remaining: u64,
 }
 
 // ============================================================================
@@ -668,4 +6259,247 @@ pub enum ErrorCode {
     
     #[msg("Arithmetic overflow")]
     Overflow,
+    
+    #[msg("Invalid emission schedule")]
+    InvalidEmissionSchedule,
+    
+    #[msg("Too many emission epochs")]
+    TooManyEpochs,
+    
+    #[msg("This epoch's reward budget is used up")]
+    EmissionBudgetExhausted,
+    
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    
+    #[msg("Vault is not owned by the token config")]
+    InvalidVault,
+    
+    #[msg("Stake position listed twice")]
+    DuplicatePosition,
+    
+    #[msg("All open stake positions must be provided")]
+    MissingStakePositions,
+    
+    #[msg("Not enough veDRONEOS voting power")]
+    InsufficientVotingPower,
+    
+    #[msg("Invalid proposal instruction")]
+    InvalidProposal,
+    
+    #[msg("Proposal is not active")]
+    ProposalNotActive,
+    
+    #[msg("Voting on this proposal has closed")]
+    VotingClosed,
+    
+    #[msg("Voting period has not ended")]
+    VotingNotEnded,
+    
+    #[msg("An unstake is already unbonding")]
+    UnbondingInProgress,
+    
+    #[msg("No unstake has been requested")]
+    NoUnbondingRequest,
+    
+    #[msg("Unbonding period has not ended")]
+    UnbondingNotComplete,
+    
+    #[msg("Operator still has active tasks")]
+    OperatorHasActiveTasks,
+    
+    #[msg("No slash to appeal")]
+    NothingToAppeal,
+    
+    #[msg("Slash appeal window has closed")]
+    AppealWindowClosed,
+    
+    #[msg("Appeal is not open")]
+    AppealNotOpen,
+    
+    #[msg("Invalid insurance share")]
+    InvalidInsuranceShare,
+    
+    #[msg("Claim must reference a task-market task")]
+    InvalidClaim,
+    
+    #[msg("Claim is not pending")]
+    ClaimNotPending,
+    
+    #[msg("Insurance pool can't cover this claim")]
+    InsufficientInsurance,
+    
+    #[msg("No reputation to recover yet")]
+    NothingToRecover,
+    
+    #[msg("Invalid reputation recovery settings")]
+    InvalidReputationRecovery,
+    
+    #[msg("Program is not a registered slasher")]
+    SlasherNotRegistered,
+    
+    #[msg("Program is already a registered slasher")]
+    SlasherAlreadyRegistered,
+    
+    #[msg("Too many slasher programs")]
+    TooManySlashers,
+    
+    #[msg("Staking parameters out of range")]
+    InvalidParameters,
+    
+    #[msg("No parameter change queued")]
+    NoPendingParameters,
+    
+    #[msg("Parameter timelock has not passed")]
+    ParametersNotReady,
+    
+    #[msg("Invalid burn share")]
+    InvalidBurnShare,
+    
+    #[msg("Token metadata too long")]
+    InvalidMetadata,
+    
+    #[msg("Invalid transfer fee")]
+    InvalidTransferFee,
+    
+    #[msg("Invalid delegator share")]
+    InvalidDelegatorShare,
+    
+    #[msg("Undelegation already in progress")]
+    UndelegationInProgress,
+    
+    #[msg("No undelegation requested")]
+    NoUndelegationRequest,
+    
+    #[msg("Operator has no delegators")]
+    NoDelegators,
+    
+    #[msg("Invalid referrer")]
+    InvalidReferrer,
+    
+    #[msg("Referral accounts missing")]
+    MissingReferralAccounts,
+    
+    #[msg("Stake receipt already minted")]
+    ReceiptAlreadyMinted,
+    
+    #[msg("Caller does not hold the stake receipt")]
+    NotReceiptHolder,
+    
+    #[msg("Positions with a receipt must be unstaked in full")]
+    ReceiptRequiresFullUnstake,
+    
+    #[msg("Emission epoch has not started")]
+    EpochNotStarted,
+    
+    #[msg("Treasury balance too low")]
+    InsufficientTreasury,
+    
+    #[msg("Rewards vault is empty")]
+    InsufficientRewardsVault,
+    
+    #[msg("Position has auto re-lock enabled")]
+    AutoRelockActive,
+    
+    #[msg("Position does not have auto re-lock enabled")]
+    AutoRelockNotEnabled,
+    
+    #[msg("Not a stake account with a known layout version")]
+    InvalidAccountVersion,
+    
+    #[msg("Account already uses the current layout")]
+    AccountUpToDate,
+    
+    #[msg("Swap program is not whitelisted")]
+    SwapProgramNotAllowed,
+    
+    #[msg("Swap program is already whitelisted")]
+    SwapProgramAlreadyAllowed,
+    
+    #[msg("Too many swap programs")]
+    TooManySwapPrograms,
+    
+    #[msg("Buyback swap outside slippage bounds")]
+    BuybackSlippage,
+    
+    #[msg("Buyback would exceed this epoch's limit")]
+    BuybackLimitExceeded,
+    
+    #[msg("Robot is not operated by the staker")]
+    RobotNotOperatedByStaker,
+    
+    #[msg("Withdrawals this large must go through the exit queue")]
+    ExitQueueRequired,
+    
+    #[msg("Withdrawal is below the exit queue threshold")]
+    BelowExitQueueThreshold,
+    
+    #[msg("Exit queue has paid out its cap for this epoch")]
+    ExitEpochCapReached,
+    
+    #[msg("Lock tiers must be 1-8 tiers in increasing order of days, each at least 1x")]
+    InvalidLockTiers,
+    
+    #[msg("Operator's free slashable stake can't cover the task bond")]
+    InsufficientTaskBond,
+    
+    #[msg("Claims need a task that failed through the robot's fault")]
+    TaskNotRobotFault,
+    
+    #[msg("Staking vaults are already registered")]
+    StakingVaultsAlreadySet,
+    
+    #[msg("Appeal vault is already registered")]
+    AppealVaultAlreadySet,
+    
+    #[msg("Referred stakes must be locked")]
+    ReferralRequiresLock,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(amount: u64) -> StakeAccount {
+        StakeAccount {
+            version: STAKE_ACCOUNT_VERSION,
+            owner: Pubkey::new_unique(),
+            index: 0,
+            amount,
+            staked_at: 0,
+            lock_duration: 0,
+            lock_until: 0,
+            multiplier: 10_000,
+            accumulated_rewards: 0,
+            last_claim_at: 0,
+            settled_rewards: 0,
+            receipt_mint: None,
+            auto_relock: false,
+            boost_robot: None,
+            reputation_boost_bps: 0,
+            event_seq: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn fee_tiers_start_at_each_threshold() {
+        let cases = [
+            (0, 0, 0),
+            (10_000 * 1_000_000 - 1, 0, 0),
+            (10_000 * 1_000_000, 1, 5_000),
+            (100_000 * 1_000_000 - 1, 1, 5_000),
+            (100_000 * 1_000_000, 2, 7_500),
+            (u64::MAX, 2, 7_500),
+        ];
+        for (amount, tier, discount_bps) in cases {
+            assert_eq!(fee_tier(&position(amount)), tier, "tier of {amount}");
+            assert_eq!(fee_tier_discount(tier), discount_bps, "discount of tier {tier}");
+        }
+    }
+
+    #[test]
+    fn fee_tiers_past_the_table_keep_the_top_discount() {
+        assert_eq!(fee_tier_discount(FEE_TIERS.len() as u8 + 1), FEE_TIERS[FEE_TIERS.len() - 1].1);
+    }
 }
@@ -46,6 +46,17 @@ export class IdentityClient {
     return { publicKey, bump };
   }
 
+  /**
+   * Derive an operator's fleet PDA, which counts their registered robots
+   */
+  getFleetPDA(operator: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('fleet'), operator.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
   /**
    * Derive robot PDA from device ID
    */
@@ -79,6 +90,7 @@ export class IdentityClient {
       keys: [
         { pubkey: registryPDA.publicKey, isSigner: false, isWritable: true },
         { pubkey: robotPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getFleetPDA(operator.publicKey).publicKey, isSigner: false, isWritable: true },
         { pubkey: operator.publicKey, isSigner: true, isWritable: true },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       ],
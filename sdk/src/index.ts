@@ -7,6 +7,7 @@ export const PROGRAM_IDS = {
   PAYMENT_STREAMS: new PublicKey('DOS4pay1111111111111111111111111111111111111'),
   TASK_MARKET: new PublicKey('DOS4mkt1111111111111111111111111111111111111'),
   DRONEOS_TOKEN: new PublicKey('DOS4tkn1111111111111111111111111111111111111'),
+  SWARM_COORDINATOR: new PublicKey('DOS4swm1111111111111111111111111111111111111'),
 };
 
 // Re-export modules
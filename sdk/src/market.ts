@@ -1,4 +1,6 @@
 import { Connection, PublicKey, Keypair, Transaction, SystemProgram } from '@solana/web3.js';
+import { TOKEN_2022_PROGRAM_ID, getAssociatedTokenAddressSync } from '@solana/spl-token';
+import { createHash } from 'crypto';
 import { PROGRAM_IDS } from './index';
 import {
   TaskAccount,
@@ -9,10 +11,22 @@ import {
   Capability,
   CreateTaskParams,
   SubmitBidParams,
+  UpdateTaskParams,
+  TaskIndexKind,
+  MilestoneTerms,
   TransactionResult,
   PDAResult,
 } from './types';
 
+/**
+ * Sealed-bid commitment: sha256(rate as u64 LE || salt)
+ */
+export function bidCommitment(proposedRate: bigint, salt: Uint8Array): Buffer {
+  const rate = Buffer.alloc(8);
+  rate.writeBigUInt64LE(proposedRate);
+  return createHash('sha256').update(rate).update(salt).digest();
+}
+
 /**
  * Task Market Client
  * 
@@ -57,6 +71,30 @@ export class MarketClient {
     return { publicKey, bump };
   }
 
+  getRecurringTaskPDA(creator: PublicKey, index: number): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('recurring'),
+        creator.toBuffer(),
+        Buffer.from(new BigUint64Array([BigInt(index)]).buffer),
+      ],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getTaskTemplatePDA(publisher: PublicKey, templateId: number): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('task-template'),
+        publisher.toBuffer(),
+        Buffer.from(new BigUint64Array([BigInt(templateId)]).buffer),
+      ],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
   getBidPDA(task: PublicKey, robot: PublicKey): PDAResult {
     const [publicKey, bump] = PublicKey.findProgramAddressSync(
       [Buffer.from('bid'), task.toBuffer(), robot.toBuffer()],
@@ -65,34 +103,1230 @@ export class MarketClient {
     return { publicKey, bump };
   }
 
-  // ============================================================================
-  // TASK OPERATIONS
-  // ============================================================================
+  getBlacklistPDA(creator: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('blacklist'), creator.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  /**
+   * Get the discovery index PDA for a robot class or capability
+   */
+  getTaskIndexPDA(kind: TaskIndexKind, key: RobotClass | Capability): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('task-index'), Buffer.from([kind]), Buffer.from([key])],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  /**
+   * Get the PDA of a task index page
+   */
+  getTaskIndexPagePDA(index: PublicKey, page: number): PDAResult {
+    const pageBuffer = Buffer.alloc(4);
+    pageBuffer.writeUInt32LE(page);
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('task-index-page'), index.toBuffer(), pageBuffer],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  // ============================================================================
+  // TASK OPERATIONS
+  // ============================================================================
+
+  /**
+   * Create a new task. Tasks needing more than one robot (`requiredRobots`)
+   * are run by a swarm-coordinator group task created alongside.
+   */
+  async createTask(
+    params: CreateTaskParams,
+    creator: Keypair,
+    requiredRobots: number = 1,
+    insuredAmount: bigint = BigInt(0)
+  ): Promise<{ result: TransactionResult; taskPubkey: PublicKey }> {
+    const marketPDA = this.getMarketPDA();
+    
+    // Get current task count for PDA derivation
+    const marketAccount = await this.connection.getAccountInfo(marketPDA.publicKey);
+    const taskIndex = marketAccount ? this.decodeTaskCount(marketAccount.data) : 0;
+    
+    const taskPDA = this.getTaskPDA(creator.publicKey, taskIndex);
+
+    const insured = Buffer.alloc(8);
+    insured.writeBigUInt64LE(insuredAmount);
+    const data = Buffer.concat([this.encodeCreateTask(params), Buffer.from([requiredRobots]), insured]);
+
+    // Optional swarm-coordinator accounts; absent ones are passed as this program
+    let swarmKeys = [this.programId, this.programId, this.programId, this.programId];
+    if (requiredRobots > 1) {
+      const swarmProgram = PROGRAM_IDS.SWARM_COORDINATOR;
+      const [coordinator] = PublicKey.findProgramAddressSync([Buffer.from('coordinator')], swarmProgram);
+      const coordinatorAccount = await this.connection.getAccountInfo(coordinator);
+      const groupTaskIndex = Buffer.alloc(8);
+      // Coordinator: discriminator, authority, total_swarms, total_group_tasks
+      groupTaskIndex.writeBigUInt64LE(
+        coordinatorAccount ? coordinatorAccount.data.readBigUInt64LE(48) : BigInt(0)
+      );
+      const [groupTask] = PublicKey.findProgramAddressSync(
+        [Buffer.from('group-task'), creator.publicKey.toBuffer(), groupTaskIndex],
+        swarmProgram
+      );
+      const [eventAuthority] = PublicKey.findProgramAddressSync(
+        [Buffer.from('__event_authority')],
+        swarmProgram
+      );
+      swarmKeys = [coordinator, groupTask, eventAuthority, swarmProgram];
+    }
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: marketPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: taskPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: swarmKeys[0], isSigner: false, isWritable: requiredRobots > 1 },
+        { pubkey: swarmKeys[1], isSigner: false, isWritable: requiredRobots > 1 },
+        { pubkey: swarmKeys[2], isSigner: false, isWritable: false },
+        { pubkey: swarmKeys[3], isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      
+      return { 
+        result: { signature, success: true },
+        taskPubkey: taskPDA.publicKey,
+      };
+    } catch (error) {
+      return { 
+        result: { signature: '', success: false, error: (error as Error).message },
+        taskPubkey: taskPDA.publicKey,
+      };
+    }
+  }
+
+  /**
+   * droneos_token accounts for paying into or out of its insurance pool:
+   * config, pool, vault, the creator's DRONEOS account, mint, token program,
+   * event authority and the token program itself
+   */
+  private async insuranceKeys(creator: PublicKey) {
+    const tokenProgram = PROGRAM_IDS.DRONEOS_TOKEN;
+    const pda = (seed: string) => PublicKey.findProgramAddressSync([Buffer.from(seed)], tokenProgram)[0];
+    const pool = pda('insurance');
+    const mint = pda('mint');
+    // InsurancePool: discriminator, vault
+    const poolAccount = await this.connection.getAccountInfo(pool);
+    const vault = poolAccount ? new PublicKey(poolAccount.data.subarray(8, 40)) : PublicKey.default;
+
+    return [
+      { pubkey: pda('config'), isSigner: false, isWritable: false },
+      { pubkey: pool, isSigner: false, isWritable: true },
+      { pubkey: vault, isSigner: false, isWritable: true },
+      {
+        pubkey: getAssociatedTokenAddressSync(mint, creator, false, TOKEN_2022_PROGRAM_ID),
+        isSigner: false,
+        isWritable: true,
+      },
+      { pubkey: mint, isSigner: false, isWritable: false },
+      { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: pda('__event_authority'), isSigner: false, isWritable: false },
+      { pubkey: tokenProgram, isSigner: false, isWritable: false },
+    ];
+  }
+
+  /**
+   * Pay an insured task's creator from the insurance pool after the task
+   * failed through its robot's fault (anyone can call)
+   */
+  async claimTaskInsurance(
+    taskPubkey: PublicKey,
+    creator: PublicKey,
+    payer: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffd4444'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getMarketPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        {
+          pubkey: PublicKey.findProgramAddressSync(
+            [Buffer.from('task_stream'), taskPubkey.toBuffer()],
+            PROGRAM_IDS.PAYMENT_STREAMS
+          )[0],
+          isSigner: false,
+          isWritable: false,
+        },
+        ...(await this.insuranceKeys(creator)),
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Publish a recurring task: `occurrences` tasks opened from `params`, the
+   * first due at `firstDueAt` (unix seconds) and then every `interval`
+   * seconds. Rent for all occurrences is prepaid by the creator.
+   */
+  async createRecurringTask(
+    params: CreateTaskParams,
+    interval: number,
+    occurrences: number,
+    firstDueAt: number,
+    creator: Keypair
+  ): Promise<{ result: TransactionResult; recurringPubkey: PublicKey }> {
+    const marketPDA = this.getMarketPDA();
+    const marketAccount = await this.connection.getAccountInfo(marketPDA.publicKey);
+    const index = marketAccount ? this.decodeRecurringCount(marketAccount.data) : 0;
+    const recurringPDA = this.getRecurringTaskPDA(creator.publicKey, index);
+
+    const schedule = Buffer.alloc(8 + 4 + 8);
+    schedule.writeBigInt64LE(BigInt(interval), 0);
+    schedule.writeUInt32LE(occurrences, 8);
+    schedule.writeBigInt64LE(BigInt(firstDueAt), 12);
+    const data = Buffer.concat([
+      this.encodeCreateTask(params, BigInt('0xffffffffffff9999')),
+      schedule,
+    ]);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: marketPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: recurringPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { result: { signature, success: true }, recurringPubkey: recurringPDA.publicKey };
+    } catch (error) {
+      return {
+        result: { signature: '', success: false, error: (error as Error).message },
+        recurringPubkey: recurringPDA.publicKey,
+      };
+    }
+  }
+
+  /**
+   * Open the next due task of a recurring task (anyone can call; the task
+   * rent is refunded from the recurring task)
+   */
+  async spawnOccurrence(
+    recurringPubkey: PublicKey,
+    creator: PublicKey,
+    payer: Keypair
+  ): Promise<{ result: TransactionResult; taskPubkey: PublicKey }> {
+    const marketPDA = this.getMarketPDA();
+    const marketAccount = await this.connection.getAccountInfo(marketPDA.publicKey);
+    const taskIndex = marketAccount ? this.decodeTaskCount(marketAccount.data) : 0;
+    const taskPDA = this.getTaskPDA(creator, taskIndex);
+
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffeaaaa'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: marketPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: recurringPubkey, isSigner: false, isWritable: true },
+        { pubkey: taskPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { result: { signature, success: true }, taskPubkey: taskPDA.publicKey };
+    } catch (error) {
+      return {
+        result: { signature: '', success: false, error: (error as Error).message },
+        taskPubkey: taskPDA.publicKey,
+      };
+    }
+  }
+
+  /**
+   * Publish task parameters once, for any creator to open tasks from with
+   * `createTaskFromTemplate`
+   */
+  async createTaskTemplate(
+    templateId: number,
+    params: CreateTaskParams,
+    publisher: Keypair
+  ): Promise<{ result: TransactionResult; templatePubkey: PublicKey }> {
+    const templatePDA = this.getTaskTemplatePDA(publisher.publicKey, templateId);
+
+    const header = Buffer.alloc(16);
+    header.writeBigUInt64LE(BigInt('0xfffffffffffeeeee'), 0);
+    header.writeBigUInt64LE(BigInt(templateId), 8);
+    // Params follow the id, encoded as for createTask minus its discriminator
+    const data = Buffer.concat([header, this.encodeCreateTask(params).subarray(8)]);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: templatePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: publisher.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [publisher]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { result: { signature, success: true }, templatePubkey: templatePDA.publicKey };
+    } catch (error) {
+      return {
+        result: { signature: '', success: false, error: (error as Error).message },
+        templatePubkey: templatePDA.publicKey,
+      };
+    }
+  }
+
+  /**
+   * Open a task with a template's parameters
+   */
+  async createTaskFromTemplate(
+    templatePubkey: PublicKey,
+    creator: Keypair
+  ): Promise<{ result: TransactionResult; taskPubkey: PublicKey }> {
+    const marketPDA = this.getMarketPDA();
+    const marketAccount = await this.connection.getAccountInfo(marketPDA.publicKey);
+    const taskIndex = marketAccount ? this.decodeTaskCount(marketAccount.data) : 0;
+    const taskPDA = this.getTaskPDA(creator.publicKey, taskIndex);
+
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffe1111'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: marketPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: templatePubkey, isSigner: false, isWritable: true },
+        { pubkey: taskPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { result: { signature, success: true }, taskPubkey: taskPDA.publicKey };
+    } catch (error) {
+      return {
+        result: { signature: '', success: false, error: (error as Error).message },
+        taskPubkey: taskPDA.publicKey,
+      };
+    }
+  }
+
+  /**
+   * Submit a bid on a task
+   */
+  async submitBid(
+    taskPubkey: PublicKey,
+    robotPubkey: PublicKey,
+    params: SubmitBidParams,
+    operator: Keypair
+  ): Promise<{ result: TransactionResult; bidPubkey: PublicKey }> {
+    const bidPDA = this.getBidPDA(taskPubkey, robotPubkey);
+
+    const messageBytes = Buffer.from(params.message || '');
+    const proof = params.allowlistProof ?? [];
+    const data = Buffer.alloc(8 + 8 + 4 + 4 + messageBytes.length + 8 + 4 + 32 * proof.length);
+    
+    let offset = 0;
+    data.writeBigUInt64LE(BigInt('0xaaaaaaaaaaaaaaaa'), offset); // discriminator
+    offset += 8;
+    data.writeBigUInt64LE(params.proposedRate, offset);
+    offset += 8;
+    data.writeUInt32LE(params.estimatedDuration, offset);
+    offset += 4;
+    data.writeUInt32LE(messageBytes.length, offset);
+    offset += 4;
+    messageBytes.copy(data, offset);
+    offset += messageBytes.length;
+    data.writeBigInt64LE(BigInt(params.validFor ?? 86400), offset);
+    offset += 8;
+    data.writeUInt32LE(proof.length, offset);
+    offset += 4;
+    for (const node of proof) {
+      node.copy(data, offset, 0, 32);
+      offset += 32;
+    }
+
+    const instruction = {
+      programId: this.programId,
+      keys: await this.bidKeys(taskPubkey, robotPubkey, operator),
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      
+      return { 
+        result: { signature, success: true },
+        bidPubkey: bidPDA.publicKey,
+      };
+    } catch (error) {
+      return { 
+        result: { signature: '', success: false, error: (error as Error).message },
+        bidPubkey: bidPDA.publicKey,
+      };
+    }
+  }
+
+  /**
+   * Commit a sealed bid on a task during its commit window. Keep the salt to
+   * reveal the rate with `revealBid`.
+   */
+  async commitBid(
+    taskPubkey: PublicKey,
+    robotPubkey: PublicKey,
+    params: SubmitBidParams,
+    salt: Uint8Array,
+    operator: Keypair
+  ): Promise<{ result: TransactionResult; bidPubkey: PublicKey }> {
+    const bidPDA = this.getBidPDA(taskPubkey, robotPubkey);
+
+    const messageBytes = Buffer.from(params.message || '');
+    const proof = params.allowlistProof ?? [];
+    const data = Buffer.alloc(8 + 32 + 4 + 4 + messageBytes.length + 8 + 4 + 32 * proof.length);
+
+    let offset = 0;
+    data.writeBigUInt64LE(BigInt('0xffffffffffff7777'), offset); // discriminator
+    offset += 8;
+    bidCommitment(params.proposedRate, salt).copy(data, offset);
+    offset += 32;
+    data.writeUInt32LE(params.estimatedDuration, offset);
+    offset += 4;
+    data.writeUInt32LE(messageBytes.length, offset);
+    offset += 4;
+    messageBytes.copy(data, offset);
+    offset += messageBytes.length;
+    data.writeBigInt64LE(BigInt(params.validFor ?? 86400), offset);
+    offset += 8;
+    data.writeUInt32LE(proof.length, offset);
+    offset += 4;
+    for (const node of proof) {
+      node.copy(data, offset, 0, 32);
+      offset += 32;
+    }
+
+    const instruction = {
+      programId: this.programId,
+      keys: await this.bidKeys(taskPubkey, robotPubkey, operator),
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { result: { signature, success: true }, bidPubkey: bidPDA.publicKey };
+    } catch (error) {
+      return {
+        result: { signature: '', success: false, error: (error as Error).message },
+        bidPubkey: bidPDA.publicKey,
+      };
+    }
+  }
+
+  /**
+   * Reveal a committed bid during the task's reveal window
+   */
+  async revealBid(
+    taskPubkey: PublicKey,
+    bidPubkey: PublicKey,
+    proposedRate: bigint,
+    salt: Uint8Array,
+    operator: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8 + 8 + 32);
+    data.writeBigUInt64LE(BigInt('0xffffffffffff8888'), 0);
+    data.writeBigUInt64LE(proposedRate, 8);
+    Buffer.from(salt).copy(data, 16);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: false },
+        { pubkey: bidPubkey, isSigner: false, isWritable: true },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Accounts for submit_bid and commit_bid
+   */
+  private async bidKeys(taskPubkey: PublicKey, robotPubkey: PublicKey, operator: Keypair) {
+    const creator = (await this.getTask(taskPubkey))?.creator ?? PublicKey.default;
+    return [
+      { pubkey: taskPubkey, isSigner: false, isWritable: true },
+      { pubkey: this.getBidPDA(taskPubkey, robotPubkey).publicKey, isSigner: false, isWritable: true },
+      { pubkey: robotPubkey, isSigner: false, isWritable: false },
+      { pubkey: operator.publicKey, isSigner: true, isWritable: true },
+      { pubkey: this.getMarketPDA().publicKey, isSigner: false, isWritable: false },
+      {
+        pubkey: PublicKey.findProgramAddressSync(
+          [Buffer.from('operator'), operator.publicKey.toBuffer()],
+          PROGRAM_IDS.DRONEOS_TOKEN
+        )[0],
+        isSigner: false,
+        isWritable: false,
+      },
+      {
+        pubkey: PublicKey.findProgramAddressSync(
+          [Buffer.from('fleet'), operator.publicKey.toBuffer()],
+          PROGRAM_IDS.IDENTITY_REGISTRY
+        )[0],
+        isSigner: false,
+        isWritable: false,
+      },
+      { pubkey: this.getBlacklistPDA(creator).publicKey, isSigner: false, isWritable: false },
+      { pubkey: PROGRAM_IDS.IDENTITY_REGISTRY, isSigner: false, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ];
+  }
+
+  /**
+   * Accept a bid, rejecting and closing `losingBids` (other bids on the task,
+   * with their operators) in the same instruction
+   */
+  async acceptBid(
+    taskPubkey: PublicKey,
+    bidPubkey: PublicKey,
+    creator: Keypair,
+    losingBids: { bid: PublicKey; operator: PublicKey }[] = []
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8 + 1);
+    data.writeBigUInt64LE(BigInt('0xbbbbbbbbbbbbbbbb'), 0);
+    data.writeUInt8(losingBids.length, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: bidPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
+        ...this.bidPairKeys(losingBids),
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Reject and close the bids left on a task that is no longer open,
+   * returning their rent and bonds to their operators (permissionless)
+   */
+  async rejectRemainingBids(
+    taskPubkey: PublicKey,
+    bids: { bid: PublicKey; operator: PublicKey }[],
+    payer: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffd6666'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        ...this.bidPairKeys(bids),
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * `(bid, operator)` account pairs for instructions closing bids
+   */
+  private bidPairKeys(bids: { bid: PublicKey; operator: PublicKey }[]) {
+    return bids.flatMap(({ bid, operator }) => [
+      { pubkey: bid, isSigner: false, isWritable: true },
+      { pubkey: operator, isSigner: false, isWritable: true },
+    ]);
+  }
+
+  /**
+   * Start task execution
+   */
+  async startTask(
+    taskPubkey: PublicKey,
+    robotPubkey: PublicKey,
+    operator: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xcccccccccccccccc'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: robotPubkey, isSigner: false, isWritable: false },
+        { pubkey: this.getBidPDA(taskPubkey, robotPubkey).publicKey, isSigner: false, isWritable: true },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: true },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Forfeit an accepted bid's bond to the task creator once the robot
+   * missed the market's start window (permissionless)
+   */
+  async forfeitBidBond(
+    taskPubkey: PublicKey,
+    robotPubkey: PublicKey,
+    creator: PublicKey,
+    payer: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffe9999'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getMarketPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: taskPubkey, isSigner: false, isWritable: false },
+        { pubkey: this.getBidPDA(taskPubkey, robotPubkey).publicKey, isSigner: false, isWritable: true },
+        { pubkey: creator, isSigner: false, isWritable: true },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Update task progress, backed by a verified oracle proof above 50%
+   */
+  async updateProgress(
+    taskPubkey: PublicKey,
+    robotPubkey: PublicKey,
+    progress: number,
+    operator: Keypair,
+    proofPubkey?: PublicKey
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(9);
+    data.writeBigUInt64LE(BigInt('0xdddddddddddddddd'), 0);
+    data.writeUInt8(progress, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: robotPubkey, isSigner: false, isWritable: false },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
+        // Absent optional accounts are passed as the program id
+        { pubkey: proofPubkey ?? this.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Complete task, submitting the SHA-256 hash of the deliverable and
+   * optionally a URI it can be fetched from
+   */
+  async completeTask(
+    taskPubkey: PublicKey,
+    robotPubkey: PublicKey,
+    operator: Keypair,
+    deliverableHash: Buffer,
+    deliverableUri?: string
+  ): Promise<TransactionResult> {
+    const uriBytes = deliverableUri !== undefined ? Buffer.from(deliverableUri) : null;
+    const data = Buffer.alloc(8 + 32 + 1 + (uriBytes ? 4 + uriBytes.length : 0));
+
+    let offset = 0;
+    data.writeBigUInt64LE(BigInt('0xeeeeeeeeeeeeeeee'), offset);
+    offset += 8;
+    deliverableHash.copy(data, offset, 0, 32);
+    offset += 32;
+    data.writeUInt8(uriBytes ? 1 : 0, offset);
+    offset += 1;
+    if (uriBytes) {
+      data.writeUInt32LE(uriBytes.length, offset);
+      offset += 4;
+      uriBytes.copy(data, offset);
+    }
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: robotPubkey, isSigner: false, isWritable: false },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Split an open task without bids into milestones paid per approval
+   */
+  async setMilestones(
+    taskPubkey: PublicKey,
+    milestones: MilestoneTerms[],
+    creator: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8 + 4 + milestones.length * 4);
+
+    let offset = 0;
+    data.writeBigUInt64LE(BigInt('0xfffffffffffebbbb'), offset);
+    offset += 8;
+    data.writeUInt32LE(milestones.length, offset);
+    offset += 4;
+    for (const milestone of milestones) {
+      data.writeUInt16LE(milestone.shareBps, offset);
+      offset += 2;
+      if (milestone.proofType === undefined) {
+        data.writeUInt8(0, offset);
+        offset += 1;
+      } else {
+        data.writeUInt8(1, offset);
+        data.writeUInt8(milestone.proofType, offset + 1);
+        offset += 2;
+      }
+    }
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data: data.slice(0, offset),
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Boost an open task for `duration` seconds, paying the market's hourly
+   * boost fee to its treasury
+   */
+  async boostTask(
+    taskPubkey: PublicKey,
+    duration: number,
+    treasury: PublicKey,
+    creator: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(16);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffd1111'), 0);
+    data.writeBigInt64LE(BigInt(duration), 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getMarketPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: true },
+        { pubkey: treasury, isSigner: false, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Edit the description, reward, expiry or capabilities of an open task
+   * without bids
+   */
+  async updateTask(
+    taskPubkey: PublicKey,
+    params: UpdateTaskParams,
+    creator: Keypair
+  ): Promise<TransactionResult> {
+    const parts: Buffer[] = [Buffer.alloc(8)];
+    parts[0].writeBigUInt64LE(BigInt('0xfffffffffffe8888'), 0);
+
+    const option = (value: Buffer | null) =>
+      value ? Buffer.concat([Buffer.from([1]), value]) : Buffer.from([0]);
+    const vec = (bytes: Buffer) => {
+      const len = Buffer.alloc(4);
+      len.writeUInt32LE(bytes.length);
+      return Buffer.concat([len, bytes]);
+    };
+    const u64 = (value: bigint) => {
+      const buf = Buffer.alloc(8);
+      buf.writeBigUInt64LE(value);
+      return buf;
+    };
+
+    parts.push(option(params.description !== undefined ? vec(Buffer.from(params.description)) : null));
+    parts.push(option(params.reward !== undefined ? u64(params.reward) : null));
+    parts.push(option(params.expiresIn !== undefined ? u64(BigInt(params.expiresIn)) : null));
+    parts.push(option(params.capabilities ? vec(Buffer.from(params.capabilities)) : null));
+    const data = Buffer.concat(parts);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Block a robot or operator from bidding on any of the creator's tasks
+   */
+  async addToBlacklist(member: PublicKey, creator: Keypair): Promise<TransactionResult> {
+    return this.updateBlacklist(member, creator, true);
+  }
+
+  /**
+   * Let a blacklisted robot or operator bid on the creator's tasks again
+   */
+  async removeFromBlacklist(member: PublicKey, creator: Keypair): Promise<TransactionResult> {
+    return this.updateBlacklist(member, creator, false);
+  }
+
+  private async updateBlacklist(
+    member: PublicKey,
+    creator: Keypair,
+    add: boolean
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(40);
+    data.writeBigUInt64LE(BigInt(add ? '0xfffffffffffd2222' : '0xfffffffffffd3333'), 0);
+    member.toBuffer().copy(data, 8);
+
+    const keys = [
+      { pubkey: this.getBlacklistPDA(creator.publicKey).publicKey, isSigner: false, isWritable: true },
+      { pubkey: creator.publicKey, isSigner: true, isWritable: add },
+    ];
+    if (add) {
+      keys.push({ pubkey: SystemProgram.programId, isSigner: false, isWritable: false });
+    }
+
+    const transaction = new Transaction().add({ programId: this.programId, keys, data });
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Restrict bidding on an open task without bids to the listed robots or
+   * operators, plus any provable against a merkle root of keccak(pubkey)
+   * leaves. No members and no root make the task public again.
+   */
+  async setAllowlist(
+    taskPubkey: PublicKey,
+    members: PublicKey[],
+    root: Buffer | null,
+    creator: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8 + 4 + 32 * members.length + 1 + (root ? 32 : 0));
+
+    let offset = 0;
+    data.writeBigUInt64LE(BigInt('0xfffffffffffe5555'), offset);
+    offset += 8;
+    data.writeUInt32LE(members.length, offset);
+    offset += 4;
+    for (const member of members) {
+      member.toBuffer().copy(data, offset);
+      offset += 32;
+    }
+    data.writeUInt8(root ? 1 : 0, offset);
+    offset += 1;
+    if (root) {
+      root.copy(data, offset, 0, 32);
+    }
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Restrict an open task without bids to a circular geofence. Coordinates
+   * are in microdegrees (degrees * 1_000_000), as in oracle GPS proofs.
+   */
+  async setGeofence(
+    taskPubkey: PublicKey,
+    latitude: number,
+    longitude: number,
+    radiusMeters: number,
+    creator: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8 + 8 + 8 + 4);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffeffff'), 0);
+    data.writeBigInt64LE(BigInt(latitude), 8);
+    data.writeBigInt64LE(BigInt(longitude), 16);
+    data.writeUInt32LE(radiusMeters, 24);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Raise an open task's reward and accepted rate by `stepBps` every
+   * `interval` seconds since creation, up to `capBps`, applied when a bid
+   * is accepted. A `stepBps` of 0 turns escalation off.
+   */
+  async setEscalation(
+    taskPubkey: PublicKey,
+    stepBps: number,
+    interval: number,
+    capBps: number,
+    creator: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8 + 2 + 4 + 2);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffd5555'), 0);
+    data.writeUInt16LE(stepBps, 8);
+    data.writeUInt32LE(interval, 10);
+    data.writeUInt16LE(capBps, 14);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Mark a milestone done, with the verified oracle proof it requires, if any
+   */
+  async completeMilestone(
+    taskPubkey: PublicKey,
+    robotPubkey: PublicKey,
+    index: number,
+    operator: Keypair,
+    proofPubkey?: PublicKey
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(9);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffecccc'), 0);
+    data.writeUInt8(index, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: robotPubkey, isSigner: false, isWritable: false },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
+        // Absent optional accounts are passed as the program id
+        { pubkey: proofPubkey ?? this.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Approve a completed milestone, paying its share to the operator
+   */
+  async approveMilestone(
+    taskPubkey: PublicKey,
+    index: number,
+    creator: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(9);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffedddd'), 0);
+    data.writeUInt8(index, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Verify task completion
+   */
+  async verifyCompletion(
+    taskPubkey: PublicKey,
+    approved: boolean,
+    creator: Keypair
+  ): Promise<TransactionResult> {
+    const marketPDA = this.getMarketPDA();
+    
+    const data = Buffer.alloc(9);
+    data.writeBigUInt64LE(BigInt('0xffffffffffff0000'), 0);
+    data.writeUInt8(approved ? 1 : 0, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: marketPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Approve a completed task its creator left unverified past the task's
+   * verification window (anyone can call)
+   */
+  async finalizeUnverified(taskPubkey: PublicKey, payer: Keypair): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffe4444'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getMarketPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
 
   /**
-   * Create a new task
+   * Cancel task
    */
-  async createTask(
-    params: CreateTaskParams,
+  async cancelTask(
+    taskPubkey: PublicKey,
     creator: Keypair
-  ): Promise<{ result: TransactionResult; taskPubkey: PublicKey }> {
-    const marketPDA = this.getMarketPDA();
-    
-    // Get current task count for PDA derivation
-    const marketAccount = await this.connection.getAccountInfo(marketPDA.publicKey);
-    const taskIndex = marketAccount ? this.decodeTaskCount(marketAccount.data) : 0;
-    
-    const taskPDA = this.getTaskPDA(creator.publicKey, taskIndex);
-
-    const data = this.encodeCreateTask(params);
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xffffffffffff1111'), 0);
 
     const instruction = {
       programId: this.programId,
       keys: [
-        { pubkey: marketPDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: taskPDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: creator.publicKey, isSigner: true, isWritable: true },
-        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
       ],
       data,
     };
@@ -102,52 +1336,32 @@ export class MarketClient {
     try {
       const signature = await this.connection.sendTransaction(transaction, [creator]);
       await this.connection.confirmTransaction(signature, 'confirmed');
-      
-      return { 
-        result: { signature, success: true },
-        taskPubkey: taskPDA.publicKey,
-      };
+      return { signature, success: true };
     } catch (error) {
-      return { 
-        result: { signature: '', success: false, error: (error as Error).message },
-        taskPubkey: taskPDA.publicKey,
-      };
+      return { signature: '', success: false, error: (error as Error).message };
     }
   }
 
   /**
-   * Submit a bid on a task
+   * Cancel an assigned or in-progress task. The robot is paid for time
+   * elapsed plus the market's cancellation fee; with `reopen` the task goes
+   * back to open for new bids.
    */
-  async submitBid(
+  async cancelAssignedTask(
     taskPubkey: PublicKey,
-    robotPubkey: PublicKey,
-    params: SubmitBidParams,
-    operator: Keypair
-  ): Promise<{ result: TransactionResult; bidPubkey: PublicKey }> {
-    const bidPDA = this.getBidPDA(taskPubkey, robotPubkey);
-
-    const messageBytes = Buffer.from(params.message || '');
-    const data = Buffer.alloc(8 + 8 + 4 + 4 + messageBytes.length);
-    
-    let offset = 0;
-    data.writeBigUInt64LE(BigInt('0xaaaaaaaaaaaaaaaa'), offset); // discriminator
-    offset += 8;
-    data.writeBigUInt64LE(params.proposedRate, offset);
-    offset += 8;
-    data.writeUInt32LE(params.estimatedDuration, offset);
-    offset += 4;
-    data.writeUInt32LE(messageBytes.length, offset);
-    offset += 4;
-    messageBytes.copy(data, offset);
+    creator: Keypair,
+    reopen: boolean
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(9);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffe3333'), 0);
+    data.writeUInt8(reopen ? 1 : 0, 8);
 
     const instruction = {
       programId: this.programId,
       keys: [
+        { pubkey: this.getMarketPDA().publicKey, isSigner: false, isWritable: false },
         { pubkey: taskPubkey, isSigner: false, isWritable: true },
-        { pubkey: bidPDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: robotPubkey, isSigner: false, isWritable: false },
-        { pubkey: operator.publicKey, isSigner: true, isWritable: true },
-        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
       ],
       data,
     };
@@ -155,37 +1369,31 @@ export class MarketClient {
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
       await this.connection.confirmTransaction(signature, 'confirmed');
-      
-      return { 
-        result: { signature, success: true },
-        bidPubkey: bidPDA.publicKey,
-      };
+      return { signature, success: true };
     } catch (error) {
-      return { 
-        result: { signature: '', success: false, error: (error as Error).message },
-        bidPubkey: bidPDA.publicKey,
-      };
+      return { signature: '', success: false, error: (error as Error).message };
     }
   }
 
   /**
-   * Accept a bid
+   * Link a disputed task to the oracle-verifier dispute its creator opened
+   * against the task's completion proof
    */
-  async acceptBid(
+  async openDispute(
     taskPubkey: PublicKey,
-    bidPubkey: PublicKey,
+    disputePubkey: PublicKey,
     creator: Keypair
   ): Promise<TransactionResult> {
     const data = Buffer.alloc(8);
-    data.writeBigUInt64LE(BigInt('0xbbbbbbbbbbbbbbbb'), 0);
+    data.writeBigUInt64LE(BigInt('0xffffffffffff6666'), 0);
 
     const instruction = {
       programId: this.programId,
       keys: [
         { pubkey: taskPubkey, isSigner: false, isWritable: true },
-        { pubkey: bidPubkey, isSigner: false, isWritable: true },
+        { pubkey: disputePubkey, isSigner: false, isWritable: false },
         { pubkey: creator.publicKey, isSigner: true, isWritable: false },
       ],
       data,
@@ -203,30 +1411,22 @@ export class MarketClient {
   }
 
   /**
-   * Start task execution
+   * Cancel an open task past its expiry (anyone can call)
    */
-  async startTask(
-    taskPubkey: PublicKey,
-    robotPubkey: PublicKey,
-    operator: Keypair
-  ): Promise<TransactionResult> {
+  async expireTask(taskPubkey: PublicKey, payer: Keypair): Promise<TransactionResult> {
     const data = Buffer.alloc(8);
-    data.writeBigUInt64LE(BigInt('0xcccccccccccccccc'), 0);
+    data.writeBigUInt64LE(BigInt('0xffffffffffff2222'), 0);
 
     const instruction = {
       programId: this.programId,
-      keys: [
-        { pubkey: taskPubkey, isSigner: false, isWritable: true },
-        { pubkey: robotPubkey, isSigner: false, isWritable: false },
-        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
-      ],
+      keys: [{ pubkey: taskPubkey, isSigner: false, isWritable: true }],
       data,
     };
 
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
       await this.connection.confirmTransaction(signature, 'confirmed');
       return { signature, success: true };
     } catch (error) {
@@ -235,24 +1435,18 @@ export class MarketClient {
   }
 
   /**
-   * Update task progress
+   * Flag a task in progress past its SLA deadline (anyone can call). The
+   * market's late penalty share of the task bond goes to the creator.
    */
-  async updateProgress(
-    taskPubkey: PublicKey,
-    robotPubkey: PublicKey,
-    progress: number,
-    operator: Keypair
-  ): Promise<TransactionResult> {
-    const data = Buffer.alloc(9);
-    data.writeBigUInt64LE(BigInt('0xdddddddddddddddd'), 0);
-    data.writeUInt8(progress, 8);
+  async flagLate(taskPubkey: PublicKey, payer: Keypair): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffe2222'), 0);
 
     const instruction = {
       programId: this.programId,
       keys: [
+        { pubkey: this.getMarketPDA().publicKey, isSigner: false, isWritable: false },
         { pubkey: taskPubkey, isSigner: false, isWritable: true },
-        { pubkey: robotPubkey, isSigner: false, isWritable: false },
-        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
       ],
       data,
     };
@@ -260,7 +1454,7 @@ export class MarketClient {
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
       await this.connection.confirmTransaction(signature, 'confirmed');
       return { signature, success: true };
     } catch (error) {
@@ -269,22 +1463,23 @@ export class MarketClient {
   }
 
   /**
-   * Complete task
+   * Close a finished task and the given bids on it, reclaiming their rent.
+   * Task rent goes to the creator, each bid's rent to its operator.
    */
-  async completeTask(
+  async closeTask(
     taskPubkey: PublicKey,
-    robotPubkey: PublicKey,
-    operator: Keypair
+    creator: Keypair,
+    bids: { bid: PublicKey; operator: PublicKey }[] = []
   ): Promise<TransactionResult> {
     const data = Buffer.alloc(8);
-    data.writeBigUInt64LE(BigInt('0xeeeeeeeeeeeeeeee'), 0);
+    data.writeBigUInt64LE(BigInt('0xffffffffffff3333'), 0);
 
     const instruction = {
       programId: this.programId,
       keys: [
         { pubkey: taskPubkey, isSigner: false, isWritable: true },
-        { pubkey: robotPubkey, isSigner: false, isWritable: false },
-        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
+        { pubkey: creator.publicKey, isSigner: true, isWritable: true },
+        ...this.bidPairKeys(bids),
       ],
       data,
     };
@@ -292,7 +1487,7 @@ export class MarketClient {
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      const signature = await this.connection.sendTransaction(transaction, [creator]);
       await this.connection.confirmTransaction(signature, 'confirmed');
       return { signature, success: true };
     } catch (error) {
@@ -301,33 +1496,22 @@ export class MarketClient {
   }
 
   /**
-   * Verify task completion
+   * Mark a pending bid past its validity as expired (anyone can call)
    */
-  async verifyCompletion(
-    taskPubkey: PublicKey,
-    approved: boolean,
-    creator: Keypair
-  ): Promise<TransactionResult> {
-    const marketPDA = this.getMarketPDA();
-    
-    const data = Buffer.alloc(9);
-    data.writeBigUInt64LE(BigInt('0xffffffffffff0000'), 0);
-    data.writeUInt8(approved ? 1 : 0, 8);
+  async expireBid(bidPubkey: PublicKey, payer: Keypair): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xffffffffffff4444'), 0);
 
     const instruction = {
       programId: this.programId,
-      keys: [
-        { pubkey: marketPDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: taskPubkey, isSigner: false, isWritable: true },
-        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
-      ],
+      keys: [{ pubkey: bidPubkey, isSigner: false, isWritable: true }],
       data,
     };
 
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
       await this.connection.confirmTransaction(signature, 'confirmed');
       return { signature, success: true };
     } catch (error) {
@@ -336,20 +1520,17 @@ export class MarketClient {
   }
 
   /**
-   * Cancel task
+   * Close a rejected, withdrawn or expired bid, reclaiming its rent
    */
-  async cancelTask(
-    taskPubkey: PublicKey,
-    creator: Keypair
-  ): Promise<TransactionResult> {
+  async closeBid(bidPubkey: PublicKey, operator: Keypair): Promise<TransactionResult> {
     const data = Buffer.alloc(8);
-    data.writeBigUInt64LE(BigInt('0xffffffffffff1111'), 0);
+    data.writeBigUInt64LE(BigInt('0xffffffffffff5555'), 0);
 
     const instruction = {
       programId: this.programId,
       keys: [
-        { pubkey: taskPubkey, isSigner: false, isWritable: true },
-        { pubkey: creator.publicKey, isSigner: true, isWritable: false },
+        { pubkey: bidPubkey, isSigner: false, isWritable: true },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: true },
       ],
       data,
     };
@@ -357,7 +1538,7 @@ export class MarketClient {
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [creator]);
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
       await this.connection.confirmTransaction(signature, 'confirmed');
       return { signature, success: true };
     } catch (error) {
@@ -396,6 +1577,119 @@ export class MarketClient {
     return [];
   }
 
+  /**
+   * Add an open task to the index for its robot class or one of its
+   * capabilities (anyone can call). The index's last page must have room.
+   */
+  async indexTask(
+    taskPubkey: PublicKey,
+    kind: TaskIndexKind,
+    key: RobotClass | Capability,
+    payer: Keypair
+  ): Promise<TransactionResult> {
+    const indexPDA = this.getTaskIndexPDA(kind, key);
+    const indexAccount = await this.connection.getAccountInfo(indexPDA.publicKey);
+    if (!indexAccount) {
+      return { signature: '', success: false, error: 'Task index does not exist' };
+    }
+    // TaskIndex: discriminator, kind, key, pages
+    const pagePDA = this.getTaskIndexPagePDA(indexPDA.publicKey, indexAccount.data.readUInt32LE(10) - 1);
+
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffe6666'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: taskPubkey, isSigner: false, isWritable: true },
+        { pubkey: indexPDA.publicKey, isSigner: false, isWritable: false },
+        { pubkey: pagePDA.publicKey, isSigner: false, isWritable: true },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Refresh a task's open bit in an index page after it was assigned,
+   * finished, closed or reopened (anyone can call)
+   */
+  async syncTaskIndex(
+    pagePubkey: PublicKey,
+    slot: number,
+    taskPubkey: PublicKey,
+    payer: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(9);
+    data.writeBigUInt64LE(BigInt('0xfffffffffffe7777'), 0);
+    data.writeUInt8(slot, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: pagePubkey, isSigner: false, isWritable: true },
+        { pubkey: taskPubkey, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * List tasks marked open in the index for a robot class or capability, as
+   * of their last sync, boosted tasks first
+   */
+  async getIndexedOpenTasks(kind: TaskIndexKind, key: RobotClass | Capability): Promise<PublicKey[]> {
+    const indexPDA = this.getTaskIndexPDA(kind, key);
+    const indexAccount = await this.connection.getAccountInfo(indexPDA.publicKey);
+    if (!indexAccount) return [];
+
+    const pages = indexAccount.data.readUInt32LE(10);
+    const pageKeys = Array.from(
+      { length: pages },
+      (_, page) => this.getTaskIndexPagePDA(indexPDA.publicKey, page).publicKey
+    );
+    const pageAccounts = await this.connection.getMultipleAccountsInfo(pageKeys);
+
+    // Boosted tasks first, each group in index order
+    const boostedTasks: PublicKey[] = [];
+    const tasks: PublicKey[] = [];
+    for (const pageAccount of pageAccounts) {
+      if (!pageAccount) continue;
+      // TaskIndexPage: discriminator, index, page, tasks, open bitmap, boosted bitmap
+      const data = pageAccount.data;
+      const count = data.readUInt32LE(8 + 32 + 4);
+      const tasksOffset = 8 + 32 + 4 + 4;
+      const open = data.readBigUInt64LE(tasksOffset + 32 * count);
+      const boosted = data.readBigUInt64LE(tasksOffset + 32 * count + 8);
+      for (let slot = 0; slot < count; slot++) {
+        if ((open >> BigInt(slot)) & BigInt(1)) {
+          const task = new PublicKey(data.subarray(tasksOffset + 32 * slot, tasksOffset + 32 * (slot + 1)));
+          ((boosted >> BigInt(slot)) & BigInt(1) ? boostedTasks : tasks).push(task);
+        }
+      }
+    }
+    return [...boostedTasks, ...tasks];
+  }
+
   /**
    * Check if task is open for bids
    */
@@ -411,7 +1705,10 @@ export class MarketClient {
   // ENCODING/DECODING
   // ============================================================================
 
-  private encodeCreateTask(params: CreateTaskParams): Buffer {
+  private encodeCreateTask(
+    params: CreateTaskParams,
+    discriminator = BigInt('0x9999999999999999')
+  ): Buffer {
     const titleBytes = Buffer.from(params.title);
     const descBytes = Buffer.from(params.description);
     const capsBytes = Buffer.from(params.capabilities.map(c => c as number));
@@ -426,12 +1723,13 @@ export class MarketClient {
       8 + // rate_per_second
       4 + // estimated_duration
       1 + // priority
-      8;  // expires_in
+      8 + // expires_in
+      1;  // requires_proof
 
     const data = Buffer.alloc(size);
     let offset = 0;
 
-    data.writeBigUInt64LE(BigInt('0x9999999999999999'), offset);
+    data.writeBigUInt64LE(discriminator, offset);
     offset += 8;
 
     data.writeUInt32LE(titleBytes.length, offset);
@@ -469,6 +1767,9 @@ export class MarketClient {
     offset += 1;
 
     data.writeBigInt64LE(BigInt(params.expiresIn), offset);
+    offset += 8;
+
+    data.writeUInt8(params.requiresProof ? 1 : 0, offset);
 
     return data;
   }
@@ -478,109 +1779,53 @@ export class MarketClient {
     return Number(data.readBigUInt64LE(40));
   }
 
-  private decodeTaskAccount(data: Buffer): TaskAccount {
-    let offset = 8; // Skip discriminator
-
-    const creator = new PublicKey(data.slice(offset, offset + 32));
-    offset += 32;
-
-    const titleLen = data.readUInt32LE(offset);
-    offset += 4;
-    const title = data.slice(offset, offset + titleLen).toString();
-    offset += titleLen;
-
-    const descLen = data.readUInt32LE(offset);
-    offset += 4;
-    const description = data.slice(offset, offset + descLen).toString();
-    offset += descLen;
+  private decodeRecurringCount(data: Buffer): number {
+    // After total_tasks, total_completed, total_volume, fee_basis_points and
+    // min_stake_per_robot
+    return Number(data.readBigUInt64LE(8 + 32 + 8 + 8 + 8 + 2 + 8));
+  }
 
-    const robotClass = data.readUInt8(offset) as RobotClass;
-    offset += 1;
+  private decodeTaskAccount(data: Buffer): TaskAccount {
+    // Tasks are zero-copy: fixed offsets (past the 8-byte discriminator),
+    // unset keys and times stored as zero
+    const key = (offset: number): PublicKey | null => {
+      const bytes = data.subarray(offset, offset + 32);
+      return bytes.every(b => b === 0) ? null : new PublicKey(bytes);
+    };
+    const time = (offset: number): number | null => {
+      const value = Number(data.readBigInt64LE(offset));
+      return value === 0 ? null : value;
+    };
 
-    const capsLen = data.readUInt32LE(offset);
-    offset += 4;
+    const titleLen = data.readUInt8(1455);
+    const descLen = data.readUInt16LE(1000);
+    const capsLen = data.readUInt8(1457);
     const requiredCapabilities: Capability[] = [];
     for (let i = 0; i < capsLen; i++) {
-      requiredCapabilities.push(data.readUInt8(offset + i) as Capability);
+      requiredCapabilities.push(data.readUInt8(1450 + i) as Capability);
     }
-    offset += capsLen;
-
-    const minReputation = data.readUInt16LE(offset);
-    offset += 2;
-
-    const reward = data.readBigUInt64LE(offset);
-    offset += 8;
-
-    const ratePerSecond = data.readBigUInt64LE(offset);
-    offset += 8;
-
-    const estimatedDuration = data.readUInt32LE(offset);
-    offset += 4;
-
-    const priority = data.readUInt8(offset);
-    offset += 1;
-
-    const status = data.readUInt8(offset) as TaskStatus;
-    offset += 1;
-
-    const createdAt = Number(data.readBigInt64LE(offset));
-    offset += 8;
-
-    const expiresAt = Number(data.readBigInt64LE(offset));
-    offset += 8;
-
-    // Option fields
-    const hasAssignedRobot = data.readUInt8(offset) === 1;
-    offset += 1;
-    const assignedRobot = hasAssignedRobot ? new PublicKey(data.slice(offset, offset + 32)) : null;
-    if (hasAssignedRobot) offset += 32;
-
-    const hasAssignedAt = data.readUInt8(offset) === 1;
-    offset += 1;
-    const assignedAt = hasAssignedAt ? Number(data.readBigInt64LE(offset)) : null;
-    if (hasAssignedAt) offset += 8;
-
-    const hasStartedAt = data.readUInt8(offset) === 1;
-    offset += 1;
-    const startedAt = hasStartedAt ? Number(data.readBigInt64LE(offset)) : null;
-    if (hasStartedAt) offset += 8;
-
-    const hasCompletedAt = data.readUInt8(offset) === 1;
-    offset += 1;
-    const completedAt = hasCompletedAt ? Number(data.readBigInt64LE(offset)) : null;
-    if (hasCompletedAt) offset += 8;
-
-    const hasStreamId = data.readUInt8(offset) === 1;
-    offset += 1;
-    const streamId = hasStreamId ? new PublicKey(data.slice(offset, offset + 32)) : null;
-    if (hasStreamId) offset += 32;
-
-    const progress = data.readUInt8(offset);
-    offset += 1;
-
-    const bidsCount = data.readUInt16LE(offset);
 
     return {
-      creator,
-      title,
-      description,
-      robotClass,
+      creator: new PublicKey(data.subarray(8, 40)),
+      title: data.subarray(1002, 1002 + titleLen).toString(),
+      description: data.subarray(1066, 1066 + descLen).toString(),
+      robotClass: data.readUInt8(45) as RobotClass,
       requiredCapabilities,
-      minReputation,
-      reward,
-      ratePerSecond,
-      estimatedDuration,
-      priority,
-      status,
-      createdAt,
-      expiresAt,
-      assignedRobot,
-      assignedAt,
-      startedAt,
-      completedAt,
-      streamId,
-      progress,
-      bidsCount,
+      minReputation: data.readUInt16LE(992),
+      reward: data.readBigUInt64LE(48),
+      ratePerSecond: data.readBigUInt64LE(56),
+      estimatedDuration: data.readUInt32LE(980),
+      priority: data.readUInt8(46),
+      status: data.readUInt8(40) as TaskStatus,
+      createdAt: Number(data.readBigInt64LE(64)),
+      expiresAt: Number(data.readBigInt64LE(72)),
+      assignedRobot: key(224),
+      assignedAt: time(80),
+      startedAt: time(88),
+      completedAt: time(96),
+      streamId: key(288),
+      progress: data.readUInt8(47),
+      bidsCount: data.readUInt16LE(994),
     };
   }
 
@@ -611,6 +1856,9 @@ export class MarketClient {
     offset += 1;
 
     const submittedAt = Number(data.readBigInt64LE(offset));
+    offset += 8;
+
+    const validUntil = Number(data.readBigInt64LE(offset));
 
     return {
       task,
@@ -621,6 +1869,7 @@ export class MarketClient {
       message,
       status,
       submittedAt,
+      validUntil,
     };
   }
 }
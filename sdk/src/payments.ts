@@ -44,19 +44,35 @@ export class PaymentsClient {
     return { publicKey, bump };
   }
 
-  getStreamPDA(payer: PublicKey, payee: PublicKey, timestamp: number): PDAResult {
+  getStreamPDA(payer: PublicKey, payee: PublicKey, nonce: bigint): PDAResult {
     const [publicKey, bump] = PublicKey.findProgramAddressSync(
       [
         Buffer.from('stream'),
         payer.toBuffer(),
         payee.toBuffer(),
-        Buffer.from(new BigInt64Array([BigInt(timestamp)]).buffer),
+        Buffer.from(new BigUint64Array([nonce]).buffer),
       ],
       this.programId
     );
     return { publicKey, bump };
   }
 
+  getRegistryPDA(kind: 'payer_streams' | 'payee_streams', wallet: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from(kind), wallet.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getReferrerPDA(referrer: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('referrer'), referrer.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
   getEscrowPDA(stream: PublicKey): PDAResult {
     const [publicKey, bump] = PublicKey.findProgramAddressSync(
       [Buffer.from('escrow'), stream.toBuffer()],
@@ -78,17 +94,19 @@ export class PaymentsClient {
     payerTokenAccount: PublicKey,
     payer: Keypair
   ): Promise<{ result: TransactionResult; streamPubkey: PublicKey }> {
-    const timestamp = Math.floor(Date.now() / 1000);
-    const streamPDA = this.getStreamPDA(payer.publicKey, params.payee, timestamp);
+    const streamPDA = this.getStreamPDA(payer.publicKey, params.payee, params.nonce);
     const escrowPDA = this.getEscrowPDA(streamPDA.publicKey);
     const configPDA = this.getConfigPDA();
 
     // Encode instruction
-    const data = Buffer.alloc(8 + 8 + 8 + 8 + 1);
+    const earlyTermination = params.earlyTermination;
+    const data = Buffer.alloc(8 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + (earlyTermination ? 11 : 1));
     let offset = 0;
     
     data.writeBigUInt64LE(BigInt('0x1111111111111111'), offset); // discriminator
     offset += 8;
+    data.writeBigUInt64LE(params.nonce, offset);
+    offset += 8;
     data.writeBigUInt64LE(params.ratePerSecond, offset);
     offset += 8;
     data.writeBigInt64LE(BigInt(params.maxDuration), offset);
@@ -96,6 +114,18 @@ export class PaymentsClient {
     data.writeBigInt64LE(BigInt(params.gracePeriod || 60), offset);
     offset += 8;
     data.writeUInt8(params.autoTerminate !== false ? 1 : 0, offset);
+    offset += 1;
+    data.writeBigUInt64LE(params.securityDeposit ?? BigInt(0), offset);
+    offset += 8;
+    data.writeBigUInt64LE(params.activationFee ?? BigInt(0), offset);
+    offset += 8;
+    data.writeUInt8(earlyTermination ? 1 : 0, offset);
+    offset += 1;
+    if (earlyTermination) {
+      data.writeBigInt64LE(BigInt(earlyTermination.minRuntime), offset);
+      offset += 8;
+      data.writeUInt16LE(earlyTermination.penaltyBps, offset);
+    }
 
     const instruction = {
       programId: this.programId,
@@ -107,6 +137,14 @@ export class PaymentsClient {
         { pubkey: payerTokenAccount, isSigner: false, isWritable: true },
         { pubkey: payer.publicKey, isSigner: true, isWritable: true },
         { pubkey: params.payee, isSigner: false, isWritable: false },
+        { pubkey: params.payerStake ?? this.programId, isSigner: false, isWritable: false }, // optional
+        {
+          pubkey: params.referrer ? this.getReferrerPDA(params.referrer).publicKey : this.programId,
+          isSigner: false,
+          isWritable: !!params.referrer,
+        }, // optional
+        { pubkey: this.getRegistryPDA('payer_streams', payer.publicKey).publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getRegistryPDA('payee_streams', params.payee).publicKey, isSigner: false, isWritable: true },
         { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       ],
@@ -196,6 +234,36 @@ export class PaymentsClient {
     }
   }
 
+  /**
+   * Accept stream terms (by payee)
+   */
+  async acceptStream(
+    streamPubkey: PublicKey,
+    payee: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x7777777777777777'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: streamPubkey, isSigner: false, isWritable: true },
+        { pubkey: payee.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payee]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
   /**
    * Pause stream
    */
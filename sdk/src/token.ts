@@ -1,18 +1,41 @@
-import { Connection, PublicKey, Keypair, Transaction, SystemProgram } from '@solana/web3.js';
-import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import {
+  Connection,
+  PublicKey,
+  Keypair,
+  Transaction,
+  TransactionInstruction,
+  SystemProgram,
+} from '@solana/web3.js';
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from '@solana/spl-token';
 import { PROGRAM_IDS } from './index';
 import {
   StakeAccount,
   OperatorStakeAccount,
+  SlashRecord,
+  LockTier,
   StakeParams,
+  StakingParameters,
   TransactionResult,
   PDAResult,
 } from './types';
 
 // Constants
 const DECIMALS = 6;
+// Launch defaults; the live values are in the token config
 const MIN_STAKE = 100 * 1_000_000; // 100 DRON
+const MIN_OPERATOR_STAKE = 1_000 * 1_000_000; // 1K DRON
 const BASE_APY_BPS = 1200; // 12%
+const LAUNCH_LOCK_TIERS: LockTier[] = [
+  { days: 0, multiplierBps: 10000 },
+  { days: 30, multiplierBps: 11000 },
+  { days: 90, multiplierBps: 12500 },
+  { days: 180, multiplierBps: 15000 },
+  { days: 365, multiplierBps: 20000 },
+];
 const SECONDS_PER_YEAR = 365 * 24 * 60 * 60;
 
 /**
@@ -35,80 +58,1167 @@ export class TokenClient {
     this.wallet = wallet;
   }
 
-  // ============================================================================
-  // PDA DERIVATIONS
-  // ============================================================================
+  // ============================================================================
+  // PDA DERIVATIONS
+  // ============================================================================
+
+  getConfigPDA(): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('config')],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getMintPDA(): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('mint')],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getStakePDA(user: PublicKey, position = 0): PDAResult {
+    const index = Buffer.alloc(4);
+    index.writeUInt32LE(position);
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('stake'), user.toBuffer(), index],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getPositionsPDA(user: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('positions'), user.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getEmissionsPDA(): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('emissions')],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getVotingPowerPDA(user: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('voting_power'), user.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getGovernancePDA(): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('governance')],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getProposalPDA(id: bigint): PDAResult {
+    const idBytes = Buffer.alloc(8);
+    idBytes.writeBigUInt64LE(id);
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('proposal'), idBytes],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getInsurancePoolPDA(): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('insurance')],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getSlashAppealPDA(operator: PublicKey, slashedAt: number): PDAResult {
+    const slashedAtBytes = Buffer.alloc(8);
+    slashedAtBytes.writeBigInt64LE(BigInt(slashedAt));
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('appeal'), operator.toBuffer(), slashedAtBytes],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getReferrerPDA(referrer: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('referrer'), referrer.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getSwarmStakePDA(swarm: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('swarm-stake'), swarm.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getSlashRecordPDA(operator: PublicKey, index: bigint): PDAResult {
+    const indexBytes = Buffer.alloc(8);
+    indexBytes.writeBigUInt64LE(index);
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('slash'), operator.toBuffer(), indexBytes],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getLockTiersPDA(): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('lock-tiers')],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getExitQueuePDA(): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('exit-queue')],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getExitTicketPDA(id: bigint): PDAResult {
+    const idBytes = Buffer.alloc(8);
+    idBytes.writeBigUInt64LE(id);
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('exit-ticket'), idBytes],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getSnapshotPDA(id: bigint): PDAResult {
+    const idBytes = Buffer.alloc(8);
+    idBytes.writeBigUInt64LE(id);
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('snapshot'), idBytes],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getDelegationPDA(operator: PublicKey, delegator: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('delegation'), operator.toBuffer(), delegator.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getReceiptMintPDA(stake: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('receipt'), stake.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  getOperatorStakePDA(operator: PublicKey): PDAResult {
+    const [publicKey, bump] = PublicKey.findProgramAddressSync(
+      [Buffer.from('operator'), operator.toBuffer()],
+      this.programId
+    );
+    return { publicKey, bump };
+  }
+
+  // ============================================================================
+  // STAKING OPERATIONS
+  // ============================================================================
+
+  /**
+   * Stake tokens
+   */
+  async stake(
+    params: StakeParams,
+    stakeVault: PublicKey,
+    userTokenAccount: PublicKey,
+    user: Keypair,
+    referral?: { referrer: PublicKey; referrerTokenAccount: PublicKey; rewardsVault: PublicKey }
+  ): Promise<TransactionResult> {
+    const { minStake } = await this.getStakingParameters();
+    if (params.amount < minStake) {
+      return { 
+        signature: '', 
+        success: false, 
+        error: `Minimum stake is ${minStake / BigInt(1_000_000)} DRON` 
+      };
+    }
+
+    const configPDA = this.getConfigPDA();
+    const positionsPDA = this.getPositionsPDA(user.publicKey);
+    const stakePDA = this.getStakePDA(user.publicKey, await this.getPositionCount(user.publicKey));
+
+    const data = Buffer.alloc(8 + 8 + 2 + 1 + (referral ? 32 : 0));
+    data.writeBigUInt64LE(BigInt('0x1111111111111111'), 0);
+    data.writeBigUInt64LE(params.amount, 8);
+    data.writeUInt16LE(params.lockDays, 16);
+    if (referral) {
+      data.writeUInt8(1, 18);
+      referral.referrer.toBuffer().copy(data, 19);
+    }
+
+    // Optional accounts left out are passed as the program id
+    const referralKeys = [
+      referral ? this.getReferrerPDA(referral.referrer).publicKey : this.programId,
+      referral ? referral.referrerTokenAccount : this.programId,
+      referral ? referral.rewardsVault : this.programId,
+    ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: !!referral }));
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getLockTiersPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: positionsPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: stakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: stakeVault, isSigner: false, isWritable: true },
+        { pubkey: userTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: user.publicKey, isSigner: true, isWritable: true },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        ...referralKeys,
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+    if (params.mintReceipt) {
+      transaction.add(this.mintStakeReceiptInstruction(stakePDA.publicKey, user.publicKey));
+    }
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [user]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Mint a receipt NFT for a stake position to its owner. Whoever holds it
+   * can unstake the position; the owner keeps its rewards.
+   */
+  mintStakeReceiptInstruction(stake: PublicKey, owner: PublicKey): TransactionInstruction {
+    const receiptMint = this.getReceiptMintPDA(stake).publicKey;
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x5454545454545454'), 0);
+
+    return new TransactionInstruction({
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: stake, isSigner: false, isWritable: true },
+        { pubkey: receiptMint, isSigner: false, isWritable: true },
+        {
+          pubkey: getAssociatedTokenAddressSync(receiptMint, owner, false, TOKEN_2022_PROGRAM_ID),
+          isSigner: false,
+          isWritable: true,
+        },
+        { pubkey: owner, isSigner: true, isWritable: true },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    });
+  }
+
+  /**
+   * Register as a referrer so stakers can name this wallet when staking
+   */
+  async registerReferrer(owner: Keypair): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x5353535353535353'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getReferrerPDA(owner.publicKey).publicKey, isSigner: false, isWritable: true },
+        { pubkey: owner.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [owner]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Claim staking rewards
+   */
+  async claimRewards(
+    rewardsVault: PublicKey,
+    userTokenAccount: PublicKey,
+    user: Keypair,
+    position = 0
+  ): Promise<TransactionResult> {
+    const configPDA = this.getConfigPDA();
+    const stakePDA = this.getStakePDA(user.publicKey, position);
+
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x2222222222222222'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: stakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getEmissionsPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: rewardsVault, isSigner: false, isWritable: true },
+        { pubkey: userTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: user.publicKey, isSigner: true, isWritable: false },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [user]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Restake pending rewards into a stake position
+   */
+  async compoundRewards(
+    rewardsVault: PublicKey,
+    stakeVault: PublicKey,
+    user: Keypair,
+    position = 0
+  ): Promise<TransactionResult> {
+    const configPDA = this.getConfigPDA();
+    const stakePDA = this.getStakePDA(user.publicKey, position);
+
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x2323232323232323'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: stakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getEmissionsPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: rewardsVault, isSigner: false, isWritable: true },
+        { pubkey: stakeVault, isSigner: false, isWritable: true },
+        { pubkey: user.publicKey, isSigner: true, isWritable: false },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [user]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Move a stake position up to a longer lock tier
+   */
+  async extendLock(lockDays: number, user: Keypair, position = 0): Promise<TransactionResult> {
+    const stakePDA = this.getStakePDA(user.publicKey, position);
+
+    const data = Buffer.alloc(8 + 2);
+    data.writeBigUInt64LE(BigInt('0x2424242424242424'), 0);
+    data.writeUInt16LE(lockDays, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: this.getLockTiersPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: stakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: user.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [user]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Roll the position's lock over for the same tier each time it runs out
+   */
+  async enableAutoRelock(user: Keypair, position = 0): Promise<TransactionResult> {
+    return this.sendLockInstruction(BigInt('0x5656565656565656'), user, position);
+  }
+
+  /**
+   * Stop a rolling lock; the position unlocks one full lock period from now,
+   * at the next rollover
+   */
+  async requestUnlock(user: Keypair, position = 0): Promise<TransactionResult> {
+    return this.sendLockInstruction(BigInt('0x5757575757575757'), user, position);
+  }
+
+  /**
+   * Boost a position's rewards by the reputation of a robot the owner
+   * operates (up to 1.5x). `caller` may be anyone when refreshing from the
+   * robot the position already uses.
+   */
+  async refreshReputationBoost(
+    owner: PublicKey,
+    robot: PublicKey,
+    caller: Keypair,
+    position = 0
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x5a5a5a5a5a5a5a5a'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: this.getStakePDA(owner, position).publicKey, isSigner: false, isWritable: true },
+        { pubkey: robot, isSigner: false, isWritable: false },
+        { pubkey: caller.publicKey, isSigner: true, isWritable: false },
+        { pubkey: PROGRAM_IDS.IDENTITY_REGISTRY, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [caller]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  private async sendLockInstruction(
+    discriminator: bigint,
+    user: Keypair,
+    position: number
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(discriminator, 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: this.getLockTiersPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: this.getStakePDA(user.publicKey, position).publicKey, isSigner: false, isWritable: true },
+        { pubkey: user.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [user]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Recompute veDRONEOS voting power from every stake position the user has opened
+   */
+  async updateVotingPower(user: Keypair): Promise<TransactionResult> {
+    const positionCount = await this.getPositionCount(user.publicKey);
+    const positionKeys = Array.from({ length: positionCount }, (_, i) => ({
+      pubkey: this.getStakePDA(user.publicKey, i).publicKey,
+      isSigner: false,
+      isWritable: false,
+    }));
+
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x2525252525252525'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getVotingPowerPDA(user.publicKey).publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getPositionsPDA(user.publicKey).publicKey, isSigner: false, isWritable: false },
+        { pubkey: user.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ...positionKeys,
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [user]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Propose an instruction for the governance PDA to run if the vote passes
+   */
+  async createProposal(
+    proposal: TransactionInstruction,
+    proposer: Keypair
+  ): Promise<TransactionResult> {
+    const configInfo = await this.connection.getAccountInfo(this.getConfigPDA().publicKey);
+    if (!configInfo) {
+      return { signature: '', success: false, error: 'Token config not found' };
+    }
+    // authority, mint, total_staked, total_rewards_distributed, stake_count
+    const proposalId = configInfo.data.readBigUInt64LE(8 + 32 + 32 + 8 + 8 + 8);
+
+    const metas = Buffer.alloc(4 + proposal.keys.length * 34);
+    metas.writeUInt32LE(proposal.keys.length, 0);
+    proposal.keys.forEach((meta, i) => {
+      meta.pubkey.toBuffer().copy(metas, 4 + i * 34);
+      metas.writeUInt8(meta.isSigner ? 1 : 0, 4 + i * 34 + 32);
+      metas.writeUInt8(meta.isWritable ? 1 : 0, 4 + i * 34 + 33);
+    });
+    const dataLen = Buffer.alloc(4);
+    dataLen.writeUInt32LE(proposal.data.length);
+
+    const discriminator = Buffer.alloc(8);
+    discriminator.writeBigUInt64LE(BigInt('0x2626262626262626'), 0);
+    const data = Buffer.concat([
+      discriminator,
+      proposal.programId.toBuffer(),
+      metas,
+      dataLen,
+      proposal.data,
+    ]);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getProposalPDA(proposalId).publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getVotingPowerPDA(proposer.publicKey).publicKey, isSigner: false, isWritable: false },
+        { pubkey: this.getGovernancePDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: proposer.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [proposer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Vote on a governance proposal with current veDRONEOS power
+   */
+  async castVote(proposalId: bigint, support: boolean, voter: Keypair): Promise<TransactionResult> {
+    const proposalPDA = this.getProposalPDA(proposalId);
+    const [votePDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('vote'), proposalPDA.publicKey.toBuffer(), voter.publicKey.toBuffer()],
+      this.programId
+    );
+
+    const data = Buffer.alloc(8 + 1);
+    data.writeBigUInt64LE(BigInt('0x2727272727272727'), 0);
+    data.writeUInt8(support ? 1 : 0, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: proposalPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: votePDA, isSigner: false, isWritable: true },
+        { pubkey: this.getVotingPowerPDA(voter.publicKey).publicKey, isSigner: false, isWritable: false },
+        { pubkey: voter.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [voter]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Close voting on a proposal, running its instruction if it passed.
+   * `proposal` must be the instruction the proposal was created with.
+   */
+  async executeProposal(
+    proposalId: bigint,
+    proposal: TransactionInstruction,
+    payer: Keypair
+  ): Promise<TransactionResult> {
+    const governance = this.getGovernancePDA().publicKey;
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x2828282828282828'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getProposalPDA(proposalId).publicKey, isSigner: false, isWritable: true },
+        { pubkey: governance, isSigner: false, isWritable: false },
+        ...proposal.keys
+          .filter((meta) => !meta.pubkey.equals(governance))
+          .map((meta) => ({ ...meta, isSigner: false })),
+        { pubkey: proposal.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Unstake tokens
+   */
+  async unstake(
+    amount: bigint | null,
+    stakeVault: PublicKey,
+    rewardsVault: PublicKey,
+    userTokenAccount: PublicKey,
+    user: Keypair,
+    position = 0,
+    receiptOwner?: PublicKey
+  ): Promise<TransactionResult> {
+    // For a position with a receipt NFT, `user` holds the NFT and
+    // `receiptOwner` is the wallet that opened the position
+    const owner = receiptOwner ?? user.publicKey;
+    const configPDA = this.getConfigPDA();
+    const stakePDA = this.getStakePDA(owner, position);
+    const receiptMint = this.getReceiptMintPDA(stakePDA.publicKey).publicKey;
+    const receiptKeys = (
+      receiptOwner
+        ? [receiptMint, getAssociatedTokenAddressSync(receiptMint, user.publicKey, false, TOKEN_2022_PROGRAM_ID)]
+        : [this.programId, this.programId]
+    ).map((pubkey) => ({ pubkey, isSigner: false, isWritable: !!receiptOwner }));
+
+    const data = Buffer.alloc(8 + 1 + (amount ? 8 : 0));
+    data.writeBigUInt64LE(BigInt('0x3333333333333333'), 0);
+    
+    if (amount) {
+      data.writeUInt8(1, 8); // Some
+      data.writeBigUInt64LE(amount, 9);
+    } else {
+      data.writeUInt8(0, 8); // None
+    }
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: stakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getPositionsPDA(owner).publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getEmissionsPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: stakeVault, isSigner: false, isWritable: true },
+        { pubkey: rewardsVault, isSigner: false, isWritable: true },
+        { pubkey: userTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: user.publicKey, isSigner: true, isWritable: false },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        ...receiptKeys,
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [user]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Withdraw through the exit queue, required at or above the configured
+   * threshold. Tokens are paid out later by `processExitQueue`.
+   */
+  async queueUnstake(
+    amount: bigint | null,
+    rewardsVault: PublicKey,
+    userTokenAccount: PublicKey,
+    user: Keypair,
+    position = 0,
+    receiptOwner?: PublicKey
+  ): Promise<TransactionResult> {
+    const owner = receiptOwner ?? user.publicKey;
+    const stakePDA = this.getStakePDA(owner, position);
+    const receiptMint = this.getReceiptMintPDA(stakePDA.publicKey).publicKey;
+    const receiptKeys = (
+      receiptOwner
+        ? [receiptMint, getAssociatedTokenAddressSync(receiptMint, user.publicKey, false, TOKEN_2022_PROGRAM_ID)]
+        : [this.programId, this.programId]
+    ).map((pubkey) => ({ pubkey, isSigner: false, isWritable: !!receiptOwner }));
+
+    const queuePDA = this.getExitQueuePDA();
+    const queueInfo = await this.connection.getAccountInfo(queuePDA.publicKey);
+    if (!queueInfo) {
+      return { signature: '', success: false, error: 'Exit queue not configured' };
+    }
+    const tail = queueInfo.data.readBigUInt64LE(16);
+
+    const data = Buffer.alloc(8 + 1 + (amount ? 8 : 0));
+    data.writeBigUInt64LE(BigInt('0x5b5b5b5b5b5b5b5b'), 0);
+    
+    if (amount) {
+      data.writeUInt8(1, 8); // Some
+      data.writeBigUInt64LE(amount, 9);
+    } else {
+      data.writeUInt8(0, 8); // None
+    }
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: stakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getPositionsPDA(owner).publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getEmissionsPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: queuePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getExitTicketPDA(tail).publicKey, isSigner: false, isWritable: true },
+        { pubkey: rewardsVault, isSigner: false, isWritable: true },
+        { pubkey: userTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: user.publicKey, isSigner: true, isWritable: true },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        ...receiptKeys,
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [user]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Pay out the ticket at the head of the exit queue (permissionless)
+   */
+  async processExitQueue(
+    stakeVault: PublicKey,
+    rewardsVault: PublicKey,
+    payer: Keypair
+  ): Promise<TransactionResult> {
+    const queuePDA = this.getExitQueuePDA();
+    const queueInfo = await this.connection.getAccountInfo(queuePDA.publicKey);
+    if (!queueInfo) {
+      return { signature: '', success: false, error: 'Exit queue not configured' };
+    }
+    const ticketPDA = this.getExitTicketPDA(queueInfo.data.readBigUInt64LE(8));
+    const ticketInfo = await this.connection.getAccountInfo(ticketPDA.publicKey);
+    if (!ticketInfo) {
+      return { signature: '', success: false, error: 'Exit queue is empty' };
+    }
+    const owner = new PublicKey(ticketInfo.data.slice(16, 48));
+
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x5c5c5c5c5c5c5c5c'), 0);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getEmissionsPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: queuePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: ticketPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: owner, isSigner: false, isWritable: true },
+        {
+          pubkey: getAssociatedTokenAddressSync(this.getMintPDA().publicKey, owner, false, TOKEN_2022_PROGRAM_ID),
+          isSigner: false,
+          isWritable: true,
+        },
+        { pubkey: stakeVault, isSigner: false, isWritable: true },
+        { pubkey: rewardsVault, isSigner: false, isWritable: true },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Create operator stake
+   */
+  async createOperatorStake(
+    amount: bigint,
+    operatorVault: PublicKey,
+    operatorTokenAccount: PublicKey,
+    operator: Keypair
+  ): Promise<TransactionResult> {
+    const configPDA = this.getConfigPDA();
+    const operatorStakePDA = this.getOperatorStakePDA(operator.publicKey);
+
+    const data = Buffer.alloc(8 + 8);
+    data.writeBigUInt64LE(BigInt('0x4444444444444444'), 0);
+    data.writeBigUInt64LE(amount, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: operatorStakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: operatorVault, isSigner: false, isWritable: true },
+        { pubkey: operatorTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: true },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Post a collective bond for the leader's swarm, slashable when the swarm
+   * fails a group task
+   */
+  async createSwarmStake(
+    amount: bigint,
+    swarmVault: PublicKey,
+    leaderTokenAccount: PublicKey,
+    leader: Keypair
+  ): Promise<TransactionResult> {
+    const [swarm] = PublicKey.findProgramAddressSync(
+      [Buffer.from('swarm'), leader.publicKey.toBuffer()],
+      PROGRAM_IDS.SWARM_COORDINATOR
+    );
+
+    const data = Buffer.alloc(8 + 8);
+    data.writeBigUInt64LE(BigInt('0x5959595959595959'), 0);
+    data.writeBigUInt64LE(amount, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: swarm, isSigner: false, isWritable: false },
+        { pubkey: this.getSwarmStakePDA(swarm).publicKey, isSigner: false, isWritable: true },
+        { pubkey: swarmVault, isSigner: false, isWritable: true },
+        { pubkey: leaderTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: leader.publicKey, isSigner: true, isWritable: true },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [leader]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Add tokens to an existing operator stake
+   */
+  async topUpOperatorStake(
+    amount: bigint,
+    operatorVault: PublicKey,
+    operatorTokenAccount: PublicKey,
+    operator: Keypair
+  ): Promise<TransactionResult> {
+    const configPDA = this.getConfigPDA();
+    const operatorStakePDA = this.getOperatorStakePDA(operator.publicKey);
+
+    const data = Buffer.alloc(8 + 8);
+    data.writeBigUInt64LE(BigInt('0x4747474747474747'), 0);
+    data.writeBigUInt64LE(amount, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: operatorStakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: operatorVault, isSigner: false, isWritable: true },
+        { pubkey: operatorTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Delegate tokens to an operator, adding to its bond and earning a share
+   * of its income
+   */
+  async delegateStake(
+    operator: PublicKey,
+    amount: bigint,
+    operatorVault: PublicKey,
+    delegatorTokenAccount: PublicKey,
+    delegator: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8 + 8);
+    data.writeBigUInt64LE(BigInt('0x5151515151515151'), 0);
+    data.writeBigUInt64LE(amount, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getOperatorStakePDA(operator).publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getDelegationPDA(operator, delegator.publicKey).publicKey, isSigner: false, isWritable: true },
+        { pubkey: operatorVault, isSigner: false, isWritable: true },
+        { pubkey: delegatorTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: delegator.publicKey, isSigner: true, isWritable: true },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [delegator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Start the 7-day cooldown on delegated shares
+   */
+  async undelegateStake(operator: PublicKey, shares: bigint, delegator: Keypair): Promise<TransactionResult> {
+    const data = Buffer.alloc(8 + 8);
+    data.writeBigUInt64LE(BigInt('0x5252525252525252'), 0);
+    data.writeBigUInt64LE(shares, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getDelegationPDA(operator, delegator.publicKey).publicKey, isSigner: false, isWritable: true },
+        { pubkey: delegator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [delegator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Start the 7-day unbonding of part or all of an operator stake
+   */
+  async requestOperatorUnstake(amount: bigint, operator: Keypair): Promise<TransactionResult> {
+    const operatorStakePDA = this.getOperatorStakePDA(operator.publicKey);
+
+    const data = Buffer.alloc(8 + 8);
+    data.writeBigUInt64LE(BigInt('0x4545454545454545'), 0);
+    data.writeBigUInt64LE(amount, 8);
+
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: operatorStakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
+  }
+
+  /**
+   * Withdraw unbonded operator stake
+   */
+  async finalizeOperatorUnstake(
+    operatorVault: PublicKey,
+    operatorTokenAccount: PublicKey,
+    operator: Keypair
+  ): Promise<TransactionResult> {
+    const configPDA = this.getConfigPDA();
+    const operatorStakePDA = this.getOperatorStakePDA(operator.publicKey);
 
-  getConfigPDA(): PDAResult {
-    const [publicKey, bump] = PublicKey.findProgramAddressSync(
-      [Buffer.from('config')],
-      this.programId
-    );
-    return { publicKey, bump };
-  }
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x4646464646464646'), 0);
 
-  getMintPDA(): PDAResult {
-    const [publicKey, bump] = PublicKey.findProgramAddressSync(
-      [Buffer.from('mint')],
-      this.programId
-    );
-    return { publicKey, bump };
-  }
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: operatorStakePDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: operatorVault, isSigner: false, isWritable: true },
+        { pubkey: operatorTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: false },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
 
-  getStakePDA(user: PublicKey): PDAResult {
-    const [publicKey, bump] = PublicKey.findProgramAddressSync(
-      [Buffer.from('stake'), user.toBuffer()],
-      this.programId
-    );
-    return { publicKey, bump };
-  }
+    const transaction = new Transaction().add(instruction);
 
-  getOperatorStakePDA(operator: PublicKey): PDAResult {
-    const [publicKey, bump] = PublicKey.findProgramAddressSync(
-      [Buffer.from('operator'), operator.toBuffer()],
-      this.programId
-    );
-    return { publicKey, bump };
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
+    }
   }
 
-  // ============================================================================
-  // STAKING OPERATIONS
-  // ============================================================================
-
   /**
-   * Stake tokens
+   * Appeal the operator's latest slash, posting the appeal bond. The appeal is
+   * then argued as an oracle-verifier dispute.
    */
-  async stake(
-    params: StakeParams,
-    stakeVault: PublicKey,
-    userTokenAccount: PublicKey,
-    user: Keypair
+  async appealSlash(
+    appealVault: PublicKey,
+    operatorTokenAccount: PublicKey,
+    operator: Keypair
   ): Promise<TransactionResult> {
-    if (params.amount < BigInt(MIN_STAKE)) {
-      return { 
-        signature: '', 
-        success: false, 
-        error: `Minimum stake is ${MIN_STAKE / 1_000_000} DRON` 
-      };
+    const operatorStake = await this.getOperatorStake(operator.publicKey);
+    if (!operatorStake || operatorStake.lastSlashAt === null) {
+      return { signature: '', success: false, error: 'No slash to appeal' };
     }
 
-    const configPDA = this.getConfigPDA();
-    const stakePDA = this.getStakePDA(user.publicKey);
-
-    const data = Buffer.alloc(8 + 8 + 2);
-    data.writeBigUInt64LE(BigInt('0x1111111111111111'), 0);
-    data.writeBigUInt64LE(params.amount, 8);
-    data.writeUInt16LE(params.lockDays, 16);
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x4848484848484848'), 0);
 
     const instruction = {
       programId: this.programId,
       keys: [
-        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: stakePDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: stakeVault, isSigner: false, isWritable: true },
-        { pubkey: userTokenAccount, isSigner: false, isWritable: true },
-        { pubkey: user.publicKey, isSigner: true, isWritable: true },
-        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: this.getOperatorStakePDA(operator.publicKey).publicKey, isSigner: false, isWritable: false },
+        {
+          pubkey: this.getSlashAppealPDA(operator.publicKey, operatorStake.lastSlashAt).publicKey,
+          isSigner: false,
+          isWritable: true,
+        },
+        { pubkey: appealVault, isSigner: false, isWritable: true },
+        { pubkey: operatorTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: operator.publicKey, isSigner: true, isWritable: true },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       ],
       data,
@@ -117,7 +1227,7 @@ export class TokenClient {
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [user]);
+      const signature = await this.connection.sendTransaction(transaction, [operator]);
       await this.connection.confirmTransaction(signature, 'confirmed');
       return { signature, success: true };
     } catch (error) {
@@ -126,28 +1236,44 @@ export class TokenClient {
   }
 
   /**
-   * Claim staking rewards
+   * File an insurance claim for a task that failed through the robot's fault
+   * (by the task's creator)
    */
-  async claimRewards(
-    rewardsVault: PublicKey,
-    userTokenAccount: PublicKey,
-    user: Keypair
+  async fileClaim(
+    task: PublicKey,
+    amount: bigint,
+    reason: string,
+    claimant: Keypair
   ): Promise<TransactionResult> {
-    const configPDA = this.getConfigPDA();
-    const stakePDA = this.getStakePDA(user.publicKey);
+    const poolPDA = this.getInsurancePoolPDA();
+    const poolInfo = await this.connection.getAccountInfo(poolPDA.publicKey);
+    if (!poolInfo) {
+      return { signature: '', success: false, error: 'Insurance pool not found' };
+    }
+    // vault, balance, total_received, total_paid
+    const claimId = poolInfo.data.readBigUInt64LE(8 + 32 + 8 + 8 + 8);
+    const claimIdBytes = Buffer.alloc(8);
+    claimIdBytes.writeBigUInt64LE(claimId);
+    const [claimPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('claim'), claimIdBytes],
+      this.programId
+    );
 
-    const data = Buffer.alloc(8);
-    data.writeBigUInt64LE(BigInt('0x2222222222222222'), 0);
+    const reasonBytes = Buffer.from(reason);
+    const data = Buffer.alloc(8 + 8 + 4 + reasonBytes.length);
+    data.writeBigUInt64LE(BigInt('0x4949494949494949'), 0);
+    data.writeBigUInt64LE(amount, 8);
+    data.writeUInt32LE(reasonBytes.length, 16);
+    reasonBytes.copy(data, 20);
 
     const instruction = {
       programId: this.programId,
       keys: [
-        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: stakePDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: rewardsVault, isSigner: false, isWritable: true },
-        { pubkey: userTokenAccount, isSigner: false, isWritable: true },
-        { pubkey: user.publicKey, isSigner: true, isWritable: false },
-        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: poolPDA.publicKey, isSigner: false, isWritable: true },
+        { pubkey: claimPDA, isSigner: false, isWritable: true },
+        { pubkey: task, isSigner: false, isWritable: false },
+        { pubkey: claimant.publicKey, isSigner: true, isWritable: true },
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       ],
       data,
     };
@@ -155,7 +1281,7 @@ export class TokenClient {
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [user]);
+      const signature = await this.connection.sendTransaction(transaction, [claimant]);
       await this.connection.confirmTransaction(signature, 'confirmed');
       return { signature, success: true };
     } catch (error) {
@@ -164,38 +1290,66 @@ export class TokenClient {
   }
 
   /**
-   * Unstake tokens
+   * Pay protocol fees into the treasury; the configured burn share is burned
    */
-  async unstake(
-    amount: bigint | null,
-    stakeVault: PublicKey,
-    rewardsVault: PublicKey,
-    userTokenAccount: PublicKey,
-    user: Keypair
+  async depositFees(
+    amount: bigint,
+    treasury: PublicKey,
+    payerTokenAccount: PublicKey,
+    payer: Keypair
   ): Promise<TransactionResult> {
-    const configPDA = this.getConfigPDA();
-    const stakePDA = this.getStakePDA(user.publicKey);
+    const data = Buffer.alloc(8 + 8);
+    data.writeBigUInt64LE(BigInt('0x5050505050505050'), 0);
+    data.writeBigUInt64LE(amount, 8);
 
-    const data = Buffer.alloc(8 + 1 + (amount ? 8 : 0));
-    data.writeBigUInt64LE(BigInt('0x3333333333333333'), 0);
-    
-    if (amount) {
-      data.writeUInt8(1, 8); // Some
-      data.writeBigUInt64LE(amount, 9);
-    } else {
-      data.writeUInt8(0, 8); // None
+    const instruction = {
+      programId: this.programId,
+      keys: [
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: treasury, isSigner: false, isWritable: true },
+        { pubkey: payerTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+      ],
+      data,
+    };
+
+    const transaction = new Transaction().add(instruction);
+
+    try {
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
+      await this.connection.confirmTransaction(signature, 'confirmed');
+      return { signature, success: true };
+    } catch (error) {
+      return { signature: '', success: false, error: (error as Error).message };
     }
+  }
+
+  /**
+   * Fund the rewards vault with the next started emission epoch's budget
+   * from the treasury; the caller is paid a small tip
+   */
+  async distributeEpochRewards(
+    treasury: PublicKey,
+    rewardsVault: PublicKey,
+    crankerTokenAccount: PublicKey,
+    cranker: Keypair
+  ): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x5555555555555555'), 0);
 
     const instruction = {
       programId: this.programId,
       keys: [
-        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: stakePDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: stakeVault, isSigner: false, isWritable: true },
+        { pubkey: this.getConfigPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: this.getEmissionsPDA().publicKey, isSigner: false, isWritable: true },
+        { pubkey: treasury, isSigner: false, isWritable: true },
         { pubkey: rewardsVault, isSigner: false, isWritable: true },
-        { pubkey: userTokenAccount, isSigner: false, isWritable: true },
-        { pubkey: user.publicKey, isSigner: true, isWritable: false },
-        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: crankerTokenAccount, isSigner: false, isWritable: true },
+        { pubkey: cranker.publicKey, isSigner: true, isWritable: false },
+        { pubkey: this.getMintPDA().publicKey, isSigner: false, isWritable: false },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
       ],
       data,
     };
@@ -203,7 +1357,7 @@ export class TokenClient {
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [user]);
+      const signature = await this.connection.sendTransaction(transaction, [cranker]);
       await this.connection.confirmTransaction(signature, 'confirmed');
       return { signature, success: true };
     } catch (error) {
@@ -212,30 +1366,18 @@ export class TokenClient {
   }
 
   /**
-   * Create operator stake
+   * Upgrade a stake or operator stake account with an older layout in place;
+   * the payer covers any extra rent
    */
-  async createOperatorStake(
-    amount: bigint,
-    operatorVault: PublicKey,
-    operatorTokenAccount: PublicKey,
-    operator: Keypair
-  ): Promise<TransactionResult> {
-    const configPDA = this.getConfigPDA();
-    const operatorStakePDA = this.getOperatorStakePDA(operator.publicKey);
-
-    const data = Buffer.alloc(8 + 8);
-    data.writeBigUInt64LE(BigInt('0x4444444444444444'), 0);
-    data.writeBigUInt64LE(amount, 8);
+  async migrateStakeAccount(account: PublicKey, payer: Keypair): Promise<TransactionResult> {
+    const data = Buffer.alloc(8);
+    data.writeBigUInt64LE(BigInt('0x5858585858585858'), 0);
 
     const instruction = {
       programId: this.programId,
       keys: [
-        { pubkey: configPDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: operatorStakePDA.publicKey, isSigner: false, isWritable: true },
-        { pubkey: operatorVault, isSigner: false, isWritable: true },
-        { pubkey: operatorTokenAccount, isSigner: false, isWritable: true },
-        { pubkey: operator.publicKey, isSigner: true, isWritable: true },
-        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: account, isSigner: false, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       ],
       data,
@@ -244,7 +1386,7 @@ export class TokenClient {
     const transaction = new Transaction().add(instruction);
 
     try {
-      const signature = await this.connection.sendTransaction(transaction, [operator]);
+      const signature = await this.connection.sendTransaction(transaction, [payer]);
       await this.connection.confirmTransaction(signature, 'confirmed');
       return { signature, success: true };
     } catch (error) {
@@ -259,13 +1401,82 @@ export class TokenClient {
   /**
    * Get stake account
    */
-  async getStake(user: PublicKey): Promise<StakeAccount | null> {
-    const stakePDA = this.getStakePDA(user);
+  async getStake(user: PublicKey, position = 0): Promise<StakeAccount | null> {
+    const stakePDA = this.getStakePDA(user, position);
     const accountInfo = await this.connection.getAccountInfo(stakePDA.publicKey);
     if (!accountInfo) return null;
     return this.decodeStakeAccount(accountInfo.data);
   }
 
+  /**
+   * Current base APY and stake minimums, falling back to the launch
+   * defaults before the token is initialized
+   */
+  async getStakingParameters(): Promise<StakingParameters> {
+    const accountInfo = await this.connection.getAccountInfo(this.getConfigPDA().publicKey);
+    if (!accountInfo) {
+      return {
+        baseApyBps: BASE_APY_BPS,
+        minStake: BigInt(MIN_STAKE),
+        minOperatorStake: BigInt(MIN_OPERATOR_STAKE),
+      };
+    }
+    // authority, mint, four u64 counters, insurance share, reputation recovery
+    const offset = 8 + 32 + 32 + 8 * 4 + 2 + 8 + 2 + 2;
+    return {
+      baseApyBps: accountInfo.data.readUInt16LE(offset),
+      minStake: accountInfo.data.readBigUInt64LE(offset + 2),
+      minOperatorStake: accountInfo.data.readBigUInt64LE(offset + 10),
+    };
+  }
+
+  /**
+   * Number of stake positions a wallet has opened; the next one gets this index
+   */
+  async getPositionCount(user: PublicKey): Promise<number> {
+    const accountInfo = await this.connection.getAccountInfo(this.getPositionsPDA(user).publicKey);
+    if (!accountInfo) return 0;
+    return accountInfo.data.readUInt32LE(8 + 32);
+  }
+
+  /**
+   * Lock periods currently offered and their reward multipliers
+   */
+  async getLockTiers(): Promise<LockTier[]> {
+    const accountInfo = await this.connection.getAccountInfo(this.getLockTiersPDA().publicKey);
+    if (!accountInfo) return [];
+
+    const count = accountInfo.data.readUInt32LE(8);
+    const tiers: LockTier[] = [];
+    for (let i = 0; i < count; i++) {
+      const offset = 8 + 4 + i * 4;
+      tiers.push({
+        days: accountInfo.data.readUInt16LE(offset),
+        multiplierBps: accountInfo.data.readUInt16LE(offset + 2),
+      });
+    }
+    return tiers;
+  }
+
+  /**
+   * Current veDRONEOS voting power, decayed from the last update
+   */
+  async getVotingPower(user: PublicKey): Promise<bigint> {
+    const accountInfo = await this.connection.getAccountInfo(this.getVotingPowerPDA(user).publicKey);
+    if (!accountInfo) return BigInt(0);
+
+    let offset = 8 + 32;
+    const power = accountInfo.data.readBigUInt64LE(offset);
+    offset += 8;
+    const slope = accountInfo.data.readBigUInt64LE(offset);
+    offset += 8 + 8;
+    const updatedAt = Number(accountInfo.data.readBigInt64LE(offset));
+
+    const elapsed = BigInt(Math.max(Math.floor(Date.now() / 1000) - updatedAt, 0));
+    const decay = (slope * elapsed) / BigInt(SECONDS_PER_YEAR);
+    return decay >= power ? BigInt(0) : power - decay;
+  }
+
   /**
    * Get operator stake account
    */
@@ -276,6 +1487,23 @@ export class TokenClient {
     return this.decodeOperatorStakeAccount(accountInfo.data);
   }
 
+  /**
+   * Get an operator's slash history, oldest first
+   */
+  async getSlashHistory(operator: PublicKey): Promise<SlashRecord[]> {
+    const stake = await this.getOperatorStake(operator);
+    if (!stake) return [];
+
+    const keys = [];
+    for (let i = BigInt(0); i < stake.slashCount; i++) {
+      keys.push(this.getSlashRecordPDA(operator, i).publicKey);
+    }
+    const accounts = await this.connection.getMultipleAccountsInfo(keys);
+    return accounts
+      .filter((account): account is NonNullable<typeof account> => account !== null)
+      .map((account) => this.decodeSlashRecord(account.data));
+  }
+
   /**
    * Calculate pending rewards
    */
@@ -283,40 +1511,37 @@ export class TokenClient {
     const stake = await this.getStake(user);
     if (!stake) return BigInt(0);
 
+    const { baseApyBps } = await this.getStakingParameters();
     const now = Math.floor(Date.now() / 1000);
-    return this.calculateRewards(stake, now);
+    return this.calculateRewards(stake, now, baseApyBps);
   }
 
   /**
    * Calculate rewards for a stake position
    */
-  calculateRewards(stake: StakeAccount, currentTime: number): bigint {
+  calculateRewards(stake: StakeAccount, currentTime: number, baseApyBps = BASE_APY_BPS): bigint {
     const elapsed = BigInt(currentTime - stake.lastClaimAt);
     
     // Base reward
-    const baseReward = (stake.amount * BigInt(BASE_APY_BPS) * elapsed) / 
+    const baseReward = (stake.amount * BigInt(baseApyBps) * elapsed) / 
       (BigInt(10000) * BigInt(SECONDS_PER_YEAR));
     
     // Apply multiplier
     const multipliedReward = (baseReward * BigInt(stake.multiplier)) / BigInt(10000);
+
+    // Apply reputation boost
+    const boostedReward = (multipliedReward * BigInt(10000 + stake.reputationBoostBps)) / BigInt(10000);
     
-    return multipliedReward;
+    return boostedReward + stake.settledRewards;
   }
 
   /**
-   * Get APY for lock duration
+   * Get APY for lock duration. Pass the live tiers from `getLockTiers`;
+   * the launch tiers are used otherwise.
    */
-  getAPY(lockDays: number): number {
-    const multipliers: Record<number, number> = {
-      0: 10000,
-      30: 11000,
-      90: 12500,
-      180: 15000,
-      365: 20000,
-    };
-    
-    const multiplier = multipliers[lockDays] || 10000;
-    return (BASE_APY_BPS * multiplier) / 1000000; // Returns percentage
+  getAPY(lockDays: number, baseApyBps = BASE_APY_BPS, tiers: LockTier[] = LAUNCH_LOCK_TIERS): number {
+    const multiplier = tiers.find((tier) => tier.days === lockDays)?.multiplierBps || 10000;
+    return (baseApyBps * multiplier) / 1000000; // Returns percentage
   }
 
   /**
@@ -327,7 +1552,7 @@ export class TokenClient {
     if (!stake) return false;
     
     const now = Math.floor(Date.now() / 1000);
-    return now < stake.lockUntil;
+    return stake.autoRelock || now < stake.lockUntil;
   }
 
   // ============================================================================
@@ -360,9 +1585,15 @@ export class TokenClient {
   private decodeStakeAccount(data: Buffer): StakeAccount {
     let offset = 8;
 
+    const version = data.readUInt8(offset);
+    offset += 1;
+
     const owner = new PublicKey(data.slice(offset, offset + 32));
     offset += 32;
 
+    const index = data.readUInt32LE(offset);
+    offset += 4;
+
     const amount = data.readBigUInt64LE(offset);
     offset += 8;
 
@@ -382,9 +1613,30 @@ export class TokenClient {
     offset += 8;
 
     const lastClaimAt = Number(data.readBigInt64LE(offset));
+    offset += 8;
+
+    const settledRewards = data.readBigUInt64LE(offset);
+    offset += 8;
+
+    const receiptMint = data.readUInt8(offset) === 1
+      ? new PublicKey(data.slice(offset + 1, offset + 33))
+      : null;
+    offset += receiptMint ? 33 : 1;
+
+    const autoRelock = data.readUInt8(offset) === 1;
+    offset += 1;
+
+    const boostRobot = data.readUInt8(offset) === 1
+      ? new PublicKey(data.slice(offset + 1, offset + 33))
+      : null;
+    offset += boostRobot ? 33 : 1;
+
+    const reputationBoostBps = data.readUInt16LE(offset);
 
     return {
+      version,
       owner,
+      index,
       amount,
       stakedAt,
       lockDuration,
@@ -392,12 +1644,20 @@ export class TokenClient {
       multiplier,
       accumulatedRewards,
       lastClaimAt,
+      settledRewards,
+      receiptMint,
+      autoRelock,
+      boostRobot,
+      reputationBoostBps,
     };
   }
 
   private decodeOperatorStakeAccount(data: Buffer): OperatorStakeAccount {
     let offset = 8;
 
+    const version = data.readUInt8(offset);
+    offset += 1;
+
     const operator = new PublicKey(data.slice(offset, offset + 32));
     offset += 32;
 
@@ -416,14 +1676,93 @@ export class TokenClient {
     if (hasLastSlash) offset += 8;
 
     const reputation = data.readUInt16LE(offset);
+    offset += 2;
+
+    // last_slash_amount, last_slash_reputation_loss, reputation_recovered_at
+    offset += 8 + 2 + 8;
+
+    const activeTasks = data.readUInt32LE(offset);
+    offset += 4;
+
+    const unbondingAmount = data.readBigUInt64LE(offset);
+    offset += 8;
+
+    const isUnbonding = data.readUInt8(offset) === 1;
+    offset += 1;
+    const unbondingStartedAt = isUnbonding ? Number(data.readBigInt64LE(offset)) : null;
+    if (isUnbonding) offset += 8;
+
+    const delegatedAmount = data.readBigUInt64LE(offset);
+    offset += 8;
+
+    const delegationShares = data.readBigUInt64LE(offset);
+    offset += 8;
+
+    const delegatorCount = data.readUInt32LE(offset);
+    offset += 4;
+
+    const delegatorShareBps = data.readUInt16LE(offset);
+    offset += 2;
+
+    // income_per_share, last_slash_delegated
+    offset += 16 + 8;
+
+    const slashCount = data.readBigUInt64LE(offset);
+    offset += 8;
+
+    // Task bonds arrived with version 3
+    const lockedTaskBonds = version >= 3 ? data.readBigUInt64LE(offset) : BigInt(0);
 
     return {
+      version,
       operator,
       totalStaked,
       slashableAmount,
       createdAt,
       lastSlashAt,
       reputation,
+      activeTasks,
+      unbondingAmount,
+      unbondingStartedAt,
+      delegatedAmount,
+      delegationShares,
+      delegatorCount,
+      delegatorShareBps,
+      slashCount,
+      lockedTaskBonds,
     };
   }
+
+  private decodeSlashRecord(data: Buffer): SlashRecord {
+    let offset = 8;
+
+    const operator = new PublicKey(data.slice(offset, offset + 32));
+    offset += 32;
+
+    const index = data.readBigUInt64LE(offset);
+    offset += 8;
+
+    const amount = data.readBigUInt64LE(offset);
+    offset += 8;
+
+    const fromDelegated = data.readBigUInt64LE(offset);
+    offset += 8;
+
+    const reasonLen = data.readUInt32LE(offset);
+    offset += 4;
+    const reason = data.slice(offset, offset + reasonLen).toString('utf8');
+    offset += reasonLen;
+
+    const task = data.readUInt8(offset) === 1
+      ? new PublicKey(data.slice(offset + 1, offset + 33))
+      : null;
+    offset += task ? 33 : 1;
+
+    const slasher = new PublicKey(data.slice(offset, offset + 32));
+    offset += 32;
+
+    const slashedAt = Number(data.readBigInt64LE(offset));
+
+    return { operator, index, amount, fromDelegated, reason, task, slasher, slashedAt };
+  }
 }
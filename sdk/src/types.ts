@@ -82,6 +82,8 @@ export enum StreamStatus {
   Completed = 3,
   Cancelled = 4,
   Disputed = 5,
+  Grace = 6,
+  Accepted = 7,
 }
 
 export interface PaymentStreamAccount {
@@ -103,16 +105,29 @@ export interface PaymentStreamAccount {
 
 export interface CreateStreamParams {
   payee: PublicKey;
+  /** Distinguishes concurrent streams between the same payer and payee */
+  nonce: bigint;
   ratePerSecond: bigint;
   maxDuration: number;
   gracePeriod?: number;
   autoTerminate?: boolean;
+  securityDeposit?: bigint;
+  activationFee?: bigint;
+  earlyTermination?: { minRuntime: number; penaltyBps: number };
+  payerStake?: PublicKey;
+  referrer?: PublicKey;
 }
 
 // ============================================================================
 // TASK MARKET TYPES
 // ============================================================================
 
+/** What a task index groups tasks by */
+export enum TaskIndexKind {
+  Class = 0,
+  Capability = 1,
+}
+
 export enum TaskStatus {
   Open = 0,
   Assigned = 1,
@@ -130,6 +145,20 @@ export enum BidStatus {
   Rejected = 2,
   Withdrawn = 3,
   Expired = 4,
+  Committed = 5,
+}
+
+/** oracle-verifier proof types a milestone can require */
+export enum ProofType {
+  GPS = 0,
+  Completion = 1,
+  Sensor = 2,
+}
+
+export interface MilestoneTerms {
+  /** Share of the task's escrow, in basis points; all shares total 10000 */
+  shareBps: number;
+  proofType?: ProofType;
 }
 
 export interface TaskAccount {
@@ -164,6 +193,7 @@ export interface BidAccount {
   message: string;
   status: BidStatus;
   submittedAt: number;
+  validUntil: number;
 }
 
 export interface CreateTaskParams {
@@ -177,12 +207,27 @@ export interface CreateTaskParams {
   estimatedDuration: number;
   priority?: number;
   expiresIn: number;
+  /** Approve completion only with a verified oracle-verifier proof */
+  requiresProof?: boolean;
+}
+
+/** Fields of an open task without bids to change; omitted ones are kept */
+export interface UpdateTaskParams {
+  description?: string;
+  reward?: bigint;
+  /** Seconds from now */
+  expiresIn?: number;
+  capabilities?: Capability[];
 }
 
 export interface SubmitBidParams {
   proposedRate: bigint;
   estimatedDuration: number;
   message?: string;
+  /** Seconds the bid stays open for acceptance (default 1 day) */
+  validFor?: number;
+  /** Merkle proof for allowlisted tasks the bidder isn't listed on directly */
+  allowlistProof?: Buffer[];
 }
 
 // ============================================================================
@@ -190,7 +235,9 @@ export interface SubmitBidParams {
 // ============================================================================
 
 export interface StakeAccount {
+  version: number;
   owner: PublicKey;
+  index: number;
   amount: bigint;
   stakedAt: number;
   lockDuration: number;
@@ -198,20 +245,60 @@ export interface StakeAccount {
   multiplier: number;
   accumulatedRewards: bigint;
   lastClaimAt: number;
+  settledRewards: bigint;
+  receiptMint: PublicKey | null;
+  autoRelock: boolean;
+  boostRobot: PublicKey | null;
+  reputationBoostBps: number;
 }
 
 export interface OperatorStakeAccount {
+  version: number;
   operator: PublicKey;
   totalStaked: bigint;
   slashableAmount: bigint;
   createdAt: number;
   lastSlashAt: number | null;
   reputation: number;
+  activeTasks: number;
+  unbondingAmount: bigint;
+  unbondingStartedAt: number | null;
+  delegatedAmount: bigint;
+  delegationShares: bigint;
+  delegatorCount: number;
+  delegatorShareBps: number;
+  slashCount: bigint;
+  /** Stake held as performance bonds for assigned tasks */
+  lockedTaskBonds: bigint;
+}
+
+export interface SlashRecord {
+  operator: PublicKey;
+  index: bigint;
+  amount: bigint;
+  fromDelegated: bigint;
+  reason: string;
+  task: PublicKey | null;
+  slasher: PublicKey;
+  slashedAt: number;
+}
+
+export interface LockTier {
+  days: number;
+  multiplierBps: number;
+}
+
+export interface StakingParameters {
+  baseApyBps: number;
+  minStake: bigint;
+  minOperatorStake: bigint;
 }
 
 export interface StakeParams {
   amount: bigint;
   lockDays: number;
+  /** Also mint a receipt NFT for the position */
+  mintReceipt?: boolean;
 }
 
 // ============================================================================
@@ -0,0 +1,41 @@
+import * as anchor from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { TokenSetup, expectError, programs, setupToken, stakedOperator } from "./helpers";
+
+/**
+ * Account migration: stake and operator accounts carry a layout version,
+ * and anyone can migrate an old layout in place. Accounts already on the
+ * current layout, or of any other kind, are refused.
+ */
+describe("DRONEOS Token: account migration", () => {
+  const { droneosToken } = programs();
+  const payer = anchor.getProvider().publicKey!;
+
+  let t: TokenSetup;
+  let operatorStake: PublicKey;
+
+  function migrateStakeAccount(account: PublicKey) {
+    return droneosToken.methods.migrateStakeAccount().accountsPartial({ account, payer }).rpc();
+  }
+
+  before(async () => {
+    t = await setupToken();
+    ({ operatorStake } = await stakedOperator());
+  });
+
+  it("creates operator stakes on the current layout", async () => {
+    const { version } = await droneosToken.account.operatorStake.fetch(operatorStake);
+    expect(version).to.equal(3);
+
+    await expectError(migrateStakeAccount(operatorStake), "AccountUpToDate");
+  });
+
+  it("rejects accounts that aren't stakes", async () => {
+    await expectError(migrateStakeAccount(t.config), "InvalidAccountVersion");
+  });
+
+  it("rejects accounts the program doesn't own", async () => {
+    await expectError(migrateStakeAccount(t.treasury), "InvalidAccountVersion");
+  });
+});
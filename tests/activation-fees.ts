@@ -0,0 +1,56 @@
+import * as anchor from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  acceptStream,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  terminateStream,
+} from "./helpers";
+
+/**
+ * Activation fees: escrowed apart at creation and paid to the payee once,
+ * when the stream starts, or refunded to the payer if it never does.
+ */
+describe("Payment Streams: activation fees", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const FEE = 20_000;
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token)).amount);
+  }
+
+  it("rejects a fee the payer can't cover", async () => {
+    await expectError(openStream({ activationFee: 2_000_000_000 }), "InsufficientFunds");
+  });
+
+  it("pays the fee out once when the stream starts", async () => {
+    const s = await openStream({ activationFee: FEE });
+    await acceptStream(s).rpc();
+    const payeeBefore = await balance(s.payeeToken);
+    const treasuryBefore = await balance(s.treasury);
+    await startStream(s).rpc();
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const paid = (await balance(s.payeeToken)) - payeeBefore + (await balance(s.treasury)) - treasuryBefore;
+    expect(paid).to.equal(FEE);
+    expect(account.totalPaid.toNumber()).to.equal(FEE);
+    expect(account.activationFee.toNumber()).to.equal(0);
+  });
+
+  it("refunds the fee if the stream never starts", async () => {
+    const s = await openStream({ activationFee: FEE });
+    await acceptStream(s).rpc();
+    const { escrowBalance } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const payerBefore = await balance(s.payerToken);
+    await terminateStream(s).rpc();
+
+    expect((await balance(s.payerToken)) - payerBefore).to.equal(escrowBalance.toNumber() + FEE);
+    expect(await balance(s.escrow)).to.equal(0);
+  });
+});
@@ -0,0 +1,85 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  createTask,
+  expectError,
+  fund,
+  programs,
+  registerRobot,
+  setupMarket,
+  submitBid,
+} from "./helpers";
+
+/**
+ * Allowlisted tasks: before the first bid a creator can restrict bidding to
+ * listed robots or operators, or to those proving membership of a merkle
+ * root, and open the task again with an empty list.
+ */
+describe("Task Market: allowlisted tasks", () => {
+  const { taskMarket } = programs();
+
+  const creator = Keypair.generate();
+  const listedOperator = Keypair.generate();
+  const robotOperator = Keypair.generate();
+  const outsider = Keypair.generate();
+  let listedRobot: PublicKey;
+  let listedOperatorRobot: PublicKey;
+  let outsiderRobot: PublicKey;
+
+  function setAllowlist(task: PublicKey, members: PublicKey[], root: number[] | null = null, signer = creator) {
+    return taskMarket.methods
+      .setAllowlist(members, root)
+      .accountsPartial({ task, creator: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(creator, listedOperator, robotOperator, outsider);
+    await setupMarket();
+    listedRobot = await registerRobot(robotOperator);
+    listedOperatorRobot = await registerRobot(listedOperator);
+    outsiderRobot = await registerRobot(outsider);
+  });
+
+  it("rejects allowlists set by anyone but the creator", async () => {
+    const task = await createTask(creator);
+    await expectError(setAllowlist(task, [listedRobot], null, outsider), "Unauthorized");
+  });
+
+  it("rejects more members than the task holds", async () => {
+    const task = await createTask(creator);
+    const members = Array.from({ length: 11 }, () => Keypair.generate().publicKey);
+    await expectError(setAllowlist(task, members), "AllowlistTooLarge");
+  });
+
+  it("takes bids only from listed robots and operators", async () => {
+    const task = await createTask(creator);
+    await setAllowlist(task, [listedRobot, listedOperator.publicKey]);
+
+    await expectError(submitBid(task, creator.publicKey, outsiderRobot, outsider), "NotAllowlisted");
+    await submitBid(task, creator.publicKey, listedRobot, robotOperator);
+    await submitBid(task, creator.publicKey, listedOperatorRobot, listedOperator);
+
+    const account: any = await taskMarket.account.task.fetch(task);
+    expect(account.bidsCount).to.equal(2);
+    await expectError(setAllowlist(task, []), "TaskHasBids");
+  });
+
+  it("rejects bidders without a proof against the root", async () => {
+    const task = await createTask(creator);
+    await setAllowlist(task, [], Array.from(Keypair.generate().publicKey.toBytes()));
+
+    await expectError(submitBid(task, creator.publicKey, outsiderRobot, outsider), "NotAllowlisted");
+  });
+
+  it("opens the task again with an empty list", async () => {
+    const task = await createTask(creator);
+    await setAllowlist(task, [listedRobot]);
+    await setAllowlist(task, []);
+
+    await submitBid(task, creator.publicKey, outsiderRobot, outsider);
+    const account: any = await taskMarket.account.task.fetch(task);
+    expect(account.allowlistLen).to.equal(0);
+  });
+});
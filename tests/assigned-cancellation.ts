@@ -0,0 +1,132 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Assignment,
+  TaskStatus,
+  acceptBid,
+  bidderAccounts,
+  expectError,
+  fund,
+  openBidTask,
+  pda,
+  programs,
+  registerRobot,
+  settlementAccounts,
+  submitBid,
+} from "./helpers";
+
+type AssignedTask = Assignment & { stream: Awaited<ReturnType<typeof settlementAccounts>> };
+
+/**
+ * Post-assignment cancellation: a creator can cancel an assigned task, the
+ * robot keeping the market's cancellation fee share of the unstreamed
+ * reward, and either close the task or reopen it for new bids.
+ */
+describe("Task Market: cancelling assigned tasks", () => {
+  const { taskMarket, paymentStreams, identityRegistry } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+  const market = pda(taskMarket.programId, Buffer.from("market"));
+
+  const FEE_BPS = 2_500;
+
+  function setCancellationFee(bps: number) {
+    return taskMarket.methods.setCancellationFeeBps(bps).accountsPartial({ market, authority }).rpc();
+  }
+
+  async function assigned(): Promise<AssignedTask> {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    return { ...a, stream: await settlementAccounts(a) };
+  }
+
+  function cancelAssignedTask(a: AssignedTask, reopen: boolean, signer = a.creator) {
+    return taskMarket.methods
+      .cancelAssignedTask(reopen)
+      .accountsPartial({
+        market,
+        task: a.task,
+        creator: signer.publicKey,
+        stream: a.stream,
+        operatorStake: bidderAccounts(a.task, a.creator.publicKey, a.operator.publicKey).operatorStake,
+        robot: a.robot,
+        registryEventAuthority: pda(identityRegistry.programId, Buffer.from("__event_authority")),
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    await setCancellationFee(FEE_BPS);
+  });
+
+  after(async () => {
+    // The market is shared with other test files
+    await setCancellationFee(0);
+  });
+
+  it("rejects a fee above 100%", async () => {
+    await expectError(setCancellationFee(10_001), "InvalidCancellationFee");
+  });
+
+  it("rejects cancellation by anyone but the creator", async () => {
+    const a = await assigned();
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(cancelAssignedTask(a, false, intruder), "Unauthorized");
+  });
+
+  it("rejects cancelling a task that isn't assigned", async () => {
+    const a = await openBidTask();
+    await expectError(
+      cancelAssignedTask({ ...a, stream: await settlementAccounts(a) }, false),
+      "TaskCannotBeCancelled"
+    );
+  });
+
+  it("pays the robot its fee share and refunds the creator the rest", async () => {
+    const a = await assigned();
+    const { escrowBalance } = await paymentStreams.account.paymentStream.fetch(a.stream.stream);
+    const escrowed = Number(escrowBalance);
+    const creatorBefore = Number((await getAccount(connection, a.creatorToken)).amount);
+
+    await cancelAssignedTask(a, false);
+
+    const fee = Math.floor((escrowed * FEE_BPS) / 10_000);
+    const creatorAfter = Number((await getAccount(connection, a.creatorToken)).amount);
+    expect(Number((await getAccount(connection, a.stream.operatorToken)).amount)).to.equal(fee);
+    expect(creatorAfter - creatorBefore).to.equal(escrowed - fee);
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.status).to.equal(TaskStatus.Cancelled);
+    await expectError(cancelAssignedTask(a, false), "TaskCannotBeCancelled");
+  });
+
+  it("reopens the task for new bids, closing its settled stream", async () => {
+    const a = await assigned();
+    await cancelAssignedTask(a, true);
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.status).to.equal(TaskStatus.Open);
+    expect(task.assignedRobot.equals(PublicKey.default)).to.equal(true);
+    expect(task.assignedOperator.equals(PublicKey.default)).to.equal(true);
+    expect(task.streamId.equals(PublicKey.default)).to.equal(true);
+    expect(await connection.getAccountInfo(a.stream.stream)).to.equal(null);
+    expect(await connection.getAccountInfo(a.stream.escrow)).to.equal(null);
+
+    const operator = Keypair.generate();
+    await fund(operator);
+    const robot = await registerRobot(operator);
+    const bid = await submitBid(a.task, a.creator.publicKey, robot, operator);
+    await acceptBid({ ...a, bid, robot, operator }).signers([a.creator]).rpc();
+
+    const reassigned: any = await taskMarket.account.task.fetch(a.task);
+    expect(reassigned.status).to.equal(TaskStatus.Assigned);
+    expect(reassigned.assignedRobot.toBase58()).to.equal(robot.toBase58());
+    expect(reassigned.streamId.toBase58()).to.equal(a.stream.stream.toBase58());
+    const stream: any = await paymentStreams.account.paymentStream.fetch(a.stream.stream);
+    expect(stream.payee.toBase58()).to.equal(operator.publicKey.toBase58());
+  });
+});
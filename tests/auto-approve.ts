@@ -0,0 +1,104 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { createHash } from "crypto";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Assignment,
+  TaskStatus,
+  acceptBid,
+  bidderAccounts,
+  completeTask,
+  expectError,
+  openBidTask,
+  pda,
+  programs,
+  settlementAccounts,
+  startTask,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Auto-approval: a completion its creator leaves unverified past the
+ * market's verification window can be approved by anyone, settling the
+ * task as the creator's approval would.
+ */
+describe("Task Market: auto-approval", () => {
+  const { taskMarket, identityRegistry } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+  const market = pda(taskMarket.programId, Buffer.from("market"));
+
+  const WINDOW = 3;
+  const deliverable = createHash("sha256").update("roof survey photos").digest();
+
+  function setVerificationWindow(window: number) {
+    return taskMarket.methods
+      .setVerificationWindow(new BN(window))
+      .accountsPartial({ market, authority })
+      .rpc();
+  }
+
+  async function completed(): Promise<Assignment> {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await startTask(a).rpc();
+    await completeTask(a, deliverable).rpc();
+    return a;
+  }
+
+  async function finalizeUnverified(a: Assignment, stream?: Awaited<ReturnType<typeof settlementAccounts>>) {
+    return taskMarket.methods
+      .finalizeUnverified()
+      .accountsPartial({
+        market,
+        task: a.task,
+        stream: stream ?? (await settlementAccounts(a)),
+        operatorStake: bidderAccounts(a.task, a.creator.publicKey, a.operator.publicKey).operatorStake,
+        gpsProof: null,
+        completionProof: null,
+        robot: a.robot,
+        registryEventAuthority: pda(identityRegistry.programId, Buffer.from("__event_authority")),
+      })
+      .rpc();
+  }
+
+  after(async () => {
+    // The market is shared with other test files
+    await setVerificationWindow(0);
+  });
+
+  it("rejects a negative window", async () => {
+    await expectError(setVerificationWindow(-1), "InvalidVerificationWindow");
+  });
+
+  it("leaves completions open indefinitely without a window", async () => {
+    await setVerificationWindow(0);
+    const a = await completed();
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.verificationDueAt.toNumber()).to.equal(0);
+    await expectError(finalizeUnverified(a), "VerificationWindowOpen");
+  });
+
+  it("rejects approval within the window", async () => {
+    await setVerificationWindow(3_600);
+    const a = await completed();
+    await expectError(finalizeUnverified(a), "VerificationWindowOpen");
+  });
+
+  it("approves an unverified completion past the window", async () => {
+    await setVerificationWindow(WINDOW);
+    const a = await completed();
+    const { verificationDueAt } = await taskMarket.account.task.fetch(a.task);
+    await waitForClock(verificationDueAt);
+
+    const stream = await settlementAccounts(a);
+    await finalizeUnverified(a, stream);
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.status).to.equal(TaskStatus.Completed);
+    expect(Number((await getAccount(connection, stream.operatorToken)).amount)).to.be.gt(0);
+    await expectError(finalizeUnverified(a, stream), "TaskNotPendingVerification");
+  });
+});
@@ -0,0 +1,73 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, programs, setupToken, stake, unstake } from "./helpers";
+
+/**
+ * Auto re-lock: a locked position can roll its lock over for the same tier
+ * until its owner requests an unlock, a full lock period's notice.
+ */
+describe("DRONEOS Token: rolling locks", () => {
+  const { droneosToken } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const LOCK = 30 * 86_400;
+  const staker = Keypair.generate();
+  let t: TokenSetup;
+  let stakerToken: PublicKey;
+  let unlocked: PublicKey;
+  let locked: PublicKey;
+
+  function relockAccounts(stakeAccount: PublicKey) {
+    return { config: t.config, stakeAccount, user: staker.publicKey };
+  }
+
+  function enableAutoRelock(stakeAccount: PublicKey) {
+    return droneosToken.methods
+      .enableAutoRelock()
+      .accountsPartial(relockAccounts(stakeAccount))
+      .signers([staker])
+      .rpc();
+  }
+
+  function requestUnlock(stakeAccount: PublicKey) {
+    return droneosToken.methods
+      .requestUnlock()
+      .accountsPartial(relockAccounts(stakeAccount))
+      .signers([staker])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(staker);
+    t = await setupToken();
+    stakerToken = await drip(staker, 2 * AMOUNT);
+    unlocked = await stake(staker, stakerToken, AMOUNT);
+    locked = await stake(staker, stakerToken, AMOUNT, 30);
+  });
+
+  it("rejects rolling a position without a lock", async () => {
+    await expectError(enableAutoRelock(unlocked), "InvalidLockPeriod");
+  });
+
+  it("rejects an unlock request for a lock that isn't rolling", async () => {
+    await expectError(requestUnlock(locked), "AutoRelockNotEnabled");
+  });
+
+  it("keeps a rolling position locked", async () => {
+    await enableAutoRelock(locked);
+    expect((await droneosToken.account.stakeAccount.fetch(locked)).autoRelock).to.equal(true);
+    await expectError(enableAutoRelock(locked), "AutoRelockActive");
+
+    await expectError(unstake(staker, stakerToken, locked), "StakeLocked");
+  });
+
+  it("unlocks at the first rollover a full lock period after the request", async () => {
+    const { lockUntil } = await droneosToken.account.stakeAccount.fetch(locked);
+    await requestUnlock(locked);
+
+    const position: any = await droneosToken.account.stakeAccount.fetch(locked);
+    expect(position.autoRelock).to.equal(false);
+    // The request came within the first period, so the next rollover
+    expect(position.lockUntil.toNumber()).to.equal(lockUntil.toNumber() + LOCK);
+  });
+});
@@ -0,0 +1,135 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Assignment,
+  acceptBid,
+  expectError,
+  fund,
+  openBidTask,
+  programs,
+  setupMarket,
+  startTask,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Bid bonds: every bid holds the market's lamport bond, returned when the
+ * bid is withdrawn, rejected or started on and forfeited to the creator
+ * when an accepted bidder doesn't start within the start window.
+ */
+describe("Task Market: bid bonds", () => {
+  const { taskMarket } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const BOND = 0.05 * anchor.web3.LAMPORTS_PER_SOL;
+  const START_WINDOW = 4;
+  let market: PublicKey;
+
+  function setBidBondTerms(bond: number, startWindow: number) {
+    return taskMarket.methods.setBidBondTerms(new BN(bond), new BN(startWindow)).accountsPartial({ market, authority });
+  }
+
+  function forfeitBidBond(a: Assignment) {
+    return taskMarket.methods
+      .forfeitBidBond()
+      .accountsPartial({ market, task: a.task, bid: a.bid, creator: a.creator.publicKey })
+      .rpc();
+  }
+
+  before(async () => {
+    market = await setupMarket();
+  });
+
+  after(async () => {
+    // The market is shared with other test files
+    await setBidBondTerms(0, 0).rpc();
+  });
+
+  it("rejects bond terms set by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      taskMarket.methods
+        .setBidBondTerms(new BN(BOND), new BN(START_WINDOW))
+        .accountsPartial({ market, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects a negative start window", async () => {
+    await expectError(setBidBondTerms(BOND, -1).rpc(), "InvalidStartWindow");
+  });
+
+  it("holds the bond in each new bid", async () => {
+    await setBidBondTerms(BOND, START_WINDOW).rpc();
+    const a = await openBidTask();
+
+    const bid: any = await taskMarket.account.bid.fetch(a.bid);
+    expect(bid.bond.toNumber()).to.equal(BOND);
+    const info = await connection.getAccountInfo(a.bid);
+    const rent = await connection.getMinimumBalanceForRentExemption(info!.data.length);
+    expect(info!.lamports - rent).to.equal(BOND);
+  });
+
+  it("returns the bond to the bidder on withdrawal, and only to them", async () => {
+    const a = await openBidTask();
+
+    await expectError(
+      taskMarket.methods
+        .withdrawBid()
+        .accountsPartial({ bid: a.bid, operator: a.creator.publicKey })
+        .signers([a.creator])
+        .rpc(),
+      "Unauthorized"
+    );
+
+    const before = await connection.getBalance(a.operator.publicKey);
+    await taskMarket.methods
+      .withdrawBid()
+      .accountsPartial({ bid: a.bid, operator: a.operator.publicKey })
+      .signers([a.operator])
+      .rpc();
+    const after = await connection.getBalance(a.operator.publicKey);
+
+    expect(after - before).to.be.gt(BOND - 10_000);
+    const bid: any = await taskMarket.account.bid.fetch(a.bid);
+    expect(bid.status).to.have.property("withdrawn");
+    expect(bid.bond.toNumber()).to.equal(0);
+  });
+
+  it("returns the bond when the accepted bidder starts in time", async () => {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+
+    const before = await connection.getBalance(a.operator.publicKey);
+    await startTask(a).rpc();
+    const after = await connection.getBalance(a.operator.publicKey);
+
+    expect(after - before).to.be.gt(BOND - 10_000);
+    const bid: any = await taskMarket.account.bid.fetch(a.bid);
+    expect(bid.bond.toNumber()).to.equal(0);
+  });
+
+  it("forfeits the bond to the creator once the start window passes", async () => {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+
+    await expectError(forfeitBidBond(a), "StartWindowOpen");
+
+    const { assignedAt } = await taskMarket.account.task.fetch(a.task);
+    await waitForClock(assignedAt.addn(START_WINDOW));
+
+    const before = await connection.getBalance(a.creator.publicKey);
+    await forfeitBidBond(a);
+    const after = await connection.getBalance(a.creator.publicKey);
+
+    expect(after - before).to.equal(BOND);
+    const bid: any = await taskMarket.account.bid.fetch(a.bid);
+    expect(bid.bond.toNumber()).to.equal(0);
+  });
+});
@@ -0,0 +1,99 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Assignment,
+  acceptBid,
+  createTask,
+  expectError,
+  fund,
+  openBidTask,
+  programs,
+  registerRobot,
+  setupMarket,
+  submitBid,
+  tokenFor,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Bid expiry: a bid can't be accepted past its validity window, anyone can
+ * then mark it expired, and its operator can close it for the rent.
+ */
+describe("Task Market: bid expiry", () => {
+  const { taskMarket } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const VALID_FOR = 3;
+  let a: Assignment;
+
+  function expireBid() {
+    return taskMarket.methods.expireBid().accountsPartial({ bid: a.bid }).rpc();
+  }
+
+  function closeBid(operator: Keypair, bid = a.bid) {
+    return taskMarket.methods
+      .closeBid()
+      .accountsPartial({ bid, operator: operator.publicKey })
+      .signers([operator])
+      .rpc();
+  }
+
+  before(async () => {
+    const creator = Keypair.generate();
+    const operator = Keypair.generate();
+    await fund(creator, operator);
+    await setupMarket();
+
+    const { mint, token: creatorToken, treasury } = await tokenFor(creator, 1_000_000_000);
+    const task = await createTask(creator);
+    const robot = await registerRobot(operator);
+    const bid = await submitBid(task, creator.publicKey, robot, operator, 10_000, 3_600, VALID_FOR);
+    a = { task, bid, robot, creator, operator, mint, creatorToken, treasury };
+  });
+
+  it("rejects closing a bid that is still pending", async () => {
+    await expectError(closeBid(a.operator), "BidStillActive");
+  });
+
+  it("rejects expiring a bid within its window", async () => {
+    await expectError(expireBid(), "BidNotExpired");
+  });
+
+  it("rejects accepting a bid past its window", async () => {
+    const { validUntil } = await taskMarket.account.bid.fetch(a.bid);
+    await waitForClock(validUntil);
+
+    await expectError(acceptBid(a).signers([a.creator]).rpc(), "BidExpired");
+  });
+
+  it("marks a bid past its window expired", async () => {
+    await expireBid();
+
+    const bid: any = await taskMarket.account.bid.fetch(a.bid);
+    expect(bid.status).to.have.property("expired");
+    await expectError(expireBid(), "BidNotPending");
+  });
+
+  it("rejects closing a bid by anyone but its operator", async () => {
+    await expectError(closeBid(a.creator), "Unauthorized");
+  });
+
+  it("returns a closed bid's rent to its operator", async () => {
+    const rent = await connection.getBalance(a.bid);
+    const before = await connection.getBalance(a.operator.publicKey);
+    await closeBid(a.operator);
+    const after = await connection.getBalance(a.operator.publicKey);
+
+    expect(await connection.getAccountInfo(a.bid)).to.equal(null);
+    expect(after - before).to.be.gt(rent - 10_000);
+  });
+
+  it("leaves bids within their window acceptable", async () => {
+    const fresh = await openBidTask();
+    await acceptBid(fresh).signers([fresh.creator]).rpc();
+
+    const bid: any = await taskMarket.account.bid.fetch(fresh.bid);
+    expect(bid.status).to.have.property("accepted");
+  });
+});
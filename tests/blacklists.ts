@@ -0,0 +1,97 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  bidAddress,
+  createTask,
+  expectError,
+  fund,
+  pda,
+  programs,
+  registerRobot,
+  setupMarket,
+  submitBid,
+} from "./helpers";
+
+/**
+ * Creator blacklists: robots or operators a creator lists can't bid on any
+ * of that creator's tasks until removed; other creators' tasks are open to
+ * them.
+ */
+describe("Task Market: creator blacklists", () => {
+  const { taskMarket } = programs();
+
+  const creator = Keypair.generate();
+  const listedOperator = Keypair.generate();
+  const robotOperator = Keypair.generate();
+  let blacklist: PublicKey;
+  let task: PublicKey;
+  let listedOperatorRobot: PublicKey;
+  let listedRobot: PublicKey;
+
+  function addToBlacklist(member: PublicKey) {
+    return taskMarket.methods
+      .addToBlacklist(member)
+      .accountsPartial({ blacklist, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+  }
+
+  function removeFromBlacklist(member: PublicKey) {
+    return taskMarket.methods
+      .removeFromBlacklist(member)
+      .accountsPartial({ blacklist, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(creator, listedOperator, robotOperator);
+    await setupMarket();
+    blacklist = pda(taskMarket.programId, Buffer.from("blacklist"), creator.publicKey.toBuffer());
+    task = await createTask(creator);
+    listedOperatorRobot = await registerRobot(listedOperator);
+    listedRobot = await registerRobot(robotOperator);
+  });
+
+  it("lists a robot and an operator", async () => {
+    await addToBlacklist(listedRobot);
+    await addToBlacklist(listedOperator.publicKey);
+
+    const account: any = await taskMarket.account.blacklist.fetch(blacklist);
+    expect(account.members.map((m: PublicKey) => m.toBase58())).to.have.members([
+      listedRobot.toBase58(),
+      listedOperator.publicKey.toBase58(),
+    ]);
+  });
+
+  it("rejects listing a member twice", async () => {
+    await expectError(addToBlacklist(listedRobot), "AlreadyBlacklisted");
+  });
+
+  it("rejects bids from a listed robot or any robot of a listed operator", async () => {
+    await expectError(submitBid(task, creator.publicKey, listedRobot, robotOperator), "Blacklisted");
+    await expectError(submitBid(task, creator.publicKey, listedOperatorRobot, listedOperator), "Blacklisted");
+  });
+
+  it("leaves other creators' tasks open to listed operators", async () => {
+    const other = Keypair.generate();
+    await fund(other);
+    const otherTask = await createTask(other);
+
+    await submitBid(otherTask, other.publicKey, listedOperatorRobot, listedOperator);
+    const bid: any = await taskMarket.account.bid.fetch(bidAddress(otherTask, listedOperatorRobot));
+    expect(bid.status).to.have.property("pending");
+  });
+
+  it("rejects removing a member that isn't listed", async () => {
+    await expectError(removeFromBlacklist(Keypair.generate().publicKey), "NotBlacklisted");
+  });
+
+  it("accepts bids again once the member is removed", async () => {
+    await removeFromBlacklist(listedRobot);
+    await submitBid(task, creator.publicKey, listedRobot, robotOperator);
+
+    const account: any = await taskMarket.account.blacklist.fetch(blacklist);
+    expect(account.members).to.have.length(1);
+  });
+});
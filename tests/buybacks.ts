@@ -0,0 +1,159 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createAccount,
+  createMint,
+  createTransferInstruction,
+  getAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, expectError, fund, pda, programs, setupToken } from "./helpers";
+
+/**
+ * Buyback and burn: the authority sells fee revenue for DRONEOS through a
+ * whitelisted AMM, within a weekly limit per fee mint and a minimum out,
+ * and burns what it buys. The SPL Token program stands in for the AMM: its
+ * transfer spends fee revenue without buying anything back.
+ */
+describe("DRONEOS Token: buyback and burn", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const FEES = 10_000_000;
+  const LIMIT = 1_000_000;
+  const seller = Keypair.generate();
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let buyback: PublicKey;
+  let feeMint: PublicKey;
+  let budget: PublicKey;
+  let feeVault: PublicKey;
+  let buybackVault: PublicKey;
+  let proceeds: PublicKey;
+
+  function addSwapProgram(program: PublicKey, signer?: Keypair) {
+    return droneosToken.methods
+      .addSwapProgram(program)
+      .accountsPartial({ config: t.config, authority: signer?.publicKey ?? authority })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  function removeSwapProgram(program: PublicKey) {
+    return droneosToken.methods.removeSwapProgram(program).accountsPartial({ config: t.config, authority }).rpc();
+  }
+
+  function setBuybackLimit(mint: PublicKey, limit: number, signer?: Keypair) {
+    return droneosToken.methods
+      .setBuybackLimit(new BN(limit))
+      .accountsPartial({
+        config: t.config,
+        feeMint: mint,
+        budget: pda(droneosToken.programId, Buffer.from("buyback"), mint.toBuffer()),
+        authority: signer?.publicKey ?? authority,
+      })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  function buybackAndBurn(amountIn: number, minOut: number, swap: PublicKey = TOKEN_PROGRAM_ID) {
+    const transfer = createTransferInstruction(feeVault, proceeds, buyback, amountIn);
+    return droneosToken.methods
+      .buybackAndBurn(new BN(amountIn), new BN(minOut), transfer.data)
+      .accountsPartial({
+        config: t.config,
+        mint: t.mint,
+        budget,
+        buyback,
+        feeVault,
+        buybackVault,
+        swapProgram: swap,
+        authority,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .remainingAccounts(transfer.keys.map((key) => ({ ...key, isSigner: false })))
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(seller, intruder);
+    t = await setupToken();
+    buyback = pda(droneosToken.programId, Buffer.from("buyback"));
+    feeMint = await createMint(connection, seller, seller.publicKey, null, 6);
+    budget = pda(droneosToken.programId, Buffer.from("buyback"), feeMint.toBuffer());
+    feeVault = await createAccount(connection, seller, feeMint, buyback, Keypair.generate());
+    proceeds = await createAccount(connection, seller, feeMint, seller.publicKey);
+    await mintTo(connection, seller, feeMint, feeVault, seller, FEES);
+    buybackVault = await createAccount(
+      connection,
+      seller,
+      t.mint,
+      buyback,
+      Keypair.generate(),
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+  });
+
+  after(async () => {
+    // The token config is shared with other test files
+    await removeSwapProgram(TOKEN_PROGRAM_ID);
+  });
+
+  it("rejects swap programs allowed by anyone but the authority", async () => {
+    await expectError(addSwapProgram(TOKEN_PROGRAM_ID, intruder), "Unauthorized");
+  });
+
+  it("allows a swap program once", async () => {
+    await addSwapProgram(TOKEN_PROGRAM_ID);
+    const { swapPrograms } = await droneosToken.account.tokenConfig.fetch(t.config);
+    expect(swapPrograms.map((p) => p.toBase58())).to.include(TOKEN_PROGRAM_ID.toBase58());
+
+    await expectError(addSwapProgram(TOKEN_PROGRAM_ID), "SwapProgramAlreadyAllowed");
+    await expectError(removeSwapProgram(Keypair.generate().publicKey), "SwapProgramNotAllowed");
+  });
+
+  it("rejects buyback limits set by anyone but the authority", async () => {
+    await expectError(setBuybackLimit(feeMint, LIMIT, intruder), "Unauthorized");
+  });
+
+  it("rejects a buyback limit for DRONEOS itself", async () => {
+    await expectError(setBuybackLimit(t.mint, LIMIT), "InvalidVault");
+  });
+
+  it("sets a fee mint's weekly buyback limit", async () => {
+    await setBuybackLimit(feeMint, LIMIT);
+
+    const set: any = await droneosToken.account.buybackBudget.fetch(budget);
+    expect(set.feeMint.toBase58()).to.equal(feeMint.toBase58());
+    expect(set.epochLimit.toNumber()).to.equal(LIMIT);
+    expect(set.spentInEpoch.toNumber()).to.equal(0);
+  });
+
+  it("rejects swapping through a program that isn't allowed", async () => {
+    await expectError(buybackAndBurn(LIMIT, 0, TOKEN_2022_PROGRAM_ID), "SwapProgramNotAllowed");
+  });
+
+  it("rejects a swap returning less than the minimum out", async () => {
+    await expectError(buybackAndBurn(LIMIT / 2, 1), "BuybackSlippage");
+  });
+
+  it("counts what a swap spends against the weekly limit", async () => {
+    await buybackAndBurn(LIMIT / 2, 0);
+
+    const spent: any = await droneosToken.account.buybackBudget.fetch(budget);
+    expect(spent.spentInEpoch.toNumber()).to.equal(LIMIT / 2);
+    expect(Number((await getAccount(connection, feeVault)).amount)).to.equal(FEES - LIMIT / 2);
+
+    await expectError(buybackAndBurn(LIMIT, 0), "BuybackLimitExceeded");
+  });
+
+  it("rejects selling more than the fee vault holds", async () => {
+    await expectError(buybackAndBurn(FEES + 1, 0), "InvalidAmount");
+  });
+});
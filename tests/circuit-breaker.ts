@@ -0,0 +1,88 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Assignment,
+  TaskStatus,
+  acceptBid,
+  createTask,
+  expectError,
+  fund,
+  openBidTask,
+  programs,
+  registerRobot,
+  setupMarket,
+  startTask,
+  submitBid,
+} from "./helpers";
+
+/**
+ * Circuit breaker: while the authority has the market paused, no task can
+ * be created, bid on or assigned, but assigned tasks can still be worked.
+ */
+describe("Task Market: circuit breaker", () => {
+  const { taskMarket } = programs();
+  const authority = anchor.getProvider().publicKey!;
+
+  let market: PublicKey;
+  let pending: Assignment;
+  let assigned: Assignment;
+
+  function setPaused(paused: boolean) {
+    return taskMarket.methods.setPaused(paused).accountsPartial({ market, authority }).rpc();
+  }
+
+  before(async () => {
+    market = await setupMarket();
+    pending = await openBidTask();
+    assigned = await openBidTask();
+    await acceptBid(assigned).signers([assigned.creator]).rpc();
+  });
+
+  after(async () => {
+    // The market is shared with other test files
+    await setPaused(false);
+  });
+
+  it("rejects pausing by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      taskMarket.methods
+        .setPaused(true)
+        .accountsPartial({ market, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects new tasks, bids and assignments while paused", async () => {
+    await setPaused(true);
+    expect((await taskMarket.account.market.fetch(market)).paused).to.equal(true);
+
+    await expectError(createTask(pending.creator), "MarketPaused");
+
+    const bidder = Keypair.generate();
+    await fund(bidder);
+    const robot = await registerRobot(bidder);
+    await expectError(submitBid(pending.task, pending.creator.publicKey, robot, bidder), "MarketPaused");
+
+    await expectError(acceptBid(pending).signers([pending.creator]).rpc(), "MarketPaused");
+  });
+
+  it("lets assigned tasks be worked while paused", async () => {
+    await startTask(assigned).rpc();
+
+    const task: any = await taskMarket.account.task.fetch(assigned.task);
+    expect(task.status).to.equal(TaskStatus.InProgress);
+  });
+
+  it("reopens the market once unpaused", async () => {
+    await setPaused(false);
+    await acceptBid(pending).signers([pending.creator]).rpc();
+
+    const task: any = await taskMarket.account.task.fetch(pending.task);
+    expect(task.status).to.equal(TaskStatus.Assigned);
+  });
+});
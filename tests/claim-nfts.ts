@@ -0,0 +1,112 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createAccount,
+  createAssociatedTokenAccount,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  transfer,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  pda,
+  programs,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Claim NFTs: a payer can mint a pending stream's payee a one-off NFT,
+ * after which the stream pays whoever holds it, so its income moves with
+ * the NFT rather than through transfer_payee.
+ */
+describe("Payment Streams: claim NFTs", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const buyer = Keypair.generate();
+  let s: Stream;
+  let claimMint: PublicKey;
+
+  function mintClaimNft(stream: Stream, signer = stream.payer) {
+    const mint = pda(paymentStreams.programId, Buffer.from("claim_mint"), stream.stream.toBuffer());
+    return paymentStreams.methods
+      .mintClaimNft()
+      .accountsPartial({
+        stream: stream.stream,
+        claimMint: mint,
+        payeeClaimToken: getAssociatedTokenAddressSync(mint, stream.payee.publicKey),
+        payee: stream.payee.publicKey,
+        payer: signer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  async function nextSecond() {
+    const { lastTickAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(lastTickAt).addn(1));
+  }
+
+  before(async () => {
+    await fund(buyer);
+    s = await openStream();
+    claimMint = pda(paymentStreams.programId, Buffer.from("claim_mint"), s.stream.toBuffer());
+  });
+
+  it("rejects minting by anyone but the payer", async () => {
+    await expectError(mintClaimNft(s, s.payee), "Unauthorized");
+  });
+
+  it("rejects minting once the stream is accepted", async () => {
+    const accepted = await openStream();
+    await acceptStream(accepted).rpc();
+    await expectError(mintClaimNft(accepted), "StreamNotPending");
+  });
+
+  it("mints the payee a single claim NFT", async () => {
+    await mintClaimNft(s);
+
+    const claimToken = getAssociatedTokenAddressSync(claimMint, s.payee.publicKey);
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.claimMint.toBase58()).to.equal(claimMint.toBase58());
+    expect(Number((await getAccount(connection, claimToken)).amount)).to.equal(1);
+
+    s = { ...s, claimToken };
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("rejects reassigning the payee while the NFT exists", async () => {
+    await expectError(
+      paymentStreams.methods
+        .transferPayee(buyer.publicKey)
+        .accountsPartial({ stream: s.stream, payer: s.payer.publicKey, consenter: s.payee.publicKey })
+        .signers([s.payer, s.payee])
+        .rpc(),
+      "ClaimNftIssued"
+    );
+  });
+
+  it("pays whoever holds the NFT", async () => {
+    const buyerClaimToken = await createAssociatedTokenAccount(connection, buyer, claimMint, buyer.publicKey);
+    await transfer(connection, s.payee, s.claimToken!, buyerClaimToken, s.payee, 1);
+    await nextSecond();
+
+    // The seller no longer holds the NFT
+    await expectError(tick(s).rpc(), "NotClaimHolder");
+
+    const buyerToken = await createAccount(connection, buyer, s.mint, buyer.publicKey);
+    await tick({ ...s, claimToken: buyerClaimToken, payeeToken: buyerToken }).rpc();
+    expect(Number((await getAccount(connection, buyerToken)).amount)).to.be.gt(0);
+  });
+});
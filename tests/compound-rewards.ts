@@ -0,0 +1,85 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, createAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, pda, programs, setupToken, stake, waitForClock } from "./helpers";
+
+/**
+ * Compounding: a staker can restake pending rewards into the position
+ * instead of claiming them, moving them from the config's rewards vault
+ * into its stake vault within the same limits as a claim.
+ */
+describe("DRONEOS Token: compounding rewards", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const AMOUNT = 100 * 1_000_000;
+  const staker = Keypair.generate();
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let position: PublicKey;
+  let stakerToken: PublicKey;
+  let foreignVault: PublicKey;
+
+  function compoundRewards(signer = staker, vaults: { rewardsVault?: PublicKey; stakeVault?: PublicKey } = {}) {
+    return droneosToken.methods
+      .compoundRewards()
+      .accountsPartial({
+        config: t.config,
+        stakeAccount: position,
+        emissions: pda(droneosToken.programId, Buffer.from("emissions")),
+        rewardsVault: vaults.rewardsVault ?? t.rewardsVault,
+        stakeVault: vaults.stakeVault ?? t.stakeVault,
+        user: signer.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(staker, intruder);
+    t = await setupToken();
+    stakerToken = await drip(staker, AMOUNT);
+    position = await stake(staker, stakerToken, AMOUNT);
+    foreignVault = await createAccount(
+      connection,
+      staker,
+      t.mint,
+      t.config,
+      Keypair.generate(),
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+  });
+
+  it("rejects compounding before any rewards are pending", async () => {
+    // 100 DRONEOS at the base APY earns under one base unit in two seconds
+    await expectError(compoundRewards(), "NoRewardsToClaim");
+  });
+
+  it("rejects compounding someone else's position", async () => {
+    await expectError(compoundRewards(intruder), "ConstraintSeeds");
+  });
+
+  it("rejects restaking into a stake vault other than the config's", async () => {
+    const { stakedAt } = await droneosToken.account.stakeAccount.fetch(position);
+    await waitForClock(stakedAt.addn(5));
+    // The staker's own account, which would keep the rewards outside the vault
+    await expectError(compoundRewards(staker, { stakeVault: stakerToken }), "InvalidVault");
+    await expectError(compoundRewards(staker, { stakeVault: foreignVault }), "InvalidVault");
+  });
+
+  it("rejects paying from a rewards vault other than the config's", async () => {
+    await expectError(compoundRewards(staker, { rewardsVault: foreignVault }), "InvalidVault");
+  });
+
+  it("rejects compounding once the emission schedule has nothing left to pay", async () => {
+    // The test schedule's epochs are all over
+    await expectError(compoundRewards(), "EmissionBudgetExhausted");
+
+    const { amount } = await droneosToken.account.stakeAccount.fetch(position);
+    expect(amount.toNumber()).to.equal(AMOUNT);
+  });
+});
@@ -0,0 +1,469 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { PublicKey, Keypair, Ed25519Program } from "@solana/web3.js";
+import { createMint, createAccount, mintTo, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import { fund, initializeOnce } from "./helpers";
+
+/**
+ * Compute-unit benchmarks for the protocol's hot paths.
+ *
+ * Each benchmark runs the instruction on a local validator, reads
+ * `computeUnitsConsumed` from the confirmed transaction and fails if it
+ * exceeds the budget below. Budgets are set with ~15% headroom over the
+ * measured cost, as printed by the `after` hook; tighten them when an
+ * optimization lands, never loosen them without a reason in the commit
+ * message.
+ */
+const CU_BUDGETS = {
+  tick: 28_000, // Two token transfers: payee share + platform fee
+  verify_proof: 18_000,
+  accept_bid: 70_000, // Opens the payment stream: stream, escrow and registry inits + escrow deposit
+  distribute_rewards: 9_000,
+};
+
+describe("$DRONEOS Compute Unit Benchmarks", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const connection = provider.connection;
+
+  const paymentStreams = anchor.workspace.PaymentStreams as Program<any>;
+  const taskMarket = anchor.workspace.TaskMarket as Program<any>;
+  const oracleVerifier = anchor.workspace.OracleVerifier as Program<any>;
+  const swarmCoordinator = anchor.workspace.SwarmCoordinator as Program<any>;
+  const droneosToken = anchor.workspace.DroneosToken as Program<any>;
+  const identityRegistry = anchor.workspace.IdentityRegistry as Program<any>;
+
+  const results: Record<string, number> = {};
+
+  async function computeUnits(signature: string): Promise<number> {
+    await connection.confirmTransaction(signature, "confirmed");
+    const tx = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    return tx?.meta?.computeUnitsConsumed ?? Number.MAX_SAFE_INTEGER;
+  }
+
+  function checkBudget(name: keyof typeof CU_BUDGETS, consumed: number) {
+    results[name] = consumed;
+    expect(consumed, `${name} consumed ${consumed} CU`).to.be.lte(CU_BUDGETS[name]);
+  }
+
+  after(() => {
+    console.table(
+      Object.entries(results).map(([ix, cu]) => ({
+        instruction: ix,
+        consumed: cu,
+        budget: CU_BUDGETS[ix as keyof typeof CU_BUDGETS],
+      }))
+    );
+  });
+
+  it("tick stays within budget", async () => {
+    const payer = Keypair.generate();
+    const payee = Keypair.generate();
+    await fund(payer, payee);
+
+    await initializeOnce(() =>
+      paymentStreams.methods.initialize().accounts({ authority: provider.wallet.publicKey }).rpc()
+    );
+
+    const mint = await createMint(connection, payer, payer.publicKey, null, 6);
+    const payerToken = await createAccount(connection, payer, mint, payer.publicKey);
+    const payeeToken = await createAccount(connection, payer, mint, payee.publicKey);
+    await mintTo(connection, payer, mint, payerToken, payer, 1_000_000_000);
+
+    const [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), mint.toBuffer()],
+      paymentStreams.programId
+    );
+    await paymentStreams.methods
+      .initializeTreasury()
+      .accountsPartial({ treasury, mint, payer: payer.publicKey, tokenProgram: TOKEN_PROGRAM_ID })
+      .signers([payer])
+      .rpc();
+
+    const nonce = new BN(0);
+    const [stream] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("stream"),
+        payer.publicKey.toBuffer(),
+        payee.publicKey.toBuffer(),
+        nonce.toArrayLike(Buffer, "le", 8),
+      ],
+      paymentStreams.programId
+    );
+    await paymentStreams.methods
+      .createStream(nonce, new BN(1_000), new BN(3_600), new BN(60), true, new BN(0), new BN(0), null)
+      .accountsPartial({
+        stream,
+        mint,
+        payerToken,
+        payer: payer.publicKey,
+        payee: payee.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([payer])
+      .rpc();
+
+    await paymentStreams.methods
+      .acceptStream()
+      .accountsPartial({ stream, payee: payee.publicKey })
+      .signers([payee])
+      .rpc();
+
+    await paymentStreams.methods
+      .startStream()
+      .accountsPartial({
+        stream,
+        mint,
+        claimToken: null,
+        payeeToken,
+        treasury,
+        payer: payer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([payer])
+      .rpc();
+
+    await new Promise((resolve) => setTimeout(resolve, 2_000));
+
+    const sig = await paymentStreams.methods
+      .tick()
+      .accountsPartial({
+        stream,
+        mint,
+        payeeToken,
+        claimToken: null,
+        treasury,
+        cranker: provider.wallet.publicKey,
+        crankerToken: null,
+        referrerAccount: null,
+        referrerToken: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    checkBudget("tick", await computeUnits(sig));
+  });
+
+  it("verify_proof stays within budget", async () => {
+    const provider_ = Keypair.generate();
+    const operator = Keypair.generate();
+    await fund(provider_, operator);
+
+    await initializeOnce(() =>
+      oracleVerifier.methods.initialize().accounts({ authority: provider.wallet.publicKey }).rpc()
+    );
+
+    const [verifier] = PublicKey.findProgramAddressSync([Buffer.from("verifier")], oracleVerifier.programId);
+    const [oracle] = PublicKey.findProgramAddressSync(
+      [Buffer.from("oracle"), provider_.publicKey.toBuffer()],
+      oracleVerifier.programId
+    );
+
+    await oracleVerifier.methods
+      .registerOracle({ gps: {} }, "https://oracle.droneos.dev", 90)
+      .accountsPartial({ oracle, provider: provider_.publicKey })
+      .signers([provider_])
+      .rpc();
+
+    // GPS proofs are signed by the robot's registered device key
+    await initializeOnce(() =>
+      identityRegistry.methods.initialize().accounts({ authority: provider.wallet.publicKey }).rpc()
+    );
+    const device = Keypair.generate();
+    const [robot] = PublicKey.findProgramAddressSync(
+      [Buffer.from("robot"), device.publicKey.toBuffer()],
+      identityRegistry.programId
+    );
+    await identityRegistry.methods
+      .registerRobot([...device.publicKey.toBuffer()], "Acme", "X1", Array(32).fill(0), { drone: {} })
+      .accountsPartial({ robot, operator: operator.publicKey })
+      .signers([operator])
+      .rpc();
+
+    const task = Keypair.generate().publicKey;
+    const [proof] = PublicKey.findProgramAddressSync(
+      [Buffer.from("proof"), task.toBuffer(), robot.toBuffer()],
+      oracleVerifier.programId
+    );
+
+    const [latitude, longitude, altitude] = [40_712_776, -74_005_974, 120];
+    const timestamp = Math.floor(Date.now() / 1000);
+    const message = Buffer.alloc(92);
+    task.toBuffer().copy(message, 0);
+    robot.toBuffer().copy(message, 32);
+    message.writeBigInt64LE(BigInt(latitude), 64);
+    message.writeBigInt64LE(BigInt(longitude), 72);
+    message.writeInt32LE(altitude, 80);
+    message.writeBigInt64LE(BigInt(timestamp), 84);
+    const ed25519Ix = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: device.secretKey,
+      message,
+    });
+    // The Ed25519 instruction holds the public key at 16 and the signature at 48
+    const signature = [...ed25519Ix.data.subarray(48, 112)];
+
+    await oracleVerifier.methods
+      .submitGpsProof(new BN(latitude), new BN(longitude), altitude, new BN(timestamp), signature)
+      .accountsPartial({ task, robot, oracle, proof, operator: operator.publicKey })
+      .preInstructions([ed25519Ix])
+      .signers([operator])
+      .rpc();
+
+    const sig = await oracleVerifier.methods
+      .verifyProof(95, true, "")
+      .accountsPartial({ verifier, oracle, proof, oracleAuthority: provider_.publicKey })
+      .signers([provider_])
+      .rpc();
+
+    checkBudget("verify_proof", await computeUnits(sig));
+  });
+
+  it("accept_bid stays within budget", async () => {
+    const creator = Keypair.generate();
+    const operator = Keypair.generate();
+    await fund(creator, operator);
+
+    await initializeOnce(() =>
+      taskMarket.methods.initialize().accounts({ authority: provider.wallet.publicKey }).rpc()
+    );
+    await initializeOnce(() =>
+      paymentStreams.methods.initialize().accounts({ authority: provider.wallet.publicKey }).rpc()
+    );
+
+    const mint = await createMint(connection, creator, creator.publicKey, null, 6);
+    const creatorToken = await createAccount(connection, creator, mint, creator.publicKey);
+    await mintTo(connection, creator, mint, creatorToken, creator, 100_000_000);
+
+    const [market] = PublicKey.findProgramAddressSync([Buffer.from("market")], taskMarket.programId);
+    const marketAccount: any = await taskMarket.account.market.fetch(market);
+    const [task] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("task"),
+        creator.publicKey.toBuffer(),
+        new BN(marketAccount.totalTasks).toArrayLike(Buffer, "le", 8),
+      ],
+      taskMarket.programId
+    );
+
+    await taskMarket.methods
+      .createTask(
+        "Bridge inspection",
+        "Inspect pylons 3-7",
+        0,
+        Buffer.from([2]),
+        0,
+        new BN(50_000_000),
+        new BN(13_889),
+        3_600,
+        2,
+        new BN(86_400),
+        false,
+        1,
+        new BN(0)
+      )
+      .accountsPartial({
+        market,
+        task,
+        creator: creator.publicKey,
+        coordinator: null,
+        groupTask: null,
+        swarmEventAuthority: null,
+        swarmCoordinatorProgram: null,
+      })
+      .signers([creator])
+      .rpc();
+
+    // submit_bid checks the robot against the task's requirements
+    await initializeOnce(() =>
+      identityRegistry.methods.initialize().accounts({ authority: provider.wallet.publicKey }).rpc()
+    );
+    const deviceId = Keypair.generate().publicKey.toBuffer();
+    const [robot] = PublicKey.findProgramAddressSync(
+      [Buffer.from("robot"), deviceId],
+      identityRegistry.programId
+    );
+    await identityRegistry.methods
+      .registerRobot([...deviceId], "Acme", "X1", Array(32).fill(0), { drone: {} })
+      .accountsPartial({ robot, operator: operator.publicKey })
+      .signers([operator])
+      .rpc();
+    await identityRegistry.methods
+      .addCapability({ inspection: {} }, 3, 30)
+      .accountsPartial({ robot, authority: operator.publicKey })
+      .signers([operator])
+      .rpc();
+    await identityRegistry.methods
+      .updateStatus({ available: {} })
+      .accountsPartial({ robot, operator: operator.publicKey })
+      .signers([operator])
+      .rpc();
+
+    const [bid] = PublicKey.findProgramAddressSync(
+      [Buffer.from("bid"), task.toBuffer(), robot.toBuffer()],
+      taskMarket.programId
+    );
+
+    await taskMarket.methods
+      .submitBid(new BN(12_000), 3_000, "Ready now", new BN(3_600), [])
+      .accountsPartial({ task, bid, robot, operator: operator.publicKey })
+      .signers([operator])
+      .rpc();
+
+    const [streamConfig] = PublicKey.findProgramAddressSync([Buffer.from("config")], paymentStreams.programId);
+    const [stream] = PublicKey.findProgramAddressSync(
+      [Buffer.from("task_stream"), task.toBuffer()],
+      paymentStreams.programId
+    );
+    const [escrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("escrow"), stream.toBuffer()],
+      paymentStreams.programId
+    );
+    const [creatorRegistry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("payer_streams"), creator.publicKey.toBuffer()],
+      paymentStreams.programId
+    );
+    const [operatorRegistry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("payee_streams"), operator.publicKey.toBuffer()],
+      paymentStreams.programId
+    );
+    const [streamEventAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("__event_authority")],
+      paymentStreams.programId
+    );
+    const [registryEventAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("__event_authority")],
+      identityRegistry.programId
+    );
+    // The operator has no operator stake, so no active-task CPI is made
+    const [operatorStake] = PublicKey.findProgramAddressSync(
+      [Buffer.from("operator"), operator.publicKey.toBuffer()],
+      droneosToken.programId
+    );
+
+    const sig = await taskMarket.methods
+      .acceptBid(0)
+      .accountsPartial({
+        market,
+        task,
+        bid,
+        creator: creator.publicKey,
+        streamConfig,
+        stream,
+        escrow,
+        mint,
+        creatorToken,
+        operator: operator.publicKey,
+        creatorRegistry,
+        operatorRegistry,
+        streamEventAuthority,
+        operatorStake,
+        robot,
+        registryEventAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenConfig: null,
+        insurancePool: null,
+        insuranceVault: null,
+        premiumToken: null,
+        premiumMint: null,
+        premiumTokenProgram: null,
+        tokenEventAuthority: null,
+      })
+      .signers([creator])
+      .rpc();
+
+    checkBudget("accept_bid", await computeUnits(sig));
+  });
+
+  it("distribute_rewards stays within budget", async () => {
+    const leader = Keypair.generate();
+    const creator = Keypair.generate();
+    await fund(leader, creator);
+
+    await initializeOnce(() =>
+      swarmCoordinator.methods.initialize().accounts({ authority: provider.wallet.publicKey }).rpc()
+    );
+
+    const [coordinator] = PublicKey.findProgramAddressSync([Buffer.from("coordinator")], swarmCoordinator.programId);
+    const [swarm] = PublicKey.findProgramAddressSync(
+      [Buffer.from("swarm"), leader.publicKey.toBuffer()],
+      swarmCoordinator.programId
+    );
+
+    await swarmCoordinator.methods
+      .createSwarm("bench-swarm", 2, 0)
+      .accountsPartial({ coordinator, swarm, leader: leader.publicKey })
+      .signers([leader])
+      .rpc();
+
+    const robots = [Keypair.generate().publicKey, Keypair.generate().publicKey];
+    const memberships: PublicKey[] = [];
+    for (const robot of robots) {
+      const [membership] = PublicKey.findProgramAddressSync(
+        [Buffer.from("membership"), swarm.toBuffer(), robot.toBuffer()],
+        swarmCoordinator.programId
+      );
+      memberships.push(membership);
+      await swarmCoordinator.methods
+        .joinSwarm()
+        .accountsPartial({ swarm, membership, robot, operator: leader.publicKey })
+        .signers([leader])
+        .rpc();
+    }
+
+    const coordinatorAccount: any = await swarmCoordinator.account.coordinator.fetch(coordinator);
+    const [groupTask] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("group-task"),
+        creator.publicKey.toBuffer(),
+        new BN(coordinatorAccount.totalGroupTasks).toArrayLike(Buffer, "le", 8),
+      ],
+      swarmCoordinator.programId
+    );
+
+    await swarmCoordinator.methods
+      .createGroupTask("Field survey", "Map 40ha", 2, new BN(100_000_000), new BN(7_200))
+      .accountsPartial({ coordinator, groupTask, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+
+    const [bid] = PublicKey.findProgramAddressSync(
+      [Buffer.from("swarm-bid"), groupTask.toBuffer(), swarm.toBuffer()],
+      swarmCoordinator.programId
+    );
+
+    await swarmCoordinator.methods
+      .swarmBid(new BN(10_000), new BN(7_000))
+      .accountsPartial({ swarm, groupTask, bid, leader: leader.publicKey })
+      .signers([leader])
+      .rpc();
+
+    await swarmCoordinator.methods
+      .acceptSwarmBid()
+      .accountsPartial({ groupTask, bid, swarm, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+
+    await swarmCoordinator.methods
+      .completeGroupTask()
+      .accountsPartial({ groupTask, swarm, leader: leader.publicKey })
+      .signers([leader])
+      .rpc();
+
+    const sig = await swarmCoordinator.methods
+      .distributeRewards()
+      .accountsPartial({
+        groupTask,
+        membership: memberships[0],
+        operator: leader.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([leader])
+      .rpc();
+
+    checkBudget("distribute_rewards", await computeUnits(sig));
+  });
+});
@@ -0,0 +1,120 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  pda,
+  programs,
+  startStream,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Cranker tips: whoever submits a tick with a token account for the stream's
+ * mint is paid the configured tip, flat or a share of the tick, out of
+ * escrow. Crankers who bring no token account forgo it.
+ */
+describe("Payment Streams: cranker tips", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+  const config = pda(paymentStreams.programId, Buffer.from("config"));
+
+  const keeper = Keypair.generate();
+  let s: Stream;
+  let keeperToken: PublicKey;
+
+  function setCrankerTip(crankerTip: object) {
+    return paymentStreams.methods.setCrankerTip(crankerTip as any).accountsPartial({ config, authority }).rpc();
+  }
+
+  function tickAs(crankerToken: PublicKey | null) {
+    return paymentStreams.methods
+      .tick()
+      .accountsPartial({
+        stream: s.stream,
+        mint: s.mint,
+        claimToken: null,
+        payeeToken: s.payeeToken,
+        treasury: s.treasury,
+        cranker: keeper.publicKey,
+        crankerToken,
+        referrerAccount: null,
+        referrerToken: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([keeper])
+      .rpc();
+  }
+
+  /** Tick after the next second passes, returning the tick amount and tip */
+  async function tickAndTip(crankerToken: PublicKey | null) {
+    const before: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(before.lastTickAt.addn(1));
+    const tokenBefore = (await getAccount(connection, keeperToken)).amount;
+    await tickAs(crankerToken);
+
+    const after: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    return {
+      amount: after.totalPaid.sub(before.totalPaid).toNumber(),
+      tip: Number((await getAccount(connection, keeperToken)).amount - tokenBefore),
+    };
+  }
+
+  before(async () => {
+    await fund(keeper);
+    s = await openStream();
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    keeperToken = await createAccount(connection, keeper, s.mint, keeper.publicKey);
+  });
+
+  after(async () => {
+    // The config is shared with other test files
+    await setCrankerTip({ none: {} });
+  });
+
+  it("rejects a tip set by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      paymentStreams.methods
+        .setCrankerTip({ flat: [new BN(500)] } as any)
+        .accountsPartial({ config, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects a tip over 100% of the tick", async () => {
+    await expectError(setCrankerTip({ basisPoints: [10_001] }), "InvalidCrankerTip");
+  });
+
+  it("pays a share of the tick to the cranker", async () => {
+    await setCrankerTip({ basisPoints: [1_000] });
+    const { amount, tip } = await tickAndTip(keeperToken);
+
+    expect(tip).to.be.gt(0);
+    expect(tip).to.equal(Math.floor((amount * 1_000) / 10_000));
+  });
+
+  it("pays a flat tip to the cranker", async () => {
+    await setCrankerTip({ flat: [new BN(500)] });
+    const { tip } = await tickAndTip(keeperToken);
+
+    expect(tip).to.equal(500);
+  });
+
+  it("pays nothing to a cranker without a token account", async () => {
+    const { tip } = await tickAndTip(null);
+
+    expect(tip).to.equal(0);
+  });
+});
@@ -0,0 +1,61 @@
+import { createHash } from "crypto";
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Assignment,
+  TaskStatus,
+  acceptBid,
+  completeTask,
+  drip,
+  expectError,
+  openBidTask,
+  programs,
+  settlementAccounts,
+  stake,
+  stakedOperator,
+  startTask,
+  verifyCompletion,
+} from "./helpers";
+
+/**
+ * Creator fee tiers: a creator approving a task can name one of their
+ * DRONEOS stake positions, recording its fee tier on the task and
+ * discounting the platform fee on the task's final payout.
+ */
+describe("Task Market: creator fee tiers", () => {
+  const { taskMarket, paymentStreams } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const deliverable = createHash("sha256").update("pylons 3-7: no cracks found").digest();
+  let a: Assignment;
+  let stream: Awaited<ReturnType<typeof settlementAccounts>>;
+  let creatorStake: PublicKey;
+
+  before(async () => {
+    a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await startTask(a).rpc();
+    await completeTask(a, deliverable).rpc();
+    stream = await settlementAccounts(a);
+    creatorStake = await stake(a.creator, await drip(a.creator, AMOUNT), AMOUNT);
+  });
+
+  it("rejects a stake account that isn't a stake position", async () => {
+    const { operatorStake } = await stakedOperator();
+    await expectError(
+      verifyCompletion(a, stream).accountsPartial({ creatorStake: operatorStake }).rpc(),
+      "AccountDiscriminatorMismatch"
+    );
+  });
+
+  it("records the position's fee tier on the approved task", async () => {
+    await verifyCompletion(a, stream).accountsPartial({ creatorStake }).rpc();
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.status).to.equal(TaskStatus.Completed);
+    // 100 DRONEOS is below the first tier
+    expect(task.feeTier).to.equal(0);
+    const settled: any = await paymentStreams.account.paymentStream.fetch(stream.stream);
+    expect(settled.feeDiscountBps).to.equal(0);
+  });
+});
@@ -0,0 +1,188 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount, transferChecked } from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, pda, programs, setupToken, stakedOperator } from "./helpers";
+
+/**
+ * Delegated staking: holders delegate tokens to an operator's bond, earn a
+ * share of the income the operator passes on and withdraw after a cooldown.
+ */
+describe("DRONEOS Token: delegated staking", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const AMOUNT = 100 * 1_000_000;
+  const INCOME = 1_000_000;
+  const SHARE_BPS = 5000;
+  const delegator = Keypair.generate();
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let operator: Keypair;
+  let operatorStake: PublicKey;
+  let operatorToken: PublicKey;
+  let delegatorToken: PublicKey;
+  let delegation: PublicKey;
+
+  function setDelegatorShare(shareBps: number, signer = operator) {
+    return droneosToken.methods
+      .setDelegatorShare(shareBps)
+      .accountsPartial({ operatorStake, operator: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  function delegationAccounts() {
+    return {
+      config: t.config,
+      operatorStake,
+      delegation,
+      operatorVault: t.operatorVault,
+      delegatorToken,
+      delegator: delegator.publicKey,
+      mint: t.mint,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    };
+  }
+
+  function delegateStake(amount: number) {
+    return droneosToken.methods
+      .delegateStake(new BN(amount))
+      .accountsPartial(delegationAccounts())
+      .signers([delegator])
+      .rpc();
+  }
+
+  function undelegateStake(shares: BN) {
+    return droneosToken.methods
+      .undelegateStake(shares)
+      .accountsPartial({ delegation, delegator: delegator.publicKey })
+      .signers([delegator])
+      .rpc();
+  }
+
+  function claimDelegationIncome() {
+    return droneosToken.methods
+      .claimDelegationIncome()
+      .accountsPartial(delegationAccounts())
+      .signers([delegator])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(delegator, intruder);
+    t = await setupToken();
+    ({ operator, operatorStake } = await stakedOperator());
+    delegation = pda(
+      droneosToken.programId,
+      Buffer.from("delegation"),
+      operator.publicKey.toBuffer(),
+      delegator.publicKey.toBuffer()
+    );
+    delegatorToken = await drip(delegator, 2 * AMOUNT);
+
+    // Income for the operator to pass on
+    operatorToken = await drip(operator);
+    await transferChecked(
+      connection,
+      delegator,
+      delegatorToken,
+      t.mint,
+      operatorToken,
+      delegator,
+      INCOME,
+      6,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+  });
+
+  after(async () => {
+    // The operator is shared with other test files
+    await setDelegatorShare(0);
+  });
+
+  it("rejects a delegator share set by anyone but the operator", async () => {
+    await expectError(setDelegatorShare(SHARE_BPS, intruder), "ConstraintSeeds");
+  });
+
+  it("rejects a delegator share over 100%", async () => {
+    await expectError(setDelegatorShare(10_001), "InvalidDelegatorShare");
+  });
+
+  it("rejects a delegation under the minimum stake", async () => {
+    const { minStake } = await droneosToken.account.tokenConfig.fetch(t.config);
+    await expectError(delegateStake(minStake.toNumber() - 1), "BelowMinimumStake");
+  });
+
+  it("adds a delegation to the operator's bond", async () => {
+    const before: any = await droneosToken.account.operatorStake.fetch(operatorStake);
+    const vaultBefore = await balance(t.operatorVault);
+
+    await delegateStake(AMOUNT);
+
+    const after: any = await droneosToken.account.operatorStake.fetch(operatorStake);
+    expect(after.delegatedAmount.sub(before.delegatedAmount).toNumber()).to.equal(AMOUNT);
+    expect(after.delegatorCount).to.equal(before.delegatorCount + 1);
+    expect((await balance(t.operatorVault)) - vaultBefore).to.equal(AMOUNT);
+    const delegated: any = await droneosToken.account.delegation.fetch(delegation);
+    expect(delegated.delegator.toBase58()).to.equal(delegator.publicKey.toBase58());
+    expect(delegated.shares.toNumber()).to.be.gt(0);
+  });
+
+  it("rejects claiming before the operator shares any income", async () => {
+    await expectError(claimDelegationIncome(), "NoRewardsToClaim");
+  });
+
+  it("pays delegators their share of the operator's income", async () => {
+    await setDelegatorShare(SHARE_BPS);
+    await droneosToken.methods
+      .shareOperatorIncome(new BN(INCOME))
+      .accountsPartial({
+        config: t.config,
+        operatorStake,
+        operatorVault: t.operatorVault,
+        operatorToken,
+        operator: operator.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([operator])
+      .rpc();
+    expect(await balance(operatorToken)).to.equal(INCOME - (INCOME * SHARE_BPS) / 10_000);
+
+    const before = await balance(delegatorToken);
+    await claimDelegationIncome();
+
+    // The only delegator, so all of the cut less rounding
+    const claimed = (await balance(delegatorToken)) - before;
+    expect(claimed).to.be.lte((INCOME * SHARE_BPS) / 10_000);
+    expect(claimed).to.be.gte((INCOME * SHARE_BPS) / 10_000 - 1);
+  });
+
+  it("holds undelegated shares through the cooldown", async () => {
+    const { shares } = await droneosToken.account.delegation.fetch(delegation);
+    await expectError(undelegateStake(shares.addn(1)), "InsufficientStake");
+
+    await undelegateStake(shares);
+    const delegated: any = await droneosToken.account.delegation.fetch(delegation);
+    expect(delegated.unbondingShares.toNumber()).to.equal(shares.toNumber());
+    expect(delegated.unbondingStartedAt).to.not.equal(null);
+    await expectError(undelegateStake(shares), "UndelegationInProgress");
+
+    await expectError(
+      droneosToken.methods
+        .finalizeUndelegation()
+        .accountsPartial(delegationAccounts())
+        .signers([delegator])
+        .rpc(),
+      "UnbondingNotComplete"
+    );
+  });
+});
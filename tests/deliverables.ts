@@ -0,0 +1,73 @@
+import { createHash } from "crypto";
+import { expect } from "chai";
+import {
+  Assignment,
+  TaskStatus,
+  acceptBid,
+  completeTask,
+  expectError,
+  openBidTask,
+  programs,
+  registerRobot,
+  startTask,
+  taskStreamAccounts,
+} from "./helpers";
+
+/**
+ * Deliverables: a robot completes its task with the SHA-256 hash of what it
+ * delivered and optionally a URI to fetch it from, recorded on the task for
+ * verification and any dispute.
+ */
+describe("Task Market: deliverables", () => {
+  const { taskMarket, paymentStreams } = programs();
+
+  const deliverable = createHash("sha256").update("pylons 3-7: no cracks found").digest();
+  const URI = "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi";
+
+  async function inProgress(): Promise<Assignment> {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await startTask(a).rpc();
+    return a;
+  }
+
+  it("rejects completing a task that hasn't started", async () => {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await expectError(completeTask(a, deliverable).rpc(), "TaskNotInProgress");
+  });
+
+  it("rejects completion for another robot", async () => {
+    const a = await inProgress();
+    const other = await registerRobot(a.operator);
+    await expectError(completeTask({ ...a, robot: other }, deliverable).rpc(), "NotAssignedRobot");
+  });
+
+  it("rejects a URI longer than the task holds", async () => {
+    const a = await inProgress();
+    await expectError(completeTask(a, deliverable, "x".repeat(129)).rpc(), "DeliverableUriTooLong");
+  });
+
+  it("records the deliverable and pauses the stream for verification", async () => {
+    const a = await inProgress();
+    await completeTask(a, deliverable, URI).rpc();
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.status).to.equal(TaskStatus.PendingVerification);
+    expect(task.progress).to.equal(100);
+    expect(Buffer.from(task.deliverableHash).equals(deliverable)).to.equal(true);
+    expect(Buffer.from(task.deliverableUri.slice(0, task.deliverableUriLen)).toString()).to.equal(URI);
+
+    const { stream } = taskStreamAccounts(a.task, a.mint, a.creator.publicKey, a.operator.publicKey);
+    const account: any = await paymentStreams.account.paymentStream.fetch(stream);
+    expect(account.status).to.have.property("paused");
+  });
+
+  it("records a deliverable without a URI", async () => {
+    const a = await inProgress();
+    await completeTask(a, deliverable).rpc();
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.deliverableUriLen).to.equal(0);
+  });
+});
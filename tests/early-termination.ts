@@ -0,0 +1,73 @@
+import * as anchor from "@coral-xyz/anchor";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  terminateStream,
+} from "./helpers";
+
+/**
+ * Early-termination fees: a payer who ends a started stream inside its
+ * minimum runtime pays the payee a share of the unstreamed escrow. The
+ * payee ending it early costs the payer nothing.
+ */
+describe("Payment Streams: early-termination fees", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const EARLY = { minRuntime: 3_600, penaltyBps: 2_000 };
+
+  async function runningStream() {
+    const s = await openStream({ earlyTermination: EARLY });
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    return s;
+  }
+
+  /** Terminate as `authority`, returning the unstreamed escrow left after
+   *  the final payment and what the payer got back */
+  async function settle(s: Stream, authority = s.payer) {
+    const before: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const payerBefore = (await getAccount(connection, s.payerToken)).amount;
+    await terminateStream(s, authority).rpc();
+
+    const after: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const payerAfter = (await getAccount(connection, s.payerToken)).amount;
+    const finalPayment = after.totalPaid.sub(before.totalPaid).toNumber();
+    return {
+      unstreamed: before.escrowBalance.toNumber() - finalPayment,
+      refunded: Number(payerAfter - payerBefore),
+    };
+  }
+
+  it("rejects a zero minimum runtime or a penalty over 100%", async () => {
+    await expectError(
+      openStream({ earlyTermination: { minRuntime: 0, penaltyBps: 2_000 } }),
+      "InvalidEarlyTermination"
+    );
+    await expectError(
+      openStream({ earlyTermination: { minRuntime: 3_600, penaltyBps: 10_001 } }),
+      "InvalidEarlyTermination"
+    );
+  });
+
+  it("charges the payer for ending the stream inside the window", async () => {
+    const { unstreamed, refunded } = await settle(await runningStream());
+
+    const fee = Math.floor((unstreamed * EARLY.penaltyBps) / 10_000);
+    expect(fee).to.be.gt(0);
+    expect(refunded).to.equal(unstreamed - fee);
+  });
+
+  it("charges nothing when the payee ends the stream", async () => {
+    const s = await runningStream();
+    const { unstreamed, refunded } = await settle(s, s.payee);
+
+    expect(refunded).to.equal(unstreamed);
+  });
+});
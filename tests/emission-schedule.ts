@@ -0,0 +1,45 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { TokenSetup, expectError, fund, pda, programs, setupToken } from "./helpers";
+
+/**
+ * Emission schedule: staking reward payouts are capped per epoch, and only
+ * the authority can append epochs, up to the schedule's fixed length.
+ */
+describe("DRONEOS Token: emission schedule", () => {
+  const { droneosToken } = programs();
+  const authority = anchor.getProvider().publicKey!;
+
+  const CAP = new BN(1_000 * 1_000_000);
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let emissions: PublicKey;
+
+  function appendEmissionEpochs(caps: BN[], signer?: Keypair) {
+    return droneosToken.methods
+      .appendEmissionEpochs(caps)
+      .accountsPartial({ config: t.config, emissions, authority: signer?.publicKey ?? authority })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    emissions = pda(droneosToken.programId, Buffer.from("emissions"));
+  });
+
+  it("rejects epochs appended by anyone but the authority", async () => {
+    await expectError(appendEmissionEpochs([CAP], intruder), "Unauthorized");
+  });
+
+  it("rejects growing the schedule past its maximum length", async () => {
+    // The test schedule is created at the maximum length
+    const { epochCaps } = await droneosToken.account.emissionSchedule.fetch(emissions);
+    expect(epochCaps.length).to.equal(64);
+
+    await expectError(appendEmissionEpochs([CAP]), "TooManyEpochs");
+  });
+});
@@ -0,0 +1,77 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import { CRANK_TIP, TokenSetup, drip, expectError, fund, pda, programs, setupToken } from "./helpers";
+
+/**
+ * Epoch reward crank: anyone can move the next epoch's emission budget from
+ * the treasury into the rewards vault, and is tipped for doing so.
+ */
+describe("DRONEOS Token: epoch reward crank", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const cranker = Keypair.generate();
+  const bystander = Keypair.generate();
+  let t: TokenSetup;
+  let emissions: PublicKey;
+  let crankerToken: PublicKey;
+  let bystanderToken: PublicKey;
+
+  function distributeEpochRewards(rewardsVault: PublicKey, token: PublicKey = crankerToken) {
+    return droneosToken.methods
+      .distributeEpochRewards()
+      .accountsPartial({
+        config: t.config,
+        emissions,
+        treasury: t.treasury,
+        rewardsVault,
+        crankerToken: token,
+        cranker: cranker.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([cranker])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(cranker, bystander);
+    t = await setupToken();
+    emissions = pda(droneosToken.programId, Buffer.from("emissions"));
+    crankerToken = await drip(cranker);
+    bystanderToken = await drip(bystander);
+  });
+
+  it("rejects paying the epoch budget back into the treasury", async () => {
+    await expectError(distributeEpochRewards(t.treasury), "InvalidVault");
+  });
+
+  it("rejects a rewards vault the config doesn't own", async () => {
+    await expectError(distributeEpochRewards(crankerToken), "InvalidVault");
+  });
+
+  it("rejects tipping a token account the cranker doesn't own", async () => {
+    await expectError(distributeEpochRewards(t.rewardsVault, bystanderToken), "ConstraintRaw");
+  });
+
+  it("funds the next epoch's budget and tips the cranker", async () => {
+    const before: any = await droneosToken.account.emissionSchedule.fetch(emissions);
+    const budget = before.epochCaps[before.fundedEpochs.toNumber()].toNumber();
+    const treasuryBefore = await balance(t.treasury);
+    const vaultBefore = await balance(t.rewardsVault);
+
+    await distributeEpochRewards(t.rewardsVault);
+
+    expect((await balance(t.rewardsVault)) - vaultBefore).to.equal(budget);
+    expect(await balance(crankerToken)).to.equal(CRANK_TIP);
+    expect(treasuryBefore - (await balance(t.treasury))).to.equal(budget + CRANK_TIP);
+    const after: any = await droneosToken.account.emissionSchedule.fetch(emissions);
+    expect(after.fundedEpochs.toNumber()).to.equal(before.fundedEpochs.toNumber() + 1);
+  });
+});
@@ -0,0 +1,83 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, mintTo } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  pda,
+  programs,
+  startStream,
+  terminateStream,
+} from "./helpers";
+
+/**
+ * Escrow sponsorship: anyone can top up a stream's escrow from their own
+ * tokens, with each sponsor's running total tracked on a sponsorship PDA.
+ */
+describe("Payment Streams: escrow sponsorship", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const sponsor = Keypair.generate();
+  let s: Stream;
+  let sponsorToken: PublicKey;
+  let sponsorship: PublicKey;
+
+  function sponsorEscrow(amount: number, fromToken = sponsorToken) {
+    return paymentStreams.methods
+      .sponsorEscrow(new BN(amount))
+      .accountsPartial({
+        stream: s.stream,
+        escrow: s.escrow,
+        mint: s.mint,
+        sponsorship,
+        sponsorToken: fromToken,
+        sponsor: sponsor.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([sponsor])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(sponsor);
+    s = await openStream();
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+
+    sponsorToken = await createAccount(connection, sponsor, s.mint, sponsor.publicKey);
+    await mintTo(connection, s.payer, s.mint, sponsorToken, s.payer, 1_000_000);
+    sponsorship = pda(
+      paymentStreams.programId,
+      Buffer.from("sponsorship"),
+      s.stream.toBuffer(),
+      sponsor.publicKey.toBuffer()
+    );
+  });
+
+  it("rejects sponsoring from someone else's tokens", async () => {
+    await expectError(sponsorEscrow(100_000, s.payerToken), "ConstraintRaw");
+  });
+
+  it("adds to the escrow and tracks the sponsor's total", async () => {
+    const before = (await paymentStreams.account.paymentStream.fetch(s.stream)).escrowBalance.toNumber();
+    await sponsorEscrow(100_000);
+    await sponsorEscrow(50_000);
+
+    const stream: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const account: any = await paymentStreams.account.sponsorship.fetch(sponsorship);
+    expect(stream.escrowBalance.toNumber()).to.equal(before + 150_000);
+    expect(account.sponsor.toBase58()).to.equal(sponsor.publicKey.toBase58());
+    expect(account.totalContributed.toNumber()).to.equal(150_000);
+  });
+
+  it("rejects sponsoring a finished stream", async () => {
+    await terminateStream(s).rpc();
+    await expectError(sponsorEscrow(100_000), "StreamAlreadyTerminated");
+  });
+});
@@ -0,0 +1,109 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Stream,
+  TokenSetup,
+  acceptStream,
+  cpiEvents,
+  drip,
+  fund,
+  openStream,
+  programs,
+  setupToken,
+  stake,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Event sequencing: every protocol event opens with a versioned header
+ * naming its program and entity, numbered per entity without gaps so an
+ * indexer can order them and detect missed ones.
+ */
+describe("Payment Streams: event headers", () => {
+  const { paymentStreams } = programs();
+
+  let s: Stream;
+
+  before(async () => {
+    s = await openStream();
+  });
+
+  it("numbers a stream's events consecutively across transactions", async () => {
+    const signatures = [await acceptStream(s).rpc(), await startStream(s).rpc()];
+    const { startedAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(startedAt).addn(1));
+    signatures.push(await tick(s).rpc());
+
+    const emitted = [];
+    for (const signature of signatures) {
+      emitted.push(await cpiEvents(paymentStreams, signature));
+    }
+    // One event per instruction: no low-escrow threshold is set and the tick isn't stale
+    expect(emitted.map((events) => events.map((e) => e.name))).to.deep.equal([
+      ["streamAccepted"],
+      ["streamStarted"],
+      ["streamTick"],
+    ]);
+
+    const headers = emitted.flat().map((e) => e.data.header);
+    for (const header of headers) {
+      expect(header.version).to.equal(1);
+      expect(header.program).to.have.property("paymentStreams");
+      expect(header.entity.equals(s.stream)).to.equal(true);
+    }
+    // The stream's creation event was number 1
+    expect(headers.map((h) => h.seq.toNumber())).to.deep.equal([2, 3, 4]);
+
+    const { eventSeq } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(eventSeq.toNumber()).to.equal(4);
+  });
+});
+
+describe("DRONEOS Token: event headers", () => {
+  const { droneosToken } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const staker = Keypair.generate();
+  let t: TokenSetup;
+  let position: PublicKey;
+
+  before(async () => {
+    await fund(staker);
+    t = await setupToken();
+    position = await stake(staker, await drip(staker, AMOUNT), AMOUNT, 30);
+  });
+
+  it("numbers a position's events consecutively across transactions", async () => {
+    // Staking without a referrer emits TokensStaked alone
+    expect((await droneosToken.account.stakeAccount.fetch(position)).eventSeq.toNumber()).to.equal(1);
+
+    const accounts = { config: t.config, stakeAccount: position, user: staker.publicKey };
+    const signatures = [
+      await droneosToken.methods.enableAutoRelock().accountsPartial(accounts).signers([staker]).rpc(),
+      await droneosToken.methods.requestUnlock().accountsPartial(accounts).signers([staker]).rpc(),
+    ];
+
+    const emitted = [];
+    for (const signature of signatures) {
+      emitted.push(await cpiEvents(droneosToken, signature));
+    }
+    expect(emitted.map((events) => events.map((e) => e.name))).to.deep.equal([
+      ["autoRelockEnabled"],
+      ["unlockRequested"],
+    ]);
+
+    const headers = emitted.flat().map((e) => e.data.header);
+    for (const header of headers) {
+      expect(header.version).to.equal(1);
+      expect(header.program).to.have.property("token");
+      expect(header.entity.equals(position)).to.equal(true);
+    }
+    expect(headers.map((h) => h.seq.toNumber())).to.deep.equal([2, 3]);
+
+    const { eventSeq } = await droneosToken.account.stakeAccount.fetch(position);
+    expect(eventSeq.toNumber()).to.equal(3);
+  });
+});
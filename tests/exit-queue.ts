@@ -0,0 +1,188 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  TokenSetup,
+  drip,
+  expectError,
+  fund,
+  pda,
+  programs,
+  setupToken,
+  stake,
+  u64,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Exit queue: withdrawals of at least the threshold leave their position
+ * at once but are paid out first in, first out, at most the epoch cap per
+ * emission epoch.
+ */
+describe("DRONEOS Token: exit queue", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const POSITION = 100 * 1_000_000;
+  const THRESHOLD = POSITION;
+  const EPOCH_CAP = 150 * 1_000_000;
+  const staker = Keypair.generate();
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let stakerToken: PublicKey;
+  let exitQueue: PublicKey;
+  let emissions: PublicKey;
+  const positions: PublicKey[] = [];
+
+  function setExitQueue(threshold: number, epochCap: number) {
+    return droneosToken.methods
+      .setExitQueue(new BN(threshold), new BN(epochCap))
+      .accountsPartial({ config: t.config, exitQueue, authority })
+      .rpc();
+  }
+
+  function withdrawalAccounts(stakeAccount: PublicKey) {
+    return {
+      config: t.config,
+      stakeAccount,
+      positions: pda(droneosToken.programId, Buffer.from("positions"), staker.publicKey.toBuffer()),
+      emissions,
+      rewardsVault: t.rewardsVault,
+      userToken: stakerToken,
+      user: staker.publicKey,
+      mint: t.mint,
+      receiptMint: null,
+      receiptToken: null,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    };
+  }
+
+  async function queueUnstake(stakeAccount: PublicKey, amount: number | null = null): Promise<PublicKey> {
+    const { tail } = await droneosToken.account.exitQueue.fetch(exitQueue);
+    const ticket = pda(droneosToken.programId, Buffer.from("exit-ticket"), u64(tail));
+    await droneosToken.methods
+      .queueUnstake(amount === null ? null : new BN(amount))
+      .accountsPartial({ ...withdrawalAccounts(stakeAccount), exitQueue, ticket })
+      .signers([staker])
+      .rpc();
+    return ticket;
+  }
+
+  function processExitQueue(head: BN, ownerToken = stakerToken) {
+    return droneosToken.methods.processExitQueue().accountsPartial({
+      config: t.config,
+      emissions,
+      exitQueue,
+      ticket: pda(droneosToken.programId, Buffer.from("exit-ticket"), u64(head)),
+      owner: staker.publicKey,
+      ownerToken,
+      stakeVault: t.stakeVault,
+      rewardsVault: t.rewardsVault,
+      mint: t.mint,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    });
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(staker, intruder);
+    t = await setupToken();
+    exitQueue = pda(droneosToken.programId, Buffer.from("exit-queue"));
+    emissions = pda(droneosToken.programId, Buffer.from("emissions"));
+    stakerToken = await drip(staker, 2 * POSITION);
+    positions.push(await stake(staker, stakerToken, POSITION));
+    positions.push(await stake(staker, stakerToken, POSITION));
+  });
+
+  after(async () => {
+    // The token config is shared with other test files
+    await setExitQueue(0, 0);
+  });
+
+  it("rejects exit queue settings by anyone but the authority", async () => {
+    await expectError(
+      droneosToken.methods
+        .setExitQueue(new BN(THRESHOLD), new BN(EPOCH_CAP))
+        .accountsPartial({ config: t.config, exitQueue, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects a queue that could never pay out", async () => {
+    await expectError(setExitQueue(THRESHOLD, 0), "InvalidAmount");
+  });
+
+  it("routes withdrawals of the threshold or more through the queue", async () => {
+    await setExitQueue(THRESHOLD, EPOCH_CAP);
+
+    await expectError(
+      droneosToken.methods
+        .unstake(null)
+        .accountsPartial({ ...withdrawalAccounts(positions[0]), stakeVault: t.stakeVault })
+        .signers([staker])
+        .rpc(),
+      "ExitQueueRequired"
+    );
+    await expectError(queueUnstake(positions[0], THRESHOLD / 2), "BelowExitQueueThreshold");
+  });
+
+  it("queues a withdrawal, taking it out of the position", async () => {
+    const before: any = await droneosToken.account.exitQueue.fetch(exitQueue);
+    const ticket = await queueUnstake(positions[0]);
+    await queueUnstake(positions[1]);
+
+    const queued: any = await droneosToken.account.exitTicket.fetch(ticket);
+    expect(queued.amount.toNumber()).to.equal(POSITION);
+    expect(queued.owner.toBase58()).to.equal(staker.publicKey.toBase58());
+    const position: any = await droneosToken.account.stakeAccount.fetch(positions[0]);
+    expect(position.amount.toNumber()).to.equal(0);
+    const queue: any = await droneosToken.account.exitQueue.fetch(exitQueue);
+    expect(queue.tail.sub(before.tail).toNumber()).to.equal(2);
+    expect(queue.queuedAmount.sub(before.queuedAmount).toNumber()).to.equal(2 * POSITION);
+  });
+
+  it("rejects paying a ticket to anyone but its owner", async () => {
+    const { head } = await droneosToken.account.exitQueue.fetch(exitQueue);
+    await expectError(processExitQueue(head, await drip(intruder)).rpc(), "Unauthorized");
+  });
+
+  it("rejects paying out more than the epoch cap in one epoch", async () => {
+    const { head } = await droneosToken.account.exitQueue.fetch(exitQueue);
+    // Both in one transaction, so within one emission epoch
+    const next = await processExitQueue(head.addn(1)).instruction();
+    await expectError(processExitQueue(head).postInstructions([next]).rpc(), "ExitEpochCapReached");
+  });
+
+  it("pays the head of the queue, closing its ticket", async () => {
+    const { head } = await droneosToken.account.exitQueue.fetch(exitQueue);
+    const ticket = pda(droneosToken.programId, Buffer.from("exit-ticket"), u64(head));
+    const before = await balance(stakerToken);
+
+    await processExitQueue(head).rpc();
+
+    // The schedule's epochs are over, so there are no rewards on top
+    expect((await balance(stakerToken)) - before).to.equal(POSITION);
+    expect(await connection.getAccountInfo(ticket)).to.equal(null);
+    const queue: any = await droneosToken.account.exitQueue.fetch(exitQueue);
+    expect(queue.head.toNumber()).to.equal(head.toNumber() + 1);
+  });
+
+  it("pays the next ticket in a later epoch", async () => {
+    const now = await connection.getBlockTime(await connection.getSlot("confirmed"));
+    await waitForClock(now! + 1);
+
+    const { head } = await droneosToken.account.exitQueue.fetch(exitQueue);
+    const before = await balance(stakerToken);
+    await processExitQueue(head).rpc();
+
+    expect((await balance(stakerToken)) - before).to.equal(POSITION);
+  });
+});
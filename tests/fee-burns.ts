@@ -0,0 +1,125 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount, getMint } from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, programs, setupToken } from "./helpers";
+
+/**
+ * Fee burns: the burn share of protocol fees deposited into the treasury is
+ * burned on arrival, and the authority can burn treasury tokens outright.
+ */
+describe("DRONEOS Token: protocol fee burns", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const FEES = 100 * 1_000_000;
+  const BURN_SHARE = 2_500;
+  const payer = Keypair.generate();
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let payerToken: PublicKey;
+  let originalShare: number;
+
+  function setBurnShare(bps: number, signer?: Keypair) {
+    return droneosToken.methods
+      .setBurnShare(bps)
+      .accountsPartial({ config: t.config, authority: signer?.publicKey ?? authority })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  function burnFromTreasury(amount: number, signer?: Keypair) {
+    return droneosToken.methods
+      .burnFromTreasury(new BN(amount))
+      .accountsPartial({
+        config: t.config,
+        mint: t.mint,
+        treasury: t.treasury,
+        authority: signer?.publicKey ?? authority,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  function depositFees(amount: number) {
+    return droneosToken.methods
+      .depositFees(new BN(amount))
+      .accountsPartial({
+        config: t.config,
+        mint: t.mint,
+        treasury: t.treasury,
+        payerToken,
+        payer: payer.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([payer])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  async function supply() {
+    return Number((await getMint(connection, t.mint, undefined, TOKEN_2022_PROGRAM_ID)).supply);
+  }
+
+  async function totalBurned() {
+    return (await droneosToken.account.tokenConfig.fetch(t.config)).totalBurned.toNumber();
+  }
+
+  before(async () => {
+    await fund(payer, intruder);
+    t = await setupToken();
+    payerToken = await drip(payer, FEES);
+    ({ burnShareBps: originalShare } = await droneosToken.account.tokenConfig.fetch(t.config));
+  });
+
+  after(async () => {
+    // The token config is shared with other test files
+    await setBurnShare(originalShare);
+  });
+
+  it("rejects a burn share set by anyone but the authority", async () => {
+    await expectError(setBurnShare(BURN_SHARE, intruder), "Unauthorized");
+  });
+
+  it("rejects a burn share over 100%", async () => {
+    await expectError(setBurnShare(10_001), "InvalidBurnShare");
+  });
+
+  it("burns the burn share of deposited fees", async () => {
+    await setBurnShare(BURN_SHARE);
+    const treasuryBefore = await balance(t.treasury);
+    const supplyBefore = await supply();
+    const burnedBefore = await totalBurned();
+
+    await depositFees(FEES);
+
+    const burned = (FEES * BURN_SHARE) / 10_000;
+    expect((await balance(t.treasury)) - treasuryBefore).to.equal(FEES - burned);
+    expect(supplyBefore - (await supply())).to.equal(burned);
+    expect((await totalBurned()) - burnedBefore).to.equal(burned);
+  });
+
+  it("rejects treasury burns by anyone but the authority", async () => {
+    await expectError(burnFromTreasury(1_000_000, intruder), "Unauthorized");
+  });
+
+  it("rejects burning nothing", async () => {
+    await expectError(burnFromTreasury(0), "InvalidAmount");
+  });
+
+  it("burns treasury tokens on the authority's say", async () => {
+    const supplyBefore = await supply();
+    const burnedBefore = await totalBurned();
+
+    await burnFromTreasury(1_000_000);
+
+    expect(supplyBefore - (await supply())).to.equal(1_000_000);
+    expect((await totalBurned()) - burnedBefore).to.equal(1_000_000);
+  });
+});
@@ -0,0 +1,115 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  drip,
+  expectError,
+  fund,
+  pda,
+  programs,
+  setupMarket,
+  setupToken,
+  stake,
+  stakedOperator,
+  startStream,
+  tick,
+  tokenFor,
+  u64,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Fee tiers: a stake position's tier, the number of `FEE_TIERS` thresholds
+ * its amount meets, is readable as a view for staking-based fee discounts.
+ * The tiers at and around each threshold are covered by the programs' unit
+ * tests, as test wallets can't be funded past the first one.
+ */
+describe("DRONEOS Token: fee tiers", () => {
+  const { droneosToken, paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const AMOUNT = 100 * 1_000_000;
+  const RATE = 1_000_000;
+  const staker = Keypair.generate();
+  const payee = Keypair.generate();
+  let position: PublicKey;
+
+  function getFeeTier(stakeAccount: PublicKey) {
+    return droneosToken.methods.getFeeTier().accountsPartial({ stakeAccount }).view();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token)).amount);
+  }
+
+  /** A stream from the staker naming `payerStake` for its fee discount */
+  async function openStakerStream(payerStake: PublicKey): Promise<Stream> {
+    const { mint, token: payerToken, treasury } = await tokenFor(staker, 1_000_000_000);
+    const payeeToken = await createAccount(connection, payee, mint, payee.publicKey);
+    const nonce = new BN(0);
+    const stream = pda(
+      paymentStreams.programId,
+      Buffer.from("stream"),
+      staker.publicKey.toBuffer(),
+      payee.publicKey.toBuffer(),
+      u64(nonce)
+    );
+    await paymentStreams.methods
+      .createStream(nonce, new BN(RATE), new BN(600), new BN(60), true, new BN(0), new BN(0), null)
+      .accountsPartial({
+        stream,
+        mint,
+        payerToken,
+        payer: staker.publicKey,
+        payee: payee.publicKey,
+        payerStake,
+        referrerAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([staker])
+      .rpc();
+
+    const escrow = pda(paymentStreams.programId, Buffer.from("escrow"), stream.toBuffer());
+    return { stream, escrow, payer: staker, payee, mint, payerToken, payeeToken, treasury };
+  }
+
+  before(async () => {
+    await fund(staker, payee);
+    await setupToken();
+    await setupMarket();
+    position = await stake(staker, await drip(staker, AMOUNT), AMOUNT);
+  });
+
+  it("puts a position under the lowest threshold in no tier", async () => {
+    expect(await getFeeTier(position)).to.equal(0);
+  });
+
+  it("rejects reading a tier from anything but a stake position", async () => {
+    const { operatorStake } = await stakedOperator();
+    await expectError(getFeeTier(operatorStake), "AccountDiscriminatorMismatch");
+  });
+
+  it("charges the full platform fee on a stream naming a position in no tier", async () => {
+    const s = await openStakerStream(position);
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    const { startedAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(startedAt).addn(2));
+    await tick(s).rpc();
+
+    const ticked: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(ticked.feeDiscountBps).to.equal(0);
+    const { feeBasisPoints } = await paymentStreams.account.programConfig.fetch(
+      pda(paymentStreams.programId, Buffer.from("config"))
+    );
+    const paid = ticked.totalPaid.toNumber();
+    const fee = Math.floor((paid * feeBasisPoints) / 10_000);
+    expect(fee).to.be.gt(0);
+    expect(await balance(s.treasury)).to.equal(fee);
+    expect(await balance(s.payeeToken)).to.equal(paid - fee);
+  });
+});
@@ -0,0 +1,86 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  createTask,
+  expectError,
+  fund,
+  openBidTask,
+  pda,
+  programs,
+  registerRobot,
+  setupMarket,
+  stakedOperator,
+  submitBid,
+} from "./helpers";
+
+/**
+ * Fleet bonds: with a minimum stake per robot set, an operator can only bid
+ * while their slashable and delegated stake covers it for every robot in
+ * their fleet.
+ */
+describe("Task Market: fleet-scaled operator bonds", () => {
+  const { taskMarket, droneosToken, identityRegistry } = programs();
+  const authority = anchor.getProvider().publicKey!;
+  const market = pda(taskMarket.programId, Buffer.from("market"));
+
+  const creator = Keypair.generate();
+  let operator: Keypair;
+  let operatorStake: PublicKey;
+
+  function setMinStakePerRobot(amount: number) {
+    return taskMarket.methods.setMinStakePerRobot(new BN(amount)).accountsPartial({ market, authority }).rpc();
+  }
+
+  /** The largest minimum per robot the shared operator's bond covers */
+  async function coveredPerRobot() {
+    const stake: any = await droneosToken.account.operatorStake.fetch(operatorStake);
+    const fleet = pda(identityRegistry.programId, Buffer.from("fleet"), operator.publicKey.toBuffer());
+    const { robotCount } = await identityRegistry.account.operatorFleet.fetch(fleet);
+    return Math.floor(stake.slashableAmount.add(stake.delegatedAmount).toNumber() / robotCount);
+  }
+
+  before(async () => {
+    await fund(creator);
+    await setupMarket();
+    ({ operator, operatorStake } = await stakedOperator());
+  });
+
+  after(async () => {
+    // The market is shared with other test files
+    await setMinStakePerRobot(0);
+  });
+
+  it("rejects a minimum set by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      taskMarket.methods
+        .setMinStakePerRobot(new BN(1))
+        .accountsPartial({ market, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects bids from operators without stake", async () => {
+    await setMinStakePerRobot(1);
+    await expectError(openBidTask(), "InsufficientFleetBond");
+  });
+
+  it("allows bids only while the bond covers every robot", async () => {
+    const robot = await registerRobot(operator);
+    const perRobot = await coveredPerRobot();
+
+    await setMinStakePerRobot(perRobot);
+    const covered = await createTask(creator);
+    await submitBid(covered, creator.publicKey, robot, operator);
+    expect((await taskMarket.account.task.fetch(covered)).bidsCount).to.equal(1);
+
+    await setMinStakePerRobot(perRobot + 1);
+    const uncovered = await createTask(creator);
+    await expectError(submitBid(uncovered, creator.publicKey, robot, operator), "InsufficientFleetBond");
+  });
+});
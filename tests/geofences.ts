@@ -0,0 +1,117 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { createHash } from "crypto";
+import { expect } from "chai";
+import {
+  Assignment,
+  TaskStatus,
+  acceptBid,
+  bidAddress,
+  completeTask,
+  createTask,
+  expectError,
+  fund,
+  gpsProofAddress,
+  openBidTask,
+  programs,
+  registerRobot,
+  settlementAccounts,
+  setupMarket,
+  startTask,
+  submitBid,
+  submitGpsProof,
+  tokenFor,
+  verifyCompletion,
+  verifyProof,
+} from "./helpers";
+
+/**
+ * Geofenced tasks: a creator can fence an open task without bids to a
+ * circle, after which approving its completion needs a verified GPS proof
+ * from the robot, taken since the task started, inside the fence.
+ */
+describe("Task Market: geofences", () => {
+  const { taskMarket } = programs();
+
+  // Microdegrees, as in oracle GPS proofs
+  const CENTER = { latitude: 40_712_776, longitude: -74_005_974 };
+  const RADIUS = 500;
+  const deliverable = createHash("sha256").update("pylons 3-7: no cracks found").digest();
+
+  function setGeofence(task: Assignment, latitude: number, longitude: number, radius: number, signer = task.creator) {
+    return taskMarket.methods
+      .setGeofence(new BN(latitude), new BN(longitude), radius)
+      .accountsPartial({ task: task.task, creator: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  /** A geofenced task, completed by its robot and awaiting verification */
+  async function fencedCompletion() {
+    const creator = Keypair.generate();
+    const operator = Keypair.generate();
+    await fund(creator, operator);
+    await setupMarket();
+    const { mint, token: creatorToken, treasury } = await tokenFor(creator, 1_000_000_000);
+    const task = await createTask(creator);
+    const device = Keypair.generate();
+    const robot = await registerRobot(operator, device);
+    const a: Assignment = {
+      task, bid: bidAddress(task, robot), robot, creator, operator, mint, creatorToken, treasury, device,
+    };
+
+    await setGeofence(a, CENTER.latitude, CENTER.longitude, RADIUS);
+    await submitBid(task, creator.publicKey, robot, operator);
+    await acceptBid(a).signers([creator]).rpc();
+    await startTask(a).rpc();
+    await completeTask(a, deliverable).rpc();
+    return { a, stream: await settlementAccounts(a) };
+  }
+
+  /** Prove the robot was at `latitude`, `longitude` once the task started */
+  async function proveLocation(a: Assignment, latitude: number, longitude: number) {
+    const { startedAt } = await taskMarket.account.task.fetch(a.task);
+    await (await submitGpsProof(a, { latitude, longitude, timestamp: startedAt })).rpc();
+    await verifyProof(gpsProofAddress(a));
+    return gpsProofAddress(a);
+  }
+
+  it("rejects a fence set by anyone but the creator", async () => {
+    const a = await openBidTask();
+    await expectError(setGeofence(a, CENTER.latitude, CENTER.longitude, RADIUS, a.operator), "Unauthorized");
+  });
+
+  it("rejects fencing a task that has bids", async () => {
+    const a = await openBidTask();
+    await expectError(setGeofence(a, CENTER.latitude, CENTER.longitude, RADIUS), "TaskHasBids");
+  });
+
+  it("rejects coordinates off the globe or a zero radius", async () => {
+    const creator = Keypair.generate();
+    await fund(creator);
+    await setupMarket();
+    const task = await createTask(creator);
+    const a = { task, creator } as Assignment;
+
+    await expectError(setGeofence(a, 91_000_000, CENTER.longitude, RADIUS), "InvalidGeofence");
+    await expectError(setGeofence(a, CENTER.latitude, 181_000_000, RADIUS), "InvalidGeofence");
+    await expectError(setGeofence(a, CENTER.latitude, CENTER.longitude, 0), "InvalidGeofence");
+  });
+
+  it("rejects approval without a GPS proof or from outside the fence", async () => {
+    const { a, stream } = await fencedCompletion();
+    await expectError(verifyCompletion(a, stream).rpc(), "ProofRequired");
+
+    // About 11km north
+    const gpsProof = await proveLocation(a, CENTER.latitude + 100_000, CENTER.longitude);
+    await expectError(verifyCompletion(a, stream, true, { gpsProof }).rpc(), "OutsideGeofence");
+  });
+
+  it("approves a completion proven inside the fence", async () => {
+    const { a, stream } = await fencedCompletion();
+    const gpsProof = await proveLocation(a, CENTER.latitude + 1_000, CENTER.longitude);
+    await verifyCompletion(a, stream, true, { gpsProof }).rpc();
+
+    expect((await taskMarket.account.task.fetch(a.task)).status).to.equal(TaskStatus.Completed);
+  });
+});
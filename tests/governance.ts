@@ -0,0 +1,75 @@
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  TokenSetup,
+  drip,
+  expectError,
+  fund,
+  pda,
+  programs,
+  setupToken,
+  stake,
+  u64,
+  updateVotingPower,
+} from "./helpers";
+
+/**
+ * Governance: proposals need `MIN_PROPOSAL_POWER` veDRONEOS to open. The
+ * voting period and quorum are out of reach of a test validator, so this
+ * covers who may propose.
+ */
+describe("DRONEOS Token: governance proposals", () => {
+  const { droneosToken } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const proposer = Keypair.generate();
+  let t: TokenSetup;
+
+  // A proposal to stop burning protocol fees, run as the governance PDA
+  function instruction() {
+    const governance = pda(droneosToken.programId, Buffer.from("governance"));
+    return {
+      programId: droneosToken.programId,
+      accounts: [
+        { pubkey: t.config, isSigner: false, isWritable: true },
+        { pubkey: governance, isSigner: true, isWritable: false },
+      ],
+      data: droneosToken.coder.instruction.encode("setBurnShare", { shareBps: 0 }),
+    };
+  }
+
+  async function createProposal(signer: Keypair) {
+    const { proposalCount } = await droneosToken.account.tokenConfig.fetch(t.config);
+    return droneosToken.methods
+      .createProposal(instruction())
+      .accountsPartial({
+        config: t.config,
+        proposal: pda(droneosToken.programId, Buffer.from("proposal"), u64(proposalCount)),
+        votingPower: pda(droneosToken.programId, Buffer.from("voting_power"), signer.publicKey.toBuffer()),
+        proposer: signer.publicKey,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(proposer);
+    t = await setupToken();
+    const position = await stake(proposer, await drip(proposer, AMOUNT), AMOUNT, 365);
+    await updateVotingPower(proposer, [position]);
+  });
+
+  it("rejects proposals from wallets without voting power", async () => {
+    const stranger = Keypair.generate();
+    await fund(stranger);
+    await expectError(createProposal(stranger), "AccountNotInitialized");
+  });
+
+  it("rejects proposals from wallets under the proposal threshold", async () => {
+    const before: any = await droneosToken.account.tokenConfig.fetch(t.config);
+    await expectError(createProposal(proposer), "InsufficientVotingPower");
+
+    const after: any = await droneosToken.account.tokenConfig.fetch(t.config);
+    expect(after.proposalCount.toNumber()).to.equal(before.proposalCount.toNumber());
+  });
+});
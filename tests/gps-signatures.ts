@@ -0,0 +1,66 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Assignment,
+  expectError,
+  gpsProofAddress,
+  openBidTask,
+  programs,
+  setupOracle,
+  submitGpsProof,
+} from "./helpers";
+
+/**
+ * GPS proof signatures: a GPS proof is only accepted with an Ed25519
+ * instruction right before it checking the robot's device key signed the
+ * exact reading submitted.
+ */
+describe("Oracle Verifier: GPS proof signatures", () => {
+  const { oracleVerifier } = programs();
+
+  const READING = { latitude: 40_712_776, longitude: -74_005_974, timestamp: 1_700_000_000 };
+  let a: Assignment;
+
+  before(async () => {
+    a = await openBidTask();
+  });
+
+  it("rejects a proof without a signature check", async () => {
+    const { oracle } = await setupOracle();
+    await expectError(
+      oracleVerifier.methods
+        .submitGpsProof(
+          new BN(READING.latitude),
+          new BN(READING.longitude),
+          120,
+          new BN(READING.timestamp),
+          Array(64).fill(0)
+        )
+        .accountsPartial({
+          task: a.task,
+          robot: a.robot,
+          oracle,
+          proof: gpsProofAddress(a),
+          operator: a.operator.publicKey,
+        })
+        .signers([a.operator])
+        .rpc(),
+      "MissingSignature"
+    );
+  });
+
+  it("rejects a proof signed by anything but the robot's device key", async () => {
+    await expectError((await submitGpsProof(a, READING, Keypair.generate())).rpc(), "InvalidSignature");
+  });
+
+  it("records a reading the device signed", async () => {
+    await (await submitGpsProof(a, READING)).rpc();
+
+    const proof: any = await oracleVerifier.account.proof.fetch(gpsProofAddress(a));
+    expect(proof.robot.toBase58()).to.equal(a.robot.toBase58());
+    expect(proof.latitude.toNumber()).to.equal(READING.latitude);
+    expect(proof.longitude.toNumber()).to.equal(READING.longitude);
+    expect(proof.status).to.have.property("pending");
+  });
+});
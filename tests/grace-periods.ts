@@ -0,0 +1,82 @@
+import { BN } from "@coral-xyz/anchor";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  openStream,
+  overdrawStream,
+  programs,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Grace periods: a stream whose escrow can't cover a tick enters Grace for
+ * its grace period, during which a top-up rescues it. Only once the window
+ * passes does the next tick pay out what's left and end it.
+ */
+describe("Payment Streams: grace periods", () => {
+  const { paymentStreams } = programs();
+
+  function topUpEscrow(s: Stream, amount: number, signer = s.payer, signerToken = s.payerToken) {
+    return paymentStreams.methods
+      .topUpEscrow(new BN(amount))
+      .accountsPartial({
+        stream: s.stream,
+        escrow: s.escrow,
+        mint: s.mint,
+        payerToken: signerToken,
+        payer: signer.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  async function overdrawnStream(gracePeriod: number) {
+    const s = await openStream({ gracePeriod });
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    await overdrawStream(s);
+    return s;
+  }
+
+  it("enters Grace instead of terminating when escrow falls short", async () => {
+    const s = await overdrawnStream(60);
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.status).to.have.property("grace");
+    expect(account.graceStartedAt.toNumber()).to.be.gt(0);
+
+    await waitForClock(account.graceStartedAt.addn(1));
+    await expectError(tick(s).rpc(), "GracePeriodActive");
+  });
+
+  it("rejects a rescue by anyone but the payer", async () => {
+    const s = await overdrawnStream(60);
+    await expectError(topUpEscrow(s, 500_000_000, s.payee, s.payeeToken), "Unauthorized");
+  });
+
+  it("returns the stream to Active when a top-up covers what's owed", async () => {
+    const s = await overdrawnStream(60);
+    await topUpEscrow(s, 500_000_000);
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.status).to.have.property("active");
+    expect(account.graceStartedAt.toNumber()).to.equal(0);
+  });
+
+  it("pays out and ends the stream once the window passes", async () => {
+    const s = await overdrawnStream(1);
+    const { graceStartedAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(graceStartedAt).addn(1));
+    await tick(s).rpc();
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.status).to.have.property("completed");
+    expect(account.escrowBalance.toNumber()).to.equal(0);
+  });
+});
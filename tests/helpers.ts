@@ -0,0 +1,1157 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { Ed25519Program, PublicKey, Keypair } from "@solana/web3.js";
+import {
+  createMint,
+  createAccount,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+} from "@solana/spl-token";
+import { expect } from "chai";
+
+/**
+ * Setup shared by the behaviour tests: funding, the programs' singleton
+ * PDAs and the steps of a task's lifecycle up to the point each test file
+ * starts exercising its own instructions.
+ */
+
+export const LAMPORTS = 10 * anchor.web3.LAMPORTS_PER_SOL;
+
+// task-market's TaskStatus discriminants, as stored in zero-copy tasks
+export const TaskStatus = {
+  Open: 0,
+  Assigned: 1,
+  InProgress: 2,
+  PendingVerification: 3,
+  Completed: 4,
+  Failed: 5,
+  Cancelled: 6,
+  Disputed: 7,
+};
+
+// identity-registry's Capability::Inspection, which test robots are certified for
+export const INSPECTION = 2;
+
+export function programs() {
+  return {
+    taskMarket: anchor.workspace.TaskMarket as Program<any>,
+    paymentStreams: anchor.workspace.PaymentStreams as Program<any>,
+    identityRegistry: anchor.workspace.IdentityRegistry as Program<any>,
+    droneosToken: anchor.workspace.DroneosToken as Program<any>,
+    oracleVerifier: anchor.workspace.OracleVerifier as Program<any>,
+    swarmCoordinator: anchor.workspace.SwarmCoordinator as Program<any>,
+  };
+}
+
+export function pda(programId: PublicKey, ...seeds: (Buffer | Uint8Array)[]): PublicKey {
+  return PublicKey.findProgramAddressSync(seeds, programId)[0];
+}
+
+export function u64(value: number | BN): Buffer {
+  return new BN(value).toArrayLike(Buffer, "le", 8);
+}
+
+export function u32(value: number): Buffer {
+  return new BN(value).toArrayLike(Buffer, "le", 4);
+}
+
+export function sleep(ms: number) {
+  return new Promise((resolve) => setTimeout(resolve, ms));
+}
+
+/** Wait until the cluster's clock reaches `unixTimestamp` */
+export async function waitForClock(unixTimestamp: number | BN) {
+  const connection = anchor.getProvider().connection;
+  const target = new BN(unixTimestamp).toNumber();
+  for (;;) {
+    const time = await connection.getBlockTime(await connection.getSlot("confirmed"));
+    if (time !== null && time >= target) return;
+    await sleep(500);
+  }
+}
+
+/** Events a confirmed transaction emitted through `program`'s emit_cpi! */
+export async function cpiEvents(program: Program<any>, signature: string) {
+  const connection = anchor.getProvider().connection;
+  for (;;) {
+    const tx = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    if (tx === null) {
+      await sleep(500);
+      continue;
+    }
+
+    const keys = tx.transaction.message.getAccountKeys();
+    return (tx.meta?.innerInstructions ?? [])
+      .flatMap((inner) => inner.instructions)
+      .filter((ix) => keys.get(ix.programIdIndex)?.equals(program.programId))
+      .flatMap((ix) => {
+        // Skip the 8-byte event instruction tag before the event itself
+        const data = Buffer.from(anchor.utils.bytes.bs58.decode(ix.data)).subarray(8);
+        const event = program.coder.events.decode(data.toString("base64"));
+        return event ? [event] : [];
+      });
+  }
+}
+
+export async function fund(...keypairs: Keypair[]) {
+  const connection = anchor.getProvider().connection;
+  for (const kp of keypairs) {
+    const sig = await connection.requestAirdrop(kp.publicKey, LAMPORTS);
+    await connection.confirmTransaction(sig, "confirmed");
+  }
+}
+
+export async function initializeOnce(fn: () => Promise<unknown>) {
+  try {
+    await fn();
+  } catch (err) {
+    // Config PDAs are shared with other test files
+    if (!String(err).includes("already in use")) throw err;
+  }
+}
+
+/** Expect `tx` to fail with the program error `code` (its variant name) */
+export async function expectError(tx: Promise<unknown>, code: string) {
+  let failed = false;
+  try {
+    await tx;
+  } catch (err: any) {
+    failed = true;
+    const actual = err?.error?.errorCode?.code ?? String(err);
+    expect(actual, String(err)).to.include(code);
+  }
+  expect(failed, `expected ${code}`).to.equal(true);
+}
+
+/** Initialize the market, stream config and registry if no other file has */
+export async function setupMarket() {
+  const { taskMarket, paymentStreams, identityRegistry } = programs();
+  const authority = anchor.getProvider().publicKey!;
+  await initializeOnce(() => taskMarket.methods.initialize().accounts({ authority }).rpc());
+  await initializeOnce(() => paymentStreams.methods.initialize().accounts({ authority }).rpc());
+  await initializeOnce(() => identityRegistry.methods.initialize().accounts({ authority }).rpc());
+  return pda(taskMarket.programId, Buffer.from("market"));
+}
+
+/** A fresh mint, with `amount` minted to an account of `owner`'s */
+export async function tokenFor(owner: Keypair, amount: number) {
+  const connection = anchor.getProvider().connection;
+  const { paymentStreams } = programs();
+  const mint = await createMint(connection, owner, owner.publicKey, null, 6);
+  const token = await createAccount(connection, owner, mint, owner.publicKey);
+  await mintTo(connection, owner, mint, token, owner, amount);
+
+  const treasury = pda(paymentStreams.programId, Buffer.from("treasury"), mint.toBuffer());
+  await paymentStreams.methods
+    .initializeTreasury()
+    .accountsPartial({ treasury, mint, payer: owner.publicKey, tokenProgram: TOKEN_PROGRAM_ID })
+    .signers([owner])
+    .rpc();
+
+  return { mint, token, treasury };
+}
+
+/** Register an available drone certified for inspection */
+export async function registerRobot(operator: Keypair, device = Keypair.generate()): Promise<PublicKey> {
+  const { identityRegistry } = programs();
+  const deviceId = device.publicKey.toBuffer();
+  const robot = pda(identityRegistry.programId, Buffer.from("robot"), deviceId);
+
+  await identityRegistry.methods
+    .registerRobot([...deviceId], "Acme", "X1", Array(32).fill(0), { drone: {} })
+    .accountsPartial({ robot, operator: operator.publicKey })
+    .signers([operator])
+    .rpc();
+  await identityRegistry.methods
+    .addCapability({ inspection: {} }, 3, 30)
+    .accountsPartial({ robot, authority: operator.publicKey })
+    .signers([operator])
+    .rpc();
+  await identityRegistry.methods
+    .updateStatus({ available: {} })
+    .accountsPartial({ robot, operator: operator.publicKey })
+    .signers([operator])
+    .rpc();
+
+  return robot;
+}
+
+export interface TaskOptions {
+  /** `RobotClass` index, Drone unless given */
+  robotClass?: number;
+  /** `Capability` indices, inspection unless given */
+  capabilities?: number[];
+  minReputation?: number;
+  reward?: number;
+  rate?: number;
+  duration?: number;
+  expiresIn?: number;
+  requiresProof?: boolean;
+  insuredAmount?: number;
+  /** More than one delegates the task to a swarm-coordinator group task */
+  requiredRobots?: number;
+}
+
+/** The swarm-coordinator accounts `createTask` needs to delegate a new
+ *  task of `creator`'s to a group task, initializing the coordinator once */
+export async function groupTaskAccounts(creator: PublicKey) {
+  const { swarmCoordinator } = programs();
+  const authority = anchor.getProvider().publicKey!;
+  await initializeOnce(() => swarmCoordinator.methods.initialize().accounts({ authority }).rpc());
+
+  const coordinator = pda(swarmCoordinator.programId, Buffer.from("coordinator"));
+  const { totalGroupTasks } = await swarmCoordinator.account.coordinator.fetch(coordinator);
+  return {
+    coordinator,
+    groupTask: pda(swarmCoordinator.programId, Buffer.from("group-task"), creator.toBuffer(), u64(totalGroupTasks)),
+    swarmEventAuthority: pda(swarmCoordinator.programId, Buffer.from("__event_authority")),
+    swarmCoordinatorProgram: swarmCoordinator.programId,
+  };
+}
+
+/** Open an inspection task, returning its PDA */
+export async function createTask(creator: Keypair, options: TaskOptions = {}): Promise<PublicKey> {
+  const { taskMarket } = programs();
+  const market = pda(taskMarket.programId, Buffer.from("market"));
+  const { totalTasks } = await taskMarket.account.market.fetch(market);
+  const task = pda(taskMarket.programId, Buffer.from("task"), creator.publicKey.toBuffer(), u64(totalTasks));
+
+  await taskMarket.methods
+    .createTask(
+      "Bridge inspection",
+      "Inspect pylons 3-7",
+      options.robotClass ?? 0,
+      Buffer.from(options.capabilities ?? [INSPECTION]),
+      options.minReputation ?? 0,
+      new BN(options.reward ?? 50_000_000),
+      new BN(options.rate ?? 10_000),
+      options.duration ?? 3_600,
+      2,
+      new BN(options.expiresIn ?? 86_400),
+      options.requiresProof ?? false,
+      options.requiredRobots ?? 1,
+      new BN(options.insuredAmount ?? 0)
+    )
+    .accountsPartial({
+      market,
+      task,
+      creator: creator.publicKey,
+      ...((options.requiredRobots ?? 1) > 1
+        ? await groupTaskAccounts(creator.publicKey)
+        : { coordinator: null, groupTask: null, swarmEventAuthority: null, swarmCoordinatorProgram: null }),
+    })
+    .signers([creator])
+    .rpc();
+
+  return task;
+}
+
+export function bidAddress(task: PublicKey, robot: PublicKey): PublicKey {
+  return pda(programs().taskMarket.programId, Buffer.from("bid"), task.toBuffer(), robot.toBuffer());
+}
+
+/** Accounts every bid instruction takes besides the task, bid and robot */
+export function bidderAccounts(task: PublicKey, creator: PublicKey, operator: PublicKey) {
+  const { taskMarket, droneosToken, identityRegistry } = programs();
+  return {
+    task,
+    operator,
+    market: pda(taskMarket.programId, Buffer.from("market")),
+    operatorStake: pda(droneosToken.programId, Buffer.from("operator"), operator.toBuffer()),
+    operatorFleet: pda(identityRegistry.programId, Buffer.from("fleet"), operator.toBuffer()),
+    creatorBlacklist: pda(taskMarket.programId, Buffer.from("blacklist"), creator.toBuffer()),
+  };
+}
+
+export async function submitBid(
+  task: PublicKey,
+  creator: PublicKey,
+  robot: PublicKey,
+  operator: Keypair,
+  rate = 10_000,
+  duration = 3_600,
+  validFor = 3_600
+): Promise<PublicKey> {
+  const { taskMarket } = programs();
+  const bid = bidAddress(task, robot);
+  await taskMarket.methods
+    .submitBid(new BN(rate), duration, "Ready now", new BN(validFor), [])
+    .accountsPartial({ ...bidderAccounts(task, creator, operator.publicKey), bid, robot })
+    .signers([operator])
+    .rpc();
+  return bid;
+}
+
+/** Stream accounts of a task assigned to `operator`, paid in `mint` */
+export function taskStreamAccounts(
+  task: PublicKey,
+  mint: PublicKey,
+  creator: PublicKey,
+  operator: PublicKey
+) {
+  const { paymentStreams } = programs();
+  const stream = pda(paymentStreams.programId, Buffer.from("task_stream"), task.toBuffer());
+  return {
+    streamConfig: pda(paymentStreams.programId, Buffer.from("config")),
+    stream,
+    escrow: pda(paymentStreams.programId, Buffer.from("escrow"), stream.toBuffer()),
+    creatorRegistry: pda(paymentStreams.programId, Buffer.from("payer_streams"), creator.toBuffer()),
+    operatorRegistry: pda(paymentStreams.programId, Buffer.from("payee_streams"), operator.toBuffer()),
+    streamEventAuthority: pda(paymentStreams.programId, Buffer.from("__event_authority")),
+    mint,
+  };
+}
+
+export interface Assignment {
+  task: PublicKey;
+  bid: PublicKey;
+  robot: PublicKey;
+  creator: Keypair;
+  operator: Keypair;
+  mint: PublicKey;
+  creatorToken: PublicKey;
+  treasury: PublicKey;
+  /** The robot's device key, which signs its GPS proofs, if known */
+  device?: Keypair;
+}
+
+/** Accept `bid`, funding the task's stream from the creator's tokens */
+export function acceptBid(a: Assignment, losingBids = 0) {
+  const { taskMarket, identityRegistry } = programs();
+  const streamAccounts = taskStreamAccounts(a.task, a.mint, a.creator.publicKey, a.operator.publicKey);
+  return taskMarket.methods.acceptBid(losingBids).accountsPartial({
+    market: pda(taskMarket.programId, Buffer.from("market")),
+    task: a.task,
+    bid: a.bid,
+    creator: a.creator.publicKey,
+    streamConfig: streamAccounts.streamConfig,
+    stream: streamAccounts.stream,
+    escrow: streamAccounts.escrow,
+    mint: a.mint,
+    creatorToken: a.creatorToken,
+    operator: a.operator.publicKey,
+    creatorRegistry: streamAccounts.creatorRegistry,
+    operatorRegistry: streamAccounts.operatorRegistry,
+    streamEventAuthority: streamAccounts.streamEventAuthority,
+    operatorStake: bidderAccounts(a.task, a.creator.publicKey, a.operator.publicKey).operatorStake,
+    operatorFleet: bidderAccounts(a.task, a.creator.publicKey, a.operator.publicKey).operatorFleet,
+    robot: a.robot,
+    registryEventAuthority: pda(identityRegistry.programId, Buffer.from("__event_authority")),
+    tokenProgram: TOKEN_PROGRAM_ID,
+    tokenConfig: null,
+    insurancePool: null,
+    insuranceVault: null,
+    premiumToken: null,
+    premiumMint: null,
+    premiumTokenProgram: null,
+    tokenEventAuthority: null,
+  });
+}
+
+/** Start an assigned task and its stream */
+export function startTask(a: Assignment) {
+  const { taskMarket } = programs();
+  const streamAccounts = taskStreamAccounts(a.task, a.mint, a.creator.publicKey, a.operator.publicKey);
+  return taskMarket.methods
+    .startTask()
+    .accountsPartial({
+      task: a.task,
+      robot: a.robot,
+      bid: a.bid,
+      operator: a.operator.publicKey,
+      streamConfig: streamAccounts.streamConfig,
+      stream: streamAccounts.stream,
+      streamEventAuthority: streamAccounts.streamEventAuthority,
+    })
+    .signers([a.operator]);
+}
+
+/** Complete a task in progress with a deliverable, pausing its stream */
+export function completeTask(a: Assignment, deliverableHash: Buffer, deliverableUri: string | null = null) {
+  const { taskMarket } = programs();
+  const streamAccounts = taskStreamAccounts(a.task, a.mint, a.creator.publicKey, a.operator.publicKey);
+  return taskMarket.methods
+    .completeTask(Array.from(deliverableHash), deliverableUri)
+    .accountsPartial({
+      task: a.task,
+      robot: a.robot,
+      operator: a.operator.publicKey,
+      streamConfig: streamAccounts.streamConfig,
+      stream: streamAccounts.stream,
+      streamEventAuthority: streamAccounts.streamEventAuthority,
+    })
+    .signers([a.operator]);
+}
+
+/**
+ * A task open to a freshly registered robot's bid: creates the creator's
+ * mint and tokens, the task, the robot and its pending bid
+ */
+export async function openBidTask(options: TaskOptions = {}): Promise<Assignment> {
+  const creator = Keypair.generate();
+  const operator = Keypair.generate();
+  await fund(creator, operator);
+  await setupMarket();
+
+  const { mint, token: creatorToken, treasury } = await tokenFor(creator, 1_000_000_000);
+  const task = await createTask(creator, options);
+  const device = Keypair.generate();
+  const robot = await registerRobot(operator, device);
+  const bid = await submitBid(task, creator.publicKey, robot, operator, options.rate, options.duration);
+  return { task, bid, robot, creator, operator, mint, creatorToken, treasury, device };
+}
+
+export interface OracleSetup {
+  verifier: PublicKey;
+  oracle: PublicKey;
+  provider: Keypair;
+}
+
+let oracleSetup: Promise<OracleSetup> | undefined;
+
+/** Initialize the verifier and register one GPS oracle, once per test run */
+export function setupOracle(): Promise<OracleSetup> {
+  oracleSetup ??= (async () => {
+    const { oracleVerifier } = programs();
+    const authority = anchor.getProvider().publicKey!;
+    const provider = Keypair.generate();
+    await fund(provider);
+
+    await initializeOnce(() => oracleVerifier.methods.initialize().accounts({ authority }).rpc());
+    const oracle = pda(oracleVerifier.programId, Buffer.from("oracle"), provider.publicKey.toBuffer());
+    await oracleVerifier.methods
+      .registerOracle({ gps: {} }, "https://oracle.droneos.dev", 90)
+      .accountsPartial({ oracle, provider: provider.publicKey })
+      .signers([provider])
+      .rpc();
+
+    return { verifier: pda(oracleVerifier.programId, Buffer.from("verifier")), oracle, provider };
+  })();
+  return oracleSetup;
+}
+
+export interface GpsReading {
+  latitude: number;
+  longitude: number;
+  timestamp: number | BN;
+}
+
+/** The GPS proof PDA of an assignment's robot */
+export function gpsProofAddress(a: Assignment): PublicKey {
+  return pda(programs().oracleVerifier.programId, Buffer.from("proof"), a.task.toBuffer(), a.robot.toBuffer());
+}
+
+/** Submit a GPS proof of the assigned robot, signed by `signer` (the
+ *  robot's device key unless given), with its Ed25519 check */
+export async function submitGpsProof(a: Assignment, reading: GpsReading, signer = a.device!) {
+  const { oracleVerifier } = programs();
+  const { oracle } = await setupOracle();
+  const altitude = 120;
+  const timestamp = new BN(reading.timestamp);
+
+  // gps_proof_message: task, robot, then the reading little-endian
+  const message = Buffer.alloc(92);
+  a.task.toBuffer().copy(message, 0);
+  a.robot.toBuffer().copy(message, 32);
+  message.writeBigInt64LE(BigInt(reading.latitude), 64);
+  message.writeBigInt64LE(BigInt(reading.longitude), 72);
+  message.writeInt32LE(altitude, 80);
+  message.writeBigInt64LE(BigInt(timestamp.toString()), 84);
+  const ed25519Ix = Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message });
+  // The Ed25519 instruction holds the signature at 48
+  const signature = [...ed25519Ix.data.subarray(48, 112)];
+
+  return oracleVerifier.methods
+    .submitGpsProof(new BN(reading.latitude), new BN(reading.longitude), altitude, timestamp, signature)
+    .accountsPartial({
+      task: a.task,
+      robot: a.robot,
+      oracle,
+      proof: gpsProofAddress(a),
+      operator: a.operator.publicKey,
+    })
+    .preInstructions([ed25519Ix])
+    .signers([a.operator]);
+}
+
+/** The completion proof PDA of a task */
+export function completionProofAddress(a: Assignment): PublicKey {
+  return pda(programs().oracleVerifier.programId, Buffer.from("completion-proof"), a.task.toBuffer());
+}
+
+/** Submit a completion proof of `dataHash` for the assigned robot, as its operator */
+export async function submitCompletionProof(a: Assignment, dataHash: Buffer): Promise<PublicKey> {
+  const { oracleVerifier } = programs();
+  const { oracle } = await setupOracle();
+  const proof = completionProofAddress(a);
+  await oracleVerifier.methods
+    .submitCompletionProof(Array.from(dataHash), "ipfs://inspection-report", "{}")
+    .accountsPartial({ task: a.task, robot: a.robot, oracle, proof, operator: a.operator.publicKey })
+    .signers([a.operator])
+    .rpc();
+  return proof;
+}
+
+/** Have the test oracle rule a proof valid, or invalid if `valid` is false */
+export async function verifyProof(proof: PublicKey, valid = true) {
+  const { oracleVerifier } = programs();
+  const { verifier, oracle, provider } = await setupOracle();
+  return oracleVerifier.methods
+    .verifyProof(95, valid, "")
+    .accountsPartial({ verifier, oracle, proof, oracleAuthority: provider.publicKey })
+    .signers([provider])
+    .rpc();
+}
+
+/** The nested `TaskStream` accounts settling a task's stream */
+export async function settlementAccounts(a: Assignment) {
+  const connection = anchor.getProvider().connection;
+  const streamAccounts = taskStreamAccounts(a.task, a.mint, a.creator.publicKey, a.operator.publicKey);
+  const operatorToken = await createAccount(connection, a.operator, a.mint, a.operator.publicKey);
+  return {
+    streamConfig: streamAccounts.streamConfig,
+    stream: streamAccounts.stream,
+    escrow: streamAccounts.escrow,
+    mint: a.mint,
+    creatorToken: a.creatorToken,
+    operatorToken,
+    treasury: a.treasury,
+    streamEventAuthority: streamAccounts.streamEventAuthority,
+    paymentStreamsProgram: programs().paymentStreams.programId,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  };
+}
+
+export interface TaskProofs {
+  gpsProof?: PublicKey;
+  completionProof?: PublicKey;
+}
+
+/** Approve, or dispute, a task pending verification as its creator.
+ *  `stream` is its `settlementAccounts`. */
+export function verifyCompletion(
+  a: Assignment,
+  stream: Awaited<ReturnType<typeof settlementAccounts>>,
+  approved = true,
+  proofs: TaskProofs = {}
+) {
+  const { taskMarket, identityRegistry } = programs();
+  return taskMarket.methods
+    .verifyCompletion(approved)
+    .accountsPartial({
+      market: pda(taskMarket.programId, Buffer.from("market")),
+      task: a.task,
+      creator: a.creator.publicKey,
+      stream,
+      operatorStake: bidderAccounts(a.task, a.creator.publicKey, a.operator.publicKey).operatorStake,
+      gpsProof: proofs.gpsProof ?? null,
+      completionProof: proofs.completionProof ?? null,
+      creatorStake: null,
+      robot: a.robot,
+      registryEventAuthority: pda(identityRegistry.programId, Buffer.from("__event_authority")),
+    })
+    .signers([a.creator]);
+}
+
+export interface Stream {
+  stream: PublicKey;
+  escrow: PublicKey;
+  payer: Keypair;
+  payee: Keypair;
+  mint: PublicKey;
+  payerToken: PublicKey;
+  payeeToken: PublicKey;
+  treasury: PublicKey;
+  /** The payee's claim NFT account, once the stream has a claim NFT */
+  claimToken?: PublicKey;
+}
+
+export interface StreamOptions {
+  rate?: number;
+  duration?: number;
+  gracePeriod?: number;
+  securityDeposit?: number;
+  activationFee?: number;
+  earlyTermination?: { minRuntime: number; penaltyBps: number };
+  /** Pay from this stream's payer, in its mint, rather than fresh ones */
+  from?: Stream;
+  /** A registered referrer to credit the stream to */
+  referrerAccount?: PublicKey;
+}
+
+/** A pending stream to a fresh payee, from a fresh payer in a fresh mint
+ *  unless `options.from` is given */
+export async function openStream(options: StreamOptions = {}): Promise<Stream> {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+  const { from } = options;
+  const payer = from?.payer ?? Keypair.generate();
+  const payee = Keypair.generate();
+  await fund(...(from ? [payee] : [payer, payee]));
+  await setupMarket();
+
+  const { mint, token: payerToken, treasury } = from
+    ? { mint: from.mint, token: from.payerToken, treasury: from.treasury }
+    : await tokenFor(payer, 1_000_000_000);
+  const payeeToken = await createAccount(connection, payee, mint, payee.publicKey);
+  const nonce = new BN(0);
+  const stream = pda(
+    paymentStreams.programId,
+    Buffer.from("stream"),
+    payer.publicKey.toBuffer(),
+    payee.publicKey.toBuffer(),
+    u64(nonce)
+  );
+
+  await paymentStreams.methods
+    .createStream(
+      nonce,
+      new BN(options.rate ?? 1_000),
+      new BN(options.duration ?? 3_600),
+      new BN(options.gracePeriod ?? 60),
+      true,
+      new BN(options.securityDeposit ?? 0),
+      new BN(options.activationFee ?? 0),
+      options.earlyTermination
+        ? {
+            minRuntime: new BN(options.earlyTermination.minRuntime),
+            penaltyBps: options.earlyTermination.penaltyBps,
+          }
+        : null
+    )
+    .accountsPartial({
+      stream,
+      mint,
+      payerToken,
+      payer: payer.publicKey,
+      payee: payee.publicKey,
+      payerStake: null,
+      referrerAccount: options.referrerAccount ?? null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([payer])
+    .rpc();
+
+  const escrow = pda(paymentStreams.programId, Buffer.from("escrow"), stream.toBuffer());
+  return { stream, escrow, payer, payee, mint, payerToken, payeeToken, treasury };
+}
+
+/** Accept a pending stream as its payee */
+export function acceptStream(s: Stream) {
+  return programs()
+    .paymentStreams.methods.acceptStream()
+    .accountsPartial({ stream: s.stream, payee: s.payee.publicKey })
+    .signers([s.payee]);
+}
+
+/** Start an accepted stream as its payer */
+export function startStream(s: Stream) {
+  return programs()
+    .paymentStreams.methods.startStream()
+    .accountsPartial({
+      stream: s.stream,
+      mint: s.mint,
+      claimToken: s.claimToken ?? null,
+      payeeToken: s.payeeToken,
+      treasury: s.treasury,
+      payer: s.payer.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([s.payer]);
+}
+
+/** Tick a stream, forgoing any cranker tip */
+export function tick(s: Stream, cranker = s.payee) {
+  return programs()
+    .paymentStreams.methods.tick()
+    .accountsPartial({
+      stream: s.stream,
+      mint: s.mint,
+      claimToken: s.claimToken ?? null,
+      payeeToken: s.payeeToken,
+      treasury: s.treasury,
+      cranker: cranker.publicKey,
+      crankerToken: null,
+      referrerAccount: null,
+      referrerToken: null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([cranker]);
+}
+
+/** Terminate a stream as its payer, or as its payee if `authority` is */
+export function terminateStream(s: Stream, authority = s.payer) {
+  return programs()
+    .paymentStreams.methods.terminateStream("Done")
+    .accountsPartial({
+      stream: s.stream,
+      mint: s.mint,
+      payerToken: s.payerToken,
+      claimToken: s.claimToken ?? null,
+      payeeToken: s.payeeToken,
+      treasury: s.treasury,
+      authority: authority.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([authority]);
+}
+
+/** Push a running stream into its grace window: the payee proposes a rate
+ *  its escrow can't cover, the payer accepts, and the next tick falls short */
+export async function overdrawStream(s: Stream) {
+  const { paymentStreams } = programs();
+  await paymentStreams.methods
+    .proposeRateChange(new BN(10_000_000))
+    .accountsPartial({ stream: s.stream, claimToken: null, authority: s.payee.publicKey })
+    .signers([s.payee])
+    .rpc();
+  await paymentStreams.methods
+    .acceptRateChange()
+    .accountsPartial({ stream: s.stream, claimToken: null, authority: s.payer.publicKey })
+    .signers([s.payer])
+    .rpc();
+
+  const { lastTickAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+  await waitForClock(new BN(lastTickAt).addn(1));
+  await tick(s).rpc();
+}
+
+// droneos_token's crank tip at the epoch budget below: 100 DRONEOS
+export const CRANK_TIP = 100 * 1_000_000;
+const EPOCH_BUDGET = 100_000 * 1_000_000;
+const EMISSION_EPOCHS = 64;
+
+export interface TokenSetup {
+  config: PublicKey;
+  mint: PublicKey;
+  treasury: PublicKey;
+  rewardsVault: PublicKey;
+  stakeVault: PublicKey;
+  appealVault: PublicKey;
+  operatorVault: PublicKey;
+  insurancePool: PublicKey;
+  insuranceVault: PublicKey;
+}
+
+let tokenSetup: Promise<TokenSetup> | undefined;
+
+/**
+ * Initialize the DRONEOS mint, its treasury, staking and appeal vaults,
+ * insurance pool, lock tiers and a one-second-epoch emission schedule, once
+ * per test run. Test wallets are
+ * funded from the treasury through the emission crank's tip (see `drip`).
+ */
+export function setupToken(): Promise<TokenSetup> {
+  tokenSetup ??= initializeToken();
+  return tokenSetup;
+}
+
+async function initializeToken(): Promise<TokenSetup> {
+  const { droneosToken } = programs();
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const connection = provider.connection;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  const authority = provider.publicKey;
+
+  const config = pda(droneosToken.programId, Buffer.from("config"));
+  const mint = pda(droneosToken.programId, Buffer.from("mint"));
+  const insurancePool = pda(droneosToken.programId, Buffer.from("insurance"));
+  const emissions = pda(droneosToken.programId, Buffer.from("emissions"));
+  const lockTiers = pda(droneosToken.programId, Buffer.from("lock-tiers"));
+  const configVault = () =>
+    createAccount(connection, payer, mint, config, Keypair.generate(), undefined, TOKEN_2022_PROGRAM_ID);
+
+  await initializeOnce(() =>
+    droneosToken.methods
+      .initialize("DroneOS", "DRONEOS", "https://droneos.dev/token.json", null)
+      .accountsPartial({ config, mint, authority, tokenProgram: TOKEN_2022_PROGRAM_ID })
+      .rpc()
+  );
+
+  let state: any = await droneosToken.account.tokenConfig.fetch(config);
+  if (state.treasury.equals(PublicKey.default)) {
+    const treasury = await configVault();
+    await droneosToken.methods
+      .mintInitialSupply()
+      .accountsPartial({ config, mint, treasury, authority, tokenProgram: TOKEN_2022_PROGRAM_ID })
+      .rpc();
+    state = await droneosToken.account.tokenConfig.fetch(config);
+  }
+
+  if (state.stakeVault.equals(PublicKey.default)) {
+    await droneosToken.methods
+      .initializeStakingVaults()
+      .accountsPartial({ config, stakeVault: await configVault(), rewardsVault: await configVault(), authority })
+      .rpc();
+    state = await droneosToken.account.tokenConfig.fetch(config);
+  }
+
+  if (state.appealVault.equals(PublicKey.default)) {
+    await droneosToken.methods
+      .initializeAppealVault()
+      .accountsPartial({ config, appealVault: await configVault(), authority })
+      .rpc();
+    state = await droneosToken.account.tokenConfig.fetch(config);
+  }
+
+  if (!(await connection.getAccountInfo(insurancePool))) {
+    const insuranceVault = await configVault();
+    await droneosToken.methods
+      .initializeInsurancePool()
+      .accountsPartial({ config, insurancePool, insuranceVault, authority })
+      .rpc();
+  }
+  const { vault: insuranceVault } = await droneosToken.account.insurancePool.fetch(insurancePool);
+
+  if (!(await connection.getAccountInfo(lockTiers))) {
+    await droneosToken.methods.initializeLockTiers().accountsPartial({ config, lockTiers, authority }).rpc();
+  }
+
+  // Every epoch has started, so each crank funds the next one at once
+  if (!(await connection.getAccountInfo(emissions))) {
+    await droneosToken.methods
+      .initializeEmissionSchedule(new BN(0), new BN(1), Array(EMISSION_EPOCHS).fill(new BN(EPOCH_BUDGET)))
+      .accountsPartial({ config, emissions, authority })
+      .rpc();
+  }
+
+  // The first operator stake fixes the operator vault
+  const operatorVault = state.operatorVault.equals(PublicKey.default) ? await configVault() : state.operatorVault;
+
+  return {
+    config,
+    mint,
+    treasury: state.treasury,
+    rewardsVault: state.rewardsVault,
+    stakeVault: state.stakeVault,
+    appealVault: state.appealVault,
+    operatorVault,
+    insurancePool,
+    insuranceVault,
+  };
+}
+
+/** A new DRONEOS account of `owner`'s, funded with at least `amount` */
+export async function drip(owner: Keypair, amount = 0): Promise<PublicKey> {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+  const t = await setupToken();
+  const token = await createAccount(
+    connection,
+    owner,
+    t.mint,
+    owner.publicKey,
+    Keypair.generate(),
+    undefined,
+    TOKEN_2022_PROGRAM_ID
+  );
+
+  for (let funded = 0; funded < amount; funded += CRANK_TIP) {
+    await droneosToken.methods
+      .distributeEpochRewards()
+      .accountsPartial({
+        config: t.config,
+        emissions: pda(droneosToken.programId, Buffer.from("emissions")),
+        treasury: t.treasury,
+        rewardsVault: t.rewardsVault,
+        crankerToken: token,
+        cranker: owner.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+  }
+
+  return token;
+}
+
+/** Post an operator stake of `amount` DRONEOS base units */
+export async function stakeOperator(operator: Keypair, amount: number): Promise<PublicKey> {
+  const { droneosToken } = programs();
+  const t = await setupToken();
+  const operatorToken = await drip(operator, amount);
+  const operatorStake = pda(droneosToken.programId, Buffer.from("operator"), operator.publicKey.toBuffer());
+
+  await droneosToken.methods
+    .createOperatorStake(new BN(amount))
+    .accountsPartial({
+      config: t.config,
+      operatorStake,
+      operatorVault: t.operatorVault,
+      operatorToken,
+      operator: operator.publicKey,
+      mint: t.mint,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    })
+    .signers([operator])
+    .rpc();
+
+  return operatorStake;
+}
+
+/** Stake `amount` of `user`'s DRONEOS from `userToken`, returning the new
+ *  position. A `referral` names a registered referrer and their token account. */
+export async function stake(
+  user: Keypair,
+  userToken: PublicKey,
+  amount: number,
+  lockDays = 0,
+  referral: { referrer: PublicKey; referrerToken: PublicKey; rewardsVault?: PublicKey } | null = null
+): Promise<PublicKey> {
+  const { droneosToken } = programs();
+  const t = await setupToken();
+  const positions = pda(droneosToken.programId, Buffer.from("positions"), user.publicKey.toBuffer());
+  const existing = await droneosToken.account.stakerPositions.fetchNullable(positions);
+  const stakeAccount = pda(
+    droneosToken.programId,
+    Buffer.from("stake"),
+    user.publicKey.toBuffer(),
+    u32(existing?.nextIndex ?? 0)
+  );
+
+  await droneosToken.methods
+    .stake(new BN(amount), lockDays, referral?.referrer ?? null)
+    .accountsPartial({
+      config: t.config,
+      positions,
+      stakeAccount,
+      stakeVault: t.stakeVault,
+      userToken,
+      user: user.publicKey,
+      mint: t.mint,
+      referrerAccount: referral && pda(droneosToken.programId, Buffer.from("referrer"), referral.referrer.toBuffer()),
+      referrerToken: referral?.referrerToken ?? null,
+      rewardsVault: referral && (referral.rewardsVault ?? t.rewardsVault),
+      emissions: referral && pda(droneosToken.programId, Buffer.from("emissions")),
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    })
+    .signers([user])
+    .rpc();
+
+  return stakeAccount;
+}
+
+/** Withdraw `amount` of a position, all of it unless given, to `user`'s
+ *  `userToken`, burning its `receipt` NFT if it has one */
+export async function unstake(
+  user: Keypair,
+  userToken: PublicKey,
+  stakeAccount: PublicKey,
+  amount: number | null = null,
+  receipt: { mint: PublicKey; token: PublicKey } | null = null
+) {
+  const { droneosToken } = programs();
+  const t = await setupToken();
+  const { owner } = await droneosToken.account.stakeAccount.fetch(stakeAccount);
+  return droneosToken.methods
+    .unstake(amount === null ? null : new BN(amount))
+    .accountsPartial({
+      config: t.config,
+      stakeAccount,
+      positions: pda(droneosToken.programId, Buffer.from("positions"), owner.toBuffer()),
+      emissions: pda(droneosToken.programId, Buffer.from("emissions")),
+      stakeVault: t.stakeVault,
+      rewardsVault: t.rewardsVault,
+      userToken,
+      user: user.publicKey,
+      mint: t.mint,
+      receiptMint: receipt?.mint ?? null,
+      receiptToken: receipt?.token ?? null,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    })
+    .signers([user])
+    .rpc();
+}
+
+/** Recount `user`'s veDRONEOS from their open stake `positions` */
+export function updateVotingPower(user: Keypair, positions: PublicKey[]) {
+  const { droneosToken } = programs();
+  return droneosToken.methods
+    .updateVotingPower()
+    .accountsPartial({
+      votingPower: pda(droneosToken.programId, Buffer.from("voting_power"), user.publicKey.toBuffer()),
+      positions: pda(droneosToken.programId, Buffer.from("positions"), user.publicKey.toBuffer()),
+      user: user.publicKey,
+    })
+    .remainingAccounts(positions.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false })))
+    .signers([user])
+    .rpc();
+}
+
+/** Let task-market slash operators, if it can't already */
+export async function registerTaskMarketSlasher() {
+  const { droneosToken, taskMarket } = programs();
+  const t = await setupToken();
+  try {
+    await droneosToken.methods
+      .addSlasherProgram(taskMarket.programId)
+      .accountsPartial({ config: t.config, authority: anchor.getProvider().publicKey })
+      .rpc();
+  } catch (err: any) {
+    if (err?.error?.errorCode?.code !== "SlasherAlreadyRegistered") throw err;
+  }
+}
+
+let sharedOperator: Promise<{ operator: Keypair; operatorStake: PublicKey }> | undefined;
+
+/**
+ * An operator with the minimum operator stake, shared by the tests that
+ * need one slashed: stakes only come from the crank tips `drip` collects,
+ * which the emission schedule limits.
+ */
+export function stakedOperator() {
+  sharedOperator ??= (async () => {
+    const operator = Keypair.generate();
+    await fund(operator);
+    const operatorStake = await stakeOperator(operator, 1_000 * 1_000_000);
+    return { operator, operatorStake };
+  })();
+  return sharedOperator;
+}
+
+/** A task bid on by a new robot of the shared staked operator's */
+export async function stakedBidTask(options: TaskOptions = {}): Promise<Assignment> {
+  const creator = Keypair.generate();
+  await fund(creator);
+  await setupMarket();
+  const { operator } = await stakedOperator();
+  const { mint, token: creatorToken, treasury } = await tokenFor(creator, 1_000_000_000);
+  const task = await createTask(creator, options);
+  const robot = await registerRobot(operator);
+  const bid = await submitBid(task, creator.publicKey, robot, operator, options.rate, options.duration);
+  return { task, bid, robot, creator, operator, mint, creatorToken, treasury };
+}
+
+export interface LateTask extends Assignment {
+  stream: Awaited<ReturnType<typeof settlementAccounts>>;
+  operatorStake: PublicKey;
+  /** The creator's (empty) DRONEOS account, receiving forfeited bonds */
+  creatorDroneos: PublicKey;
+}
+
+/**
+ * A task in progress past its deadline and flagged late, assigned to a
+ * staked operator, so a creator abort is the robot's fault
+ */
+export async function lateTask(options: TaskOptions = {}): Promise<LateTask> {
+  const { taskMarket } = programs();
+  const t = await setupToken();
+  await registerTaskMarketSlasher();
+
+  // The shortest stream payment-streams allows
+  const duration = 60;
+  const creator = Keypair.generate();
+  await fund(creator);
+  await setupMarket();
+
+  const { operator, operatorStake } = await stakedOperator();
+  // Insured tasks' premiums are paid in DRONEOS on acceptance
+  const creatorDroneos = await drip(creator, options.insuredAmount ? CRANK_TIP : 0);
+  const { mint, token: creatorToken, treasury } = await tokenFor(creator, 1_000_000_000);
+  const task = await createTask(creator, { ...options, duration });
+  const robot = await registerRobot(operator);
+  const bid = await submitBid(task, creator.publicKey, robot, operator, options.rate, duration);
+  const a = { task, bid, robot, creator, operator, mint, creatorToken, treasury };
+
+  const insurance = options.insuredAmount ? premiumAccounts(t, creatorDroneos) : {};
+  await acceptBid(a).accountsPartial(insurance).signers([creator]).rpc();
+  await startTask(a).rpc();
+  const stream = await settlementAccounts(a);
+
+  const { startedAt, slaWindow } = await taskMarket.account.task.fetch(task);
+  await waitForClock(startedAt.add(slaWindow).addn(1));
+  await taskMarket.methods
+    .flagLate()
+    .accountsPartial({
+      market: pda(taskMarket.programId, Buffer.from("market")),
+      task,
+      operatorStake,
+      bond: bondForfeitAccounts(t, creatorDroneos),
+    })
+    .rpc();
+
+  return { ...a, stream, operatorStake, creatorDroneos };
+}
+
+/** `acceptBid`'s accounts paying an insured task's premium from `premiumToken` */
+export function premiumAccounts(t: TokenSetup, premiumToken: PublicKey) {
+  const { droneosToken } = programs();
+  return {
+    tokenConfig: t.config,
+    insurancePool: t.insurancePool,
+    insuranceVault: t.insuranceVault,
+    premiumToken,
+    premiumMint: t.mint,
+    premiumTokenProgram: TOKEN_2022_PROGRAM_ID,
+    tokenEventAuthority: pda(droneosToken.programId, Buffer.from("__event_authority")),
+  };
+}
+
+export function bondForfeitAccounts(t: TokenSetup, creatorDroneos: PublicKey) {
+  const { droneosToken } = programs();
+  return {
+    tokenConfig: t.config,
+    operatorVault: t.operatorVault,
+    creatorToken: creatorDroneos,
+    mint: t.mint,
+    tokenEventAuthority: pda(droneosToken.programId, Buffer.from("__event_authority")),
+    tokenProgram: TOKEN_2022_PROGRAM_ID,
+  };
+}
+
+/**
+ * The nested `OperatorSlash` accounts slashing `operator`'s stake through
+ * task-market, paid for by `payer`. Operators without a stake get the
+ * address their first slash record would have.
+ */
+export async function operatorSlashAccounts(operator: PublicKey, payer: PublicKey) {
+  const { taskMarket, droneosToken } = programs();
+  const t = await setupToken();
+  const operatorStake = pda(droneosToken.programId, Buffer.from("operator"), operator.toBuffer());
+  const slashCount = (await droneosToken.account.operatorStake.fetchNullable(operatorStake))?.slashCount ?? 0;
+
+  return {
+    slasher: pda(taskMarket.programId, Buffer.from("slasher")),
+    tokenConfig: t.config,
+    slashRecord: pda(droneosToken.programId, Buffer.from("slash"), operator.toBuffer(), u64(slashCount)),
+    operatorVault: t.operatorVault,
+    treasury: t.treasury,
+    insurancePool: t.insurancePool,
+    insuranceVault: t.insuranceVault,
+    mint: t.mint,
+    tokenEventAuthority: pda(droneosToken.programId, Buffer.from("__event_authority")),
+    payer,
+    taskMarketProgram: taskMarket.programId,
+    droneosTokenProgram: droneosToken.programId,
+    tokenProgram: TOKEN_2022_PROGRAM_ID,
+    systemProgram: anchor.web3.SystemProgram.programId,
+  };
+}
+
+/** Abort a late task as `authority`, its creator unless given, slashing
+ *  the operator's stake */
+export async function abortLateTask(a: LateTask, slash: Record<string, PublicKey> = {}, authority = a.creator) {
+  const { taskMarket, identityRegistry } = programs();
+  const t = await setupToken();
+
+  return taskMarket.methods
+    .abortTask("Missed the deadline")
+    .accountsPartial({
+      market: pda(taskMarket.programId, Buffer.from("market")),
+      task: a.task,
+      authority: authority.publicKey,
+      stream: a.stream,
+      operatorStake: a.operatorStake,
+      bond: bondForfeitAccounts(t, a.creatorDroneos),
+      slash: { ...(await operatorSlashAccounts(a.operator.publicKey, authority.publicKey)), ...slash },
+      robot: a.robot,
+      registryEventAuthority: pda(identityRegistry.programId, Buffer.from("__event_authority")),
+    })
+    .signers([authority])
+    .rpc();
+}
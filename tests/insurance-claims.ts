@@ -0,0 +1,146 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  LateTask,
+  TokenSetup,
+  abortLateTask,
+  createTask,
+  drip,
+  expectError,
+  fund,
+  lateTask,
+  pda,
+  programs,
+  setupToken,
+  u64,
+} from "./helpers";
+
+/**
+ * Insurance claims: the creator of a task that failed through the robot's
+ * fault files a claim against the insurance pool, and the authority pays
+ * it out of the pool or denies it.
+ */
+describe("DRONEOS Token: insurance claims", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const AMOUNT = 20 * 1_000_000;
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let failed: LateTask;
+  let claim: PublicKey;
+  let originalShare: number;
+
+  function setInsuranceShare(bps: number) {
+    return droneosToken.methods.setInsuranceShare(bps).accountsPartial({ config: t.config, authority }).rpc();
+  }
+
+  async function fileClaim(task: PublicKey, amount: number, claimant = failed.creator): Promise<PublicKey> {
+    const { claimCount } = await droneosToken.account.insurancePool.fetch(t.insurancePool);
+    const address = pda(droneosToken.programId, Buffer.from("claim"), u64(claimCount));
+    await droneosToken.methods
+      .fileClaim(new BN(amount), "Robot missed its deadline")
+      .accountsPartial({ insurancePool: t.insurancePool, claim: address, task, claimant: claimant.publicKey })
+      .signers([claimant])
+      .rpc();
+    return address;
+  }
+
+  function payClaim(address: PublicKey, approve: boolean, accounts: Record<string, PublicKey> = {}, signers: Keypair[] = []) {
+    return droneosToken.methods
+      .payClaim(approve)
+      .accountsPartial({
+        config: t.config,
+        insurancePool: t.insurancePool,
+        claim: address,
+        insuranceVault: t.insuranceVault,
+        claimantToken: failed.creatorDroneos,
+        authority,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        ...accounts,
+      })
+      .signers(signers)
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    ({ insuranceShareBps: originalShare } = await droneosToken.account.tokenConfig.fetch(t.config));
+
+    // Fund the pool with the whole slash of the failed task's operator
+    await setInsuranceShare(10_000);
+    failed = await lateTask();
+    await abortLateTask(failed);
+  });
+
+  after(async () => {
+    // The token config is shared with other test files
+    await setInsuranceShare(originalShare);
+  });
+
+  it("rejects claims on tasks that didn't fail through the robot's fault", async () => {
+    const open = await createTask(failed.creator);
+    await expectError(fileClaim(open, AMOUNT), "TaskNotRobotFault");
+  });
+
+  it("rejects claims by anyone but the task's creator", async () => {
+    await expectError(fileClaim(failed.task, AMOUNT, intruder), "Unauthorized");
+  });
+
+  it("rejects claims on accounts that aren't tasks", async () => {
+    await expectError(fileClaim(t.config, AMOUNT), "InvalidClaim");
+  });
+
+  it("files a pending claim for a robot-fault failure", async () => {
+    claim = await fileClaim(failed.task, AMOUNT);
+
+    const filed: any = await droneosToken.account.insuranceClaim.fetch(claim);
+    expect(filed.status).to.have.property("pending");
+    expect(filed.amount.toNumber()).to.equal(AMOUNT);
+    expect(filed.task.toBase58()).to.equal(failed.task.toBase58());
+    expect(filed.claimant.toBase58()).to.equal(failed.creator.publicKey.toBase58());
+  });
+
+  it("rejects payouts by anyone but the authority", async () => {
+    await expectError(payClaim(claim, true, { authority: intruder.publicKey }, [intruder]), "Unauthorized");
+  });
+
+  it("rejects payouts to anyone but the claimant", async () => {
+    const intruderToken = await drip(intruder);
+    await expectError(payClaim(claim, true, { claimantToken: intruderToken }), "Unauthorized");
+  });
+
+  it("pays an approved claim out of the pool", async () => {
+    const poolBefore: any = await droneosToken.account.insurancePool.fetch(t.insurancePool);
+    await payClaim(claim, true);
+
+    const paid: any = await droneosToken.account.insuranceClaim.fetch(claim);
+    expect(paid.status).to.have.property("paid");
+    const { amount } = await getAccount(connection, failed.creatorDroneos, undefined, TOKEN_2022_PROGRAM_ID);
+    expect(Number(amount)).to.equal(AMOUNT);
+    const poolAfter: any = await droneosToken.account.insurancePool.fetch(t.insurancePool);
+    expect(poolBefore.balance.sub(poolAfter.balance).toNumber()).to.equal(AMOUNT);
+  });
+
+  it("rejects settling a claim twice", async () => {
+    await expectError(payClaim(claim, true), "ClaimNotPending");
+    await expectError(payClaim(claim, false), "ClaimNotPending");
+  });
+
+  it("rejects paying more than the pool holds, but can deny it", async () => {
+    const { balance } = await droneosToken.account.insurancePool.fetch(t.insurancePool);
+    const excessive = await fileClaim(failed.task, balance.toNumber() + 1);
+
+    await expectError(payClaim(excessive, true), "InsufficientInsurance");
+    await payClaim(excessive, false);
+
+    const denied: any = await droneosToken.account.insuranceClaim.fetch(excessive);
+    expect(denied.status).to.have.property("denied");
+  });
+});
@@ -0,0 +1,54 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, programs, setupToken, stake } from "./helpers";
+
+/**
+ * Lock extensions: a staker can move a position up to a longer lock tier,
+ * its new lock running from then at the new tier's multiplier.
+ */
+describe("DRONEOS Token: lock extensions", () => {
+  const { droneosToken } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const DAY = 86_400;
+  const staker = Keypair.generate();
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let position: PublicKey;
+
+  function extendLock(lockDays: number, user = staker) {
+    return droneosToken.methods
+      .extendLock(lockDays)
+      .accountsPartial({ config: t.config, stakeAccount: position, user: user.publicKey })
+      .signers([user])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(staker, intruder);
+    t = await setupToken();
+    position = await stake(staker, await drip(staker, AMOUNT), AMOUNT, 30);
+  });
+
+  it("rejects extensions by anyone but the position's owner", async () => {
+    await expectError(extendLock(90, intruder), "ConstraintSeeds");
+  });
+
+  it("rejects lock periods that aren't a tier or not longer", async () => {
+    await expectError(extendLock(45), "InvalidLockPeriod");
+    await expectError(extendLock(30), "InvalidLockPeriod");
+    await expectError(extendLock(0), "InvalidLockPeriod");
+  });
+
+  it("moves the position to the longer tier from now", async () => {
+    const before: any = await droneosToken.account.stakeAccount.fetch(position);
+    await extendLock(90);
+
+    const after: any = await droneosToken.account.stakeAccount.fetch(position);
+    expect(after.multiplier).to.equal(12_500);
+    expect(after.lockDuration.toNumber()).to.equal(90 * DAY);
+    expect(after.lockUntil.toNumber()).to.equal(after.lastClaimAt.toNumber() + 90 * DAY);
+    expect(after.lastClaimAt.toNumber()).to.be.gte(before.lastClaimAt.toNumber());
+    expect(after.amount.toNumber()).to.equal(before.amount.toNumber());
+  });
+});
@@ -0,0 +1,71 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { TokenSetup, expectError, fund, pda, programs, setupToken } from "./helpers";
+
+type LockTier = { days: number; multiplierBps: number };
+
+/**
+ * Lock tier table: the lock periods stakers may choose and their reward
+ * multipliers live in one account the authority or governance can replace.
+ */
+describe("DRONEOS Token: lock tier table", () => {
+  const { droneosToken } = programs();
+  const authority = anchor.getProvider().publicKey!;
+
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let lockTiers: PublicKey;
+  let originalTiers: LockTier[];
+
+  function setLockTiers(tiers: LockTier[], signer?: Keypair) {
+    return droneosToken.methods
+      .setLockTiers(tiers)
+      .accountsPartial({ config: t.config, lockTiers, authority: signer?.publicKey ?? authority })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    lockTiers = pda(droneosToken.programId, Buffer.from("lock-tiers"));
+    ({ tiers: originalTiers } = await droneosToken.account.lockTierTable.fetch(lockTiers));
+  });
+
+  after(async () => {
+    // The token config is shared with other test files
+    await setLockTiers(originalTiers);
+  });
+
+  it("rejects tiers set by anyone but the authority", async () => {
+    await expectError(setLockTiers(originalTiers, intruder), "Unauthorized");
+  });
+
+  it("rejects an empty table", async () => {
+    await expectError(setLockTiers([]), "InvalidLockTiers");
+  });
+
+  it("rejects tiers out of order", async () => {
+    const tiers = [
+      { days: 30, multiplierBps: 11000 },
+      { days: 0, multiplierBps: 10000 },
+    ];
+    await expectError(setLockTiers(tiers), "InvalidLockTiers");
+  });
+
+  it("rejects a multiplier below 1x", async () => {
+    await expectError(setLockTiers([{ days: 0, multiplierBps: 9999 }]), "InvalidLockTiers");
+  });
+
+  it("replaces the table", async () => {
+    const tiers = [
+      { days: 0, multiplierBps: 10000 },
+      { days: 45, multiplierBps: 11500 },
+    ];
+    await setLockTiers(tiers);
+
+    const set: any = await droneosToken.account.lockTierTable.fetch(lockTiers);
+    expect(set.tiers).to.deep.equal(tiers);
+  });
+});
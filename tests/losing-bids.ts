@@ -0,0 +1,121 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Assignment,
+  acceptBid,
+  expectError,
+  fund,
+  openBidTask,
+  programs,
+  registerRobot,
+  submitBid,
+} from "./helpers";
+
+/**
+ * Losing bids: accepting a bid can reject and close the task's other bids
+ * in the same instruction, and any left over are closed permissionlessly
+ * once the task is no longer open. Each bid's rent and bond go back to its
+ * operator.
+ */
+describe("Task Market: closing losing bids", () => {
+  const { taskMarket } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const losers = [Keypair.generate(), Keypair.generate()];
+  let a: Assignment;
+  let losingBids: PublicKey[];
+
+  function pairs(...bids: [PublicKey, PublicKey][]) {
+    return bids.flatMap(([bid, operator]) => [
+      { pubkey: bid, isSigner: false, isWritable: true },
+      { pubkey: operator, isSigner: false, isWritable: true },
+    ]);
+  }
+
+  function rejectRemainingBids(task: PublicKey, bids: [PublicKey, PublicKey][]) {
+    return taskMarket.methods
+      .rejectRemainingBids()
+      .accountsPartial({ task })
+      .remainingAccounts(pairs(...bids))
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(...losers);
+    a = await openBidTask();
+    losingBids = [];
+    for (const operator of losers) {
+      const robot = await registerRobot(operator);
+      losingBids.push(await submitBid(a.task, a.creator.publicKey, robot, operator));
+    }
+  });
+
+  it("rejects a bid count without its accounts", async () => {
+    await expectError(acceptBid(a, 1).signers([a.creator]).rpc(), "InvalidBidAccounts");
+  });
+
+  it("rejects closing the accepted bid", async () => {
+    await expectError(
+      acceptBid(a, 1)
+        .remainingAccounts(pairs([a.bid, a.operator.publicKey]))
+        .signers([a.creator])
+        .rpc(),
+      "InvalidBidAccounts"
+    );
+  });
+
+  it("rejects closing a bid into anyone but its operator", async () => {
+    await expectError(
+      acceptBid(a, 1)
+        .remainingAccounts(pairs([losingBids[0], a.creator.publicKey]))
+        .signers([a.creator])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects closing another task's bid", async () => {
+    const other = await openBidTask();
+    await expectError(
+      acceptBid(a, 1)
+        .remainingAccounts(pairs([other.bid, other.operator.publicKey]))
+        .signers([a.creator])
+        .rpc(),
+      "BidTaskMismatch"
+    );
+  });
+
+  it("rejects sweeping bids while the task is open", async () => {
+    await expectError(rejectRemainingBids(a.task, [[losingBids[1], losers[1].publicKey]]), "TaskStillOpen");
+  });
+
+  it("closes losing bids given on acceptance", async () => {
+    const lamports = await connection.getBalance(losingBids[0]);
+    const before = await connection.getBalance(losers[0].publicKey);
+
+    await acceptBid(a, 1)
+      .remainingAccounts(pairs([losingBids[0], losers[0].publicKey]))
+      .signers([a.creator])
+      .rpc();
+
+    const accepted: any = await taskMarket.account.bid.fetch(a.bid);
+    expect(accepted.status).to.have.property("accepted");
+    expect(await connection.getAccountInfo(losingBids[0])).to.equal(null);
+    expect((await connection.getBalance(losers[0].publicKey)) - before).to.equal(lamports);
+  });
+
+  it("sweeps bids left over once the task is assigned", async () => {
+    const lamports = await connection.getBalance(losingBids[1]);
+    const before = await connection.getBalance(losers[1].publicKey);
+
+    await rejectRemainingBids(a.task, [[losingBids[1], losers[1].publicKey]]);
+
+    expect(await connection.getAccountInfo(losingBids[1])).to.equal(null);
+    expect((await connection.getBalance(losers[1].publicKey)) - before).to.equal(lamports);
+  });
+
+  it("rejects sweeping the accepted bid", async () => {
+    await expectError(rejectRemainingBids(a.task, [[a.bid, a.operator.publicKey]]), "InvalidBidAccounts");
+  });
+});
@@ -0,0 +1,69 @@
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  cpiEvents,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Low-escrow warnings: once a payer sets a runway threshold, any tick that
+ * leaves less escrow than that many seconds at the current rate emits
+ * LowEscrowWarning with the runway left.
+ */
+describe("Payment Streams: low-escrow warnings", () => {
+  const { paymentStreams } = programs();
+
+  const RATE = 1_000;
+  let s: Stream;
+
+  function setLowBalanceThreshold(seconds: number, signer = s.payer) {
+    return paymentStreams.methods
+      .setLowBalanceThreshold(seconds)
+      .accountsPartial({ stream: s.stream, authority: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  /** Tick after the next second passes, returning its warnings */
+  async function warningsOnTick() {
+    const { lastTickAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(lastTickAt).addn(1));
+    const signature = await tick(s).rpc();
+    return (await cpiEvents(paymentStreams, signature)).filter((e) => e.name === "lowEscrowWarning");
+  }
+
+  before(async () => {
+    s = await openStream({ rate: RATE, duration: 3_600 });
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("rejects a threshold set by anyone but the payer", async () => {
+    await expectError(setLowBalanceThreshold(7_200, s.payee), "Unauthorized");
+  });
+
+  it("stays quiet without a threshold", async () => {
+    expect(await warningsOnTick()).to.have.length(0);
+  });
+
+  it("stays quiet while the runway exceeds the threshold", async () => {
+    await setLowBalanceThreshold(60);
+    expect(await warningsOnTick()).to.have.length(0);
+  });
+
+  it("warns with the runway left once it falls below the threshold", async () => {
+    await setLowBalanceThreshold(7_200);
+    const warnings = await warningsOnTick();
+
+    const { escrowBalance } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(warnings).to.have.length(1);
+    expect(warnings[0].data.remainingSeconds.toNumber()).to.equal(Math.floor(escrowBalance.toNumber() / RATE));
+  });
+});
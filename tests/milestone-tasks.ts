@@ -0,0 +1,163 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Assignment,
+  TaskStatus,
+  acceptBid,
+  bidAddress,
+  createTask,
+  expectError,
+  fund,
+  programs,
+  registerRobot,
+  settlementAccounts,
+  setupMarket,
+  startTask,
+  submitBid,
+  tokenFor,
+} from "./helpers";
+
+// task-market's MilestoneStatus discriminants
+const MilestoneStatus = { Pending: 0, Completed: 1, Released: 2 };
+
+/**
+ * Milestone tasks: the creator splits an open task's escrow into shares,
+ * the assigned robot completes each and the creator's approval pays it out.
+ */
+describe("Task Market: milestone tasks", () => {
+  const { taskMarket } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const RATE = 10_000;
+  const DURATION = 3_600;
+  const milestones = [
+    { shareBps: 4_000, proofType: null },
+    { shareBps: 6_000, proofType: null },
+  ];
+
+  let a: Assignment;
+  let stream: Awaited<ReturnType<typeof settlementAccounts>>;
+
+  function setMilestones(terms: unknown[], creator = a.creator) {
+    return taskMarket.methods
+      .setMilestones(terms)
+      .accountsPartial({ task: a.task, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+  }
+
+  function completeMilestone(index: number, operator = a.operator) {
+    return taskMarket.methods
+      .completeMilestone(index)
+      .accountsPartial({ task: a.task, robot: a.robot, operator: operator.publicKey, proof: null })
+      .signers([operator])
+      .rpc();
+  }
+
+  function approveMilestone(index: number, creator = a.creator) {
+    return taskMarket.methods
+      .approveMilestone(index)
+      .accountsPartial({ task: a.task, creator: creator.publicKey, stream })
+      .signers([creator])
+      .rpc();
+  }
+
+  before(async () => {
+    const creator = Keypair.generate();
+    const operator = Keypair.generate();
+    await fund(creator, operator);
+    await setupMarket();
+
+    const { mint, token: creatorToken, treasury } = await tokenFor(creator, 1_000_000_000);
+    const task = await createTask(creator, { rate: RATE, duration: DURATION });
+    const robot = await registerRobot(operator);
+    a = { task, bid: bidAddress(task, robot), robot, creator, operator, mint, creatorToken, treasury };
+  });
+
+  it("rejects shares that don't add up to the whole escrow", async () => {
+    await expectError(setMilestones([{ shareBps: 4_000, proofType: null }]), "InvalidMilestones");
+    await expectError(setMilestones([]), "InvalidMilestones");
+    await expectError(
+      setMilestones([{ shareBps: 0, proofType: null }, { shareBps: 10_000, proofType: null }]),
+      "InvalidMilestones"
+    );
+  });
+
+  it("rejects milestones set by anyone but the creator", async () => {
+    await expectError(setMilestones(milestones, a.operator), "Unauthorized");
+  });
+
+  it("splits an open task into milestones", async () => {
+    await setMilestones(milestones);
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.milestoneCount).to.equal(2);
+    expect(task.milestones[0].shareBps).to.equal(4_000);
+    expect(task.milestones[1].shareBps).to.equal(6_000);
+    expect(task.milestones[0].status).to.equal(MilestoneStatus.Pending);
+  });
+
+  it("rejects changing milestones once the task has bids", async () => {
+    await submitBid(a.task, a.creator.publicKey, a.robot, a.operator, RATE, DURATION);
+    await expectError(setMilestones(milestones), "TaskHasBids");
+  });
+
+  it("splits the stream's escrow on assignment", async () => {
+    await acceptBid(a).signers([a.creator]).rpc();
+    await startTask(a).rpc();
+    stream = await settlementAccounts(a);
+
+    const account: any = await programs().paymentStreams.account.paymentStream.fetch(stream.stream);
+    expect(account.mode).to.have.property("milestone");
+    expect(account.milestones.map((m: any) => m.amount.toNumber())).to.deep.equal([
+      (RATE * DURATION * 4_000) / 10_000,
+      (RATE * DURATION * 6_000) / 10_000,
+    ]);
+  });
+
+  it("rejects milestone completion by anyone but the assigned operator", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(completeMilestone(0, intruder), "Unauthorized");
+  });
+
+  it("rejects approving a milestone the robot hasn't completed", async () => {
+    await expectError(approveMilestone(1), "MilestoneNotCompleted");
+  });
+
+  it("marks a milestone completed by the robot", async () => {
+    await completeMilestone(0);
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.status).to.equal(TaskStatus.InProgress);
+    expect(task.milestones[0].status).to.equal(MilestoneStatus.Completed);
+  });
+
+  it("rejects completing a milestone twice or one that doesn't exist", async () => {
+    await expectError(completeMilestone(0), "MilestoneNotPending");
+    await expectError(completeMilestone(2), "InvalidMilestone");
+  });
+
+  it("rejects approval by anyone but the creator", async () => {
+    await expectError(approveMilestone(0, a.operator), "Unauthorized");
+  });
+
+  it("pays a completed milestone's share on approval", async () => {
+    await approveMilestone(0);
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.milestones[0].status).to.equal(MilestoneStatus.Released);
+    expect(task.milestones[1].status).to.equal(MilestoneStatus.Pending);
+
+    // The share less the platform fee
+    const paid = Number((await getAccount(connection, stream.operatorToken)).amount);
+    expect(paid).to.be.gt(0);
+    expect(paid).to.be.lte((RATE * DURATION * 4_000) / 10_000);
+  });
+
+  it("rejects approving a released milestone again", async () => {
+    await expectError(approveMilestone(0), "MilestoneNotCompleted");
+  });
+});
@@ -0,0 +1,123 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  terminateStream,
+  tick,
+} from "./helpers";
+
+/**
+ * Milestone streams: milestones added before start switch a stream from
+ * per-second payment to releasing escrow per milestone the payer approves.
+ */
+describe("Payment Streams: milestones", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  // The default stream escrows 1,000 per second for an hour
+  const ESCROW = 3_600_000;
+  const FIRST = 1_000_000;
+  const SECOND = 2_000_000;
+  let s: Stream;
+
+  function addMilestone(amount: number, signer = s.payer) {
+    return paymentStreams.methods
+      .addMilestone(new BN(amount), Array.from(Keypair.generate().publicKey.toBytes()))
+      .accountsPartial({ stream: s.stream, payer: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  function approveMilestone(index: number, signer = s.payer) {
+    return paymentStreams.methods
+      .approveMilestone(index)
+      .accountsPartial({ stream: s.stream, payer: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  function releaseMilestone(index: number) {
+    return paymentStreams.methods
+      .releaseMilestone(index)
+      .accountsPartial({
+        stream: s.stream,
+        escrow: s.escrow,
+        mint: s.mint,
+        claimToken: null,
+        payeeToken: s.payeeToken,
+        treasury: s.treasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token)).amount);
+  }
+
+  before(async () => {
+    s = await openStream();
+  });
+
+  it("rejects milestones added by anyone but the payer", async () => {
+    await expectError(addMilestone(FIRST, s.payee), "Unauthorized");
+  });
+
+  it("rejects empty milestones and milestones escrow can't cover", async () => {
+    await expectError(addMilestone(0), "InvalidMilestoneAmount");
+    await expectError(addMilestone(ESCROW + 1), "InsufficientEscrow");
+  });
+
+  it("switches the stream to milestone mode", async () => {
+    await addMilestone(FIRST);
+    await addMilestone(SECOND);
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.mode).to.have.property("milestone");
+    expect(account.milestones).to.have.length(2);
+    await expectError(tick(s).rpc(), "NotContinuousStream");
+  });
+
+  it("rejects approval by anyone but the payer, or of a missing milestone", async () => {
+    await expectError(approveMilestone(0, s.payee), "Unauthorized");
+    await expectError(approveMilestone(2), "InvalidMilestone");
+  });
+
+  it("rejects releasing a milestone before it's approved", async () => {
+    await expectError(releaseMilestone(0), "MilestoneNotApproved");
+  });
+
+  it("releases an approved milestone to the payee", async () => {
+    await approveMilestone(0);
+    const payeeBefore = await balance(s.payeeToken);
+    const treasuryBefore = await balance(s.treasury);
+    await releaseMilestone(0);
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const paid = (await balance(s.payeeToken)) - payeeBefore + (await balance(s.treasury)) - treasuryBefore;
+    expect(paid).to.equal(FIRST);
+    expect(account.milestones[0].status).to.have.property("released");
+    expect(account.escrowBalance.toNumber()).to.equal(ESCROW - FIRST);
+    await expectError(approveMilestone(0), "MilestoneNotPending");
+  });
+
+  it("pays approved milestones and refunds the rest on termination", async () => {
+    await approveMilestone(1);
+    const payerBefore = await balance(s.payerToken);
+    await terminateStream(s).rpc();
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.totalPaid.toNumber()).to.equal(FIRST + SECOND);
+    expect((await balance(s.payerToken)) - payerBefore).to.equal(ESCROW - FIRST - SECOND);
+  });
+});
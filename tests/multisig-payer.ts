@@ -0,0 +1,184 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { PublicKey, Keypair, SystemProgram, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { createMint, createAccount, mintTo, getAccount, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+
+/**
+ * Streams whose payer is a PDA rather than a keypair, as with a Squads
+ * vault. The mock multisig program signs for its vault via CPI, so every
+ * payer-side instruction below is wrapped in `mockMultisig.execute`.
+ */
+describe("Payment Streams: multisig payer", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const connection = provider.connection;
+
+  const paymentStreams = anchor.workspace.PaymentStreams as Program<any>;
+  const mockMultisig = anchor.workspace.MockMultisig as Program<any>;
+
+  const controller = Keypair.generate();
+  const payee = Keypair.generate();
+  const [vault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), controller.publicKey.toBuffer()],
+    mockMultisig.programId
+  );
+
+  let mint: PublicKey;
+  let vaultToken: PublicKey;
+  let payeeToken: PublicKey;
+  let treasury: PublicKey;
+  let stream: PublicKey | null = null;
+
+  async function viaVault(ix: TransactionInstruction): Promise<string> {
+    return mockMultisig.methods
+      .execute(ix.data)
+      .accountsPartial({ vault, controller: controller.publicKey, targetProgram: ix.programId })
+      .remainingAccounts(
+        ix.keys.map((key) => ({ ...key, isSigner: key.pubkey.equals(vault) ? false : key.isSigner }))
+      )
+      .signers([controller])
+      .rpc();
+  }
+
+  before(async () => {
+    const sig = await connection.requestAirdrop(controller.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL);
+    await connection.confirmTransaction(sig, "confirmed");
+
+    // The vault pays rent for the stream, escrow and registries
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: controller.publicKey,
+          toPubkey: vault,
+          lamports: 2 * anchor.web3.LAMPORTS_PER_SOL,
+        })
+      ),
+      [controller]
+    );
+
+    try {
+      await paymentStreams.methods.initialize().accounts({ authority: provider.wallet.publicKey }).rpc();
+    } catch (err) {
+      // Config PDA is shared with other test files
+      if (!String(err).includes("already in use")) throw err;
+    }
+
+    mint = await createMint(connection, controller, controller.publicKey, null, 6);
+    vaultToken = await createAccount(connection, controller, mint, vault, Keypair.generate());
+    payeeToken = await createAccount(connection, controller, mint, payee.publicKey);
+    await mintTo(connection, controller, mint, vaultToken, controller, 1_000_000_000);
+
+    [treasury] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury"), mint.toBuffer()],
+      paymentStreams.programId
+    );
+    await paymentStreams.methods
+      .initializeTreasury()
+      .accountsPartial({ treasury, mint, payer: controller.publicKey, tokenProgram: TOKEN_PROGRAM_ID })
+      .signers([controller])
+      .rpc();
+  });
+
+  it("creates a stream with a PDA payer", async () => {
+    const nonce = new BN(0);
+    [stream] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stream"), vault.toBuffer(), payee.publicKey.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+      paymentStreams.programId
+    );
+    await viaVault(
+      await paymentStreams.methods
+        .createStream(nonce, new BN(1_000), new BN(3_600), new BN(60), true, new BN(0), new BN(0), null)
+        .accountsPartial({
+          stream,
+          mint,
+          payerToken: vaultToken,
+          payer: vault,
+          payee: payee.publicKey,
+          payerStake: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction()
+    );
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(stream!);
+    expect(account.payer.toBase58()).to.equal(vault.toBase58());
+    expect(account.escrowBalance.toNumber()).to.equal(3_600_000);
+  });
+
+  it("tops up escrow from the PDA payer", async () => {
+    const ix = await paymentStreams.methods
+      .topUpEscrow(new BN(400_000))
+      .accountsPartial({ stream, mint, payerToken: vaultToken, payer: vault, tokenProgram: TOKEN_PROGRAM_ID })
+      .instruction();
+    await viaVault(ix);
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(stream!);
+    expect(account.escrowBalance.toNumber()).to.equal(4_000_000);
+  });
+
+  it("starts and terminates under the PDA payer's authority", async () => {
+    await paymentStreams.methods
+      .acceptStream()
+      .accountsPartial({ stream, payee: payee.publicKey })
+      .signers([payee])
+      .rpc();
+
+    await viaVault(
+      await paymentStreams.methods
+        .startStream()
+        .accountsPartial({
+          stream,
+          mint,
+          claimToken: null,
+          payeeToken,
+          treasury,
+          payer: vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction()
+    );
+
+    const before = (await getAccount(connection, vaultToken)).amount;
+    await viaVault(
+      await paymentStreams.methods
+        .terminateStream("Job done")
+        .accountsPartial({
+          stream,
+          mint,
+          payerToken: vaultToken,
+          payeeToken,
+          claimToken: null,
+          treasury,
+          authority: vault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .instruction()
+    );
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(stream!);
+    expect(account.status).to.have.property("completed");
+    expect(account.escrowBalance.toNumber()).to.equal(0);
+    expect((await getAccount(connection, vaultToken)).amount > before).to.be.true;
+  });
+
+  it("rejects a payer-only instruction not signed by the vault", async () => {
+    const ix = await paymentStreams.methods
+      .topUpEscrow(new BN(1))
+      .accountsPartial({
+        stream,
+        mint,
+        payerToken: vaultToken,
+        payer: controller.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .instruction();
+
+    try {
+      await provider.sendAndConfirm(new Transaction().add(ix), [controller]);
+      expect.fail("top-up by a non-payer should fail");
+    } catch (err) {
+      expect(String(err)).to.match(/Unauthorized|ConstraintRaw|constraint/i);
+    }
+  });
+});
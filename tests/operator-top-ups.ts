@@ -0,0 +1,70 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, programs, setupToken, stakedOperator } from "./helpers";
+
+/**
+ * Operator top-ups: an operator adds tokens to their stake, raising what
+ * can be slashed and so what they may bid on.
+ */
+describe("DRONEOS Token: operator top-ups", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const AMOUNT = 100 * 1_000_000;
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let operator: Keypair;
+  let operatorStake: PublicKey;
+  let operatorToken: PublicKey;
+
+  function topUp(amount: number, signer = operator, token = operatorToken) {
+    return droneosToken.methods
+      .topUpOperatorStake(new BN(amount))
+      .accountsPartial({
+        config: t.config,
+        operatorStake,
+        operatorVault: t.operatorVault,
+        operatorToken: token,
+        operator: signer.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    ({ operator, operatorStake } = await stakedOperator());
+    operatorToken = await drip(operator, AMOUNT);
+  });
+
+  it("rejects topping up someone else's stake", async () => {
+    await expectError(topUp(AMOUNT, intruder, await drip(intruder)), "ConstraintSeeds");
+  });
+
+  it("rejects an empty top-up", async () => {
+    await expectError(topUp(0), "InvalidAmount");
+  });
+
+  it("adds the top-up to the stake and its slashable amount", async () => {
+    const before: any = await droneosToken.account.operatorStake.fetch(operatorStake);
+    const vaultBefore = await balance(t.operatorVault);
+
+    await topUp(AMOUNT);
+
+    const after: any = await droneosToken.account.operatorStake.fetch(operatorStake);
+    expect(after.totalStaked.sub(before.totalStaked).toNumber()).to.equal(AMOUNT);
+    expect(after.slashableAmount.sub(before.slashableAmount).toNumber()).to.equal(AMOUNT);
+    expect((await balance(t.operatorVault)) - vaultBefore).to.equal(AMOUNT);
+    expect(await balance(operatorToken)).to.equal(0);
+  });
+});
@@ -0,0 +1,91 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, programs, setupToken, stakedOperator } from "./helpers";
+
+/**
+ * Operator withdrawals: an operator asks to withdraw stake, leaving either
+ * nothing or at least the minimum, and can collect it once a 7-day
+ * unbonding period is over.
+ */
+describe("DRONEOS Token: operator withdrawals", () => {
+  const { droneosToken } = programs();
+
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let operator: Keypair;
+  let operatorStake: PublicKey;
+  let operatorToken: PublicKey;
+
+  function requestOperatorUnstake(amount: number, signer = operator) {
+    return droneosToken.methods
+      .requestOperatorUnstake(new BN(amount))
+      .accountsPartial({ config: t.config, operatorStake, operator: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  function finalizeOperatorUnstake() {
+    return droneosToken.methods
+      .finalizeOperatorUnstake()
+      .accountsPartial({
+        config: t.config,
+        operatorStake,
+        operatorVault: t.operatorVault,
+        operatorToken,
+        operator: operator.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([operator])
+      .rpc();
+  }
+
+  async function slashable() {
+    return (await droneosToken.account.operatorStake.fetch(operatorStake)).slashableAmount.toNumber();
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    ({ operator, operatorStake } = await stakedOperator());
+    operatorToken = await drip(operator);
+  });
+
+  it("rejects withdrawing from someone else's stake", async () => {
+    await expectError(requestOperatorUnstake(1_000_000, intruder), "ConstraintSeeds");
+  });
+
+  it("rejects collecting a withdrawal that was never requested", async () => {
+    await expectError(finalizeOperatorUnstake(), "NoUnbondingRequest");
+  });
+
+  it("rejects withdrawing nothing or more than the slashable stake", async () => {
+    await expectError(requestOperatorUnstake(0), "InsufficientStake");
+    await expectError(requestOperatorUnstake((await slashable()) + 1), "InsufficientStake");
+  });
+
+  it("rejects leaving less than the minimum operator stake", async () => {
+    await expectError(requestOperatorUnstake((await slashable()) - 1), "BelowMinimumOperatorStake");
+  });
+
+  it("starts unbonding the whole stake", async () => {
+    const amount = await slashable();
+    await requestOperatorUnstake(amount);
+
+    const unbonding: any = await droneosToken.account.operatorStake.fetch(operatorStake);
+    expect(unbonding.unbondingAmount.toNumber()).to.equal(amount);
+    expect(unbonding.unbondingStartedAt).to.not.equal(null);
+    expect(unbonding.slashableAmount.toNumber()).to.equal(amount);
+  });
+
+  it("rejects a second request while unbonding", async () => {
+    await expectError(requestOperatorUnstake(1_000_000), "UnbondingInProgress");
+  });
+
+  it("rejects collecting before the unbonding period is over", async () => {
+    await expectError(finalizeOperatorUnstake(), "UnbondingNotComplete");
+  });
+});
@@ -0,0 +1,76 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, pda, programs, setupToken, stake, unstake } from "./helpers";
+
+/**
+ * Partial unstakes: the withdrawn part takes its share of the rewards and
+ * the remainder stays in the position with its original lock and timing.
+ */
+describe("DRONEOS Token: partial unstakes", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const AMOUNT = 200 * 1_000_000;
+  const PART = 100 * 1_000_000;
+  const staker = Keypair.generate();
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let stakerToken: PublicKey;
+  let positions: PublicKey;
+  let unlocked: PublicKey;
+  let locked: PublicKey;
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(staker, intruder);
+    t = await setupToken();
+    positions = pda(droneosToken.programId, Buffer.from("positions"), staker.publicKey.toBuffer());
+    stakerToken = await drip(staker, AMOUNT + PART);
+    unlocked = await stake(staker, stakerToken, AMOUNT);
+    locked = await stake(staker, stakerToken, PART, 30);
+  });
+
+  it("rejects withdrawing nothing or more than the position holds", async () => {
+    await expectError(unstake(staker, stakerToken, unlocked, 0), "InvalidAmount");
+    await expectError(unstake(staker, stakerToken, unlocked, AMOUNT + 1), "InsufficientStake");
+  });
+
+  it("rejects a withdrawal by anyone but the owner", async () => {
+    await expectError(unstake(intruder, await drip(intruder), unlocked, PART), "Unauthorized");
+  });
+
+  it("rejects withdrawing part of a position still locked", async () => {
+    await expectError(unstake(staker, stakerToken, locked, PART / 2), "StakeLocked");
+  });
+
+  it("withdraws part of a position, leaving the rest's lock and timing", async () => {
+    const before: any = await droneosToken.account.stakeAccount.fetch(unlocked);
+    const { openPositions } = await droneosToken.account.stakerPositions.fetch(positions);
+    const balanceBefore = await balance(stakerToken);
+
+    await unstake(staker, stakerToken, unlocked, PART);
+
+    const after: any = await droneosToken.account.stakeAccount.fetch(unlocked);
+    expect(after.amount.toNumber()).to.equal(AMOUNT - PART);
+    expect(after.stakedAt.toNumber()).to.equal(before.stakedAt.toNumber());
+    expect(after.lockUntil.toNumber()).to.equal(before.lockUntil.toNumber());
+    expect(after.lastClaimAt.toNumber()).to.equal(before.lastClaimAt.toNumber());
+    expect(after.multiplier).to.equal(before.multiplier);
+    // The withdrawn part's rewards, if the schedule has any left, come on top
+    expect((await balance(stakerToken)) - balanceBefore).to.be.gte(PART);
+    expect((await droneosToken.account.stakerPositions.fetch(positions)).openPositions).to.equal(openPositions);
+  });
+
+  it("closes out the position when the rest is withdrawn", async () => {
+    const { openPositions } = await droneosToken.account.stakerPositions.fetch(positions);
+    await unstake(staker, stakerToken, unlocked);
+
+    expect((await droneosToken.account.stakeAccount.fetch(unlocked)).amount.toNumber()).to.equal(0);
+    expect((await droneosToken.account.stakerPositions.fetch(positions)).openPositions).to.equal(openPositions - 1);
+  });
+});
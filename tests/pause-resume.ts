@@ -0,0 +1,73 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Pause and resume: pausing snapshots what has accrued so far, nothing
+ * accrues while paused, and the next tick after resuming pays the snapshot
+ * plus the time since resuming.
+ */
+describe("Payment Streams: pause and resume", () => {
+  const { paymentStreams } = programs();
+
+  const RATE = 1_000;
+  let s: Stream;
+  let accrued: number;
+
+  function control(method: "pauseStream" | "resumeStream", signer: Keypair = s.payer) {
+    return paymentStreams.methods[method]()
+      .accountsPartial({ stream: s.stream, authority: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    s = await openStream({ rate: RATE });
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("rejects resuming a stream that isn't paused", async () => {
+    await expectError(control("resumeStream"), "StreamNotPaused");
+  });
+
+  it("rejects pausing by anyone but the payer", async () => {
+    await expectError(control("pauseStream", s.payee), "Unauthorized");
+  });
+
+  it("keeps what accrued before the pause", async () => {
+    const { startedAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(startedAt).addn(2));
+    await control("pauseStream");
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    accrued = RATE * account.lastTickAt.sub(account.startedAt).toNumber();
+    expect(account.status).to.have.property("paused");
+    expect(account.accruedUnpaid.toNumber()).to.equal(accrued);
+    await expectError(tick(s).rpc(), "StreamNotActive");
+  });
+
+  it("pays the snapshot plus only the time since resuming", async () => {
+    const { lastTickAt: pausedAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(pausedAt).addn(2));
+    await control("resumeStream");
+    const { lastTickAt: resumedAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(resumedAt).addn(1));
+    await tick(s).rpc();
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const sinceResume = account.lastTickAt.sub(new BN(resumedAt)).toNumber();
+    expect(account.totalPaid.toNumber()).to.equal(accrued + RATE * sinceResume);
+    expect(account.accruedUnpaid.toNumber()).to.equal(0);
+  });
+});
@@ -0,0 +1,74 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { createAccount, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  programs,
+  startStream,
+  terminateStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Payee transfers: with its payee's consent a payer can reassign a stream
+ * to a new payee, who is paid from then on without touching escrow.
+ */
+describe("Payment Streams: payee transfers", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const successor = Keypair.generate();
+  let s: Stream;
+
+  function transferPayee(stream: Stream, payer = stream.payer, consenter = stream.payee) {
+    return paymentStreams.methods
+      .transferPayee(successor.publicKey)
+      .accountsPartial({ stream: stream.stream, payer: payer.publicKey, consenter: consenter.publicKey })
+      .signers([payer, consenter])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(successor);
+    s = await openStream();
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("rejects a transfer without the payer", async () => {
+    await expectError(transferPayee(s, s.payee, s.payee), "Unauthorized");
+  });
+
+  it("rejects a transfer without the payee's consent", async () => {
+    await expectError(transferPayee(s, s.payer, successor), "Unauthorized");
+  });
+
+  it("pays the new payee from then on", async () => {
+    const escrowed = (await paymentStreams.account.paymentStream.fetch(s.stream)).escrowBalance.toNumber();
+    await transferPayee(s);
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.payee.toBase58()).to.equal(successor.publicKey.toBase58());
+    expect(account.escrowBalance.toNumber()).to.equal(escrowed);
+
+    await waitForClock(new BN(account.lastTickAt).addn(1));
+    await expectError(tick(s).rpc(), "NotClaimHolder");
+
+    const successorToken = await createAccount(connection, successor, s.mint, successor.publicKey);
+    s = { ...s, payee: successor, payeeToken: successorToken };
+    await tick(s).rpc();
+    expect(Number((await getAccount(connection, successorToken)).amount)).to.be.gt(0);
+  });
+
+  it("rejects transferring a finished stream", async () => {
+    await terminateStream(s).rpc();
+    await expectError(transferPayee(s), "StreamAlreadyTerminated");
+  });
+});
@@ -0,0 +1,95 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  pda,
+  programs,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Platform fees: every stream payout splits between the payee and the
+ * platform's treasury for the stream's mint, which only the program
+ * authority can withdraw from.
+ */
+describe("Payment Streams: platform fees", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const RATE = 1_000_000;
+  const intruder = Keypair.generate();
+  let s: Stream;
+  let destination: PublicKey;
+
+  function withdrawFees(amount: number, signer?: Keypair) {
+    return paymentStreams.methods
+      .withdrawFees(new BN(amount))
+      .accountsPartial({
+        treasury: s.treasury,
+        destination,
+        mint: s.mint,
+        authority: signer?.publicKey ?? authority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token)).amount);
+  }
+
+  before(async () => {
+    await fund(intruder);
+    s = await openStream({ rate: RATE, duration: 600 });
+    destination = await createAccount(connection, intruder, s.mint, intruder.publicKey);
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("splits a tick between the payee and the treasury", async () => {
+    const config = pda(paymentStreams.programId, Buffer.from("config"));
+    const { feeBasisPoints, totalVolume } = await paymentStreams.account.programConfig.fetch(config);
+    const before: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const payeeBefore = await balance(s.payeeToken);
+    const treasuryBefore = await balance(s.treasury);
+
+    await waitForClock(new BN(before.lastTickAt).addn(2));
+    await tick(s).rpc();
+
+    const after: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const amount = after.totalPaid.sub(before.totalPaid).toNumber();
+    const fee = Math.floor((amount * feeBasisPoints) / 10_000);
+    expect(fee).to.be.gt(0);
+    expect((await balance(s.treasury)) - treasuryBefore).to.equal(fee);
+    expect((await balance(s.payeeToken)) - payeeBefore).to.equal(amount - fee);
+    const { totalVolume: volume } = await paymentStreams.account.programConfig.fetch(config);
+    expect(volume.sub(totalVolume).toNumber()).to.equal(amount);
+  });
+
+  it("rejects fee withdrawals by anyone but the authority", async () => {
+    await expectError(withdrawFees(1, intruder), "Unauthorized");
+  });
+
+  it("rejects withdrawing more than the treasury holds", async () => {
+    await expectError(withdrawFees((await balance(s.treasury)) + 1), "InsufficientFunds");
+  });
+
+  it("withdraws collected fees on the authority's say", async () => {
+    const fees = await balance(s.treasury);
+    await withdrawFees(fees);
+
+    expect(await balance(s.treasury)).to.equal(0);
+    expect(await balance(destination)).to.equal(fees);
+  });
+});
@@ -0,0 +1,92 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  acceptBid,
+  createTask,
+  expectError,
+  fund,
+  openBidTask,
+  pda,
+  programs,
+  setupMarket,
+} from "./helpers";
+
+/**
+ * Priority boosts: a creator can boost an open task for discovery by paying
+ * the market's hourly fee into the treasury the authority sets.
+ */
+describe("Task Market: priority boosts", () => {
+  const { taskMarket } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+  const market = pda(taskMarket.programId, Buffer.from("market"));
+
+  const FEE_PER_HOUR = LAMPORTS_PER_SOL / 10;
+  const creator = Keypair.generate();
+  const treasury = Keypair.generate();
+  let task: PublicKey;
+
+  function setBoostTerms(feePerHour: number, to: PublicKey) {
+    return taskMarket.methods.setBoostTerms(new BN(feePerHour), to).accountsPartial({ market, authority }).rpc();
+  }
+
+  function boostTask(duration: number, signer = creator, address = task, to = treasury.publicKey) {
+    return taskMarket.methods
+      .boostTask(new BN(duration))
+      .accountsPartial({ market, task: address, creator: signer.publicKey, treasury: to })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(creator, treasury);
+    await setupMarket();
+    task = await createTask(creator);
+    await setBoostTerms(FEE_PER_HOUR, treasury.publicKey);
+  });
+
+  after(async () => {
+    // The market is shared with other test files
+    await setBoostTerms(0, PublicKey.default);
+  });
+
+  it("rejects boosts by anyone but the creator", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(boostTask(3_600, intruder), "Unauthorized");
+  });
+
+  it("rejects a fee paid anywhere but the treasury", async () => {
+    await expectError(boostTask(3_600, creator, task, creator.publicKey), "Unauthorized");
+  });
+
+  it("rejects a boost without a duration", async () => {
+    await expectError(boostTask(0), "InvalidBoostDuration");
+  });
+
+  it("charges the hourly fee for the boost", async () => {
+    const before = await connection.getBalance(treasury.publicKey);
+    await boostTask(3_600);
+
+    expect((await connection.getBalance(treasury.publicKey)) - before).to.equal(FEE_PER_HOUR);
+    const boosted: any = await taskMarket.account.task.fetch(task);
+    const now = await connection.getBlockTime(await connection.getSlot("confirmed"));
+    expect(boosted.boostedUntil.toNumber()).to.be.gt(now!);
+  });
+
+  it("extends a boosted task's boost", async () => {
+    const { boostedUntil } = await taskMarket.account.task.fetch(task);
+    await boostTask(1_800);
+
+    const extended: any = await taskMarket.account.task.fetch(task);
+    expect(extended.boostedUntil.toNumber()).to.equal(boostedUntil.toNumber() + 1_800);
+  });
+
+  it("rejects boosting a task that isn't open", async () => {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await expectError(boostTask(3_600, a.creator, a.task), "TaskNotOpen");
+  });
+});
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+declare_id!("DOS4msg1111111111111111111111111111111111111");
+
+/// Test-only multisig stand-in
+///
+/// Owns a system-owned vault PDA per controller and executes arbitrary
+/// instructions with the vault as signer, the way a Squads vault
+/// transaction does. Used to exercise PDA payers in other programs.
+
+#[program]
+pub mod mock_multisig {
+    use super::*;
+
+    /// Invoke `target_program` with `data`. Remaining accounts are passed
+    /// through as the instruction's accounts, with the vault marked signer.
+    pub fn execute<'info>(
+        ctx: Context<'_, '_, '_, 'info, Execute<'info>>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        let vault = ctx.accounts.vault.key();
+        let accounts = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer || *account.key == vault,
+                is_writable: account.is_writable,
+            })
+            .collect();
+
+        let mut account_infos = ctx.remaining_accounts.to_vec();
+        account_infos.push(ctx.accounts.target_program.to_account_info());
+
+        let controller = ctx.accounts.controller.key();
+        let seeds = &[b"vault".as_ref(), controller.as_ref(), &[ctx.bumps.vault]];
+        invoke_signed(
+            &Instruction {
+                program_id: ctx.accounts.target_program.key(),
+                accounts,
+                data,
+            },
+            &account_infos,
+            &[&seeds[..]],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Execute<'info> {
+    /// CHECK: System-owned vault PDA, only ever used as a CPI signer
+    #[account(mut, seeds = [b"vault", controller.key().as_ref()], bump)]
+    pub vault: UncheckedAccount<'info>,
+
+    pub controller: Signer<'info>,
+
+    /// CHECK: Any program; the caller picks what the vault signs for
+    #[account(executable)]
+    pub target_program: UncheckedAccount<'info>,
+}
@@ -0,0 +1,95 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Assignment,
+  acceptBid,
+  expectError,
+  gpsProofAddress,
+  openBidTask,
+  programs,
+  registerRobot,
+  startTask,
+  submitGpsProof,
+  verifyProof,
+} from "./helpers";
+
+/**
+ * Progress checkpoints: a robot may report progress up to the proof
+ * threshold on its own word. Past it, each checkpoint needs a verified
+ * oracle proof for the task taken since it started, which the task keeps.
+ */
+describe("Task Market: progress checkpoints", () => {
+  const { taskMarket } = programs();
+
+  // task_market::PROGRESS_PROOF_THRESHOLD
+  const THRESHOLD = 50;
+  let a: Assignment;
+
+  function updateProgress(t: Assignment, progress: number, proof: PublicKey | null = null) {
+    return taskMarket.methods
+      .updateProgress(progress)
+      .accountsPartial({ task: t.task, robot: t.robot, operator: t.operator.publicKey, proof })
+      .signers([t.operator])
+      .rpc();
+  }
+
+  async function inProgress(): Promise<Assignment> {
+    const t = await openBidTask();
+    await acceptBid(t).signers([t.creator]).rpc();
+    await startTask(t).rpc();
+    return t;
+  }
+
+  async function startedAt(t: Assignment): Promise<BN> {
+    return new BN((await taskMarket.account.task.fetch(t.task)).startedAt);
+  }
+
+  before(async () => {
+    a = await inProgress();
+  });
+
+  it("rejects progress on a task that hasn't started", async () => {
+    const t = await openBidTask();
+    await acceptBid(t).signers([t.creator]).rpc();
+    await expectError(updateProgress(t, 10), "TaskNotInProgress");
+  });
+
+  it("rejects progress from another robot or past 100", async () => {
+    const other = await registerRobot(a.operator);
+    await expectError(updateProgress({ ...a, robot: other }, 10), "NotAssignedRobot");
+    await expectError(updateProgress(a, 101), "InvalidProgress");
+  });
+
+  it("takes progress up to the threshold without a proof", async () => {
+    await updateProgress(a, THRESHOLD);
+    expect((await taskMarket.account.task.fetch(a.task)).progress).to.equal(THRESHOLD);
+
+    await expectError(updateProgress(a, THRESHOLD + 1), "ProofRequired");
+  });
+
+  it("rejects a proof the oracle hasn't verified", async () => {
+    const t = await inProgress();
+    await (await submitGpsProof(t, { latitude: 0, longitude: 0, timestamp: await startedAt(t) })).rpc();
+    await expectError(updateProgress(t, 80, gpsProofAddress(t)), "ProofNotVerified");
+  });
+
+  it("rejects a proof taken before the task started", async () => {
+    const t = await inProgress();
+    const early = (await startedAt(t)).subn(60);
+    await (await submitGpsProof(t, { latitude: 0, longitude: 0, timestamp: early })).rpc();
+    await verifyProof(gpsProofAddress(t));
+    await expectError(updateProgress(t, 80, gpsProofAddress(t)), "ProofMismatch");
+  });
+
+  it("records a proven checkpoint past the threshold", async () => {
+    const proof = gpsProofAddress(a);
+    await (await submitGpsProof(a, { latitude: 0, longitude: 0, timestamp: await startedAt(a) })).rpc();
+    await verifyProof(proof);
+    await updateProgress(a, 80, proof);
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.progress).to.equal(80);
+    expect(task.progressProof.toBase58()).to.equal(proof.toBase58());
+  });
+});
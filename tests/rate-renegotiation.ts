@@ -0,0 +1,81 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  programs,
+  startStream,
+  terminateStream,
+} from "./helpers";
+
+/**
+ * Rate renegotiation: either party proposes a new rate and only the other
+ * can accept it, the new rate applying from acceptance with what accrued at
+ * the old rate kept for the next tick.
+ */
+describe("Payment Streams: rate renegotiation", () => {
+  const { paymentStreams } = programs();
+
+  let s: Stream;
+
+  function proposeRateChange(rate: number, signer: Keypair) {
+    return paymentStreams.methods
+      .proposeRateChange(new BN(rate))
+      .accountsPartial({ stream: s.stream, claimToken: null, authority: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  function acceptRateChange(signer: Keypair) {
+    return paymentStreams.methods
+      .acceptRateChange()
+      .accountsPartial({ stream: s.stream, claimToken: null, authority: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    s = await openStream({ rate: 1_000 });
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("rejects proposals by anyone but the payer or payee", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(proposeRateChange(2_000, intruder), "Unauthorized");
+  });
+
+  it("rejects a zero rate", async () => {
+    await expectError(proposeRateChange(0, s.payee), "InvalidRate");
+  });
+
+  it("rejects accepting without a proposal", async () => {
+    await expectError(acceptRateChange(s.payer), "NoRateProposal");
+  });
+
+  it("rejects the proposer accepting their own proposal", async () => {
+    await proposeRateChange(1_500, s.payee);
+    await expectError(acceptRateChange(s.payee), "Unauthorized");
+  });
+
+  it("applies the counterparty's accepted rate from then on", async () => {
+    await acceptRateChange(s.payer);
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.ratePerSecond.toNumber()).to.equal(1_500);
+    expect(account.pendingRate).to.equal(null);
+    expect(account.accruedUnpaid.toNumber()).to.equal(
+      1_000 * account.lastTickAt.sub(account.startedAt).toNumber()
+    );
+  });
+
+  it("rejects proposals on a finished stream", async () => {
+    await terminateStream(s).rpc();
+    await expectError(proposeRateChange(2_000, s.payer), "StreamAlreadyTerminated");
+  });
+});
@@ -0,0 +1,74 @@
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Rate schedules: before start, a payer can give a stream rate segments
+ * taking effect at offsets from its start, and ticks integrate the
+ * piecewise rate.
+ */
+describe("Payment Streams: rate schedules", () => {
+  const { paymentStreams } = programs();
+
+  const RATE = 1_000;
+  const NIGHT_RATE = 2_000;
+  const NIGHT_OFFSET = 2;
+  let s: Stream;
+
+  function setRateSchedule(segments: [number, number][], signer = s.payer) {
+    return paymentStreams.methods
+      .setRateSchedule(
+        segments.map(([startOffset, ratePerSecond]) => ({
+          startOffset: new BN(startOffset),
+          ratePerSecond: new BN(ratePerSecond),
+        }))
+      )
+      .accountsPartial({ stream: s.stream, payer: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    s = await openStream({ rate: RATE });
+  });
+
+  it("rejects a schedule set by anyone but the payer", async () => {
+    await expectError(setRateSchedule([[NIGHT_OFFSET, NIGHT_RATE]], s.payee), "Unauthorized");
+  });
+
+  it("rejects more segments than a stream holds", async () => {
+    const segments: [number, number][] = Array.from({ length: 9 }, (_, i) => [i + 1, RATE]);
+    await expectError(setRateSchedule(segments), "TooManyRateSegments");
+  });
+
+  it("rejects zero rates and out-of-order segments", async () => {
+    await expectError(setRateSchedule([[NIGHT_OFFSET, 0]]), "InvalidRateSchedule");
+    await expectError(setRateSchedule([[20, RATE], [10, NIGHT_RATE]]), "InvalidRateSchedule");
+  });
+
+  it("bills each second at the rate in effect", async () => {
+    await setRateSchedule([[NIGHT_OFFSET, NIGHT_RATE]]);
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    const { startedAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(startedAt).addn(NIGHT_OFFSET + 2));
+    await tick(s).rpc();
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const elapsed = account.lastTickAt.sub(account.startedAt).toNumber();
+    expect(account.totalPaid.toNumber()).to.equal(RATE * NIGHT_OFFSET + NIGHT_RATE * (elapsed - NIGHT_OFFSET));
+  });
+
+  it("rejects changing the schedule once the stream is accepted", async () => {
+    await expectError(setRateSchedule([[NIGHT_OFFSET, RATE]]), "StreamNotPending");
+  });
+});
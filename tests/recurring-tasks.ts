@@ -0,0 +1,137 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INSPECTION, TaskStatus, expectError, fund, pda, programs, setupMarket, u64, waitForClock } from "./helpers";
+
+/**
+ * Recurring tasks: the creator prepays rent for every occurrence, anyone
+ * spawns each one once it is due and is refunded that rent, and cancelling
+ * returns what is left.
+ */
+describe("Task Market: recurring tasks", () => {
+  const { taskMarket } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const INTERVAL = 5;
+  const creator = Keypair.generate();
+  const cranker = Keypair.generate();
+  let market: PublicKey;
+  let recurring: PublicKey;
+
+  const params = {
+    title: "Weekly roof survey",
+    description: "Photograph the north roof",
+    robotClass: 0,
+    capabilities: Buffer.from([INSPECTION]),
+    minReputation: 0,
+    reward: new BN(5_000_000),
+    ratePerSecond: new BN(1_000),
+    estimatedDuration: 1_800,
+    priority: 2,
+    expiresIn: new BN(86_400),
+    requiresProof: false,
+  };
+
+  async function createRecurring(interval: number, occurrences: number): Promise<PublicKey> {
+    const { totalRecurring } = await taskMarket.account.market.fetch(market);
+    const address = pda(taskMarket.programId, Buffer.from("recurring"), creator.publicKey.toBuffer(), u64(totalRecurring));
+    await taskMarket.methods
+      .createRecurringTask(params, new BN(interval), occurrences, new BN(0))
+      .accountsPartial({ market, recurring: address, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+    return address;
+  }
+
+  async function spawn(): Promise<PublicKey> {
+    const { totalTasks } = await taskMarket.account.market.fetch(market);
+    const task = pda(taskMarket.programId, Buffer.from("task"), creator.publicKey.toBuffer(), u64(totalTasks));
+    await taskMarket.methods
+      .spawnOccurrence()
+      .accountsPartial({ market, recurring, task, payer: cranker.publicKey })
+      .signers([cranker])
+      .rpc();
+    return task;
+  }
+
+  before(async () => {
+    await fund(creator, cranker);
+    market = await setupMarket();
+  });
+
+  it("rejects a zero interval or occurrence count", async () => {
+    await expectError(createRecurring(0, 2), "InvalidRecurrence");
+    await expectError(createRecurring(INTERVAL, 0), "InvalidRecurrence");
+  });
+
+  it("prepays rent for every occurrence", async () => {
+    recurring = await createRecurring(INTERVAL, 2);
+
+    const account: any = await taskMarket.account.recurringTask.fetch(recurring);
+    expect(account.occurrences).to.equal(2);
+    expect(account.spawned).to.equal(0);
+    const lamports = await connection.getBalance(recurring);
+    expect(lamports).to.be.gte(2 * account.rentPerOccurrence.toNumber());
+  });
+
+  it("spawns a due occurrence for the creator, refunding the spawner's rent", async () => {
+    const before = await connection.getBalance(cranker.publicKey);
+    const task = await spawn();
+    const after = await connection.getBalance(cranker.publicKey);
+
+    const opened: any = await taskMarket.account.task.fetch(task);
+    expect(opened.creator.toBase58()).to.equal(creator.publicKey.toBase58());
+    expect(opened.status).to.equal(TaskStatus.Open);
+    expect(opened.reward.toNumber()).to.equal(params.reward.toNumber());
+    // Only the transaction fee is left out of pocket
+    expect(before - after).to.be.lt(10_000);
+
+    const account: any = await taskMarket.account.recurringTask.fetch(recurring);
+    expect(account.spawned).to.equal(1);
+  });
+
+  it("rejects spawning before the next occurrence is due", async () => {
+    await expectError(spawn(), "OccurrenceNotDue");
+  });
+
+  it("spawns the next occurrence once due", async () => {
+    const { nextDueAt } = await taskMarket.account.recurringTask.fetch(recurring);
+    await waitForClock(nextDueAt);
+    await spawn();
+
+    const account: any = await taskMarket.account.recurringTask.fetch(recurring);
+    expect(account.spawned).to.equal(2);
+  });
+
+  it("rejects spawning past the last occurrence", async () => {
+    await expectError(spawn(), "RecurrenceFinished");
+  });
+
+  it("rejects cancellation by anyone but the creator", async () => {
+    await expectError(
+      taskMarket.methods
+        .cancelRecurringTask()
+        .accountsPartial({ recurring, creator: cranker.publicKey })
+        .signers([cranker])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("returns the unspawned occurrences' rent on cancellation", async () => {
+    const pending = await createRecurring(INTERVAL, 3);
+    const prepaid = await connection.getBalance(pending);
+    const before = await connection.getBalance(creator.publicKey);
+
+    await taskMarket.methods
+      .cancelRecurringTask()
+      .accountsPartial({ recurring: pending, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+
+    expect(await connection.getAccountInfo(pending)).to.equal(null);
+    const after = await connection.getBalance(creator.publicKey);
+    expect(after - before).to.be.gt(prepaid - 10_000);
+  });
+});
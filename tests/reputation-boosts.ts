@@ -0,0 +1,82 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  TokenSetup,
+  drip,
+  expectError,
+  fund,
+  programs,
+  registerRobot,
+  setupToken,
+  stake,
+  unstake,
+} from "./helpers";
+
+/**
+ * Reputation boosts: a staker who operates a robot can boost a position's
+ * rewards by the robot's reputation, and anyone can refresh the boost from
+ * the robot its owner picked.
+ */
+describe("DRONEOS Token: reputation boosts", () => {
+  const { droneosToken, identityRegistry } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const staker = Keypair.generate();
+  const stranger = Keypair.generate();
+  let t: TokenSetup;
+  let stakerToken: PublicKey;
+  let position: PublicKey;
+  let robot: PublicKey;
+
+  function refreshReputationBoost(boostRobot: PublicKey, caller = staker) {
+    return droneosToken.methods
+      .refreshReputationBoost()
+      .accountsPartial({
+        config: t.config,
+        stakeAccount: position,
+        robot: boostRobot,
+        caller: caller.publicKey,
+        identityRegistryProgram: identityRegistry.programId,
+      })
+      .signers([caller])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(staker, stranger);
+    t = await setupToken();
+    stakerToken = await drip(staker, AMOUNT);
+    position = await stake(staker, stakerToken, AMOUNT);
+    robot = await registerRobot(staker);
+  });
+
+  it("rejects a robot the staker doesn't operate", async () => {
+    await expectError(refreshReputationBoost(await registerRobot(stranger)), "RobotNotOperatedByStaker");
+  });
+
+  it("rejects anyone but the owner picking the robot", async () => {
+    await expectError(refreshReputationBoost(robot, stranger), "Unauthorized");
+  });
+
+  it("boosts by nothing at the starting reputation", async () => {
+    await refreshReputationBoost(robot);
+
+    const boosted: any = await droneosToken.account.stakeAccount.fetch(position);
+    expect(boosted.boostRobot.toBase58()).to.equal(robot.toBase58());
+    expect(boosted.reputationBoostBps).to.equal(0);
+  });
+
+  it("lets anyone refresh the boost from the picked robot", async () => {
+    const before: any = await droneosToken.account.stakeAccount.fetch(position);
+    await refreshReputationBoost(robot, stranger);
+
+    const after: any = await droneosToken.account.stakeAccount.fetch(position);
+    expect(after.boostRobot.toBase58()).to.equal(robot.toBase58());
+    expect(after.lastClaimAt.toNumber()).to.be.gte(before.lastClaimAt.toNumber());
+  });
+
+  it("rejects boosting an empty position", async () => {
+    await unstake(staker, stakerToken, position);
+    await expectError(refreshReputationBoost(robot), "InsufficientStake");
+  });
+});
@@ -0,0 +1,69 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { TokenSetup, expectError, fund, programs, setupToken, stakedOperator } from "./helpers";
+
+/**
+ * Reputation recovery: an operator's reputation climbs back a little each
+ * day once they've gone the recovery delay without a slash, up to a ceiling.
+ */
+describe("DRONEOS Token: operator reputation recovery", () => {
+  const { droneosToken } = programs();
+  const authority = anchor.getProvider().publicKey!;
+
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let operatorStake: PublicKey;
+  let current: any;
+
+  function setReputationRecovery(delayDays: number, ratePerDay: number, ceiling: number, signer?: Keypair) {
+    return droneosToken.methods
+      .setReputationRecovery(delayDays, ratePerDay, ceiling)
+      .accountsPartial({ config: t.config, authority: signer?.publicKey ?? authority })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    ({ operatorStake } = await stakedOperator());
+    current = await droneosToken.account.tokenConfig.fetch(t.config);
+  });
+
+  after(async () => {
+    // The token config is shared with other test files
+    await setReputationRecovery(
+      current.reputationRecoveryDelay.toNumber() / 86_400,
+      current.reputationRecoveryRate,
+      current.reputationCeiling
+    );
+  });
+
+  it("rejects recovery settings by anyone but the authority", async () => {
+    await expectError(setReputationRecovery(0, 100, 5000, intruder), "Unauthorized");
+  });
+
+  it("rejects a rate or ceiling over 100%", async () => {
+    await expectError(setReputationRecovery(0, 10_001, 5000), "InvalidReputationRecovery");
+    await expectError(setReputationRecovery(0, 100, 10_001), "InvalidReputationRecovery");
+  });
+
+  it("stores the recovery delay in seconds", async () => {
+    await setReputationRecovery(1, 100, 6000);
+
+    const config: any = await droneosToken.account.tokenConfig.fetch(t.config);
+    expect(config.reputationRecoveryDelay.toNumber()).to.equal(86_400);
+    expect(config.reputationRecoveryRate).to.equal(100);
+    expect(config.reputationCeiling).to.equal(6000);
+  });
+
+  it("rejects recovering before a clean day has passed", async () => {
+    // Even without a delay, the operator is younger than a day
+    await setReputationRecovery(0, 100, 6000);
+    await expectError(
+      droneosToken.methods.recoverReputation().accountsPartial({ config: t.config, operatorStake }).rpc(),
+      "NothingToRecover"
+    );
+  });
+});
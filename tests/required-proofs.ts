@@ -0,0 +1,62 @@
+import { createHash } from "crypto";
+import { expect } from "chai";
+import {
+  TaskStatus,
+  acceptBid,
+  completeTask,
+  expectError,
+  openBidTask,
+  programs,
+  settlementAccounts,
+  startTask,
+  submitCompletionProof,
+  verifyCompletion,
+  verifyProof,
+} from "./helpers";
+
+/**
+ * Required proofs: a task created requiring proof is only approved with a
+ * verified oracle proof of its robot, taken since the task started.
+ */
+describe("Task Market: required completion proofs", () => {
+  const { taskMarket } = programs();
+
+  const deliverable = createHash("sha256").update("pylons 3-7: no cracks found").digest();
+
+  /** A task requiring proof, completed by its robot and awaiting verification */
+  async function pendingVerification() {
+    const a = await openBidTask({ requiresProof: true });
+    await acceptBid(a).signers([a.creator]).rpc();
+    await startTask(a).rpc();
+    await completeTask(a, deliverable).rpc();
+    return { a, stream: await settlementAccounts(a) };
+  }
+
+  it("rejects approval without a proof", async () => {
+    const { a, stream } = await pendingVerification();
+    await expectError(verifyCompletion(a, stream).rpc(), "ProofRequired");
+  });
+
+  it("rejects a proof the oracle hasn't verified", async () => {
+    const { a, stream } = await pendingVerification();
+    const completionProof = await submitCompletionProof(a, deliverable);
+    await expectError(verifyCompletion(a, stream, true, { completionProof }).rpc(), "ProofNotVerified");
+  });
+
+  it("rejects another task's proof", async () => {
+    const { a, stream } = await pendingVerification();
+    const other = await pendingVerification();
+    const completionProof = await submitCompletionProof(other.a, deliverable);
+    await verifyProof(completionProof);
+    await expectError(verifyCompletion(a, stream, true, { completionProof }).rpc(), "ProofMismatch");
+  });
+
+  it("approves a completion with a verified proof", async () => {
+    const { a, stream } = await pendingVerification();
+    const completionProof = await submitCompletionProof(a, deliverable);
+    await verifyProof(completionProof);
+    await verifyCompletion(a, stream, true, { completionProof }).rpc();
+
+    expect((await taskMarket.account.task.fetch(a.task)).status).to.equal(TaskStatus.Completed);
+  });
+});
@@ -0,0 +1,94 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Assignment,
+  acceptBid,
+  createTask,
+  expectError,
+  fund,
+  programs,
+  registerRobot,
+  setupMarket,
+  submitBid,
+  tokenFor,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Reward escalation: a creator can raise an unfilled task's reward and
+ * accepted rate step by step while it stays open, dutch-auction style, the
+ * escalated terms fixed when a bid is accepted.
+ */
+describe("Task Market: reward escalation", () => {
+  const { taskMarket } = programs();
+
+  const RATE = 10_000;
+  const REWARD = 50_000_000;
+  const creator = Keypair.generate();
+  const operator = Keypair.generate();
+  let robot: PublicKey;
+  let tokens: { mint: PublicKey; token: PublicKey; treasury: PublicKey };
+
+  function setEscalation(task: PublicKey, stepBps: number, interval: number, capBps: number, signer = creator) {
+    return taskMarket.methods
+      .setEscalation(stepBps, interval, capBps)
+      .accountsPartial({ task, creator: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  /** A task with a bid 10% above its rate */
+  async function overbidTask(): Promise<Assignment> {
+    const task = await createTask(creator, { reward: REWARD, rate: RATE });
+    const bid = await submitBid(task, creator.publicKey, robot, operator, (RATE * 11) / 10);
+    const { mint, token: creatorToken, treasury } = tokens;
+    return { task, bid, robot, creator, operator, mint, creatorToken, treasury };
+  }
+
+  before(async () => {
+    await fund(creator, operator);
+    await setupMarket();
+    tokens = await tokenFor(creator, 1_000_000_000);
+    robot = await registerRobot(operator);
+  });
+
+  it("rejects escalation set by anyone but the creator", async () => {
+    const task = await createTask(creator);
+    await expectError(setEscalation(task, 500, 60, 1_000, operator), "Unauthorized");
+  });
+
+  it("rejects a schedule without an interval or with a cap below its step", async () => {
+    const task = await createTask(creator);
+    await expectError(setEscalation(task, 500, 0, 1_000), "InvalidEscalation");
+    await expectError(setEscalation(task, 500, 60, 400), "InvalidEscalation");
+  });
+
+  it("turns escalation off with a zero step", async () => {
+    const task = await createTask(creator);
+    await setEscalation(task, 500, 60, 1_000);
+    await setEscalation(task, 0, 0, 0);
+
+    const account: any = await taskMarket.account.task.fetch(task);
+    expect(account.escalationStepBps).to.equal(0);
+  });
+
+  it("rejects bids above the rate escalated so far", async () => {
+    const a = await overbidTask();
+    await setEscalation(a.task, 1_000, 3_600, 1_000);
+
+    await expectError(acceptBid(a).signers([creator]).rpc(), "BidAboveEscalatedRate");
+  });
+
+  it("fixes the capped escalated reward on acceptance", async () => {
+    const a = await overbidTask();
+    await setEscalation(a.task, 500, 1, 1_000);
+
+    const { createdAt } = await taskMarket.account.task.fetch(a.task);
+    await waitForClock(new BN(createdAt).addn(2));
+    await acceptBid(a).signers([creator]).rpc();
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.reward.toNumber()).to.equal((REWARD * 11) / 10);
+  });
+});
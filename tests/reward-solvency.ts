@@ -0,0 +1,111 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  TokenSetup,
+  drip,
+  expectError,
+  fund,
+  pda,
+  programs,
+  setupToken,
+  stake,
+  unstake,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Reward solvency: claims are paid only from the config's rewards vault
+ * and fail with an explicit error when it or the epoch's emission budget
+ * can't pay, while unstakes still return the principal and settle the
+ * unpaid rewards on the position.
+ */
+describe("DRONEOS Token: reward solvency", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const AMOUNT = 100 * 1_000_000;
+  const staker = Keypair.generate();
+  let t: TokenSetup;
+  let stakerToken: PublicKey;
+  let position: PublicKey;
+  let foreignVault: PublicKey;
+
+  function claimRewards(rewardsVault = t.rewardsVault) {
+    return droneosToken.methods
+      .claimRewards()
+      .accountsPartial({
+        config: t.config,
+        stakeAccount: position,
+        emissions: pda(droneosToken.programId, Buffer.from("emissions")),
+        rewardsVault,
+        userToken: stakerToken,
+        user: staker.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([staker])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(staker);
+    t = await setupToken();
+    stakerToken = await drip(staker, 2 * AMOUNT);
+    position = await stake(staker, stakerToken, AMOUNT);
+    foreignVault = await createAccount(
+      connection,
+      staker,
+      t.mint,
+      t.config,
+      Keypair.generate(),
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    // Long enough at the base APY for rewards to be pending
+    const { stakedAt } = await droneosToken.account.stakeAccount.fetch(position);
+    await waitForClock(stakedAt.addn(5));
+  });
+
+  it("rejects a claim from a vault other than the config's rewards vault", async () => {
+    // Config-owned, but its balance says nothing about what the rewards vault can pay
+    await expectError(claimRewards(foreignVault), "InvalidVault");
+  });
+
+  it("rejects a claim once the emission schedule has nothing left to pay", async () => {
+    const funded = await balance(t.rewardsVault);
+    await droneosToken.methods
+      .fundRewardsVault(new BN(1_000_000))
+      .accountsPartial({
+        config: t.config,
+        rewardsVault: t.rewardsVault,
+        funderToken: stakerToken,
+        funder: staker.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([staker])
+      .rpc();
+    expect((await balance(t.rewardsVault)) - funded).to.equal(1_000_000);
+
+    // The test schedule's epochs are all over
+    await expectError(claimRewards(), "EmissionBudgetExhausted");
+  });
+
+  it("returns the principal on unstake, settling the unpaid rewards", async () => {
+    const before = await balance(stakerToken);
+    await unstake(staker, stakerToken, position);
+
+    expect((await balance(stakerToken)) - before).to.equal(AMOUNT);
+    const after: any = await droneosToken.account.stakeAccount.fetch(position);
+    expect(after.amount.toNumber()).to.equal(0);
+    expect(after.settledRewards.toNumber()).to.be.gt(0);
+  });
+});
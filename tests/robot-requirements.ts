@@ -0,0 +1,68 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  TaskOptions,
+  createTask,
+  expectError,
+  fund,
+  programs,
+  registerRobot,
+  setupMarket,
+  submitBid,
+} from "./helpers";
+
+/**
+ * Robot requirements: a robot can only bid on a task if identity-registry
+ * confirms it is active, of the task's class, certified for each of its
+ * capabilities and reputable enough.
+ */
+describe("Task Market: robot requirements", () => {
+  const { taskMarket, identityRegistry } = programs();
+
+  // Robots register with 50.00% reputation
+  const REPUTATION = 5_000;
+  const creator = Keypair.generate();
+  const operator = Keypair.generate();
+  let robot: PublicKey;
+
+  async function bidOn(options: TaskOptions, bidder = robot) {
+    const task = await createTask(creator, options);
+    await submitBid(task, creator.publicKey, bidder, operator);
+    return task;
+  }
+
+  before(async () => {
+    await fund(creator, operator);
+    await setupMarket();
+    robot = await registerRobot(operator);
+  });
+
+  it("rejects robots of another class", async () => {
+    // Ground robots only
+    await expectError(bidOn({ robotClass: 1 }), "WrongRobotClass");
+  });
+
+  it("rejects robots missing a required capability", async () => {
+    // Delivery, which the robot isn't certified for
+    await expectError(bidOn({ capabilities: [0] }), "CapabilityNotFound");
+  });
+
+  it("rejects robots below the minimum reputation", async () => {
+    await expectError(bidOn({ minReputation: REPUTATION + 1 }), "ReputationTooLow");
+  });
+
+  it("rejects robots not active", async () => {
+    const idle = await registerRobot(operator);
+    await identityRegistry.methods
+      .updateStatus({ idle: {} })
+      .accountsPartial({ robot: idle, operator: operator.publicKey })
+      .signers([operator])
+      .rpc();
+    await expectError(bidOn({}, idle), "RobotNotActive");
+  });
+
+  it("accepts bids from robots meeting every requirement", async () => {
+    const task = await bidOn({ minReputation: REPUTATION });
+    expect((await taskMarket.account.task.fetch(task)).bidsCount).to.equal(1);
+  });
+});
@@ -0,0 +1,84 @@
+import { Keypair } from "@solana/web3.js";
+import { createHash } from "crypto";
+import { expect } from "chai";
+import {
+  Assignment,
+  acceptBid,
+  completeTask,
+  createTask,
+  expectError,
+  fund,
+  openBidTask,
+  programs,
+  settlementAccounts,
+  startTask,
+  submitBid,
+  verifyCompletion,
+} from "./helpers";
+
+/**
+ * Robot status: task-market marks a robot busy when assigned a task and
+ * available once the task ends, so it can't take on overlapping tasks, and
+ * only task-market can do either.
+ */
+describe("Identity Registry: robot status on assignment", () => {
+  const { identityRegistry } = programs();
+
+  let a: Assignment;
+  let overlapping: Assignment;
+
+  function updateStatus(status: object) {
+    return identityRegistry.methods
+      .updateStatus(status)
+      .accountsPartial({ robot: a.robot, operator: a.operator.publicKey })
+      .signers([a.operator])
+      .rpc();
+  }
+
+  async function robotStatus() {
+    return (await identityRegistry.account.robot.fetch(a.robot)).status;
+  }
+
+  before(async () => {
+    a = await openBidTask();
+    // The same robot bids on a second task of its creator's
+    const task = await createTask(a.creator);
+    overlapping = { ...a, task, bid: await submitBid(task, a.creator.publicKey, a.robot, a.operator) };
+  });
+
+  it("rejects marking a robot busy by anyone but task-market", async () => {
+    await expectError(updateStatus({ busy: {} }), "InvalidStatusTransition");
+
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      identityRegistry.methods
+        .beginRobotTask()
+        .accountsPartial({ robot: a.robot, market: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "ConstraintSeeds"
+    );
+  });
+
+  it("marks the robot busy when assigned", async () => {
+    await acceptBid(a).signers([a.creator]).rpc();
+    expect(await robotStatus()).to.have.property("busy");
+  });
+
+  it("rejects status changes by the operator while busy", async () => {
+    await expectError(updateStatus({ idle: {} }), "RobotBusy");
+  });
+
+  it("rejects assigning a busy robot another task", async () => {
+    await expectError(acceptBid(overlapping).signers([a.creator]).rpc(), "RobotNotAvailable");
+  });
+
+  it("makes the robot available once its task is approved", async () => {
+    await startTask(a).rpc();
+    await completeTask(a, createHash("sha256").update("pylons 3-7").digest()).rpc();
+    await verifyCompletion(a, await settlementAccounts(a)).rpc();
+
+    expect(await robotStatus()).to.have.property("available");
+  });
+});
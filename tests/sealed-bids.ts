@@ -0,0 +1,189 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { createHash } from "crypto";
+import { expect } from "chai";
+import {
+  Assignment,
+  TaskStatus,
+  acceptBid,
+  bidAddress,
+  bidderAccounts,
+  createTask,
+  expectError,
+  fund,
+  programs,
+  registerRobot,
+  setupMarket,
+  submitBid,
+  tokenFor,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Commit-reveal bidding: bids are sealed as `sha256(rate LE || salt)` during
+ * the commit window, opened during the reveal window and only accepted once
+ * it has closed.
+ */
+describe("Task Market: sealed bids", () => {
+  const { taskMarket } = programs();
+
+  const COMMIT_SECONDS = 6;
+  const REVEAL_SECONDS = 6;
+  const RATE = 9_000;
+
+  const salt = [...Keypair.generate().publicKey.toBuffer()];
+  let sealed: Assignment;
+  let commitEndsAt: BN;
+  let revealEndsAt: BN;
+
+  function commitment(rate: number, salt: number[]): number[] {
+    const hash = createHash("sha256").update(new BN(rate).toArrayLike(Buffer, "le", 8)).update(Buffer.from(salt));
+    return [...hash.digest()];
+  }
+
+  function commitBid(task: PublicKey, creator: PublicKey, robot: PublicKey, operator: Keypair, sealedRate: number[]) {
+    return taskMarket.methods
+      .commitBid(sealedRate, 3_600, "Sealed", new BN(3_600), [])
+      .accountsPartial({ ...bidderAccounts(task, creator, operator.publicKey), bid: bidAddress(task, robot), robot })
+      .signers([operator])
+      .rpc();
+  }
+
+  function revealBid(a: Assignment, rate: number, revealSalt: number[], operator = a.operator) {
+    return taskMarket.methods
+      .revealBid(new BN(rate), revealSalt)
+      .accountsPartial({ task: a.task, bid: a.bid, operator: operator.publicKey })
+      .signers([operator])
+      .rpc();
+  }
+
+  before(async () => {
+    const creator = Keypair.generate();
+    const operator = Keypair.generate();
+    await fund(creator, operator);
+    await setupMarket();
+
+    const { mint, token: creatorToken, treasury } = await tokenFor(creator, 1_000_000_000);
+    const task = await createTask(creator);
+    const robot = await registerRobot(operator);
+    sealed = { task, bid: bidAddress(task, robot), robot, creator, operator, mint, creatorToken, treasury };
+  });
+
+  it("rejects enabling sealed bidding by anyone but the creator", async () => {
+    await expectError(
+      taskMarket.methods
+        .enableSealedBidding(new BN(COMMIT_SECONDS), new BN(REVEAL_SECONDS))
+        .accountsPartial({ task: sealed.task, creator: sealed.operator.publicKey })
+        .signers([sealed.operator])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("enables sealed bidding on an open task", async () => {
+    await taskMarket.methods
+      .enableSealedBidding(new BN(COMMIT_SECONDS), new BN(REVEAL_SECONDS))
+      .accountsPartial({ task: sealed.task, creator: sealed.creator.publicKey })
+      .signers([sealed.creator])
+      .rpc();
+
+    const task: any = await taskMarket.account.task.fetch(sealed.task);
+    commitEndsAt = task.commitEndsAt;
+    revealEndsAt = task.revealEndsAt;
+    expect(revealEndsAt.sub(commitEndsAt).toNumber()).to.equal(REVEAL_SECONDS);
+  });
+
+  it("rejects open bids on a sealed task", async () => {
+    await expectError(
+      submitBid(sealed.task, sealed.creator.publicKey, sealed.robot, sealed.operator),
+      "SealedBidsOnly"
+    );
+  });
+
+  it("commits a sealed bid without revealing its rate", async () => {
+    await commitBid(sealed.task, sealed.creator.publicKey, sealed.robot, sealed.operator, commitment(RATE, salt));
+
+    const bid: any = await taskMarket.account.bid.fetch(sealed.bid);
+    expect(bid.status).to.have.property("committed");
+    expect(bid.proposedRate.toNumber()).to.equal(0);
+    const task: any = await taskMarket.account.task.fetch(sealed.task);
+    expect(task.bidsCount).to.equal(1);
+  });
+
+  it("rejects changing the bid windows once bids are in", async () => {
+    await expectError(
+      taskMarket.methods
+        .enableSealedBidding(new BN(COMMIT_SECONDS), new BN(REVEAL_SECONDS))
+        .accountsPartial({ task: sealed.task, creator: sealed.creator.publicKey })
+        .signers([sealed.creator])
+        .rpc(),
+      "TaskHasBids"
+    );
+  });
+
+  it("rejects reveals during the commit window", async () => {
+    await expectError(revealBid(sealed, RATE, salt), "NotInRevealWindow");
+  });
+
+  it("rejects accepting a bid before it is revealed", async () => {
+    await expectError(acceptBid(sealed).signers([sealed.creator]).rpc(), "BidNotPending");
+  });
+
+  it("rejects commits after the commit window", async () => {
+    const late = Keypair.generate();
+    await fund(late);
+    const lateRobot = await registerRobot(late);
+
+    await waitForClock(commitEndsAt);
+    await expectError(
+      commitBid(sealed.task, sealed.creator.publicKey, lateRobot, late, commitment(RATE, salt)),
+      "CommitWindowClosed"
+    );
+  });
+
+  it("rejects a reveal that doesn't open the commitment", async () => {
+    await expectError(revealBid(sealed, RATE + 1, salt), "CommitmentMismatch");
+    await expectError(revealBid(sealed, RATE, [...Keypair.generate().publicKey.toBuffer()]), "CommitmentMismatch");
+  });
+
+  it("rejects reveals by anyone but the bidder", async () => {
+    const other = Keypair.generate();
+    await fund(other);
+    await expectError(revealBid(sealed, RATE, salt, other), "Unauthorized");
+  });
+
+  it("reveals the committed rate", async () => {
+    await revealBid(sealed, RATE, salt);
+
+    const bid: any = await taskMarket.account.bid.fetch(sealed.bid);
+    expect(bid.status).to.have.property("pending");
+    expect(bid.proposedRate.toNumber()).to.equal(RATE);
+  });
+
+  it("rejects accepting while other bids can still be revealed", async () => {
+    await expectError(acceptBid(sealed).signers([sealed.creator]).rpc(), "RevealWindowOpen");
+  });
+
+  it("accepts a revealed bid once the reveal window closes", async () => {
+    await waitForClock(revealEndsAt);
+    await acceptBid(sealed).signers([sealed.creator]).rpc();
+
+    const task: any = await taskMarket.account.task.fetch(sealed.task);
+    expect(task.status).to.equal(TaskStatus.Assigned);
+    expect(task.assignedRobot.toBase58()).to.equal(sealed.robot.toBase58());
+    expect(task.ratePerSecond.toNumber()).to.equal(RATE);
+  });
+
+  it("rejects commits on tasks without sealed bidding", async () => {
+    const creator = Keypair.generate();
+    const operator = Keypair.generate();
+    await fund(creator, operator);
+    const task = await createTask(creator);
+    const robot = await registerRobot(operator);
+
+    await expectError(
+      commitBid(task, creator.publicKey, robot, operator, commitment(RATE, salt)),
+      "NotSealedBidding"
+    );
+  });
+});
@@ -0,0 +1,78 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  acceptStream,
+  expectError,
+  openStream,
+  overdrawStream,
+  programs,
+  startStream,
+  terminateStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Security deposits: held in escrow apart from the streaming balance, a
+ * deposit goes back to the payer when the stream ends cleanly and to the
+ * payee when the payer lets it lapse past its grace window.
+ */
+describe("Payment Streams: security deposits", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const DEPOSIT = 50_000;
+
+  async function runningStream() {
+    const s = await openStream({ securityDeposit: DEPOSIT, gracePeriod: 1 });
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    return s;
+  }
+
+  it("rejects a deposit the payer can't cover", async () => {
+    await expectError(openStream({ securityDeposit: 2_000_000_000 }), "InsufficientFunds");
+  });
+
+  it("holds the deposit apart from the streaming balance", async () => {
+    const s = await runningStream();
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const escrowed = (await getAccount(connection, s.escrow)).amount;
+    expect(account.securityDeposit.toNumber()).to.equal(DEPOSIT);
+    expect(Number(escrowed)).to.equal(account.escrowBalance.toNumber() + DEPOSIT);
+  });
+
+  it("refunds the deposit when the stream ends cleanly", async () => {
+    const s = await runningStream();
+    const before: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const payerBefore = (await getAccount(connection, s.payerToken)).amount;
+    await terminateStream(s).rpc();
+
+    const after: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const payerAfter = (await getAccount(connection, s.payerToken)).amount;
+    const unstreamed = before.escrowBalance.sub(after.totalPaid.sub(before.totalPaid)).toNumber();
+    expect(Number(payerAfter - payerBefore)).to.equal(unstreamed + DEPOSIT);
+    expect(after.securityDeposit.toNumber()).to.equal(0);
+  });
+
+  it("forfeits the deposit to the payee when the payer lets the stream lapse", async () => {
+    const s = await runningStream();
+    await overdrawStream(s);
+    const { graceStartedAt, gracePeriod } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(graceStartedAt).add(new BN(gracePeriod)));
+
+    const payerBefore = (await getAccount(connection, s.payerToken)).amount;
+    const payeeBefore = (await getAccount(connection, s.payeeToken)).amount;
+    await tick(s).rpc();
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.status).to.have.property("completed");
+    expect(account.securityDeposit.toNumber()).to.equal(0);
+    expect(Number((await getAccount(connection, s.escrow)).amount)).to.equal(0);
+    expect((await getAccount(connection, s.payerToken)).amount).to.equal(payerBefore);
+    expect(Number((await getAccount(connection, s.payeeToken)).amount - payeeBefore)).to.be.gte(DEPOSIT);
+  });
+});
@@ -0,0 +1,95 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Assignment,
+  acceptBid,
+  bidderAccounts,
+  bondForfeitAccounts,
+  drip,
+  expectError,
+  fund,
+  lateTask,
+  openBidTask,
+  pda,
+  programs,
+  setupToken,
+  startTask,
+} from "./helpers";
+
+/**
+ * SLA deadlines: an assigned task is due its accepted bid's duration plus
+ * the market's buffer after it starts, and anyone can flag it late once
+ * past that, once.
+ */
+describe("Task Market: SLA deadlines", () => {
+  const { taskMarket } = programs();
+  const authority = anchor.getProvider().publicKey!;
+  const market = pda(taskMarket.programId, Buffer.from("market"));
+
+  function setSlaTerms(buffer: number, penaltyBps: number) {
+    return taskMarket.methods.setSlaTerms(new BN(buffer), penaltyBps).accountsPartial({ market, authority }).rpc();
+  }
+
+  async function flagLate(a: Assignment) {
+    const t = await setupToken();
+    return taskMarket.methods
+      .flagLate()
+      .accountsPartial({
+        market,
+        task: a.task,
+        operatorStake: bidderAccounts(a.task, a.creator.publicKey, a.operator.publicKey).operatorStake,
+        bond: bondForfeitAccounts(t, await drip(a.creator)),
+      })
+      .rpc();
+  }
+
+  it("rejects SLA terms set by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      taskMarket.methods
+        .setSlaTerms(new BN(0), 0)
+        .accountsPartial({ market, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects a negative buffer or a penalty above 100%", async () => {
+    await expectError(setSlaTerms(-1, 0), "InvalidSlaTerms");
+    await expectError(setSlaTerms(0, 10_001), "InvalidSlaTerms");
+  });
+
+  it("sets the SLA window from the accepted bid", async () => {
+    const { slaBufferSeconds } = await taskMarket.account.market.fetch(market);
+    const a = await openBidTask({ duration: 1_800 });
+    await acceptBid(a).signers([a.creator]).rpc();
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.slaWindow.toNumber()).to.equal(1_800 + slaBufferSeconds.toNumber());
+  });
+
+  it("rejects flagging a task that hasn't started", async () => {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await expectError(flagLate(a), "TaskNotInProgress");
+  });
+
+  it("rejects flagging a task within its deadline", async () => {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await startTask(a).rpc();
+    await expectError(flagLate(a), "TaskNotLate");
+  });
+
+  it("flags a task past its deadline once", async () => {
+    const late = await lateTask();
+
+    const task: any = await taskMarket.account.task.fetch(late.task);
+    expect(task.slaBreached).to.equal(1);
+    await expectError(flagLate(late), "AlreadyFlaggedLate");
+  });
+});
@@ -0,0 +1,160 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  LateTask,
+  OracleSetup,
+  TokenSetup,
+  abortLateTask,
+  drip,
+  expectError,
+  fund,
+  lateTask,
+  pda,
+  programs,
+  setupOracle,
+  setupToken,
+  u64,
+} from "./helpers";
+
+/**
+ * Slash appeals: within three days of a slash its operator can post a bond
+ * and appeal, arguing the case as an oracle-verifier dispute whose outcome
+ * refunds the slash or forfeits the bond.
+ */
+describe("DRONEOS Token: slash appeals", () => {
+  const { droneosToken, oracleVerifier } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const BOND = 100 * 1_000_000;
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let o: OracleSetup;
+  let late: LateTask;
+  let operatorToken: PublicKey;
+  let foreignVault: PublicKey;
+  let appeal: PublicKey;
+  let dispute: PublicKey;
+
+  function appealSlash(vault = t.appealVault, operator = late.operator) {
+    return droneosToken.methods
+      .appealSlash()
+      .accountsPartial({
+        config: t.config,
+        operatorStake: late.operatorStake,
+        appeal,
+        appealVault: vault,
+        operatorToken,
+        operator: operator.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([operator])
+      .rpc();
+  }
+
+  function openSlashAppeal(operator = late.operator) {
+    return oracleVerifier.methods
+      .openSlashAppeal("The robot was grounded by weather", "https://evidence.droneos.dev/weather")
+      .accountsPartial({ verifier: o.verifier, appeal, dispute, operator: operator.publicKey })
+      .signers([operator])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    o = await setupOracle();
+    late = await lateTask();
+    await abortLateTask(late);
+
+    const { lastSlashAt } = await droneosToken.account.operatorStake.fetch(late.operatorStake);
+    appeal = pda(droneosToken.programId, Buffer.from("appeal"), late.operator.publicKey.toBuffer(), u64(lastSlashAt!));
+    dispute = pda(
+      oracleVerifier.programId,
+      Buffer.from("dispute"),
+      appeal.toBuffer(),
+      late.operator.publicKey.toBuffer()
+    );
+    operatorToken = await drip(late.operator, BOND);
+    foreignVault = await createAccount(
+      connection,
+      late.operator,
+      t.mint,
+      t.config,
+      Keypair.generate(),
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+  });
+
+  it("rejects appealing another operator's slash", async () => {
+    await expectError(appealSlash(t.appealVault, intruder), "ConstraintSeeds");
+  });
+
+  it("rejects a bond paid anywhere but the config's appeal vault", async () => {
+    await expectError(appealSlash(operatorToken), "InvalidVault");
+    await expectError(appealSlash(foreignVault), "InvalidVault");
+  });
+
+  it("opens an appeal of the latest slash for the bond", async () => {
+    const stake: any = await droneosToken.account.operatorStake.fetch(late.operatorStake);
+    const vaultBefore = await balance(t.appealVault);
+    await appealSlash();
+
+    const opened: any = await droneosToken.account.slashAppeal.fetch(appeal);
+    expect(opened.status).to.have.property("open");
+    expect(opened.operator.toBase58()).to.equal(late.operator.publicKey.toBase58());
+    expect(opened.slashedAt.toNumber()).to.equal(stake.lastSlashAt.toNumber());
+    expect(opened.amount.toNumber()).to.equal(stake.lastSlashAmount.toNumber());
+    expect(opened.bond.toNumber()).to.equal(BOND);
+    expect((await balance(t.appealVault)) - vaultBefore).to.equal(BOND);
+    expect(await balance(operatorToken)).to.equal(0);
+  });
+
+  it("rejects appealing the same slash twice", async () => {
+    await expectError(appealSlash(), "already in use");
+  });
+
+  it("rejects arguing someone else's appeal", async () => {
+    await expectError(openSlashAppeal(intruder), "ConstraintSeeds");
+  });
+
+  it("opens the appeal's dispute with the oracle verifier", async () => {
+    await openSlashAppeal();
+
+    const opened: any = await oracleVerifier.account.dispute.fetch(dispute);
+    expect(opened.status).to.have.property("open");
+    expect(opened.proof.toBase58()).to.equal(appeal.toBase58());
+    expect(opened.challenger.toBase58()).to.equal(late.operator.publicKey.toBase58());
+  });
+
+  it("rejects settling the appeal before its vote ends", async () => {
+    await expectError(
+      oracleVerifier.methods
+        .resolveSlashAppeal()
+        .accountsPartial({
+          dispute,
+          verifier: o.verifier,
+          appeal,
+          tokenConfig: t.config,
+          operatorStake: late.operatorStake,
+          treasury: t.treasury,
+          appealVault: t.appealVault,
+          operatorVault: t.operatorVault,
+          operatorToken,
+          tokenMint: t.mint,
+          tokenEventAuthority: pda(droneosToken.programId, Buffer.from("__event_authority")),
+          droneosTokenProgram: droneosToken.programId,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc(),
+      "VotingPeriodNotEnded"
+    );
+  });
+});
@@ -0,0 +1,59 @@
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { LateTask, abortLateTask, expectError, lateTask, pda, programs, u64 } from "./helpers";
+
+/**
+ * Slash history: every slash of an operator is kept in its own record,
+ * numbered from zero, so an operator's full history can be enumerated from
+ * their slash count.
+ */
+describe("DRONEOS Token: slash history", () => {
+  const { droneosToken, taskMarket } = programs();
+
+  let late: LateTask;
+
+  function slashRecord(index: number) {
+    return pda(droneosToken.programId, Buffer.from("slash"), late.operator.publicKey.toBuffer(), u64(index));
+  }
+
+  before(async () => {
+    late = await lateTask();
+  });
+
+  it("rejects a record out of the operator's sequence", async () => {
+    const { slashCount } = await droneosToken.account.operatorStake.fetch(late.operatorStake);
+    await expectError(
+      abortLateTask(late, { slashRecord: slashRecord(slashCount.toNumber() + 1) }),
+      "ConstraintSeeds"
+    );
+  });
+
+  it("records the slash under the next index", async () => {
+    const before: any = await droneosToken.account.operatorStake.fetch(late.operatorStake);
+    await abortLateTask(late);
+
+    const after: any = await droneosToken.account.operatorStake.fetch(late.operatorStake);
+    expect(after.slashCount.toNumber()).to.equal(before.slashCount.toNumber() + 1);
+
+    const record: any = await droneosToken.account.slashRecord.fetch(slashRecord(before.slashCount.toNumber()));
+    expect(record.operator.toBase58()).to.equal(late.operator.publicKey.toBase58());
+    expect(record.index.toNumber()).to.equal(before.slashCount.toNumber());
+    expect(record.amount.toNumber()).to.equal(after.lastSlashAmount.toNumber());
+    expect(record.slashedAt.toNumber()).to.equal(after.lastSlashAt.toNumber());
+    expect(record.task.toBase58()).to.equal(late.task.toBase58());
+    expect(record.slasher.toBase58()).to.equal(taskMarket.programId.toBase58());
+    expect(record.reason).to.equal("Missed the deadline");
+  });
+
+  it("keeps one record per slash, enumerable from the slash count", async () => {
+    const { slashCount } = await droneosToken.account.operatorStake.fetch(late.operatorStake);
+    const addresses: PublicKey[] = [...Array(slashCount.toNumber()).keys()].map(slashRecord);
+    const records: any[] = await droneosToken.account.slashRecord.fetchMultiple(addresses);
+
+    records.forEach((record, index) => {
+      expect(record).to.not.equal(null);
+      expect(record.index.toNumber()).to.equal(index);
+      expect(record.operator.toBase58()).to.equal(late.operator.publicKey.toBase58());
+    });
+  });
+});
@@ -0,0 +1,146 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  LateTask,
+  TaskStatus,
+  TokenSetup,
+  abortLateTask,
+  expectError,
+  fund,
+  lateTask,
+  pda,
+  programs,
+  setupToken,
+  u64,
+} from "./helpers";
+
+/**
+ * Slash routing: only registered slasher programs, signing with their
+ * `["slasher"]` PDA, can slash an operator, and each slash is split between
+ * the insurance pool and the treasury by the insurance share.
+ */
+describe("DRONEOS Token: slash routing", () => {
+  const { taskMarket, droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const INSURANCE_SHARE = 4_000;
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let late: LateTask;
+  let originalShare: number;
+
+  function setInsuranceShare(bps: number) {
+    return droneosToken.methods.setInsuranceShare(bps).accountsPartial({ config: t.config, authority }).rpc();
+  }
+
+  async function slashDirectly(slasherProgram: PublicKey) {
+    const { slashCount } = await droneosToken.account.operatorStake.fetch(late.operatorStake);
+    return droneosToken.methods
+      .slashOperator(new BN(1_000_000), "Unauthorized slash", null)
+      .accountsPartial({
+        config: t.config,
+        operatorStake: late.operatorStake,
+        slashRecord: pda(
+          droneosToken.programId,
+          Buffer.from("slash"),
+          late.operator.publicKey.toBuffer(),
+          u64(slashCount)
+        ),
+        operatorVault: t.operatorVault,
+        treasury: t.treasury,
+        insurancePool: t.insurancePool,
+        insuranceVault: t.insuranceVault,
+        slasherProgram,
+        authority: intruder.publicKey,
+        mint: t.mint,
+        payer: intruder.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([intruder])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    late = await lateTask();
+    ({ insuranceShareBps: originalShare } = await droneosToken.account.tokenConfig.fetch(t.config));
+  });
+
+  after(async () => {
+    // The token config is shared with other test files
+    await setInsuranceShare(originalShare);
+  });
+
+  it("rejects slasher registration by anyone but the authority", async () => {
+    await expectError(
+      droneosToken.methods
+        .addSlasherProgram(Keypair.generate().publicKey)
+        .accountsPartial({ config: t.config, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects registering a slasher twice", async () => {
+    await expectError(
+      droneosToken.methods.addSlasherProgram(taskMarket.programId).accountsPartial({ config: t.config, authority }).rpc(),
+      "SlasherAlreadyRegistered"
+    );
+  });
+
+  it("rejects an insurance share over the whole slash", async () => {
+    await expectError(setInsuranceShare(10_001), "InvalidInsuranceShare");
+  });
+
+  it("rejects slashes by unregistered programs", async () => {
+    await expectError(slashDirectly(Keypair.generate().publicKey), "SlasherNotRegistered");
+  });
+
+  it("rejects slashes not signed by the slasher program's PDA", async () => {
+    await expectError(slashDirectly(taskMarket.programId), "ConstraintSeeds");
+  });
+
+  it("rejects a slash routed anywhere but the treasury", async () => {
+    await expectError(abortLateTask(late, { treasury: late.creatorDroneos }), "InvalidVault");
+  });
+
+  it("splits a robot-fault slash between the insurance pool and the treasury", async () => {
+    await setInsuranceShare(INSURANCE_SHARE);
+    const stakeBefore: any = await droneosToken.account.operatorStake.fetch(late.operatorStake);
+    const insuranceBefore = await balance(t.insuranceVault);
+    const treasuryBefore = await balance(t.treasury);
+
+    await abortLateTask(late);
+
+    const task: any = await taskMarket.account.task.fetch(late.task);
+    expect(task.status).to.equal(TaskStatus.Failed);
+    expect(task.robotFault).to.equal(1);
+
+    // The task's reward, being under 10% of the operator's bond
+    const reward: BN = task.reward;
+    const record: any = await droneosToken.account.slashRecord.fetch(
+      pda(droneosToken.programId, Buffer.from("slash"), late.operator.publicKey.toBuffer(), u64(stakeBefore.slashCount))
+    );
+    expect(record.amount.toNumber()).to.equal(reward.toNumber());
+    expect(record.slasher.toBase58()).to.equal(taskMarket.programId.toBase58());
+    expect(record.task.toBase58()).to.equal(late.task.toBase58());
+
+    const toInsurance = (reward.toNumber() * INSURANCE_SHARE) / 10_000;
+    expect((await balance(t.insuranceVault)) - insuranceBefore).to.equal(toInsurance);
+    expect((await balance(t.treasury)) - treasuryBefore).to.equal(reward.toNumber() - toInsurance);
+
+    const stakeAfter: any = await droneosToken.account.operatorStake.fetch(late.operatorStake);
+    expect(stakeBefore.slashableAmount.sub(stakeAfter.slashableAmount).toNumber()).to.equal(reward.toNumber());
+    expect(stakeAfter.slashCount.toNumber()).to.equal(stakeBefore.slashCount.toNumber() + 1);
+  });
+});
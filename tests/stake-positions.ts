@@ -0,0 +1,71 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, pda, programs, setupToken, stake, u32 } from "./helpers";
+
+/**
+ * Stake positions: a wallet holds any number of positions, numbered in the
+ * order opened, each with its own lock tier.
+ */
+describe("DRONEOS Token: multiple stake positions", () => {
+  const { droneosToken } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const staker = Keypair.generate();
+  let t: TokenSetup;
+  let stakerToken: PublicKey;
+  let positions: PublicKey;
+
+  before(async () => {
+    await fund(staker);
+    t = await setupToken();
+    positions = pda(droneosToken.programId, Buffer.from("positions"), staker.publicKey.toBuffer());
+    stakerToken = await drip(staker, 2 * AMOUNT);
+  });
+
+  it("rejects a lock period that isn't a tier", async () => {
+    await expectError(stake(staker, stakerToken, AMOUNT, 45), "InvalidLockPeriod");
+  });
+
+  it("opens positions with different locks side by side", async () => {
+    const first = await stake(staker, stakerToken, AMOUNT);
+    const second = await stake(staker, stakerToken, AMOUNT, 30);
+
+    const unlocked: any = await droneosToken.account.stakeAccount.fetch(first);
+    const locked: any = await droneosToken.account.stakeAccount.fetch(second);
+    expect(unlocked.index).to.equal(0);
+    expect(locked.index).to.equal(1);
+    expect(unlocked.multiplier).to.equal(10_000);
+    expect(locked.multiplier).to.equal(11_000);
+    expect(locked.lockUntil.toNumber()).to.be.gt(unlocked.lockUntil.toNumber());
+
+    const opened: any = await droneosToken.account.stakerPositions.fetch(positions);
+    expect(opened.nextIndex).to.equal(2);
+    expect(opened.openPositions).to.equal(2);
+  });
+
+  it("rejects a position opened out of order", async () => {
+    await expectError(
+      droneosToken.methods
+        .stake(new BN(AMOUNT), 0, null)
+        .accountsPartial({
+          config: t.config,
+          positions,
+          stakeAccount: pda(droneosToken.programId, Buffer.from("stake"), staker.publicKey.toBuffer(), u32(5)),
+          stakeVault: t.stakeVault,
+          userToken: stakerToken,
+          user: staker.publicKey,
+          mint: t.mint,
+          referrerAccount: null,
+          referrerToken: null,
+          rewardsVault: null,
+          emissions: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([staker])
+        .rpc(),
+      "ConstraintSeeds"
+    );
+  });
+});
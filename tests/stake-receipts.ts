@@ -0,0 +1,123 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  createAssociatedTokenAccount,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getTokenMetadata,
+  transferChecked,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, pda, programs, setupToken, stake, unstake } from "./helpers";
+
+/**
+ * Stake receipts: a position's NFT carries its amount and lock, and once
+ * minted only its holder can unstake the position, in full, burning it.
+ */
+describe("DRONEOS Token: stake receipt NFTs", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const AMOUNT = 100 * 1_000_000;
+  const staker = Keypair.generate();
+  const holder = Keypair.generate();
+  let t: TokenSetup;
+  let stakerToken: PublicKey;
+  let position: PublicKey;
+  let receiptMint: PublicKey;
+
+  function mintStakeReceipt(user = staker) {
+    return droneosToken.methods
+      .mintStakeReceipt()
+      .accountsPartial({
+        config: t.config,
+        stakeAccount: position,
+        receiptMint,
+        receiptToken: getAssociatedTokenAddressSync(receiptMint, user.publicKey, false, TOKEN_2022_PROGRAM_ID),
+        user: user.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(staker, holder);
+    t = await setupToken();
+    stakerToken = await drip(staker, AMOUNT);
+    position = await stake(staker, stakerToken, AMOUNT);
+    receiptMint = pda(droneosToken.programId, Buffer.from("receipt"), position.toBuffer());
+  });
+
+  it("rejects a receipt minted by anyone but the position's owner", async () => {
+    await expectError(mintStakeReceipt(holder), "ConstraintSeeds");
+  });
+
+  it("mints a receipt describing the position", async () => {
+    await mintStakeReceipt();
+
+    const { receiptMint: minted } = await droneosToken.account.stakeAccount.fetch(position);
+    expect(minted!.toBase58()).to.equal(receiptMint.toBase58());
+    const receiptToken = getAssociatedTokenAddressSync(receiptMint, staker.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    expect(await balance(receiptToken)).to.equal(1);
+
+    const metadata = await getTokenMetadata(connection, receiptMint, undefined, TOKEN_2022_PROGRAM_ID);
+    const fields = Object.fromEntries(metadata!.additionalMetadata);
+    expect(fields.position).to.equal(position.toBase58());
+    expect(fields.amount).to.equal(AMOUNT.toString());
+    expect(fields.lock_days).to.equal("0");
+  });
+
+  it("rejects an unstake by the owner once the receipt is elsewhere", async () => {
+    const stakerReceipt = getAssociatedTokenAddressSync(receiptMint, staker.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const holderReceipt = await createAssociatedTokenAccount(
+      connection,
+      holder,
+      receiptMint,
+      holder.publicKey,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await transferChecked(
+      connection,
+      staker,
+      stakerReceipt,
+      receiptMint,
+      holderReceipt,
+      staker,
+      1,
+      0,
+      [],
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+
+    await expectError(unstake(staker, stakerToken, position), "NotReceiptHolder");
+    await expectError(
+      unstake(staker, stakerToken, position, null, { mint: receiptMint, token: stakerReceipt }),
+      "NotReceiptHolder"
+    );
+  });
+
+  it("lets the holder unstake the whole position, burning the receipt", async () => {
+    const receipt = {
+      mint: receiptMint,
+      token: getAssociatedTokenAddressSync(receiptMint, holder.publicKey, false, TOKEN_2022_PROGRAM_ID),
+    };
+    const holderToken = await drip(holder);
+    await expectError(unstake(holder, holderToken, position, AMOUNT / 2, receipt), "ReceiptRequiresFullUnstake");
+
+    await unstake(holder, holderToken, position, null, receipt);
+
+    expect(await balance(holderToken)).to.be.gte(AMOUNT);
+    expect(await balance(receipt.token)).to.equal(0);
+    const after: any = await droneosToken.account.stakeAccount.fetch(position);
+    expect(after.amount.toNumber()).to.equal(0);
+    expect(after.receiptMint).to.equal(null);
+  });
+});
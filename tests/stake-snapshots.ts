@@ -0,0 +1,59 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { TokenSetup, expectError, fund, pda, programs, setupToken, u64 } from "./helpers";
+
+/**
+ * Stake snapshots: the authority records total stake and the merkle root of
+ * per-wallet balances, against which anyone can check a wallet's balance.
+ */
+describe("DRONEOS Token: stake snapshots", () => {
+  const { droneosToken } = programs();
+  const authority = anchor.getProvider().publicKey!;
+
+  const ROOT = Array.from({ length: 32 }, (_, i) => i + 1);
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let snapshot: PublicKey;
+
+  function takeSnapshot(signer?: Keypair) {
+    return droneosToken.methods
+      .takeSnapshot(ROOT, 3)
+      .accountsPartial({ config: t.config, snapshot, authority: signer?.publicKey ?? authority })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    const { snapshotCount } = await droneosToken.account.tokenConfig.fetch(t.config);
+    snapshot = pda(droneosToken.programId, Buffer.from("snapshot"), u64(snapshotCount));
+  });
+
+  it("rejects snapshots by anyone but the authority", async () => {
+    await expectError(takeSnapshot(intruder), "Unauthorized");
+  });
+
+  it("records total stake and the balance root", async () => {
+    const before: any = await droneosToken.account.tokenConfig.fetch(t.config);
+    await takeSnapshot();
+
+    const taken: any = await droneosToken.account.snapshot.fetch(snapshot);
+    expect(taken.id.toNumber()).to.equal(before.snapshotCount.toNumber());
+    expect(taken.totalStaked.toNumber()).to.equal(before.totalStaked.toNumber());
+    expect(taken.merkleRoot).to.deep.equal(ROOT);
+    expect(taken.holderCount).to.equal(3);
+    const config: any = await droneosToken.account.tokenConfig.fetch(t.config);
+    expect(config.snapshotCount.toNumber()).to.equal(before.snapshotCount.toNumber() + 1);
+  });
+
+  it("rejects a balance the snapshot's root doesn't commit to", async () => {
+    const held = await droneosToken.methods
+      .verifySnapshotBalance(intruder.publicKey, new BN(1_000_000), [])
+      .accountsPartial({ snapshot })
+      .view();
+    expect(held).to.equal(false);
+  });
+});
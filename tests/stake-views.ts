@@ -0,0 +1,46 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { TokenSetup, drip, fund, programs, setupToken, stake, stakedOperator } from "./helpers";
+
+/**
+ * Stake views: pending rewards and operator bonds come back as structured
+ * return data, so other programs and clients needn't redo the math.
+ */
+describe("DRONEOS Token: stake views", () => {
+  const { droneosToken } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const staker = Keypair.generate();
+  let t: TokenSetup;
+  let position: PublicKey;
+
+  before(async () => {
+    await fund(staker);
+    t = await setupToken();
+    position = await stake(staker, await drip(staker, AMOUNT), AMOUNT, 30);
+  });
+
+  it("returns a position's pending rewards with its lock and multiplier", async () => {
+    const view: any = await droneosToken.methods
+      .getPendingRewards()
+      .accountsPartial({ config: t.config, stakeAccount: position })
+      .view();
+
+    const { lockUntil } = await droneosToken.account.stakeAccount.fetch(position);
+    expect(view.lockUntil.toNumber()).to.equal(lockUntil.toNumber());
+    expect(view.multiplier).to.equal(11_000);
+    // Nothing has been settled at an earlier multiplier
+    expect(view.pending.toNumber()).to.equal(view.accrued.toNumber());
+  });
+
+  it("returns an operator's bond and standing", async () => {
+    const { operatorStake } = await stakedOperator();
+    const view: any = await droneosToken.methods.getOperatorStake().accountsPartial({ operatorStake }).view();
+
+    const stored: any = await droneosToken.account.operatorStake.fetch(operatorStake);
+    expect(view.bond.toNumber()).to.equal(stored.slashableAmount.add(stored.delegatedAmount).toNumber());
+    expect(view.slashableAmount.toNumber()).to.equal(stored.slashableAmount.toNumber());
+    expect(view.reputation).to.equal(stored.reputation);
+    expect(view.activeTasks).to.equal(stored.activeTasks);
+  });
+});
@@ -0,0 +1,69 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import { drip, expectError, fund, pda, programs, setupMarket, stake, tokenFor, u64 } from "./helpers";
+
+/**
+ * Staker discounts: a payer can name one of their DRONEOS stake positions
+ * when opening a stream, and the stream's platform fee is discounted by
+ * the position's fee tier.
+ */
+describe("Payment Streams: staker fee discounts", () => {
+  const { paymentStreams } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const payer = Keypair.generate();
+  const payee = Keypair.generate();
+  const stranger = Keypair.generate();
+  let mint: PublicKey;
+  let payerToken: PublicKey;
+  let position: PublicKey;
+  let strangerPosition: PublicKey;
+
+  function createStream(nonce: number, payerStake: PublicKey) {
+    return paymentStreams.methods
+      .createStream(new BN(nonce), new BN(1_000), new BN(3_600), new BN(60), true, new BN(0), new BN(0), null)
+      .accountsPartial({
+        stream: streamAddress(nonce),
+        mint,
+        payerToken,
+        payer: payer.publicKey,
+        payee: payee.publicKey,
+        payerStake,
+        referrerAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([payer])
+      .rpc();
+  }
+
+  function streamAddress(nonce: number) {
+    return pda(
+      paymentStreams.programId,
+      Buffer.from("stream"),
+      payer.publicKey.toBuffer(),
+      payee.publicKey.toBuffer(),
+      u64(nonce)
+    );
+  }
+
+  before(async () => {
+    await fund(payer, payee, stranger);
+    await setupMarket();
+    ({ mint, token: payerToken } = await tokenFor(payer, 1_000_000_000));
+    position = await stake(payer, await drip(payer, AMOUNT), AMOUNT);
+    strangerPosition = await stake(stranger, await drip(stranger, AMOUNT), AMOUNT);
+  });
+
+  it("rejects someone else's stake position", async () => {
+    await expectError(createStream(0, strangerPosition), "ConstraintSeeds");
+  });
+
+  it("discounts nothing for a position below the first fee tier", async () => {
+    await createStream(0, position);
+
+    const stream: any = await paymentStreams.account.paymentStream.fetch(streamAddress(0));
+    expect(stream.feeDiscountBps).to.equal(0);
+  });
+});
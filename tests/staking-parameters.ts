@@ -0,0 +1,74 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import { TokenSetup, expectError, fund, programs, setupToken } from "./helpers";
+
+/**
+ * Staking parameters: the authority queues base APY and the stake minimums,
+ * which anyone can apply once `PARAMETER_TIMELOCK` (two days) has passed.
+ */
+describe("DRONEOS Token: timelocked staking parameters", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const TIMELOCK = 2 * 24 * 60 * 60;
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let current: any;
+
+  function updateParameters(baseApyBps: number, minStake: BN, minOperatorStake: BN, signer?: Keypair) {
+    return droneosToken.methods
+      .updateParameters(baseApyBps, minStake, minOperatorStake)
+      .accountsPartial({ config: t.config, authority: signer?.publicKey ?? authority })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  function applyParameters() {
+    return droneosToken.methods.applyParameters().accountsPartial({ config: t.config }).rpc();
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+    current = await droneosToken.account.tokenConfig.fetch(t.config);
+  });
+
+  it("rejects applying with nothing queued", async () => {
+    await expectError(applyParameters(), "NoPendingParameters");
+  });
+
+  it("rejects parameters queued by anyone but the authority", async () => {
+    await expectError(
+      updateParameters(current.baseApyBps, current.minStake, current.minOperatorStake, intruder),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects a zero staker minimum or an operator minimum below it", async () => {
+    const { baseApyBps, minStake, minOperatorStake } = current;
+    await expectError(updateParameters(baseApyBps, new BN(0), minOperatorStake), "InvalidParameters");
+    await expectError(updateParameters(baseApyBps, minStake, minStake.subn(1)), "InvalidParameters");
+  });
+
+  it("queues parameters behind the timelock", async () => {
+    // The current values, so other test files are unaffected when they apply
+    await updateParameters(current.baseApyBps, current.minStake, current.minOperatorStake);
+
+    const config: any = await droneosToken.account.tokenConfig.fetch(t.config);
+    const pending = config.pendingParameters;
+    expect(pending.minStake.toNumber()).to.equal(current.minStake.toNumber());
+    const now = await connection.getBlockTime(await connection.getSlot("confirmed"));
+    expect(pending.effectiveAt.toNumber()).to.be.gte(now! + TIMELOCK - 5);
+    expect(config.baseApyBps).to.equal(current.baseApyBps);
+  });
+
+  it("rejects applying before the timelock passes", async () => {
+    await expectError(applyParameters(), "ParametersNotReady");
+
+    const config: any = await droneosToken.account.tokenConfig.fetch(t.config);
+    expect(config.pendingParameters).to.not.equal(null);
+  });
+});
@@ -0,0 +1,130 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, drip, expectError, fund, pda, programs, setupToken, stake, u32 } from "./helpers";
+
+/**
+ * Staking referrals: a locked stake naming a registered referrer pays them
+ * a bonus from the rewards vault, capped per referrer per week and charged
+ * against the emission budget.
+ */
+describe("DRONEOS Token: staking referrals", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const AMOUNT = 100 * 1_000_000;
+  const LOCK_DAYS = 30;
+  const staker = Keypair.generate();
+  const referrer = Keypair.generate();
+  const stranger = Keypair.generate();
+  let t: TokenSetup;
+  let stakerToken: PublicKey;
+  let referrerToken: PublicKey;
+  let referrerAccount: PublicKey;
+
+  function registerReferrer(owner: Keypair) {
+    return droneosToken.methods
+      .registerReferrer()
+      .accountsPartial({
+        referrerAccount: pda(droneosToken.programId, Buffer.from("referrer"), owner.publicKey.toBuffer()),
+        owner: owner.publicKey,
+      })
+      .signers([owner])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(staker, referrer, stranger);
+    t = await setupToken();
+    referrerAccount = pda(droneosToken.programId, Buffer.from("referrer"), referrer.publicKey.toBuffer());
+    await registerReferrer(referrer);
+    await registerReferrer(staker);
+    stakerToken = await drip(staker, AMOUNT);
+    referrerToken = await drip(referrer);
+  });
+
+  it("registers a referrer with nothing referred yet", async () => {
+    const registered: any = await droneosToken.account.referrer.fetch(referrerAccount);
+    expect(registered.owner.toBase58()).to.equal(referrer.publicKey.toBase58());
+    expect(registered.referredVolume.toNumber()).to.equal(0);
+    expect(registered.referralCount).to.equal(0);
+  });
+
+  it("rejects naming a referrer without their accounts", async () => {
+    const positions = pda(droneosToken.programId, Buffer.from("positions"), staker.publicKey.toBuffer());
+    await expectError(
+      droneosToken.methods
+        .stake(new BN(AMOUNT), 0, referrer.publicKey)
+        .accountsPartial({
+          config: t.config,
+          positions,
+          stakeAccount: pda(droneosToken.programId, Buffer.from("stake"), staker.publicKey.toBuffer(), u32(0)),
+          stakeVault: t.stakeVault,
+          userToken: stakerToken,
+          user: staker.publicKey,
+          mint: t.mint,
+          referrerAccount: null,
+          referrerToken: null,
+          rewardsVault: null,
+          emissions: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([staker])
+        .rpc(),
+      "MissingReferralAccounts"
+    );
+  });
+
+  it("rejects referring yourself", async () => {
+    await expectError(
+      stake(staker, stakerToken, AMOUNT, 0, { referrer: staker.publicKey, referrerToken: stakerToken }),
+      "InvalidReferrer"
+    );
+  });
+
+  it("rejects paying the bonus to someone else's account", async () => {
+    await expectError(
+      stake(staker, stakerToken, AMOUNT, 0, { referrer: referrer.publicKey, referrerToken: await drip(stranger) }),
+      "InvalidReferrer"
+    );
+  });
+
+  it("rejects naming an unregistered referrer", async () => {
+    await expectError(
+      stake(staker, stakerToken, AMOUNT, 0, { referrer: stranger.publicKey, referrerToken: await drip(stranger) }),
+      "AccountNotInitialized"
+    );
+  });
+
+  it("rejects a referral on an unlocked stake", async () => {
+    await expectError(
+      stake(staker, stakerToken, AMOUNT, 0, { referrer: referrer.publicKey, referrerToken }),
+      "ReferralRequiresLock"
+    );
+  });
+
+  it("rejects paying the bonus from a vault other than the config's", async () => {
+    const referral = { referrer: referrer.publicKey, referrerToken, rewardsVault: t.stakeVault };
+    await expectError(stake(staker, stakerToken, AMOUNT, LOCK_DAYS, referral), "InvalidVault");
+  });
+
+  it("credits the referrer with the stake, paying no more than the emission budget", async () => {
+    const vaultBefore = await balance(t.rewardsVault);
+    await stake(staker, stakerToken, AMOUNT, LOCK_DAYS, { referrer: referrer.publicKey, referrerToken });
+
+    const credited: any = await droneosToken.account.referrer.fetch(referrerAccount);
+    expect(credited.referredVolume.toNumber()).to.equal(AMOUNT);
+    expect(credited.referralCount).to.equal(1);
+    // The test schedule's epochs are all over, so the 1% bonus has no budget
+    expect(credited.totalBonus.toNumber()).to.equal(0);
+    expect(credited.epochBonus.toNumber()).to.equal(0);
+    expect(await balance(referrerToken)).to.equal(0);
+    expect(await balance(t.rewardsVault)).to.equal(vaultBefore);
+  });
+});
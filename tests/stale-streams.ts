@@ -0,0 +1,94 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  cpiEvents,
+  expectError,
+  fund,
+  openStream,
+  pda,
+  programs,
+  setupMarket,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Stale streams: a tick pays for at most the program's maximum tick
+ * interval in full. Time before that moves into arrears, emitted as
+ * StreamStale and paid down one interval's worth per tick.
+ */
+describe("Payment Streams: maximum tick interval", () => {
+  const { paymentStreams } = programs();
+  const authority = anchor.getProvider().publicKey!;
+  const config = pda(paymentStreams.programId, Buffer.from("config"));
+
+  const RATE = 1_000;
+  let s: Stream;
+
+  function setMaxTickInterval(seconds: number) {
+    return paymentStreams.methods
+      .setMaxTickInterval(new BN(seconds))
+      .accountsPartial({ config, authority })
+      .rpc();
+  }
+
+  before(async () => {
+    await setupMarket();
+  });
+
+  after(async () => {
+    // The config is shared with other test files
+    await setMaxTickInterval(0);
+  });
+
+  it("rejects a maximum set by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      paymentStreams.methods
+        .setMaxTickInterval(new BN(1))
+        .accountsPartial({ config, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects a negative maximum", async () => {
+    await expectError(setMaxTickInterval(-1), "InvalidDuration");
+  });
+
+  it("moves time past the maximum into arrears", async () => {
+    await setMaxTickInterval(1);
+    s = await openStream({ rate: RATE });
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    const { lastTickAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(lastTickAt).addn(4));
+    const signature = await tick(s).rpc();
+
+    const [stale] = (await cpiEvents(paymentStreams, signature)).filter((e) => e.name === "streamStale");
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(stale.data.staleSeconds.toNumber()).to.be.gte(3);
+    expect(stale.data.movedToArrears.toNumber()).to.equal(RATE * stale.data.staleSeconds.toNumber());
+    // One interval's worth of arrears is paid alongside the current interval
+    expect(account.arrears.toNumber()).to.equal(stale.data.movedToArrears.toNumber() - RATE);
+    expect(account.totalPaid.toNumber()).to.equal(2 * RATE);
+  });
+
+  it("pays arrears down over later ticks", async () => {
+    const before: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(before.lastTickAt.addn(1));
+    await tick(s).rpc();
+
+    // Anything past the first second since the last tick is stale again
+    const after: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const restaled = RATE * (after.lastTickAt.sub(before.lastTickAt).toNumber() - 1);
+    expect(after.arrears.toNumber()).to.equal(before.arrears.toNumber() + restaled - RATE);
+  });
+});
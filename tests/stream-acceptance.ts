@@ -0,0 +1,65 @@
+import * as anchor from "@coral-xyz/anchor";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import { Stream, acceptStream, expectError, openStream, programs, startStream } from "./helpers";
+
+/**
+ * Payee acceptance: a stream can't start until its payee accepts the terms,
+ * and a payee who rejects them returns everything to the payer.
+ */
+describe("Payment Streams: payee acceptance", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  function rejectStream(s: Stream) {
+    return paymentStreams.methods
+      .rejectStream()
+      .accountsPartial({
+        stream: s.stream,
+        mint: s.mint,
+        payerToken: s.payerToken,
+        payee: s.payee.publicKey,
+      })
+      .signers([s.payee])
+      .rpc();
+  }
+
+  it("rejects starting a stream its payee hasn't accepted", async () => {
+    const s = await openStream();
+    await expectError(startStream(s).rpc(), "StreamNotAccepted");
+  });
+
+  it("rejects acceptance by anyone but the payee", async () => {
+    const s = await openStream();
+    await expectError(
+      paymentStreams.methods
+        .acceptStream()
+        .accountsPartial({ stream: s.stream, payee: s.payer.publicKey })
+        .signers([s.payer])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("starts once the payee accepts", async () => {
+    const s = await openStream();
+    await acceptStream(s).rpc();
+    expect((await paymentStreams.account.paymentStream.fetch(s.stream)).status).to.have.property("accepted");
+    await expectError(acceptStream(s).rpc(), "StreamNotPending");
+    await expectError(rejectStream(s), "StreamNotPending");
+
+    await startStream(s).rpc();
+    expect((await paymentStreams.account.paymentStream.fetch(s.stream)).status).to.have.property("active");
+  });
+
+  it("refunds the escrow, deposit and activation fee on rejection", async () => {
+    const s = await openStream({ securityDeposit: 50_000, activationFee: 20_000 });
+    const before = (await getAccount(connection, s.payerToken)).amount;
+    await rejectStream(s);
+
+    const after = (await getAccount(connection, s.payerToken)).amount;
+    expect(Number(after - before)).to.equal(3_600_000 + 50_000 + 20_000);
+    expect(Number((await getAccount(connection, s.escrow)).amount)).to.equal(0);
+    expect((await paymentStreams.account.paymentStream.fetch(s.stream)).status).to.have.property("cancelled");
+  });
+});
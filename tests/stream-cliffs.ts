@@ -0,0 +1,67 @@
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Cliffs: a stream set with a cliff before it starts accrues from its start
+ * as usual, but ticks pay nothing until the cliff ends and the first tick
+ * after it pays everything accrued so far.
+ */
+describe("Payment Streams: cliffs", () => {
+  const { paymentStreams } = programs();
+
+  const RATE = 1_000;
+  const CLIFF = 3;
+
+  function setCliff(s: Stream, seconds: number, signer = s.payer) {
+    return paymentStreams.methods
+      .setCliff(new BN(seconds))
+      .accountsPartial({ stream: s.stream, authority: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  it("rejects a cliff set by anyone but the payer", async () => {
+    const s = await openStream();
+    await expectError(setCliff(s, CLIFF, s.payee), "Unauthorized");
+  });
+
+  it("rejects a negative cliff or one past the stream's duration", async () => {
+    const s = await openStream({ duration: 3_600 });
+    await expectError(setCliff(s, -1), "InvalidDuration");
+    await expectError(setCliff(s, 3_601), "InvalidDuration");
+  });
+
+  it("rejects a cliff once the payee has accepted", async () => {
+    const s = await openStream();
+    await acceptStream(s).rpc();
+    await expectError(setCliff(s, CLIFF), "StreamNotPending");
+  });
+
+  it("holds payment back until the cliff ends, then pays it all", async () => {
+    const s = await openStream({ rate: RATE });
+    await setCliff(s, CLIFF);
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+
+    await expectError(tick(s).rpc(), "CliffNotReached");
+
+    const { startedAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(startedAt).addn(CLIFF));
+    await tick(s).rpc();
+
+    const stream: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const elapsed = stream.lastTickAt.sub(stream.startedAt).toNumber();
+    expect(elapsed).to.be.gte(CLIFF);
+    expect(stream.totalPaid.toNumber()).to.equal(RATE * elapsed);
+  });
+});
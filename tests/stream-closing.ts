@@ -0,0 +1,56 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  terminateStream,
+} from "./helpers";
+
+/**
+ * Closing streams: once a stream has finished and its escrow is empty, its
+ * payer can close the stream and escrow accounts to reclaim their rent.
+ */
+describe("Payment Streams: closing finished streams", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  let s: Stream;
+
+  function closeStream(signer: Keypair = s.payer) {
+    return paymentStreams.methods
+      .closeStream()
+      .accountsPartial({ stream: s.stream, escrow: s.escrow, payer: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    s = await openStream();
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("rejects closing a stream still running", async () => {
+    await expectError(closeStream(), "StreamNotFinished");
+  });
+
+  it("rejects closing by anyone but the payer", async () => {
+    await terminateStream(s).rpc();
+    await expectError(closeStream(s.payee), "Unauthorized");
+  });
+
+  it("returns the stream's and escrow's rent to the payer", async () => {
+    const rent = (await connection.getBalance(s.stream)) + (await connection.getBalance(s.escrow));
+    const before = await connection.getBalance(s.payer.publicKey);
+    await closeStream();
+
+    expect(await connection.getAccountInfo(s.stream)).to.equal(null);
+    expect(await connection.getAccountInfo(s.escrow)).to.equal(null);
+    expect((await connection.getBalance(s.payer.publicKey)) - before).to.equal(rent);
+  });
+});
@@ -0,0 +1,70 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  terminateStream,
+} from "./helpers";
+
+/**
+ * Stream extension: a payer can lengthen a running stream, depositing the
+ * escrow the extra time needs at its final rate in the same instruction.
+ */
+describe("Payment Streams: extension", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const RATE = 1_000;
+  let s: Stream;
+
+  function extendStream(seconds: number, signer = s.payer, signerToken = s.payerToken) {
+    return paymentStreams.methods
+      .extendStream(new BN(seconds))
+      .accountsPartial({
+        stream: s.stream,
+        mint: s.mint,
+        payerToken: signerToken,
+        payer: signer.publicKey,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    s = await openStream({ rate: RATE, duration: 3_600 });
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("rejects extension by anyone but the payer", async () => {
+    await expectError(extendStream(600, s.payee, s.payeeToken), "Unauthorized");
+  });
+
+  it("rejects no extra time or a total past the program maximum", async () => {
+    await expectError(extendStream(0), "InvalidDuration");
+    await expectError(extendStream(30 * 86_400), "InvalidDuration");
+  });
+
+  it("lengthens the stream and deposits the escrow it needs", async () => {
+    const before: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const payerBefore = (await getAccount(connection, s.payerToken)).amount;
+    await extendStream(600);
+
+    const after: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const payerAfter = (await getAccount(connection, s.payerToken)).amount;
+    expect(after.maxDuration.toNumber()).to.equal(before.maxDuration.toNumber() + 600);
+    expect(after.escrowBalance.toNumber()).to.equal(before.escrowBalance.toNumber() + RATE * 600);
+    expect(Number(payerBefore - payerAfter)).to.equal(RATE * 600);
+  });
+
+  it("rejects extending a finished stream", async () => {
+    await terminateStream(s).rpc();
+    await expectError(extendStream(600), "StreamAlreadyTerminated");
+  });
+});
@@ -0,0 +1,90 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  pda,
+  programs,
+  setupMarket,
+  startStream,
+  terminateStream,
+  tick,
+} from "./helpers";
+
+/**
+ * Emergency freeze: while the authority has payment-streams frozen, no
+ * stream can be created, started or ticked, but streams can still be ended
+ * so escrowed funds are never stranded.
+ */
+describe("Payment Streams: emergency freeze", () => {
+  const { paymentStreams } = programs();
+  const authority = anchor.getProvider().publicKey!;
+  const config = pda(paymentStreams.programId, Buffer.from("config"));
+
+  let accepted: Stream;
+  let active: Stream;
+
+  function freeze() {
+    return paymentStreams.methods.freezeProgram().accountsPartial({ config, authority }).rpc();
+  }
+
+  function unfreeze() {
+    return paymentStreams.methods.unfreezeProgram().accountsPartial({ config, authority }).rpc();
+  }
+
+  before(async () => {
+    await setupMarket();
+    accepted = await openStream();
+    await acceptStream(accepted).rpc();
+    active = await openStream();
+    await acceptStream(active).rpc();
+    await startStream(active).rpc();
+  });
+
+  after(async () => {
+    // The config is shared with other test files
+    if ((await paymentStreams.account.programConfig.fetch(config)).frozen) await unfreeze();
+  });
+
+  it("rejects freezing by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      paymentStreams.methods
+        .freezeProgram()
+        .accountsPartial({ config, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects unfreezing a program that isn't frozen", async () => {
+    await expectError(unfreeze(), "ProgramNotFrozen");
+  });
+
+  it("refuses new streams, starts and ticks while frozen", async () => {
+    await freeze();
+    expect((await paymentStreams.account.programConfig.fetch(config)).frozen).to.equal(true);
+    await expectError(freeze(), "ProgramFrozen");
+
+    await expectError(openStream(), "ProgramFrozen");
+    await expectError(startStream(accepted).rpc(), "ProgramFrozen");
+    await expectError(tick(active).rpc(), "ProgramFrozen");
+  });
+
+  it("lets streams be ended while frozen", async () => {
+    await terminateStream(active).rpc();
+    expect((await paymentStreams.account.paymentStream.fetch(active.stream)).status).to.have.property("completed");
+  });
+
+  it("resumes streaming once unfrozen", async () => {
+    await unfreeze();
+    await startStream(accepted).rpc();
+    expect((await paymentStreams.account.paymentStream.fetch(accepted.stream)).status).to.have.property("active");
+  });
+});
@@ -0,0 +1,69 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  acceptStream,
+  openStream,
+  pda,
+  programs,
+  setupMarket,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Stats and indexes: the program config counts every stream and all volume
+ * paid, and each wallet's registry PDA lists the streams it pays or is
+ * paid by.
+ */
+describe("Payment Streams: stats and wallet indexes", () => {
+  const { paymentStreams } = programs();
+  const config = pda(paymentStreams.programId, Buffer.from("config"));
+
+  function registry(kind: "payer_streams" | "payee_streams", wallet: PublicKey) {
+    return pda(paymentStreams.programId, Buffer.from(kind), wallet.toBuffer());
+  }
+
+  async function listed(kind: "payer_streams" | "payee_streams", wallet: PublicKey) {
+    const account: any = await paymentStreams.account.streamRegistry.fetch(registry(kind, wallet));
+    return account.streams.map((stream: PublicKey) => stream.toBase58());
+  }
+
+  before(async () => {
+    await setupMarket();
+  });
+
+  it("counts each stream created", async () => {
+    const before = (await paymentStreams.account.programConfig.fetch(config)).totalStreams.toNumber();
+    await openStream();
+
+    expect((await paymentStreams.account.programConfig.fetch(config)).totalStreams.toNumber()).to.equal(before + 1);
+  });
+
+  it("lists a stream under both its payer and its payee", async () => {
+    const first = await openStream();
+    const second = await openStream({ from: first });
+
+    expect(await listed("payer_streams", first.payer.publicKey)).to.deep.equal([
+      first.stream.toBase58(),
+      second.stream.toBase58(),
+    ]);
+    expect(await listed("payee_streams", first.payee.publicKey)).to.deep.equal([first.stream.toBase58()]);
+    expect(await listed("payee_streams", second.payee.publicKey)).to.deep.equal([second.stream.toBase58()]);
+  });
+
+  it("adds what each tick pays to the total volume", async () => {
+    const s = await openStream();
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    const { lastTickAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(lastTickAt).addn(1));
+    const before = (await paymentStreams.account.programConfig.fetch(config)).totalVolume.toNumber();
+    await tick(s).rpc();
+
+    const { totalPaid } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const after = (await paymentStreams.account.programConfig.fetch(config)).totalVolume.toNumber();
+    expect(after - before).to.equal(totalPaid.toNumber());
+  });
+});
@@ -0,0 +1,62 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import { Stream, expectError, openStream, pda, programs, u64 } from "./helpers";
+
+/**
+ * Stream nonces: the nonce in a stream's PDA seeds lets one payer run
+ * several streams to the same payee at once, each under its own nonce.
+ */
+describe("Payment Streams: nonces", () => {
+  const { paymentStreams } = programs();
+
+  let s: Stream;
+
+  function streamFor(nonce: number) {
+    return pda(
+      paymentStreams.programId,
+      Buffer.from("stream"),
+      s.payer.publicKey.toBuffer(),
+      s.payee.publicKey.toBuffer(),
+      u64(nonce)
+    );
+  }
+
+  function createStream(nonce: number) {
+    return paymentStreams.methods
+      .createStream(new BN(nonce), new BN(1_000), new BN(3_600), new BN(60), true, new BN(0), new BN(0), null)
+      .accountsPartial({
+        stream: streamFor(nonce),
+        mint: s.mint,
+        payerToken: s.payerToken,
+        payer: s.payer.publicKey,
+        payee: s.payee.publicKey,
+        payerStake: null,
+        referrerAccount: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([s.payer])
+      .rpc();
+  }
+
+  before(async () => {
+    // Opened under nonce 0
+    s = await openStream();
+  });
+
+  it("opens a concurrent stream to the same payee under a new nonce", async () => {
+    await createStream(1);
+
+    const escrow = (stream: PublicKey) => pda(paymentStreams.programId, Buffer.from("escrow"), stream.toBuffer());
+    const account: any = await paymentStreams.account.paymentStream.fetch(streamFor(1));
+    expect(account.nonce.toNumber()).to.equal(1);
+    expect(account.payee.toBase58()).to.equal(s.payee.publicKey.toBase58());
+    expect(escrow(streamFor(1)).toBase58()).to.not.equal(s.escrow.toBase58());
+    expect((await paymentStreams.account.paymentStream.fetch(s.stream)).nonce.toNumber()).to.equal(0);
+  });
+
+  it("rejects reusing a nonce", async () => {
+    await expectError(createStream(1), "already in use");
+  });
+});
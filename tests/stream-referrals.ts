@@ -0,0 +1,138 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  pda,
+  programs,
+  setupMarket,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Stream referrals: a stream created with a registered referrer pays it a
+ * share of the platform fee on every tick, tracked on its referrer PDA.
+ */
+describe("Payment Streams: referrals", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+  const config = pda(paymentStreams.programId, Buffer.from("config"));
+
+  const SHARE_BPS = 5_000;
+  const referrer = Keypair.generate();
+  let referrerAccount: PublicKey;
+  let referrerToken: PublicKey;
+  let s: Stream;
+
+  function setReferralShare(shareBps: number) {
+    return paymentStreams.methods.setReferralShare(shareBps).accountsPartial({ config, authority }).rpc();
+  }
+
+  function registerReferrer(who: Keypair) {
+    return paymentStreams.methods
+      .registerReferrer()
+      .accountsPartial({
+        referrerAccount: pda(paymentStreams.programId, Buffer.from("referrer"), who.publicKey.toBuffer()),
+        referrer: who.publicKey,
+      })
+      .signers([who])
+      .rpc();
+  }
+
+  function referredTick() {
+    return paymentStreams.methods
+      .tick()
+      .accountsPartial({
+        stream: s.stream,
+        mint: s.mint,
+        claimToken: null,
+        payeeToken: s.payeeToken,
+        treasury: s.treasury,
+        cranker: s.payee.publicKey,
+        crankerToken: null,
+        referrerAccount,
+        referrerToken,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([s.payee])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token)).amount);
+  }
+
+  before(async () => {
+    await fund(referrer);
+    await setupMarket();
+    await registerReferrer(referrer);
+    referrerAccount = pda(paymentStreams.programId, Buffer.from("referrer"), referrer.publicKey.toBuffer());
+  });
+
+  after(async () => {
+    // The config is shared with other test files
+    await setReferralShare(0);
+  });
+
+  it("rejects a share set by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      paymentStreams.methods
+        .setReferralShare(SHARE_BPS)
+        .accountsPartial({ config, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects a share over 100% of the fee", async () => {
+    await expectError(setReferralShare(10_001), "InvalidReferralShare");
+  });
+
+  it("credits the referrer with the streams it refers", async () => {
+    await setReferralShare(SHARE_BPS);
+    // A rate high enough that a second's platform fee splits evenly
+    s = await openStream({ rate: 100_000, referrerAccount });
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    referrerToken = await createAccount(connection, referrer, s.mint, referrer.publicKey);
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(account.referrer.toBase58()).to.equal(referrer.publicKey.toBase58());
+    expect((await paymentStreams.account.referrer.fetch(referrerAccount)).streamsReferred.toNumber()).to.equal(1);
+  });
+
+  it("rejects payers referring their own streams", async () => {
+    await registerReferrer(s.payer);
+    const self = pda(paymentStreams.programId, Buffer.from("referrer"), s.payer.publicKey.toBuffer());
+    await expectError(openStream({ from: s, referrerAccount: self }), "InvalidReferrer");
+  });
+
+  it("rejects ticks that leave out the referrer", async () => {
+    const { lastTickAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(lastTickAt).addn(1));
+    await expectError(tick(s).rpc(), "InvalidReferrer");
+  });
+
+  it("pays the referrer its share of the platform fee", async () => {
+    const treasuryBefore = await balance(s.treasury);
+    await referredTick();
+
+    const referral = await balance(referrerToken);
+    const fee = (await balance(s.treasury)) - treasuryBefore + referral;
+    expect(referral).to.be.gt(0);
+    expect(referral).to.equal(Math.floor((fee * SHARE_BPS) / 10_000));
+    expect((await paymentStreams.account.referrer.fetch(referrerAccount)).totalEarned.toNumber()).to.equal(referral);
+  });
+});
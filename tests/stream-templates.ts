@@ -0,0 +1,111 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import { expectError, fund, pda, programs, setupMarket, tokenFor, u64 } from "./helpers";
+
+/**
+ * Stream templates: an owner saves a stream's terms once and opens streams
+ * on them to any payee, with the template counting the streams it created.
+ */
+describe("Payment Streams: templates", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const owner = Keypair.generate();
+  const payee = Keypair.generate();
+  const RATE = 2_000;
+  const DURATION = 1_800;
+  let mint: PublicKey;
+  let ownerToken: PublicKey;
+
+  function templateFor(id: number) {
+    return pda(paymentStreams.programId, Buffer.from("template"), owner.publicKey.toBuffer(), u64(new BN(id)));
+  }
+
+  function createTemplate(id: number, rate: number, gracePeriod: number) {
+    return paymentStreams.methods
+      .createTemplate(new BN(id), new BN(rate), new BN(DURATION), new BN(gracePeriod), true)
+      .accountsPartial({ template: templateFor(id), mint, owner: owner.publicKey })
+      .signers([owner])
+      .rpc();
+  }
+
+  function createStreamFromTemplate(id: number, signer = owner, signerToken = ownerToken) {
+    const stream = pda(
+      paymentStreams.programId,
+      Buffer.from("stream"),
+      signer.publicKey.toBuffer(),
+      payee.publicKey.toBuffer(),
+      u64(new BN(0))
+    );
+    const tx = paymentStreams.methods
+      .createStreamFromTemplate(new BN(0))
+      .accountsPartial({
+        template: templateFor(id),
+        stream,
+        mint,
+        payerToken: signerToken,
+        payer: signer.publicKey,
+        payee: payee.publicKey,
+        payerStake: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([signer])
+      .rpc();
+    return { stream, tx };
+  }
+
+  function closeTemplate(id: number, signer = owner) {
+    return paymentStreams.methods
+      .closeTemplate()
+      .accountsPartial({ template: templateFor(id), owner: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(owner, payee);
+    await setupMarket();
+    ({ mint, token: ownerToken } = await tokenFor(owner, 1_000_000_000));
+  });
+
+  it("rejects terms create_stream would reject", async () => {
+    await expectError(createTemplate(1, 0, 60), "InvalidRate");
+    await expectError(createTemplate(1, RATE, 301), "InvalidGracePeriod");
+  });
+
+  it("opens streams on the template's terms", async () => {
+    await createTemplate(1, RATE, 60);
+    const { stream, tx } = createStreamFromTemplate(1);
+    await tx;
+
+    const account: any = await paymentStreams.account.paymentStream.fetch(stream);
+    expect(account.payee.toBase58()).to.equal(payee.publicKey.toBase58());
+    expect(account.ratePerSecond.toNumber()).to.equal(RATE);
+    expect(account.maxDuration.toNumber()).to.equal(DURATION);
+    expect(account.escrowBalance.toNumber()).to.equal(RATE * DURATION);
+    expect(account.status).to.have.property("pending");
+    expect((await paymentStreams.account.streamTemplate.fetch(templateFor(1))).streamsCreated.toNumber()).to.equal(1);
+  });
+
+  it("rejects streams from someone else's template", async () => {
+    const stranger = Keypair.generate();
+    await fund(stranger);
+    await expectError(createStreamFromTemplate(1, stranger).tx, "Unauthorized");
+  });
+
+  it("rejects closing by anyone but the owner", async () => {
+    await expectError(closeTemplate(1, payee), "ConstraintHasOne");
+  });
+
+  it("returns the template's rent on close", async () => {
+    const rent = await connection.getBalance(templateFor(1));
+    const before = await connection.getBalance(owner.publicKey);
+    await closeTemplate(1);
+
+    expect(await connection.getAccountInfo(templateFor(1))).to.equal(null);
+    expect((await connection.getBalance(owner.publicKey)) - before).to.equal(rent);
+  });
+});
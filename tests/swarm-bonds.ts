@@ -0,0 +1,166 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, createAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  CRANK_TIP,
+  TokenSetup,
+  drip,
+  expectError,
+  fund,
+  groupTaskAccounts,
+  pda,
+  programs,
+  setupToken,
+} from "./helpers";
+
+/**
+ * Swarm bonds: a swarm's leader posts a DRONEOS bond, and a group task's
+ * creator can only accept a swarm's bid if the bond covers the configured
+ * share of the task's reward.
+ */
+describe("Swarm Coordinator: swarm bonds", () => {
+  const { droneosToken, swarmCoordinator } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const BOND_BPS = 1_000;
+  // 10% of the reward is one crank tip's worth of DRONEOS
+  const REWARD = 10 * CRANK_TIP;
+  const leader = Keypair.generate();
+  const creator = Keypair.generate();
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+  let coordinator: PublicKey;
+  let originalBps: number;
+  let swarm: PublicKey;
+  let swarmStake: PublicKey;
+  let groupTask: PublicKey;
+  let bid: PublicKey;
+
+  function setSwarmBondBps(bps: number, signer?: Keypair) {
+    return swarmCoordinator.methods
+      .setSwarmBondBps(bps)
+      .accountsPartial({ coordinator, authority: signer?.publicKey ?? authority })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  function acceptSwarmBid() {
+    return swarmCoordinator.methods
+      .acceptSwarmBid()
+      .accountsPartial({
+        groupTask,
+        bid,
+        swarm,
+        creator: creator.publicKey,
+        coordinator,
+        swarmStake,
+        droneosTokenProgram: droneosToken.programId,
+      })
+      .signers([creator])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(leader, creator, intruder);
+    t = await setupToken();
+    ({ coordinator } = await groupTaskAccounts(creator.publicKey));
+    ({ swarmBondBps: originalBps } = await swarmCoordinator.account.coordinator.fetch(coordinator));
+
+    swarm = pda(swarmCoordinator.programId, Buffer.from("swarm"), leader.publicKey.toBuffer());
+    swarmStake = pda(droneosToken.programId, Buffer.from("swarm-stake"), swarm.toBuffer());
+    await swarmCoordinator.methods
+      .createSwarm("Survey crew", 2, 0)
+      .accountsPartial({ coordinator, swarm, leader: leader.publicKey })
+      .signers([leader])
+      .rpc();
+    for (let joined = 0; joined < 2; joined++) {
+      await swarmCoordinator.methods
+        .joinSwarm()
+        .accountsPartial({ swarm, robot: Keypair.generate().publicKey, operator: leader.publicKey })
+        .signers([leader])
+        .rpc();
+    }
+
+    ({ groupTask } = await groupTaskAccounts(creator.publicKey));
+    await swarmCoordinator.methods
+      .createGroupTask("Field survey", "Map 40ha", 2, new BN(REWARD), new BN(3_600))
+      .accountsPartial({ coordinator, groupTask, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+    bid = pda(swarmCoordinator.programId, Buffer.from("swarm-bid"), groupTask.toBuffer(), swarm.toBuffer());
+    await swarmCoordinator.methods
+      .swarmBid(new BN(1_000), new BN(3_600))
+      .accountsPartial({ swarm, groupTask, bid, leader: leader.publicKey })
+      .signers([leader])
+      .rpc();
+  });
+
+  after(async () => {
+    // The coordinator is shared with other test files
+    await setSwarmBondBps(originalBps);
+  });
+
+  it("rejects a bond share set by anyone but the authority", async () => {
+    await expectError(setSwarmBondBps(BOND_BPS, intruder), "Unauthorized");
+  });
+
+  it("rejects a bond share over 100%", async () => {
+    await expectError(setSwarmBondBps(10_001), "InvalidBondBps");
+  });
+
+  it("rejects a swarm bid without a bond", async () => {
+    await setSwarmBondBps(BOND_BPS);
+    await expectError(acceptSwarmBid(), "InsufficientSwarmBond");
+  });
+
+  it("posts a swarm bond", async () => {
+    const leaderToken = await drip(leader, CRANK_TIP);
+    const swarmVault = await createAccount(
+      connection,
+      leader,
+      t.mint,
+      t.config,
+      Keypair.generate(),
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const { totalStaked: stakedBefore } = await droneosToken.account.tokenConfig.fetch(t.config);
+
+    await droneosToken.methods
+      .createSwarmStake(new BN(CRANK_TIP))
+      .accountsPartial({
+        config: t.config,
+        swarm,
+        swarmStake,
+        swarmVault,
+        leaderToken,
+        leader: leader.publicKey,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([leader])
+      .rpc();
+
+    const posted: any = await droneosToken.account.swarmStake.fetch(swarmStake);
+    expect(posted.swarm.toBase58()).to.equal(swarm.toBase58());
+    expect(posted.leader.toBase58()).to.equal(leader.publicKey.toBase58());
+    expect(posted.amount.toNumber()).to.equal(CRANK_TIP);
+    const { totalStaked } = await droneosToken.account.tokenConfig.fetch(t.config);
+    expect(totalStaked.sub(stakedBefore).toNumber()).to.equal(CRANK_TIP);
+
+    const bond = await droneosToken.methods.getSwarmBond().accountsPartial({ swarmStake }).view();
+    expect(bond.toNumber()).to.equal(CRANK_TIP);
+  });
+
+  it("accepts a bid the swarm's bond covers, holding the bond against the task", async () => {
+    await acceptSwarmBid();
+
+    const task: any = await swarmCoordinator.account.groupTask.fetch(groupTask);
+    expect(task.bondedAmount.toNumber()).to.equal((REWARD * BOND_BPS) / 10_000);
+    expect(task.assignedSwarm.toBase58()).to.equal(swarm.toBase58());
+    expect(task.status).to.have.property("inProgress");
+  });
+});
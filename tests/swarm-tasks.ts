@@ -0,0 +1,70 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  createTask,
+  expectError,
+  fund,
+  groupTaskAccounts,
+  programs,
+  registerRobot,
+  setupMarket,
+  submitBid,
+} from "./helpers";
+
+/**
+ * Swarm tasks: a task needing more than one robot is delegated to a
+ * swarm-coordinator group task created and linked with it, which swarms
+ * bid on instead of robots bidding on the task.
+ */
+describe("Task Market: delegating tasks to swarms", () => {
+  const { taskMarket, swarmCoordinator } = programs();
+
+  const REWARD = 80_000_000;
+  const creator = Keypair.generate();
+  const operator = Keypair.generate();
+
+  before(async () => {
+    await fund(creator, operator);
+    await setupMarket();
+  });
+
+  it("rejects group tasks not created by a task-market task", async () => {
+    const accounts = await groupTaskAccounts(creator.publicKey);
+    await expectError(
+      swarmCoordinator.methods
+        .createMarketGroupTask("Field survey", "Map 40ha", 2, new BN(REWARD), new BN(3_600))
+        .accountsPartial({
+          coordinator: accounts.coordinator,
+          groupTask: accounts.groupTask,
+          creator: creator.publicKey,
+          marketTask: creator.publicKey,
+        })
+        .signers([creator])
+        .rpc(),
+      "ConstraintOwner"
+    );
+  });
+
+  it("rejects more robots than a swarm task can take", async () => {
+    await expectError(createTask(creator, { requiredRobots: 21 }), "InvalidRobotCount");
+  });
+
+  it("links a new multi-robot task to its group task", async () => {
+    const { groupTask } = await groupTaskAccounts(creator.publicKey);
+    const task = await createTask(creator, { reward: REWARD, requiredRobots: 3 });
+
+    expect((await taskMarket.account.task.fetch(task)).groupTask.toBase58()).to.equal(groupTask.toBase58());
+    const group: any = await swarmCoordinator.account.groupTask.fetch(groupTask);
+    expect(group.marketTask.toBase58()).to.equal(task.toBase58());
+    expect(group.creator.toBase58()).to.equal(creator.publicKey.toBase58());
+    expect(group.requiredRobots).to.equal(3);
+    expect(group.totalReward.toNumber()).to.equal(REWARD);
+  });
+
+  it("rejects robot bids on a task run by a swarm", async () => {
+    const task = await createTask(creator, { requiredRobots: 2 });
+    const robot = await registerRobot(operator);
+    await expectError(submitBid(task, creator.publicKey, robot, operator), "SwarmTask");
+  });
+});
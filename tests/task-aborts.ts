@@ -0,0 +1,80 @@
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  LateTask,
+  TaskStatus,
+  abortLateTask,
+  acceptBid,
+  drip,
+  expectError,
+  fund,
+  lateTask,
+  programs,
+  registerTaskMarketSlasher,
+  settlementAccounts,
+  stakedBidTask,
+  stakedOperator,
+  startTask,
+} from "./helpers";
+
+/**
+ * Task aborts: the creator or assigned robot can abort a task in progress,
+ * failing it. A creator abort of a late task is the robot's fault, costing
+ * it reputation and its operator stake; an abort on time costs neither.
+ */
+describe("Task Market: aborting tasks", () => {
+  const { taskMarket, droneosToken, identityRegistry } = programs();
+
+  const ABORT_REPUTATION_PENALTY = 500;
+
+  /** A staked operator's task in progress, within its deadline */
+  async function onTimeTask(): Promise<LateTask> {
+    await registerTaskMarketSlasher();
+    const a = await stakedBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await startTask(a).rpc();
+    const { operatorStake } = await stakedOperator();
+    return { ...a, stream: await settlementAccounts(a), operatorStake, creatorDroneos: await drip(a.creator) };
+  }
+
+  async function slashCount() {
+    const { operatorStake } = await stakedOperator();
+    return (await droneosToken.account.operatorStake.fetch(operatorStake)).slashCount.toNumber();
+  }
+
+  it("rejects aborts by anyone but the creator or assigned robot", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(abortLateTask(await onTimeTask(), {}, intruder), "Unauthorized");
+  });
+
+  it("fails a task aborted on time without fault", async () => {
+    const a = await onTimeTask();
+    const slashes = await slashCount();
+    await abortLateTask(a);
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.status).to.equal(TaskStatus.Failed);
+    expect(task.robotFault).to.equal(0);
+    expect(await slashCount()).to.equal(slashes);
+
+    const robot = await identityRegistry.account.robot.fetch(a.robot);
+    expect(robot.reputationScore).to.equal(5_000);
+    expect(robot.status).to.have.property("available");
+  });
+
+  it("penalizes the robot and slashes its operator for a late task", async () => {
+    const late = await lateTask();
+    const slashes = await slashCount();
+    await abortLateTask(late);
+
+    const task: any = await taskMarket.account.task.fetch(late.task);
+    expect(task.status).to.equal(TaskStatus.Failed);
+    expect(task.robotFault).to.equal(1);
+    expect(await slashCount()).to.equal(slashes + 1);
+
+    const robot = await identityRegistry.account.robot.fetch(late.robot);
+    expect(robot.reputationScore).to.equal(5_000 - ABORT_REPUTATION_PENALTY);
+    expect(robot.status).to.have.property("available");
+  });
+});
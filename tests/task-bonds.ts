@@ -0,0 +1,110 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { createHash } from "crypto";
+import { expect } from "chai";
+import {
+  abortLateTask,
+  acceptBid,
+  completeTask,
+  expectError,
+  fund,
+  lateTask,
+  openBidTask,
+  pda,
+  programs,
+  settlementAccounts,
+  setupMarket,
+  stakedBidTask,
+  stakedOperator,
+  startTask,
+  verifyCompletion,
+} from "./helpers";
+
+/**
+ * Performance bonds: with bonds on, accepting a bid holds the market's
+ * share of the task's reward in its operator's stake, released when the
+ * task settles and paid to the creator when it fails through robot fault.
+ */
+describe("Task Market: performance bonds", () => {
+  const { taskMarket, droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+  const market = pda(taskMarket.programId, Buffer.from("market"));
+
+  const BOND_BPS = 1_000;
+  const REWARD = 50_000_000;
+  const BOND = (REWARD * BOND_BPS) / 10_000;
+
+  function setTaskBondBps(bps: number) {
+    return taskMarket.methods.setTaskBondBps(bps).accountsPartial({ market, authority }).rpc();
+  }
+
+  async function lockedBonds() {
+    const { operatorStake } = await stakedOperator();
+    return (await droneosToken.account.operatorStake.fetch(operatorStake)).lockedTaskBonds.toNumber();
+  }
+
+  before(async () => {
+    await setupMarket();
+    await stakedOperator();
+  });
+
+  after(async () => {
+    // The market is shared with other test files
+    await setTaskBondBps(0);
+  });
+
+  it("rejects bond terms set by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      taskMarket.methods
+        .setTaskBondBps(BOND_BPS)
+        .accountsPartial({ market, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects a bond above the whole reward", async () => {
+    await expectError(setTaskBondBps(10_001), "InvalidTaskBond");
+  });
+
+  it("rejects assigning a bonded task to an operator without stake", async () => {
+    await setTaskBondBps(BOND_BPS);
+    const a = await openBidTask();
+    await expectError(acceptBid(a).signers([a.creator]).rpc(), "TaskBondRequired");
+  });
+
+  it("holds the bond while assigned and releases it on approval", async () => {
+    const a = await stakedBidTask({ reward: REWARD });
+    const before = await lockedBonds();
+    await acceptBid(a).signers([a.creator]).rpc();
+
+    expect((await taskMarket.account.task.fetch(a.task)).bond.toNumber()).to.equal(BOND);
+    expect((await lockedBonds()) - before).to.equal(BOND);
+
+    await startTask(a).rpc();
+    await completeTask(a, createHash("sha256").update("pylons 3-7").digest()).rpc();
+    await verifyCompletion(a, await settlementAccounts(a)).rpc();
+
+    expect((await taskMarket.account.task.fetch(a.task)).bond.toNumber()).to.equal(0);
+    expect(await lockedBonds()).to.equal(before);
+  });
+
+  it("pays the bond to the creator when the robot is at fault", async () => {
+    const late = await lateTask({ reward: REWARD });
+    const before = await lockedBonds();
+    await abortLateTask(late);
+
+    const task: any = await taskMarket.account.task.fetch(late.task);
+    expect(task.bond.toNumber()).to.equal(0);
+    expect(task.bondForfeited.toNumber()).to.equal(BOND);
+    expect(before - (await lockedBonds())).to.equal(BOND);
+
+    const paid = await getAccount(connection, late.creatorDroneos, undefined, TOKEN_2022_PROGRAM_ID);
+    expect(Number(paid.amount)).to.equal(BOND);
+  });
+});
@@ -0,0 +1,170 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { createHash } from "crypto";
+import { expect } from "chai";
+import {
+  Assignment,
+  TaskStatus,
+  acceptBid,
+  bidderAccounts,
+  completeTask,
+  expectError,
+  fund,
+  openBidTask,
+  operatorSlashAccounts,
+  pda,
+  programs,
+  settlementAccounts,
+  setupOracle,
+  startTask,
+  submitCompletionProof,
+  verifyCompletion,
+  verifyProof,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Task disputes: a creator disputing a completion links the oracle-verifier
+ * dispute they raise against its completion proof, and the task settles on
+ * that dispute's outcome, or as rejected once the market's dispute window
+ * passes without one.
+ */
+describe("Task Market: disputes", () => {
+  const { taskMarket, oracleVerifier, identityRegistry } = programs();
+  const authority = anchor.getProvider().publicKey!;
+  const market = pda(taskMarket.programId, Buffer.from("market"));
+
+  // Long enough to link and check a dispute before it passes
+  const WINDOW = 10;
+  const deliverable = createHash("sha256").update("pylons 3-7: no cracks found").digest();
+  const intruder = Keypair.generate();
+  let originalWindow: BN;
+
+  function setDisputeWindow(window: number | BN) {
+    return taskMarket.methods.setDisputeWindow(new BN(window)).accountsPartial({ market, authority }).rpc();
+  }
+
+  /** A completed task its creator disputed, with a verified completion proof */
+  async function disputedTask() {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await startTask(a).rpc();
+    await completeTask(a, deliverable).rpc();
+    const proof = await submitCompletionProof(a, deliverable);
+    await verifyProof(proof);
+    const stream = await settlementAccounts(a);
+    await verifyCompletion(a, stream, false).rpc();
+    return { a, stream, proof };
+  }
+
+  /** Raise an oracle-verifier dispute against `proof` as `challenger` */
+  async function createDispute(proof: PublicKey, challenger: Keypair) {
+    const { verifier } = await setupOracle();
+    const dispute = pda(
+      oracleVerifier.programId,
+      Buffer.from("dispute"),
+      proof.toBuffer(),
+      challenger.publicKey.toBuffer()
+    );
+    await oracleVerifier.methods
+      .createDispute("Pylon 5 was never inspected", "ipfs://counter-evidence")
+      .accountsPartial({ verifier, proof, dispute, challenger: challenger.publicKey })
+      .signers([challenger])
+      .rpc();
+    return dispute;
+  }
+
+  function openDispute(a: Assignment, dispute: PublicKey, signer = a.creator) {
+    return taskMarket.methods
+      .openDispute()
+      .accountsPartial({ task: a.task, dispute, creator: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  async function resolveDispute(
+    a: Assignment,
+    stream: Awaited<ReturnType<typeof settlementAccounts>>,
+    dispute: PublicKey | null
+  ) {
+    return taskMarket.methods
+      .resolveDispute()
+      .accountsPartial({
+        market,
+        task: a.task,
+        dispute,
+        stream,
+        operatorStake: bidderAccounts(a.task, a.creator.publicKey, a.operator.publicKey).operatorStake,
+        slash: await operatorSlashAccounts(a.operator.publicKey, authority),
+        robot: a.robot,
+        registryEventAuthority: pda(identityRegistry.programId, Buffer.from("__event_authority")),
+      })
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(intruder);
+    ({ disputeWindow: originalWindow } = await taskMarket.account.market.fetch(market));
+    await setDisputeWindow(WINDOW);
+  });
+
+  after(async () => {
+    // The market is shared with other test files
+    await setDisputeWindow(originalWindow);
+  });
+
+  it("rejects a zero dispute window", async () => {
+    await expectError(setDisputeWindow(0), "InvalidDisputeWindow");
+  });
+
+  it("marks a disputed completion and starts its dispute window", async () => {
+    const { a } = await disputedTask();
+
+    const task: any = await taskMarket.account.task.fetch(a.task);
+    expect(task.status).to.equal(TaskStatus.Disputed);
+    expect(task.disputeDueAt.toNumber()).to.be.gt(0);
+  });
+
+  it("rejects linking a dispute by anyone but the creator or raised by another", async () => {
+    const { a, proof } = await disputedTask();
+    const dispute = await createDispute(proof, intruder);
+
+    await expectError(openDispute(a, dispute, intruder), "Unauthorized");
+    await expectError(openDispute(a, dispute), "DisputeMismatch");
+  });
+
+  it("links the creator's dispute once and holds settlement while it is open", async () => {
+    const { a, stream, proof } = await disputedTask();
+    const dispute = await createDispute(proof, a.creator);
+    await openDispute(a, dispute);
+
+    expect((await taskMarket.account.task.fetch(a.task)).dispute.toBase58()).to.equal(dispute.toBase58());
+    await expectError(openDispute(a, dispute), "DisputeAlreadyOpen");
+    await expectError(resolveDispute(a, stream, null), "DisputeMismatch");
+    await expectError(resolveDispute(a, stream, dispute), "DisputeNotResolved");
+  });
+
+  it("settles a dispute still undecided after the window as rejected", async () => {
+    const { a, stream, proof } = await disputedTask();
+    const dispute = await createDispute(proof, a.creator);
+    await openDispute(a, dispute);
+
+    const { disputeDueAt } = await taskMarket.account.task.fetch(a.task);
+    await waitForClock(disputeDueAt);
+    await resolveDispute(a, stream, dispute);
+
+    expect((await taskMarket.account.task.fetch(a.task)).status).to.equal(TaskStatus.Completed);
+  });
+
+  it("settles a dispute never raised as rejected after the window", async () => {
+    const { a, stream } = await disputedTask();
+    await expectError(resolveDispute(a, stream, null), "DisputeNotResolved");
+
+    const { disputeDueAt } = await taskMarket.account.task.fetch(a.task);
+    await waitForClock(disputeDueAt);
+    await resolveDispute(a, stream, null);
+
+    expect((await taskMarket.account.task.fetch(a.task)).status).to.equal(TaskStatus.Completed);
+  });
+});
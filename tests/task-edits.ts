@@ -0,0 +1,94 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  INSPECTION,
+  acceptBid,
+  createTask,
+  expectError,
+  fund,
+  openBidTask,
+  programs,
+  registerRobot,
+  setupMarket,
+  submitBid,
+} from "./helpers";
+
+interface TaskEdit {
+  description?: string;
+  reward?: number;
+  expiresIn?: number;
+  capabilities?: number[];
+}
+
+/**
+ * Task edits: a creator can change an open task's description, reward,
+ * expiry and capabilities until the first bid arrives.
+ */
+describe("Task Market: editing open tasks", () => {
+  const { taskMarket } = programs();
+
+  const creator = Keypair.generate();
+  let task: PublicKey;
+
+  function updateTask(edit: TaskEdit, signer = creator, address = task) {
+    return taskMarket.methods
+      .updateTask(
+        edit.description ?? null,
+        edit.reward === undefined ? null : new BN(edit.reward),
+        edit.expiresIn === undefined ? null : new BN(edit.expiresIn),
+        edit.capabilities === undefined ? null : Buffer.from(edit.capabilities)
+      )
+      .accountsPartial({ task: address, creator: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(creator);
+    await setupMarket();
+    task = await createTask(creator);
+  });
+
+  it("rejects edits by anyone but the creator", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(updateTask({ reward: 1 }, intruder), "Unauthorized");
+  });
+
+  it("rejects invalid values", async () => {
+    await expectError(updateTask({ reward: 0 }), "InvalidReward");
+    await expectError(updateTask({ expiresIn: 0 }), "InvalidExpiration");
+    await expectError(updateTask({ expiresIn: 7 * 86_400 + 1 }), "InvalidExpiration");
+    await expectError(updateTask({ description: "x".repeat(257) }), "DescriptionTooLong");
+    await expectError(updateTask({ capabilities: Array(6).fill(INSPECTION) }), "TooManyCapabilities");
+  });
+
+  it("replaces only the fields given", async () => {
+    const before: any = await taskMarket.account.task.fetch(task);
+    await updateTask({ description: "Inspect pylons 1-9", reward: 60_000_000, expiresIn: 3_600 });
+
+    const after: any = await taskMarket.account.task.fetch(task);
+    const description = Buffer.from(after.description.slice(0, after.descriptionLen)).toString();
+    expect(description).to.equal("Inspect pylons 1-9");
+    expect(after.reward.toNumber()).to.equal(60_000_000);
+    expect(after.expiresAt.toNumber()).to.be.lt(before.expiresAt.toNumber());
+    expect(after.ratePerSecond.toNumber()).to.equal(before.ratePerSecond.toNumber());
+    expect(after.capabilitiesLen).to.equal(before.capabilitiesLen);
+  });
+
+  it("rejects edits once the task has a bid", async () => {
+    const operator = Keypair.generate();
+    await fund(operator);
+    await submitBid(task, creator.publicKey, await registerRobot(operator), operator);
+
+    await expectError(updateTask({ reward: 70_000_000 }), "TaskHasBids");
+  });
+
+  it("rejects edits once the task is assigned", async () => {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+
+    await expectError(updateTask({ reward: 70_000_000 }, a.creator, a.task), "TaskNotOpen");
+  });
+});
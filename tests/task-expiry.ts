@@ -0,0 +1,95 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  TaskStatus,
+  createTask,
+  expectError,
+  fund,
+  programs,
+  registerRobot,
+  setupMarket,
+  submitBid,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Task expiry: anyone can cancel an open task once its bidding window has
+ * passed, and its creator then closes it and its bids, returning their rent.
+ */
+describe("Task Market: task expiry and cleanup", () => {
+  const { taskMarket } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const EXPIRES_IN = 3;
+  const creator = Keypair.generate();
+  const operator = Keypair.generate();
+  let task: PublicKey;
+  let bid: PublicKey;
+
+  function expireTask(address = task) {
+    return taskMarket.methods.expireTask().accountsPartial({ task: address }).rpc();
+  }
+
+  function closeTask(signer = creator, bids: [PublicKey, PublicKey][] = []) {
+    return taskMarket.methods
+      .closeTask()
+      .accountsPartial({ task, creator: signer.publicKey })
+      .remainingAccounts(
+        bids.flatMap(([bid, operator]) => [
+          { pubkey: bid, isSigner: false, isWritable: true },
+          { pubkey: operator, isSigner: false, isWritable: true },
+        ])
+      )
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(creator, operator);
+    await setupMarket();
+    const robot = await registerRobot(operator);
+    task = await createTask(creator, { expiresIn: EXPIRES_IN });
+    bid = await submitBid(task, creator.publicKey, robot, operator);
+  });
+
+  it("rejects closing a task that hasn't finished", async () => {
+    await expectError(closeTask(), "TaskNotFinished");
+  });
+
+  it("rejects expiring a task within its bidding window", async () => {
+    await expectError(expireTask(), "TaskNotExpired");
+  });
+
+  it("cancels an open task past its bidding window", async () => {
+    const { expiresAt } = await taskMarket.account.task.fetch(task);
+    await waitForClock(expiresAt);
+    await expireTask();
+
+    const expired: any = await taskMarket.account.task.fetch(task);
+    expect(expired.status).to.equal(TaskStatus.Cancelled);
+    await expectError(expireTask(), "TaskNotOpen");
+  });
+
+  it("rejects closing by anyone but the creator", async () => {
+    await expectError(closeTask(operator), "Unauthorized");
+  });
+
+  it("rejects closing a bid into anyone but its operator", async () => {
+    await expectError(closeTask(creator, [[bid, creator.publicKey]]), "Unauthorized");
+  });
+
+  it("closes the task and its bids, returning their rent", async () => {
+    const bidRent = await connection.getBalance(bid);
+    const operatorBefore = await connection.getBalance(operator.publicKey);
+    const taskRent = await connection.getBalance(task);
+    const creatorBefore = await connection.getBalance(creator.publicKey);
+
+    await closeTask(creator, [[bid, operator.publicKey]]);
+
+    expect(await connection.getAccountInfo(task)).to.equal(null);
+    expect(await connection.getAccountInfo(bid)).to.equal(null);
+    expect((await connection.getBalance(operator.publicKey)) - operatorBefore).to.equal(bidRent);
+    expect((await connection.getBalance(creator.publicKey)) - creatorBefore).to.equal(taskRent);
+  });
+});
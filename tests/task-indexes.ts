@@ -0,0 +1,137 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  INSPECTION,
+  acceptBid,
+  createTask,
+  expectError,
+  fund,
+  initializeOnce,
+  openBidTask,
+  pda,
+  programs,
+  setupMarket,
+} from "./helpers";
+
+/**
+ * Task indexes: paged per-class and per-capability lists of tasks that
+ * anyone can append open tasks to and resync, so discovery tooling can find
+ * open tasks without scanning every account.
+ */
+describe("Task Market: task indexes", () => {
+  const { taskMarket } = programs();
+  const payer = anchor.getProvider().publicKey!;
+
+  const CAPABILITY = { capability: {} };
+  const CLASS = { class: {} };
+  const GROUND = 1;
+  let inspection: PublicKey;
+  let firstPage: PublicKey;
+
+  function indexAddress(kind: number, key: number) {
+    return pda(taskMarket.programId, Buffer.from("task-index"), Buffer.from([kind]), Buffer.from([key]));
+  }
+
+  function pageAddress(index: PublicKey, page: number) {
+    return pda(
+      taskMarket.programId,
+      Buffer.from("task-index-page"),
+      index.toBuffer(),
+      new BN(page).toArrayLike(Buffer, "le", 4)
+    );
+  }
+
+  function createIndex(kind: object, kindByte: number, key: number) {
+    const index = indexAddress(kindByte, key);
+    return taskMarket.methods
+      .createTaskIndex(kind as any, key)
+      .accountsPartial({ index, page: pageAddress(index, 0), payer })
+      .rpc();
+  }
+
+  function indexTask(task: PublicKey, index = inspection) {
+    return taskMarket.methods.indexTask().accountsPartial({ task, index, page: pageAddress(index, 0) }).rpc();
+  }
+
+  function syncTaskIndex(task: PublicKey, slot: number) {
+    return taskMarket.methods.syncTaskIndex(slot).accountsPartial({ page: firstPage, task }).rpc();
+  }
+
+  async function slotOf(task: PublicKey): Promise<number> {
+    const page: any = await taskMarket.account.taskIndexPage.fetch(firstPage);
+    return page.tasks.findIndex((t: PublicKey) => t.equals(task));
+  }
+
+  async function isOpen(slot: number): Promise<boolean> {
+    const page: any = await taskMarket.account.taskIndexPage.fetch(firstPage);
+    return !page.open.and(new BN(1).shln(slot)).isZero();
+  }
+
+  before(async () => {
+    await setupMarket();
+    inspection = indexAddress(1, INSPECTION);
+    firstPage = pageAddress(inspection, 0);
+    await initializeOnce(() => createIndex(CAPABILITY, 1, INSPECTION));
+  });
+
+  it("rejects indexes for unknown classes or capabilities", async () => {
+    await expectError(createIndex(CLASS, 0, 5), "InvalidRobotClass");
+    await expectError(createIndex(CAPABILITY, 1, 10), "InvalidCapability");
+  });
+
+  it("appends an open task, marked open, once", async () => {
+    const creator = Keypair.generate();
+    await fund(creator);
+    const task = await createTask(creator);
+    await indexTask(task);
+
+    const slot = await slotOf(task);
+    expect(slot).to.be.gte(0);
+    expect(await isOpen(slot)).to.equal(true);
+    await expectError(indexTask(task), "TaskAlreadyIndexed");
+  });
+
+  it("rejects indexing a task under a class it isn't", async () => {
+    const creator = Keypair.generate();
+    await fund(creator);
+    const task = await createTask(creator);
+    await initializeOnce(() => createIndex(CLASS, 0, GROUND));
+
+    await expectError(indexTask(task, indexAddress(0, GROUND)), "TaskIndexMismatch");
+  });
+
+  it("rejects indexing a task that isn't open", async () => {
+    const a = await openBidTask();
+    await acceptBid(a).signers([a.creator]).rpc();
+    await expectError(indexTask(a.task), "TaskNotOpen");
+  });
+
+  it("clears a task's open bit once it is assigned", async () => {
+    const a = await openBidTask();
+    await indexTask(a.task);
+    const slot = await slotOf(a.task);
+
+    await acceptBid(a).signers([a.creator]).rpc();
+    expect(await isOpen(slot)).to.equal(true);
+    await expectError(syncTaskIndex(a.task, slot + 1), "TaskIndexMismatch");
+    await syncTaskIndex(a.task, slot);
+    expect(await isOpen(slot)).to.equal(false);
+  });
+
+  it("rejects a page until the last one is full", async () => {
+    await expectError(
+      taskMarket.methods
+        .addTaskIndexPage()
+        .accountsPartial({
+          index: inspection,
+          lastPage: firstPage,
+          page: pageAddress(inspection, 1),
+          payer,
+        })
+        .rpc(),
+      "TaskIndexPageNotFull"
+    );
+  });
+});
@@ -0,0 +1,150 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  CRANK_TIP,
+  LateTask,
+  TokenSetup,
+  abortLateTask,
+  createTask,
+  drip,
+  expectError,
+  fund,
+  lateTask,
+  pda,
+  programs,
+  setupMarket,
+  setupToken,
+} from "./helpers";
+
+/**
+ * Task insurance: an insured task's creator pays a premium on acceptance
+ * and, once the task fails through its robot's fault, is repaid what the
+ * stream paid the operator, up to the insured amount, exactly once.
+ */
+describe("Task Market: task insurance", () => {
+  const { taskMarket, droneosToken, paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const PREMIUM_BPS = 500;
+  const INSURED = 10_000_000;
+  const intruder = Keypair.generate();
+  let market: PublicKey;
+  let t: TokenSetup;
+  let insured: LateTask;
+  let originalPremium: number;
+  let originalShare: number;
+
+  function setPremium(bps: number, signer?: Keypair) {
+    return taskMarket.methods
+      .setInsurancePremiumBps(bps)
+      .accountsPartial({ market, authority: signer?.publicKey ?? authority })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  function setInsuranceShare(bps: number) {
+    return droneosToken.methods.setInsuranceShare(bps).accountsPartial({ config: t.config, authority }).rpc();
+  }
+
+  function claimInsurance(task: PublicKey, creatorToken = insured.creatorDroneos) {
+    return taskMarket.methods
+      .claimTaskInsurance()
+      .accountsPartial({
+        market,
+        task,
+        stream: insured.stream.stream,
+        tokenConfig: t.config,
+        insurancePool: t.insurancePool,
+        insuranceVault: t.insuranceVault,
+        creatorToken,
+        mint: t.mint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+        tokenEventAuthority: pda(droneosToken.programId, Buffer.from("__event_authority")),
+      })
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  before(async () => {
+    await fund(intruder);
+    market = await setupMarket();
+    t = await setupToken();
+    ({ insurancePremiumBps: originalPremium } = await taskMarket.account.market.fetch(market));
+    ({ insuranceShareBps: originalShare } = await droneosToken.account.tokenConfig.fetch(t.config));
+
+    // The failed task's slash keeps the pool able to cover its loss
+    await setInsuranceShare(10_000);
+  });
+
+  after(async () => {
+    // The market and token config are shared with other test files
+    await setPremium(originalPremium);
+    await setInsuranceShare(originalShare);
+  });
+
+  it("rejects insured tasks while the market sells no insurance", async () => {
+    await setPremium(0);
+    const creator = Keypair.generate();
+    await fund(creator);
+    await expectError(createTask(creator, { insuredAmount: INSURED }), "InsuranceDisabled");
+  });
+
+  it("rejects premium rates set by anyone but the authority, or over 100%", async () => {
+    await expectError(setPremium(PREMIUM_BPS, intruder), "Unauthorized");
+    await expectError(setPremium(10_001), "InvalidInsurancePremium");
+  });
+
+  it("rejects insuring more than the reward", async () => {
+    await setPremium(PREMIUM_BPS);
+    const creator = Keypair.generate();
+    await fund(creator);
+    await expectError(createTask(creator, { reward: INSURED, insuredAmount: INSURED + 1 }), "InvalidInsuredAmount");
+  });
+
+  it("charges the creator the premium on acceptance", async () => {
+    insured = await lateTask({ insuredAmount: INSURED });
+
+    const task: any = await taskMarket.account.task.fetch(insured.task);
+    const premium = task.insurancePremium.toNumber();
+    // At most the market rate, for a robot with no reputation
+    expect(premium).to.be.gt(0);
+    expect(premium).to.be.lte((INSURED * PREMIUM_BPS) / 10_000);
+    expect(await balance(insured.creatorDroneos)).to.equal(CRANK_TIP - premium);
+  });
+
+  it("rejects claims on uninsured tasks", async () => {
+    const uninsured = await createTask(insured.creator);
+    await expectError(claimInsurance(uninsured), "TaskNotInsured");
+  });
+
+  it("rejects claims before the task fails through the robot's fault", async () => {
+    await expectError(claimInsurance(insured.task), "NotRobotFault");
+  });
+
+  it("pays the claim to the creator only", async () => {
+    await abortLateTask(insured);
+    await expectError(claimInsurance(insured.task, await drip(intruder)), "Unauthorized");
+  });
+
+  it("repays what the stream paid the operator, up to the insured amount", async () => {
+    const before = await balance(insured.creatorDroneos);
+    await claimInsurance(insured.task);
+
+    const stream: any = await paymentStreams.account.paymentStream.fetch(insured.stream.stream);
+    const task: any = await taskMarket.account.task.fetch(insured.task);
+    const covered = Math.min(stream.totalPaid.sub(task.bondForfeited).toNumber(), INSURED);
+    expect(covered).to.be.gt(0);
+    expect((await balance(insured.creatorDroneos)) - before).to.equal(covered);
+    expect(task.insuranceClaimed).to.equal(1);
+  });
+
+  it("rejects a second claim", async () => {
+    await expectError(claimInsurance(insured.task), "InsuranceAlreadyClaimed");
+  });
+});
@@ -0,0 +1,110 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { INSPECTION, TaskStatus, expectError, fund, pda, programs, setupMarket, u64 } from "./helpers";
+
+/**
+ * Task templates: a publisher stores task parameters once, any creator opens
+ * tasks from them, and the publisher alone can close the template.
+ */
+describe("Task Market: task templates", () => {
+  const { taskMarket } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const TEMPLATE_ID = 7;
+  const publisher = Keypair.generate();
+  const creator = Keypair.generate();
+  let market: PublicKey;
+  let template: PublicKey;
+
+  const params = {
+    title: "Solar panel inspection",
+    description: "Thermal scan of every panel row",
+    robotClass: 0,
+    capabilities: Buffer.from([INSPECTION]),
+    minReputation: 0,
+    reward: new BN(20_000_000),
+    ratePerSecond: new BN(2_000),
+    estimatedDuration: 2_400,
+    priority: 3,
+    expiresIn: new BN(86_400),
+    requiresProof: false,
+  };
+
+  function templateAddress(id: number) {
+    return pda(taskMarket.programId, Buffer.from("task-template"), publisher.publicKey.toBuffer(), u64(id));
+  }
+
+  function createTemplate(id: number, overrides: Partial<typeof params> = {}) {
+    return taskMarket.methods
+      .createTaskTemplate(new BN(id), { ...params, ...overrides })
+      .accountsPartial({ template: templateAddress(id), publisher: publisher.publicKey })
+      .signers([publisher])
+      .rpc();
+  }
+
+  async function createFromTemplate(): Promise<PublicKey> {
+    const { totalTasks } = await taskMarket.account.market.fetch(market);
+    const task = pda(taskMarket.programId, Buffer.from("task"), creator.publicKey.toBuffer(), u64(totalTasks));
+    await taskMarket.methods
+      .createTaskFromTemplate()
+      .accountsPartial({ market, template, task, creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+    return task;
+  }
+
+  function closeTemplate(signer: Keypair) {
+    return taskMarket.methods
+      .closeTaskTemplate()
+      .accountsPartial({ template, publisher: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(publisher, creator);
+    market = await setupMarket();
+    template = templateAddress(TEMPLATE_ID);
+  });
+
+  it("rejects invalid parameters", async () => {
+    await expectError(createTemplate(1, { reward: new BN(0) }), "InvalidReward");
+    await expectError(createTemplate(1, { priority: 6 }), "InvalidPriority");
+    await expectError(createTemplate(1, { expiresIn: new BN(0) }), "InvalidExpiration");
+  });
+
+  it("publishes a template", async () => {
+    await createTemplate(TEMPLATE_ID);
+
+    const account: any = await taskMarket.account.taskTemplate.fetch(template);
+    expect(account.publisher.toBase58()).to.equal(publisher.publicKey.toBase58());
+    expect(account.params.title).to.equal(params.title);
+    expect(account.tasksCreated.toNumber()).to.equal(0);
+  });
+
+  it("opens tasks from the template for any creator", async () => {
+    const task = await createFromTemplate();
+
+    const account: any = await taskMarket.account.task.fetch(task);
+    expect(account.creator.toBase58()).to.equal(creator.publicKey.toBase58());
+    expect(account.status).to.equal(TaskStatus.Open);
+    expect(account.reward.toNumber()).to.equal(params.reward.toNumber());
+    expect(account.ratePerSecond.toNumber()).to.equal(params.ratePerSecond.toNumber());
+    expect((await taskMarket.account.taskTemplate.fetch(template)).tasksCreated.toNumber()).to.equal(1);
+  });
+
+  it("rejects closing by anyone but the publisher", async () => {
+    await expectError(closeTemplate(creator), "Unauthorized");
+  });
+
+  it("closes the template, returning its rent", async () => {
+    const rent = await connection.getBalance(template);
+    const before = await connection.getBalance(publisher.publicKey);
+    await closeTemplate(publisher);
+
+    expect(await connection.getAccountInfo(template)).to.equal(null);
+    expect((await connection.getBalance(publisher.publicKey)) - before).to.equal(rent);
+  });
+});
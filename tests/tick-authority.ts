@@ -0,0 +1,71 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  programs,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Tick authority: a payer can restrict who ticks their stream to one
+ * designated cranker besides the payee, and make ticking permissionless
+ * again.
+ */
+describe("Payment Streams: tick authority", () => {
+  const { paymentStreams } = programs();
+
+  const keeper = Keypair.generate();
+  const stranger = Keypair.generate();
+  let s: Stream;
+
+  function setTickAuthority(tickAuthority: PublicKey | null, signer = s.payer) {
+    return paymentStreams.methods
+      .setTickAuthority(tickAuthority)
+      .accountsPartial({ stream: s.stream, authority: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  async function nextSecond() {
+    const { lastTickAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(lastTickAt).addn(1));
+  }
+
+  before(async () => {
+    await fund(keeper, stranger);
+    s = await openStream();
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("rejects a tick authority set by anyone but the payer", async () => {
+    await expectError(setTickAuthority(keeper.publicKey, s.payee), "Unauthorized");
+  });
+
+  it("refuses ticks from anyone but the tick authority and the payee", async () => {
+    await setTickAuthority(keeper.publicKey);
+    await nextSecond();
+
+    await expectError(tick(s, stranger).rpc(), "UnauthorizedCranker");
+    await tick(s, keeper).rpc();
+    await nextSecond();
+    await tick(s, s.payee).rpc();
+
+    expect((await paymentStreams.account.paymentStream.fetch(s.stream)).totalTicks).to.equal(2);
+  });
+
+  it("opens ticking to anyone again", async () => {
+    await setTickAuthority(null);
+    await nextSecond();
+    await tick(s, stranger).rpc();
+
+    expect((await paymentStreams.account.paymentStream.fetch(s.stream)).totalTicks).to.equal(3);
+  });
+});
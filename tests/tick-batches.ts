@@ -0,0 +1,89 @@
+import { BN } from "@coral-xyz/anchor";
+import { AccountMeta, PublicKey } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  openStream,
+  programs,
+  startStream,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Tick batches: tick_many ticks every (stream, escrow, payee_token) triple
+ * passed in one mint, skipping any stream that can't be ticked instead of
+ * failing the rest.
+ */
+describe("Payment Streams: tick batches", () => {
+  const { paymentStreams } = programs();
+
+  let first: Stream;
+  let second: Stream;
+  let pending: Stream;
+
+  function writable(...keys: PublicKey[]): AccountMeta[] {
+    return keys.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }));
+  }
+
+  function triple(s: Stream) {
+    return writable(s.stream, s.escrow, s.payeeToken);
+  }
+
+  function tickMany(accounts: AccountMeta[]) {
+    return paymentStreams.methods
+      .tickMany()
+      .accountsPartial({
+        mint: first.mint,
+        treasury: first.treasury,
+        cranker: first.payer.publicKey,
+        crankerToken: null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts(accounts)
+      .signers([first.payer])
+      .rpc();
+  }
+
+  async function totalTicks(s: Stream) {
+    return (await paymentStreams.account.paymentStream.fetch(s.stream)).totalTicks;
+  }
+
+  before(async () => {
+    first = await openStream();
+    second = await openStream({ from: first });
+    pending = await openStream({ from: first });
+    for (const s of [first, second]) {
+      await acceptStream(s).rpc();
+      await startStream(s).rpc();
+    }
+    const { lastTickAt } = await paymentStreams.account.paymentStream.fetch(second.stream);
+    await waitForClock(new BN(lastTickAt).addn(1));
+  });
+
+  it("rejects an empty batch or a partial triple", async () => {
+    await expectError(tickMany([]), "InvalidTickBatch");
+    await expectError(tickMany(triple(first).slice(0, 2)), "InvalidTickBatch");
+  });
+
+  it("ticks every stream in the batch", async () => {
+    await tickMany([...triple(first), ...triple(second)]);
+
+    expect(await totalTicks(first)).to.equal(1);
+    expect(await totalTicks(second)).to.equal(1);
+  });
+
+  it("skips streams it can't tick without failing the rest", async () => {
+    const { lastTickAt } = await paymentStreams.account.paymentStream.fetch(first.stream);
+    await waitForClock(new BN(lastTickAt).addn(1));
+    // A stream that hasn't started, and one paired with the wrong payee token
+    const mismatched = writable(second.stream, second.escrow, first.payeeToken);
+    await tickMany([...triple(pending), ...mismatched, ...triple(first)]);
+
+    expect(await totalTicks(first)).to.equal(2);
+    expect(await totalTicks(second)).to.equal(1);
+    expect(await totalTicks(pending)).to.equal(0);
+  });
+});
@@ -0,0 +1,100 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  pda,
+  programs,
+  setupMarket,
+  startStream,
+  tick,
+  waitForClock,
+} from "./helpers";
+
+/**
+ * Minimum tick interval: ticks closer together than the program's minimum,
+ * or a stream's own override set before it starts, are refused.
+ */
+describe("Payment Streams: minimum tick interval", () => {
+  const { paymentStreams } = programs();
+  const authority = anchor.getProvider().publicKey!;
+  const config = pda(paymentStreams.programId, Buffer.from("config"));
+
+  function setMinTickInterval(seconds: number) {
+    return paymentStreams.methods
+      .setMinTickInterval(new BN(seconds))
+      .accountsPartial({ config, authority })
+      .rpc();
+  }
+
+  function setStreamMinTickInterval(s: Stream, seconds: number | null) {
+    return paymentStreams.methods
+      .setStreamMinTickInterval(seconds === null ? null : new BN(seconds))
+      .accountsPartial({ stream: s.stream, authority: s.payer.publicKey })
+      .signers([s.payer])
+      .rpc();
+  }
+
+  async function started(s: Stream): Promise<Stream> {
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+    const { startedAt } = await paymentStreams.account.paymentStream.fetch(s.stream);
+    await waitForClock(new BN(startedAt).addn(1));
+    return s;
+  }
+
+  before(async () => {
+    await setupMarket();
+  });
+
+  after(async () => {
+    // The config is shared with other test files
+    await setMinTickInterval(0);
+  });
+
+  it("rejects a minimum set by anyone but the authority", async () => {
+    const intruder = Keypair.generate();
+    await fund(intruder);
+    await expectError(
+      paymentStreams.methods
+        .setMinTickInterval(new BN(60))
+        .accountsPartial({ config, authority: intruder.publicKey })
+        .signers([intruder])
+        .rpc(),
+      "Unauthorized"
+    );
+  });
+
+  it("rejects negative intervals", async () => {
+    await expectError(setMinTickInterval(-1), "InvalidDuration");
+    await expectError(setStreamMinTickInterval(await openStream(), -1), "InvalidDuration");
+  });
+
+  it("rejects a stream override once the payee has accepted", async () => {
+    const s = await openStream();
+    await acceptStream(s).rpc();
+    await expectError(setStreamMinTickInterval(s, 1), "StreamNotPending");
+  });
+
+  it("refuses ticks within the program minimum", async () => {
+    await setMinTickInterval(3_600);
+    const s = await started(await openStream());
+
+    await expectError(tick(s).rpc(), "TickTooSoon");
+  });
+
+  it("lets a stream's override replace the program minimum", async () => {
+    const s = await openStream();
+    await setStreamMinTickInterval(s, 1);
+    expect((await paymentStreams.account.paymentStream.fetch(s.stream)).minTickInterval.toNumber()).to.equal(1);
+    await started(s);
+
+    await tick(s).rpc();
+    expect((await paymentStreams.account.paymentStream.fetch(s.stream)).totalTicks).to.equal(1);
+  });
+});
@@ -0,0 +1,172 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, Transaction, sendAndConfirmTransaction } from "@solana/web3.js";
+import {
+  ExtensionType,
+  TOKEN_2022_PROGRAM_ID,
+  createAccount,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  getAccount,
+  getMintLen,
+  mintTo,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { expectError, fund, pda, programs, setupMarket, tokenFor, u64, waitForClock } from "./helpers";
+
+/**
+ * Token-2022 streams: streams can be paid in Token-2022 mints, and a mint's
+ * transfer fee comes out of the deposit, so the stream's escrow balance
+ * matches what its escrow account actually holds.
+ */
+describe("Payment Streams: Token-2022 mints", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const RATE = 1_000;
+  const DURATION = 3_600;
+  const TRANSFER_FEE_BPS = 100;
+  const payer = Keypair.generate();
+  const payee = Keypair.generate();
+  let mint: PublicKey;
+  let payerToken: PublicKey;
+  let payeeToken: PublicKey;
+  let treasury: PublicKey;
+  let stream: PublicKey;
+  let escrow: PublicKey;
+
+  function tick(streamTreasury = treasury) {
+    return paymentStreams.methods
+      .tick()
+      .accountsPartial({
+        stream,
+        mint,
+        claimToken: null,
+        payeeToken,
+        treasury: streamTreasury,
+        cranker: payee.publicKey,
+        crankerToken: null,
+        referrerAccount: null,
+        referrerToken: null,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([payee])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+  }
+
+  // A Token-2022 mint of `payer`'s charging TRANSFER_FEE_BPS on transfers
+  async function createFeeMint() {
+    const mintKeypair = Keypair.generate();
+    const space = getMintLen([ExtensionType.TransferFeeConfig]);
+    const tx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: payer.publicKey,
+        newAccountPubkey: mintKeypair.publicKey,
+        space,
+        lamports: await connection.getMinimumBalanceForRentExemption(space),
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      createInitializeTransferFeeConfigInstruction(
+        mintKeypair.publicKey,
+        payer.publicKey,
+        payer.publicKey,
+        TRANSFER_FEE_BPS,
+        BigInt(1_000_000_000),
+        TOKEN_2022_PROGRAM_ID
+      ),
+      createInitializeMintInstruction(mintKeypair.publicKey, 6, payer.publicKey, null, TOKEN_2022_PROGRAM_ID)
+    );
+    await sendAndConfirmTransaction(connection, tx, [payer, mintKeypair]);
+    return mintKeypair.publicKey;
+  }
+
+  before(async () => {
+    await fund(payer, payee);
+    await setupMarket();
+    mint = await createFeeMint();
+    const accountOf = (owner: Keypair) =>
+      createAccount(connection, owner, mint, owner.publicKey, undefined, undefined, TOKEN_2022_PROGRAM_ID);
+    payerToken = await accountOf(payer);
+    payeeToken = await accountOf(payee);
+    await mintTo(connection, payer, mint, payerToken, payer, 1_000_000_000, [], undefined, TOKEN_2022_PROGRAM_ID);
+
+    treasury = pda(paymentStreams.programId, Buffer.from("treasury"), mint.toBuffer());
+    await paymentStreams.methods
+      .initializeTreasury()
+      .accountsPartial({ treasury, mint, payer: payer.publicKey, tokenProgram: TOKEN_2022_PROGRAM_ID })
+      .signers([payer])
+      .rpc();
+
+    const nonce = new BN(0);
+    stream = pda(
+      paymentStreams.programId,
+      Buffer.from("stream"),
+      payer.publicKey.toBuffer(),
+      payee.publicKey.toBuffer(),
+      u64(nonce)
+    );
+    escrow = pda(paymentStreams.programId, Buffer.from("escrow"), stream.toBuffer());
+  });
+
+  it("credits the escrow with the deposit net of the transfer fee", async () => {
+    await paymentStreams.methods
+      .createStream(new BN(0), new BN(RATE), new BN(DURATION), new BN(60), true, new BN(0), new BN(0), null)
+      .accountsPartial({
+        stream,
+        mint,
+        payerToken,
+        payer: payer.publicKey,
+        payee: payee.publicKey,
+        payerStake: null,
+        referrerAccount: null,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([payer])
+      .rpc();
+
+    const deposit = RATE * DURATION;
+    const received = deposit - (deposit * TRANSFER_FEE_BPS) / 10_000;
+    const created: any = await paymentStreams.account.paymentStream.fetch(stream);
+    expect(created.escrowBalance.toNumber()).to.equal(received);
+    expect(await balance(escrow)).to.equal(received);
+  });
+
+  it("rejects a tick paying fees into another mint's treasury", async () => {
+    await paymentStreams.methods
+      .acceptStream()
+      .accountsPartial({ stream, payee: payee.publicKey })
+      .signers([payee])
+      .rpc();
+    await paymentStreams.methods
+      .startStream()
+      .accountsPartial({
+        stream,
+        mint,
+        claimToken: null,
+        payeeToken,
+        treasury,
+        payer: payer.publicKey,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([payer])
+      .rpc();
+
+    const { treasury: otherTreasury } = await tokenFor(payer, 0);
+    await expectError(tick(otherTreasury), "InvalidTreasury");
+  });
+
+  it("pays ticks out of the escrow in the Token-2022 mint", async () => {
+    const { startedAt } = await paymentStreams.account.paymentStream.fetch(stream);
+    await waitForClock(new BN(startedAt).addn(2));
+    await tick();
+
+    const ticked: any = await paymentStreams.account.paymentStream.fetch(stream);
+    expect(ticked.totalPaid.toNumber()).to.be.gt(0);
+    expect(await balance(escrow)).to.equal(ticked.escrowBalance.toNumber());
+    expect(await balance(payeeToken)).to.be.gt(0);
+  });
+});
@@ -0,0 +1,66 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+import { TOKEN_2022_PROGRAM_ID, getMint, getTokenMetadata } from "@solana/spl-token";
+import { expect } from "chai";
+import { TokenSetup, expectError, fund, programs, setupToken } from "./helpers";
+
+/**
+ * Token-2022 mint: DRONEOS is a Token-2022 mint carrying its own metadata,
+ * with the config PDA as metadata and transfer fee authority.
+ */
+describe("DRONEOS Token: Token-2022 mint", () => {
+  const { droneosToken } = programs();
+  const connection = anchor.getProvider().connection;
+  const authority = anchor.getProvider().publicKey!;
+
+  const intruder = Keypair.generate();
+  let t: TokenSetup;
+
+  function setTransferFee(basisPoints: number, signer?: Keypair) {
+    return droneosToken.methods
+      .setTransferFee(basisPoints, new BN(1_000_000))
+      .accountsPartial({
+        config: t.config,
+        mint: t.mint,
+        authority: signer?.publicKey ?? authority,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers(signer ? [signer] : [])
+      .rpc();
+  }
+
+  before(async () => {
+    await fund(intruder);
+    t = await setupToken();
+  });
+
+  it("creates the mint under Token-2022 with its metadata on the mint", async () => {
+    const mint = await getMint(connection, t.mint, undefined, TOKEN_2022_PROGRAM_ID);
+    expect(mint.decimals).to.equal(6);
+
+    const metadata = await getTokenMetadata(connection, t.mint, undefined, TOKEN_2022_PROGRAM_ID);
+    expect(metadata!.name).to.equal("DroneOS");
+    expect(metadata!.symbol).to.equal("DRONEOS");
+    expect(metadata!.uri).to.equal("https://droneos.dev/token.json");
+    expect(metadata!.updateAuthority!.toBase58()).to.equal(t.config.toBase58());
+  });
+
+  it("rejects initializing the token twice", async () => {
+    await expectError(
+      droneosToken.methods
+        .initialize("DroneOS", "DRONEOS", "https://droneos.dev/token.json", null)
+        .accountsPartial({ config: t.config, mint: t.mint, authority, tokenProgram: TOKEN_2022_PROGRAM_ID })
+        .rpc(),
+      "already in use"
+    );
+  });
+
+  it("rejects transfer fee changes by anyone but the authority", async () => {
+    await expectError(setTransferFee(100, intruder), "Unauthorized");
+  });
+
+  it("rejects a transfer fee over the cap", async () => {
+    await expectError(setTransferFee(501), "InvalidTransferFee");
+  });
+});
@@ -0,0 +1,144 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN } from "@coral-xyz/anchor";
+import { Ed25519Program, Keypair, PublicKey, SYSVAR_INSTRUCTIONS_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  Stream,
+  acceptStream,
+  expectError,
+  fund,
+  openStream,
+  programs,
+  registerRobot,
+  startStream,
+  tick,
+  u64,
+} from "./helpers";
+
+/**
+ * Usage metering: a metered stream pays per unit of usage its robot's device
+ * key reports and signs, instead of per second. Each report is numbered, so
+ * a signed report can only be billed once.
+ */
+describe("Payment Streams: usage metering", () => {
+  const { paymentStreams } = programs();
+  const connection = anchor.getProvider().connection;
+
+  const RATE_PER_UNIT = 10_000;
+  const UNITS = 25;
+  const device = Keypair.generate();
+  const stranger = Keypair.generate();
+  let s: Stream;
+  let robot: PublicKey;
+
+  function setUsageMetering(stream: Stream, ratePerUnit: number, meteredRobot = robot, signer = stream.payer) {
+    return paymentStreams.methods
+      .setUsageMetering(new BN(ratePerUnit))
+      .accountsPartial({ stream: stream.stream, robot: meteredRobot, payer: signer.publicKey })
+      .signers([signer])
+      .rpc();
+  }
+
+  // The device's signature over stream || report number || units
+  function usageReport(stream: Stream, report: number, units: number, signer = device) {
+    const message = Buffer.concat([stream.stream.toBuffer(), u64(report), u64(units)]);
+    return Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message });
+  }
+
+  function tickUsage(stream: Stream, units: number, preInstructions = [usageReport(stream, 0, units)]) {
+    return paymentStreams.methods
+      .tickUsage(new BN(units))
+      .accountsPartial({
+        stream: stream.stream,
+        mint: stream.mint,
+        claimToken: null,
+        payeeToken: stream.payeeToken,
+        treasury: stream.treasury,
+        cranker: stream.payee.publicKey,
+        crankerToken: null,
+        referrerAccount: null,
+        referrerToken: null,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .preInstructions(preInstructions)
+      .signers([stream.payee])
+      .rpc();
+  }
+
+  async function balance(token: PublicKey) {
+    return Number((await getAccount(connection, token)).amount);
+  }
+
+  before(async () => {
+    await fund(stranger);
+    s = await openStream();
+    robot = await registerRobot(s.payee, device);
+  });
+
+  it("rejects metering set by anyone but the payer", async () => {
+    await expectError(setUsageMetering(s, RATE_PER_UNIT, robot, s.payee), "Unauthorized");
+  });
+
+  it("rejects metering by a robot the payee doesn't operate", async () => {
+    await expectError(setUsageMetering(s, RATE_PER_UNIT, await registerRobot(stranger)), "Unauthorized");
+  });
+
+  it("rejects a zero rate per unit", async () => {
+    await expectError(setUsageMetering(s, 0), "InvalidRate");
+  });
+
+  it("meters the stream by its robot's device key", async () => {
+    await setUsageMetering(s, RATE_PER_UNIT);
+
+    const stream: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(stream.mode).to.have.property("metered");
+    expect(stream.metering.robot.toBase58()).to.equal(robot.toBase58());
+    expect(stream.metering.deviceKey.toBase58()).to.equal(device.publicKey.toBase58());
+    expect(stream.metering.ratePerUnit.toNumber()).to.equal(RATE_PER_UNIT);
+    expect(stream.metering.reports.toNumber()).to.equal(0);
+
+    await acceptStream(s).rpc();
+    await startStream(s).rpc();
+  });
+
+  it("rejects per-second ticks on a metered stream", async () => {
+    await expectError(tick(s).rpc(), "NotContinuousStream");
+  });
+
+  it("rejects usage ticks on a stream that isn't metered", async () => {
+    const unmetered = await openStream();
+    await acceptStream(unmetered).rpc();
+    await startStream(unmetered).rpc();
+    await expectError(tickUsage(unmetered, UNITS), "NotMeteredStream");
+  });
+
+  it("rejects a report without the device's signature", async () => {
+    await expectError(tickUsage(s, UNITS, []), "InvalidUsageReport");
+    await expectError(tickUsage(s, UNITS, [usageReport(s, 0, UNITS, stranger)]), "InvalidUsageReport");
+  });
+
+  it("rejects a report for other units than signed", async () => {
+    await expectError(tickUsage(s, UNITS + 1, [usageReport(s, 0, UNITS)]), "InvalidUsageReport");
+  });
+
+  it("bills signed usage at the rate per unit", async () => {
+    const before: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    const paidOut = async () => (await balance(s.payeeToken)) + (await balance(s.treasury));
+    const paidOutBefore = await paidOut();
+
+    await tickUsage(s, UNITS);
+
+    const amount = UNITS * RATE_PER_UNIT;
+    const after: any = await paymentStreams.account.paymentStream.fetch(s.stream);
+    expect(after.totalPaid.sub(before.totalPaid).toNumber()).to.equal(amount);
+    expect(before.escrowBalance.sub(after.escrowBalance).toNumber()).to.equal(amount);
+    expect(after.metering.reports.toNumber()).to.equal(1);
+    expect((await paidOut()) - paidOutBefore).to.equal(amount);
+  });
+
+  it("rejects billing the same report twice", async () => {
+    await expectError(tickUsage(s, UNITS), "InvalidUsageReport");
+  });
+});
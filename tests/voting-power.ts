@@ -0,0 +1,49 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { drip, expectError, fund, pda, programs, setupToken, stake, updateVotingPower } from "./helpers";
+
+/**
+ * veDRONEOS: a wallet's voting power is recounted from all of its open
+ * stake positions, each weighted by its remaining lock over a year.
+ */
+describe("DRONEOS Token: voting power", () => {
+  const { droneosToken } = programs();
+
+  const AMOUNT = 100 * 1_000_000;
+  const YEAR = 365 * 24 * 60 * 60;
+  const voter = Keypair.generate();
+  const other = Keypair.generate();
+  let position: PublicKey;
+  let otherPosition: PublicKey;
+
+  before(async () => {
+    await fund(voter, other);
+    await setupToken();
+    position = await stake(voter, await drip(voter, AMOUNT), AMOUNT, 365);
+    otherPosition = await stake(other, await drip(other, AMOUNT), AMOUNT, 365);
+  });
+
+  it("rejects a recount leaving out an open position", async () => {
+    await expectError(updateVotingPower(voter, []), "MissingStakePositions");
+  });
+
+  it("rejects counting a position twice", async () => {
+    await expectError(updateVotingPower(voter, [position, position]), "DuplicatePosition");
+  });
+
+  it("rejects counting someone else's position", async () => {
+    await expectError(updateVotingPower(voter, [position, otherPosition]), "Unauthorized");
+  });
+
+  it("weights locked stake by its remaining lock", async () => {
+    await updateVotingPower(voter, [position]);
+
+    const power: any = await droneosToken.account.votingPower.fetch(
+      pda(droneosToken.programId, Buffer.from("voting_power"), voter.publicKey.toBuffer())
+    );
+    // A full year's lock, less the seconds since staking
+    expect(power.power.toNumber()).to.be.lte(AMOUNT);
+    expect(power.power.toNumber()).to.be.gt(AMOUNT * (1 - 600 / YEAR));
+    expect(power.slope.toNumber()).to.equal(AMOUNT);
+  });
+});